@@ -0,0 +1,57 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{env, path::PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-changed=shim/decklink_shim.cpp");
+    println!("cargo:rerun-if-changed=shim/decklink_shim.h");
+    println!("cargo:rerun-if-env-changed=DECKLINK_SDK_DIR");
+
+    // the SDK can not be redistributed, so there is nothing to build against without it
+    let Some(sdk) = env::var_os("DECKLINK_SDK_DIR").map(PathBuf::from) else {
+        println!("cargo:warning=DECKLINK_SDK_DIR is not set, the DeckLink shim is not built and linking will fail");
+        return;
+    };
+
+    let mut build = cc::Build::new();
+    build
+        .cpp(true)
+        .include(&sdk)
+        .include("shim")
+        .file("shim/decklink_shim.cpp");
+
+    match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("windows") => {
+            build.file(sdk.join("DeckLinkAPI_i.c"));
+            println!("cargo:rustc-link-lib=ole32");
+            println!("cargo:rustc-link-lib=oleaut32");
+            println!("cargo:rustc-link-lib=comsuppw");
+        }
+        Ok("macos") => {
+            build.file(sdk.join("DeckLinkAPIDispatch.cpp"));
+            println!("cargo:rustc-link-lib=framework=CoreFoundation");
+        }
+        _ => {
+            // the dispatch `dlopen`s libDeckLinkAPI.so, which comes with the Desktop Video driver
+            build.file(sdk.join("DeckLinkAPIDispatch.cpp"));
+            println!("cargo:rustc-link-lib=dl");
+            println!("cargo:rustc-link-lib=pthread");
+        }
+    }
+
+    build.compile("nokhwa_decklink_shim");
+}
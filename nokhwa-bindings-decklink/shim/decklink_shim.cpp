@@ -0,0 +1,429 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#include "decklink_shim.h"
+
+#include <atomic>
+#include <chrono>
+#include <condition_variable>
+#include <cstring>
+#include <mutex>
+#include <string>
+#include <vector>
+
+#ifdef _WIN32
+#include <comutil.h>
+#include <windows.h>
+#include "DeckLinkAPI_h.h"
+typedef BSTR dlstring_t;
+typedef BOOL dlbool_t;
+static std::string to_string(dlstring_t s) {
+    std::string out = std::string(_bstr_t(s));
+    SysFreeString(s);
+    return out;
+}
+static IDeckLinkIterator* create_iterator() {
+    IDeckLinkIterator* iterator = nullptr;
+    CoInitializeEx(nullptr, COINIT_MULTITHREADED);
+    if (FAILED(CoCreateInstance(CLSID_CDeckLinkIterator, nullptr, CLSCTX_ALL, IID_IDeckLinkIterator, (void**)&iterator))) {
+        return nullptr;
+    }
+    return iterator;
+}
+#elif defined(__APPLE__)
+#include <CoreFoundation/CoreFoundation.h>
+#include "DeckLinkAPI.h"
+typedef CFStringRef dlstring_t;
+typedef bool dlbool_t;
+static std::string to_string(dlstring_t s) {
+    char buffer[256] = {0};
+    CFStringGetCString(s, buffer, sizeof(buffer), kCFStringEncodingUTF8);
+    CFRelease(s);
+    return std::string(buffer);
+}
+static IDeckLinkIterator* create_iterator() { return CreateDeckLinkIteratorInstance(); }
+#else
+#include "DeckLinkAPI.h"
+typedef const char* dlstring_t;
+typedef bool dlbool_t;
+static std::string to_string(dlstring_t s) {
+    std::string out(s);
+    free((void*)s);
+    return out;
+}
+static IDeckLinkIterator* create_iterator() { return CreateDeckLinkIteratorInstance(); }
+#endif
+
+static int32_t copy_string(const std::string& from, char* buffer, size_t length) {
+    if (buffer == nullptr || length == 0) {
+        return E_INVALIDARG;
+    }
+    std::strncpy(buffer, from.c_str(), length - 1);
+    buffer[length - 1] = '\0';
+    return S_OK;
+}
+
+class InputCallback;
+
+struct nokhwa_decklink {
+    IDeckLink* decklink = nullptr;
+    IDeckLinkInput* input = nullptr;
+    IDeckLinkProfileAttributes* attributes = nullptr;
+    IDeckLinkConfiguration* configuration = nullptr;
+    InputCallback* callback = nullptr;
+
+    std::mutex lock;
+    std::condition_variable arrived;
+    // written by the callback thread
+    std::vector<uint8_t> pending;
+    nokhwa_decklink_frame pending_info = {};
+    bool has_pending = false;
+    // handed to the caller
+    std::vector<uint8_t> current;
+
+    std::atomic<uint64_t> sequence{0};
+    std::atomic<int32_t> signal_present{0};
+    std::atomic<uint32_t> display_mode{0};
+    uint32_t pixel_format = 0;
+    bool streaming = false;
+};
+
+class InputCallback : public IDeckLinkInputCallback {
+public:
+    explicit InputCallback(nokhwa_decklink* device) : device_(device) {}
+
+    HRESULT STDMETHODCALLTYPE QueryInterface(REFIID, LPVOID* ppv) override {
+        *ppv = nullptr;
+        return E_NOINTERFACE;
+    }
+    ULONG STDMETHODCALLTYPE AddRef() override { return ++references_; }
+    ULONG STDMETHODCALLTYPE Release() override {
+        ULONG references = --references_;
+        if (references == 0) {
+            delete this;
+        }
+        return references;
+    }
+
+    // the card saw a different signal, restart in the new mode with the same pixel format
+    HRESULT STDMETHODCALLTYPE VideoInputFormatChanged(BMDVideoInputFormatChangedEvents,
+                                                      IDeckLinkDisplayMode* mode,
+                                                      BMDDetectedVideoInputFormatFlags) override {
+        BMDDisplayMode display_mode = mode->GetDisplayMode();
+        device_->input->PauseStreams();
+        device_->input->EnableVideoInput(display_mode, (BMDPixelFormat)device_->pixel_format,
+                                         bmdVideoInputEnableFormatDetection);
+        device_->input->FlushStreams();
+        device_->input->StartStreams();
+        device_->display_mode.store(display_mode);
+        return S_OK;
+    }
+
+    HRESULT STDMETHODCALLTYPE VideoInputFrameArrived(IDeckLinkVideoInputFrame* frame,
+                                                     IDeckLinkAudioInputPacket*) override {
+        if (frame == nullptr) {
+            return S_OK;
+        }
+        bool present = (frame->GetFlags() & bmdFrameHasNoInputSource) == 0;
+        device_->signal_present.store(present ? 1 : 0);
+
+        void* bytes = nullptr;
+        if (FAILED(frame->GetBytes(&bytes)) || bytes == nullptr) {
+            return S_OK;
+        }
+        size_t length = (size_t)frame->GetRowBytes() * (size_t)frame->GetHeight();
+
+        nokhwa_decklink_frame info = {};
+        info.width = (int32_t)frame->GetWidth();
+        info.height = (int32_t)frame->GetHeight();
+        info.row_bytes = frame->GetRowBytes();
+        info.pixel_format = (uint32_t)frame->GetPixelFormat();
+        info.display_mode = device_->display_mode.load();
+        info.hardware_time_scale = 1000000000;
+        BMDTimeValue time = 0, duration = 0;
+        if (SUCCEEDED(frame->GetHardwareReferenceTimestamp(info.hardware_time_scale, &time, &duration))) {
+            info.hardware_time = time;
+            info.hardware_duration = duration;
+        }
+        info.sequence = device_->sequence.fetch_add(1);
+        info.signal_present = present ? 1 : 0;
+
+        {
+            std::lock_guard<std::mutex> guard(device_->lock);
+            const uint8_t* data = static_cast<const uint8_t*>(bytes);
+            device_->pending.assign(data, data + length);
+            device_->pending_info = info;
+            device_->has_pending = true;
+        }
+        device_->arrived.notify_one();
+        return S_OK;
+    }
+
+private:
+    nokhwa_decklink* device_;
+    std::atomic<ULONG> references_{1};
+};
+
+static IDeckLink* nth_device(int32_t index) {
+    IDeckLinkIterator* iterator = create_iterator();
+    if (iterator == nullptr) {
+        return nullptr;
+    }
+    IDeckLink* decklink = nullptr;
+    int32_t current = 0;
+    while (iterator->Next(&decklink) == S_OK) {
+        // cards without inputs (e.g. playout only) do not count
+        IDeckLinkInput* input = nullptr;
+        if (decklink->QueryInterface(IID_IDeckLinkInput, (void**)&input) == S_OK) {
+            input->Release();
+            if (current == index) {
+                break;
+            }
+            current++;
+        }
+        decklink->Release();
+        decklink = nullptr;
+    }
+    iterator->Release();
+    return decklink;
+}
+
+extern "C" {
+
+int32_t nokhwa_decklink_count(void) {
+    IDeckLinkIterator* iterator = create_iterator();
+    if (iterator == nullptr) {
+        return 0;
+    }
+    int32_t count = 0;
+    IDeckLink* decklink = nullptr;
+    while (iterator->Next(&decklink) == S_OK) {
+        IDeckLinkInput* input = nullptr;
+        if (decklink->QueryInterface(IID_IDeckLinkInput, (void**)&input) == S_OK) {
+            input->Release();
+            count++;
+        }
+        decklink->Release();
+    }
+    iterator->Release();
+    return count;
+}
+
+nokhwa_decklink* nokhwa_decklink_open(int32_t index) {
+    IDeckLink* decklink = nth_device(index);
+    if (decklink == nullptr) {
+        return nullptr;
+    }
+    nokhwa_decklink* device = new nokhwa_decklink();
+    device->decklink = decklink;
+    decklink->QueryInterface(IID_IDeckLinkInput, (void**)&device->input);
+    decklink->QueryInterface(IID_IDeckLinkProfileAttributes, (void**)&device->attributes);
+    decklink->QueryInterface(IID_IDeckLinkConfiguration, (void**)&device->configuration);
+    device->callback = new InputCallback(device);
+    device->input->SetCallback(device->callback);
+    return device;
+}
+
+void nokhwa_decklink_close(nokhwa_decklink* device) {
+    if (device == nullptr) {
+        return;
+    }
+    nokhwa_decklink_stop(device);
+    device->input->SetCallback(nullptr);
+    device->callback->Release();
+    if (device->configuration != nullptr) {
+        device->configuration->Release();
+    }
+    if (device->attributes != nullptr) {
+        device->attributes->Release();
+    }
+    device->input->Release();
+    device->decklink->Release();
+    delete device;
+}
+
+int32_t nokhwa_decklink_name(nokhwa_decklink* device, char* buffer, size_t length) {
+    dlstring_t name;
+    HRESULT result = device->decklink->GetDisplayName(&name);
+    if (FAILED(result)) {
+        return result;
+    }
+    return copy_string(to_string(name), buffer, length);
+}
+
+int32_t nokhwa_decklink_model(nokhwa_decklink* device, char* buffer, size_t length) {
+    dlstring_t name;
+    HRESULT result = device->decklink->GetModelName(&name);
+    if (FAILED(result)) {
+        return result;
+    }
+    return copy_string(to_string(name), buffer, length);
+}
+
+int64_t nokhwa_decklink_persistent_id(nokhwa_decklink* device) {
+    int64_t id = -1;
+    if (device->attributes == nullptr || FAILED(device->attributes->GetInt(BMDDeckLinkPersistentID, &id))) {
+        return -1;
+    }
+    return id;
+}
+
+int32_t nokhwa_decklink_mode_count(nokhwa_decklink* device) {
+    IDeckLinkDisplayModeIterator* iterator = nullptr;
+    HRESULT result = device->input->GetDisplayModeIterator(&iterator);
+    if (FAILED(result)) {
+        return result;
+    }
+    int32_t count = 0;
+    IDeckLinkDisplayMode* mode = nullptr;
+    while (iterator->Next(&mode) == S_OK) {
+        count++;
+        mode->Release();
+    }
+    iterator->Release();
+    return count;
+}
+
+int32_t nokhwa_decklink_mode(nokhwa_decklink* device, int32_t index, nokhwa_decklink_mode* out) {
+    IDeckLinkDisplayModeIterator* iterator = nullptr;
+    HRESULT result = device->input->GetDisplayModeIterator(&iterator);
+    if (FAILED(result)) {
+        return result;
+    }
+    int32_t current = 0;
+    IDeckLinkDisplayMode* mode = nullptr;
+    result = E_INVALIDARG;
+    while (iterator->Next(&mode) == S_OK) {
+        if (current++ == index) {
+            BMDTimeValue duration = 0;
+            BMDTimeScale scale = 0;
+            mode->GetFrameRate(&duration, &scale);
+            out->display_mode = (uint32_t)mode->GetDisplayMode();
+            out->width = (int32_t)mode->GetWidth();
+            out->height = (int32_t)mode->GetHeight();
+            out->frame_duration = duration;
+            out->time_scale = scale;
+            dlstring_t name;
+            if (SUCCEEDED(mode->GetName(&name))) {
+                copy_string(to_string(name), out->name, sizeof(out->name));
+            }
+            result = S_OK;
+        }
+        mode->Release();
+        if (result == S_OK) {
+            break;
+        }
+    }
+    iterator->Release();
+    return result;
+}
+
+int32_t nokhwa_decklink_supports(nokhwa_decklink* device, uint32_t display_mode, uint32_t pixel_format) {
+    dlbool_t supported = false;
+    BMDDisplayMode actual;
+    HRESULT result = device->input->DoesSupportVideoMode(bmdVideoConnectionUnspecified, (BMDDisplayMode)display_mode,
+                                                         (BMDPixelFormat)pixel_format, bmdNoVideoInputConversion,
+                                                         bmdSupportedVideoModeDefault, &actual, &supported);
+    if (FAILED(result)) {
+        return result;
+    }
+    return supported ? 1 : 0;
+}
+
+int64_t nokhwa_decklink_connectors(nokhwa_decklink* device) {
+    int64_t connectors = 0;
+    if (device->attributes == nullptr ||
+        FAILED(device->attributes->GetInt(BMDDeckLinkVideoInputConnections, &connectors))) {
+        return 0;
+    }
+    return connectors;
+}
+
+int64_t nokhwa_decklink_connector(nokhwa_decklink* device) {
+    int64_t connector = 0;
+    if (device->configuration == nullptr ||
+        FAILED(device->configuration->GetInt(bmdDeckLinkConfigVideoInputConnection, &connector))) {
+        return 0;
+    }
+    return connector;
+}
+
+int32_t nokhwa_decklink_set_connector(nokhwa_decklink* device, int64_t connector) {
+    if (device->configuration == nullptr) {
+        return E_NOINTERFACE;
+    }
+    return device->configuration->SetInt(bmdDeckLinkConfigVideoInputConnection, connector);
+}
+
+int32_t nokhwa_decklink_start(nokhwa_decklink* device, uint32_t display_mode, uint32_t pixel_format,
+                              int32_t detect_format) {
+    nokhwa_decklink_stop(device);
+    dlbool_t detection = false;
+    if (detect_format != 0 && device->attributes != nullptr) {
+        device->attributes->GetFlag(BMDDeckLinkSupportsInputFormatDetection, &detection);
+    }
+    device->pixel_format = pixel_format;
+    device->display_mode.store(display_mode);
+    HRESULT result = device->input->EnableVideoInput(
+        (BMDDisplayMode)display_mode, (BMDPixelFormat)pixel_format,
+        detection ? bmdVideoInputEnableFormatDetection : bmdVideoInputFlagDefault);
+    if (FAILED(result)) {
+        return result;
+    }
+    device->sequence.store(0);
+    result = device->input->StartStreams();
+    if (FAILED(result)) {
+        device->input->DisableVideoInput();
+        return result;
+    }
+    device->streaming = true;
+    return S_OK;
+}
+
+int32_t nokhwa_decklink_stop(nokhwa_decklink* device) {
+    if (!device->streaming) {
+        return S_OK;
+    }
+    device->input->StopStreams();
+    device->input->DisableVideoInput();
+    device->streaming = false;
+    std::lock_guard<std::mutex> guard(device->lock);
+    device->has_pending = false;
+    return S_OK;
+}
+
+int32_t nokhwa_decklink_wait_frame(nokhwa_decklink* device, uint32_t timeout_ms, nokhwa_decklink_frame* frame) {
+    std::unique_lock<std::mutex> guard(device->lock);
+    if (!device->arrived.wait_for(guard, std::chrono::milliseconds(timeout_ms),
+                                  [device] { return device->has_pending; })) {
+        return 0;
+    }
+    device->current.swap(device->pending);
+    device->has_pending = false;
+    *frame = device->pending_info;
+    frame->data = device->current.data();
+    frame->length = device->current.size();
+    return 1;
+}
+
+int32_t nokhwa_decklink_signal_present(nokhwa_decklink* device) {
+    return device->signal_present.load();
+}
+
+uint32_t nokhwa_decklink_current_mode(nokhwa_decklink* device) {
+    return device->display_mode.load();
+}
+}
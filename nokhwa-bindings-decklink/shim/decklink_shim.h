@@ -0,0 +1,83 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// A C interface over the DeckLink SDK's COM interfaces, so Rust does not need to know about vtables.
+// Every function returning int32_t returns 0 (or a positive count) on success and a negative HRESULT on failure.
+
+#ifndef NOKHWA_DECKLINK_SHIM_H
+#define NOKHWA_DECKLINK_SHIM_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct nokhwa_decklink nokhwa_decklink;
+
+typedef struct {
+    uint32_t display_mode;
+    int32_t width;
+    int32_t height;
+    int64_t frame_duration;
+    int64_t time_scale;
+    char name[64];
+} nokhwa_decklink_mode;
+
+typedef struct {
+    // valid until the next call to nokhwa_decklink_wait_frame or nokhwa_decklink_stop
+    const uint8_t* data;
+    size_t length;
+    int32_t width;
+    int32_t height;
+    int64_t row_bytes;
+    uint32_t pixel_format;
+    uint32_t display_mode;
+    int64_t hardware_time;
+    int64_t hardware_duration;
+    int64_t hardware_time_scale;
+    uint64_t sequence;
+    int32_t signal_present;
+} nokhwa_decklink_frame;
+
+int32_t nokhwa_decklink_count(void);
+nokhwa_decklink* nokhwa_decklink_open(int32_t index);
+void nokhwa_decklink_close(nokhwa_decklink* device);
+
+int32_t nokhwa_decklink_name(nokhwa_decklink* device, char* buffer, size_t length);
+int32_t nokhwa_decklink_model(nokhwa_decklink* device, char* buffer, size_t length);
+int64_t nokhwa_decklink_persistent_id(nokhwa_decklink* device);
+
+int32_t nokhwa_decklink_mode_count(nokhwa_decklink* device);
+int32_t nokhwa_decklink_mode(nokhwa_decklink* device, int32_t index, nokhwa_decklink_mode* mode);
+int32_t nokhwa_decklink_supports(nokhwa_decklink* device, uint32_t display_mode, uint32_t pixel_format);
+
+int64_t nokhwa_decklink_connectors(nokhwa_decklink* device);
+int64_t nokhwa_decklink_connector(nokhwa_decklink* device);
+int32_t nokhwa_decklink_set_connector(nokhwa_decklink* device, int64_t connector);
+
+int32_t nokhwa_decklink_start(nokhwa_decklink* device, uint32_t display_mode, uint32_t pixel_format, int32_t detect_format);
+int32_t nokhwa_decklink_stop(nokhwa_decklink* device);
+int32_t nokhwa_decklink_wait_frame(nokhwa_decklink* device, uint32_t timeout_ms, nokhwa_decklink_frame* frame);
+int32_t nokhwa_decklink_signal_present(nokhwa_decklink* device);
+uint32_t nokhwa_decklink_current_mode(nokhwa_decklink* device);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
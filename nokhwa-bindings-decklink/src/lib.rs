@@ -0,0 +1,568 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The Blackmagic `DeckLink` bindings for `nokhwa`, over a small C++ shim around the SDK's COM interfaces.
+
+use nokhwa_core::error::NokhwaError;
+use std::{
+    ffi::{c_char, CStr},
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+#[allow(non_camel_case_types)]
+mod ffi {
+    use std::ffi::c_char;
+
+    #[repr(C)]
+    pub struct nokhwa_decklink {
+        _private: [u8; 0],
+    }
+
+    #[repr(C)]
+    pub struct nokhwa_decklink_mode {
+        pub display_mode: u32,
+        pub width: i32,
+        pub height: i32,
+        pub frame_duration: i64,
+        pub time_scale: i64,
+        pub name: [c_char; 64],
+    }
+
+    #[repr(C)]
+    pub struct nokhwa_decklink_frame {
+        pub data: *const u8,
+        pub length: usize,
+        pub width: i32,
+        pub height: i32,
+        pub row_bytes: i64,
+        pub pixel_format: u32,
+        pub display_mode: u32,
+        pub hardware_time: i64,
+        pub hardware_duration: i64,
+        pub hardware_time_scale: i64,
+        pub sequence: u64,
+        pub signal_present: i32,
+    }
+
+    extern "C" {
+        pub fn nokhwa_decklink_count() -> i32;
+        pub fn nokhwa_decklink_open(index: i32) -> *mut nokhwa_decklink;
+        pub fn nokhwa_decklink_close(device: *mut nokhwa_decklink);
+        pub fn nokhwa_decklink_name(
+            device: *mut nokhwa_decklink,
+            buffer: *mut c_char,
+            length: usize,
+        ) -> i32;
+        pub fn nokhwa_decklink_model(
+            device: *mut nokhwa_decklink,
+            buffer: *mut c_char,
+            length: usize,
+        ) -> i32;
+        pub fn nokhwa_decklink_persistent_id(device: *mut nokhwa_decklink) -> i64;
+        pub fn nokhwa_decklink_mode_count(device: *mut nokhwa_decklink) -> i32;
+        pub fn nokhwa_decklink_mode(
+            device: *mut nokhwa_decklink,
+            index: i32,
+            mode: *mut nokhwa_decklink_mode,
+        ) -> i32;
+        pub fn nokhwa_decklink_supports(
+            device: *mut nokhwa_decklink,
+            display_mode: u32,
+            pixel_format: u32,
+        ) -> i32;
+        pub fn nokhwa_decklink_connectors(device: *mut nokhwa_decklink) -> i64;
+        pub fn nokhwa_decklink_connector(device: *mut nokhwa_decklink) -> i64;
+        pub fn nokhwa_decklink_set_connector(device: *mut nokhwa_decklink, connector: i64) -> i32;
+        pub fn nokhwa_decklink_start(
+            device: *mut nokhwa_decklink,
+            display_mode: u32,
+            pixel_format: u32,
+            detect_format: i32,
+        ) -> i32;
+        pub fn nokhwa_decklink_stop(device: *mut nokhwa_decklink) -> i32;
+        pub fn nokhwa_decklink_wait_frame(
+            device: *mut nokhwa_decklink,
+            timeout_ms: u32,
+            frame: *mut nokhwa_decklink_frame,
+        ) -> i32;
+        pub fn nokhwa_decklink_signal_present(device: *mut nokhwa_decklink) -> i32;
+        pub fn nokhwa_decklink_current_mode(device: *mut nokhwa_decklink) -> u32;
+    }
+}
+
+/// `bmdFormat8BitYUV`, 4:2:2 packed as `UYVY`.
+pub const BMD_FORMAT_8BIT_YUV: u32 = u32::from_be_bytes(*b"2vuy");
+/// `bmdFormat10BitYUV`, 4:2:2 packed as `v210`.
+pub const BMD_FORMAT_10BIT_YUV: u32 = u32::from_be_bytes(*b"v210");
+/// `bmdFormat8BitBGRA`.
+pub const BMD_FORMAT_8BIT_BGRA: u32 = u32::from_be_bytes(*b"BGRA");
+/// `bmdFormat10BitRGB`, packed as `r210`.
+pub const BMD_FORMAT_10BIT_RGB: u32 = u32::from_be_bytes(*b"r210");
+
+/// A video input connector of a `DeckLink` card.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeckLinkConnector {
+    Sdi,
+    Hdmi,
+    OpticalSdi,
+    Component,
+    Composite,
+    SVideo,
+}
+
+impl DeckLinkConnector {
+    /// Every connector, in the order of their `BMDVideoConnection` bits.
+    pub const ALL: [DeckLinkConnector; 6] = [
+        DeckLinkConnector::Sdi,
+        DeckLinkConnector::Hdmi,
+        DeckLinkConnector::OpticalSdi,
+        DeckLinkConnector::Component,
+        DeckLinkConnector::Composite,
+        DeckLinkConnector::SVideo,
+    ];
+
+    /// The `BMDVideoConnection` bit of this connector.
+    #[must_use]
+    pub const fn bit(self) -> i64 {
+        match self {
+            DeckLinkConnector::Sdi => 1,
+            DeckLinkConnector::Hdmi => 1 << 1,
+            DeckLinkConnector::OpticalSdi => 1 << 2,
+            DeckLinkConnector::Component => 1 << 3,
+            DeckLinkConnector::Composite => 1 << 4,
+            DeckLinkConnector::SVideo => 1 << 5,
+        }
+    }
+
+    /// The connector of a `BMDVideoConnection` bit, or `None` if it is not one bit of a known connector.
+    #[must_use]
+    pub fn from_bit(bit: i64) -> Option<Self> {
+        DeckLinkConnector::ALL
+            .into_iter()
+            .find(|connector| connector.bit() == bit)
+    }
+}
+
+impl Display for DeckLinkConnector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DeckLinkConnector::Sdi => "SDI",
+            DeckLinkConnector::Hdmi => "HDMI",
+            DeckLinkConnector::OpticalSdi => "Optical SDI",
+            DeckLinkConnector::Component => "Component",
+            DeckLinkConnector::Composite => "Composite",
+            DeckLinkConnector::SVideo => "S-Video",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A display mode (`BMDDisplayMode`) a `DeckLink` card can capture, e.g. `1080p59.94`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DeckLinkDisplayMode {
+    id: u32,
+    name: String,
+    width: u32,
+    height: u32,
+    frame_duration: u32,
+    time_scale: u32,
+}
+
+impl DeckLinkDisplayMode {
+    /// The `BMDDisplayMode` of this mode.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The name the SDK gives this mode.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The width of a frame.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of a frame.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The length of a frame, in units of [`time_scale()`](DeckLinkDisplayMode::time_scale). The frame rate is `time_scale / frame_duration`, e.g. `60000 / 1001`.
+    #[must_use]
+    pub fn frame_duration(&self) -> u32 {
+        self.frame_duration
+    }
+
+    /// The units per second of [`frame_duration()`](DeckLinkDisplayMode::frame_duration).
+    #[must_use]
+    pub fn time_scale(&self) -> u32 {
+        self.time_scale
+    }
+}
+
+/// A frame captured by a `DeckLink` card.
+#[derive(Clone, Debug)]
+pub struct DeckLinkFrame {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    row_bytes: usize,
+    pixel_format: u32,
+    display_mode: u32,
+    hardware_time: Option<Duration>,
+    hardware_duration: Option<Duration>,
+    sequence: u64,
+    signal_present: bool,
+}
+
+impl DeckLinkFrame {
+    /// The data of the frame, [`row_bytes()`](DeckLinkFrame::row_bytes) per row.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Takes the data of the frame.
+    #[must_use]
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// The width of the frame.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the frame.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The length of a row, including the padding `v210` needs to a multiple of 48 pixels.
+    #[must_use]
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+
+    /// The `BMDPixelFormat` of the frame, e.g. [`BMD_FORMAT_10BIT_YUV`].
+    #[must_use]
+    pub fn pixel_format(&self) -> u32 {
+        self.pixel_format
+    }
+
+    /// The `BMDDisplayMode` the card was in.
+    #[must_use]
+    pub fn display_mode(&self) -> u32 {
+        self.display_mode
+    }
+
+    /// When the frame arrived, on the card's hardware reference clock.
+    #[must_use]
+    pub fn hardware_time(&self) -> Option<Duration> {
+        self.hardware_time
+    }
+
+    /// How long the frame lasts, on the card's hardware reference clock.
+    #[must_use]
+    pub fn hardware_duration(&self) -> Option<Duration> {
+        self.hardware_duration
+    }
+
+    /// The number of the frame since the stream started.
+    #[must_use]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Weather there was a signal on the input. Without one, the card hands out black frames.
+    #[must_use]
+    pub fn signal_present(&self) -> bool {
+        self.signal_present
+    }
+}
+
+fn hresult_error(operation: &str, result: i32) -> NokhwaError {
+    NokhwaError::GeneralError(format!(
+        "DeckLink {operation} failed: HRESULT {result:#010x}"
+    ))
+}
+
+fn nanos(value: i64) -> Option<Duration> {
+    u64::try_from(value)
+        .ok()
+        .filter(|nanos| *nanos != 0)
+        .map(Duration::from_nanos)
+}
+
+/// A `DeckLink` card (or one of the sub-devices of a card with several inputs) that can capture.
+///
+/// The card's frames arrive on a thread of the SDK, which keeps the newest one for [`wait_frame()`](DeckLinkDevice::wait_frame).
+pub struct DeckLinkDevice {
+    device: *mut ffi::nokhwa_decklink,
+}
+
+// SAFETY: the shim locks what the SDK's callback thread writes, and the SDK's interfaces are free threaded
+unsafe impl Send for DeckLinkDevice {}
+
+impl DeckLinkDevice {
+    /// The amount of `DeckLink` devices with an input. Without the Desktop Video driver, this is 0.
+    #[must_use]
+    pub fn count() -> u32 {
+        // SAFETY: takes no arguments
+        u32::try_from(unsafe { ffi::nokhwa_decklink_count() }).unwrap_or(0)
+    }
+
+    /// Opens the `DeckLink` device at `index`, counting only devices with an input.
+    /// # Errors
+    /// If the device does not exist, this will error.
+    pub fn open(index: u32) -> Result<Self, NokhwaError> {
+        let no_device = || {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such DeckLink device".to_string())
+        };
+        let idx = i32::try_from(index).map_err(|_| no_device())?;
+        // SAFETY: the shim returns null for indices that do not exist
+        let device = unsafe { ffi::nokhwa_decklink_open(idx) };
+        if device.is_null() {
+            return Err(no_device());
+        }
+        Ok(DeckLinkDevice { device })
+    }
+
+    fn string(
+        &self,
+        getter: unsafe extern "C" fn(*mut ffi::nokhwa_decklink, *mut c_char, usize) -> i32,
+    ) -> String {
+        let mut buffer = [0 as c_char; 256];
+        // SAFETY: the shim writes at most `buffer.len()` bytes, including the terminating nul
+        let result = unsafe { getter(self.device, buffer.as_mut_ptr(), buffer.len()) };
+        if result < 0 {
+            return String::new();
+        }
+        // SAFETY: the shim always terminates the string
+        unsafe { CStr::from_ptr(buffer.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// The display name of the device, e.g. `DeckLink Duo (1)`.
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.string(ffi::nokhwa_decklink_name)
+    }
+
+    /// The model of the device, e.g. `DeckLink Duo`.
+    #[must_use]
+    pub fn model(&self) -> String {
+        self.string(ffi::nokhwa_decklink_model)
+    }
+
+    /// The ID of the device that stays the same across reboots, if the card has one.
+    #[must_use]
+    pub fn persistent_id(&self) -> Option<i64> {
+        // SAFETY: the device is open
+        Some(unsafe { ffi::nokhwa_decklink_persistent_id(self.device) }).filter(|id| *id >= 0)
+    }
+
+    /// The display modes the device can capture.
+    #[must_use]
+    pub fn display_modes(&self) -> Vec<DeckLinkDisplayMode> {
+        // SAFETY: the device is open
+        let count = unsafe { ffi::nokhwa_decklink_mode_count(self.device) };
+        (0..count.max(0))
+            .filter_map(|index| {
+                let mut mode = ffi::nokhwa_decklink_mode {
+                    display_mode: 0,
+                    width: 0,
+                    height: 0,
+                    frame_duration: 0,
+                    time_scale: 0,
+                    name: [0; 64],
+                };
+                // SAFETY: the shim fills `mode` if it returns 0
+                if unsafe {
+                    ffi::nokhwa_decklink_mode(self.device, index, std::ptr::addr_of_mut!(mode))
+                } < 0
+                {
+                    return None;
+                }
+                // SAFETY: the shim terminates the name, which starts out zeroed
+                let name = unsafe { CStr::from_ptr(mode.name.as_ptr()) }
+                    .to_string_lossy()
+                    .into_owned();
+                Some(DeckLinkDisplayMode {
+                    id: mode.display_mode,
+                    name,
+                    width: u32::try_from(mode.width).ok()?,
+                    height: u32::try_from(mode.height).ok()?,
+                    frame_duration: u32::try_from(mode.frame_duration).ok()?,
+                    time_scale: u32::try_from(mode.time_scale).ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Weather the device can capture `display_mode` as `pixel_format`.
+    #[must_use]
+    pub fn supports(&self, display_mode: u32, pixel_format: u32) -> bool {
+        // SAFETY: the device is open
+        unsafe { ffi::nokhwa_decklink_supports(self.device, display_mode, pixel_format) == 1 }
+    }
+
+    /// The input connectors of the device.
+    #[must_use]
+    pub fn connectors(&self) -> Vec<DeckLinkConnector> {
+        // SAFETY: the device is open
+        let connectors = unsafe { ffi::nokhwa_decklink_connectors(self.device) };
+        DeckLinkConnector::ALL
+            .into_iter()
+            .filter(|connector| connectors & connector.bit() != 0)
+            .collect()
+    }
+
+    /// The input connector the device captures from.
+    #[must_use]
+    pub fn connector(&self) -> Option<DeckLinkConnector> {
+        // SAFETY: the device is open
+        DeckLinkConnector::from_bit(unsafe { ffi::nokhwa_decklink_connector(self.device) })
+    }
+
+    /// Sets the input connector the device captures from.
+    /// # Errors
+    /// If the device does not have the connector, this will error.
+    pub fn set_connector(&mut self, connector: DeckLinkConnector) -> Result<(), NokhwaError> {
+        // SAFETY: the device is open
+        let result = unsafe { ffi::nokhwa_decklink_set_connector(self.device, connector.bit()) };
+        if result < 0 {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Input Connector".to_string(),
+                value: connector.to_string(),
+                error: format!("HRESULT {result:#010x}"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Starts capturing `display_mode` as `pixel_format`. With `detect_format`, cards that can detect the input's format switch to it
+    /// on their own, see [`current_mode()`](DeckLinkDevice::current_mode).
+    /// # Errors
+    /// If the device is in use by another application, or can not capture the mode, this will error.
+    pub fn start(
+        &mut self,
+        display_mode: u32,
+        pixel_format: u32,
+        detect_format: bool,
+    ) -> Result<(), NokhwaError> {
+        // SAFETY: the device is open
+        let result = unsafe {
+            ffi::nokhwa_decklink_start(
+                self.device,
+                display_mode,
+                pixel_format,
+                i32::from(detect_format),
+            )
+        };
+        if result < 0 {
+            return Err(hresult_error("start", result));
+        }
+        Ok(())
+    }
+
+    /// Stops capturing. Stopping a device that is not capturing does nothing.
+    pub fn stop(&mut self) {
+        // SAFETY: the device is open
+        unsafe {
+            ffi::nokhwa_decklink_stop(self.device);
+        }
+    }
+
+    /// Waits for the next frame.
+    /// # Errors
+    /// If no frame arrives in `timeout`, this will error.
+    pub fn wait_frame(&mut self, timeout: Duration) -> Result<DeckLinkFrame, NokhwaError> {
+        let mut frame = ffi::nokhwa_decklink_frame {
+            data: std::ptr::null(),
+            length: 0,
+            width: 0,
+            height: 0,
+            row_bytes: 0,
+            pixel_format: 0,
+            display_mode: 0,
+            hardware_time: 0,
+            hardware_duration: 0,
+            hardware_time_scale: 0,
+            sequence: 0,
+            signal_present: 0,
+        };
+        let timeout_ms = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        // SAFETY: the shim fills `frame` if it returns 1
+        match unsafe {
+            ffi::nokhwa_decklink_wait_frame(self.device, timeout_ms, std::ptr::addr_of_mut!(frame))
+        } {
+            1 => {}
+            0 => return Err(NokhwaError::TimeoutError(timeout)),
+            result => return Err(hresult_error("wait frame", result)),
+        }
+
+        let data = if frame.data.is_null() {
+            vec![]
+        } else {
+            // SAFETY: the data stays valid until the next wait, and `&mut self` keeps anyone else from waiting
+            unsafe { std::slice::from_raw_parts(frame.data, frame.length) }.to_vec()
+        };
+        Ok(DeckLinkFrame {
+            data,
+            width: u32::try_from(frame.width).unwrap_or_default(),
+            height: u32::try_from(frame.height).unwrap_or_default(),
+            row_bytes: usize::try_from(frame.row_bytes).unwrap_or_default(),
+            pixel_format: frame.pixel_format,
+            display_mode: frame.display_mode,
+            hardware_time: nanos(frame.hardware_time),
+            hardware_duration: nanos(frame.hardware_duration),
+            sequence: frame.sequence,
+            signal_present: frame.signal_present != 0,
+        })
+    }
+
+    /// Weather there was a signal on the input in the last frame.
+    #[must_use]
+    pub fn signal_present(&self) -> bool {
+        // SAFETY: the device is open
+        unsafe { ffi::nokhwa_decklink_signal_present(self.device) != 0 }
+    }
+
+    /// The `BMDDisplayMode` the device captures, which changes when it detects a different input format.
+    #[must_use]
+    pub fn current_mode(&self) -> u32 {
+        // SAFETY: the device is open
+        unsafe { ffi::nokhwa_decklink_current_mode(self.device) }
+    }
+}
+
+impl Drop for DeckLinkDevice {
+    fn drop(&mut self) {
+        // SAFETY: the device is open, and is never used again
+        unsafe { ffi::nokhwa_decklink_close(self.device) }
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Dma-buf import (`V4L2_MEMORY_DMABUF`) capture, where the driver writes frames into dma-bufs allocated by the application.
+
+use crate::ioctl::wait_readable;
+use std::{
+    hash::{Hash, Hasher},
+    io, mem,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
+    sync::Arc,
+    time::Duration,
+};
+use v4l::{
+    buffer::{Metadata, Type},
+    device::Handle,
+    memory::Memory,
+    v4l2,
+    v4l_sys::{v4l2_buffer, v4l2_requestbuffers},
+    Device,
+};
+
+const ENODEV: i32 = 19;
+
+/// A dma-buf allocated by the application (e.g. from a `dma-heap`, GBM, or a Vulkan/EGL exporter) that the driver captures frames into,
+/// see [`CaptureMemory::DmaBufImport`](crate::CaptureMemory::DmaBufImport).
+///
+/// Clones share the file descriptor. Equality is by identity, two [`ImportedDmaBuf`]s are equal if they share the same descriptor.
+#[derive(Clone, Debug)]
+pub struct ImportedDmaBuf {
+    fd: Arc<OwnedFd>,
+    len: usize,
+}
+
+impl ImportedDmaBuf {
+    /// Takes ownership of the dma-buf `fd`. Its size is read from the file descriptor.
+    /// # Errors
+    /// If `fd` can not be seeked (it is not a dma-buf), this will error.
+    pub fn new(fd: OwnedFd) -> io::Result<Self> {
+        // dma-bufs report their size as the end of the file
+        let len = unsafe { libc::lseek(fd.as_raw_fd(), 0, libc::SEEK_END) };
+        let len = usize::try_from(len).map_err(|_| io::Error::last_os_error())?;
+        unsafe {
+            libc::lseek(fd.as_raw_fd(), 0, libc::SEEK_SET);
+        }
+        Ok(ImportedDmaBuf {
+            fd: Arc::new(fd),
+            len,
+        })
+    }
+
+    /// Gets the dma-buf file descriptor.
+    #[must_use]
+    pub fn fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+
+    /// Gets the size of the dma-buf in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the dma-buf has a size of 0.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl PartialEq for ImportedDmaBuf {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.fd, &other.fd)
+    }
+}
+
+impl Eq for ImportedDmaBuf {}
+
+impl Hash for ImportedDmaBuf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.fd).hash(state);
+    }
+}
+
+/// Returns `true` if `device` can capture into imported dma-bufs.
+pub fn is_supported(device: &Device) -> bool {
+    // requesting 0 buffers frees nothing, but fails if the memory type is not supported
+    request_buffers(&device.handle(), 0).is_ok()
+}
+
+/// A stream that captures into [`ImportedDmaBuf`]s.
+///
+/// The stream is stopped and the buffers handed back on drop, after which the driver no longer writes to them.
+pub struct DmaBufImportStream {
+    handle: Arc<Handle>,
+    buffers: Vec<ImportedDmaBuf>,
+    queued_index: Option<usize>,
+    active: bool,
+    timeout: Option<Duration>,
+}
+
+impl DmaBufImportStream {
+    /// Hands `buffers` to the driver, which must have room for a frame of `frame_size` bytes each.
+    pub fn with_buffers(
+        device: &Device,
+        buffers: &[ImportedDmaBuf],
+        frame_size: usize,
+    ) -> io::Result<Self> {
+        if let Some(buffer) = buffers.iter().find(|buffer| buffer.len() < frame_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Dma-buf of {} bytes can not hold a frame of {frame_size} bytes",
+                    buffer.len()
+                ),
+            ));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let granted = request_buffers(&device.handle(), buffers.len() as u32)? as usize;
+        Ok(DmaBufImportStream {
+            handle: device.handle(),
+            buffers: buffers[..granted.min(buffers.len())].to_vec(),
+            queued_index: None,
+            active: false,
+            timeout: None,
+        })
+    }
+
+    /// Gets the amount of buffers the driver accepted.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn buffer_count(&self) -> u32 {
+        self.buffers.len() as u32
+    }
+
+    /// Sets how long [`next()`](DmaBufImportStream::next) waits for a buffer, `None` waiting forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Returns `true` if the stream is started.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Queues all buffers and starts the stream, if it is not started already.
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.active {
+            return Ok(());
+        }
+        for index in 0..self.buffers.len() {
+            self.queue(index)?;
+        }
+        self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMON)?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Hands the previous buffer back to the driver and waits for the next one, returning its file descriptor, its index,
+    /// and the buffer's metadata. The stream is started on the first call.
+    ///
+    /// Fails with [`TimedOut`](io::ErrorKind::TimedOut) if no buffer is ready within the timeout.
+    pub fn next(&mut self) -> io::Result<(BorrowedFd<'_>, usize, Metadata)> {
+        match self.queued_index {
+            Some(index) => self.queue(index)?,
+            None => self.start()?,
+        }
+
+        wait_readable(&self.handle, self.timeout)?;
+        let mut v4l2_buf = buffer_desc();
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_DQBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )?;
+        }
+        let index = v4l2_buf.index as usize;
+        self.queued_index = Some(index);
+
+        let metadata = Metadata {
+            bytesused: v4l2_buf.bytesused,
+            flags: v4l2_buf.flags.into(),
+            field: v4l2_buf.field,
+            timestamp: v4l2_buf.timestamp.into(),
+            sequence: v4l2_buf.sequence,
+        };
+        Ok((self.buffers[index].fd(), index, metadata))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn queue(&mut self, index: usize) -> io::Result<()> {
+        let buffer = &self.buffers[index];
+        let mut v4l2_buf = buffer_desc();
+        v4l2_buf.index = index as u32;
+        v4l2_buf.m.fd = buffer.fd().as_raw_fd();
+        v4l2_buf.length = buffer.len() as u32;
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_QBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )
+        }
+    }
+
+    fn stream_ioctl(&self, request: v4l2::vidioc::_IOC_TYPE) -> io::Result<()> {
+        let mut typ = Type::VideoCapture as u32;
+        unsafe { v4l2::ioctl(self.handle.fd(), request, (&mut typ as *mut u32).cast()) }
+    }
+
+    fn release(&mut self) -> io::Result<()> {
+        // STREAMOFF dequeues every buffer, after which the driver drops its references to the dma-bufs
+        if self.active {
+            self.active = false;
+            self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMOFF)?;
+        }
+        self.buffers.clear();
+        request_buffers(&self.handle, 0).map(drop)
+    }
+}
+
+impl Drop for DmaBufImportStream {
+    fn drop(&mut self) {
+        if let Err(why) = self.release() {
+            // the device is gone, so are its references to the buffers
+            if why.raw_os_error() != Some(ENODEV) {
+                panic!("Failed to release imported dma-bufs: {why}")
+            }
+        }
+    }
+}
+
+fn buffer_desc() -> v4l2_buffer {
+    v4l2_buffer {
+        type_: Type::VideoCapture as u32,
+        memory: Memory::DmaBuf as u32,
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+fn request_buffers(handle: &Handle, count: u32) -> io::Result<u32> {
+    let mut v4l2_reqbufs = v4l2_requestbuffers {
+        count,
+        type_: Type::VideoCapture as u32,
+        memory: Memory::DmaBuf as u32,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        v4l2::ioctl(
+            handle.fd(),
+            v4l2::vidioc::VIDIOC_REQBUFS,
+            (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+        )?;
+    }
+    Ok(v4l2_reqbufs.count)
+}
@@ -0,0 +1,173 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Control change notifications through V4L2 control events (`VIDIOC_SUBSCRIBE_EVENT`).
+
+use crate::{
+    id_to_known_camera_control,
+    ioctl::{VIDIOC_DQEVENT, VIDIOC_SUBSCRIBE_EVENT, VIDIOC_UNSUBSCRIBE_EVENT},
+};
+use nokhwa_core::types::{ControlChange, ControlValueSetter};
+use std::{
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+use v4l::{
+    control::Type,
+    device::Handle,
+    v4l2,
+    v4l_sys::{v4l2_event, v4l2_event_subscription},
+};
+use v4l2_sys_mit::{
+    V4L2_EVENT_ALL, V4L2_EVENT_CTRL, V4L2_EVENT_CTRL_CH_FLAGS, V4L2_EVENT_CTRL_CH_RANGE,
+    V4L2_EVENT_CTRL_CH_VALUE,
+};
+
+// how often the event thread checks whether it should stop
+const STOP_POLL_INTERVAL_MS: i32 = 100;
+
+fn subscription(type_: u32, id: u32) -> v4l2_event_subscription {
+    v4l2_event_subscription {
+        type_,
+        id,
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+/// A thread that dequeues the control events of a device and hands them to a callback.
+///
+/// Unsubscribes from all events when dropped.
+pub struct ControlEvents {
+    die_bool: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ControlEvents {
+    /// Subscribes to change events of the `controls` of `handle`, and starts a thread calling `callback` with each.
+    pub fn subscribe(
+        handle: &Handle,
+        controls: &[u32],
+        mut callback: Box<dyn FnMut(ControlChange) + Send + 'static>,
+    ) -> io::Result<Self> {
+        // the duplicate shares the file handle the events are queued on
+        let fd = unsafe { libc::dup(handle.fd()) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        for id in controls {
+            let mut sub = subscription(V4L2_EVENT_CTRL, *id);
+            let subscribed = unsafe {
+                v4l2::ioctl(
+                    fd.as_raw_fd(),
+                    VIDIOC_SUBSCRIBE_EVENT,
+                    (&mut sub as *mut v4l2_event_subscription).cast(),
+                )
+            };
+            if let Err(why) = subscribed {
+                unsubscribe_all(fd.as_raw_fd());
+                return Err(why);
+            }
+        }
+
+        let die_bool = Arc::new(AtomicBool::new(false));
+        let thread_die_bool = die_bool.clone();
+        let handle = thread::spawn(move || {
+            while !thread_die_bool.load(Ordering::SeqCst) {
+                // only asking for POLLPRI, so a device that is not streaming does not report POLLERR
+                let mut pollfd = libc::pollfd {
+                    fd: fd.as_raw_fd(),
+                    events: libc::POLLPRI,
+                    revents: 0,
+                };
+                let ready = unsafe { libc::poll(&mut pollfd, 1, STOP_POLL_INTERVAL_MS) };
+                if ready <= 0 {
+                    continue;
+                }
+                if pollfd.revents & libc::POLLPRI == 0 {
+                    // the device is gone
+                    break;
+                }
+                while let Some(change) = dequeue(fd.as_raw_fd()) {
+                    callback(change);
+                }
+            }
+            unsubscribe_all(fd.as_raw_fd());
+        });
+
+        Ok(ControlEvents {
+            die_bool,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for ControlEvents {
+    fn drop(&mut self) {
+        self.die_bool.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            // a panicking callback already stopped the thread
+            let _ = handle.join();
+        }
+    }
+}
+
+fn unsubscribe_all(fd: RawFd) {
+    let mut sub = subscription(V4L2_EVENT_ALL, 0);
+    // fails only if the device is gone, taking the subscriptions with it
+    let _ = unsafe {
+        v4l2::ioctl(
+            fd,
+            VIDIOC_UNSUBSCRIBE_EVENT,
+            (&mut sub as *mut v4l2_event_subscription).cast(),
+        )
+    };
+}
+
+// Dequeues the next control event, `None` once there are no more
+fn dequeue(fd: RawFd) -> Option<ControlChange> {
+    loop {
+        let mut event: v4l2_event = unsafe { mem::zeroed() };
+        unsafe {
+            v4l2::ioctl(fd, VIDIOC_DQEVENT, (&mut event as *mut v4l2_event).cast()).ok()?;
+        }
+        if event.type_ != V4L2_EVENT_CTRL {
+            continue;
+        }
+
+        let ctrl = unsafe { event.u.ctrl };
+        let value = if ctrl.type_ == Type::Integer64 as u32 {
+            ControlValueSetter::Integer(unsafe { ctrl.__bindgen_anon_1.value64 })
+        } else if ctrl.type_ == Type::Boolean as u32 {
+            ControlValueSetter::Boolean(unsafe { ctrl.__bindgen_anon_1.value } != 0)
+        } else {
+            ControlValueSetter::Integer(i64::from(unsafe { ctrl.__bindgen_anon_1.value }))
+        };
+        return Some(ControlChange::new(
+            id_to_known_camera_control(event.id),
+            value,
+            ctrl.changes & V4L2_EVENT_CTRL_CH_VALUE != 0,
+            ctrl.changes & V4L2_EVENT_CTRL_CH_FLAGS != 0,
+            ctrl.changes & V4L2_EVENT_CTRL_CH_RANGE != 0,
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Request codes of the V4L2 ioctls that the `v4l` crate does not define, following `linux/videodev2.h`, and helpers for driving
+//! the device file descriptor directly.
+
+use std::{
+    io::{self, ErrorKind},
+    mem::size_of,
+    time::Duration,
+};
+use v4l::{
+    device::Handle,
+    v4l2::vidioc::_IOC_TYPE,
+    v4l_sys::{v4l2_event, v4l2_event_subscription, v4l2_selection},
+};
+
+const IOC_WRITE: u64 = 1;
+const IOC_READ: u64 = 2;
+
+#[allow(clippy::cast_possible_truncation)]
+pub const fn ioc(dir: u64, typ: u8, nr: u64, size: usize) -> _IOC_TYPE {
+    ((dir << 30) | ((size as u64) << 16) | ((typ as u64) << 8) | nr) as _IOC_TYPE
+}
+
+pub const fn ior(typ: u8, nr: u64, size: usize) -> _IOC_TYPE {
+    ioc(IOC_READ, typ, nr, size)
+}
+
+pub const fn iow(typ: u8, nr: u64, size: usize) -> _IOC_TYPE {
+    ioc(IOC_WRITE, typ, nr, size)
+}
+
+pub const fn iowr(typ: u8, nr: u64, size: usize) -> _IOC_TYPE {
+    ioc(IOC_READ | IOC_WRITE, typ, nr, size)
+}
+
+pub const VIDIOC_G_SELECTION: _IOC_TYPE = iowr(b'V', 94, size_of::<v4l2_selection>());
+pub const VIDIOC_S_SELECTION: _IOC_TYPE = iowr(b'V', 95, size_of::<v4l2_selection>());
+pub const VIDIOC_DQEVENT: _IOC_TYPE = ior(b'V', 89, size_of::<v4l2_event>());
+pub const VIDIOC_SUBSCRIBE_EVENT: _IOC_TYPE = iow(b'V', 90, size_of::<v4l2_event_subscription>());
+pub const VIDIOC_UNSUBSCRIBE_EVENT: _IOC_TYPE =
+    iow(b'V', 91, size_of::<v4l2_event_subscription>());
+
+/// Converts a timeout into the milliseconds `poll()` takes, `None` blocking forever.
+#[allow(clippy::cast_possible_truncation)]
+pub fn poll_timeout(timeout: Option<Duration>) -> i32 {
+    timeout.map_or(-1, |timeout| timeout.as_millis().min(i32::MAX as u128) as i32)
+}
+
+/// Waits until a buffer can be dequeued from `handle`, which is opened with `O_NONBLOCK`.
+/// Fails with [`ErrorKind::TimedOut`] if none is ready within `timeout`.
+pub fn wait_readable(handle: &Handle, timeout: Option<Duration>) -> io::Result<()> {
+    if handle.poll(libc::POLLIN, poll_timeout(timeout))? == 0 {
+        return Err(io::Error::new(ErrorKind::TimedOut, "VIDIOC_DQBUF"));
+    }
+    Ok(())
+}
+
+/// Waits until a frame can be written to `handle`, which is opened with `O_NONBLOCK`.
+/// Fails with [`ErrorKind::TimedOut`] if the consumer does not make room within `timeout`.
+pub fn wait_writable(handle: &Handle, timeout: Option<Duration>) -> io::Result<()> {
+    if handle.poll(libc::POLLOUT, poll_timeout(timeout))? == 0 {
+        return Err(io::Error::new(ErrorKind::TimedOut, "write"));
+    }
+    Ok(())
+}
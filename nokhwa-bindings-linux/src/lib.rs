@@ -14,40 +14,100 @@
  * limitations under the License.
  */
 
+#[cfg(target_os = "linux")]
+mod dmabuf;
+#[cfg(target_os = "linux")]
+mod events;
+#[cfg(target_os = "linux")]
+mod ioctl;
+#[cfg(target_os = "linux")]
+pub mod media;
+#[cfg(target_os = "linux")]
+mod metadata;
+#[cfg(target_os = "linux")]
+mod mplane;
+#[cfg(target_os = "linux")]
+mod poller;
+#[cfg(target_os = "linux")]
+mod usb;
+#[cfg(target_os = "linux")]
+mod userptr;
+
 #[cfg(target_os = "linux")]
 mod internal {
+    use crate::{
+        dmabuf::{self, DmaBufImportStream, ImportedDmaBuf},
+        events::ControlEvents,
+        ioctl::{wait_readable, wait_writable, VIDIOC_G_SELECTION, VIDIOC_S_SELECTION},
+        media::MediaGraphConfig,
+        metadata::{self, MetadataNode},
+        mplane::{self, MplaneStream},
+        usb::{self, UsbBandwidth},
+        userptr::{self, UserBuffer, UserPtrStream},
+    };
     use nokhwa_core::format_request::FormatFilter;
     use nokhwa_core::{
-        buffer::Buffer,
+        bayer::{unpack_bayer, BayerPacking},
+        buffer::{Buffer, FrameCounter, FrameTimestamp, FrameType, TimestampClock},
         error::NokhwaError,
-        traits::CaptureTrait,
+        frame_format::{BayerPattern, PlatformSpecific},
+        traits::{CaptureTrait, VirtualBackendTrait},
         types::{
-            ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo,
-            ControlValueDescription, ControlValueSetter, FrameFormat, KnownCameraControl,
-            KnownCameraControlFlag, RequestedFormat, RequestedFormatType, Resolution,
+            ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlChange,
+            ControlMenuEntry, FormatChanged,
+            ControlValueDescription, ControlValueSetter, DeviceCapabilities, DeviceDetails,
+            FrameFormat, FrameRate, KnownCameraControl,
+            KnownCameraControlFlag, Rect, RequestedFormat, RequestedFormatType, Resolution,
         },
     };
     use std::{
         borrow::Cow,
         collections::HashMap,
+        fs,
         io::{self, ErrorKind},
+        mem,
+        os::{
+            fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+            unix::fs::FileTypeExt,
+        },
+        path::{Path, PathBuf},
+        thread,
+        time::{Duration, Instant},
     };
     use v4l::{
-        control::{Control, Flags, Type, Value},
+        buffer::{Flags as BufferFlags, Metadata},
+        capability::{Capabilities, Flags as CapabilityFlags},
+        control::{Control, Flags, MenuItem, Type, Value},
         frameinterval::FrameIntervalEnum,
         framesize::FrameSizeEnum,
-        io::traits::CaptureStream,
+        fraction::Fraction,
+        io::traits::{CaptureStream, Stream as StreamTrait},
+        memory::Memory,
         prelude::MmapStream,
-        video::{capture::Parameters, Capture},
+        v4l2,
+        v4l_sys::{v4l2_buffer, v4l2_exportbuffer, v4l2_rect, v4l2_selection},
+        video::{
+            capture::Parameters, output::Parameters as OutputParameters, Capture, Output,
+        },
         Device, Format, FourCC,
     };
     use v4l2_sys_mit::{
         V4L2_CID_BACKLIGHT_COMPENSATION, V4L2_CID_BRIGHTNESS, V4L2_CID_CONTRAST, V4L2_CID_EXPOSURE,
         V4L2_CID_FOCUS_RELATIVE, V4L2_CID_GAIN, V4L2_CID_GAMMA, V4L2_CID_HUE,
-        V4L2_CID_IRIS_RELATIVE, V4L2_CID_PAN_RELATIVE, V4L2_CID_SATURATION, V4L2_CID_SHARPNESS,
+        V4L2_CID_IRIS_RELATIVE, V4L2_CID_JPEG_COMPRESSION_QUALITY, V4L2_CID_PAN_RELATIVE,
+        V4L2_CID_PRIVACY, V4L2_CID_SATURATION, V4L2_CID_SHARPNESS,
         V4L2_CID_TILT_RELATIVE, V4L2_CID_WHITE_BALANCE_TEMPERATURE, V4L2_CID_ZOOM_RELATIVE,
+        V4L2_CID_MPEG_VIDEO_BITRATE, V4L2_CID_MPEG_VIDEO_BITRATE_MODE,
+        V4L2_CID_MPEG_VIDEO_BITRATE_PEAK, V4L2_CID_MPEG_VIDEO_FORCE_KEY_FRAME,
+        V4L2_CID_MPEG_VIDEO_GOP_SIZE, V4L2_CID_MPEG_VIDEO_H264_I_PERIOD,
+        V4L2_CID_MPEG_VIDEO_H264_LEVEL, V4L2_CID_MPEG_VIDEO_H264_PROFILE,
+        V4L2_CID_MPEG_VIDEO_HEVC_LEVEL, V4L2_CID_MPEG_VIDEO_HEVC_PROFILE, V4L2_SEL_TGT_CROP,
+        V4L2_SEL_TGT_CROP_BOUNDS, V4L2_SEL_TGT_CROP_DEFAULT,
     };
 
+    // not in the generated bindings, `V4L2_CID_FLASH_CLASS_BASE + 1`. Its menu has `V4L2_FLASH_LED_MODE_TORCH` as 2.
+    const V4L2_CID_FLASH_LED_MODE: u32 = 0x009c_0901;
+
     /// Attempts to convert a [`KnownCameraControl`] into a V4L2 Control ID.
     /// If the associated control is not found, this will return `None` (`ColorEnable`, `Roll`)
     #[allow(clippy::cast_possible_truncation)]
@@ -68,6 +128,9 @@ mod internal {
             KnownCameraControl::Exposure => V4L2_CID_EXPOSURE,
             KnownCameraControl::Iris => V4L2_CID_IRIS_RELATIVE,
             KnownCameraControl::Focus => V4L2_CID_FOCUS_RELATIVE,
+            KnownCameraControl::JpegQuality => V4L2_CID_JPEG_COMPRESSION_QUALITY,
+            KnownCameraControl::Privacy => V4L2_CID_PRIVACY,
+            KnownCameraControl::Torch => V4L2_CID_FLASH_LED_MODE,
             KnownCameraControl::Other(id) => id as u32,
         }
     }
@@ -92,11 +155,84 @@ mod internal {
             V4L2_CID_EXPOSURE => KnownCameraControl::Exposure,
             V4L2_CID_IRIS_RELATIVE => KnownCameraControl::Iris,
             V4L2_CID_FOCUS_RELATIVE => KnownCameraControl::Focus,
+            V4L2_CID_JPEG_COMPRESSION_QUALITY => KnownCameraControl::JpegQuality,
+            V4L2_CID_PRIVACY => KnownCameraControl::Privacy,
+            V4L2_CID_FLASH_LED_MODE => KnownCameraControl::Torch,
             id => KnownCameraControl::Other(id as u128),
         }
     }
 
+    /// An encoder parameter of a camera that outputs compressed video, such as H.264 over UVC (`V4L2_CID_MPEG_*`).
+    ///
+    /// The current values (and whether the device supports them at all) show up in [`camera_controls()`](CaptureTrait::camera_controls)
+    /// as [`KnownCameraControl::Other`] with the control's `V4L2_CID_MPEG_*` ID.
+    #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+    pub enum CodecControl {
+        /// Average bitrate in bits per second.
+        Bitrate(u32),
+        /// Peak bitrate in bits per second. Only used with a variable bitrate.
+        BitratePeak(u32),
+        /// `true` for a constant bitrate, `false` for a variable bitrate.
+        ConstantBitrate(bool),
+        /// Size of a group of pictures, in frames.
+        GopSize(u32),
+        /// Distance between I-frames, in frames.
+        H264IFramePeriod(u32),
+        /// H.264 profile, as a `V4L2_MPEG_VIDEO_H264_PROFILE_*` value.
+        H264Profile(u32),
+        /// H.264 level, as a `V4L2_MPEG_VIDEO_H264_LEVEL_*` value.
+        H264Level(u32),
+        /// HEVC profile, as a `V4L2_MPEG_VIDEO_HEVC_PROFILE_*` value.
+        HevcProfile(u32),
+        /// HEVC level, as a `V4L2_MPEG_VIDEO_HEVC_LEVEL_*` value.
+        HevcLevel(u32),
+        /// Make the next frame a keyframe.
+        ForceKeyFrame,
+    }
+
+    impl CodecControl {
+        /// Gets the V4L2 Control ID of this control.
+        #[must_use]
+        pub fn id(&self) -> u32 {
+            match self {
+                CodecControl::Bitrate(_) => V4L2_CID_MPEG_VIDEO_BITRATE,
+                CodecControl::BitratePeak(_) => V4L2_CID_MPEG_VIDEO_BITRATE_PEAK,
+                CodecControl::ConstantBitrate(_) => V4L2_CID_MPEG_VIDEO_BITRATE_MODE,
+                CodecControl::GopSize(_) => V4L2_CID_MPEG_VIDEO_GOP_SIZE,
+                CodecControl::H264IFramePeriod(_) => V4L2_CID_MPEG_VIDEO_H264_I_PERIOD,
+                CodecControl::H264Profile(_) => V4L2_CID_MPEG_VIDEO_H264_PROFILE,
+                CodecControl::H264Level(_) => V4L2_CID_MPEG_VIDEO_H264_LEVEL,
+                CodecControl::HevcProfile(_) => V4L2_CID_MPEG_VIDEO_HEVC_PROFILE,
+                CodecControl::HevcLevel(_) => V4L2_CID_MPEG_VIDEO_HEVC_LEVEL,
+                CodecControl::ForceKeyFrame => V4L2_CID_MPEG_VIDEO_FORCE_KEY_FRAME,
+            }
+        }
+
+        fn to_control(self) -> Control {
+            let value = match self {
+                CodecControl::Bitrate(v)
+                | CodecControl::BitratePeak(v)
+                | CodecControl::GopSize(v)
+                | CodecControl::H264IFramePeriod(v)
+                | CodecControl::H264Profile(v)
+                | CodecControl::H264Level(v)
+                | CodecControl::HevcProfile(v)
+                | CodecControl::HevcLevel(v) => Value::Integer(i64::from(v)),
+                // V4L2_MPEG_VIDEO_BITRATE_MODE_VBR = 0, V4L2_MPEG_VIDEO_BITRATE_MODE_CBR = 1
+                CodecControl::ConstantBitrate(cbr) => Value::Integer(i64::from(cbr)),
+                CodecControl::ForceKeyFrame => Value::None,
+            };
+            Control {
+                id: self.id(),
+                value,
+            }
+        }
+    }
+
     /// query v4l2 cameras
+    ///
+    /// Every node gets [`DeviceDetails`] if it can be opened. Use [`DeviceCapabilities::is_video_capture()`] to tell cameras apart from
+    /// codec (memory-to-memory) and metadata nodes.
     #[allow(clippy::unnecessary_wraps)]
     #[allow(clippy::cast_possible_truncation)]
     pub fn query() -> Result<Vec<CameraInfo>, NokhwaError> {
@@ -104,30 +240,528 @@ mod internal {
             let camera_info: Vec<CameraInfo> = v4l::context::enum_devices()
                 .iter()
                 .map(|node| {
-                    CameraInfo::new(
+                    let camera_info = CameraInfo::new(
                         &node
                             .name()
                             .unwrap_or(format!("{}", node.path().to_string_lossy())),
                         &format!("Video4Linux Device @ {}", node.path().to_string_lossy()),
                         "",
                         CameraIndex::Index(node.index() as u32),
-                    )
+                    );
+                    match Device::with_path(node.path()).and_then(|device| device.query_caps()) {
+                        Ok(capabilities) => camera_info.with_details(device_details(&capabilities)),
+                        Err(_) => camera_info,
+                    }
                 })
                 .collect();
             camera_info
         })
     }
 
+    const BY_ID_DIR: &str = "/dev/v4l/by-id";
+    const BY_PATH_DIR: &str = "/dev/v4l/by-path";
+
+    /// Resolves a [`CameraIndex`] to the device node it refers to.
+    ///
+    /// [`CameraIndex::Index`] maps to `/dev/video{index}`. [`CameraIndex::String`] is a path to the node or to a symlink pointing at it,
+    /// such as the stable `/dev/v4l/by-id/...` and `/dev/v4l/by-path/...` names udev creates, which survive re-enumeration. A bare link name
+    /// (e.g. `usb-046d_HD_Pro_Webcam_C920-video-index0`) is looked up in `/dev/v4l/by-id`, then `/dev/v4l/by-path`.
+    /// # Errors
+    /// This will error if the path does not exist or does not resolve to a character device.
+    pub fn device_path(index: &CameraIndex) -> Result<PathBuf, NokhwaError> {
+        let path = match index {
+            CameraIndex::Index(idx) => return Ok(PathBuf::from(format!("/dev/video{idx}"))),
+            CameraIndex::String(name) if name.contains('/') => PathBuf::from(name),
+            CameraIndex::String(name) => [BY_ID_DIR, BY_PATH_DIR]
+                .iter()
+                .map(|dir| Path::new(dir).join(name))
+                .find(|link| link.exists())
+                .unwrap_or_else(|| PathBuf::from(name)),
+        };
+
+        let resolved = fs::canonicalize(&path).map_err(|why| {
+            NokhwaError::OpenDeviceError(path.to_string_lossy().to_string(), why.to_string())
+        })?;
+        let is_char_device = fs::metadata(&resolved)
+            .map(|metadata| metadata.file_type().is_char_device())
+            .unwrap_or(false);
+        if !is_char_device {
+            return Err(NokhwaError::OpenDeviceError(
+                path.to_string_lossy().to_string(),
+                format!("{} is not a device node", resolved.to_string_lossy()),
+            ));
+        }
+        Ok(resolved)
+    }
+
+    /// Checks whether `cameras` can stream their current formats at the same time, given the isochronous bandwidth of the USB buses they are on.
+    /// Bandwidth already reserved by other devices on the same buses (e.g. cameras of other processes) is not known and not accounted for.
+    /// # Errors
+    /// If the cameras on any bus are estimated to need more bandwidth than the bus has, this will error with the cameras and bandwidths involved.
+    /// Lowering the resolution or frame rate, using a compressed format such as [`FrameFormat::MJpeg`], or moving a camera to another bus
+    /// (e.g. another USB controller) may help. If the sysfs entries of a camera can not be read, this will error too.
+    pub fn check_usb_bandwidth(cameras: &[&V4LCaptureDevice<'_>]) -> Result<(), NokhwaError> {
+        let mut buses: HashMap<u32, (u64, u64, Vec<String>)> = HashMap::new();
+        for camera in cameras {
+            if let Some(bandwidth) = camera.usb_bandwidth()? {
+                let (needed, capacity, names) = buses.entry(bandwidth.bus()).or_default();
+                *needed += bandwidth.estimated();
+                *capacity = bandwidth.bus_capacity();
+                names.push(camera.camera_info.human_name());
+            }
+        }
+
+        for (bus, (needed, capacity, names)) in buses {
+            if needed > capacity {
+                return Err(NokhwaError::OpenStreamError(format!(
+                    "{} on USB bus {bus} need about {needed} of {capacity} bytes/s, lower the resolution or frame rate, use a compressed format, or move a camera to another USB controller",
+                    names.join(", "),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The memory model used to exchange frame buffers with the driver.
+    #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum CaptureMemory {
+        /// Driver allocated buffers mapped into this process (`V4L2_MEMORY_MMAP`). Frames are read through [`frame()`](CaptureTrait::frame) and [`frame_raw()`](CaptureTrait::frame_raw).
+        #[default]
+        Mmap,
+        /// Driver allocated buffers that are additionally exported as dma-buf file descriptors (`VIDIOC_EXPBUF`).
+        /// Frames are read through [`frame_dmabuf()`](V4LCaptureDevice::frame_dmabuf) and can be imported into EGL, Vulkan, `wgpu` or a hardware encoder without a CPU copy.
+        DmaBuf,
+        /// Frames are copied out of the driver with `read()` (`V4L2_CAP_READWRITE`), and read through [`frame()`](CaptureTrait::frame) and [`frame_raw()`](CaptureTrait::frame_raw).
+        /// Used automatically for devices that do not support streaming I/O, such as some virtual and legacy drivers.
+        Read,
+        /// Buffers allocated by the application, which the driver captures into directly (`V4L2_MEMORY_USERPTR`), e.g. pinned memory
+        /// for CUDA or a shared memory segment. Set them with [`user_buffers()`](V4LCaptureDeviceBuilder::user_buffers).
+        /// Frames are read through [`frame_userptr()`](V4LCaptureDevice::frame_userptr), [`frame()`](CaptureTrait::frame) and [`frame_raw()`](CaptureTrait::frame_raw).
+        UserPtr,
+        /// Dma-bufs allocated by the application, which the driver captures into directly (`V4L2_MEMORY_DMABUF`), e.g. from a `dma-heap`,
+        /// GBM, or memory exported by a GPU or encoder. Set them with [`dmabufs()`](V4LCaptureDeviceBuilder::dmabufs).
+        /// Frames are read through [`frame_dmabuf()`](V4LCaptureDevice::frame_dmabuf).
+        DmaBufImport,
+    }
+
+    /// A frame that lives in a dma-buf, either exported by the driver or imported from the application.
+    ///
+    /// The buffer stays dequeued (and therefore will not be overwritten by the driver) until the next call to [`frame_dmabuf()`](V4LCaptureDevice::frame_dmabuf).
+    /// If the file descriptor is duplicated to keep it past that point, synchronization is up to the importer.
+    #[derive(Debug)]
+    pub struct DmaBufFrame<'a> {
+        fd: BorrowedFd<'a>,
+        index: usize,
+        bytes_used: u32,
+        resolution: Resolution,
+        source_frame_format: FrameFormat,
+        timestamp: Option<FrameTimestamp>,
+        sequence: u32,
+    }
+
+    impl<'a> DmaBufFrame<'a> {
+        /// Get the dma-buf file descriptor backing this frame.
+        #[must_use]
+        pub fn fd(&self) -> BorrowedFd<'a> {
+            self.fd
+        }
+
+        /// Get the driver's index of the buffer backing this frame.
+        #[must_use]
+        pub fn index(&self) -> usize {
+            self.index
+        }
+
+        /// Get the amount of bytes of the dma-buf that are occupied by frame data.
+        #[must_use]
+        pub fn bytes_used(&self) -> u32 {
+            self.bytes_used
+        }
+
+        /// Get the resolution of the frame.
+        #[must_use]
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
+        }
+
+        /// Get the [`FrameFormat`] of the frame data.
+        #[must_use]
+        pub fn source_frame_format(&self) -> FrameFormat {
+            self.source_frame_format
+        }
+
+        /// Get the time the frame was captured at, if the driver reports it.
+        #[must_use]
+        pub fn timestamp(&self) -> Option<FrameTimestamp> {
+            self.timestamp
+        }
+
+        /// Get the driver's sequence number of the frame. Gaps between consecutive frames mean frames were dropped.
+        #[must_use]
+        pub fn sequence(&self) -> u32 {
+            self.sequence
+        }
+    }
+
+    /// A frame that the driver captured into one of the application's [`UserBuffer`]s.
+    ///
+    /// The buffer stays dequeued (and therefore will not be overwritten by the driver) until the next call to [`frame_userptr()`](V4LCaptureDevice::frame_userptr).
+    #[derive(Debug)]
+    pub struct UserPtrFrame<'a> {
+        data: &'a [u8],
+        buffer: UserBuffer,
+        resolution: Resolution,
+        source_frame_format: FrameFormat,
+        timestamp: Option<FrameTimestamp>,
+        sequence: u32,
+    }
+
+    impl<'a> UserPtrFrame<'a> {
+        /// Get the frame data, the part of the [`UserBuffer`] the driver filled.
+        #[must_use]
+        pub fn data(&self) -> &'a [u8] {
+            self.data
+        }
+
+        /// Get the [`UserBuffer`] the frame was captured into.
+        #[must_use]
+        pub fn buffer(&self) -> UserBuffer {
+            self.buffer
+        }
+
+        /// Get the resolution of the frame.
+        #[must_use]
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
+        }
+
+        /// Get the [`FrameFormat`] of the frame data.
+        #[must_use]
+        pub fn source_frame_format(&self) -> FrameFormat {
+            self.source_frame_format
+        }
+
+        /// Get the time the frame was captured at, if the driver reports it.
+        #[must_use]
+        pub fn timestamp(&self) -> Option<FrameTimestamp> {
+            self.timestamp
+        }
+
+        /// Get the driver's sequence number of the frame. Gaps between consecutive frames mean frames were dropped.
+        #[must_use]
+        pub fn sequence(&self) -> u32 {
+            self.sequence
+        }
+    }
+
+    /// The amount of buffers requested from the driver if none is specified.
+    pub const DEFAULT_BUFFER_COUNT: u32 = 4;
+
+    /// How long a frame is waited for if no timeout is specified.
+    pub const DEFAULT_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Builder for a [`V4LCaptureDevice`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct V4LCaptureDeviceBuilder {
+        index: CameraIndex,
+        capture_memory: CaptureMemory,
+        buffer_count: u32,
+        frame_timeout: Option<Duration>,
+        media_graph: Option<MediaGraphConfig>,
+        attach_metadata: bool,
+        user_buffers: Vec<UserBuffer>,
+        dmabufs: Vec<ImportedDmaBuf>,
+    }
+
+    impl V4LCaptureDeviceBuilder {
+        /// Creates a new builder for the device at `index`.
+        #[must_use]
+        pub fn new(index: &CameraIndex) -> Self {
+            V4LCaptureDeviceBuilder {
+                index: index.clone(),
+                capture_memory: CaptureMemory::default(),
+                buffer_count: DEFAULT_BUFFER_COUNT,
+                frame_timeout: Some(DEFAULT_FRAME_TIMEOUT),
+                media_graph: None,
+                attach_metadata: false,
+                user_buffers: vec![],
+                dmabufs: vec![],
+            }
+        }
+
+        /// Sets the [`CaptureMemory`] used to exchange frame buffers with the driver.
+        #[must_use]
+        pub fn capture_memory(mut self, capture_memory: CaptureMemory) -> Self {
+            self.capture_memory = capture_memory;
+            self
+        }
+
+        /// Sets the amount of buffers requested from the driver when the stream is opened. More buffers absorb bursts of consumer latency
+        /// at the cost of memory and, if the consumer falls behind, older frames.
+        ///
+        /// The driver may grant a different amount, see [`V4LCaptureDevice::buffer_count()`].
+        #[must_use]
+        pub fn buffer_count(mut self, buffer_count: u32) -> Self {
+            self.buffer_count = buffer_count.max(1);
+            self
+        }
+
+        /// Sets how long reading a frame waits for the driver before failing with [`NokhwaError::TimeoutError`], `None` waiting forever.
+        /// Defaults to [`DEFAULT_FRAME_TIMEOUT`].
+        #[must_use]
+        pub fn frame_timeout(mut self, frame_timeout: Option<Duration>) -> Self {
+            self.frame_timeout = frame_timeout;
+            self
+        }
+
+        /// Sets a [`MediaGraphConfig`] that is applied before the device is opened. SoC camera pipelines need their sensor, CSI-2 receiver
+        /// and ISP linked and their pad formats set before the capture node produces frames.
+        #[must_use]
+        pub fn media_graph(mut self, media_graph: MediaGraphConfig) -> Self {
+            self.media_graph = Some(media_graph);
+            self
+        }
+
+        /// Sets whether the metadata capture node paired with the device (`V4L2_CAP_META_CAPTURE`, e.g. the second node of a UVC camera)
+        /// is streamed alongside it. Its records are matched to frames by sequence number, and attached to the [`Buffer`]s returned by
+        /// [`frame()`](CaptureTrait::frame) as [`FrameMetadata`](nokhwa_core::buffer::FrameMetadata).
+        ///
+        /// Only supported with [`CaptureMemory::Mmap`] and [`CaptureMemory::UserPtr`].
+        #[must_use]
+        pub fn attach_metadata(mut self, attach_metadata: bool) -> Self {
+            self.attach_metadata = attach_metadata;
+            self
+        }
+
+        /// Sets the buffers the driver captures into with [`CaptureMemory::UserPtr`], which also selects it. One buffer is queued per
+        /// [`UserBuffer`], [`buffer_count()`](V4LCaptureDeviceBuilder::buffer_count) is ignored. Each must hold a whole frame of every
+        /// [`CameraFormat`] the stream is opened with.
+        #[must_use]
+        pub fn user_buffers(mut self, user_buffers: Vec<UserBuffer>) -> Self {
+            self.capture_memory = CaptureMemory::UserPtr;
+            self.user_buffers = user_buffers;
+            self
+        }
+
+        /// Sets the dma-bufs the driver captures into with [`CaptureMemory::DmaBufImport`], which also selects it. One buffer is queued per
+        /// [`ImportedDmaBuf`], [`buffer_count()`](V4LCaptureDeviceBuilder::buffer_count) is ignored. Each must hold a whole frame of every
+        /// [`CameraFormat`] the stream is opened with.
+        #[must_use]
+        pub fn dmabufs(mut self, dmabufs: Vec<ImportedDmaBuf>) -> Self {
+            self.capture_memory = CaptureMemory::DmaBufImport;
+            self.dmabufs = dmabufs;
+            self
+        }
+
+        /// Opens the device.
+        /// # Errors
+        /// This function will error if the camera is currently busy, if `V4L2` can't read device information, or if the device does not support streaming I/O.
+        pub fn build<'a>(self) -> Result<V4LCaptureDevice<'a>, NokhwaError> {
+            if let Some(media_graph) = &self.media_graph {
+                media_graph.apply()?;
+            }
+            let path = device_path(&self.index)?.to_string_lossy().to_string();
+            let device = Device::with_path(&path)
+                .map_err(|why| NokhwaError::OpenDeviceError(path.clone(), why.to_string()))?;
+            let capabilities =
+                device
+                    .query_caps()
+                    .map_err(|why| NokhwaError::GetPropertyError {
+                        property: "Capabilities".to_string(),
+                        error: why.to_string(),
+                    })?;
+            // by-id links also point at the metadata nodes of UVC cameras
+            if !capabilities.capabilities.intersects(
+                CapabilityFlags::VIDEO_CAPTURE | CapabilityFlags::VIDEO_CAPTURE_MPLANE,
+            ) {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    "Device is not a video capture node".to_string(),
+                ));
+            }
+            let mut capture_memory = self.capture_memory;
+            if !capabilities
+                .capabilities
+                .contains(CapabilityFlags::STREAMING)
+            {
+                if capture_memory != CaptureMemory::Mmap
+                    || !capabilities
+                        .capabilities
+                        .contains(CapabilityFlags::READ_WRITE)
+                {
+                    return Err(NokhwaError::OpenDeviceError(
+                        path,
+                        "Device does not support streaming I/O".to_string(),
+                    ));
+                }
+                capture_memory = CaptureMemory::Read;
+            }
+            if capture_memory == CaptureMemory::Read
+                && !capabilities
+                    .capabilities
+                    .contains(CapabilityFlags::READ_WRITE)
+            {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    "Device does not support read() I/O".to_string(),
+                ));
+            }
+            // Some SoC capture interfaces only implement the multi-planar API
+            let multiplanar = !capabilities
+                .capabilities
+                .contains(CapabilityFlags::VIDEO_CAPTURE)
+                && capabilities
+                    .capabilities
+                    .contains(CapabilityFlags::VIDEO_CAPTURE_MPLANE);
+            if multiplanar && capture_memory != CaptureMemory::Mmap {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    format!("{capture_memory:?} is not supported on multi-planar devices"),
+                ));
+            }
+            if capture_memory == CaptureMemory::UserPtr {
+                if self.user_buffers.is_empty() {
+                    return Err(NokhwaError::OpenDeviceError(
+                        path,
+                        "CaptureMemory::UserPtr needs buffers, see user_buffers()".to_string(),
+                    ));
+                }
+                if !userptr::is_supported(&device) {
+                    return Err(NokhwaError::OpenDeviceError(
+                        path,
+                        "Device does not support user pointer I/O".to_string(),
+                    ));
+                }
+            }
+            if capture_memory == CaptureMemory::DmaBufImport {
+                if self.dmabufs.is_empty() {
+                    return Err(NokhwaError::OpenDeviceError(
+                        path,
+                        "CaptureMemory::DmaBufImport needs buffers, see dmabufs()".to_string(),
+                    ));
+                }
+                if !dmabuf::is_supported(&device) {
+                    return Err(NokhwaError::OpenDeviceError(
+                        path,
+                        "Device does not support dma-buf import".to_string(),
+                    ));
+                }
+            }
+            if self.attach_metadata
+                && !matches!(capture_memory, CaptureMemory::Mmap | CaptureMemory::UserPtr)
+            {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    format!(
+                        "Metadata can not be attached to frames captured with {capture_memory:?}"
+                    ),
+                ));
+            }
+            let metadata_node = if self.attach_metadata {
+                Some(open_metadata_node(&path)?)
+            } else {
+                None
+            };
+
+            let camera_info = CameraInfo::new(
+                &capabilities.card,
+                &format!("Video4Linux Device @ {path}"),
+                &capabilities.driver,
+                &self.index,
+            )
+            .with_details(device_details(&capabilities));
+
+            Ok(V4LCaptureDevice {
+                init: false,
+                camera_format: None,
+                camera_info,
+                device,
+                stream_handle: None,
+                multiplanar,
+                mplane_stream: None,
+                userptr_stream: None,
+                user_buffers: self.user_buffers,
+                dmabuf_import_stream: None,
+                dmabufs: self.dmabufs,
+                capture_memory,
+                requested_buffer_count: self.buffer_count,
+                granted_buffer_count: None,
+                frame_timeout: self.frame_timeout,
+                packed_bayer: None,
+                dmabuf_fds: vec![],
+                dmabuf_index: None,
+                strides: vec![],
+                read_buffer: None,
+                mmap_started: false,
+                stream_lost: false,
+                frame_counter: FrameCounter::new(),
+                metadata_node,
+                control_events: None,
+                format_change_callback: None,
+            })
+        }
+    }
+
     /// The backend struct that interfaces with V4L2.
     /// To see what this does, please see [`CaptureTrait`].
     /// # Quirks
     /// - Calling [`set_resolution()`](CaptureTrait::set_resolution), [`set_frame_rate()`](CaptureTrait::set_frame_rate), or [`set_frame_format()`](CaptureTrait::set_frame_format) each internally calls [`set_camera_format()`](CaptureTrait::set_camera_format).
+    /// - Devices that only implement the multi-planar API (`V4L2_CAP_VIDEO_CAPTURE_MPLANE`) return [`Buffer`]s with one [`BufferPlane`](nokhwa_core::buffer::BufferPlane) per plane. [`frame_raw()`](CaptureTrait::frame_raw) copies the planes together.
+    /// - When created with [`CaptureMemory::DmaBuf`] or [`CaptureMemory::DmaBufImport`], frames must be read with [`frame_dmabuf()`](V4LCaptureDevice::frame_dmabuf).
+    ///   [`frame()`](CaptureTrait::frame) and [`frame_raw()`](CaptureTrait::frame_raw) will error.
+    /// - Devices without streaming I/O are read with `read()` instead, see [`CaptureMemory::Read`]. Their [`Buffer`]s carry no timestamp or sequence number,
+    ///   and [`dropped_frames()`](CaptureTrait::dropped_frames) stays `0`.
+    /// - [`dropped_frames()`](CaptureTrait::dropped_frames) counts the gaps in the driver's sequence numbers, over all ways of reading frames.
+    /// - With [`CaptureMemory::UserPtr`], [`frame_raw()`](CaptureTrait::frame_raw) borrows the frame from the application's [`UserBuffer`] without a copy.
+    ///   [`frame()`](CaptureTrait::frame) copies it into the [`Buffer`].
+    /// - The device is opened non-blocking. Reading a frame waits up to the [frame timeout](V4LCaptureDevice::set_frame_timeout) and then fails with
+    ///   [`NokhwaError::TimeoutError`], so a stalled sensor does not hang the caller. The file descriptor (see [`AsFd`]) polls readable once a frame is ready.
+    /// - Bayer formats captured in a MIPI packed layout (e.g. `pRAA`) are reported as [`FrameFormat::Bayer10`] / [`FrameFormat::Bayer12`] and unpacked
+    ///   by [`frame()`](CaptureTrait::frame). [`frame_raw()`](CaptureTrait::frame_raw) returns the packed data.
+    /// - Drivers may pad rows, so [`Buffer`]s carry the driver's `bytesperline` of each plane as its [`stride`](nokhwa_core::buffer::BufferPlane::stride).
+    /// - [`CameraIndex::String`] may be a device path or a stable `/dev/v4l/by-id` / `/dev/v4l/by-path` name, see [`device_path()`].
+    /// - [`on_control_change()`](CaptureTrait::on_control_change) reports changes made by the driver or by other processes, not the ones made through this device.
+    /// - [`hardware_privacy()`](CaptureTrait::hardware_privacy) reads a read-only `V4L2_CID_PRIVACY`. A writable one is a software mode, set through [`KnownCameraControl::Privacy`].
+    /// - A USB camera whose bus has no bandwidth left fails to start streaming with [`NokhwaError::OpenStreamError`].
+    ///   See [`usb_bandwidth()`](V4LCaptureDevice::usb_bandwidth) and [`check_usb_bandwidth()`].
+    /// - If the camera is unplugged or resets mid-stream, reading a frame fails with [`NokhwaError::DeviceLost`]. The stream's buffers are unmapped
+    ///   on the next call, which keeps failing with [`NokhwaError::DeviceLost`] until the device is [`reconnect()`](V4LCaptureDevice::reconnect)ed.
+    /// - With [`attach_metadata()`](V4LCaptureDeviceBuilder::attach_metadata), a frame whose metadata record has not arrived yet is returned without
+    ///   [`FrameMetadata`](nokhwa_core::buffer::FrameMetadata). UVC records carry the host timestamp and clock references, and exposure, ISO and
+    ///   white balance if the camera sends Microsoft's UVC 1.5 capture statistics.
     pub struct V4LCaptureDevice<'a> {
         init: bool,
         camera_format: Option<CameraFormat>,
         camera_info: CameraInfo,
         device: Device,
         stream_handle: Option<MmapStream<'a>>,
+        multiplanar: bool,
+        mplane_stream: Option<MplaneStream>,
+        userptr_stream: Option<UserPtrStream>,
+        user_buffers: Vec<UserBuffer>,
+        dmabuf_import_stream: Option<DmaBufImportStream>,
+        dmabufs: Vec<ImportedDmaBuf>,
+        capture_memory: CaptureMemory,
+        requested_buffer_count: u32,
+        granted_buffer_count: Option<u32>,
+        frame_timeout: Option<Duration>,
+        // packing and bytes per line of a packed Bayer format, unpacked in frame()
+        packed_bayer: Option<(BayerPacking, u32)>,
+        dmabuf_fds: Vec<OwnedFd>,
+        dmabuf_index: Option<usize>,
+        // bytes per line of each plane of the open stream
+        strides: Vec<usize>,
+        // the frame read() copies into when using CaptureMemory::Read
+        read_buffer: Option<Vec<u8>>,
+        // whether the single-planar mmap stream has its buffers queued and is streaming
+        mmap_started: bool,
+        // set once the driver reported the device gone, the stream is torn down on the next call
+        stream_lost: bool,
+        // counts gaps in the driver's sequence numbers of the open stream
+        frame_counter: FrameCounter,
+        metadata_node: Option<MetadataNode>,
+        control_events: Option<ControlEvents>,
+        format_change_callback: Option<Box<dyn FnMut(FormatChanged) + Send + 'static>>,
     }
 
     impl<'a> V4LCaptureDevice<'a> {
@@ -135,41 +769,612 @@ mod internal {
         /// # Errors
         /// This function will error if the camera is currently busy or if `V4L2` can't read device information.
         #[allow(clippy::too_many_lines)]
-        pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {}
+        pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+            Self::new_with_memory(index, CaptureMemory::default())
+        }
+
+        /// Creates a new capture device using the `V4L2` backend, exchanging frame buffers with the driver using `memory`.
+        /// # Errors
+        /// This function will error if the camera is currently busy, if `V4L2` can't read device information, or if the device does not support streaming I/O.
+        pub fn new_with_memory(
+            index: &CameraIndex,
+            memory: CaptureMemory,
+        ) -> Result<Self, NokhwaError> {
+            Self::builder(index).capture_memory(memory).build()
+        }
+
+        /// Creates a [`V4LCaptureDeviceBuilder`] for the device at `index`, to configure the device before it is opened.
+        #[must_use]
+        pub fn builder(index: &CameraIndex) -> V4LCaptureDeviceBuilder {
+            V4LCaptureDeviceBuilder::new(index)
+        }
+
+        /// Gets the [`CaptureMemory`] this device uses. This is [`CaptureMemory::Read`] if the device was created with [`CaptureMemory::Mmap`]
+        /// but does not support streaming I/O.
+        #[must_use]
+        pub fn capture_memory(&self) -> CaptureMemory {
+            self.capture_memory
+        }
+
+        /// Returns `true` if the device is driven through the multi-planar API.
+        #[must_use]
+        pub fn is_multiplanar(&self) -> bool {
+            self.multiplanar
+        }
+
+        /// Gets the amount of buffers granted by the driver for the open stream, which may differ from the amount requested with
+        /// [`V4LCaptureDeviceBuilder::buffer_count()`]. Returns `None` if the stream is not open.
+        #[must_use]
+        pub fn buffer_count(&self) -> Option<u32> {
+            self.granted_buffer_count
+        }
+
+        /// Gets how long reading a frame waits for the driver, `None` waiting forever.
+        #[must_use]
+        pub fn frame_timeout(&self) -> Option<Duration> {
+            self.frame_timeout
+        }
+
+        /// Sets how long reading a frame waits for the driver before failing with [`NokhwaError::TimeoutError`], `None` waiting forever.
+        /// This applies to the open stream immediately.
+        pub fn set_frame_timeout(&mut self, frame_timeout: Option<Duration>) {
+            self.frame_timeout = frame_timeout;
+            if let Some(stream) = &mut self.stream_handle {
+                match frame_timeout {
+                    Some(timeout) => stream.set_timeout(timeout),
+                    None => stream.clear_timeout(),
+                }
+            }
+            if let Some(stream) = &mut self.mplane_stream {
+                stream.set_timeout(frame_timeout);
+            }
+            if let Some(stream) = &mut self.userptr_stream {
+                stream.set_timeout(frame_timeout);
+            }
+            if let Some(stream) = &mut self.dmabuf_import_stream {
+                stream.set_timeout(frame_timeout);
+            }
+        }
+
+        /// Sets encoder parameters of a camera that outputs compressed video. All controls are applied atomically through
+        /// `VIDIOC_S_EXT_CTRLS`: either all of them are applied, or none are.
+        /// # Errors
+        /// This will error if `controls` is empty, or if the device does not support, or rejects, any of the controls.
+        pub fn set_codec_controls(&mut self, controls: &[CodecControl]) -> Result<(), NokhwaError> {
+            self.device
+                .set_controls(controls.iter().map(|ctrl| ctrl.to_control()).collect())
+                .map_err(|why| NokhwaError::SetPropertyError {
+                    property: "V4L2 Codec Controls".to_string(),
+                    value: format!("{controls:?}"),
+                    error: why.to_string(),
+                })
+        }
+
+        /// Starts streaming without waiting for a frame, opening the stream if needed. Afterwards the file descriptor (see [`AsFd`])
+        /// polls readable once a frame is ready, so the device can be driven by an event loop such as
+        /// [`MultiCameraPoller`](crate::MultiCameraPoller). Has no effect if the stream is already streaming.
+        /// # Errors
+        /// This will error if the device was created with [`CaptureMemory::DmaBuf`] or [`CaptureMemory::DmaBufImport`], or if the driver fails to start the stream.
+        pub fn start_stream(&mut self) -> Result<(), NokhwaError> {
+            if matches!(
+                self.capture_memory,
+                CaptureMemory::DmaBuf | CaptureMemory::DmaBufImport
+            ) {
+                return Err(NokhwaError::OpenStreamError(format!(
+                    "Device uses CaptureMemory::{:?}, the stream starts with frame_dmabuf()",
+                    self.capture_memory
+                )));
+            }
+            self.release_lost_stream()?;
+            if !self.is_stream_open() {
+                self.open_stream()?;
+            }
+            if self.read_buffer.is_some() {
+                // the driver starts capturing on the first read()
+                return Ok(());
+            }
+            if let Some(stream) = &mut self.mplane_stream {
+                return stream.start().map_err(stream_on_error);
+            }
+            if let Some(stream) = &mut self.userptr_stream {
+                return stream.start().map_err(stream_on_error);
+            }
+            if self.mmap_started {
+                return Ok(());
+            }
+
+            let buffer_count = self.granted_buffer_count.unwrap_or_default() as usize;
+            let stream = self
+                .stream_handle
+                .as_mut()
+                .ok_or(NokhwaError::UnitializedError)?;
+            // once the stream is active, next() queues the buffer it returned last (initially buffer 0) before dequeueing
+            (1..buffer_count)
+                .try_for_each(|index| CaptureStream::queue(stream, index))
+                .and_then(|()| StreamTrait::start(stream))
+                .map_err(stream_on_error)?;
+            self.mmap_started = true;
+            Ok(())
+        }
+
+        /// Estimates the isochronous USB bandwidth the camera needs for its current format, from the USB descriptors in sysfs.
+        /// Returns `None` if the camera is not a USB camera, or uses bulk transfers which do not reserve bandwidth.
+        ///
+        /// USB cameras reserve bandwidth on their bus when they start streaming. If the bus has none left, the driver refuses with
+        /// `ENOSPC` ("No space left on device"). See [`check_usb_bandwidth()`] to check several cameras before streaming.
+        /// # Errors
+        /// If the sysfs entries of the device can not be read, this will error.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        pub fn usb_bandwidth(&self) -> Result<Option<UsbBandwidth>, NokhwaError> {
+            let path = device_path(self.camera_info.index())?;
+            let required = self
+                .camera_format
+                .filter(|format| !FrameFormat::COMPRESSED.contains(&format.format()))
+                .and_then(|format| {
+                    let size = self.device_format().ok()?.size;
+                    Some((f64::from(size) * f64::from(format.frame_rate().as_float())) as u64)
+                });
+            usb::usb_bandwidth(&path, required).map_err(|why| NokhwaError::GetPropertyError {
+                property: "USB Bandwidth".to_string(),
+                error: why.to_string(),
+            })
+        }
+
+        /// Returns `true` if the driver reported the device gone, see [`reconnect()`](V4LCaptureDevice::reconnect).
+        #[must_use]
+        pub fn is_device_lost(&self) -> bool {
+            self.stream_lost
+        }
+
+        /// Reopens the device after it was lost (e.g. a USB camera reset or was replugged), closing the stream and restoring the
+        /// [`CameraFormat`]. The stream is left closed, open it again with [`open_stream()`](CaptureTrait::open_stream).
+        ///
+        /// The device is found again through the [`CameraIndex`] it was created with. A USB camera may come back under a different `/dev/videoN`,
+        /// so devices that are expected to reconnect should be created from a stable `/dev/v4l/by-id` or `/dev/v4l/by-path` name.
+        /// # Errors
+        /// This will error if the device is not back yet, if it is no longer a capture device of the same kind, or if it rejects the previous [`CameraFormat`].
+        /// # Quirks
+        /// - Subscriptions made with [`on_control_change()`](CaptureTrait::on_control_change) are dropped.
+        /// - The [`MediaGraphConfig`] given to the builder is not applied again.
+        pub fn reconnect(&mut self) -> Result<(), NokhwaError> {
+            self.release_stream();
+            self.control_events = None;
+
+            let path = device_path(self.camera_info.index())?
+                .to_string_lossy()
+                .to_string();
+            let device = Device::with_path(&path)
+                .map_err(|why| NokhwaError::OpenDeviceError(path.clone(), why.to_string()))?;
+            let capabilities =
+                device
+                    .query_caps()
+                    .map_err(|why| NokhwaError::GetPropertyError {
+                        property: "Capabilities".to_string(),
+                        error: why.to_string(),
+                    })?;
+            let multiplanar = !capabilities
+                .capabilities
+                .contains(CapabilityFlags::VIDEO_CAPTURE)
+                && capabilities
+                    .capabilities
+                    .contains(CapabilityFlags::VIDEO_CAPTURE_MPLANE);
+            if multiplanar != self.multiplanar {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    "Device came back as a different kind of capture node".to_string(),
+                ));
+            }
+
+            if self.metadata_node.is_some() {
+                self.metadata_node = Some(open_metadata_node(&path)?);
+            }
+            self.device = device;
+            self.stream_lost = false;
+            if let Some(camera_format) = self.camera_format {
+                self.set_camera_format(camera_format)?;
+            }
+            Ok(())
+        }
+
+        /// Changes the [`CameraFormat`] of an open stream in one step: the stream is stopped, the new format negotiated, the buffers
+        /// reallocated for the new frame size, and the stream started again if it was running. Consumers only see a short gap in frames,
+        /// after which the callback set with [`on_format_change()`](V4LCaptureDevice::on_format_change) is called.
+        ///
+        /// If the stream is not open, this only sets the format.
+        /// # Errors
+        /// If the device rejects the format, the previous format and stream are restored and this will error. If restoring them fails
+        /// too, the stream is left closed.
+        /// # Quirks
+        /// - The driver starts counting [`sequence`](Buffer::sequence) numbers from 0 again.
+        pub fn set_format_live(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+            self.release_lost_stream()?;
+            let previous = self.camera_format;
+            let was_open = self.is_stream_open();
+            let was_started = self.mmap_started
+                || self
+                    .mplane_stream
+                    .as_ref()
+                    .map_or(false, MplaneStream::is_active)
+                || self
+                    .userptr_stream
+                    .as_ref()
+                    .map_or(false, UserPtrStream::is_active)
+                || self
+                    .dmabuf_import_stream
+                    .as_ref()
+                    .map_or(false, DmaBufImportStream::is_active);
+
+            // the driver refuses a new format while buffers are allocated
+            self.release_stream();
+            if let Err(why) = self.set_camera_format(new_fmt) {
+                if let Some(previous) = previous {
+                    self.set_camera_format(previous)?;
+                }
+                if was_open {
+                    self.reopen_stream(was_started)?;
+                }
+                return Err(why);
+            }
+            if was_open {
+                self.reopen_stream(was_started)?;
+            }
+
+            let current = self.camera_format.unwrap_or(new_fmt);
+            if let Some(callback) = &mut self.format_change_callback {
+                callback(FormatChanged::new(previous, current));
+            }
+            Ok(())
+        }
+
+        /// Calls `callback` after [`set_format_live()`](V4LCaptureDevice::set_format_live) changed the format, before the first frame
+        /// of the new format is read. Setting a new callback replaces the previous one. The callback is called from the thread changing
+        /// the format.
+        pub fn on_format_change(
+            &mut self,
+            callback: Box<dyn FnMut(FormatChanged) + Send + 'static>,
+        ) {
+            self.format_change_callback = Some(callback);
+        }
+
+        fn reopen_stream(&mut self, start: bool) -> Result<(), NokhwaError> {
+            self.open_stream()?;
+            // dma-buf streams start with the first frame_dmabuf()
+            if start
+                && !matches!(
+                    self.capture_memory,
+                    CaptureMemory::DmaBuf | CaptureMemory::DmaBufImport
+                )
+            {
+                self.start_stream()?;
+            }
+            Ok(())
+        }
+
+        // Unmaps the buffers of a stream whose device was lost
+        fn release_lost_stream(&mut self) -> Result<(), NokhwaError> {
+            if !self.stream_lost {
+                return Ok(());
+            }
+            self.release_stream();
+            Err(NokhwaError::DeviceLost(
+                "Reconnect the device with reconnect()".to_string(),
+            ))
+        }
+
+        fn release_stream(&mut self) {
+            self.dmabuf_fds.clear();
+            self.dmabuf_index = None;
+            self.granted_buffer_count = None;
+            self.strides.clear();
+            self.read_buffer = None;
+            self.stream_handle = None;
+            self.mplane_stream = None;
+            self.userptr_stream = None;
+            self.dmabuf_import_stream = None;
+            self.mmap_started = false;
+            if let Some(node) = &mut self.metadata_node {
+                node.stop();
+            }
+        }
+
+        fn start_metadata(&mut self) -> Result<(), NokhwaError> {
+            if let Some(node) = &mut self.metadata_node {
+                node.start(self.requested_buffer_count).map_err(|why| {
+                    NokhwaError::OpenStreamError(format!("Metadata node: {why}"))
+                })?;
+            }
+            Ok(())
+        }
+
+        // Reads the next frame with read(), returning the part of the read buffer it filled
+        fn read_frame(&mut self) -> Result<&[u8], NokhwaError> {
+            let frame_timeout = self.frame_timeout;
+            let handle = self.device.handle();
+            let Some(buffer) = &mut self.read_buffer else {
+                return Err(NokhwaError::ReadFrameError(
+                    "Stream Not Started".to_string(),
+                ));
+            };
+            loop {
+                wait_readable(&handle, frame_timeout)
+                    .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+                let read =
+                    unsafe { libc::read(handle.fd(), buffer.as_mut_ptr().cast(), buffer.len()) };
+                if let Ok(read) = usize::try_from(read) {
+                    return Ok(&buffer[..read]);
+                }
+                let why = io::Error::last_os_error();
+                // another reader took the frame, or a signal came in
+                if !matches!(why.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) {
+                    return Err(stream_error(why, frame_timeout, &mut self.stream_lost));
+                }
+            }
+        }
+
+        /// Gets the next frame as a dma-buf. The previously returned [`DmaBufFrame`] is handed back to the driver.
+        ///
+        /// With [`CaptureMemory::DmaBufImport`], the frame's file descriptor is the one of the [`ImportedDmaBuf`] the driver captured into.
+        /// # Errors
+        /// This will error if the device was not created with [`CaptureMemory::DmaBuf`] or [`CaptureMemory::DmaBufImport`], if the stream is not open,
+        /// or if the driver fails to hand out a buffer.
+        pub fn frame_dmabuf(&mut self) -> Result<DmaBufFrame<'_>, NokhwaError> {
+            if self.capture_memory == CaptureMemory::DmaBufImport {
+                return self.frame_imported_dmabuf();
+            }
+            if self.capture_memory != CaptureMemory::DmaBuf {
+                return Err(NokhwaError::ReadFrameError(
+                    "Device was not created with CaptureMemory::DmaBuf".to_string(),
+                ));
+            }
+            self.release_lost_stream()?;
+            if self.stream_handle.is_none() || self.dmabuf_fds.is_empty() {
+                return Err(NokhwaError::ReadFrameError(
+                    "Stream Not Started".to_string(),
+                ));
+            }
+            let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+
+            match self.dmabuf_index {
+                Some(index) => self
+                    .buffer_ioctl(v4l2::vidioc::VIDIOC_QBUF, index)
+                    .map(|_| ()),
+                None => (0..self.dmabuf_fds.len())
+                    .try_for_each(|index| {
+                        self.buffer_ioctl(v4l2::vidioc::VIDIOC_QBUF, index)
+                            .map(|_| ())
+                    })
+                    .and_then(|()| {
+                        let mut buffer_type = v4l::buffer::Type::VideoCapture as u32;
+                        unsafe {
+                            v4l2::ioctl(
+                                self.device.handle().fd(),
+                                v4l2::vidioc::VIDIOC_STREAMON,
+                                (&mut buffer_type as *mut u32).cast(),
+                            )
+                        }
+                    }),
+            }
+            .map_err(|why| stream_error(why, None, &mut self.stream_lost))?;
+
+            let dequeued = wait_readable(&self.device.handle(), self.frame_timeout)
+                .and_then(|()| self.buffer_ioctl(v4l2::vidioc::VIDIOC_DQBUF, 0))
+                .map_err(|why| stream_error(why, self.frame_timeout, &mut self.stream_lost))?;
+            let index = dequeued.index as usize;
+            self.dmabuf_index = Some(index);
+            self.frame_counter.observe(u64::from(dequeued.sequence));
+
+            Ok(DmaBufFrame {
+                fd: self.dmabuf_fds[index].as_fd(),
+                index,
+                bytes_used: dequeued.bytesused,
+                resolution: camera_format.resolution(),
+                source_frame_format: camera_format.format(),
+                timestamp: timestamp_from_metadata(&Metadata {
+                    bytesused: dequeued.bytesused,
+                    flags: dequeued.flags.into(),
+                    field: dequeued.field,
+                    timestamp: dequeued.timestamp.into(),
+                    sequence: dequeued.sequence,
+                }),
+                sequence: dequeued.sequence,
+            })
+        }
+
+        fn frame_imported_dmabuf(&mut self) -> Result<DmaBufFrame<'_>, NokhwaError> {
+            self.release_lost_stream()?;
+            let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+            let frame_timeout = self.frame_timeout;
+            let Some(stream) = &mut self.dmabuf_import_stream else {
+                return Err(NokhwaError::ReadFrameError(
+                    "Stream Not Started".to_string(),
+                ));
+            };
+            let (fd, index, metadata) = stream
+                .next()
+                .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+            self.frame_counter.observe(u64::from(metadata.sequence));
+            Ok(DmaBufFrame {
+                fd,
+                index,
+                bytes_used: metadata.bytesused,
+                resolution: camera_format.resolution(),
+                source_frame_format: camera_format.format(),
+                timestamp: timestamp_from_metadata(&metadata),
+                sequence: metadata.sequence,
+            })
+        }
+
+        /// Gets the next frame, which the driver captured into one of the application's [`UserBuffer`]s. The previously returned
+        /// [`UserPtrFrame`] is handed back to the driver.
+        /// # Errors
+        /// This will error if the device was not created with [`CaptureMemory::UserPtr`], if the stream is not open, or if the driver fails to hand out a buffer.
+        pub fn frame_userptr(&mut self) -> Result<UserPtrFrame<'_>, NokhwaError> {
+            if self.capture_memory != CaptureMemory::UserPtr {
+                return Err(NokhwaError::ReadFrameError(
+                    "Device was not created with CaptureMemory::UserPtr".to_string(),
+                ));
+            }
+            self.release_lost_stream()?;
+            let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+            let frame_timeout = self.frame_timeout;
+            let Some(stream) = &mut self.userptr_stream else {
+                return Err(NokhwaError::ReadFrameError(
+                    "Stream Not Started".to_string(),
+                ));
+            };
+            let (data, buffer, metadata) = stream
+                .next()
+                .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+            self.frame_counter.observe(u64::from(metadata.sequence));
+            Ok(UserPtrFrame {
+                data,
+                buffer,
+                resolution: camera_format.resolution(),
+                source_frame_format: camera_format.format(),
+                timestamp: timestamp_from_metadata(&metadata),
+                sequence: metadata.sequence,
+            })
+        }
+
+        fn buffer_ioctl(
+            &self,
+            request: v4l2::vidioc::_IOC_TYPE,
+            index: usize,
+        ) -> io::Result<v4l2_buffer> {
+            #[allow(clippy::cast_possible_truncation)]
+            let mut buffer = v4l2_buffer {
+                index: index as u32,
+                type_: v4l::buffer::Type::VideoCapture as u32,
+                memory: Memory::Mmap as u32,
+                ..unsafe { mem::zeroed() }
+            };
+            unsafe {
+                v4l2::ioctl(
+                    self.device.handle().fd(),
+                    request,
+                    (&mut buffer as *mut v4l2_buffer).cast(),
+                )?;
+            }
+            Ok(buffer)
+        }
+
+        fn selection(&self, target: u32) -> io::Result<Rect> {
+            let mut selection = v4l2_selection {
+                type_: v4l::buffer::Type::VideoCapture as u32,
+                target,
+                ..unsafe { mem::zeroed() }
+            };
+            unsafe {
+                v4l2::ioctl(
+                    self.device.handle().fd(),
+                    VIDIOC_G_SELECTION,
+                    (&mut selection as *mut v4l2_selection).cast(),
+                )?;
+            }
+            Ok(Rect::new(
+                selection.r.left,
+                selection.r.top,
+                selection.r.width,
+                selection.r.height,
+            ))
+        }
+
+        /// Picks the fourcc `frame_format` is captured with, which is the packed Bayer layout if the device only offers that.
+        fn device_fourcc(&self, frame_format: FrameFormat) -> Option<FourCC> {
+            let fourcc = frameformat_to_fourcc(frame_format)?;
+            let Some(packed) = packed_bayer_fourcc(frame_format) else {
+                return Some(fourcc);
+            };
+            let offered = self.device.enum_formats().unwrap_or_default();
+            if !offered.iter().any(|desc| desc.fourcc == fourcc)
+                && offered.iter().any(|desc| desc.fourcc == packed)
+            {
+                return Some(packed);
+            }
+            Some(fourcc)
+        }
+
+        fn device_format(&self) -> io::Result<Format> {
+            if self.multiplanar {
+                mplane::format(&self.device).map(|(format, _)| format)
+            } else {
+                Capture::format(&self.device)
+            }
+        }
+
+        fn set_device_format(&self, format: &Format) -> io::Result<Format> {
+            if self.multiplanar {
+                mplane::set_format(&self.device, format).map(|(format, _)| format)
+            } else {
+                Capture::set_format(&self.device, format)
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        fn query_buffer_count(&self) -> u32 {
+            // the driver rejects indices past the last buffer it allocated
+            (0..)
+                .take_while(|index| {
+                    self.buffer_ioctl(v4l2::vidioc::VIDIOC_QUERYBUF, *index)
+                        .is_ok()
+                })
+                .count() as u32
+        }
+
+        fn export_buffers(&self) -> Result<Vec<OwnedFd>, NokhwaError> {
+            let mut fds = vec![];
+            loop {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let mut export = v4l2_exportbuffer {
+                    type_: v4l::buffer::Type::VideoCapture as u32,
+                    index: fds.len() as u32,
+                    flags: (libc::O_CLOEXEC | libc::O_RDWR) as u32,
+                    ..unsafe { mem::zeroed() }
+                };
+                let result = unsafe {
+                    v4l2::ioctl(
+                        self.device.handle().fd(),
+                        v4l2::vidioc::VIDIOC_EXPBUF,
+                        (&mut export as *mut v4l2_exportbuffer).cast(),
+                    )
+                };
+                match result {
+                    // SAFETY: on success, the driver hands us a fresh fd that nothing else owns.
+                    Ok(()) => fds.push(unsafe { OwnedFd::from_raw_fd(export.fd) }),
+                    // running off the end of the allocated buffers
+                    Err(why) if why.raw_os_error() == Some(libc::EINVAL) && !fds.is_empty() => {
+                        break
+                    }
+                    Err(why) => {
+                        return Err(NokhwaError::OpenStreamError(format!(
+                            "VIDIOC_EXPBUF: {why}"
+                        )))
+                    }
+                }
+            }
+            Ok(fds)
+        }
 
         /// Force refreshes the inner [`CameraFormat`] state.
         /// # Errors
         /// If the internal representation in the driver is invalid, this will error.
         pub fn force_refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
-            match self.device.format() {
+            match self.device_format() {
                 Ok(format) => {
-                    let frame_format = fourcc_to_frameformat(format.fourcc).ok_or(
-                        NokhwaError::GetPropertyError {
-                            property: "FrameFormat".to_string(),
-                            error: "unsupported".to_string(),
-                        },
-                    )?;
+                    self.packed_bayer =
+                        bayer_packing(format.fourcc).map(|packing| (packing, format.stride));
+                    let frame_format = fourcc_to_frameformat(format.fourcc);
 
+                    // V4L2 reports the time per frame, so the rate is the inverse fraction.
                     let fps = match self.device.params() {
-                        Ok(params) => {
-                            if params.interval.numerator != 1
-                                || params.interval.denominator % params.interval.numerator != 0
-                            {
-                                return Err(NokhwaError::GetPropertyError {
-                                    property: "V4L2 FrameRate".to_string(),
-                                    error: format!(
-                                        "Framerate not whole number: {} / {}",
-                                        params.interval.denominator, params.interval.numerator
-                                    ),
-                                });
-                            }
-
-                            if params.interval.numerator == 1 {
-                                params.interval.denominator
-                            } else {
-                                params.interval.denominator / params.interval.numerator
-                            }
-                        }
+                        Ok(params) => FrameRate::new_rational(
+                            params.interval.denominator,
+                            params.interval.numerator,
+                        )
+                        .map_err(|why| NokhwaError::GetPropertyError {
+                            property: "V4L2 FrameRate".to_string(),
+                            error: why.to_string(),
+                        })?,
                         Err(why) => {
                             return Err(NokhwaError::GetPropertyError {
                                 property: "V4L2 FrameRate".to_string(),
@@ -180,7 +1385,7 @@ mod internal {
 
                     self.camera_format = CameraFormat::new(
                         Resolution::new(format.width, format.height),
-                        frame_format: ,
+                        frame_format,
                         fps,
                     );
                     Ok(())
@@ -219,7 +1424,7 @@ mod internal {
         }
 
         fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-            let prev_format = match Capture::format(&self.device) {
+            let prev_format = match self.device_format() {
                 Ok(fmt) => fmt,
                 Err(why) => {
                     return Err(NokhwaError::GetPropertyError {
@@ -238,18 +1443,19 @@ mod internal {
                 }
             };
 
-            let v4l_fcc = match new_fmt.format() {
-                FrameFormat::MJPEG => FourCC::new(b"MJPG"),
-                FrameFormat::YUYV => FourCC::new(b"YUYV"),
-                FrameFormat::GRAY => FourCC::new(b"GRAY"),
-                FrameFormat::RAWRGB => FourCC::new(b"RGB3"),
-                FrameFormat::NV12 => FourCC::new(b"NV12"),
-            };
+            let v4l_fcc = self.device_fourcc(new_fmt.format()).ok_or(
+                NokhwaError::SetPropertyError {
+                    property: "FrameFormat".to_string(),
+                    value: new_fmt.format().to_string(),
+                    error: "unsupported".to_string(),
+                },
+            )?;
 
             let format = Format::new(new_fmt.width(), new_fmt.height(), v4l_fcc);
-            let frame_rate = Parameters::with_fps(new_fmt.frame_rate());
+            let (fps_numerator, fps_denominator) = new_fmt.frame_rate().as_rational();
+            let frame_rate = Parameters::new(Fraction::new(fps_denominator, fps_numerator));
 
-            if let Err(why) = Capture::set_format(&self.device, &format) {
+            if let Err(why) = self.set_device_format(&format) {
                 return Err(NokhwaError::SetPropertyError {
                     property: "Resolution, FrameFormat".to_string(),
                     value: format.to_string(),
@@ -264,12 +1470,12 @@ mod internal {
                 });
             }
 
-            if self.stream_handle.is_some() {
+            if self.is_stream_open() {
                 return match self.open_stream() {
                     Ok(_) => Ok(()),
                     Err(why) => {
                         // undo
-                        if let Err(why) = Capture::set_format(&self.device, &prev_format) {
+                        if let Err(why) = self.set_device_format(&prev_format) {
                             return Err(NokhwaError::SetPropertyError {
                                 property: format!("Attempt undo due to stream acquisition failure with error {}. Resolution, FrameFormat", why),
                                 value: prev_format.to_string(),
@@ -305,9 +1511,12 @@ mod internal {
         fn compatible_list_by_resolution(
             &mut self,
             fourcc: FrameFormat,
-        ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
+        ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
             let resolutions = self.get_resolution_list(fourcc)?;
-            let format = frameformat_to_fourcc(fourcc);
+            let format = self.device_fourcc(fourcc).ok_or(NokhwaError::GetPropertyError {
+                property: "FrameFormat".to_string(),
+                error: "unsupported".to_string(),
+            })?;
             let mut res_map = HashMap::new();
             for res in resolutions {
                 let mut compatible_fps = vec![];
@@ -319,14 +1528,22 @@ mod internal {
                         for interval in intervals {
                             match interval.interval {
                                 FrameIntervalEnum::Discrete(dis) => {
-                                    compatible_fps.push(dis.denominator);
+                                    if let Ok(fps) =
+                                        FrameRate::new_rational(dis.denominator, dis.numerator)
+                                    {
+                                        compatible_fps.push(fps);
+                                    }
                                 }
                                 FrameIntervalEnum::Stepwise(step) => {
-                                    for fstep in (step.min.numerator..step.max.numerator)
-                                        .step_by(step.step.numerator as usize)
-                                    {
-                                        if step.max.denominator != 1 || step.min.denominator != 1 {
-                                            compatible_fps.push(fstep);
+                                    // Report the fastest and slowest rates, the range endpoints are exact.
+                                    for interval in [step.min, step.max] {
+                                        if let Ok(fps) = FrameRate::new_rational(
+                                            interval.denominator,
+                                            interval.numerator,
+                                        ) {
+                                            if !compatible_fps.contains(&fps) {
+                                                compatible_fps.push(fps);
+                                            }
                                         }
                                     }
                                 }
@@ -350,10 +1567,7 @@ mod internal {
                 Ok(formats) => {
                     let mut frame_format_vec = vec![];
                     for format in formats {
-                        match fourcc_to_frameformat(format.fourcc) {
-                            Some(ff) => frame_format_vec.push(ff),
-                            None => continue,
-                        }
+                        frame_format_vec.push(fourcc_to_frameformat(format.fourcc));
                     }
                     frame_format_vec.sort();
                     frame_format_vec.dedup();
@@ -426,14 +1640,32 @@ mod internal {
                     let ctrl_current = self.device.control(desc.id)?.value;
 
                     let ctrl_value_desc = match (desc.typ, ctrl_current) {
+                        (Type::Menu | Type::IntegerMenu, Value::Integer(current)) => {
+                            ControlValueDescription::Menu {
+                                value: current,
+                                items: desc
+                                    .items
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|(index, item)| {
+                                        let entry = match item {
+                                            MenuItem::Name(name) => ControlMenuEntry::Name(name),
+                                            MenuItem::Value(value) => {
+                                                ControlMenuEntry::Integer(value)
+                                            }
+                                        };
+                                        (i64::from(index), entry)
+                                    })
+                                    .collect(),
+                                default: desc.default,
+                            }
+                        }
                         (
                             Type::Integer
                             | Type::Integer64
-                            | Type::Menu
                             | Type::U8
                             | Type::U16
-                            | Type::U32
-                            | Type::IntegerMenu,
+                            | Type::U32,
                             Value::Integer(current),
                         ) => ControlValueDescription::IntegerRange {
                             min: desc.minimum as i64,
@@ -518,6 +1750,7 @@ mod internal {
                 ControlValueSetter::None => Value::None,
                 ControlValueSetter::Integer(i) => Value::Integer(i),
                 ControlValueSetter::Boolean(b) => Value::Boolean(b),
+                ControlValueSetter::EnumValue(index) => Value::Integer(index),
                 ControlValueSetter::String(s) => Value::String(s),
                 ControlValueSetter::Bytes(b) => Value::CompoundU8(b),
                 v => {
@@ -548,37 +1781,324 @@ mod internal {
                     error: "Rejected".to_string(),
                 });
             }
-            Ok(())
+            Ok(())
+        }
+
+        fn crop(&self) -> Result<ControlValueDescription, NokhwaError> {
+            let selection = |target| {
+                self.selection(target)
+                    .map_err(|why| NokhwaError::GetPropertyError {
+                        property: "Crop".to_string(),
+                        error: why.to_string(),
+                    })
+            };
+            Ok(ControlValueDescription::Area {
+                value: selection(V4L2_SEL_TGT_CROP)?,
+                bounds: selection(V4L2_SEL_TGT_CROP_BOUNDS)?,
+                default: selection(V4L2_SEL_TGT_CROP_DEFAULT)?,
+            })
+        }
+
+        fn set_crop(&mut self, crop: Rect) -> Result<(), NokhwaError> {
+            let mut selection = v4l2_selection {
+                type_: v4l::buffer::Type::VideoCapture as u32,
+                target: V4L2_SEL_TGT_CROP,
+                r: v4l2_rect {
+                    left: crop.x,
+                    top: crop.y,
+                    width: crop.width,
+                    height: crop.height,
+                },
+                ..unsafe { mem::zeroed() }
+            };
+            unsafe {
+                v4l2::ioctl(
+                    self.device.handle().fd(),
+                    VIDIOC_S_SELECTION,
+                    (&mut selection as *mut v4l2_selection).cast(),
+                )
+            }
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "Crop".to_string(),
+                value: crop.to_string(),
+                error: why.to_string(),
+            })?;
+            // cropping may change the output resolution on devices without a scaler
+            self.force_refresh_camera_format()?;
+            Ok(())
+        }
+
+        fn on_control_change(
+            &mut self,
+            callback: Box<dyn FnMut(ControlChange) + Send + 'static>,
+        ) -> Result<(), NokhwaError> {
+            // the previous thread unsubscribes from all events when it stops, so it has to stop first
+            self.control_events = None;
+            let controls = self
+                .device
+                .query_controls()
+                .map_err(|why| NokhwaError::GetPropertyError {
+                    property: "V4L2 Controls".to_string(),
+                    error: why.to_string(),
+                })?
+                .into_iter()
+                .filter(|desc| desc.typ != Type::CtrlClass)
+                .map(|desc| desc.id)
+                .collect::<Vec<u32>>();
+            let control_events = ControlEvents::subscribe(&self.device.handle(), &controls, callback)
+                .map_err(|why| NokhwaError::SetPropertyError {
+                    property: "Control events".to_string(),
+                    value: "VIDIOC_SUBSCRIBE_EVENT".to_string(),
+                    error: why.to_string(),
+                })?;
+            self.control_events = Some(control_events);
+            Ok(())
+        }
+
+        fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+            Ok(self.frame_counter.dropped())
+        }
+
+        fn hardware_privacy(&self) -> Result<bool, NokhwaError> {
+            let property_error = |error: String| NokhwaError::GetPropertyError {
+                property: "Privacy".to_string(),
+                error,
+            };
+            // a privacy switch (e.g. the privacy GPIO of UVC laptop cameras) is read-only, a writable one is only a software mode
+            let has_switch = self
+                .device
+                .query_controls()
+                .map_err(|why| property_error(why.to_string()))?
+                .into_iter()
+                .any(|desc| {
+                    desc.id == V4L2_CID_PRIVACY && desc.flags.contains(Flags::READ_ONLY)
+                });
+            if !has_switch {
+                return Err(property_error("no hardware privacy switch".to_string()));
+            }
+            match self
+                .device
+                .control(V4L2_CID_PRIVACY)
+                .map_err(|why| property_error(why.to_string()))?
+                .value
+            {
+                Value::Boolean(engaged) => Ok(engaged),
+                Value::Integer(engaged) => Ok(engaged != 0),
+                value => Err(property_error(format!("unexpected value {value:?}"))),
+            }
         }
 
         fn open_stream(&mut self) -> Result<(), NokhwaError> {
-            let stream = match MmapStream::new(&self.device, v4l::buffer::Type::VideoCapture) {
+            // drop the old stream first, the driver only hands out one set of buffers
+            self.release_stream();
+            self.frame_counter.reset();
+            if self.capture_memory == CaptureMemory::Read {
+                let format = Capture::format(&self.device)
+                    .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                if format.size == 0 {
+                    return Err(NokhwaError::OpenStreamError(
+                        "Driver did not report the frame size".to_string(),
+                    ));
+                }
+                self.read_buffer = Some(vec![0; format.size as usize]);
+                self.strides = vec![format.stride as usize];
+                self.stream_lost = false;
+                return Ok(());
+            }
+            if self.multiplanar {
+                let (_, strides) = mplane::format(&self.device)
+                    .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                let mut stream = MplaneStream::with_buffers(
+                    &self.device,
+                    strides.len(),
+                    self.requested_buffer_count,
+                )
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                stream.set_timeout(self.frame_timeout);
+                self.granted_buffer_count = Some(stream.buffer_count());
+                self.strides = strides.into_iter().map(|stride| stride as usize).collect();
+                self.mplane_stream = Some(stream);
+                self.start_metadata()?;
+                self.stream_lost = false;
+                return Ok(());
+            }
+            if self.capture_memory == CaptureMemory::UserPtr {
+                let format = Capture::format(&self.device)
+                    .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                let mut stream = UserPtrStream::with_buffers(
+                    &self.device,
+                    &self.user_buffers,
+                    format.size as usize,
+                )
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                stream.set_timeout(self.frame_timeout);
+                self.granted_buffer_count = Some(stream.buffer_count());
+                self.strides = vec![format.stride as usize];
+                self.userptr_stream = Some(stream);
+                self.start_metadata()?;
+                self.stream_lost = false;
+                return Ok(());
+            }
+            if self.capture_memory == CaptureMemory::DmaBufImport {
+                let format = Capture::format(&self.device)
+                    .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                let mut stream = DmaBufImportStream::with_buffers(
+                    &self.device,
+                    &self.dmabufs,
+                    format.size as usize,
+                )
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+                stream.set_timeout(self.frame_timeout);
+                self.granted_buffer_count = Some(stream.buffer_count());
+                self.strides = vec![format.stride as usize];
+                self.dmabuf_import_stream = Some(stream);
+                self.stream_lost = false;
+                return Ok(());
+            }
+
+            let format = Capture::format(&self.device)
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+            let mut stream = match MmapStream::with_buffers(
+                &self.device,
+                v4l::buffer::Type::VideoCapture,
+                self.requested_buffer_count,
+            ) {
                 Ok(s) => s,
                 Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
             };
+            if let Some(timeout) = self.frame_timeout {
+                stream.set_timeout(timeout);
+            }
+            self.dmabuf_fds.clear();
+            self.dmabuf_index = None;
+            if self.capture_memory == CaptureMemory::DmaBuf {
+                self.dmabuf_fds = self.export_buffers()?;
+            }
+            self.granted_buffer_count = Some(self.query_buffer_count());
+            self.strides = vec![format.stride as usize];
             self.stream_handle = Some(stream);
+            self.mmap_started = false;
+            self.start_metadata()?;
+            self.stream_lost = false;
             Ok(())
         }
 
         fn is_stream_open(&self) -> bool {
             self.stream_handle.is_some()
+                || self.mplane_stream.is_some()
+                || self.userptr_stream.is_some()
+                || self.dmabuf_import_stream.is_some()
+                || self.read_buffer.is_some()
         }
 
         fn frame(&mut self) -> Result<Buffer, NokhwaError> {
-            let cam_fmt = self.camera_format;
-            let raw_frame = self.frame_raw()?;
-            Ok(Buffer::new(
-                cam_fmt.resolution(),
-                &raw_frame,
-                cam_fmt.format(),
-            ))
+            let cam_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+            let frame_timeout = self.frame_timeout;
+            let packed_bayer = self.packed_bayer;
+            if matches!(
+                self.capture_memory,
+                CaptureMemory::DmaBuf | CaptureMemory::DmaBufImport
+            ) {
+                return Err(NokhwaError::ReadFrameError(format!(
+                    "Device uses CaptureMemory::{:?}, use frame_dmabuf()",
+                    self.capture_memory
+                )));
+            }
+            self.release_lost_stream()?;
+            if self.read_buffer.is_some() {
+                let buffer =
+                    Buffer::new(cam_fmt.resolution(), self.read_frame()?, cam_fmt.format());
+                return Ok(buffer.with_stride(self.strides.first().copied().unwrap_or_default()));
+            }
+            if let Some(stream) = &mut self.mplane_stream {
+                let (planes, metadata) = stream
+                    .next()
+                    .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+                self.frame_counter.observe(u64::from(metadata.sequence));
+                let buffer = with_metadata(
+                    Buffer::with_planes(cam_fmt.resolution(), &planes, cam_fmt.format())
+                        .with_strides(&self.strides),
+                    &metadata,
+                );
+                return Ok(with_frame_metadata(&mut self.metadata_node, buffer));
+            }
+            if let Some(stream) = &mut self.userptr_stream {
+                let (data, _, metadata) = stream
+                    .next()
+                    .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+                self.frame_counter.observe(u64::from(metadata.sequence));
+                let buffer = single_plane_buffer(cam_fmt, data, packed_bayer, &self.strides);
+                return Ok(with_frame_metadata(
+                    &mut self.metadata_node,
+                    with_metadata(buffer, &metadata),
+                ));
+            }
+            // next() starts the stream
+            self.mmap_started = true;
+            match &mut self.stream_handle {
+                Some(sh) => match sh.next() {
+                    Ok((data, metadata)) => {
+                        self.frame_counter.observe(u64::from(metadata.sequence));
+                        // the driver may hand out a bigger buffer than the (compressed) frame
+                        let data = &data[..(metadata.bytesused as usize).min(data.len())];
+                        let buffer =
+                            single_plane_buffer(cam_fmt, data, packed_bayer, &self.strides);
+                        Ok(with_frame_metadata(
+                            &mut self.metadata_node,
+                            with_metadata(buffer, metadata),
+                        ))
+                    }
+                    Err(why) => Err(stream_error(why, frame_timeout, &mut self.stream_lost)),
+                },
+                None => Err(NokhwaError::ReadFrameError(
+                    "Stream Not Started".to_string(),
+                )),
+            }
         }
 
         fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+            if matches!(
+                self.capture_memory,
+                CaptureMemory::DmaBuf | CaptureMemory::DmaBufImport
+            ) {
+                return Err(NokhwaError::ReadFrameError(format!(
+                    "Device uses CaptureMemory::{:?}, use frame_dmabuf()",
+                    self.capture_memory
+                )));
+            }
+            self.release_lost_stream()?;
+            if self.read_buffer.is_some() {
+                return self.read_frame().map(Cow::Borrowed);
+            }
+            let frame_timeout = self.frame_timeout;
+            if let Some(stream) = &mut self.mplane_stream {
+                let (planes, metadata) = stream
+                    .next()
+                    .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+                self.frame_counter.observe(u64::from(metadata.sequence));
+                return Ok(match planes.as_slice() {
+                    [plane] => Cow::Borrowed(*plane),
+                    planes => Cow::Owned(planes.concat()),
+                });
+            }
+            if let Some(stream) = &mut self.userptr_stream {
+                let (data, _, metadata) = stream
+                    .next()
+                    .map_err(|why| stream_error(why, frame_timeout, &mut self.stream_lost))?;
+                self.frame_counter.observe(u64::from(metadata.sequence));
+                return Ok(Cow::Borrowed(data));
+            }
+            // next() starts the stream
+            self.mmap_started = true;
             match &mut self.stream_handle {
                 Some(sh) => match sh.next() {
-                    Ok((data, _)) => Ok(Cow::Borrowed(data)),
-                    Err(why) => Err(NokhwaError::ReadFrameError(why.to_string())),
+                    Ok((data, metadata)) => {
+                        self.frame_counter.observe(u64::from(metadata.sequence));
+                        Ok(Cow::Borrowed(
+                            &data[..(metadata.bytesused as usize).min(data.len())],
+                        ))
+                    }
+                    Err(why) => Err(stream_error(why, frame_timeout, &mut self.stream_lost)),
                 },
                 None => Err(NokhwaError::ReadFrameError(
                     "Stream Not Started".to_string(),
@@ -587,14 +2107,281 @@ mod internal {
         }
 
         fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-            if self.stream_handle.is_some() {
-                self.stream_handle = None;
+            if self.is_stream_open() {
+                self.release_stream();
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a> AsFd for V4LCaptureDevice<'a> {
+        /// The device's file descriptor, opened with `O_NONBLOCK`. It polls readable (`POLLIN`) once a frame can be read without
+        /// waiting, so it can be registered with an async runtime (e.g. `tokio::io::unix::AsyncFd`).
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            // the handle is owned by the device, and closed when it is dropped
+            unsafe { BorrowedFd::borrow_raw(self.device.handle().fd()) }
+        }
+    }
+
+    impl<'a> AsRawFd for V4LCaptureDevice<'a> {
+        fn as_raw_fd(&self) -> RawFd {
+            self.device.handle().fd()
+        }
+    }
+
+    /// A virtual camera backed by a [`v4l2loopback`](https://github.com/umlaeute/v4l2loopback) device. Browsers and conferencing apps
+    /// capture from it like from any other V4L2 camera.
+    /// To see what this does, please see [`VirtualBackendTrait`].
+    /// # Quirks
+    /// - Frames are written with `write()`. The consumer sees the format negotiated by [`set_camera_format()`](VirtualBackendTrait::set_camera_format)
+    ///   once the first frame is written, so with `exclusive_caps=1` the device only shows up as a camera after that.
+    /// - Frames are paced to the frame rate of the [`CameraFormat`]. If the consumer stops reading, writing waits up to [`DEFAULT_FRAME_TIMEOUT`]
+    ///   and then fails with [`NokhwaError::TimeoutError`].
+    /// - Uncompressed frames must hold a whole image. Compressed frames (e.g. `MJPEG`) may be any length up to the driver's buffer size.
+    pub struct V4LLoopbackDevice {
+        device: Device,
+        path: String,
+        camera_format: CameraFormat,
+        frame_size: usize,
+        next_frame: Option<Instant>,
+    }
+
+    impl V4LLoopbackDevice {
+        /// Opens the `v4l2loopback` device at `index` (see [`device_path()`]) and negotiates `camera_format` with it.
+        /// # Errors
+        /// This will error if the device can not be opened, is not a video output device, or rejects the format.
+        pub fn new(index: &CameraIndex, camera_format: CameraFormat) -> Result<Self, NokhwaError> {
+            let path = device_path(index)?.to_string_lossy().to_string();
+            let device = Device::with_path(&path)
+                .map_err(|why| NokhwaError::OpenDeviceError(path.clone(), why.to_string()))?;
+            let capabilities =
+                device
+                    .query_caps()
+                    .map_err(|why| NokhwaError::GetPropertyError {
+                        property: "Capabilities".to_string(),
+                        error: why.to_string(),
+                    })?;
+            if !capabilities
+                .capabilities
+                .contains(CapabilityFlags::VIDEO_OUTPUT)
+            {
+                return Err(NokhwaError::OpenDeviceError(
+                    path,
+                    "Device is not a video output node".to_string(),
+                ));
+            }
+
+            let mut loopback = V4LLoopbackDevice {
+                device,
+                path,
+                camera_format,
+                frame_size: 0,
+                next_frame: None,
+            };
+            loopback.set_camera_format(camera_format)?;
+            Ok(loopback)
+        }
+
+        /// Gets the path of the device node frames are written to.
+        #[must_use]
+        pub fn path(&self) -> &str {
+            &self.path
+        }
+
+        // Sleeps until the next frame is due, without trying to catch up after a stall.
+        fn pace(&mut self) {
+            let interval =
+                Duration::from_secs_f32(1.0 / self.camera_format.frame_rate().as_float().max(1.0));
+            let now = Instant::now();
+            let due = match self.next_frame {
+                Some(due) if due > now => {
+                    thread::sleep(due - now);
+                    due
+                }
+                _ => now,
+            };
+            self.next_frame = Some(due + interval);
+        }
+    }
+
+    impl VirtualBackendTrait for V4LLoopbackDevice {
+        fn backend(&self) -> ApiBackend {
+            ApiBackend::Video4Linux
+        }
+
+        fn camera_format(&self) -> CameraFormat {
+            self.camera_format
+        }
+
+        fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+            let fourcc =
+                frameformat_to_fourcc(new_fmt.format()).ok_or(NokhwaError::SetPropertyError {
+                    property: "FrameFormat".to_string(),
+                    value: new_fmt.format().to_string(),
+                    error: "Format has no V4L2 FourCC".to_string(),
+                })?;
+            let format = Format::new(new_fmt.width(), new_fmt.height(), fourcc);
+            let applied = Output::set_format(&self.device, &format).map_err(|why| {
+                NokhwaError::SetPropertyError {
+                    property: "Resolution, FrameFormat".to_string(),
+                    value: new_fmt.to_string(),
+                    error: why.to_string(),
+                }
+            })?;
+            if applied.width != format.width
+                || applied.height != format.height
+                || applied.fourcc != fourcc
+            {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "Resolution, FrameFormat".to_string(),
+                    value: new_fmt.to_string(),
+                    error: format!("Device negotiated {applied} instead"),
+                });
+            }
+
+            let (fps_numerator, fps_denominator) = new_fmt.frame_rate().as_rational();
+            let frame_rate =
+                OutputParameters::new(Fraction::new(fps_denominator, fps_numerator));
+            Output::set_params(&self.device, &frame_rate).map_err(|why| {
+                NokhwaError::SetPropertyError {
+                    property: "Frame rate".to_string(),
+                    value: new_fmt.frame_rate().to_string(),
+                    error: why.to_string(),
+                }
+            })?;
+
+            self.camera_format = new_fmt;
+            self.frame_size = applied.size as usize;
+            self.next_frame = None;
+            Ok(())
+        }
+
+        fn write_frame(&mut self, buffer: &Buffer) -> Result<(), NokhwaError> {
+            if buffer.source_frame_format() != self.camera_format.format()
+                || buffer.resolution() != self.camera_format.resolution()
+            {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: buffer.source_frame_format(),
+                    destination: "v4l2loopback".to_string(),
+                    error: format!(
+                        "Frame of {} does not match the device format {}",
+                        buffer.resolution(),
+                        self.camera_format
+                    ),
+                });
+            }
+            let data = buffer.buffer();
+            let compressed = FrameFormat::COMPRESSED.contains(&buffer.source_frame_format());
+            if (!compressed && data.len() < self.frame_size) || data.len() > self.frame_size {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: buffer.source_frame_format(),
+                    destination: "v4l2loopback".to_string(),
+                    error: format!(
+                        "Frame is {} bytes, the device expects {}",
+                        data.len(),
+                        self.frame_size
+                    ),
+                });
+            }
+
+            self.pace();
+            wait_writable(&self.device.handle(), Some(DEFAULT_FRAME_TIMEOUT))
+                .map_err(|why| frame_error(why, Some(DEFAULT_FRAME_TIMEOUT)))?;
+            let written =
+                unsafe { libc::write(self.device.handle().fd(), data.as_ptr().cast(), data.len()) };
+            if written < 0 {
+                return Err(NokhwaError::GeneralError(format!(
+                    "v4l2loopback write: {}",
+                    io::Error::last_os_error()
+                )));
             }
             Ok(())
         }
     }
 
-    fn fourcc_to_frameformat(fourcc: FourCC) -> Option<FrameFormat> {
+    impl AsFd for V4LLoopbackDevice {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+        }
+    }
+
+    impl AsRawFd for V4LLoopbackDevice {
+        fn as_raw_fd(&self) -> RawFd {
+            self.device.handle().fd()
+        }
+    }
+
+    fn device_details(capabilities: &Capabilities) -> DeviceDetails {
+        let flags = capabilities.capabilities;
+        DeviceDetails::new(
+            &capabilities.driver,
+            &capabilities.card,
+            &capabilities.bus,
+            DeviceCapabilities {
+                video_capture: flags.contains(CapabilityFlags::VIDEO_CAPTURE),
+                multiplanar: flags.contains(CapabilityFlags::VIDEO_CAPTURE_MPLANE),
+                meta_capture: flags.contains(CapabilityFlags::META_CAPTURE),
+                memory_to_memory: flags
+                    .intersects(CapabilityFlags::VIDEO_M2M | CapabilityFlags::VIDEO_M2M_MPLANE),
+                video_output: flags
+                    .intersects(CapabilityFlags::VIDEO_OUTPUT | CapabilityFlags::VIDEO_OUTPUT_MPLANE),
+                streaming: flags.contains(CapabilityFlags::STREAMING),
+                read_write: flags.contains(CapabilityFlags::READ_WRITE),
+            },
+        )
+    }
+
+    fn frame_error(why: io::Error, frame_timeout: Option<Duration>) -> NokhwaError {
+        match (why.kind(), frame_timeout) {
+            (ErrorKind::TimedOut, Some(timeout)) => NokhwaError::TimeoutError(timeout),
+            // unplugged (ENODEV), or reset and no longer delivering buffers (EIO, EPIPE)
+            _ if matches!(
+                why.raw_os_error(),
+                Some(libc::ENODEV | libc::EIO | libc::EPIPE)
+            ) =>
+            {
+                NokhwaError::DeviceLost(why.to_string())
+            }
+            // the stream is started by reading the first frame
+            _ if why.raw_os_error() == Some(libc::ENOSPC) => stream_on_error(why),
+            _ => NokhwaError::ReadFrameError(why.to_string()),
+        }
+    }
+
+    // STREAMON of a USB camera whose bus has no isochronous bandwidth left fails with ENOSPC
+    fn stream_on_error(why: io::Error) -> NokhwaError {
+        if why.raw_os_error() == Some(libc::ENOSPC) {
+            return NokhwaError::OpenStreamError(format!(
+                "{why}: not enough USB bandwidth for this format, see V4LCaptureDevice::usb_bandwidth()"
+            ));
+        }
+        NokhwaError::OpenStreamError(why.to_string())
+    }
+
+    // frame_error(), remembering whether the stream has to be torn down
+    fn stream_error(
+        why: io::Error,
+        frame_timeout: Option<Duration>,
+        stream_lost: &mut bool,
+    ) -> NokhwaError {
+        let error = frame_error(why, frame_timeout);
+        if matches!(error, NokhwaError::DeviceLost(_)) {
+            *stream_lost = true;
+        }
+        error
+    }
+
+    // fourccs nokhwa does not know are still listed and can be requested, their frames are handed out untouched
+    fn fourcc_to_frameformat(fourcc: FourCC) -> FrameFormat {
+        known_fourcc_to_frameformat(fourcc).unwrap_or_else(|| {
+            FrameFormat::PlatformSpecificCustomFormat(PlatformSpecific::from_fourcc(
+                ApiBackend::Video4Linux,
+                fourcc.repr,
+            ))
+        })
+    }
+
+    fn known_fourcc_to_frameformat(fourcc: FourCC) -> Option<FrameFormat> {
         match fourcc.str().ok()? {
             "YUYV" => Some(FrameFormat::Yuv422),
             "UYVY" => Some(FrameFormat::Uyv422),
@@ -603,7 +2390,12 @@ mod internal {
             "GRAY" => Some(FrameFormat::Luma8),
             "RGB3" => Some(FrameFormat::Rgb8),
             "NV12" => Some(FrameFormat::Nv12),
+            "NV21" => Some(FrameFormat::Nv21),
+            "Y16 " => Some(FrameFormat::Luma16),
+            "Z16 " => Some(FrameFormat::Depth16),
+            "AB24" => Some(FrameFormat::RgbA8),
             "H264" => Some(FrameFormat::H264),
+            "HEVC" => Some(FrameFormat::H265),
             "AVC1" => Some(FrameFormat::Avc1),
             "H263" => Some(FrameFormat::H263),
             "XVID" => Some(FrameFormat::XVid),
@@ -612,14 +2404,181 @@ mod internal {
             "MPG1" => Some(FrameFormat::Mpeg1),
             "MPG2" => Some(FrameFormat::Mpeg2),
             "MPG4" => Some(FrameFormat::Mpeg4),
+            "RGGB" => Some(FrameFormat::Bayer8(BayerPattern::Rggb)),
+            "BA81" => Some(FrameFormat::Bayer8(BayerPattern::Bggr)),
+            "GRBG" => Some(FrameFormat::Bayer8(BayerPattern::Grbg)),
+            "GBRG" => Some(FrameFormat::Bayer8(BayerPattern::Gbrg)),
+            "RG10" | "pRAA" => Some(FrameFormat::Bayer10(BayerPattern::Rggb)),
+            "BG10" | "pBAA" => Some(FrameFormat::Bayer10(BayerPattern::Bggr)),
+            "BA10" | "pgAA" => Some(FrameFormat::Bayer10(BayerPattern::Grbg)),
+            "GB10" | "pGAA" => Some(FrameFormat::Bayer10(BayerPattern::Gbrg)),
+            "RG12" | "pRCC" => Some(FrameFormat::Bayer12(BayerPattern::Rggb)),
+            "BG12" | "pBCC" => Some(FrameFormat::Bayer12(BayerPattern::Bggr)),
+            "BA12" | "pgCC" => Some(FrameFormat::Bayer12(BayerPattern::Grbg)),
+            "GB12" | "pGCC" => Some(FrameFormat::Bayer12(BayerPattern::Gbrg)),
             _ => None,
         }
     }
+
+    fn bayer_packing(fourcc: FourCC) -> Option<BayerPacking> {
+        match fourcc.str().ok()? {
+            "pRAA" | "pBAA" | "pgAA" | "pGAA" => Some(BayerPacking::Raw10),
+            "pRCC" | "pBCC" | "pgCC" | "pGCC" => Some(BayerPacking::Raw12),
+            _ => None,
+        }
+    }
+
+    /// The MIPI packed variant of a 10 or 12 bit Bayer format.
+    fn packed_bayer_fourcc(frame_format: FrameFormat) -> Option<FourCC> {
+        let fcc = match frame_format {
+            FrameFormat::Bayer10(BayerPattern::Rggb) => b"pRAA",
+            FrameFormat::Bayer10(BayerPattern::Bggr) => b"pBAA",
+            FrameFormat::Bayer10(BayerPattern::Grbg) => b"pgAA",
+            FrameFormat::Bayer10(BayerPattern::Gbrg) => b"pGAA",
+            FrameFormat::Bayer12(BayerPattern::Rggb) => b"pRCC",
+            FrameFormat::Bayer12(BayerPattern::Bggr) => b"pBCC",
+            FrameFormat::Bayer12(BayerPattern::Grbg) => b"pgCC",
+            FrameFormat::Bayer12(BayerPattern::Gbrg) => b"pGCC",
+            _ => return None,
+        };
+        Some(FourCC::new(fcc))
+    }
     
 
-    fn frameformat_to_fourcc(fourcc: FrameFormat) -> FourCC {
-        match fourcc {
-            
+    #[allow(clippy::cast_possible_truncation)]
+    fn frameformat_to_fourcc(fourcc: FrameFormat) -> Option<FourCC> {
+        let fcc = match fourcc {
+            FrameFormat::Yuv422 => b"YUYV",
+            FrameFormat::Uyv422 => b"UYVY",
+            FrameFormat::Yv12 => b"YV12",
+            FrameFormat::MJpeg => b"MJPG",
+            FrameFormat::Luma8 => b"GRAY",
+            FrameFormat::Luma16 => b"Y16 ",
+            FrameFormat::Depth16 => b"Z16 ",
+            FrameFormat::Rgb8 => b"RGB3",
+            FrameFormat::RgbA8 => b"AB24",
+            FrameFormat::Nv12 => b"NV12",
+            FrameFormat::Nv21 => b"NV21",
+            FrameFormat::H264 => b"H264",
+            FrameFormat::H265 => b"HEVC",
+            FrameFormat::Avc1 => b"AVC1",
+            FrameFormat::H263 => b"H263",
+            FrameFormat::XVid => b"XVID",
+            FrameFormat::VP8 => b"VP80",
+            FrameFormat::VP9 => b"VP90",
+            FrameFormat::Mpeg1 => b"MPG1",
+            FrameFormat::Mpeg2 => b"MPG2",
+            FrameFormat::Mpeg4 => b"MPG4",
+            FrameFormat::Bayer8(BayerPattern::Rggb) => b"RGGB",
+            FrameFormat::Bayer8(BayerPattern::Bggr) => b"BA81",
+            FrameFormat::Bayer8(BayerPattern::Grbg) => b"GRBG",
+            FrameFormat::Bayer8(BayerPattern::Gbrg) => b"GBRG",
+            FrameFormat::Bayer10(BayerPattern::Rggb) => b"RG10",
+            FrameFormat::Bayer10(BayerPattern::Bggr) => b"BG10",
+            FrameFormat::Bayer10(BayerPattern::Grbg) => b"BA10",
+            FrameFormat::Bayer10(BayerPattern::Gbrg) => b"GB10",
+            FrameFormat::Bayer12(BayerPattern::Rggb) => b"RG12",
+            FrameFormat::Bayer12(BayerPattern::Bggr) => b"BG12",
+            FrameFormat::Bayer12(BayerPattern::Grbg) => b"BA12",
+            FrameFormat::Bayer12(BayerPattern::Gbrg) => b"GB12",
+            FrameFormat::PlatformSpecificCustomFormat(platform)
+                if platform.backend() == ApiBackend::Video4Linux =>
+            {
+                return platform.fourcc().map(|fourcc| FourCC::new(&fourcc))
+            }
+            FrameFormat::Custom(_) | FrameFormat::PlatformSpecificCustomFormat(_) => return None,
+        };
+        Some(FourCC::new(fcc))
+    }
+
+    // A single-planar frame, unpacking packed Bayer data
+    fn single_plane_buffer(
+        cam_fmt: CameraFormat,
+        data: &[u8],
+        packed_bayer: Option<(BayerPacking, u32)>,
+        strides: &[usize],
+    ) -> Buffer {
+        match packed_bayer {
+            Some((packing, stride)) => Buffer::new(
+                cam_fmt.resolution(),
+                &unpack_bayer(
+                    data,
+                    packing,
+                    cam_fmt.width() as usize,
+                    cam_fmt.height() as usize,
+                    stride as usize,
+                ),
+                cam_fmt.format(),
+            ),
+            // planar formats come as one block here, `with_stride` splits them into their planes
+            None => Buffer::new(cam_fmt.resolution(), data, cam_fmt.format())
+                .with_stride(strides.first().copied().unwrap_or_default()),
+        }
+    }
+
+    fn with_metadata(buffer: Buffer, metadata: &Metadata) -> Buffer {
+        let buffer = buffer
+            .with_frame_type(flags_to_frame_type(metadata.flags))
+            .with_sequence(u64::from(metadata.sequence));
+        match timestamp_from_metadata(metadata) {
+            Some(timestamp) => buffer.with_timestamp(timestamp),
+            None => buffer,
+        }
+    }
+
+    fn open_metadata_node(path: &str) -> Result<MetadataNode, NokhwaError> {
+        let node = metadata::paired_node(Path::new(path)).ok_or_else(|| {
+            NokhwaError::OpenDeviceError(
+                path.to_string(),
+                "Device has no metadata capture node".to_string(),
+            )
+        })?;
+        MetadataNode::open(&node).map_err(|why| {
+            NokhwaError::OpenDeviceError(node.to_string_lossy().to_string(), why.to_string())
+        })
+    }
+
+    // Attaches the metadata record of the same sequence number, if the node has one
+    fn with_frame_metadata(node: &mut Option<MetadataNode>, buffer: Buffer) -> Buffer {
+        let frame_metadata = node
+            .as_mut()
+            .zip(buffer.sequence().and_then(|sequence| u32::try_from(sequence).ok()))
+            .and_then(|(node, sequence)| node.metadata_for(sequence));
+        match frame_metadata {
+            Some(frame_metadata) => buffer.with_frame_metadata(frame_metadata),
+            None => buffer,
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    fn timestamp_from_metadata(metadata: &Metadata) -> Option<FrameTimestamp> {
+        let (sec, usec) = (metadata.timestamp.sec, metadata.timestamp.usec);
+        // drivers that do not timestamp leave it zeroed
+        if sec < 0 || usec < 0 || (sec == 0 && usec == 0) {
+            return None;
+        }
+        // V4L2_BUF_FLAG_TIMESTAMP_COPY timestamps were copied from an output buffer by a mem2mem device,
+        // so their clock is whatever the application used.
+        let clock = if metadata.flags & BufferFlags::TIMESTAMP_MASK == BufferFlags::TIMESTAMP_MONOTONIC {
+            TimestampClock::Monotonic
+        } else {
+            TimestampClock::Unknown
+        };
+        Some(FrameTimestamp::new(
+            Duration::from_secs(sec as u64) + Duration::from_micros(usec as u64),
+            clock,
+        ))
+    }
+
+    fn flags_to_frame_type(flags: BufferFlags) -> FrameType {
+        if flags.contains(BufferFlags::KEYFRAME) {
+            FrameType::KeyFrame
+        } else if flags.contains(BufferFlags::PFRAME) {
+            FrameType::PFrame
+        } else if flags.contains(BufferFlags::BFRAME) {
+            FrameType::BFrame
+        } else {
+            FrameType::Unknown
         }
     }
 }
@@ -628,14 +2587,16 @@ mod internal {
 mod internal {
     use nokhwa_core::buffer::Buffer;
     use nokhwa_core::error::NokhwaError;
-    use nokhwa_core::traits::CaptureTrait;
+    use nokhwa_core::traits::{CaptureTrait, VirtualBackendTrait};
     use nokhwa_core::types::{
         ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
-        FrameFormat, KnownCameraControl, RequestedFormat, Resolution,
+        FormatChanged, FrameFormat, KnownCameraControl, RequestedFormat, Resolution,
     };
     use std::borrow::Cow;
     use std::collections::HashMap;
     use std::marker::PhantomData;
+    use std::path::PathBuf;
+    use std::time::Duration;
 
     /// Attempts to convert a [`KnownCameraControl`] into a V4L2 Control ID.
     /// If the associated control is not found, this will return `None` (`ColorEnable`, `Roll`)
@@ -651,6 +2612,123 @@ mod internal {
         KnownCameraControl::Other(id as u128)
     }
 
+    /// An encoder parameter of a camera that outputs compressed video, such as H.264 over UVC (`V4L2_CID_MPEG_*`).
+    ///
+    /// The current values (and whether the device supports them at all) show up in [`camera_controls()`](CaptureTrait::camera_controls)
+    /// as [`KnownCameraControl::Other`] with the control's `V4L2_CID_MPEG_*` ID.
+    #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+    pub enum CodecControl {
+        /// Average bitrate in bits per second.
+        Bitrate(u32),
+        /// Peak bitrate in bits per second. Only used with a variable bitrate.
+        BitratePeak(u32),
+        /// `true` for a constant bitrate, `false` for a variable bitrate.
+        ConstantBitrate(bool),
+        /// Size of a group of pictures, in frames.
+        GopSize(u32),
+        /// Distance between I-frames, in frames.
+        H264IFramePeriod(u32),
+        /// H.264 profile, as a `V4L2_MPEG_VIDEO_H264_PROFILE_*` value.
+        H264Profile(u32),
+        /// H.264 level, as a `V4L2_MPEG_VIDEO_H264_LEVEL_*` value.
+        H264Level(u32),
+        /// HEVC profile, as a `V4L2_MPEG_VIDEO_HEVC_PROFILE_*` value.
+        HevcProfile(u32),
+        /// HEVC level, as a `V4L2_MPEG_VIDEO_HEVC_LEVEL_*` value.
+        HevcLevel(u32),
+        /// Make the next frame a keyframe.
+        ForceKeyFrame,
+    }
+
+    /// Resolves a [`CameraIndex`] to the device node it refers to.
+    /// # Errors
+    /// This will error if the path does not exist or does not resolve to a character device.
+    pub fn device_path(_index: &CameraIndex) -> Result<PathBuf, NokhwaError> {
+        Err(NokhwaError::NotImplementedError(
+            "V4L2 only on Linux".to_string(),
+        ))
+    }
+
+    /// The memory model used to exchange frame buffers with the driver.
+    #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum CaptureMemory {
+        /// Driver allocated buffers mapped into this process (`V4L2_MEMORY_MMAP`).
+        #[default]
+        Mmap,
+        /// Driver allocated buffers that are additionally exported as dma-buf file descriptors (`VIDIOC_EXPBUF`).
+        DmaBuf,
+        /// Frames are copied out of the driver with `read()` (`V4L2_CAP_READWRITE`).
+        Read,
+        /// Buffers allocated by the application, which the driver captures into directly (`V4L2_MEMORY_USERPTR`).
+        UserPtr,
+        /// Dma-bufs allocated by the application, which the driver captures into directly (`V4L2_MEMORY_DMABUF`).
+        DmaBufImport,
+    }
+
+    /// The amount of buffers requested from the driver if none is specified.
+    pub const DEFAULT_BUFFER_COUNT: u32 = 4;
+
+    /// How long a frame is waited for if no timeout is specified.
+    pub const DEFAULT_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Builder for a [`V4LCaptureDevice`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct V4LCaptureDeviceBuilder {
+        index: CameraIndex,
+        capture_memory: CaptureMemory,
+        buffer_count: u32,
+        frame_timeout: Option<Duration>,
+    }
+
+    impl V4LCaptureDeviceBuilder {
+        /// Creates a new builder for the device at `index`.
+        #[must_use]
+        pub fn new(index: &CameraIndex) -> Self {
+            V4LCaptureDeviceBuilder {
+                index: index.clone(),
+                capture_memory: CaptureMemory::default(),
+                buffer_count: DEFAULT_BUFFER_COUNT,
+                frame_timeout: Some(DEFAULT_FRAME_TIMEOUT),
+            }
+        }
+
+        /// Sets the [`CaptureMemory`] used to exchange frame buffers with the driver.
+        #[must_use]
+        pub fn capture_memory(mut self, capture_memory: CaptureMemory) -> Self {
+            self.capture_memory = capture_memory;
+            self
+        }
+
+        /// Sets the amount of buffers requested from the driver when the stream is opened.
+        #[must_use]
+        pub fn buffer_count(mut self, buffer_count: u32) -> Self {
+            self.buffer_count = buffer_count.max(1);
+            self
+        }
+
+        /// Sets how long reading a frame waits for the driver, `None` waiting forever.
+        #[must_use]
+        pub fn frame_timeout(mut self, frame_timeout: Option<Duration>) -> Self {
+            self.frame_timeout = frame_timeout;
+            self
+        }
+
+        /// Sets whether the metadata capture node paired with the device is streamed alongside it.
+        #[must_use]
+        pub fn attach_metadata(self, _attach_metadata: bool) -> Self {
+            self
+        }
+
+        /// Opens the device.
+        /// # Errors
+        /// This function will error if the camera is currently busy, if `V4L2` can't read device information, or if the device does not support streaming I/O.
+        pub fn build<'a>(self) -> Result<V4LCaptureDevice<'a>, NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+    }
+
     /// The backend struct that interfaces with V4L2.
     /// To see what this does, please see [`CaptureTrait`].
     /// # Quirks
@@ -671,6 +2749,48 @@ mod internal {
             ))
         }
 
+        /// Creates a new capture device using the `V4L2` backend, exchanging frame buffers with the driver using `memory`.
+        /// # Errors
+        /// This function will error if the camera is currently busy, if `V4L2` can't read device information, or if the device does not support streaming I/O.
+        pub fn new_with_memory(
+            index: &CameraIndex,
+            memory: CaptureMemory,
+        ) -> Result<Self, NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        /// Creates a [`V4LCaptureDeviceBuilder`] for the device at `index`, to configure the device before it is opened.
+        #[must_use]
+        pub fn builder(index: &CameraIndex) -> V4LCaptureDeviceBuilder {
+            V4LCaptureDeviceBuilder::new(index)
+        }
+
+        /// Gets the amount of buffers granted by the driver for the open stream. Returns `None` if the stream is not open.
+        #[must_use]
+        pub fn buffer_count(&self) -> Option<u32> {
+            None
+        }
+
+        /// Gets how long reading a frame waits for the driver, `None` waiting forever.
+        #[must_use]
+        pub fn frame_timeout(&self) -> Option<Duration> {
+            None
+        }
+
+        /// Sets how long reading a frame waits for the driver, `None` waiting forever.
+        pub fn set_frame_timeout(&mut self, frame_timeout: Option<Duration>) {}
+
+        /// Sets encoder parameters of a camera that outputs compressed video.
+        /// # Errors
+        /// This will error if `controls` is empty, or if the device does not support, or rejects, any of the controls.
+        pub fn set_codec_controls(&mut self, controls: &[CodecControl]) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
         /// Create a new `V4L2` Camera with desired settings. This may or may not work.
         /// # Errors
         /// This function will error if the camera is currently busy or if `V4L2` can't read device information.
@@ -687,6 +2807,46 @@ mod internal {
             ))
         }
 
+        /// Starts streaming without waiting for a frame, opening the stream if needed.
+        /// # Errors
+        /// This will error if the device was created with [`CaptureMemory::DmaBuf`] or [`CaptureMemory::DmaBufImport`], or if the driver fails to start the stream.
+        pub fn start_stream(&mut self) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        /// Returns `true` if the driver reported the device gone.
+        #[must_use]
+        pub fn is_device_lost(&self) -> bool {
+            false
+        }
+
+        /// Reopens the device after it was lost, closing the stream and restoring the [`CameraFormat`].
+        /// # Errors
+        /// This will error if the device is not back yet, if it is no longer a capture device of the same kind, or if it rejects the previous [`CameraFormat`].
+        pub fn reconnect(&mut self) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        /// Changes the [`CameraFormat`] of an open stream in one step, restarting the stream if it was running.
+        /// # Errors
+        /// If the device rejects the format, the previous format and stream are restored and this will error.
+        pub fn set_format_live(&mut self, _new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        /// Calls `callback` after [`set_format_live()`](V4LCaptureDevice::set_format_live) changed the format.
+        pub fn on_format_change(
+            &mut self,
+            _callback: Box<dyn FnMut(FormatChanged) + Send + 'static>,
+        ) {
+        }
+
         /// Force refreshes the inner [`CameraFormat`] state.
         /// # Errors
         /// If the internal representation in the driver is invalid, this will error.
@@ -793,6 +2953,61 @@ mod internal {
             todo!()
         }
     }
+
+    /// A virtual camera backed by a `v4l2loopback` device.
+    /// To see what this does, please see [`VirtualBackendTrait`].
+    pub struct V4LLoopbackDevice {
+        camera_format: CameraFormat,
+    }
+
+    #[allow(unused_variables)]
+    impl V4LLoopbackDevice {
+        /// Opens the `v4l2loopback` device at `index` (see [`device_path()`]) and negotiates `camera_format` with it.
+        /// # Errors
+        /// This will error if the device can not be opened, is not a video output device, or rejects the format.
+        pub fn new(index: &CameraIndex, camera_format: CameraFormat) -> Result<Self, NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        /// Gets the path of the device node frames are written to.
+        #[must_use]
+        pub fn path(&self) -> &str {
+            ""
+        }
+    }
+
+    #[allow(unused_variables)]
+    impl VirtualBackendTrait for V4LLoopbackDevice {
+        fn backend(&self) -> ApiBackend {
+            ApiBackend::Video4Linux
+        }
+
+        fn camera_format(&self) -> CameraFormat {
+            self.camera_format
+        }
+
+        fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+
+        fn write_frame(&mut self, buffer: &Buffer) -> Result<(), NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "V4L2 only on Linux".to_string(),
+            ))
+        }
+    }
 }
 
+#[cfg(target_os = "linux")]
+pub use dmabuf::ImportedDmaBuf;
 pub use internal::*;
+#[cfg(target_os = "linux")]
+pub use poller::{MultiCameraPoller, MultiCameraPollerHandle, PolledCameraId};
+#[cfg(target_os = "linux")]
+pub use usb::UsbBandwidth;
+#[cfg(target_os = "linux")]
+pub use userptr::UserBuffer;
@@ -0,0 +1,619 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Media controller (`linux/media.h`) and sub-device (`linux/v4l2-subdev.h`) support.
+//!
+//! Camera pipelines on SoCs (e.g. i.MX, Rockchip, or the Raspberry Pi `unicam` receiver) are a graph of sensor, CSI-2 receiver and ISP
+//! entities. Their links and pad formats must be configured, as `media-ctl` does, before the capture node produces frames.
+
+use crate::ioctl::iowr;
+use nokhwa_core::{error::NokhwaError, types::Resolution};
+use std::{
+    fs::{self, File, OpenOptions},
+    io, mem,
+    os::{
+        fd::{AsRawFd, RawFd},
+        raw::c_char,
+    },
+    path::{Path, PathBuf},
+};
+use v4l::v4l2::{self, vidioc::_IOC_TYPE};
+
+const MEDIA_ENT_ID_FLAG_NEXT: u32 = 1 << 31;
+const MEDIA_LNK_FL_ENABLED: u32 = 1;
+const MEDIA_LNK_FL_IMMUTABLE: u32 = 1 << 1;
+const MEDIA_PAD_FL_SINK: u32 = 1;
+const MEDIA_PAD_FL_SOURCE: u32 = 1 << 1;
+const V4L2_SUBDEV_FORMAT_ACTIVE: u32 = 1;
+
+#[repr(C)]
+#[allow(dead_code)]
+struct MediaDeviceInfo {
+    driver: [c_char; 16],
+    model: [c_char; 32],
+    serial: [c_char; 40],
+    bus_info: [c_char; 32],
+    media_version: u32,
+    hw_revision: u32,
+    driver_version: u32,
+    reserved: [u32; 31],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct MediaEntityDesc {
+    id: u32,
+    name: [c_char; 32],
+    type_: u32,
+    revision: u32,
+    flags: u32,
+    group_id: u32,
+    pads: u16,
+    links: u16,
+    reserved: [u32; 4],
+    // start of the union, `dev` for entities with a device node
+    dev_major: u32,
+    dev_minor: u32,
+    raw: [u8; 176],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+struct MediaPadDesc {
+    entity: u32,
+    index: u16,
+    flags: u32,
+    reserved: [u32; 2],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+struct MediaLinkDesc {
+    source: MediaPadDesc,
+    sink: MediaPadDesc,
+    flags: u32,
+    reserved: [u32; 2],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct MediaLinksEnum {
+    entity: u32,
+    pads: *mut MediaPadDesc,
+    links: *mut MediaLinkDesc,
+    reserved: [u32; 4],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct V4l2MbusFramefmt {
+    width: u32,
+    height: u32,
+    code: u32,
+    field: u32,
+    colorspace: u32,
+    ycbcr_enc: u16,
+    quantization: u16,
+    xfer_func: u16,
+    flags: u16,
+    reserved: [u16; 10],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct V4l2SubdevFormat {
+    which: u32,
+    pad: u32,
+    format: V4l2MbusFramefmt,
+    stream: u32,
+    reserved: [u32; 7],
+}
+
+const MEDIA_IOC_DEVICE_INFO: _IOC_TYPE = iowr(b'|', 0x00, mem::size_of::<MediaDeviceInfo>());
+const MEDIA_IOC_ENUM_ENTITIES: _IOC_TYPE = iowr(b'|', 0x01, mem::size_of::<MediaEntityDesc>());
+const MEDIA_IOC_ENUM_LINKS: _IOC_TYPE = iowr(b'|', 0x02, mem::size_of::<MediaLinksEnum>());
+const MEDIA_IOC_SETUP_LINK: _IOC_TYPE = iowr(b'|', 0x03, mem::size_of::<MediaLinkDesc>());
+const VIDIOC_SUBDEV_S_FMT: _IOC_TYPE = iowr(b'V', 5, mem::size_of::<V4l2SubdevFormat>());
+
+fn ioctl<T>(fd: RawFd, request: _IOC_TYPE, arg: &mut T) -> io::Result<()> {
+    unsafe { v4l2::ioctl(fd, request, (arg as *mut T).cast()) }
+}
+
+fn c_string(chars: &[c_char]) -> String {
+    #[allow(clippy::cast_sign_loss)]
+    let bytes = chars
+        .iter()
+        .map(|c| *c as u8)
+        .take_while(|b| *b != 0)
+        .collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// A pad of a [`MediaEntity`], the point links connect to.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct MediaPad {
+    pub entity: u32,
+    pub index: u16,
+}
+
+impl MediaPad {
+    /// Creates a new pad `index` of the entity with the id `entity`.
+    #[must_use]
+    pub fn new(entity: u32, index: u16) -> Self {
+        MediaPad { entity, index }
+    }
+
+    fn from_desc(desc: &MediaPadDesc) -> Self {
+        MediaPad::new(desc.entity, desc.index)
+    }
+
+    fn to_desc(self) -> MediaPadDesc {
+        MediaPadDesc {
+            entity: self.entity,
+            index: self.index,
+            ..unsafe { mem::zeroed() }
+        }
+    }
+}
+
+/// An entity of a media graph, such as a sensor, a CSI-2 receiver, an ISP, or a capture node.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct MediaEntity {
+    id: u32,
+    name: String,
+    function: u32,
+    pad_count: u16,
+    link_count: u16,
+    device_node: Option<PathBuf>,
+}
+
+impl MediaEntity {
+    /// Gets the id of the entity, unique within its [`MediaDevice`].
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Gets the name of the entity, e.g. `imx219 10-0010`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the function of the entity, one of the `MEDIA_ENT_F_*` constants.
+    #[must_use]
+    pub fn function(&self) -> u32 {
+        self.function
+    }
+
+    /// Gets the amount of pads of the entity.
+    #[must_use]
+    pub fn pad_count(&self) -> u16 {
+        self.pad_count
+    }
+
+    /// Gets the amount of links from the entity's source pads.
+    #[must_use]
+    pub fn link_count(&self) -> u16 {
+        self.link_count
+    }
+
+    /// Gets the device node of the entity (e.g. `/dev/video0` or `/dev/v4l-subdev0`), if it has one.
+    #[must_use]
+    pub fn device_node(&self) -> Option<&Path> {
+        self.device_node.as_deref()
+    }
+}
+
+/// A link between a source pad and a sink pad.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct MediaLink {
+    source: MediaPad,
+    sink: MediaPad,
+    flags: u32,
+}
+
+impl MediaLink {
+    /// Gets the source pad of the link.
+    #[must_use]
+    pub fn source(&self) -> MediaPad {
+        self.source
+    }
+
+    /// Gets the sink pad of the link.
+    #[must_use]
+    pub fn sink(&self) -> MediaPad {
+        self.sink
+    }
+
+    /// Returns `true` if data flows through the link.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.flags & MEDIA_LNK_FL_ENABLED != 0
+    }
+
+    /// Returns `true` if the link can not be changed.
+    #[must_use]
+    pub fn is_immutable(&self) -> bool {
+        self.flags & MEDIA_LNK_FL_IMMUTABLE != 0
+    }
+}
+
+/// An open media controller device (`/dev/mediaN`).
+pub struct MediaDevice {
+    file: File,
+    path: PathBuf,
+}
+
+impl MediaDevice {
+    /// Opens the media controller device at `path`.
+    /// # Errors
+    /// If the device can not be opened, this will error.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NokhwaError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|why| {
+                NokhwaError::OpenDeviceError(path.to_string_lossy().to_string(), why.to_string())
+            })?;
+        Ok(MediaDevice { file, path })
+    }
+
+    /// Gets the path of the device.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the model of the device, e.g. `unicam`.
+    /// # Errors
+    /// If the device can not be queried, this will error.
+    pub fn model(&self) -> Result<String, NokhwaError> {
+        let mut info: MediaDeviceInfo = unsafe { mem::zeroed() };
+        ioctl(self.file.as_raw_fd(), MEDIA_IOC_DEVICE_INFO, &mut info).map_err(|why| {
+            NokhwaError::GetPropertyError {
+                property: "Media device info".to_string(),
+                error: why.to_string(),
+            }
+        })?;
+        Ok(c_string(&info.model))
+    }
+
+    /// Enumerates the entities of the media graph.
+    /// # Errors
+    /// If the device can not be queried, this will error.
+    pub fn entities(&self) -> Result<Vec<MediaEntity>, NokhwaError> {
+        let mut entities = vec![];
+        let mut id = 0;
+        loop {
+            let mut desc = MediaEntityDesc {
+                id: id | MEDIA_ENT_ID_FLAG_NEXT,
+                ..unsafe { mem::zeroed() }
+            };
+            if let Err(why) = ioctl(self.file.as_raw_fd(), MEDIA_IOC_ENUM_ENTITIES, &mut desc) {
+                // EINVAL marks the end of the entities
+                if why.raw_os_error() == Some(libc::EINVAL) {
+                    break;
+                }
+                return Err(NokhwaError::GetPropertyError {
+                    property: "Media entities".to_string(),
+                    error: why.to_string(),
+                });
+            }
+
+            entities.push(MediaEntity {
+                id: desc.id,
+                name: c_string(&desc.name),
+                function: desc.type_,
+                pad_count: desc.pads,
+                link_count: desc.links,
+                device_node: device_node(desc.dev_major, desc.dev_minor),
+            });
+            id = desc.id;
+        }
+        Ok(entities)
+    }
+
+    /// Finds the entity named `name`.
+    /// # Errors
+    /// If the device can not be queried or has no such entity, this will error.
+    pub fn entity_by_name(&self, name: &str) -> Result<MediaEntity, NokhwaError> {
+        self.entities()?
+            .into_iter()
+            .find(|entity| entity.name == name)
+            .ok_or(NokhwaError::GetPropertyError {
+                property: format!("Media entity {name}"),
+                error: "No such entity".to_string(),
+            })
+    }
+
+    /// Enumerates the links from the source pads of `entity`.
+    /// # Errors
+    /// If the device can not be queried, this will error.
+    pub fn links(&self, entity: &MediaEntity) -> Result<Vec<MediaLink>, NokhwaError> {
+        let mut pads = vec![unsafe { mem::zeroed::<MediaPadDesc>() }; usize::from(entity.pad_count)];
+        let mut links =
+            vec![unsafe { mem::zeroed::<MediaLinkDesc>() }; usize::from(entity.link_count)];
+        let mut links_enum = MediaLinksEnum {
+            entity: entity.id,
+            pads: pads.as_mut_ptr(),
+            links: links.as_mut_ptr(),
+            reserved: [0; 4],
+        };
+        ioctl(self.file.as_raw_fd(), MEDIA_IOC_ENUM_LINKS, &mut links_enum).map_err(|why| {
+            NokhwaError::GetPropertyError {
+                property: format!("Links of {}", entity.name),
+                error: why.to_string(),
+            }
+        })?;
+
+        Ok(links
+            .iter()
+            .filter(|link| link.source.flags & MEDIA_PAD_FL_SOURCE != 0)
+            .map(|link| MediaLink {
+                source: MediaPad::from_desc(&link.source),
+                sink: MediaPad::from_desc(&link.sink),
+                flags: link.flags,
+            })
+            .collect())
+    }
+
+    /// Enables or disables the link from `source` to `sink`.
+    /// # Errors
+    /// If the link does not exist, is immutable, or the pipeline is streaming, this will error.
+    pub fn setup_link(
+        &self,
+        source: MediaPad,
+        sink: MediaPad,
+        enabled: bool,
+    ) -> Result<(), NokhwaError> {
+        let mut link = MediaLinkDesc {
+            source: MediaPadDesc {
+                flags: MEDIA_PAD_FL_SOURCE,
+                ..source.to_desc()
+            },
+            sink: MediaPadDesc {
+                flags: MEDIA_PAD_FL_SINK,
+                ..sink.to_desc()
+            },
+            flags: if enabled { MEDIA_LNK_FL_ENABLED } else { 0 },
+            reserved: [0; 2],
+        };
+        ioctl(self.file.as_raw_fd(), MEDIA_IOC_SETUP_LINK, &mut link).map_err(|why| {
+            NokhwaError::SetPropertyError {
+                property: format!(
+                    "Link {}:{} -> {}:{}",
+                    source.entity, source.index, sink.entity, sink.index
+                ),
+                value: enabled.to_string(),
+                error: why.to_string(),
+            }
+        })
+    }
+
+    /// Disables every enabled link that is not immutable, like `media-ctl --reset`.
+    /// # Errors
+    /// If the device can not be queried or a link can not be disabled, this will error.
+    pub fn reset_links(&self) -> Result<(), NokhwaError> {
+        for entity in self.entities()? {
+            for link in self.links(&entity)? {
+                if link.is_enabled() && !link.is_immutable() {
+                    self.setup_link(link.source, link.sink, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the active format of `pad` of the sub-device `entity` to `resolution` in the media bus format `mbus_code` (one of the
+    /// `MEDIA_BUS_FMT_*` constants), returning the resolution and media bus format the driver applied.
+    /// # Errors
+    /// If the entity has no sub-device node, or the sub-device rejects the format, this will error.
+    pub fn set_pad_format(
+        &self,
+        entity: &MediaEntity,
+        pad: u32,
+        resolution: Resolution,
+        mbus_code: u32,
+    ) -> Result<(Resolution, u32), NokhwaError> {
+        let property = format!("Format of {}:{pad}", entity.name);
+        let node = entity
+            .device_node()
+            .ok_or(NokhwaError::SetPropertyError {
+                property: property.clone(),
+                value: resolution.to_string(),
+                error: "Entity has no sub-device node".to_string(),
+            })?;
+        let subdev = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(node)
+            .map_err(|why| {
+                NokhwaError::OpenDeviceError(node.to_string_lossy().to_string(), why.to_string())
+            })?;
+
+        let mut format = V4l2SubdevFormat {
+            which: V4L2_SUBDEV_FORMAT_ACTIVE,
+            pad,
+            ..unsafe { mem::zeroed() }
+        };
+        format.format.width = resolution.width();
+        format.format.height = resolution.height();
+        format.format.code = mbus_code;
+        ioctl(subdev.as_raw_fd(), VIDIOC_SUBDEV_S_FMT, &mut format).map_err(|why| {
+            NokhwaError::SetPropertyError {
+                property,
+                value: resolution.to_string(),
+                error: why.to_string(),
+            }
+        })?;
+        Ok((
+            Resolution::new(format.format.width, format.format.height),
+            format.format.code,
+        ))
+    }
+}
+
+impl AsRawFd for MediaDevice {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+// resolves the device number of an entity through sysfs, as udev names the node after the kernel device
+fn device_node(major: u32, minor: u32) -> Option<PathBuf> {
+    if major == 0 && minor == 0 {
+        return None;
+    }
+    let link = fs::read_link(format!("/sys/dev/char/{major}:{minor}")).ok()?;
+    link.file_name().map(|name| Path::new("/dev").join(name))
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct LinkSetup {
+    source: String,
+    source_pad: u16,
+    sink: String,
+    sink_pad: u16,
+    enabled: bool,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct PadFormatSetup {
+    entity: String,
+    pad: u32,
+    resolution: Resolution,
+    mbus_code: u32,
+}
+
+/// A configuration of a media graph, applied by
+/// [`V4LCaptureDeviceBuilder::media_graph()`](crate::V4LCaptureDeviceBuilder::media_graph) before the capture node is opened.
+///
+/// Entities are referred to by name, so this is the equivalent of
+/// `media-ctl -d <device> [--reset] --links '"<source>":<pad> -> "<sink>":<pad> [1]' --set-v4l2 '"<entity>":<pad> [fmt:<code>/<width>x<height>]'`.
+/// Links are set up before pad formats, in the order they were added.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct MediaGraphConfig {
+    media_device: PathBuf,
+    reset: bool,
+    links: Vec<LinkSetup>,
+    pad_formats: Vec<PadFormatSetup>,
+}
+
+impl MediaGraphConfig {
+    /// Creates an empty configuration of the media controller device at `media_device`, e.g. `/dev/media0`.
+    #[must_use]
+    pub fn new(media_device: impl AsRef<Path>) -> Self {
+        MediaGraphConfig {
+            media_device: media_device.as_ref().to_path_buf(),
+            reset: false,
+            links: vec![],
+            pad_formats: vec![],
+        }
+    }
+
+    /// Sets whether all mutable links are disabled before the configured links are set up.
+    #[must_use]
+    pub fn reset(mut self, reset: bool) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    /// Adds a link from `source_pad` of the entity named `source` to `sink_pad` of the entity named `sink`, enabled or disabled.
+    #[must_use]
+    pub fn link(
+        mut self,
+        source: &str,
+        source_pad: u16,
+        sink: &str,
+        sink_pad: u16,
+        enabled: bool,
+    ) -> Self {
+        self.links.push(LinkSetup {
+            source: source.to_string(),
+            source_pad,
+            sink: sink.to_string(),
+            sink_pad,
+            enabled,
+        });
+        self
+    }
+
+    /// Adds a format for `pad` of the sub-device named `entity`. See [`MediaDevice::set_pad_format()`].
+    #[must_use]
+    pub fn pad_format(
+        mut self,
+        entity: &str,
+        pad: u32,
+        resolution: Resolution,
+        mbus_code: u32,
+    ) -> Self {
+        self.pad_formats.push(PadFormatSetup {
+            entity: entity.to_string(),
+            pad,
+            resolution,
+            mbus_code,
+        });
+        self
+    }
+
+    /// Applies the configuration, returning the opened [`MediaDevice`].
+    /// # Errors
+    /// If the device can not be opened, an entity does not exist, or a link or pad format is rejected, this will error.
+    pub fn apply(&self) -> Result<MediaDevice, NokhwaError> {
+        let media = MediaDevice::open(&self.media_device)?;
+        let entities = media.entities()?;
+        let find = |name: &str| {
+            entities
+                .iter()
+                .find(|entity| entity.name == name)
+                .ok_or(NokhwaError::GetPropertyError {
+                    property: format!("Media entity {name}"),
+                    error: "No such entity".to_string(),
+                })
+        };
+
+        if self.reset {
+            media.reset_links()?;
+        }
+        for link in &self.links {
+            let source = MediaPad::new(find(&link.source)?.id, link.source_pad);
+            let sink = MediaPad::new(find(&link.sink)?.id, link.sink_pad);
+            media.setup_link(source, sink, link.enabled)?;
+        }
+        for pad_format in &self.pad_formats {
+            let entity = find(&pad_format.entity)?;
+            let (resolution, mbus_code) = media.set_pad_format(
+                entity,
+                pad_format.pad,
+                pad_format.resolution,
+                pad_format.mbus_code,
+            )?;
+            if resolution != pad_format.resolution || mbus_code != pad_format.mbus_code {
+                return Err(NokhwaError::SetPropertyError {
+                    property: format!("Format of {}:{}", entity.name, pad_format.pad),
+                    value: pad_format.resolution.to_string(),
+                    error: format!("Sub-device applied {resolution} in {mbus_code:#x} instead"),
+                });
+            }
+        }
+        Ok(media)
+    }
+}
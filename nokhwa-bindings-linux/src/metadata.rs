@@ -0,0 +1,406 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Per-frame metadata from the metadata capture node (`V4L2_CAP_META_CAPTURE`) that `uvcvideo` creates next to each video node.
+
+use nokhwa_core::buffer::FrameMetadata;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    mem,
+    path::{Path, PathBuf},
+    ptr, slice,
+    sync::Arc,
+    time::Duration,
+};
+use v4l::{
+    buffer::Type,
+    capability::Flags as CapabilityFlags,
+    device::Handle,
+    memory::Memory,
+    v4l2,
+    v4l_sys::{v4l2_buffer, v4l2_format, v4l2_requestbuffers},
+    Device, FourCC,
+};
+
+const ENODEV: i32 = 19;
+const SYSFS_DIR: &str = "/sys/class/video4linux";
+
+// V4L2_META_FMT_UVC, see Documentation/userspace-api/media/v4l/metafmt-uvc.rst
+const UVC_FORMAT: &[u8; 4] = b"UVCH";
+// u64 ns, u16 sof, then the payload header starting with its length
+const UVC_RECORD_HEADER: usize = 10;
+const UVC_STREAM_PTS: u8 = 0x04;
+const UVC_STREAM_SCR: u8 = 0x08;
+
+// Microsoft's UVC 1.5 extension metadata, KSCAMERA_METADATA_CAPTURESTATS
+const MSXU_ITEM_HEADER: usize = 8;
+const MSXU_CAPTURE_STATS: u32 = 3;
+const CAPTURE_STATS_EXPOSURE_TIME: u32 = 0x01;
+const CAPTURE_STATS_ISO_SPEED: u32 = 0x04;
+const CAPTURE_STATS_WHITE_BALANCE: u32 = 0x20;
+
+/// Finds the metadata capture node that belongs to the same device as the video node at `node` (e.g. `/dev/video0`).
+pub fn paired_node(node: &Path) -> Option<PathBuf> {
+    let device_of = |name: &std::ffi::OsStr| {
+        fs::canonicalize(Path::new(SYSFS_DIR).join(name).join("device")).ok()
+    };
+    let node_name = node.file_name()?;
+    let device = device_of(node_name)?;
+
+    let mut siblings = fs::read_dir(SYSFS_DIR)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .filter(|name| name != node_name && device_of(name).as_ref() == Some(&device))
+        .collect::<Vec<_>>();
+    siblings.sort();
+    siblings
+        .into_iter()
+        .map(|name| Path::new("/dev").join(name))
+        .find(|path| {
+            Device::with_path(path)
+                .and_then(|device| device.query_caps())
+                .map_or(false, |caps| {
+                    caps.capabilities.contains(CapabilityFlags::META_CAPTURE)
+                })
+        })
+}
+
+/// An open metadata capture node, whose records are matched to video frames by sequence number.
+pub struct MetadataNode {
+    device: Device,
+    format: FourCC,
+    stream: Option<MetaStream>,
+    // a record of a frame that was not asked for yet
+    pending: Option<(u32, FrameMetadata)>,
+}
+
+impl MetadataNode {
+    /// Opens the metadata capture node at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let device = Device::with_path(path)?;
+        let mut v4l2_fmt = v4l2_format {
+            type_: Type::MetaCapture as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                device.handle().fd(),
+                v4l2::vidioc::VIDIOC_G_FMT,
+                (&mut v4l2_fmt as *mut v4l2_format).cast(),
+            )?;
+        }
+        let format = FourCC::from(unsafe { v4l2_fmt.fmt.meta.dataformat });
+        Ok(MetadataNode {
+            device,
+            format,
+            stream: None,
+            pending: None,
+        })
+    }
+
+    /// Requests `buffer_count` buffers and starts streaming. The node only produces records while the video node streams.
+    pub fn start(&mut self, buffer_count: u32) -> io::Result<()> {
+        self.stop();
+        let mut stream = MetaStream::with_buffers(&self.device, buffer_count)?;
+        stream.start()?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Stops streaming and frees the buffers.
+    pub fn stop(&mut self) {
+        self.stream = None;
+        self.pending = None;
+    }
+
+    /// Gets the metadata of the frame with the driver's `sequence` number, if its record arrived.
+    /// Records of earlier frames are discarded.
+    pub fn metadata_for(&mut self, sequence: u32) -> Option<FrameMetadata> {
+        let stream = self.stream.as_mut()?;
+        loop {
+            match self.pending.take() {
+                Some((pending, metadata)) if pending == sequence => return Some(metadata),
+                Some((pending, metadata)) if pending > sequence => {
+                    self.pending = Some((pending, metadata));
+                    return None;
+                }
+                // the record of a dropped frame, or of one read without metadata
+                _ => {
+                    let (pending, data) = stream.next().ok()??;
+                    let metadata = if &self.format.repr == UVC_FORMAT {
+                        parse_uvc(data)
+                    } else {
+                        FrameMetadata {
+                            raw: data,
+                            ..FrameMetadata::default()
+                        }
+                    };
+                    self.pending = Some((pending, metadata));
+                }
+            }
+        }
+    }
+}
+
+// A buffer holds one record per payload header whose PTS or SCR changed, the first one starting the frame
+fn parse_uvc(data: Vec<u8>) -> FrameMetadata {
+    let mut metadata = FrameMetadata::default();
+    let mut rest = data.as_slice();
+    while rest.len() > UVC_RECORD_HEADER {
+        let length = usize::from(rest[UVC_RECORD_HEADER]);
+        let Some(header) = rest.get(UVC_RECORD_HEADER..UVC_RECORD_HEADER + length) else {
+            break;
+        };
+        if metadata.host_timestamp.is_none() {
+            let mut ns = [0; 8];
+            ns.copy_from_slice(&rest[..8]);
+            metadata.host_timestamp = Some(Duration::from_nanos(u64::from_ne_bytes(ns)));
+        }
+        parse_payload_header(header, &mut metadata);
+        rest = &rest[UVC_RECORD_HEADER + length..];
+    }
+    metadata.raw = data;
+    metadata
+}
+
+// bHeaderLength, bmHeaderInfo, then dwPresentationTime and scrSourceClock if flagged, then extension metadata
+fn parse_payload_header(header: &[u8], metadata: &mut FrameMetadata) {
+    let [_, info, ref fields @ ..] = *header else {
+        return;
+    };
+    let mut fields = fields;
+    if info & UVC_STREAM_PTS != 0 {
+        let [a, b, c, d, ref rest @ ..] = *fields else {
+            return;
+        };
+        metadata
+            .presentation_time
+            .get_or_insert(u32::from_le_bytes([a, b, c, d]));
+        fields = rest;
+    }
+    if info & UVC_STREAM_SCR != 0 {
+        let [a, b, c, d, e, f, ref rest @ ..] = *fields else {
+            return;
+        };
+        // the last reference of the frame is the most precise
+        metadata.source_clock = Some((
+            u32::from_le_bytes([a, b, c, d]),
+            u16::from_le_bytes([e, f]) & 0x07ff,
+        ));
+        fields = rest;
+    }
+    parse_msxu(fields, metadata);
+}
+
+fn parse_msxu(mut items: &[u8], metadata: &mut FrameMetadata) {
+    while items.len() >= MSXU_ITEM_HEADER {
+        let id = read_u32(items, 0);
+        let size = read_u32(items, 4) as usize;
+        if size < MSXU_ITEM_HEADER || size > items.len() {
+            return;
+        }
+        let item = &items[MSXU_ITEM_HEADER..size];
+        items = &items[size..];
+        if id != MSXU_CAPTURE_STATS || item.len() < 44 {
+            continue;
+        }
+
+        let flags = read_u32(item, 0);
+        if flags & CAPTURE_STATS_EXPOSURE_TIME != 0 {
+            let mut exposure = [0; 8];
+            exposure.copy_from_slice(&item[8..16]);
+            // 100 ns units
+            metadata.exposure_time = Some(Duration::from_nanos(
+                u64::from_le_bytes(exposure).saturating_mul(100),
+            ));
+        }
+        if flags & CAPTURE_STATS_ISO_SPEED != 0 {
+            metadata.iso_speed = Some(read_u32(item, 28));
+        }
+        if flags & CAPTURE_STATS_WHITE_BALANCE != 0 {
+            metadata.white_balance = Some(read_u32(item, 40));
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+// A stream of memory mapped metadata buffers, read without waiting. Unmaps and frees the buffers on drop.
+struct MetaStream {
+    handle: Arc<Handle>,
+    buffers: Vec<&'static mut [u8]>,
+    active: bool,
+}
+
+impl MetaStream {
+    fn with_buffers(device: &Device, buffer_count: u32) -> io::Result<Self> {
+        let mut stream = MetaStream {
+            handle: device.handle(),
+            buffers: vec![],
+            active: false,
+        };
+
+        let mut v4l2_reqbufs = v4l2_requestbuffers {
+            count: buffer_count,
+            type_: Type::MetaCapture as u32,
+            memory: Memory::Mmap as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                stream.handle.fd(),
+                v4l2::vidioc::VIDIOC_REQBUFS,
+                (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+            )?;
+        }
+
+        for index in 0..v4l2_reqbufs.count {
+            let mut v4l2_buf = buffer_desc();
+            v4l2_buf.index = index;
+            unsafe {
+                v4l2::ioctl(
+                    stream.handle.fd(),
+                    v4l2::vidioc::VIDIOC_QUERYBUF,
+                    (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+                )?;
+            }
+            let mapping = unsafe {
+                let ptr = v4l2::mmap(
+                    ptr::null_mut(),
+                    v4l2_buf.length as usize,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    stream.handle.fd(),
+                    libc::off_t::from(v4l2_buf.m.offset),
+                )?;
+                slice::from_raw_parts_mut(ptr.cast::<u8>(), v4l2_buf.length as usize)
+            };
+            stream.buffers.push(mapping);
+        }
+
+        Ok(stream)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        for index in 0..self.buffers.len() {
+            self.queue(index)?;
+        }
+        self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMON)?;
+        self.active = true;
+        Ok(())
+    }
+
+    // Dequeues the next filled buffer, copies out its record and hands it straight back. `None` if no buffer is ready.
+    fn next(&mut self) -> io::Result<Option<(u32, Vec<u8>)>> {
+        let mut v4l2_buf = buffer_desc();
+        let dequeued = unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_DQBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )
+        };
+        match dequeued {
+            Ok(()) => {}
+            Err(why) if why.kind() == ErrorKind::WouldBlock => return Ok(None),
+            Err(why) => return Err(why),
+        }
+
+        let index = v4l2_buf.index as usize;
+        let mapping = &self.buffers[index];
+        let data = mapping[..(v4l2_buf.bytesused as usize).min(mapping.len())].to_vec();
+        self.queue(index)?;
+        Ok(Some((v4l2_buf.sequence, data)))
+    }
+
+    fn queue(&mut self, index: usize) -> io::Result<()> {
+        let mut v4l2_buf = buffer_desc();
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            v4l2_buf.index = index as u32;
+        }
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_QBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )
+        }
+    }
+
+    fn stream_ioctl(&self, request: v4l2::vidioc::_IOC_TYPE) -> io::Result<()> {
+        let mut typ = Type::MetaCapture as u32;
+        unsafe { v4l2::ioctl(self.handle.fd(), request, (&mut typ as *mut u32).cast()) }
+    }
+
+    fn release(&mut self) -> io::Result<()> {
+        // the mappings outlive a lost device, so they are unmapped even if stopping the stream fails
+        let stopped = if self.active {
+            self.active = false;
+            self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMOFF)
+        } else {
+            Ok(())
+        };
+
+        let mut unmapped = Ok(());
+        for mapping in self.buffers.drain(..) {
+            let result = unsafe { v4l2::munmap(mapping.as_mut_ptr().cast(), mapping.len()) };
+            unmapped = unmapped.and(result);
+        }
+        stopped?;
+        unmapped?;
+
+        // free all buffers by requesting 0
+        let mut v4l2_reqbufs = v4l2_requestbuffers {
+            count: 0,
+            type_: Type::MetaCapture as u32,
+            memory: Memory::Mmap as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_REQBUFS,
+                (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+            )
+        }
+    }
+}
+
+fn buffer_desc() -> v4l2_buffer {
+    v4l2_buffer {
+        type_: Type::MetaCapture as u32,
+        memory: Memory::Mmap as u32,
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+impl Drop for MetaStream {
+    fn drop(&mut self) {
+        if let Err(why) = self.release() {
+            // the device is gone, so are its buffers
+            if why.raw_os_error() != Some(ENODEV) {
+                panic!("Failed to release metadata buffers: {why}")
+            }
+        }
+    }
+}
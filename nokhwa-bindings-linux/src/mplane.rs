@@ -0,0 +1,309 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Multi-planar (`V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE`) capture. The `v4l` crate only speaks the single-planar API,
+//! which devices such as i.MX, Rockchip, or Raspberry Pi ISP outputs do not implement.
+
+use crate::ioctl::wait_readable;
+use std::{io, mem, ptr, slice, sync::Arc, time::Duration};
+use v4l::{
+    buffer::{Metadata, Type},
+    device::Handle,
+    memory::Memory,
+    v4l2,
+    v4l_sys::{v4l2_buffer, v4l2_format, v4l2_plane, v4l2_requestbuffers, VIDEO_MAX_PLANES},
+    Device, Format, FourCC,
+};
+
+const ENODEV: i32 = 19;
+
+/// Gets the current multi-planar format of `device`, along with the bytes per line of each plane a frame is made of.
+pub fn format(device: &Device) -> io::Result<(Format, Vec<u32>)> {
+    let mut v4l2_fmt = v4l2_format {
+        type_: Type::VideoCaptureMplane as u32,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        v4l2::ioctl(
+            device.handle().fd(),
+            v4l2::vidioc::VIDIOC_G_FMT,
+            (&mut v4l2_fmt as *mut v4l2_format).cast(),
+        )?;
+    }
+    Ok(from_v4l2_format(&v4l2_fmt))
+}
+
+/// Sets the multi-planar format of `device`, returning the format the driver actually applied.
+pub fn set_format(device: &Device, format: &Format) -> io::Result<(Format, Vec<u32>)> {
+    let mut v4l2_fmt = v4l2_format {
+        type_: Type::VideoCaptureMplane as u32,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        // the driver fills in the plane layout
+        v4l2_fmt.fmt.pix_mp.width = format.width;
+        v4l2_fmt.fmt.pix_mp.height = format.height;
+        v4l2_fmt.fmt.pix_mp.pixelformat = u32::from(format.fourcc);
+        v4l2_fmt.fmt.pix_mp.field = format.field_order as u32;
+        v4l2::ioctl(
+            device.handle().fd(),
+            v4l2::vidioc::VIDIOC_S_FMT,
+            (&mut v4l2_fmt as *mut v4l2_format).cast(),
+        )?;
+    }
+    Ok(from_v4l2_format(&v4l2_fmt))
+}
+
+fn from_v4l2_format(v4l2_fmt: &v4l2_format) -> (Format, Vec<u32>) {
+    let pix_mp = unsafe { v4l2_fmt.fmt.pix_mp };
+    let num_planes = usize::from(pix_mp.num_planes).min(VIDEO_MAX_PLANES as usize);
+    let strides = pix_mp.plane_fmt[..num_planes]
+        .iter()
+        .map(|plane| plane.bytesperline)
+        .collect::<Vec<u32>>();
+    let mut format = Format::new(
+        pix_mp.width,
+        pix_mp.height,
+        FourCC::from(pix_mp.pixelformat),
+    );
+    format.stride = strides.first().copied().unwrap_or_default();
+    (format, strides)
+}
+
+/// A stream of memory mapped multi-planar buffers.
+///
+/// All planes are unmapped and the buffers freed on drop.
+pub struct MplaneStream {
+    handle: Arc<Handle>,
+    // buffer index -> plane index -> mapping
+    buffers: Vec<Vec<&'static mut [u8]>>,
+    num_planes: usize,
+    queued_index: Option<usize>,
+    active: bool,
+    timeout: Option<Duration>,
+}
+
+impl MplaneStream {
+    /// Requests `buffer_count` buffers of `num_planes` planes each from the driver, and maps every plane.
+    pub fn with_buffers(device: &Device, num_planes: usize, buffer_count: u32) -> io::Result<Self> {
+        let mut stream = MplaneStream {
+            handle: device.handle(),
+            buffers: vec![],
+            num_planes: num_planes.clamp(1, VIDEO_MAX_PLANES as usize),
+            queued_index: None,
+            active: false,
+            timeout: None,
+        };
+
+        let mut v4l2_reqbufs = v4l2_requestbuffers {
+            count: buffer_count,
+            type_: Type::VideoCaptureMplane as u32,
+            memory: Memory::Mmap as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                stream.handle.fd(),
+                v4l2::vidioc::VIDIOC_REQBUFS,
+                (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+            )?;
+        }
+
+        for index in 0..v4l2_reqbufs.count {
+            let mut planes = stream.planes_desc();
+            let mut v4l2_buf = stream.buffer_desc(&mut planes);
+            v4l2_buf.index = index;
+            unsafe {
+                v4l2::ioctl(
+                    stream.handle.fd(),
+                    v4l2::vidioc::VIDIOC_QUERYBUF,
+                    (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+                )?;
+            }
+
+            let mut mappings = Vec::with_capacity(stream.num_planes);
+            for plane in planes.iter().take(v4l2_buf.length as usize) {
+                let mapping = unsafe {
+                    let ptr = v4l2::mmap(
+                        ptr::null_mut(),
+                        plane.length as usize,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_SHARED,
+                        stream.handle.fd(),
+                        libc::off_t::from(plane.m.mem_offset),
+                    )?;
+                    slice::from_raw_parts_mut(ptr.cast::<u8>(), plane.length as usize)
+                };
+                mappings.push(mapping);
+            }
+            stream.buffers.push(mappings);
+        }
+
+        Ok(stream)
+    }
+
+    /// Gets the amount of buffers granted by the driver.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn buffer_count(&self) -> u32 {
+        self.buffers.len() as u32
+    }
+
+    /// Sets how long [`next()`](MplaneStream::next) waits for a buffer, `None` waiting forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Returns `true` if the stream is started.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Queues all buffers and starts the stream, if it is not started already.
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.active {
+            return Ok(());
+        }
+        for index in 0..self.buffers.len() {
+            self.queue(index)?;
+        }
+        self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMON)?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Hands the previous buffer back to the driver and waits for the next one, returning the used part of each plane
+    /// and the buffer's metadata. The stream is started on the first call.
+    ///
+    /// Fails with [`TimedOut`](io::ErrorKind::TimedOut) if no buffer is ready within the timeout.
+    pub fn next(&mut self) -> io::Result<(Vec<&[u8]>, Metadata)> {
+        match self.queued_index {
+            Some(index) => self.queue(index)?,
+            None => self.start()?,
+        }
+
+        wait_readable(&self.handle, self.timeout)?;
+        let mut planes = self.planes_desc();
+        let mut v4l2_buf = self.buffer_desc(&mut planes);
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_DQBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )?;
+        }
+        let index = v4l2_buf.index as usize;
+        self.queued_index = Some(index);
+
+        let metadata = Metadata {
+            bytesused: planes.iter().map(|plane| plane.bytesused).sum(),
+            flags: v4l2_buf.flags.into(),
+            field: v4l2_buf.field,
+            timestamp: v4l2_buf.timestamp.into(),
+            sequence: v4l2_buf.sequence,
+        };
+        let data = self.buffers[index]
+            .iter()
+            .zip(planes.iter())
+            .map(|(mapping, plane)| {
+                let start = (plane.data_offset as usize).min(mapping.len());
+                let end = (plane.bytesused as usize).clamp(start, mapping.len());
+                &mapping[start..end]
+            })
+            .collect();
+        Ok((data, metadata))
+    }
+
+    fn queue(&mut self, index: usize) -> io::Result<()> {
+        let mut planes = self.planes_desc();
+        let mut v4l2_buf = self.buffer_desc(&mut planes);
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            v4l2_buf.index = index as u32;
+        }
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_QBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )
+        }
+    }
+
+    fn stream_ioctl(&self, request: v4l2::vidioc::_IOC_TYPE) -> io::Result<()> {
+        let mut typ = Type::VideoCaptureMplane as u32;
+        unsafe { v4l2::ioctl(self.handle.fd(), request, (&mut typ as *mut u32).cast()) }
+    }
+
+    fn planes_desc(&self) -> Vec<v4l2_plane> {
+        vec![unsafe { mem::zeroed() }; self.num_planes]
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn buffer_desc(&self, planes: &mut [v4l2_plane]) -> v4l2_buffer {
+        let mut v4l2_buf = v4l2_buffer {
+            type_: Type::VideoCaptureMplane as u32,
+            memory: Memory::Mmap as u32,
+            length: planes.len() as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        v4l2_buf.m.planes = planes.as_mut_ptr();
+        v4l2_buf
+    }
+
+    fn release(&mut self) -> io::Result<()> {
+        // the mappings outlive a lost device, so they are unmapped even if stopping the stream fails
+        let stopped = if self.active {
+            self.active = false;
+            self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMOFF)
+        } else {
+            Ok(())
+        };
+
+        let mut unmapped = Ok(());
+        for mapping in self.buffers.drain(..).flatten() {
+            let result = unsafe { v4l2::munmap(mapping.as_mut_ptr().cast(), mapping.len()) };
+            unmapped = unmapped.and(result);
+        }
+        stopped?;
+        unmapped?;
+
+        // free all buffers by requesting 0
+        let mut v4l2_reqbufs = v4l2_requestbuffers {
+            count: 0,
+            type_: Type::VideoCaptureMplane as u32,
+            memory: Memory::Mmap as u32,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_REQBUFS,
+                (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+            )
+        }
+    }
+}
+
+impl Drop for MplaneStream {
+    fn drop(&mut self) {
+        if let Err(why) = self.release() {
+            // the device is gone, so are its buffers
+            if why.raw_os_error() != Some(ENODEV) {
+                panic!("Failed to release multi-planar buffers: {why}")
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Driving many V4L2 cameras from one thread with `epoll`, instead of a thread per camera.
+
+use crate::{ioctl::poll_timeout, V4LCaptureDevice};
+use nokhwa_core::{buffer::Buffer, error::NokhwaError, traits::CaptureTrait, types::ApiBackend};
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const MAX_EVENTS: usize = 64;
+// epoll data of the eventfd that wakes up the poller, camera ids count up from 0
+const WAKE_TOKEN: u64 = u64::MAX;
+
+/// Identifies a camera added to a [`MultiCameraPoller`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PolledCameraId(u64);
+
+impl PolledCameraId {
+    /// Gets the id as a number, unique within its [`MultiCameraPoller`].
+    #[must_use]
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+fn epoll_ctl(epoll: &OwnedFd, op: i32, fd: RawFd, token: u64) -> io::Result<()> {
+    let mut event = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: token,
+    };
+    if unsafe { libc::epoll_ctl(epoll.as_raw_fd(), op, fd, &mut event) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn wake(wake: &OwnedFd) {
+    let count = 1_u64;
+    // only fails if the counter would overflow, in which case the poller is woken up already
+    let _ = unsafe { libc::write(wake.as_raw_fd(), (&count as *const u64).cast(), 8) };
+}
+
+/// Drives any number of [`V4LCaptureDevice`]s from a single thread using `epoll`, delivering each frame tagged with the
+/// [`PolledCameraId`] of the camera it came from. Surveillance-style applications with many cameras do not need a thread per camera.
+///
+/// Frames are pulled with [`poll()`](MultiCameraPoller::poll), or by a dedicated thread started with [`spawn()`](MultiCameraPoller::spawn).
+/// # Quirks
+/// - Cameras are started with [`V4LCaptureDevice::start_stream()`] when added, so cameras using [`CaptureMemory::DmaBuf`](crate::CaptureMemory::DmaBuf)
+///   or [`CaptureMemory::DmaBufImport`](crate::CaptureMemory::DmaBufImport) can not be added.
+/// - A camera that reports an error on its file descriptor (e.g. it was unplugged) is no longer polled. Its error is delivered once, and the camera
+///   stays in the poller until it is [`remove()`](MultiCameraPoller::remove)d.
+pub struct MultiCameraPoller {
+    epoll: OwnedFd,
+    wake: Arc<OwnedFd>,
+    cameras: HashMap<PolledCameraId, V4LCaptureDevice<'static>>,
+    next_id: u64,
+}
+
+impl MultiCameraPoller {
+    /// Creates a new [`MultiCameraPoller`] without any cameras.
+    /// # Errors
+    /// If the `epoll` instance can not be created, this will error.
+    pub fn new() -> Result<Self, NokhwaError> {
+        let init_error = |why: io::Error| NokhwaError::InitializeError {
+            backend: ApiBackend::Video4Linux,
+            error: why.to_string(),
+        };
+
+        let epoll = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epoll < 0 {
+            return Err(init_error(io::Error::last_os_error()));
+        }
+        let epoll = unsafe { OwnedFd::from_raw_fd(epoll) };
+        let wake = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if wake < 0 {
+            return Err(init_error(io::Error::last_os_error()));
+        }
+        let wake = unsafe { OwnedFd::from_raw_fd(wake) };
+        epoll_ctl(&epoll, libc::EPOLL_CTL_ADD, wake.as_raw_fd(), WAKE_TOKEN).map_err(init_error)?;
+
+        Ok(MultiCameraPoller {
+            epoll,
+            wake: Arc::new(wake),
+            cameras: HashMap::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Starts the stream of `camera` and adds it to the poller.
+    /// # Errors
+    /// If the stream can not be started, or the camera can not be registered with `epoll`, this will error.
+    pub fn add(
+        &mut self,
+        mut camera: V4LCaptureDevice<'static>,
+    ) -> Result<PolledCameraId, NokhwaError> {
+        camera.start_stream()?;
+        let id = PolledCameraId(self.next_id);
+        epoll_ctl(&self.epoll, libc::EPOLL_CTL_ADD, camera.as_raw_fd(), id.0)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        self.next_id += 1;
+        self.cameras.insert(id, camera);
+        Ok(id)
+    }
+
+    /// Removes the camera `id` from the poller, handing it back. Its stream is left open.
+    pub fn remove(&mut self, id: PolledCameraId) -> Option<V4LCaptureDevice<'static>> {
+        let camera = self.cameras.remove(&id)?;
+        // fails if the camera was deregistered after an error already
+        let _ = epoll_ctl(&self.epoll, libc::EPOLL_CTL_DEL, camera.as_raw_fd(), id.0);
+        Some(camera)
+    }
+
+    /// Gets the camera `id`, e.g. to read its controls.
+    #[must_use]
+    pub fn camera(&self, id: PolledCameraId) -> Option<&V4LCaptureDevice<'static>> {
+        self.cameras.get(&id)
+    }
+
+    /// Gets the camera `id` mutably, e.g. to set its controls.
+    pub fn camera_mut(&mut self, id: PolledCameraId) -> Option<&mut V4LCaptureDevice<'static>> {
+        self.cameras.get_mut(&id)
+    }
+
+    /// Gets the ids of all cameras in the poller.
+    #[must_use]
+    pub fn camera_ids(&self) -> Vec<PolledCameraId> {
+        self.cameras.keys().copied().collect()
+    }
+
+    /// Gets the amount of cameras in the poller.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cameras.len()
+    }
+
+    /// Returns `true` if the poller has no cameras.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty()
+    }
+
+    /// Waits up to `timeout` (`None` waiting forever) for any camera to have a frame, then calls `on_frame` with the frame, or the error
+    /// reading it, of every camera that is ready. Returns the amount of times `on_frame` was called, which is `0` on timeout.
+    /// # Errors
+    /// If waiting on `epoll` fails, this will error.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_possible_wrap)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn poll(
+        &mut self,
+        timeout: Option<Duration>,
+        on_frame: &mut impl FnMut(PolledCameraId, Result<Buffer, NokhwaError>),
+    ) -> Result<usize, NokhwaError> {
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; MAX_EVENTS];
+        let ready = unsafe {
+            libc::epoll_wait(
+                self.epoll.as_raw_fd(),
+                events.as_mut_ptr(),
+                MAX_EVENTS as i32,
+                poll_timeout(timeout),
+            )
+        };
+        if ready < 0 {
+            let why = io::Error::last_os_error();
+            if why.kind() == ErrorKind::Interrupted {
+                return Ok(0);
+            }
+            return Err(NokhwaError::ReadFrameError(why.to_string()));
+        }
+
+        let mut delivered = 0;
+        for event in &events[..ready as usize] {
+            let (flags, token) = (event.events, event.u64);
+            if token == WAKE_TOKEN {
+                let mut count = 0_u64;
+                let _ = unsafe {
+                    libc::read(
+                        self.wake.as_raw_fd(),
+                        (&mut count as *mut u64).cast(),
+                        8,
+                    )
+                };
+                continue;
+            }
+
+            let id = PolledCameraId(token);
+            let Some(camera) = self.cameras.get_mut(&id) else {
+                continue;
+            };
+            let frame = camera.frame();
+            if frame.is_err() && flags & (libc::EPOLLERR | libc::EPOLLHUP) as u32 != 0 {
+                let _ = epoll_ctl(&self.epoll, libc::EPOLL_CTL_DEL, camera.as_raw_fd(), token);
+            }
+            on_frame(id, frame);
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Moves the poller to a new thread, which calls `on_frame` for every frame until it is stopped with
+    /// [`MultiCameraPollerHandle::stop()`].
+    #[must_use]
+    pub fn spawn(
+        mut self,
+        mut on_frame: impl FnMut(PolledCameraId, Result<Buffer, NokhwaError>) + Send + 'static,
+    ) -> MultiCameraPollerHandle {
+        let die_bool = Arc::new(AtomicBool::new(false));
+        let wake = self.wake.clone();
+        let thread_die_bool = die_bool.clone();
+        let handle = thread::spawn(move || {
+            while !thread_die_bool.load(Ordering::SeqCst) {
+                self.poll(None, &mut on_frame)?;
+            }
+            Ok(self)
+        });
+
+        MultiCameraPollerHandle {
+            die_bool,
+            wake,
+            handle,
+        }
+    }
+}
+
+impl AsRawFd for MultiCameraPoller {
+    /// The `epoll` file descriptor. It polls readable once any camera has a frame, so the poller can be nested in another event loop.
+    fn as_raw_fd(&self) -> RawFd {
+        self.epoll.as_raw_fd()
+    }
+}
+
+/// A [`MultiCameraPoller`] running on its own thread, see [`MultiCameraPoller::spawn()`].
+pub struct MultiCameraPollerHandle {
+    die_bool: Arc<AtomicBool>,
+    wake: Arc<OwnedFd>,
+    handle: JoinHandle<Result<MultiCameraPoller, NokhwaError>>,
+}
+
+impl MultiCameraPollerHandle {
+    /// Returns `true` if the thread has stopped, either after [`stop()`](MultiCameraPollerHandle::stop) or because polling failed.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stops the thread, handing the poller and its cameras back.
+    /// # Errors
+    /// If polling failed, or the callback panicked, this will error.
+    pub fn stop(self) -> Result<MultiCameraPoller, NokhwaError> {
+        self.die_bool.store(true, Ordering::SeqCst);
+        wake(&self.wake);
+        self.handle.join().map_err(|_| {
+            NokhwaError::GeneralError("MultiCameraPoller thread panicked".to_string())
+        })?
+    }
+}
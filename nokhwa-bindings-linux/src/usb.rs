@@ -0,0 +1,266 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Estimating the isochronous USB bandwidth of UVC cameras from the USB descriptors in sysfs.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const DESCRIPTOR_INTERFACE: u8 = 0x04;
+const DESCRIPTOR_ENDPOINT: u8 = 0x05;
+const DESCRIPTOR_SS_ENDPOINT_COMPANION: u8 = 0x30;
+const CLASS_VIDEO: u8 = 0x0e;
+const SUBCLASS_VIDEO_STREAMING: u8 = 0x02;
+const TRANSFER_ISOCHRONOUS: u8 = 0x01;
+
+/// The isochronous USB bandwidth of a camera, see [`V4LCaptureDevice::usb_bandwidth()`](crate::V4LCaptureDevice::usb_bandwidth).
+///
+/// All bandwidths are in bytes per second.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UsbBandwidth {
+    bus: u32,
+    speed_mbps: u32,
+    reserved: u64,
+    estimated: u64,
+    bus_capacity: u64,
+}
+
+impl UsbBandwidth {
+    /// Gets the number of the USB bus the camera is on.
+    #[must_use]
+    pub fn bus(&self) -> u32 {
+        self.bus
+    }
+
+    /// Gets the speed the camera was enumerated at in Mbit/s, e.g. `480` for high speed.
+    #[must_use]
+    pub fn speed_mbps(&self) -> u32 {
+        self.speed_mbps
+    }
+
+    /// Gets the bandwidth the camera holds on its bus right now, `0` if it is not streaming.
+    #[must_use]
+    pub fn reserved(&self) -> u64 {
+        self.reserved
+    }
+
+    /// Gets the bandwidth the camera is expected to reserve when streaming its current format.
+    ///
+    /// Uncompressed formats reserve the smallest alternate setting that fits a frame every frame interval, as `uvcvideo` does.
+    /// The size of compressed frames is up to the camera, so they are estimated at the largest alternate setting, which many cameras ask for.
+    #[must_use]
+    pub fn estimated(&self) -> u64 {
+        self.estimated
+    }
+
+    /// Gets the bandwidth the bus can reserve for isochronous transfers in total, shared by every device on it.
+    #[must_use]
+    pub fn bus_capacity(&self) -> u64 {
+        self.bus_capacity
+    }
+}
+
+struct Endpoint {
+    alternate_setting: u8,
+    max_packet_size: u16,
+    interval: u8,
+    bytes_per_interval: Option<u16>,
+}
+
+/// Estimates the bandwidth of the video node at `node` (e.g. `/dev/video0`), given the bandwidth its format needs if it is uncompressed.
+/// Returns `None` if the node is not a USB device, or the camera uses bulk transfers which do not reserve bandwidth.
+pub fn usb_bandwidth(node: &Path, required: Option<u64>) -> io::Result<Option<UsbBandwidth>> {
+    let Some(node_name) = node.file_name() else {
+        return Ok(None);
+    };
+    // uvcvideo binds to the video control interface, e.g. 1-1:1.0
+    let interface = fs::canonicalize(
+        Path::new("/sys/class/video4linux")
+            .join(node_name)
+            .join("device"),
+    )?;
+    let Some(device) = interface.parent().map(Path::to_path_buf) else {
+        return Ok(None);
+    };
+    if !device.join("descriptors").exists() {
+        return Ok(None);
+    }
+
+    let speed_mbps = read_number(&device.join("speed"))?;
+    let bus = read_number(&device.join("busnum"))?;
+    let descriptors = fs::read(device.join("descriptors"))?;
+    let control_interface = read_hex(&interface.join("bInterfaceNumber"))?;
+
+    let Some((streaming_interface, endpoints)) =
+        streaming_endpoints(&descriptors, control_interface)
+    else {
+        return Ok(None);
+    };
+    let bandwidths = endpoints
+        .iter()
+        .map(|endpoint| {
+            (
+                endpoint.alternate_setting,
+                endpoint_bandwidth(endpoint, speed_mbps),
+            )
+        })
+        .collect::<Vec<(u8, u64)>>();
+    let largest = bandwidths
+        .iter()
+        .map(|(_, bandwidth)| *bandwidth)
+        .max()
+        .unwrap_or_default();
+    let estimated = match required {
+        Some(required) => bandwidths
+            .iter()
+            .map(|(_, bandwidth)| *bandwidth)
+            .filter(|bandwidth| *bandwidth >= required)
+            .min()
+            .unwrap_or(largest),
+        None => largest,
+    };
+
+    let current_setting = read_number(&streaming_interface_dir(&interface, streaming_interface))
+        .map(|setting| u8::try_from(setting).unwrap_or_default())
+        .unwrap_or_default();
+    let reserved = bandwidths
+        .iter()
+        .find(|(setting, _)| *setting == current_setting)
+        .map(|(_, bandwidth)| *bandwidth)
+        .unwrap_or_default();
+
+    Ok(Some(UsbBandwidth {
+        bus,
+        speed_mbps,
+        reserved,
+        estimated,
+        bus_capacity: bus_capacity(speed_mbps),
+    }))
+}
+
+// e.g. 1-1:1.0 -> 1-1:1.1/bAlternateSetting
+fn streaming_interface_dir(control_interface: &Path, streaming_interface: u8) -> PathBuf {
+    let name = control_interface
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let prefix = name
+        .rsplit_once('.')
+        .map_or(name.as_str(), |(prefix, _)| prefix);
+    control_interface
+        .with_file_name(format!("{prefix}.{streaming_interface}"))
+        .join("bAlternateSetting")
+}
+
+// The isochronous endpoints of each alternate setting of the first video streaming interface after `control_interface`
+fn streaming_endpoints(descriptors: &[u8], control_interface: u8) -> Option<(u8, Vec<Endpoint>)> {
+    let mut streaming_interface = None;
+    // alternate setting of the streaming interface the descriptors belong to
+    let mut current = None;
+    let mut endpoints: Vec<Endpoint> = vec![];
+    // whether a SuperSpeed companion descriptor belongs to the last isochronous endpoint
+    let mut companion_of_last = false;
+
+    let mut rest = descriptors;
+    while let [length, kind, ..] = *rest {
+        let length = usize::from(length);
+        if length < 2 || length > rest.len() {
+            break;
+        }
+        let descriptor = &rest[..length];
+        rest = &rest[length..];
+
+        match (kind, descriptor) {
+            (DESCRIPTOR_INTERFACE, [_, _, number, setting, _, class, subclass, ..]) => {
+                let is_streaming = *class == CLASS_VIDEO
+                    && *subclass == SUBCLASS_VIDEO_STREAMING
+                    && *number > control_interface
+                    && streaming_interface.map_or(true, |interface| interface == *number);
+                current = is_streaming.then_some(*setting);
+                if is_streaming {
+                    streaming_interface = Some(*number);
+                }
+            }
+            (DESCRIPTOR_ENDPOINT, [_, _, _, attributes, low, high, interval, ..]) => {
+                companion_of_last = false;
+                if let Some(setting) = current {
+                    if attributes & 0x03 == TRANSFER_ISOCHRONOUS {
+                        endpoints.push(Endpoint {
+                            alternate_setting: setting,
+                            max_packet_size: u16::from_le_bytes([*low, *high]),
+                            interval: *interval,
+                            bytes_per_interval: None,
+                        });
+                        companion_of_last = true;
+                    }
+                }
+            }
+            (DESCRIPTOR_SS_ENDPOINT_COMPANION, [_, _, _, _, low, high, ..]) => {
+                if let (true, Some(endpoint)) = (companion_of_last, endpoints.last_mut()) {
+                    endpoint.bytes_per_interval = Some(u16::from_le_bytes([*low, *high]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if endpoints.is_empty() {
+        return None;
+    }
+    streaming_interface.map(|interface| (interface, endpoints))
+}
+
+fn endpoint_bandwidth(endpoint: &Endpoint, speed_mbps: u32) -> u64 {
+    let packet = u64::from(endpoint.max_packet_size & 0x07ff);
+    // full speed transfers once per 1 ms frame, faster speeds once every 2^(bInterval - 1) 125 µs microframes
+    if speed_mbps <= 12 {
+        return packet * 1000;
+    }
+    let per_second = 8000 >> endpoint.interval.saturating_sub(1).min(12);
+    let per_interval = match endpoint.bytes_per_interval {
+        Some(bytes) => u64::from(bytes),
+        // high speed endpoints may transfer up to 2 additional packets per microframe
+        None => packet * (1 + u64::from((endpoint.max_packet_size >> 11) & 0x03)),
+    };
+    per_interval * per_second
+}
+
+// USB 2.0 reserves at most 90% (full speed) or 80% (high speed) of the bus for periodic transfers, USB 3 at most 90%
+fn bus_capacity(speed_mbps: u32) -> u64 {
+    let bytes_per_second = u64::from(speed_mbps) * 1_000_000 / 8;
+    if speed_mbps == 480 {
+        bytes_per_second * 8 / 10
+    } else {
+        bytes_per_second * 9 / 10
+    }
+}
+
+// sysfs numbers are decimal, the speed of low speed devices is 1.5
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn read_number(path: &Path) -> io::Result<u32> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse::<f32>()
+        .map(|number| number as u32)
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+}
+
+fn read_hex(path: &Path) -> io::Result<u8> {
+    u8::from_str_radix(fs::read_to_string(path)?.trim(), 16)
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+}
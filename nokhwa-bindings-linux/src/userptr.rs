@@ -0,0 +1,257 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! User pointer (`V4L2_MEMORY_USERPTR`) capture, where the driver writes frames into memory owned by the application.
+
+use crate::ioctl::wait_readable;
+use std::{io, mem, ptr::NonNull, slice, sync::Arc, time::Duration};
+use v4l::{
+    buffer::{Metadata, Type},
+    device::Handle,
+    memory::Memory,
+    v4l2,
+    v4l_sys::{v4l2_buffer, v4l2_requestbuffers},
+    Device,
+};
+
+const ENODEV: i32 = 19;
+
+/// A buffer owned by the application that the driver captures frames into, see [`CaptureMemory::UserPtr`](crate::CaptureMemory::UserPtr).
+///
+/// This only describes the memory, it does not own it. Whoever allocated it (e.g. `cudaHostAlloc()`, `shmat()`, or a page aligned allocation)
+/// frees it, after the stream using it is closed.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UserBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the buffer is only an address, the driver writes to it while it is queued and nothing reads it through a `UserBuffer`.
+unsafe impl Send for UserBuffer {}
+unsafe impl Sync for UserBuffer {}
+
+impl UserBuffer {
+    /// Describes the `len` bytes at `ptr`. Returns `None` if `ptr` is null.
+    ///
+    /// Most drivers need `ptr` and `len` to be aligned to the page size (`sysconf(_SC_PAGESIZE)`), and refuse to queue the buffer otherwise.
+    /// # Safety
+    /// `ptr` must be valid for writes of `len` bytes until the stream the buffer is given to is closed. While the buffer is queued,
+    /// the driver may write to it at any time, so it must not be read or written by anything else until it is returned as a frame.
+    #[must_use]
+    pub unsafe fn new(ptr: *mut u8, len: usize) -> Option<Self> {
+        NonNull::new(ptr).map(|ptr| UserBuffer { ptr, len })
+    }
+
+    /// Gets the start of the buffer.
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Gets the length of the buffer in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the buffer has a length of 0.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Returns `true` if `device` can capture into user pointers.
+pub fn is_supported(device: &Device) -> bool {
+    // requesting 0 buffers frees nothing, but fails if the memory type is not supported
+    request_buffers(&device.handle(), 0).is_ok()
+}
+
+/// A stream that captures into [`UserBuffer`]s.
+///
+/// The stream is stopped and the buffers handed back on drop, after which the driver no longer touches them.
+pub struct UserPtrStream {
+    handle: Arc<Handle>,
+    buffers: Vec<UserBuffer>,
+    queued_index: Option<usize>,
+    active: bool,
+    timeout: Option<Duration>,
+}
+
+impl UserPtrStream {
+    /// Hands `buffers` to the driver, which must have room for a frame of `frame_size` bytes each.
+    pub fn with_buffers(
+        device: &Device,
+        buffers: &[UserBuffer],
+        frame_size: usize,
+    ) -> io::Result<Self> {
+        if let Some(buffer) = buffers.iter().find(|buffer| buffer.len() < frame_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "User buffer of {} bytes can not hold a frame of {frame_size} bytes",
+                    buffer.len()
+                ),
+            ));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let granted = request_buffers(&device.handle(), buffers.len() as u32)? as usize;
+        // user pointer buffers take up no driver memory, so drivers grant them all unless they have a hard limit
+        Ok(UserPtrStream {
+            handle: device.handle(),
+            buffers: buffers[..granted.min(buffers.len())].to_vec(),
+            queued_index: None,
+            active: false,
+            timeout: None,
+        })
+    }
+
+    /// Gets the amount of buffers the driver accepted.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn buffer_count(&self) -> u32 {
+        self.buffers.len() as u32
+    }
+
+    /// Sets how long [`next()`](UserPtrStream::next) waits for a buffer, `None` waiting forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Returns `true` if the stream is started.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Queues all buffers and starts the stream, if it is not started already.
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.active {
+            return Ok(());
+        }
+        for index in 0..self.buffers.len() {
+            self.queue(index)?;
+        }
+        self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMON)?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Hands the previous buffer back to the driver and waits for the next one, returning the used part of it, the [`UserBuffer`]
+    /// it is in, and the buffer's metadata. The stream is started on the first call.
+    ///
+    /// Fails with [`TimedOut`](io::ErrorKind::TimedOut) if no buffer is ready within the timeout.
+    pub fn next(&mut self) -> io::Result<(&[u8], UserBuffer, Metadata)> {
+        match self.queued_index {
+            Some(index) => self.queue(index)?,
+            None => self.start()?,
+        }
+
+        wait_readable(&self.handle, self.timeout)?;
+        let mut v4l2_buf = buffer_desc();
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_DQBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )?;
+        }
+        let index = v4l2_buf.index as usize;
+        self.queued_index = Some(index);
+
+        let buffer = self.buffers[index];
+        let metadata = Metadata {
+            bytesused: v4l2_buf.bytesused,
+            flags: v4l2_buf.flags.into(),
+            field: v4l2_buf.field,
+            timestamp: v4l2_buf.timestamp.into(),
+            sequence: v4l2_buf.sequence,
+        };
+        // SAFETY: the driver is done with a dequeued buffer until it is queued again on the next call, which needs `&mut self`
+        let data = unsafe {
+            slice::from_raw_parts(
+                buffer.as_ptr().cast_const(),
+                (v4l2_buf.bytesused as usize).min(buffer.len()),
+            )
+        };
+        Ok((data, buffer, metadata))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn queue(&mut self, index: usize) -> io::Result<()> {
+        let buffer = self.buffers[index];
+        let mut v4l2_buf = buffer_desc();
+        v4l2_buf.index = index as u32;
+        v4l2_buf.m.userptr = buffer.as_ptr() as std::os::raw::c_ulong;
+        v4l2_buf.length = buffer.len() as u32;
+        unsafe {
+            v4l2::ioctl(
+                self.handle.fd(),
+                v4l2::vidioc::VIDIOC_QBUF,
+                (&mut v4l2_buf as *mut v4l2_buffer).cast(),
+            )
+        }
+    }
+
+    fn stream_ioctl(&self, request: v4l2::vidioc::_IOC_TYPE) -> io::Result<()> {
+        let mut typ = Type::VideoCapture as u32;
+        unsafe { v4l2::ioctl(self.handle.fd(), request, (&mut typ as *mut u32).cast()) }
+    }
+
+    fn release(&mut self) -> io::Result<()> {
+        // STREAMOFF dequeues every buffer, after which the driver no longer writes to them
+        if self.active {
+            self.active = false;
+            self.stream_ioctl(v4l2::vidioc::VIDIOC_STREAMOFF)?;
+        }
+        self.buffers.clear();
+        request_buffers(&self.handle, 0).map(drop)
+    }
+}
+
+impl Drop for UserPtrStream {
+    fn drop(&mut self) {
+        if let Err(why) = self.release() {
+            // the device is gone, so are its references to the buffers
+            if why.raw_os_error() != Some(ENODEV) {
+                panic!("Failed to release user pointer buffers: {why}")
+            }
+        }
+    }
+}
+
+fn buffer_desc() -> v4l2_buffer {
+    v4l2_buffer {
+        type_: Type::VideoCapture as u32,
+        memory: Memory::UserPtr as u32,
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+fn request_buffers(handle: &Handle, count: u32) -> io::Result<u32> {
+    let mut v4l2_reqbufs = v4l2_requestbuffers {
+        count,
+        type_: Type::VideoCapture as u32,
+        memory: Memory::UserPtr as u32,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        v4l2::ioctl(
+            handle.fd(),
+            v4l2::vidioc::VIDIOC_REQBUFS,
+            (&mut v4l2_reqbufs as *mut v4l2_requestbuffers).cast(),
+        )?;
+    }
+    Ok(v4l2_reqbufs.count)
+}
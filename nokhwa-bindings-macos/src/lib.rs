@@ -33,8 +33,8 @@ mod internal {
         // steal it idc
         use crate::internal::CGFloat;
         use core_media_sys::{
-            CMBlockBufferRef, CMFormatDescriptionRef, CMSampleBufferRef, CMTime, CMVideoDimensions,
-            FourCharCode,
+            CMBlockBufferRef, CMClockRef, CMFormatDescriptionRef, CMSampleBufferRef, CMTime,
+            CMVideoDimensions, FourCharCode,
         };
         use objc::{runtime::Object, Message};
         use std::ops::Deref;
@@ -95,6 +95,35 @@ mod internal {
 
             pub fn CMSampleBufferGetDataBuffer(sbuf: CMSampleBufferRef) -> CMBlockBufferRef;
 
+            pub fn CMSampleBufferGetFormatDescription(
+                sbuf: CMSampleBufferRef,
+            ) -> CMFormatDescriptionRef;
+
+            pub fn CMSampleBufferGetPresentationTimeStamp(sbuf: CMSampleBufferRef) -> CMTime;
+
+            pub fn CMSampleBufferGetDuration(sbuf: CMSampleBufferRef) -> CMTime;
+
+            pub fn CMClockGetHostTimeClock() -> CMClockRef;
+
+            pub fn CMSyncConvertTime(
+                time: CMTime,
+                fromClockOrTimebase: CMClockRef,
+                toClockOrTimebase: CMClockRef,
+            ) -> CMTime;
+
+            pub fn CMTimeGetSeconds(time: CMTime) -> f64;
+
+            pub fn CMGetAttachment(
+                target: CMSampleBufferRef,
+                key: *mut Object,
+                attachmentModeOut: *mut u32,
+            ) -> *mut Object;
+
+            pub static kCMSampleBufferAttachmentKey_DroppedFrameReason: NSString;
+            pub static kCMSampleBufferDroppedFrameReason_FrameWasLate: NSString;
+            pub static kCMSampleBufferDroppedFrameReason_OutOfBuffers: NSString;
+            pub static kCMSampleBufferDroppedFrameReason_Discontinuity: NSString;
+
             pub fn dispatch_queue_create(
                 label: *const std::os::raw::c_char,
                 attr: NSObject,
@@ -122,6 +151,47 @@ mod internal {
             ) -> *mut std::os::raw::c_void;
 
             pub fn CVPixelBufferGetPixelFormatType(pixelBuffer: CVPixelBufferRef) -> OSType;
+
+            pub fn CVPixelBufferIsPlanar(pixelBuffer: CVPixelBufferRef) -> Boolean;
+
+            pub fn CVPixelBufferGetPlaneCount(pixelBuffer: CVPixelBufferRef) -> usize;
+
+            pub fn CVPixelBufferGetBaseAddressOfPlane(
+                pixelBuffer: CVPixelBufferRef,
+                planeIndex: usize,
+            ) -> *mut std::os::raw::c_void;
+
+            pub fn CVPixelBufferGetBytesPerRowOfPlane(
+                pixelBuffer: CVPixelBufferRef,
+                planeIndex: usize,
+            ) -> usize;
+
+            pub fn CVPixelBufferGetWidthOfPlane(
+                pixelBuffer: CVPixelBufferRef,
+                planeIndex: usize,
+            ) -> usize;
+
+            pub fn CVPixelBufferGetHeightOfPlane(
+                pixelBuffer: CVPixelBufferRef,
+                planeIndex: usize,
+            ) -> usize;
+
+            pub fn CVPixelBufferGetBytesPerRow(pixelBuffer: CVPixelBufferRef) -> usize;
+
+            pub fn CVPixelBufferGetWidth(pixelBuffer: CVPixelBufferRef) -> usize;
+
+            pub fn CVPixelBufferGetHeight(pixelBuffer: CVPixelBufferRef) -> usize;
+
+            pub fn CVPixelBufferRetain(pixelBuffer: CVPixelBufferRef) -> CVPixelBufferRef;
+
+            pub fn CVPixelBufferRelease(pixelBuffer: CVPixelBufferRef);
+
+            pub fn CVPixelBufferGetIOSurface(pixelBuffer: CVPixelBufferRef) -> IOSurfaceRef;
+        }
+
+        #[link(name = "CoreVideo", kind = "framework")]
+        extern "C" {
+            pub static kCVPixelBufferPixelFormatTypeKey: NSString;
         }
 
         #[repr(C)]
@@ -150,8 +220,12 @@ mod internal {
 
         pub type CVImageBufferRef = CVBufferRef;
         pub type CVPixelBufferRef = CVImageBufferRef;
+        pub type IOSurfaceRef = *mut std::os::raw::c_void;
         pub type CVPixelBufferLockFlags = u64;
         pub type CVReturn = i32;
+        pub type Boolean = u8;
+
+        pub const kCVPixelBufferLock_ReadOnly: CVPixelBufferLockFlags = 1;
 
         pub type OSType = FourCharCode;
         pub type AVVideoCodecType = NSString;
@@ -192,17 +266,37 @@ mod internal {
             pub static AVCaptureExposureTargetBiasCurrent: f32;
             pub static AVCaptureExposureDurationCurrent: CMTime;
             pub static AVCaptureISOCurrent: f32;
+
+            pub static AVCaptureDeviceWasConnectedNotification: NSString;
+            pub static AVCaptureDeviceWasDisconnectedNotification: NSString;
+            pub static AVCaptureSessionWasInterruptedNotification: NSString;
+            pub static AVCaptureSessionInterruptionEndedNotification: NSString;
         }
     }
 
     use crate::core_media::{
-        dispatch_queue_create, AVCaptureExposureDurationCurrent,
-        AVCaptureExposureTargetBiasCurrent, AVCaptureISOCurrent, AVCaptureWhiteBalanceGains,
-        AVMediaTypeAudio, AVMediaTypeClosedCaption, AVMediaTypeDepthData, AVMediaTypeMetadata,
+        dispatch_queue_create, kCMSampleBufferAttachmentKey_DroppedFrameReason,
+        kCMSampleBufferDroppedFrameReason_Discontinuity,
+        kCMSampleBufferDroppedFrameReason_FrameWasLate,
+        kCMSampleBufferDroppedFrameReason_OutOfBuffers, kCVPixelBufferLock_ReadOnly,
+        kCVPixelBufferPixelFormatTypeKey, AVCaptureDeviceWasConnectedNotification,
+        AVCaptureDeviceWasDisconnectedNotification, AVCaptureExposureDurationCurrent,
+        AVCaptureISOCurrent, AVCaptureSessionInterruptionEndedNotification,
+        AVCaptureSessionWasInterruptedNotification, AVCaptureWhiteBalanceGains, AVMediaTypeAudio,
+        AVMediaTypeClosedCaption, AVMediaTypeDepthData, AVMediaTypeMetadata,
         AVMediaTypeMetadataObject, AVMediaTypeMuxed, AVMediaTypeSubtitle, AVMediaTypeText,
-        AVMediaTypeTimecode, AVMediaTypeVideo, CGPoint, CMSampleBufferGetImageBuffer,
-        CMVideoFormatDescriptionGetDimensions, CVImageBufferRef, CVPixelBufferGetBaseAddress,
-        CVPixelBufferGetDataSize, CVPixelBufferLockBaseAddress, CVPixelBufferUnlockBaseAddress,
+        AVMediaTypeTimecode, AVMediaTypeVideo, AVVideoCodecKey, AVVideoCodecTypeHEVC,
+        AVVideoCodecTypeJPEG, CGPoint, CMBlockBufferCopyDataBytes, CMBlockBufferGetDataLength,
+        CMClockGetHostTimeClock, CMGetAttachment, CMSampleBufferGetDataBuffer,
+        CMSampleBufferGetDuration, CMSampleBufferGetFormatDescription,
+        CMSampleBufferGetImageBuffer, CMSampleBufferGetPresentationTimeStamp, CMSyncConvertTime,
+        CMTimeGetSeconds, CMTimeMake, CMVideoFormatDescriptionGetDimensions, CVImageBufferRef,
+        CVPixelBufferGetBaseAddress, CVPixelBufferGetBaseAddressOfPlane,
+        CVPixelBufferGetBytesPerRow, CVPixelBufferGetBytesPerRowOfPlane, CVPixelBufferGetDataSize,
+        CVPixelBufferGetHeight, CVPixelBufferGetHeightOfPlane, CVPixelBufferGetIOSurface,
+        CVPixelBufferGetPixelFormatType, CVPixelBufferGetPlaneCount, CVPixelBufferGetWidth,
+        CVPixelBufferIsPlanar, CVPixelBufferLockBaseAddress, CVPixelBufferRef,
+        CVPixelBufferRelease, CVPixelBufferRetain, CVPixelBufferUnlockBaseAddress, IOSurfaceRef,
         NSObject, OSType,
     };
 
@@ -213,21 +307,37 @@ mod internal {
     };
     use core_media_sys::{
         kCMPixelFormat_24RGB, kCMPixelFormat_422YpCbCr8_yuvs,
-        kCMPixelFormat_8IndexedGray_WhiteIsZero, kCMVideoCodecType_422YpCbCr8,
-        kCMVideoCodecType_JPEG, kCMVideoCodecType_JPEG_OpenDML, CMFormatDescriptionGetMediaSubType,
-        CMFormatDescriptionRef, CMSampleBufferRef, CMTime, CMVideoDimensions,
+        kCMPixelFormat_8IndexedGray_WhiteIsZero, kCMTimeFlags_Valid, kCMTimeInvalid,
+        kCMVideoCodecType_422YpCbCr8, kCMVideoCodecType_AppleProRes422,
+        kCMVideoCodecType_AppleProRes422HQ, kCMVideoCodecType_AppleProRes422LT,
+        kCMVideoCodecType_AppleProRes422Proxy, kCMVideoCodecType_AppleProRes4444,
+        kCMVideoCodecType_JPEG, kCMVideoCodecType_JPEG_OpenDML, CMClockRef,
+        CMFormatDescriptionGetMediaSubType, CMFormatDescriptionRef, CMSampleBufferRef, CMTime,
+        CMVideoDimensions,
     };
     use core_video_sys::{
+        kCVPixelFormatType_420YpCbCr10BiPlanarFullRange,
         kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange,
         kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange, kCVPixelFormatType_422YpCbCr10,
+        kCVPixelFormatType_422YpCbCr10BiPlanarFullRange,
+        kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange, kCVPixelFormatType_422YpCbCr16,
+        kCVPixelFormatType_422YpCbCr8FullRange, kCVPixelFormatType_444YpCbCr10BiPlanarFullRange,
+        kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange,
     };
     use flume::{Receiver, Sender};
     use nokhwa_core::{
+        buffer::{
+            Buffer, BufferPlane, ColorRange, FrameCounter, FrameOrientation, FrameTimestamp,
+            TimestampClock,
+        },
         error::NokhwaError,
         types::{
-            ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo,
-            ControlValueDescription, ControlValueSetter, FrameFormat, KnownCameraControl,
-            KnownCameraControlFlag, Resolution,
+            ApiBackend, AudioDeviceInfo, CameraControl, CameraFormat, CameraIndex, CameraInfo,
+            CameraPosition, ControlValueDescription, ControlValueSetter, DeviceEvent,
+            FrameDropReason, FrameFormat, InterruptionReason, KnownCameraControl,
+            KnownCameraControlFlag, PermissionStatus, PhotoCodec, PhotoSettings, Resolution,
+            StreamEvent,
         },
     };
     use objc::runtime::objc_getClass;
@@ -240,15 +350,16 @@ mod internal {
     use std::{
         borrow::Cow,
         cmp::Ordering,
-        collections::BTreeMap,
         convert::TryFrom,
         error::Error,
-        ffi::{c_float, c_void, CStr},
-        sync::Arc,
+        ffi::{c_void, CStr},
+        sync::{Arc, Mutex},
+        time::Duration,
     };
 
     const UTF8_ENCODING: usize = 4;
-    type CGFloat = c_float;
+    // 64 bit only
+    type CGFloat = f64;
 
     macro_rules! create_boilerplate_impl {
         {
@@ -310,6 +421,27 @@ mod internal {
         }
     }
 
+    // Properties added in later OS versions, e.g. `isContinuityCamera`, raise an exception on older ones
+    fn responds_to(object: *mut Object, selector: Sel) -> bool {
+        let responds: BOOL = unsafe { msg_send![object, respondsToSelector: selector] };
+        responds == YES
+    }
+
+    #[repr(C)]
+    struct NSOperatingSystemVersion {
+        major: NSInteger,
+        minor: NSInteger,
+        patch: NSInteger,
+    }
+
+    // The major version of the running OS, e.g. 14 on macOS Sonoma and 17 on iOS 17
+    fn os_at_least(major: NSInteger) -> bool {
+        let process_info: *mut Object = unsafe { msg_send![class!(NSProcessInfo), processInfo] };
+        let version: NSOperatingSystemVersion =
+            unsafe { msg_send![process_info, operatingSystemVersion] };
+        version.major >= major
+    }
+
     fn nsstr_to_str<'a>(nsstr: *mut Object) -> Cow<'a, str> {
         let data = unsafe { CStr::from_ptr(nsstr.UTF8String()) };
         data.to_string_lossy()
@@ -362,19 +494,487 @@ mod internal {
     #[allow(non_upper_case_globals)]
     fn raw_fcc_to_frameformat(raw: OSType) -> Option<FrameFormat> {
         match raw {
-            kCMVideoCodecType_422YpCbCr8 | kCMPixelFormat_422YpCbCr8_yuvs => {
-                Some(FrameFormat::YUYV)
+            // '2vuy' is UYVY, 'yuvs' is YUYV
+            kCMVideoCodecType_422YpCbCr8 => Some(FrameFormat::Uyv422),
+            kCMPixelFormat_422YpCbCr8_yuvs | kCVPixelFormatType_422YpCbCr8FullRange => {
+                Some(FrameFormat::Yuv422)
             }
-            kCMVideoCodecType_JPEG | kCMVideoCodecType_JPEG_OpenDML => Some(FrameFormat::MJPEG),
-            kCMPixelFormat_8IndexedGray_WhiteIsZero => Some(FrameFormat::GRAY),
+            // 10 bit formats of HDMI capture cards
+            kCVPixelFormatType_422YpCbCr10 => Some(FrameFormat::V210),
+            kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange
+            | kCVPixelFormatType_422YpCbCr10BiPlanarFullRange => Some(FrameFormat::P210),
             kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange
-            | kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
-            | 875704438 => Some(FrameFormat::NV12),
-            kCMPixelFormat_24RGB => Some(FrameFormat::RAWRGB),
+            | kCVPixelFormatType_420YpCbCr10BiPlanarFullRange => Some(FrameFormat::P010),
+            // high bit depth formats of professional capture hardware
+            kCVPixelFormatType_422YpCbCr16 => Some(FrameFormat::V216),
+            kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange
+            | kCVPixelFormatType_444YpCbCr10BiPlanarFullRange => Some(FrameFormat::P410),
+            kCMVideoCodecType_AppleProRes4444
+            | kCMVideoCodecType_AppleProRes422HQ
+            | kCMVideoCodecType_AppleProRes422
+            | kCMVideoCodecType_AppleProRes422LT
+            | kCMVideoCodecType_AppleProRes422Proxy => Some(FrameFormat::ProRes),
+            kCMVideoCodecType_JPEG | kCMVideoCodecType_JPEG_OpenDML => Some(FrameFormat::MJpeg),
+            kCMPixelFormat_8IndexedGray_WhiteIsZero => Some(FrameFormat::Luma8),
+            // '420v' and '420f', which most cameras deliver natively
+            kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+            | kCVPixelFormatType_420YpCbCr8BiPlanarFullRange => Some(FrameFormat::Nv12),
+            kCMPixelFormat_24RGB => Some(FrameFormat::Rgb8),
+            _ => None,
+        }
+    }
+
+    #[allow(non_upper_case_globals)]
+    fn raw_fcc_to_color_range(raw: OSType) -> Option<ColorRange> {
+        match raw {
+            kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+            | kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange
+            | kCVPixelFormatType_422YpCbCr10BiPlanarVideoRange
+            | kCVPixelFormatType_444YpCbCr10BiPlanarVideoRange => Some(ColorRange::Limited),
+            kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+            | kCVPixelFormatType_420YpCbCr10BiPlanarFullRange
+            | kCVPixelFormatType_422YpCbCr10BiPlanarFullRange
+            | kCVPixelFormatType_444YpCbCr10BiPlanarFullRange
+            | kCVPixelFormatType_422YpCbCr8FullRange => Some(ColorRange::Full),
             _ => None,
         }
     }
 
+    // Modes are `NSInteger` enums counting up from 0, e.g. `AVCaptureFocusMode`
+    fn supported_modes(count: NSInteger, is_supported: impl Fn(NSInteger) -> BOOL) -> Vec<i64> {
+        (0..count)
+            .filter(|mode| is_supported(*mode) == YES)
+            .map(|mode| mode as i64)
+            .collect()
+    }
+
+    // Locked is 0 in all modes and custom exposure is 3
+    fn mode_flags(mode: NSInteger, supported: bool) -> Vec<KnownCameraControlFlag> {
+        let mut flags = vec![match mode {
+            0 | 3 => KnownCameraControlFlag::Manual,
+            1 => KnownCameraControlFlag::Automatic,
+            _ => KnownCameraControlFlag::Continuous,
+        }];
+        if mode != 0 && mode != 3 {
+            flags.push(KnownCameraControlFlag::Volatile);
+        }
+        flags.extend(support_flags(supported));
+        flags
+    }
+
+    fn support_flags(supported: bool) -> Vec<KnownCameraControlFlag> {
+        if supported {
+            vec![]
+        } else {
+            vec![
+                KnownCameraControlFlag::Disabled,
+                KnownCameraControlFlag::ReadOnly,
+            ]
+        }
+    }
+
+    fn cmtime_to_duration(time: CMTime) -> Option<Duration> {
+        if time.flags & kCMTimeFlags_Valid == 0 {
+            return None;
+        }
+        let seconds = unsafe { CMTimeGetSeconds(time) };
+        (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+    }
+
+    fn cmtime_to_micros(time: CMTime) -> i64 {
+        if time.timescale == 0 {
+            return 0;
+        }
+        (i128::from(time.value) * 1_000_000 / i128::from(time.timescale)) as i64
+    }
+
+    /// A retained `CVPixelBuffer` of a captured frame, which can be handed to Metal, `CoreImage` or `VideoToolbox` without copying it.
+    ///
+    /// The buffer comes from the capture pipeline's pool, which has only a few of them, so the camera drops frames while too many are held.
+    #[derive(Debug)]
+    pub struct PixelBuffer {
+        inner: CVPixelBufferRef,
+    }
+
+    // SAFETY: CVPixelBuffers are reference counted CF objects that may be retained, read and released from any thread
+    unsafe impl Send for PixelBuffer {}
+    unsafe impl Sync for PixelBuffer {}
+
+    impl PixelBuffer {
+        fn retain(pixel_buffer: CVPixelBufferRef) -> Self {
+            PixelBuffer {
+                inner: unsafe { CVPixelBufferRetain(pixel_buffer) },
+            }
+        }
+
+        /// Gets the `CVPixelBufferRef`, which stays valid while this is alive.
+        pub fn as_raw(&self) -> CVPixelBufferRef {
+            self.inner
+        }
+
+        /// Gets the `IOSurfaceRef` backing the buffer, if it has one. Camera buffers usually do, which lets Metal use them as textures.
+        pub fn io_surface(&self) -> Option<IOSurfaceRef> {
+            let surface = unsafe { CVPixelBufferGetIOSurface(self.inner) };
+            (!surface.is_null()).then_some(surface)
+        }
+
+        pub fn resolution(&self) -> Resolution {
+            Resolution::new(
+                unsafe { CVPixelBufferGetWidth(self.inner) } as u32,
+                unsafe { CVPixelBufferGetHeight(self.inner) } as u32,
+            )
+        }
+
+        pub fn pixel_format(&self) -> OSType {
+            unsafe { CVPixelBufferGetPixelFormatType(self.inner) }
+        }
+
+        /// Copies the samples to the CPU, with the planes of biplanar formats back to back.
+        pub fn copy_planes(&self) -> (Vec<u8>, Vec<BufferPlane>) {
+            let pixel_buffer = self.inner;
+            unsafe {
+                CVPixelBufferLockBaseAddress(pixel_buffer, kCVPixelBufferLock_ReadOnly);
+            };
+            let mut data = vec![];
+            let mut planes = vec![];
+            if unsafe { CVPixelBufferIsPlanar(pixel_buffer) } != 0 {
+                // the base address of a planar buffer points at a plane description, not at the samples
+                for plane in 0..unsafe { CVPixelBufferGetPlaneCount(pixel_buffer) } {
+                    let base = unsafe { CVPixelBufferGetBaseAddressOfPlane(pixel_buffer, plane) };
+                    if base.is_null() {
+                        continue;
+                    }
+                    let stride = unsafe { CVPixelBufferGetBytesPerRowOfPlane(pixel_buffer, plane) };
+                    let length =
+                        stride * unsafe { CVPixelBufferGetHeightOfPlane(pixel_buffer, plane) };
+                    planes.push(BufferPlane::new(data.len(), length).with_stride(stride));
+                    data.extend_from_slice(unsafe {
+                        std::slice::from_raw_parts(base.cast::<u8>(), length)
+                    });
+                }
+            } else {
+                let length = unsafe { CVPixelBufferGetDataSize(pixel_buffer) } as usize;
+                let base = unsafe { CVPixelBufferGetBaseAddress(pixel_buffer) };
+                if !base.is_null() {
+                    let stride = unsafe { CVPixelBufferGetBytesPerRow(pixel_buffer) };
+                    planes.push(BufferPlane::new(0, length).with_stride(stride));
+                    data.extend_from_slice(unsafe {
+                        std::slice::from_raw_parts(base.cast::<u8>(), length)
+                    });
+                }
+            }
+            unsafe { CVPixelBufferUnlockBaseAddress(pixel_buffer, kCVPixelBufferLock_ReadOnly) };
+            (data, planes)
+        }
+    }
+
+    impl Clone for PixelBuffer {
+        fn clone(&self) -> Self {
+            PixelBuffer::retain(self.inner)
+        }
+    }
+
+    impl Drop for PixelBuffer {
+        fn drop(&mut self) {
+            unsafe { CVPixelBufferRelease(self.inner) };
+        }
+    }
+
+    /// A frame `AVFoundation` delivered. Uncompressed frames keep their [`PixelBuffer`] and are only copied to the CPU when asked to,
+    /// compressed frames are copied out of their sample buffer right away.
+    #[derive(Clone, Debug)]
+    pub struct CapturedFrame {
+        contents: FrameContents,
+        pixel_format: OSType,
+        presentation_time: CMTime,
+        duration: CMTime,
+        orientation: Option<FrameOrientation>,
+        sequence: Option<u64>,
+    }
+
+    #[derive(Clone, Debug)]
+    enum FrameContents {
+        PixelBuffer(PixelBuffer),
+        Copied(Vec<u8>),
+    }
+
+    impl CapturedFrame {
+        fn from_sample_buffer(sample_buffer: CMSampleBufferRef, connection: *mut Object) -> Self {
+            let orientation = connection_orientation(connection);
+            let presentation_time =
+                unsafe { CMSampleBufferGetPresentationTimeStamp(sample_buffer) };
+            let duration = unsafe { CMSampleBufferGetDuration(sample_buffer) };
+            let image_buffer: CVImageBufferRef =
+                unsafe { CMSampleBufferGetImageBuffer(sample_buffer) };
+            if !image_buffer.is_null() {
+                let pixel_buffer = PixelBuffer::retain(image_buffer);
+                return CapturedFrame {
+                    pixel_format: pixel_buffer.pixel_format(),
+                    contents: FrameContents::PixelBuffer(pixel_buffer),
+                    presentation_time,
+                    duration,
+                    orientation,
+                    sequence: None,
+                };
+            }
+
+            let block_buffer = unsafe { CMSampleBufferGetDataBuffer(sample_buffer) };
+            let length = unsafe { CMBlockBufferGetDataLength(block_buffer) }.max(0) as usize;
+            let mut data = vec![0_u8; length];
+            unsafe {
+                CMBlockBufferCopyDataBytes(block_buffer, 0, length, data.as_mut_ptr().cast());
+            }
+            let format_description = unsafe { CMSampleBufferGetFormatDescription(sample_buffer) };
+            CapturedFrame {
+                contents: FrameContents::Copied(data),
+                pixel_format: unsafe { CMFormatDescriptionGetMediaSubType(format_description) },
+                presentation_time,
+                duration,
+                orientation,
+                sequence: None,
+            }
+        }
+
+        /// The time the frame was captured at, converted from `clock` (the session's, see [`AVCaptureSession::clock()`]) to the host
+        /// time clock, with its duration. `None` if the frame has no valid presentation time.
+        pub fn timestamp(&self, clock: CMClockRef) -> Option<FrameTimestamp> {
+            if self.presentation_time.flags & kCMTimeFlags_Valid == 0 {
+                return None;
+            }
+            let host_time = unsafe {
+                CMSyncConvertTime(self.presentation_time, clock, CMClockGetHostTimeClock())
+            };
+            // the host time clock is `mach_absolute_time()`, which `Instant` uses too
+            let timestamp =
+                FrameTimestamp::new(cmtime_to_duration(host_time)?, TimestampClock::Monotonic);
+            Some(match cmtime_to_duration(self.duration) {
+                Some(duration) => timestamp.with_duration(duration),
+                None => timestamp,
+            })
+        }
+
+        /// The number of the frame in its stream, counting dropped frames too. `None` for photos.
+        pub fn sequence(&self) -> Option<u64> {
+            self.sequence
+        }
+
+        /// How the frame is oriented, as its connection delivered it. `None` if the connection was not known.
+        pub fn orientation(&self) -> Option<FrameOrientation> {
+            self.orientation
+        }
+
+        /// The [`PixelBuffer`] of the frame, `None` for compressed formats.
+        pub fn pixel_buffer(&self) -> Option<&PixelBuffer> {
+            match &self.contents {
+                FrameContents::PixelBuffer(pixel_buffer) => Some(pixel_buffer),
+                FrameContents::Copied(_) => None,
+            }
+        }
+
+        pub fn into_pixel_buffer(self) -> Option<PixelBuffer> {
+            match self.contents {
+                FrameContents::PixelBuffer(pixel_buffer) => Some(pixel_buffer),
+                FrameContents::Copied(_) => None,
+            }
+        }
+
+        /// The [`FrameFormat`] of the frame, if `nokhwa` knows its pixel format.
+        pub fn frame_format(&self) -> Option<FrameFormat> {
+            raw_fcc_to_frameformat(self.pixel_format)
+        }
+
+        /// The [`ColorRange`] of the frame's samples, if its pixel format says.
+        pub fn color_range(&self) -> Option<ColorRange> {
+            raw_fcc_to_color_range(self.pixel_format)
+        }
+
+        /// Copies the frame's samples to the CPU, with the planes of biplanar formats back to back.
+        pub fn into_data(self) -> Vec<u8> {
+            match self.contents {
+                FrameContents::PixelBuffer(pixel_buffer) => pixel_buffer.copy_planes().0,
+                FrameContents::Copied(data) => data,
+            }
+        }
+
+        /// Creates a [`Buffer`] of the frame, falling back to `frame_format` if the pixel format is unknown.
+        pub fn to_buffer(&self, resolution: Resolution, frame_format: FrameFormat) -> Buffer {
+            let (data, planes) = match &self.contents {
+                FrameContents::PixelBuffer(pixel_buffer) => pixel_buffer.copy_planes(),
+                FrameContents::Copied(data) => {
+                    (data.clone(), vec![BufferPlane::new(0, data.len())])
+                }
+            };
+            let plane_data = planes
+                .iter()
+                .filter_map(|plane| data.get(plane.offset()..plane.offset() + plane.length()))
+                .collect::<Vec<&[u8]>>();
+            let strides = planes
+                .iter()
+                .map(|plane| plane.stride().unwrap_or_default())
+                .collect::<Vec<usize>>();
+            let buffer = Buffer::with_planes(
+                resolution,
+                &plane_data,
+                self.frame_format().unwrap_or(frame_format),
+            )
+            .with_strides(&strides);
+            match self.color_range() {
+                Some(color_range) => buffer.with_color_range(color_range),
+                None => buffer,
+            }
+        }
+    }
+
+    // A connection rotates the buffers of a data output itself if it can, otherwise the rotation is only a hint for whoever displays them.
+    // `videoRotationAngle` replaces `videoOrientation` from macOS 14.
+    fn connection_orientation(connection: *mut Object) -> Option<FrameOrientation> {
+        if connection.is_null() {
+            return None;
+        }
+        let mirrored: BOOL = unsafe { msg_send![connection, isVideoMirrored] };
+        let rotation = if responds_to(connection, sel!(videoRotationAngle)) {
+            let angle: CGFloat = unsafe { msg_send![connection, videoRotationAngle] };
+            let applied: BOOL =
+                unsafe { msg_send![connection, isVideoRotationAngleSupported: angle] };
+            if applied == YES {
+                0
+            } else {
+                (angle.round() as i64).rem_euclid(360) as u16
+            }
+        } else {
+            let applied: BOOL = unsafe { msg_send![connection, isVideoOrientationSupported] };
+            // `AVCaptureVideoOrientation`, where landscape right is the sensor's own orientation
+            let orientation: NSInteger = unsafe { msg_send![connection, videoOrientation] };
+            match orientation {
+                _ if applied == YES => 0,
+                1 => 90,
+                2 => 270,
+                4 => 180,
+                _ => 0,
+            }
+        };
+        Some(FrameOrientation::new(rotation, mirrored == YES))
+    }
+
+    /// A still photo `AVCapturePhotoOutput` delivered. Uncompressed photos keep their [`PixelBuffer`], encoded ones are copied out of
+    /// their file data.
+    #[derive(Clone, Debug)]
+    pub struct CapturedPhoto {
+        frame: CapturedFrame,
+        resolution: Resolution,
+    }
+
+    impl CapturedPhoto {
+        fn from_photo(photo: *mut Object) -> Self {
+            let presentation_time: CMTime = unsafe { msg_send![photo, timestamp] };
+            // a still has no duration
+            let duration = unsafe { kCMTimeInvalid };
+            let pixel_buffer: CVPixelBufferRef = unsafe { msg_send![photo, pixelBuffer] };
+            if !pixel_buffer.is_null() {
+                let pixel_buffer = PixelBuffer::retain(pixel_buffer);
+                return CapturedPhoto {
+                    resolution: pixel_buffer.resolution(),
+                    frame: CapturedFrame {
+                        pixel_format: pixel_buffer.pixel_format(),
+                        contents: FrameContents::PixelBuffer(pixel_buffer),
+                        presentation_time,
+                        duration,
+                        orientation: None,
+                        sequence: None,
+                    },
+                };
+            }
+
+            let file_data: *mut Object = unsafe { msg_send![photo, fileDataRepresentation] };
+            let data = if file_data.is_null() {
+                vec![]
+            } else {
+                let length: NSUInteger = unsafe { msg_send![file_data, length] };
+                let bytes: *const c_void = unsafe { msg_send![file_data, bytes] };
+                unsafe { std::slice::from_raw_parts(bytes.cast::<u8>(), length as usize) }.to_vec()
+            };
+            let resolved_settings: *mut Object = unsafe { msg_send![photo, resolvedSettings] };
+            let dimensions: CMVideoDimensions =
+                unsafe { msg_send![resolved_settings, photoDimensions] };
+            CapturedPhoto {
+                // the file data is a whole image file, which has no pixel format
+                frame: CapturedFrame {
+                    contents: FrameContents::Copied(data),
+                    pixel_format: 0,
+                    presentation_time,
+                    duration,
+                    orientation: None,
+                    sequence: None,
+                },
+                resolution: Resolution::new(dimensions.width as u32, dimensions.height as u32),
+            }
+        }
+
+        /// The [`CapturedFrame`] holding the photo.
+        pub fn frame(&self) -> &CapturedFrame {
+            &self.frame
+        }
+
+        pub fn resolution(&self) -> Resolution {
+            self.resolution
+        }
+
+        /// Creates a [`Buffer`] of the photo, falling back to `frame_format` if the pixel format is unknown, as it is for encoded photos.
+        pub fn to_buffer(&self, frame_format: FrameFormat) -> Buffer {
+            self.frame.to_buffer(self.resolution, frame_format)
+        }
+    }
+
+    pub type PhotoResult = Result<CapturedPhoto, NokhwaError>;
+
+    /// The callback [`StreamEvent`]s go to, shared by the video delegate and the [`AVCaptureSessionInterruptionWatcher`]. It must
+    /// outlive both.
+    pub type StreamEventCallback = Arc<Mutex<Option<Box<dyn FnMut(StreamEvent) + Send + 'static>>>>;
+
+    fn send_stream_event(
+        events: &Mutex<Option<Box<dyn FnMut(StreamEvent) + Send + 'static>>>,
+        event: StreamEvent,
+    ) {
+        if let Ok(mut callback) = events.lock() {
+            if let Some(callback) = callback.as_mut() {
+                callback(event);
+            }
+        }
+    }
+
+    fn dropped_frame_reason(sample_buffer: CMSampleBufferRef) -> FrameDropReason {
+        let reason = unsafe {
+            CMGetAttachment(
+                sample_buffer,
+                kCMSampleBufferAttachmentKey_DroppedFrameReason.clone().0,
+                std::ptr::null_mut(),
+            )
+        };
+        if reason.is_null() {
+            return FrameDropReason::Unknown;
+        }
+        unsafe {
+            if compare_ns_string(
+                reason,
+                kCMSampleBufferDroppedFrameReason_FrameWasLate.clone(),
+            ) {
+                FrameDropReason::Late
+            } else if compare_ns_string(
+                reason,
+                kCMSampleBufferDroppedFrameReason_OutOfBuffers.clone(),
+            ) {
+                FrameDropReason::OutOfBuffers
+            } else if compare_ns_string(
+                reason,
+                kCMSampleBufferDroppedFrameReason_Discontinuity.clone(),
+            ) {
+                FrameDropReason::Discontinuity
+            } else {
+                FrameDropReason::Unknown
+            }
+        }
+    }
+
     pub type CompressionData<'a> = (Cow<'a, [u8]>, FrameFormat);
     pub type DataPipe<'a> = (Sender<CompressionData<'a>>, Receiver<CompressionData<'a>>);
 
@@ -385,6 +985,8 @@ mod internal {
             // frame stack
             // oooh scary provenannce-breaking BULLSHIT AAAAAA I LOVE TYPE ERASURE
             decl.add_ivar::<*const c_void>("_arcmutptr"); // ArkMutex, the not-arknights totally not gacha totally not ripoff new vidya game from l-pleasestop-npengtul
+            decl.add_ivar::<*const c_void>("_eventsptr");
+            decl.add_ivar::<*const c_void>("_counterptr");
 
             extern "C" fn my_callback_get_arcmutptr(this: &Object, _: Sel) -> *const c_void {
                 unsafe { *this.get_ivar("_arcmutptr") }
@@ -408,45 +1010,83 @@ mod internal {
                 _: Sel,
                 _: *mut Object,
                 didOutputSampleBuffer: CMSampleBufferRef,
-                _: *mut Object,
+                fromConnection: *mut Object,
             ) {
-                let image_buffer: CVImageBufferRef =
-                    unsafe { CMSampleBufferGetImageBuffer(didOutputSampleBuffer) };
-                unsafe {
-                    CVPixelBufferLockBaseAddress(image_buffer, 0);
-                };
-
-                let buffer_length = unsafe { CVPixelBufferGetDataSize(image_buffer) };
-                let buffer_ptr = unsafe { CVPixelBufferGetBaseAddress(image_buffer) };
-                let buffer_as_vec = unsafe {
-                    std::slice::from_raw_parts_mut(buffer_ptr as *mut u8, buffer_length as usize)
-                        .to_vec()
-                };
-
-                unsafe { CVPixelBufferUnlockBaseAddress(image_buffer, 0) };
+                let mut frame =
+                    CapturedFrame::from_sample_buffer(didOutputSampleBuffer, fromConnection);
+                if let Some(counter) = frame_counter(this) {
+                    frame.sequence = counter.lock().ok().map(|mut counter| counter.next_sequence());
+                }
                 // oooooh scarey unsafe
                 // AAAAAAAAAAAAAAAAAAAAAAAAA
                 // https://c.tenor.com/0e_zWtFLOzQAAAAC/needy-streamer-overload-needy-girl-overdose.gif
                 let bufferlck_cv: *const c_void = unsafe { msg_send![this, bufferPtr] };
                 let buffer_sndr = unsafe {
-                    let ptr = bufferlck_cv.cast::<Sender<(Vec<u8>, FrameFormat)>>();
+                    let ptr = bufferlck_cv.cast::<Sender<CapturedFrame>>();
                     Arc::from_raw(ptr)
                 };
-                if let Err(_) = buffer_sndr.send((buffer_as_vec, FrameFormat::GRAY)) {
+                if let Err(_) = buffer_sndr.send(frame) {
                     // FIXME: dont, what the fuck???
                     return;
                 }
                 std::mem::forget(buffer_sndr);
             }
 
+            extern "C" fn my_callback_get_counterptr(this: &Object, _: Sel) -> *const c_void {
+                unsafe { *this.get_ivar("_counterptr") }
+            }
+            extern "C" fn my_callback_set_counterptr(
+                this: &mut Object,
+                _: Sel,
+                new_counterptr: *const c_void,
+            ) {
+                unsafe {
+                    this.set_ivar("_counterptr", new_counterptr);
+                }
+            }
+
+            fn frame_counter(this: &Object) -> Option<&Mutex<FrameCounter>> {
+                let counter: *const c_void = unsafe { msg_send![this, counterPtr] };
+                // SAFETY: the owning AVCaptureVideoCallback keeps the counter alive as long as the delegate
+                unsafe { counter.cast::<Mutex<FrameCounter>>().as_ref() }
+            }
+
+            extern "C" fn my_callback_get_eventsptr(this: &Object, _: Sel) -> *const c_void {
+                unsafe { *this.get_ivar("_eventsptr") }
+            }
+            extern "C" fn my_callback_set_eventsptr(
+                this: &mut Object,
+                _: Sel,
+                new_eventsptr: *const c_void,
+            ) {
+                unsafe {
+                    this.set_ivar("_eventsptr", new_eventsptr);
+                }
+            }
+
             #[allow(non_snake_case)]
             extern "C" fn capture_drop_callback(
-                _: &mut Object,
+                this: &mut Object,
                 _: Sel,
                 _: *mut Object,
-                _: *mut Object,
+                didDropSampleBuffer: CMSampleBufferRef,
                 _: *mut Object,
             ) {
+                if let Some(Ok(mut counter)) = frame_counter(this).map(Mutex::lock) {
+                    counter.record_drops(1);
+                }
+                let events: *const c_void = unsafe { msg_send![this, eventsPtr] };
+                if events.is_null() {
+                    return;
+                }
+                // SAFETY: the owner of the delegate keeps the callback alive until the delegate is detached
+                let events = unsafe {
+                    &*events.cast::<Mutex<Option<Box<dyn FnMut(StreamEvent) + Send + 'static>>>>()
+                };
+                send_stream_event(
+                    events,
+                    StreamEvent::FrameDropped(dropped_frame_reason(didDropSampleBuffer)),
+                );
             }
 
             unsafe {
@@ -469,10 +1109,32 @@ mod internal {
                             *mut Object,
                         ),
                 );
+                decl.add_method(
+                    sel!(eventsPtr),
+                    my_callback_get_eventsptr as extern "C" fn(&Object, Sel) -> *const c_void,
+                );
+                decl.add_method(
+                    sel!(SetEventsPtr:),
+                    my_callback_set_eventsptr as extern "C" fn(&mut Object, Sel, *const c_void),
+                );
+                decl.add_method(
+                    sel!(counterPtr),
+                    my_callback_get_counterptr as extern "C" fn(&Object, Sel) -> *const c_void,
+                );
+                decl.add_method(
+                    sel!(SetCounterPtr:),
+                    my_callback_set_counterptr as extern "C" fn(&mut Object, Sel, *const c_void),
+                );
                 decl.add_method(
                     sel!(captureOutput:didDropSampleBuffer:fromConnection:),
                     capture_drop_callback
-                        as extern "C" fn(&mut Object, Sel, *mut Object, *mut Object, *mut Object),
+                        as extern "C" fn(
+                            &mut Object,
+                            Sel,
+                            *mut Object,
+                            CMSampleBufferRef,
+                            *mut Object,
+                        ),
                 );
 
                 decl.add_protocol(
@@ -484,6 +1146,68 @@ mod internal {
         }
     });
 
+    static PHOTO_CALLBACK_CLASS: Lazy<&'static Class> = Lazy::new(|| {
+        let mut decl = ClassDecl::new("MyPhotoCaptureCallback", class!(NSObject)).unwrap();
+
+        decl.add_ivar::<*const c_void>("_senderptr");
+
+        extern "C" fn photo_callback_get_senderptr(this: &Object, _: Sel) -> *const c_void {
+            unsafe { *this.get_ivar("_senderptr") }
+        }
+        extern "C" fn photo_callback_set_senderptr(
+            this: &mut Object,
+            _: Sel,
+            new_senderptr: *const c_void,
+        ) {
+            unsafe {
+                this.set_ivar("_senderptr", new_senderptr);
+            }
+        }
+
+        #[allow(non_snake_case)]
+        extern "C" fn photo_out_callback(
+            this: &mut Object,
+            _: Sel,
+            _: *mut Object,
+            didFinishProcessingPhoto: *mut Object,
+            error: *mut Object,
+        ) {
+            let result = if error.is_null() {
+                Ok(CapturedPhoto::from_photo(didFinishProcessingPhoto))
+            } else {
+                let description: *mut Object = unsafe { msg_send![error, localizedDescription] };
+                Err(NokhwaError::ReadFrameError(
+                    nsstr_to_str(description).to_string(),
+                ))
+            };
+            let sender_ptr: *const c_void = unsafe { msg_send![this, senderPtr] };
+            // SAFETY: the sender outlives the delegate, see `AVCapturePhotoCallback::new()`
+            let sender = unsafe { &*sender_ptr.cast::<Sender<PhotoResult>>() };
+            // nobody is waiting for the photo anymore
+            let _ = sender.send(result);
+        }
+
+        unsafe {
+            decl.add_method(
+                sel!(senderPtr),
+                photo_callback_get_senderptr as extern "C" fn(&Object, Sel) -> *const c_void,
+            );
+            decl.add_method(
+                sel!(setSenderPtr:),
+                photo_callback_set_senderptr as extern "C" fn(&mut Object, Sel, *const c_void),
+            );
+            decl.add_method(
+                sel!(captureOutput:didFinishProcessingPhoto:error:),
+                photo_out_callback
+                    as extern "C" fn(&mut Object, Sel, *mut Object, *mut Object, *mut Object),
+            );
+
+            decl.add_protocol(Protocol::get("AVCapturePhotoCaptureDelegate").unwrap());
+        }
+
+        decl.register()
+    });
+
     pub fn request_permission_with_callback(callback: impl Fn(bool) + Send + Sync + 'static) {
         let cls = class!(AVCaptureDevice);
 
@@ -507,17 +1231,81 @@ mod internal {
         status
     }
 
+    /// The [`PermissionStatus`] of the camera, without asking the user. A [`PermissionStatus::Restricted`] status is set by a device
+    /// management profile or parental controls, and asking the user does not change it.
+    pub fn authorization_status() -> PermissionStatus {
+        match current_authorization_status() {
+            AVAuthorizationStatus::NotDetermined => PermissionStatus::NotDetermined,
+            AVAuthorizationStatus::Restricted => PermissionStatus::Restricted,
+            AVAuthorizationStatus::Denied => PermissionStatus::Denied,
+            AVAuthorizationStatus::Authorized => PermissionStatus::Granted,
+        }
+    }
+
+    /// Asks the user for access to the camera, resolving once they answer. Only the first request shows a prompt, later ones resolve
+    /// at once with the earlier answer.
+    /// # Errors
+    /// If access is denied or restricted, this will error.
+    pub async fn request_access() -> Result<(), NokhwaError> {
+        let status = match authorization_status() {
+            PermissionStatus::NotDetermined => {
+                let (sender, receiver) = flume::bounded(1);
+                request_permission_with_callback(move |granted| {
+                    let _ = sender.send(granted);
+                });
+                match receiver.recv_async().await {
+                    Ok(true) => PermissionStatus::Granted,
+                    // the answer may also be a restriction that was put in place while asking
+                    _ => authorization_status(),
+                }
+            }
+            status => status,
+        };
+        let error = match status {
+            PermissionStatus::Granted => return Ok(()),
+            PermissionStatus::Restricted => {
+                "Camera access is restricted by a device management profile or parental controls"
+            }
+            PermissionStatus::Denied | PermissionStatus::NotDetermined => {
+                "Camera access was denied"
+            }
+        };
+        Err(NokhwaError::InitializeError {
+            backend: ApiBackend::AVFoundation,
+            error: error.to_string(),
+        })
+    }
+
     // fuck it, use deprecated APIs
     pub fn query_avfoundation() -> Result<Vec<CameraInfo>, NokhwaError> {
-        Ok(AVCaptureDeviceDiscoverySession::new(vec![
+        let mut device_types = vec![
             AVCaptureDeviceType::UltraWide,
             AVCaptureDeviceType::WideAngle,
             AVCaptureDeviceType::Telephoto,
             AVCaptureDeviceType::TrueDepth,
-            AVCaptureDeviceType::ExternalUnknown,
-        ])?
-        .devices())
-    }
+        ];
+        // iPads take USB cameras from iPadOS 17, and unknown device types raise an exception
+        if cfg!(target_os = "ios") {
+            if os_at_least(17) {
+                device_types.push(AVCaptureDeviceType::External);
+            }
+        // Continuity Cameras are external devices until macOS 14 gives them their own type
+        } else if os_at_least(14) {
+            device_types.extend([
+                AVCaptureDeviceType::External,
+                AVCaptureDeviceType::ContinuityCamera,
+                AVCaptureDeviceType::DeskView,
+            ]);
+        } else if os_at_least(13) {
+            device_types.extend([
+                AVCaptureDeviceType::ExternalUnknown,
+                AVCaptureDeviceType::DeskView,
+            ]);
+        } else {
+            device_types.push(AVCaptureDeviceType::ExternalUnknown);
+        }
+        Ok(AVCaptureDeviceDiscoverySession::new(device_types)?.devices())
+    }
 
     pub fn get_raw_device_info(index: CameraIndex, device: *mut Object) -> CameraInfo {
         let name = nsstr_to_str(unsafe { msg_send![device, localizedName] });
@@ -526,13 +1314,156 @@ mod internal {
         let lens_aperture: f64 = unsafe { msg_send![device, lensAperture] };
         let device_type = nsstr_to_str(unsafe { msg_send![device, deviceType] });
         let model_id = nsstr_to_str(unsafe { msg_send![device, modelID] });
-        let description = format!(
+        let mut description = format!(
             "{}: {} - {}, {:?} f{}",
             manufacturer, model_id, device_type, position, lens_aperture
         );
+        if is_continuity_camera(device) {
+            description.push_str(", Continuity Camera");
+        }
         let misc = nsstr_to_str(unsafe { msg_send![device, uniqueID] });
+        let camera_position = match position {
+            AVCaptureDevicePosition::Front => CameraPosition::Front,
+            AVCaptureDevicePosition::Back => CameraPosition::Back,
+            // Continuity Cameras report the position of the camera on the phone, not the one relative to the user
+            AVCaptureDevicePosition::Unspecified
+                if device_type.contains("External") || is_continuity_camera(device) =>
+            {
+                CameraPosition::External
+            }
+            AVCaptureDevicePosition::Unspecified => CameraPosition::Unknown,
+        };
+
+        let mut info = CameraInfo::new(name.as_ref(), &description, misc.as_ref(), index)
+            .with_position(camera_position);
+        if let Some(id) = desk_view_id(device) {
+            info = info.with_related_device(CameraIndex::String(id));
+        }
+        if let Some(audio_device) = linked_audio_device(device) {
+            info = info.with_audio_device(audio_device);
+        }
+        info
+    }
+
+    // camcorders and some webcams link their microphone, iOS has no linked devices
+    fn linked_audio_device(device: *mut Object) -> Option<AudioDeviceInfo> {
+        if !responds_to(device, sel!(linkedDevices)) {
+            return None;
+        }
+        let linked: *mut Object = unsafe { msg_send![device, linkedDevices] };
+        if linked.is_null() {
+            return None;
+        }
+        ns_arr_to_vec::<*mut Object>(linked)
+            .into_iter()
+            .find(|linked| {
+                let audio: BOOL =
+                    unsafe { msg_send![*linked, hasMediaType: AVMediaTypeAudio.clone().0] };
+                audio == YES
+            })
+            .map(|microphone| {
+                let name = nsstr_to_str(unsafe { msg_send![microphone, localizedName] });
+                let id = nsstr_to_str(unsafe { msg_send![microphone, uniqueID] });
+                AudioDeviceInfo::new(name.as_ref(), id.as_ref())
+            })
+    }
+
+    fn desk_view_id(device: *mut Object) -> Option<String> {
+        if !responds_to(device, sel!(companionDeskViewCamera)) {
+            return None;
+        }
+        let desk_view: *mut Object = unsafe { msg_send![device, companionDeskViewCamera] };
+        if desk_view.is_null() {
+            return None;
+        }
+        Some(nsstr_to_str(unsafe { msg_send![desk_view, uniqueID] }).to_string())
+    }
+
+    fn is_continuity_camera(device: *mut Object) -> bool {
+        if !responds_to(device, sel!(isContinuityCamera)) {
+            return false;
+        }
+        let continuity: BOOL = unsafe { msg_send![device, isContinuityCamera] };
+        continuity == YES
+    }
+
+    fn has_video(device: *mut Object) -> bool {
+        let video: BOOL = unsafe { msg_send![device, hasMediaType: AVMediaTypeVideo.clone().0] };
+        video == YES
+    }
+
+    /// Watches for video devices being connected or disconnected, through `AVCaptureDeviceWasConnectedNotification` and
+    /// `AVCaptureDeviceWasDisconnectedNotification`. Watching stops on drop.
+    pub struct AVCaptureDeviceWatcher {
+        queue: *mut Object,
+        observers: Vec<*mut Object>,
+    }
+
+    // SAFETY: the notification center and operation queues may be used from any thread
+    unsafe impl Send for AVCaptureDeviceWatcher {}
+
+    impl AVCaptureDeviceWatcher {
+        /// Starts calling `callback` from a queue of its own, so events do not wait for the main thread.
+        pub fn new(callback: impl FnMut(DeviceEvent) + Send + 'static) -> Self {
+            let callback = Arc::new(Mutex::new(callback));
+            let center: *mut Object =
+                unsafe { msg_send![class!(NSNotificationCenter), defaultCenter] };
+            let queue: *mut Object = unsafe { msg_send![class!(NSOperationQueue), new] };
+
+            let notifications = unsafe {
+                [
+                    (AVCaptureDeviceWasConnectedNotification.clone(), true),
+                    (AVCaptureDeviceWasDisconnectedNotification.clone(), false),
+                ]
+            };
+            let observers = notifications
+                .into_iter()
+                .map(|(name, connected)| {
+                    let callback = callback.clone();
+                    let handler = move |notification: *mut Object| {
+                        let device: *mut Object = unsafe { msg_send![notification, object] };
+                        if device.is_null() || !has_video(device) {
+                            return;
+                        }
+                        // indices shift as devices come and go, so events carry the unique ID
+                        let id = nsstr_to_str(unsafe { msg_send![device, uniqueID] });
+                        let info = get_raw_device_info(CameraIndex::String(id.to_string()), device);
+                        let event = if connected {
+                            DeviceEvent::Connected(info)
+                        } else {
+                            DeviceEvent::Disconnected(info)
+                        };
+                        if let Ok(mut callback) = callback.lock() {
+                            callback(event);
+                        }
+                    };
+                    let block: ConcreteBlock<(*mut Object,), (), _> = ConcreteBlock::new(handler);
+                    let block = block.copy();
+                    let observer: *mut Object = unsafe {
+                        msg_send![center, addObserverForName:name.0 object:Nil queue:queue usingBlock:block]
+                    };
+                    observer
+                })
+                .collect();
 
-        CameraInfo::new(name.as_ref(), &description, misc.as_ref(), index)
+            AVCaptureDeviceWatcher { queue, observers }
+        }
+    }
+
+    impl Drop for AVCaptureDeviceWatcher {
+        fn drop(&mut self) {
+            let center: *mut Object =
+                unsafe { msg_send![class!(NSNotificationCenter), defaultCenter] };
+            for observer in self.observers.drain(..) {
+                let _: () = unsafe { msg_send![center, removeObserver: observer] };
+            }
+            // let callbacks that already started finish, so none run after drop, unless this is one of them
+            let current: *mut Object = unsafe { msg_send![class!(NSOperationQueue), currentQueue] };
+            if current != self.queue {
+                let _: () = unsafe { msg_send![self.queue, waitUntilAllOperationsAreFinished] };
+            }
+            let _: () = unsafe { msg_send![self.queue, release] };
+        }
     }
 
     #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
@@ -545,6 +1476,12 @@ mod internal {
         Telephoto,
         TrueDepth,
         ExternalUnknown,
+        /// External cameras, macOS 14 and up.
+        External,
+        /// iPhones used as a camera, macOS 14 and up.
+        ContinuityCamera,
+        /// The top-down Desk View of a Continuity Camera, macOS 13 and up.
+        DeskView,
     }
 
     impl From<AVCaptureDeviceType> for *mut Object {
@@ -572,6 +1509,11 @@ mod internal {
                 AVCaptureDeviceType::ExternalUnknown => {
                     str_to_nsstr("AVCaptureDeviceTypeExternalUnknown")
                 }
+                AVCaptureDeviceType::External => str_to_nsstr("AVCaptureDeviceTypeExternal"),
+                AVCaptureDeviceType::ContinuityCamera => {
+                    str_to_nsstr("AVCaptureDeviceTypeContinuityCamera")
+                }
+                AVCaptureDeviceType::DeskView => str_to_nsstr("AVCaptureDeviceTypeDeskViewCamera"),
             }
         }
     }
@@ -655,6 +1597,207 @@ mod internal {
         }
     }
 
+    /// The quality an [`AVCaptureSession`] captures at, which picks the device's format for it. Setting the device's format itself
+    /// switches the session to [`InputPriority`](AVCaptureSessionPreset::InputPriority).
+    #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+    pub enum AVCaptureSessionPreset {
+        High,
+        Medium,
+        Low,
+        Photo,
+        InputPriority,
+        Vga640x480,
+        Hd1280x720,
+        Hd1920x1080,
+        Hd4K3840x2160,
+    }
+
+    impl From<AVCaptureSessionPreset> for *mut Object {
+        fn from(preset: AVCaptureSessionPreset) -> Self {
+            str_to_nsstr(match preset {
+                AVCaptureSessionPreset::High => "AVCaptureSessionPresetHigh",
+                AVCaptureSessionPreset::Medium => "AVCaptureSessionPresetMedium",
+                AVCaptureSessionPreset::Low => "AVCaptureSessionPresetLow",
+                AVCaptureSessionPreset::Photo => "AVCaptureSessionPresetPhoto",
+                AVCaptureSessionPreset::InputPriority => "AVCaptureSessionPresetInputPriority",
+                AVCaptureSessionPreset::Vga640x480 => "AVCaptureSessionPreset640x480",
+                AVCaptureSessionPreset::Hd1280x720 => "AVCaptureSessionPreset1280x720",
+                AVCaptureSessionPreset::Hd1920x1080 => "AVCaptureSessionPreset1920x1080",
+                AVCaptureSessionPreset::Hd4K3840x2160 => "AVCaptureSessionPreset3840x2160",
+            })
+        }
+    }
+
+    /// Why an [`AVCaptureSession`] stopped delivering frames for a while. The session resumes by itself once the interruption ends.
+    #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+    pub enum AVCaptureSessionInterruptionReason {
+        /// The app moved to the background, e.g. on iOS.
+        VideoDeviceNotAvailableInBackground,
+        AudioDeviceInUseByAnotherClient,
+        VideoDeviceInUseByAnotherClient,
+        /// Another app took the camera in Split View or Slide Over on iPadOS.
+        VideoDeviceNotAvailableWithMultipleForegroundApps,
+        /// The device is too hot or its battery too low.
+        VideoDeviceNotAvailableDueToSystemPressure,
+        /// The system did not say, as on macOS.
+        Unknown,
+    }
+
+    impl AVCaptureSessionInterruptionReason {
+        fn from_raw(reason: NSInteger) -> Self {
+            match reason {
+                1 => AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableInBackground,
+                2 => AVCaptureSessionInterruptionReason::AudioDeviceInUseByAnotherClient,
+                3 => AVCaptureSessionInterruptionReason::VideoDeviceInUseByAnotherClient,
+                4 => {
+                    AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableWithMultipleForegroundApps
+                }
+                5 => AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableDueToSystemPressure,
+                _ => AVCaptureSessionInterruptionReason::Unknown,
+            }
+        }
+    }
+
+    impl From<AVCaptureSessionInterruptionReason> for InterruptionReason {
+        fn from(reason: AVCaptureSessionInterruptionReason) -> Self {
+            match reason {
+                AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableInBackground => {
+                    InterruptionReason::InBackground
+                }
+                AVCaptureSessionInterruptionReason::AudioDeviceInUseByAnotherClient
+                | AVCaptureSessionInterruptionReason::VideoDeviceInUseByAnotherClient => {
+                    InterruptionReason::InUseByAnotherClient
+                }
+                AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableWithMultipleForegroundApps => {
+                    InterruptionReason::MultipleForegroundApps
+                }
+                AVCaptureSessionInterruptionReason::VideoDeviceNotAvailableDueToSystemPressure => {
+                    InterruptionReason::SystemPressure
+                }
+                AVCaptureSessionInterruptionReason::Unknown => InterruptionReason::Unknown,
+            }
+        }
+    }
+
+    // macOS gives no reason, but says whether another application holds a device
+    fn inputs_in_use_elsewhere(session: *mut Object) -> bool {
+        let inputs: *mut Object = unsafe { msg_send![session, inputs] };
+        ns_arr_to_vec::<*mut Object>(inputs)
+            .into_iter()
+            .any(|input| {
+                if !responds_to(input, sel!(device)) {
+                    return false;
+                }
+                let device: *mut Object = unsafe { msg_send![input, device] };
+                if !responds_to(device, sel!(isInUseByAnotherApplication)) {
+                    return false;
+                }
+                let in_use: BOOL = unsafe { msg_send![device, isInUseByAnotherApplication] };
+                in_use == YES
+            })
+    }
+
+    /// Keeps track of whether an [`AVCaptureSession`] is interrupted and why, through `AVCaptureSessionWasInterruptedNotification`
+    /// and `AVCaptureSessionInterruptionEndedNotification`, and sends each change as a [`StreamEvent`]. Watching stops on drop.
+    pub struct AVCaptureSessionInterruptionWatcher {
+        queue: *mut Object,
+        observers: Vec<*mut Object>,
+        interruption: Arc<Mutex<Option<AVCaptureSessionInterruptionReason>>>,
+    }
+
+    // SAFETY: the notification center and operation queues may be used from any thread
+    unsafe impl Send for AVCaptureSessionInterruptionWatcher {}
+
+    impl AVCaptureSessionInterruptionWatcher {
+        pub fn new(session: &AVCaptureSession, events: &StreamEventCallback) -> Self {
+            let interruption = Arc::new(Mutex::new(None));
+            let center: *mut Object =
+                unsafe { msg_send![class!(NSNotificationCenter), defaultCenter] };
+            let queue: *mut Object = unsafe { msg_send![class!(NSOperationQueue), new] };
+
+            let notifications = unsafe {
+                [
+                    (AVCaptureSessionWasInterruptedNotification.clone(), true),
+                    (AVCaptureSessionInterruptionEndedNotification.clone(), false),
+                ]
+            };
+            let observers = notifications
+                .into_iter()
+                .map(|(name, interrupted)| {
+                    let interruption = interruption.clone();
+                    let events = events.clone();
+                    let session = session.inner;
+                    let handler = move |notification: *mut Object| {
+                        let reason = interrupted.then(|| {
+                            // `AVCaptureSessionInterruptionReasonKey` only exists on iOS
+                            let user_info: *mut Object = unsafe { msg_send![notification, userInfo] };
+                            let reason: *mut Object = if user_info.is_null() {
+                                std::ptr::null_mut()
+                            } else {
+                                unsafe {
+                                    msg_send![user_info, objectForKey: str_to_nsstr("AVCaptureSessionInterruptionReasonKey")]
+                                }
+                            };
+                            if reason.is_null() {
+                                if inputs_in_use_elsewhere(session) {
+                                    AVCaptureSessionInterruptionReason::VideoDeviceInUseByAnotherClient
+                                } else {
+                                    AVCaptureSessionInterruptionReason::Unknown
+                                }
+                            } else {
+                                AVCaptureSessionInterruptionReason::from_raw(unsafe {
+                                    msg_send![reason, integerValue]
+                                })
+                            }
+                        });
+                        if let Ok(mut interruption) = interruption.lock() {
+                            *interruption = reason;
+                        }
+                        send_stream_event(
+                            &events,
+                            match reason {
+                                Some(reason) => StreamEvent::Interrupted(reason.into()),
+                                None => StreamEvent::InterruptionEnded,
+                            },
+                        );
+                    };
+                    let block: ConcreteBlock<(*mut Object,), (), _> = ConcreteBlock::new(handler);
+                    let block = block.copy();
+                    let observer: *mut Object = unsafe {
+                        msg_send![center, addObserverForName:name.0 object:session queue:queue usingBlock:block]
+                    };
+                    observer
+                })
+                .collect();
+
+            AVCaptureSessionInterruptionWatcher {
+                queue,
+                observers,
+                interruption,
+            }
+        }
+
+        /// Why the session is interrupted, `None` if it is not.
+        pub fn interruption(&self) -> Option<AVCaptureSessionInterruptionReason> {
+            self.interruption
+                .lock()
+                .ok()
+                .and_then(|interruption| *interruption)
+        }
+    }
+
+    impl Drop for AVCaptureSessionInterruptionWatcher {
+        fn drop(&mut self) {
+            let center: *mut Object =
+                unsafe { msg_send![class!(NSNotificationCenter), defaultCenter] };
+            for observer in self.observers.drain(..) {
+                let _: () = unsafe { msg_send![center, removeObserver: observer] };
+            }
+            let _: () = unsafe { msg_send![self.queue, waitUntilAllOperationsAreFinished] };
+            let _: () = unsafe { msg_send![self.queue, release] };
+        }
+    }
+
     #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
     #[repr(isize)]
     pub enum AVCaptureDevicePosition {
@@ -675,12 +1818,17 @@ mod internal {
     pub struct AVCaptureVideoCallback {
         delegate: *mut Object,
         queue: NSObject,
+        // numbers the frames and counts the dropped ones, the delegate points to it
+        counter: Arc<Mutex<FrameCounter>>,
     }
 
     impl AVCaptureVideoCallback {
+        /// Creates a delegate that sends frames to `buffer`, and the reasons of dropped frames to `events`. Frames are numbered
+        /// in the order the capture output delivered or dropped them.
         pub fn new(
             device_spec: &CStr,
-            buffer: &Arc<Sender<(Vec<u8>, FrameFormat)>>,
+            buffer: &Arc<Sender<CapturedFrame>>,
+            events: &StreamEventCallback,
         ) -> Result<Self, NokhwaError> {
             let cls = &CALLBACK_CLASS as &Class;
             let delegate: *mut Object = unsafe { msg_send![cls, alloc] };
@@ -689,15 +1837,29 @@ mod internal {
                 let arc_raw = Arc::as_ptr(buffer);
                 arc_raw.cast::<c_void>()
             };
+            let events_as_ptr = Arc::as_ptr(events).cast::<c_void>();
+            let counter = Arc::new(Mutex::new(FrameCounter::new()));
+            let counter_as_ptr = Arc::as_ptr(&counter).cast::<c_void>();
             unsafe {
                 let _: () = msg_send![delegate, SetBufferPtr: buffer_as_ptr];
+                let _: () = msg_send![delegate, SetEventsPtr: events_as_ptr];
+                let _: () = msg_send![delegate, SetCounterPtr: counter_as_ptr];
             }
 
             let queue = unsafe {
                 dispatch_queue_create(device_spec.as_ptr(), NSObject(std::ptr::null_mut()))
             };
 
-            Ok(AVCaptureVideoCallback { delegate, queue })
+            Ok(AVCaptureVideoCallback {
+                delegate,
+                queue,
+                counter,
+            })
+        }
+
+        /// The number of frames the capture output dropped since the delegate was created.
+        pub fn dropped_frames(&self) -> u64 {
+            self.counter.lock().map_or(0, |counter| counter.dropped())
         }
 
         pub fn data_len(&self) -> usize {
@@ -717,7 +1879,8 @@ mod internal {
         [pub AVFrameRateRange],
         [pub AVCaptureDeviceDiscoverySession],
         [pub AVCaptureDeviceInput],
-        [pub AVCaptureSession]
+        [pub AVCaptureSession],
+        [pub AVCaptureMultiCamSession]
     }
 
     impl AVFrameRateRange {
@@ -736,6 +1899,8 @@ mod internal {
         pub resolution: CMVideoDimensions,
         pub fps_list: Vec<f64>,
         pub fourcc: FrameFormat,
+        pub pixel_format: OSType,
+        pub color_range: Option<ColorRange>,
     }
 
     impl TryFrom<*mut Object> for AVCaptureDeviceFormat {
@@ -785,6 +1950,8 @@ mod internal {
                 resolution,
                 fps_list,
                 fourcc,
+                pixel_format: fcc_raw,
+                color_range: raw_fcc_to_color_range(fcc_raw),
             })
         }
     }
@@ -829,6 +1996,19 @@ mod internal {
                 ));
             }
 
+            // a Desk View camera does not know which camera it belongs to, so link it back
+            for camera in 0..devices.len() {
+                let camera_id = CameraIndex::String(devices[camera].misc());
+                for related in devices[camera].related_devices().to_vec() {
+                    if let Some(desk_view) = devices
+                        .iter_mut()
+                        .find(|info| CameraIndex::String(info.misc()) == related)
+                    {
+                        *desk_view = desk_view.clone().with_related_device(camera_id.clone());
+                    }
+                }
+            }
+
             devices
         }
     }
@@ -900,7 +2080,8 @@ mod internal {
         }
 
         pub fn supported_formats(&self) -> Result<Vec<CameraFormat>, NokhwaError> {
-            Ok(self
+            // video and full range NV12 formats end up as the same CameraFormat
+            let mut formats = self
                 .supported_formats_raw()?
                 .iter()
                 .flat_map(|av_fmt| {
@@ -914,7 +2095,18 @@ mod internal {
                     })
                 })
                 .filter(|x| x.frame_rate() != 0)
-                .collect())
+                .collect::<Vec<CameraFormat>>();
+            formats.sort();
+            formats.dedup();
+            Ok(formats)
+        }
+
+        /// The pixel format of the active format, which the video data output is asked to deliver so frames are not converted.
+        pub fn active_pixel_format(&self) -> OSType {
+            let active_format: *mut Object = unsafe { msg_send![self.inner, activeFormat] };
+            let description: CMFormatDescriptionRef =
+                unsafe { msg_send![active_format, formatDescription] };
+            unsafe { CMFormatDescriptionGetMediaSubType(description) }
         }
 
         pub fn already_in_use(&self) -> bool {
@@ -931,6 +2123,27 @@ mod internal {
             }
         }
 
+        /// Returns `false` once the device is gone, e.g. a Continuity Camera that was picked up or walked away with.
+        pub fn is_connected(&self) -> bool {
+            unsafe {
+                let result: BOOL = msg_send![self.inner, isConnected];
+                result == YES
+            }
+        }
+
+        /// Returns `true` if this is an iPhone used as a camera.
+        pub fn is_continuity_camera(&self) -> bool {
+            is_continuity_camera(self.inner)
+        }
+
+        /// Gets the Desk View camera that shows the desk below this Continuity Camera, if it has one.
+        /// It is opened like any other camera, with the [`CameraIndex`] of the returned [`CameraInfo`].
+        pub fn desk_view_camera(&self) -> Option<CameraInfo> {
+            let id = desk_view_id(self.inner)?;
+            let desk_view: *mut Object = unsafe { msg_send![self.inner, companionDeskViewCamera] };
+            Some(get_raw_device_info(CameraIndex::String(id), desk_view))
+        }
+
         pub fn lock(&self) -> Result<(), NokhwaError> {
             if self.locked {
                 return Ok(());
@@ -986,7 +2199,12 @@ mod internal {
 
                 if dimensions.height == descriptor.resolution().height() as i32
                     && dimensions.width == descriptor.resolution().width() as i32
+                    && format.fourcc == descriptor.format()
                 {
+                    // cameras with both NV12 ranges list the video range format first, keep it
+                    if !selected_format.is_null() && format.color_range == Some(ColorRange::Full) {
+                        continue;
+                    }
                     selected_format = format.internal;
 
                     for range in ns_arr_to_vec::<AVFrameRateRange>(unsafe {
@@ -1028,50 +2246,61 @@ mod internal {
             Ok(())
         }
 
+        // Controls that have no `KnownCameraControl` of their own are `Other`:
         // 0 => Focus POI
-        // 1 => Focus Manual Setting
+        // 1 => Focus Mode
         // 2 => Exposure POI
         // 3 => Exposure Face Driven
         // 4 => Exposure Target Bias
-        // 5 => Exposure ISO
-        // 6 => Exposure Duration
+        // 5 => Torch Mode
+        // 6 => Distortion Correction
+        // 7 => Exposure Mode
+        // 8 => White Balance Mode
+        // 9 => Center Stage
+        // 10 => Portrait Effect
+        // 11 => Studio Light
+        // Controls whose properties the device does not have (e.g. zoom before macOS 14) are left out.
         pub fn get_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
             let active_format: *mut Object = unsafe { msg_send![self.inner, activeFormat] };
 
             let mut controls = vec![];
-            // get focus modes
 
-            let focus_current: NSInteger = unsafe { msg_send![self.inner, focusMode] };
-            let focus_locked: BOOL =
-                unsafe { msg_send![self.inner, isFocusModeSupported:NSInteger::from(0)] };
-            let focus_auto: BOOL =
-                unsafe { msg_send![self.inner, isFocusModeSupported:NSInteger::from(1)] };
-            let focus_continuous: BOOL =
-                unsafe { msg_send![self.inner, isFocusModeSupported:NSInteger::from(2)] };
+            // get focus
+            let focus_mode: NSInteger = unsafe { msg_send![self.inner, focusMode] };
+            let focus_modes = supported_modes(3, |mode| unsafe {
+                msg_send![self.inner, isFocusModeSupported: mode]
+            });
+            let focus_auto = focus_modes.iter().any(|mode| *mode != 0);
 
-            {
-                let mut supported_focus_values = vec![];
+            controls.push(CameraControl::new(
+                KnownCameraControl::Other(1),
+                "FocusMode".to_string(),
+                ControlValueDescription::Enum {
+                    value: focus_mode as i64,
+                    possible: focus_modes,
+                    default: focus_mode as i64,
+                },
+                vec![],
+                true,
+            ));
 
-                if focus_locked == YES {
-                    supported_focus_values.push(0)
-                }
-                if focus_auto == YES {
-                    supported_focus_values.push(1)
-                }
-                if focus_continuous == YES {
-                    supported_focus_values.push(2)
-                }
+            if responds_to(self.inner, sel!(lensPosition)) {
+                let lens_position_supported: BOOL =
+                    unsafe { msg_send![self.inner, isLockingFocusWithCustomLensPositionSupported] };
+                let lens_position: f32 = unsafe { msg_send![self.inner, lensPosition] };
 
                 controls.push(CameraControl::new(
                     KnownCameraControl::Focus,
-                    "FocusMode".to_string(),
-                    ControlValueDescription::Enum {
-                        value: focus_current,
-                        possible: supported_focus_values,
-                        default: focus_current,
+                    "FocusLensPosition".to_string(),
+                    ControlValueDescription::FloatRange {
+                        min: 0.0,
+                        max: 1.0,
+                        value: f64::from(lens_position),
+                        step: 0.0,
+                        default: 1.0,
                     },
-                    vec![],
-                    true,
+                    mode_flags(focus_mode, lens_position_supported == YES),
+                    lens_position_supported == YES,
                 ));
             }
 
@@ -1083,82 +2312,71 @@ mod internal {
                 KnownCameraControl::Other(0),
                 "FocusPointOfInterest".to_string(),
                 ControlValueDescription::Point {
-                    value: (focus_poi.x as f64, focus_poi.y as f64),
+                    value: (focus_poi.x, focus_poi.y),
                     default: (0.5, 0.5),
                 },
-                if focus_poi_supported == NO {
-                    vec![
-                        KnownCameraControlFlag::Disabled,
-                        KnownCameraControlFlag::ReadOnly,
-                    ]
-                } else {
-                    vec![]
-                },
-                focus_auto == YES || focus_continuous == YES,
+                support_flags(focus_poi_supported == YES),
+                focus_auto,
             ));
 
-            let focus_manual: BOOL =
-                unsafe { msg_send![self.inner, isLockingFocusWithCustomLensPositionSupported] };
-            let focus_lenspos: f32 = unsafe { msg_send![self.inner, lensPosition] };
+            // get exposures
+            let exposure_mode: NSInteger = unsafe { msg_send![self.inner, exposureMode] };
+            let exposure_modes = supported_modes(4, |mode| unsafe {
+                msg_send![self.inner, isExposureModeSupported: mode]
+            });
+            let exposure_auto = exposure_modes.iter().any(|mode| *mode == 1 || *mode == 2);
+            let exposure_custom = exposure_modes.contains(&3);
 
             controls.push(CameraControl::new(
-                KnownCameraControl::Other(1),
-                "FocusManualLensPosition".to_string(),
-                ControlValueDescription::FloatRange {
-                    min: 0.0,
-                    max: 1.0,
-                    value: focus_lenspos as f64,
-                    step: f64::MIN_POSITIVE,
-                    default: 1.0,
+                KnownCameraControl::Other(7),
+                "ExposureMode".to_string(),
+                ControlValueDescription::Enum {
+                    value: exposure_mode as i64,
+                    possible: exposure_modes,
+                    default: exposure_mode as i64,
                 },
-                if focus_manual == YES {
-                    vec![]
-                } else {
-                    vec![
-                        KnownCameraControlFlag::Disabled,
-                        KnownCameraControlFlag::ReadOnly,
-                    ]
-                },
-                focus_manual == YES,
+                vec![],
+                true,
             ));
 
-            // get exposures
-            let exposure_current: NSInteger = unsafe { msg_send![self.inner, exposureMode] };
-            let exposure_locked: BOOL =
-                unsafe { msg_send![self.inner, isExposureModeSupported:NSInteger::from(0)] };
-            let exposure_auto: BOOL =
-                unsafe { msg_send![self.inner, isExposureModeSupported:NSInteger::from(1)] };
-            let exposure_continuous: BOOL =
-                unsafe { msg_send![self.inner, isExposureModeSupported:NSInteger::from(2)] };
-            let exposure_custom: BOOL =
-                unsafe { msg_send![self.inner, isExposureModeSupported:NSInteger::from(3)] };
+            if responds_to(self.inner, sel!(ISO)) {
+                // exposure durations are in microseconds, whatever timescale the device uses
+                let exposure_duration: CMTime = unsafe { msg_send![self.inner, exposureDuration] };
+                let exposure_duration_min: CMTime =
+                    unsafe { msg_send![active_format, minExposureDuration] };
+                let exposure_duration_max: CMTime =
+                    unsafe { msg_send![active_format, maxExposureDuration] };
 
-            {
-                let mut supported_exposure_values = vec![];
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Exposure,
+                    "ExposureDuration".to_string(),
+                    ControlValueDescription::IntegerRange {
+                        min: cmtime_to_micros(exposure_duration_min),
+                        max: cmtime_to_micros(exposure_duration_max),
+                        value: cmtime_to_micros(exposure_duration),
+                        step: 1,
+                        default: cmtime_to_micros(exposure_duration),
+                    },
+                    mode_flags(exposure_mode, exposure_custom),
+                    exposure_custom,
+                ));
 
-                if exposure_locked == YES {
-                    supported_exposure_values.push(0);
-                }
-                if exposure_auto == YES {
-                    supported_exposure_values.push(1);
-                }
-                if exposure_continuous == YES {
-                    supported_exposure_values.push(2);
-                }
-                if exposure_custom == YES {
-                    supported_exposure_values.push(3);
-                }
+                let exposure_iso: f32 = unsafe { msg_send![self.inner, ISO] };
+                let exposure_iso_min: f32 = unsafe { msg_send![active_format, minISO] };
+                let exposure_iso_max: f32 = unsafe { msg_send![active_format, maxISO] };
 
                 controls.push(CameraControl::new(
-                    KnownCameraControl::Exposure,
-                    "ExposureMode".to_string(),
-                    ControlValueDescription::Enum {
-                        value: exposure_current,
-                        possible: supported_exposure_values,
-                        default: exposure_current,
+                    KnownCameraControl::Gain,
+                    "ExposureISO".to_string(),
+                    ControlValueDescription::FloatRange {
+                        min: f64::from(exposure_iso_min),
+                        max: f64::from(exposure_iso_max),
+                        value: f64::from(exposure_iso),
+                        step: 0.0,
+                        default: f64::from(exposure_iso),
                     },
-                    vec![],
-                    true,
+                    mode_flags(exposure_mode, exposure_custom),
+                    exposure_custom,
                 ));
             }
 
@@ -1170,1040 +2388,471 @@ mod internal {
                 KnownCameraControl::Other(2),
                 "ExposurePointOfInterest".to_string(),
                 ControlValueDescription::Point {
-                    value: (exposure_poi.x as f64, exposure_poi.y as f64),
+                    value: (exposure_poi.x, exposure_poi.y),
                     default: (0.5, 0.5),
                 },
-                if exposure_poi_supported == NO {
-                    vec![
-                        KnownCameraControlFlag::Disabled,
-                        KnownCameraControlFlag::ReadOnly,
-                    ]
-                } else {
-                    vec![]
-                },
-                focus_auto == YES || focus_continuous == YES,
+                support_flags(exposure_poi_supported == YES),
+                exposure_auto,
             ));
 
-            let expposure_face_driven_supported: BOOL =
-                unsafe { msg_send![self.inner, isFaceDrivenAutoExposureEnabled] };
-            let exposure_face_driven: BOOL = unsafe {
-                msg_send![
-                    self.inner,
-                    automaticallyAdjustsFaceDrivenAutoExposureEnabled
-                ]
-            };
+            if responds_to(self.inner, sel!(isFaceDrivenAutoExposureEnabled)) {
+                let exposure_face_driven: BOOL =
+                    unsafe { msg_send![self.inner, isFaceDrivenAutoExposureEnabled] };
 
-            controls.push(CameraControl::new(
-                KnownCameraControl::Other(3),
-                "ExposureFaceDriven".to_string(),
-                ControlValueDescription::Boolean {
-                    value: exposure_face_driven == YES,
-                    default: false,
-                },
-                if expposure_face_driven_supported == NO {
-                    vec![
-                        KnownCameraControlFlag::Disabled,
-                        KnownCameraControlFlag::ReadOnly,
-                    ]
-                } else {
-                    vec![]
-                },
-                exposure_poi_supported == YES,
-            ));
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Other(3),
+                    "ExposureFaceDriven".to_string(),
+                    ControlValueDescription::Boolean {
+                        value: exposure_face_driven == YES,
+                        default: true,
+                    },
+                    support_flags(exposure_poi_supported == YES),
+                    exposure_poi_supported == YES,
+                ));
+            }
+
+            if responds_to(self.inner, sel!(exposureTargetBias)) {
+                let exposure_bias: f32 = unsafe { msg_send![self.inner, exposureTargetBias] };
+                let exposure_bias_min: f32 =
+                    unsafe { msg_send![self.inner, minExposureTargetBias] };
+                let exposure_bias_max: f32 =
+                    unsafe { msg_send![self.inner, maxExposureTargetBias] };
+
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Other(4),
+                    "ExposureBiasTarget".to_string(),
+                    ControlValueDescription::FloatRange {
+                        min: f64::from(exposure_bias_min),
+                        max: f64::from(exposure_bias_max),
+                        value: f64::from(exposure_bias),
+                        step: 0.0,
+                        default: 0.0,
+                    },
+                    vec![],
+                    true,
+                ));
+            }
+
+            if responds_to(self.inner, sel!(lensAperture)) {
+                let lens_aperture: f32 = unsafe { msg_send![self.inner, lensAperture] };
+
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Iris,
+                    "LensAperture".to_string(),
+                    ControlValueDescription::Float {
+                        value: f64::from(lens_aperture),
+                        default: f64::from(lens_aperture),
+                        step: 0.0,
+                    },
+                    vec![KnownCameraControlFlag::ReadOnly],
+                    false,
+                ));
+            }
 
-            let exposure_bias: f32 = unsafe { msg_send![self.inner, exposureTargetBias] };
-            let exposure_bias_min: f32 = unsafe { msg_send![self.inner, minExposureTargetBias] };
-            let exposure_bias_max: f32 = unsafe { msg_send![self.inner, maxExposureTargetBias] };
+            // get whitebalance
+            let white_balance_mode: NSInteger = unsafe { msg_send![self.inner, whiteBalanceMode] };
+            let white_balance_modes = supported_modes(3, |mode| unsafe {
+                msg_send![self.inner, isWhiteBalanceModeSupported: mode]
+            });
 
             controls.push(CameraControl::new(
-                KnownCameraControl::Other(4),
-                "ExposureBiasTarget".to_string(),
-                ControlValueDescription::FloatRange {
-                    min: exposure_bias_min as f64,
-                    max: exposure_bias_max as f64,
-                    value: exposure_bias as f64,
-                    step: f32::MIN_POSITIVE as f64,
-                    default: unsafe { AVCaptureExposureTargetBiasCurrent } as f64,
+                KnownCameraControl::Other(8),
+                "WhiteBalanceMode".to_string(),
+                ControlValueDescription::Enum {
+                    value: white_balance_mode as i64,
+                    possible: white_balance_modes,
+                    default: white_balance_mode as i64,
                 },
                 vec![],
                 true,
             ));
 
-            let exposure_duration: CMTime = unsafe { msg_send![self.inner, exposureDuration] };
-            let exposure_duration_min: CMTime =
-                unsafe { msg_send![active_format, minExposureDuration] };
-            let exposure_duration_max: CMTime =
-                unsafe { msg_send![active_format, maxExposureDuration] };
-
-            controls.push(CameraControl::new(
-                KnownCameraControl::Gamma,
-                "ExposureDuration".to_string(),
-                ControlValueDescription::IntegerRange {
-                    min: exposure_duration_min.value,
-                    max: exposure_duration_max.value,
-                    value: exposure_duration.value,
-                    step: 1,
-                    default: unsafe { AVCaptureExposureDurationCurrent.value },
-                },
-                if exposure_custom == YES {
-                    vec![
-                        KnownCameraControlFlag::ReadOnly,
-                        KnownCameraControlFlag::Volatile,
+            if responds_to(self.inner, sel!(deviceWhiteBalanceGains)) {
+                let white_balance_gains: AVCaptureWhiteBalanceGains =
+                    unsafe { msg_send![self.inner, deviceWhiteBalanceGains] };
+                let white_balance_default: AVCaptureWhiteBalanceGains =
+                    unsafe { msg_send![self.inner, grayWorldDeviceWhiteBalanceGains] };
+                let white_balance_max: f32 = unsafe { msg_send![self.inner, maxWhiteBalanceGain] };
+                let white_balance_gain_supported: BOOL = unsafe {
+                    msg_send![
+                        self.inner,
+                        isLockingWhiteBalanceWithCustomDeviceGainsSupported
                     ]
-                } else {
-                    vec![KnownCameraControlFlag::Volatile]
-                },
-                exposure_custom == YES,
-            ));
-
-            let exposure_iso: f32 = unsafe { msg_send![self.inner, ISO] };
-            let exposure_iso_min: f32 = unsafe { msg_send![active_format, minISO] };
-            let exposure_iso_max: f32 = unsafe { msg_send![active_format, maxISO] };
+                };
 
-            controls.push(CameraControl::new(
-                KnownCameraControl::Brightness,
-                "ExposureISO".to_string(),
-                ControlValueDescription::FloatRange {
-                    min: exposure_iso_min as f64,
-                    max: exposure_iso_max as f64,
-                    value: exposure_iso as f64,
-                    step: f32::MIN_POSITIVE as f64,
-                    default: unsafe { AVCaptureISOCurrent } as f64,
-                },
-                if exposure_custom == YES {
-                    vec![
-                        KnownCameraControlFlag::ReadOnly,
-                        KnownCameraControlFlag::Volatile,
-                    ]
-                } else {
-                    vec![KnownCameraControlFlag::Volatile]
-                },
-                exposure_custom == YES,
-            ));
+                controls.push(CameraControl::new(
+                    KnownCameraControl::WhiteBalance,
+                    "WhiteBalanceGains".to_string(),
+                    ControlValueDescription::RGB {
+                        value: (
+                            f64::from(white_balance_gains.redGain),
+                            f64::from(white_balance_gains.greenGain),
+                            f64::from(white_balance_gains.blueGain),
+                        ),
+                        max: (
+                            f64::from(white_balance_max),
+                            f64::from(white_balance_max),
+                            f64::from(white_balance_max),
+                        ),
+                        default: (
+                            f64::from(white_balance_default.redGain),
+                            f64::from(white_balance_default.greenGain),
+                            f64::from(white_balance_default.blueGain),
+                        ),
+                    },
+                    mode_flags(white_balance_mode, white_balance_gain_supported == YES),
+                    white_balance_gain_supported == YES,
+                ));
+            }
 
-            let lens_aperture: f32 = unsafe { msg_send![self.inner, lensAperture] };
+            // get flash
+            let has_torch: BOOL = unsafe { msg_send![self.inner, hasTorch] };
+            let torch_mode: NSInteger = unsafe { msg_send![self.inner, torchMode] };
+            let torch_modes = supported_modes(3, |mode| unsafe {
+                msg_send![self.inner, isTorchModeSupported: mode]
+            });
 
             controls.push(CameraControl::new(
-                KnownCameraControl::Iris,
-                "LensAperture".to_string(),
-                ControlValueDescription::Float {
-                    value: lens_aperture as f64,
-                    default: lens_aperture as f64,
-                    step: lens_aperture as f64,
+                KnownCameraControl::Other(5),
+                "TorchMode".to_string(),
+                ControlValueDescription::Enum {
+                    value: torch_mode as i64,
+                    possible: torch_modes,
+                    default: 0,
                 },
-                vec![KnownCameraControlFlag::ReadOnly],
-                false,
+                support_flags(has_torch == YES),
+                has_torch == YES,
             ));
 
-            // get whiteblaance
-            let white_balance_current: NSInteger =
-                unsafe { msg_send![self.inner, whiteBalanceMode] };
-            let white_balance_manual: BOOL =
-                unsafe { msg_send![self.inner, isWhiteBalanceModeSupported:NSInteger::from(0)] };
-            let white_balance_auto: BOOL =
-                unsafe { msg_send![self.inner, isWhiteBalanceModeSupported:NSInteger::from(1)] };
-            let white_balance_continuous: BOOL =
-                unsafe { msg_send![self.inner, isWhiteBalanceModeSupported:NSInteger::from(2)] };
+            // get low light boost
+            if responds_to(self.inner, sel!(isLowLightBoostSupported)) {
+                let has_llb: BOOL = unsafe { msg_send![self.inner, isLowLightBoostSupported] };
+                let llb_enabled: BOOL = unsafe {
+                    msg_send![self.inner, automaticallyEnablesLowLightBoostWhenAvailable]
+                };
 
-            {
-                let mut possible = vec![];
+                controls.push(CameraControl::new(
+                    KnownCameraControl::BacklightComp,
+                    "LowLightCompensation".to_string(),
+                    ControlValueDescription::Boolean {
+                        value: llb_enabled == YES,
+                        default: false,
+                    },
+                    support_flags(has_llb == YES),
+                    has_llb == YES,
+                ));
+            }
 
-                if white_balance_manual == YES {
-                    possible.push(0);
-                }
-                if white_balance_auto == YES {
-                    possible.push(1);
-                }
-                if white_balance_continuous == YES {
-                    possible.push(2);
-                }
+            // get zoom factor
+            if responds_to(self.inner, sel!(videoZoomFactor)) {
+                let zoom_current: CGFloat = unsafe { msg_send![self.inner, videoZoomFactor] };
+                let zoom_min: CGFloat =
+                    unsafe { msg_send![self.inner, minAvailableVideoZoomFactor] };
+                let zoom_max: CGFloat =
+                    unsafe { msg_send![self.inner, maxAvailableVideoZoomFactor] };
 
                 controls.push(CameraControl::new(
-                    KnownCameraControl::WhiteBalance,
-                    "WhiteBalanceMode".to_string(),
-                    ControlValueDescription::Enum {
-                        value: white_balance_current as i64,
-                        possible,
-                        default: 0,
+                    KnownCameraControl::Zoom,
+                    "Zoom".to_string(),
+                    ControlValueDescription::FloatRange {
+                        min: zoom_min,
+                        max: zoom_max,
+                        value: zoom_current,
+                        step: 0.0,
+                        default: 1.0,
                     },
                     vec![],
                     true,
                 ));
             }
 
-            let white_balance_gains: AVCaptureWhiteBalanceGains =
-                unsafe { msg_send![self.inner, deviceWhiteBalanceGains] };
-            let white_balance_default: AVCaptureWhiteBalanceGains =
-                unsafe { msg_send![self.inner, grayWorldDeviceWhiteBalanceGains] };
-            let white_balancne_max: AVCaptureWhiteBalanceGains =
-                unsafe { msg_send![self.inner, maxWhiteBalanceGain] };
-            let white_balance_gain_supported: BOOL = unsafe {
-                msg_send![
-                    self.inner,
-                    isLockingWhiteBalanceWithCustomDeviceGainsSupported
-                ]
-            };
+            // zoom distortion correction
+            if responds_to(self.inner, sel!(isGeometricDistortionCorrectionSupported)) {
+                let distortion_correction_supported: BOOL =
+                    unsafe { msg_send![self.inner, isGeometricDistortionCorrectionSupported] };
+                let distortion_correction_current_value: BOOL =
+                    unsafe { msg_send![self.inner, isGeometricDistortionCorrectionEnabled] };
 
-            controls.push(CameraControl::new(
-                KnownCameraControl::Gain,
-                "WhiteBalanceGain".to_string(),
-                ControlValueDescription::RGB {
-                    value: (
-                        white_balance_gains.redGain as f64,
-                        white_balance_gains.greenGain as f64,
-                        white_balance_gains.blueGain as f64,
-                    ),
-                    max: (
-                        white_balancne_max.redGain as f64,
-                        white_balancne_max.greenGain as f64,
-                        white_balancne_max.blueGain as f64,
-                    ),
-                    default: (
-                        white_balance_default.redGain as f64,
-                        white_balance_default.greenGain as f64,
-                        white_balance_default.blueGain as f64,
-                    ),
-                },
-                if white_balance_gain_supported == YES {
-                    vec![
-                        KnownCameraControlFlag::Disabled,
-                        KnownCameraControlFlag::ReadOnly,
-                    ]
-                } else {
-                    vec![]
-                },
-                white_balance_gain_supported == YES,
-            ));
-
-            // get flash
-            let has_torch: BOOL = unsafe { msg_send![self.inner, isTorchAvailable] };
-            let torch_active: BOOL = unsafe { msg_send![self.inner, isTorchActive] };
-            let torch_off: BOOL =
-                unsafe { msg_send![self.inner, isTorchModeSupported:NSInteger::from(0)] };
-            let torch_on: BOOL =
-                unsafe { msg_send![self.inner, isTorchModeSupported:NSInteger::from(1)] };
-            let torch_auto: BOOL =
-                unsafe { msg_send![self.inner, isTorchModeSupported:NSInteger::from(2)] };
-
-            {
-                let mut possible = vec![];
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Other(6),
+                    "DistortionCorrection".to_string(),
+                    ControlValueDescription::Boolean {
+                        value: distortion_correction_current_value == YES,
+                        default: false,
+                    },
+                    support_flags(distortion_correction_supported == YES),
+                    distortion_correction_supported == YES,
+                ));
+            }
 
-                if torch_off == YES {
-                    possible.push(0);
-                }
-                if torch_on == YES {
-                    possible.push(1);
-                }
-                if torch_auto == YES {
-                    possible.push(2);
+            // get system video effects, which the user turns on and off in Control Center
+            if responds_to(self.inner, sel!(isCenterStageActive)) {
+                let supported: BOOL = unsafe { msg_send![active_format, isCenterStageSupported] };
+                let active: BOOL = unsafe { msg_send![self.inner, isCenterStageActive] };
+                let control_mode: NSInteger =
+                    unsafe { msg_send![class!(AVCaptureDevice), centerStageControlMode] };
+                // only the user can change it, unless the app asked to share control
+                let mut flags = if supported == YES && control_mode != 0 {
+                    vec![]
+                } else {
+                    vec![KnownCameraControlFlag::ReadOnly]
+                };
+                if supported == NO {
+                    flags.push(KnownCameraControlFlag::Disabled);
                 }
+                flags.push(KnownCameraControlFlag::Volatile);
 
                 controls.push(CameraControl::new(
-                    KnownCameraControl::Other(5),
-                    "TorchMode".to_string(),
-                    ControlValueDescription::Enum {
-                        value: (torch_active == YES) as i64,
-                        possible,
-                        default: 0,
-                    },
-                    if has_torch == YES {
-                        vec![
-                            KnownCameraControlFlag::Disabled,
-                            KnownCameraControlFlag::ReadOnly,
-                        ]
-                    } else {
-                        vec![]
+                    KnownCameraControl::Other(9),
+                    "CenterStage".to_string(),
+                    ControlValueDescription::Boolean {
+                        value: active == YES,
+                        default: false,
                     },
-                    has_torch == YES,
+                    flags,
+                    supported == YES,
                 ));
             }
 
-            // get low light boost
-            let has_llb: BOOL = unsafe { msg_send![self.inner, isLowLightBoostSupported] };
-            let llb_enabled: BOOL = unsafe { msg_send![self.inner, isLowLightBoostEnabled] };
+            if responds_to(self.inner, sel!(isPortraitEffectActive)) {
+                let supported: BOOL =
+                    unsafe { msg_send![active_format, isPortraitEffectSupported] };
+                let active: BOOL = unsafe { msg_send![self.inner, isPortraitEffectActive] };
 
-            {
                 controls.push(CameraControl::new(
-                    KnownCameraControl::BacklightComp,
-                    "LowLightCompensation".to_string(),
+                    KnownCameraControl::Other(10),
+                    "PortraitEffect".to_string(),
                     ControlValueDescription::Boolean {
-                        value: llb_enabled == YES,
+                        value: active == YES,
                         default: false,
                     },
-                    if has_llb == NO {
-                        vec![
-                            KnownCameraControlFlag::Disabled,
-                            KnownCameraControlFlag::ReadOnly,
-                        ]
-                    } else {
-                        vec![]
-                    },
-                    has_llb == YES,
+                    vec![
+                        KnownCameraControlFlag::ReadOnly,
+                        KnownCameraControlFlag::Volatile,
+                    ],
+                    supported == YES,
                 ));
             }
 
-            // get zoom factor
-            let zoom_current: CGFloat = unsafe { msg_send![self.inner, videoZoomFactor] };
-            let zoom_min: CGFloat = unsafe { msg_send![self.inner, minAvailableVideoZoomFactor] };
-            let zoom_max: CGFloat = unsafe { msg_send![self.inner, maxAvailableVideoZoomFactor] };
-
-            controls.push(CameraControl::new(
-                KnownCameraControl::Zoom,
-                "Zoom".to_string(),
-                ControlValueDescription::FloatRange {
-                    min: zoom_min as f64,
-                    max: zoom_max as f64,
-                    value: zoom_current as f64,
-                    step: f32::MIN_POSITIVE as f64,
-                    default: 1.0,
-                },
-                vec![],
-                true,
-            ));
-
-            // zoom distortion correction
-            let distortion_correction_supported: BOOL =
-                unsafe { msg_send![self.inner, isGeometricDistortionCorrectionSupported] };
-            let distortion_correction_current_value: BOOL =
-                unsafe { msg_send![self.inner, isGeometricDistortionCorrectionEnabled] };
+            if responds_to(self.inner, sel!(isStudioLightActive)) {
+                let supported: BOOL = unsafe { msg_send![active_format, isStudioLightSupported] };
+                let active: BOOL = unsafe { msg_send![self.inner, isStudioLightActive] };
 
-            controls.push(CameraControl::new(
-                KnownCameraControl::Other(6),
-                "DistortionCorrection".to_string(),
-                ControlValueDescription::Boolean {
-                    value: distortion_correction_current_value == YES,
-                    default: false,
-                },
-                if distortion_correction_supported == YES {
+                controls.push(CameraControl::new(
+                    KnownCameraControl::Other(11),
+                    "StudioLight".to_string(),
+                    ControlValueDescription::Boolean {
+                        value: active == YES,
+                        default: false,
+                    },
                     vec![
                         KnownCameraControlFlag::ReadOnly,
-                        KnownCameraControlFlag::Disabled,
-                    ]
-                } else {
-                    vec![]
-                },
-                distortion_correction_supported == YES,
-            ));
+                        KnownCameraControlFlag::Volatile,
+                    ],
+                    supported == YES,
+                ));
+            }
 
             Ok(controls)
         }
 
+        /// Asks to share control of Center Stage with the user and turns it on or off. The user can still change it in Control Center,
+        /// which [`get_controls()`](AVCaptureDevice::get_controls) reflects.
+        ///
+        /// Center Stage is a system wide setting, so this changes it for every Center Stage camera, not only this one.
+        pub fn request_center_stage(&self, enabled: bool) -> Result<(), NokhwaError> {
+            let active_format: *mut Object = unsafe { msg_send![self.inner, activeFormat] };
+            if !responds_to(active_format, sel!(isCenterStageSupported)) {
+                return Err(NokhwaError::UnsupportedOperationError(
+                    ApiBackend::AVFoundation,
+                ));
+            }
+            let supported: BOOL = unsafe { msg_send![active_format, isCenterStageSupported] };
+            if supported == NO {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "CenterStage".to_string(),
+                    value: enabled.to_string(),
+                    error: "Not supported by the active format".to_string(),
+                });
+            }
+
+            let device_cls = class!(AVCaptureDevice);
+            let enabled = if enabled { YES } else { NO };
+            unsafe {
+                // AVCaptureCenterStageControlModeCooperative
+                let _: () = msg_send![device_cls, setCenterStageControlMode: 2 as NSInteger];
+                let _: () = msg_send![device_cls, setCenterStageEnabled: enabled];
+            }
+            Ok(())
+        }
+
+        /// Sets a control from [`get_controls()`](AVCaptureDevice::get_controls). The device must be locked with [`lock()`](AVCaptureDevice::lock).
+        ///
+        /// Setting [`Focus`](KnownCameraControl::Focus) locks focus at that lens position, [`Exposure`](KnownCameraControl::Exposure) or
+        /// [`Gain`](KnownCameraControl::Gain) switches to custom exposure, and [`WhiteBalance`](KnownCameraControl::WhiteBalance) locks white balance
+        /// at those gains. Setting the matching mode back to automatic undoes this.
         pub fn set_control(
             &mut self,
             id: KnownCameraControl,
             value: ControlValueSetter,
         ) -> Result<(), NokhwaError> {
-            let rc = self.get_controls()?;
-            let controls = rc
-                .iter()
-                .map(|cc| (cc.control(), cc))
-                .collect::<BTreeMap<_, _>>();
-
-            match id {
-                KnownCameraControl::Brightness => {
-                    let isoctrl = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if isoctrl.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error:
-                                "Exposure is in improper state to set ISO (Please set to `custom`!)"
-                                    .to_string(),
-                        });
-                    }
-
-                    if isoctrl.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
+            let error = |why: &str| NokhwaError::SetPropertyError {
+                property: id.to_string(),
+                value: value.to_string(),
+                error: why.to_string(),
+            };
 
-                    let current_duration = unsafe { AVCaptureExposureDurationCurrent };
-                    let new_iso = *value.as_float().ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Expected float".to_string(),
-                    })? as f32;
+            let controls = self.get_controls()?;
+            let control = controls
+                .iter()
+                .find(|control| control.control() == id)
+                .ok_or_else(|| error("Control does not exist"))?;
+            if control.flag().contains(&KnownCameraControlFlag::ReadOnly) {
+                return Err(error("Read Only"));
+            }
+            if control.flag().contains(&KnownCameraControlFlag::Disabled) {
+                return Err(error("Disabled"));
+            }
+            if !control.description().verify_setter(&value) {
+                return Err(error("Failed to verify value"));
+            }
 
-                    if !isoctrl.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
+            let as_float = || {
+                value
+                    .as_float()
+                    .copied()
+                    .ok_or_else(|| error("Expected Float"))
+            };
+            let as_enum = || {
+                value
+                    .as_enum()
+                    .map(|mode| *mode as NSInteger)
+                    .ok_or_else(|| error("Expected Enum"))
+            };
+            let as_bool = || {
+                value
+                    .as_boolean()
+                    .map(|on| if *on { YES } else { NO })
+                    .ok_or_else(|| error("Expected Boolean"))
+            };
+            let as_point = || {
+                value
+                    .as_point()
+                    .map(|(x, y)| CGPoint { x: *x, y: *y })
+                    .ok_or_else(|| error("Expected Point"))
+            };
 
+            match id {
+                KnownCameraControl::Focus => {
+                    let lens_position = as_float()? as f32;
                     let _: () = unsafe {
-                        msg_send![self.inner, setExposureModeCustomWithDuration:current_duration ISO:new_iso completionHandler:Nil]
+                        msg_send![self.inner, setFocusModeLockedWithLensPosition: lens_position completionHandler: Nil]
                     };
-
-                    Ok(())
                 }
-                KnownCameraControl::Gamma => {
-                    let duration_ctrl = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if duration_ctrl
-                        .flag()
-                        .contains(&KnownCameraControlFlag::ReadOnly)
-                    {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Exposure is in improper state to set Duration (Please set to `custom`!)"
-                                .to_string(),
-                        });
-                    }
-
-                    if duration_ctrl
-                        .flag()
-                        .contains(&KnownCameraControlFlag::Disabled)
-                    {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-                    let current_duration: CMTime =
-                        unsafe { msg_send![self.inner, exposureDuration] };
-
+                KnownCameraControl::Exposure => {
+                    let micros = *value
+                        .as_integer()
+                        .ok_or_else(|| error("Expected Integer"))?;
+                    let duration = unsafe { CMTimeMake(micros, 1_000_000) };
                     let current_iso = unsafe { AVCaptureISOCurrent };
-                    let new_duration = CMTime {
-                        value: *value.as_integer().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected i64".to_string(),
-                        })?,
-                        timescale: current_duration.timescale,
-                        flags: current_duration.flags,
-                        epoch: current_duration.epoch,
+                    let _: () = unsafe {
+                        msg_send![self.inner, setExposureModeCustomWithDuration:duration ISO:current_iso completionHandler:Nil]
                     };
-
-                    if !duration_ctrl.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
+                }
+                KnownCameraControl::Gain => {
+                    let iso = as_float()? as f32;
+                    let current_duration = unsafe { AVCaptureExposureDurationCurrent };
                     let _: () = unsafe {
-                        msg_send![self.inner, setExposureModeCustomWithDuration:new_duration ISO:current_iso completionHandler:Nil]
+                        msg_send![self.inner, setExposureModeCustomWithDuration:current_duration ISO:iso completionHandler:Nil]
                     };
-
-                    Ok(())
                 }
                 KnownCameraControl::WhiteBalance => {
-                    let wb_enum_value = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if wb_enum_value
-                        .flag()
-                        .contains(&KnownCameraControlFlag::ReadOnly)
-                    {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if wb_enum_value
-                        .flag()
-                        .contains(&KnownCameraControlFlag::Disabled)
-                    {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-                    let setter =
-                        NSInteger::from(*value.as_enum().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Enum".to_string(),
-                        })? as i32);
-
-                    if !wb_enum_value.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
-                    let _: () = unsafe { msg_send![self.inner, whiteBalanceMode: setter] };
-
-                    Ok(())
+                    let (red, green, blue) = value.as_rgb().ok_or_else(|| error("Expected RGB"))?;
+                    // gains below 1 raise an exception
+                    let gains = AVCaptureWhiteBalanceGains {
+                        blueGain: blue.max(1.0) as f32,
+                        greenGain: green.max(1.0) as f32,
+                        redGain: red.max(1.0) as f32,
+                    };
+                    let _: () = unsafe {
+                        msg_send![self.inner, setWhiteBalanceModeLockedWithDeviceWhiteBalanceGains:gains completionHandler:Nil]
+                    };
                 }
                 KnownCameraControl::BacklightComp => {
-                    let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-
-                    let setter =
-                        NSInteger::from(*value.as_enum().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Enum".to_string(),
-                        })? as i32);
-
-                    if !ctrlvalue.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
-                    let _: () = unsafe { msg_send![self.inner, whiteBalanceMode: setter] };
-
-                    Ok(())
-                }
-                KnownCameraControl::Gain => {
-                    let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-
-                    let setter = NSInteger::from(*value.as_boolean().ok_or(
-                        NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Boolean".to_string(),
-                        },
-                    )? as i32);
-
-                    if !ctrlvalue.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
-                    let _: () = unsafe { msg_send![self.inner, whiteBalanceMode: setter] };
-
-                    Ok(())
+                    let enabled = as_bool()?;
+                    let _: () = unsafe {
+                        msg_send![
+                            self.inner,
+                            setAutomaticallyEnablesLowLightBoostWhenAvailable: enabled
+                        ]
+                    };
                 }
                 KnownCameraControl::Zoom => {
-                    let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-
-                    let setter = *value.as_float().ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Expected float".to_string(),
-                    })? as c_float;
-
-                    if !ctrlvalue.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
+                    let zoom = as_float()? as CGFloat;
+                    let _: () = unsafe { msg_send![self.inner, setVideoZoomFactor: zoom] };
+                }
+                KnownCameraControl::Other(0) => {
+                    // the point of interest is applied by setting the focus mode again
+                    let point = as_point()?;
+                    let focus_mode: NSInteger = unsafe { msg_send![self.inner, focusMode] };
+                    let _: () = unsafe { msg_send![self.inner, setFocusPointOfInterest: point] };
+                    let _: () = unsafe { msg_send![self.inner, setFocusMode: focus_mode] };
+                }
+                KnownCameraControl::Other(1) => {
+                    let mode = as_enum()?;
+                    let _: () = unsafe { msg_send![self.inner, setFocusMode: mode] };
+                }
+                KnownCameraControl::Other(2) => {
+                    let point = as_point()?;
+                    let exposure_mode: NSInteger = unsafe { msg_send![self.inner, exposureMode] };
+                    let _: () = unsafe { msg_send![self.inner, setExposurePointOfInterest: point] };
+                    let _: () = unsafe { msg_send![self.inner, setExposureMode: exposure_mode] };
+                }
+                KnownCameraControl::Other(3) => {
+                    let enabled = as_bool()?;
                     let _: () = unsafe {
-                        msg_send![self.inner, rampToVideoZoomFactor: setter withRate: 1.0_f32]
+                        msg_send![
+                            self.inner,
+                            setAutomaticallyAdjustsFaceDrivenAutoExposureEnabled: NO
+                        ]
                     };
-
-                    Ok(())
+                    let _: () =
+                        unsafe { msg_send![self.inner, setFaceDrivenAutoExposureEnabled: enabled] };
                 }
-                KnownCameraControl::Exposure => {
-                    let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-
-                    let setter =
-                        NSInteger::from(*value.as_enum().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Enum".to_string(),
-                        })? as i32);
-
-                    if !ctrlvalue.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
-                    let _: () = unsafe { msg_send![self.inner, exposureMode: setter] };
-
-                    Ok(())
+                KnownCameraControl::Other(4) => {
+                    let bias = as_float()? as f32;
+                    let _: () = unsafe {
+                        msg_send![self.inner, setExposureTargetBias: bias completionHandler: Nil]
+                    };
                 }
-                KnownCameraControl::Iris => Err(NokhwaError::SetPropertyError {
-                    property: id.to_string(),
-                    value: value.to_string(),
-                    error: "Read Only".to_string(),
-                }),
-                KnownCameraControl::Focus => {
-                    let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Control does not exist".to_string(),
-                    })?;
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Read Only".to_string(),
-                        });
-                    }
-
-                    if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Disabled".to_string(),
-                        });
-                    }
-
-                    let setter =
-                        NSInteger::from(*value.as_enum().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Enum".to_string(),
-                        })? as i32);
-
-                    if !ctrlvalue.description().verify_setter(&value) {
-                        return Err(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Failed to verify value".to_string(),
-                        });
-                    }
-
-                    let _: () = unsafe { msg_send![self.inner, focusMode: setter] };
-
-                    Ok(())
+                KnownCameraControl::Other(5) => {
+                    let mode = as_enum()?;
+                    let _: () = unsafe { msg_send![self.inner, setTorchMode: mode] };
                 }
-                KnownCameraControl::Other(i) => match i {
-                    0 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter = value
-                            .as_point()
-                            .ok_or(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Expected Point".to_string(),
-                            })
-                            .map(|(x, y)| CGPoint {
-                                x: *x as f32,
-                                y: *y as f32,
-                            })?;
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe { msg_send![self.inner, focusPointOfInterest: setter] };
-
-                        Ok(())
-                    }
-                    1 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter = *value.as_float().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected float".to_string(),
-                        })? as c_float;
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe {
-                            msg_send![self.inner, setFocusModeLockedWithLensPosition: setter handler: Nil]
-                        };
-
-                        Ok(())
-                    }
-                    2 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter = value
-                            .as_point()
-                            .ok_or(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Expected Point".to_string(),
-                            })
-                            .map(|(x, y)| CGPoint {
-                                x: *x as f32,
-                                y: *y as f32,
-                            })?;
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () =
-                            unsafe { msg_send![self.inner, exposurePointOfInterest: setter] };
-
-                        Ok(())
-                    }
-                    3 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter =
-                            if *value.as_boolean().ok_or(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Expected Boolean".to_string(),
-                            })? {
-                                YES
-                            } else {
-                                NO
-                            };
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe {
-                            msg_send![
-                                self.inner,
-                                automaticallyAdjustsFaceDrivenAutoExposureEnabled: setter
-                            ]
-                        };
-
-                        Ok(())
-                    }
-                    4 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter = *value.as_float().ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Expected Float".to_string(),
-                        })? as f32;
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe {
-                            msg_send![self.inner, setExposureTargetBias: setter handler: Nil]
-                        };
-
-                        Ok(())
-                    }
-                    5 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter = NSInteger::from(*value.as_enum().ok_or(
-                            NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Expected Enum".to_string(),
-                            },
-                        )? as i32);
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe { msg_send![self.inner, torchMode: setter] };
-
-                        Ok(())
-                    }
-                    6 => {
-                        let ctrlvalue = controls.get(&id).ok_or(NokhwaError::SetPropertyError {
-                            property: id.to_string(),
-                            value: value.to_string(),
-                            error: "Control does not exist".to_string(),
-                        })?;
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::ReadOnly) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Read Only".to_string(),
-                            });
-                        }
-
-                        if ctrlvalue.flag().contains(&KnownCameraControlFlag::Disabled) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Disabled".to_string(),
-                            });
-                        }
-
-                        let setter =
-                            if *value.as_boolean().ok_or(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Expected Boolean".to_string(),
-                            })? {
-                                YES
-                            } else {
-                                NO
-                            };
-
-                        if !ctrlvalue.description().verify_setter(&value) {
-                            return Err(NokhwaError::SetPropertyError {
-                                property: id.to_string(),
-                                value: value.to_string(),
-                                error: "Failed to verify value".to_string(),
-                            });
-                        }
-
-                        let _: () = unsafe {
-                            msg_send![self.inner, geometricDistortionCorrectionEnabled: setter]
-                        };
-
-                        Ok(())
-                    }
-                    _ => Err(NokhwaError::SetPropertyError {
-                        property: id.to_string(),
-                        value: value.to_string(),
-                        error: "Unknown Control".to_string(),
-                    }),
-                },
-                _ => Err(NokhwaError::SetPropertyError {
-                    property: id.to_string(),
-                    value: value.to_string(),
-                    error: "Unknown Control".to_string(),
-                }),
+                KnownCameraControl::Other(6) => {
+                    let enabled = as_bool()?;
+                    let _: () = unsafe {
+                        msg_send![self.inner, setGeometricDistortionCorrectionEnabled: enabled]
+                    };
+                }
+                KnownCameraControl::Other(7) => {
+                    let mode = as_enum()?;
+                    let _: () = unsafe { msg_send![self.inner, setExposureMode: mode] };
+                }
+                KnownCameraControl::Other(8) => {
+                    let mode = as_enum()?;
+                    let _: () = unsafe { msg_send![self.inner, setWhiteBalanceMode: mode] };
+                }
+                KnownCameraControl::Other(9) => {
+                    let enabled = as_bool()?;
+                    let _: () = unsafe {
+                        msg_send![class!(AVCaptureDevice), setCenterStageEnabled: enabled]
+                    };
+                }
+                _ => return Err(error("Unknown Control")),
             }
+
+            Ok(())
         }
 
         pub fn active_format(&self) -> Result<CameraFormat, NokhwaError> {
@@ -2256,6 +2905,178 @@ mod internal {
         }
     }
 
+    /// The delegate an [`AVCapturePhotoOutput`] hands its photos to, which sends them down a channel.
+    pub struct AVCapturePhotoCallback {
+        delegate: *mut Object,
+    }
+
+    impl AVCapturePhotoCallback {
+        /// Creates a delegate sending photos to `sender`, which must outlive it.
+        pub fn new(sender: &Arc<Sender<PhotoResult>>) -> Self {
+            let cls = &PHOTO_CALLBACK_CLASS as &Class;
+            let delegate: *mut Object = unsafe { msg_send![cls, alloc] };
+            let delegate: *mut Object = unsafe { msg_send![delegate, init] };
+            let sender_ptr = Arc::as_ptr(sender).cast::<c_void>();
+            unsafe {
+                let _: () = msg_send![delegate, setSenderPtr: sender_ptr];
+            }
+
+            AVCapturePhotoCallback { delegate }
+        }
+
+        pub fn inner(&self) -> *mut Object {
+            self.delegate
+        }
+    }
+
+    impl Drop for AVCapturePhotoCallback {
+        fn drop(&mut self) {
+            let _: () = unsafe { msg_send![self.delegate, release] };
+        }
+    }
+
+    /// Takes still photos while the video keeps running, through `AVCapturePhotoOutput`.
+    pub struct AVCapturePhotoOutput {
+        inner: *mut Object,
+    }
+
+    impl AVCapturePhotoOutput {
+        pub fn new() -> Self {
+            AVCapturePhotoOutput::default()
+        }
+
+        /// Asks for a photo with `settings` from `device`, which is handed to `delegate` once it is processed.
+        /// The output must be added to a running session with `device` as its input.
+        pub fn capture(
+            &self,
+            device: &AVCaptureDevice,
+            settings: PhotoSettings,
+            delegate: &AVCapturePhotoCallback,
+        ) -> Result<(), NokhwaError> {
+            let format = match settings.codec() {
+                PhotoCodec::Uncompressed => {
+                    let available: *mut Object =
+                        unsafe { msg_send![self.inner, availablePhotoPixelFormatTypes] };
+                    // photos are usually full range
+                    let pixel_format = [
+                        kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+                        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange,
+                    ]
+                    .into_iter()
+                    .map(|pixel_format| unsafe {
+                        let number: *mut Object =
+                            msg_send![class!(NSNumber), numberWithUnsignedInt: pixel_format];
+                        number
+                    })
+                    .find(|number| {
+                        let contains: BOOL =
+                            unsafe { msg_send![available, containsObject: *number] };
+                        contains == YES
+                    })
+                    .ok_or_else(|| NokhwaError::SetPropertyError {
+                        property: "PhotoCodec".to_string(),
+                        value: format!("{:?}", PhotoCodec::Uncompressed),
+                        error: "No NV12 photo pixel format".to_string(),
+                    })?;
+                    unsafe {
+                        let settings: *mut Object = msg_send![
+                            class!(NSDictionary),
+                            dictionaryWithObject: pixel_format
+                            forKey: kCVPixelBufferPixelFormatTypeKey.0
+                        ];
+                        settings
+                    }
+                }
+                codec => {
+                    let codec_type = unsafe {
+                        if codec == PhotoCodec::Heif {
+                            AVVideoCodecTypeHEVC.0
+                        } else {
+                            AVVideoCodecTypeJPEG.0
+                        }
+                    };
+                    let available: *mut Object =
+                        unsafe { msg_send![self.inner, availablePhotoCodecTypes] };
+                    let contains: BOOL =
+                        unsafe { msg_send![available, containsObject: codec_type] };
+                    if contains == NO {
+                        return Err(NokhwaError::SetPropertyError {
+                            property: "PhotoCodec".to_string(),
+                            value: format!("{codec:?}"),
+                            error: "Not available".to_string(),
+                        });
+                    }
+                    unsafe {
+                        let settings: *mut Object = msg_send![
+                            class!(NSDictionary),
+                            dictionaryWithObject: codec_type
+                            forKey: AVVideoCodecKey.0
+                        ];
+                        settings
+                    }
+                }
+            };
+            let photo_settings: *mut Object = unsafe {
+                msg_send![class!(AVCapturePhotoSettings), photoSettingsWithFormat: format]
+            };
+
+            if responds_to(self.inner, sel!(setMaxPhotoDimensions:)) {
+                // take the largest photo within the limit, the output's limit has to allow it too
+                let active_format: *mut Object = unsafe { msg_send![device.inner(), activeFormat] };
+                let supported: *mut Object =
+                    unsafe { msg_send![active_format, supportedMaxPhotoDimensions] };
+                let count: NSUInteger = unsafe { msg_send![supported, count] };
+                let largest = (0..count)
+                    .map(|index| unsafe {
+                        let value: *mut Object = msg_send![supported, objectAtIndex: index];
+                        let dimensions: CMVideoDimensions =
+                            msg_send![value, CMVideoDimensionsValue];
+                        dimensions
+                    })
+                    .filter(|dimensions| {
+                        settings.max_resolution().map_or(true, |max| {
+                            dimensions.width as u32 <= max.width()
+                                && dimensions.height as u32 <= max.height()
+                        })
+                    })
+                    .max_by_key(|dimensions| {
+                        i64::from(dimensions.width) * i64::from(dimensions.height)
+                    });
+                if let Some(dimensions) = largest {
+                    unsafe {
+                        let _: () = msg_send![self.inner, setMaxPhotoDimensions: dimensions];
+                        let _: () = msg_send![photo_settings, setMaxPhotoDimensions: dimensions];
+                    }
+                }
+            } else if settings.max_resolution().is_none()
+                && responds_to(self.inner, sel!(setHighResolutionCaptureEnabled:))
+            {
+                unsafe {
+                    let _: () = msg_send![self.inner, setHighResolutionCaptureEnabled: YES];
+                    let _: () = msg_send![photo_settings, setHighResolutionPhotoEnabled: YES];
+                }
+            }
+
+            unsafe {
+                let _: () = msg_send![
+                    self.inner,
+                    capturePhotoWithSettings: photo_settings
+                    delegate: delegate.inner()
+                ];
+            }
+            Ok(())
+        }
+    }
+
+    impl Default for AVCapturePhotoOutput {
+        fn default() -> Self {
+            let cls = class!(AVCapturePhotoOutput);
+            let inner: *mut Object = unsafe { msg_send![cls, new] };
+
+            AVCapturePhotoOutput { inner }
+        }
+    }
+
     pub struct AVCaptureVideoDataOutput {
         inner: *mut Object,
     }
@@ -2265,6 +3086,38 @@ mod internal {
             AVCaptureVideoDataOutput::default()
         }
 
+        /// Asks for frames in `pixel_format`. `AVFoundation` converts frames that arrive in any other pixel format, which costs CPU time.
+        /// Pixel formats the output can not deliver, e.g. ProRes, are ignored and frames are decoded to the output's default format.
+        pub fn set_pixel_format(&self, pixel_format: OSType) {
+            if !self.available_pixel_formats().contains(&pixel_format) {
+                return;
+            }
+            unsafe {
+                let number: *mut Object =
+                    msg_send![class!(NSNumber), numberWithUnsignedInt: pixel_format];
+                let settings: *mut Object = msg_send![
+                    class!(NSDictionary),
+                    dictionaryWithObject: number
+                    forKey: kCVPixelBufferPixelFormatTypeKey.0
+                ];
+                let _: () = msg_send![self.inner, setVideoSettings: settings];
+            }
+        }
+
+        /// Gets the pixel formats the output can deliver frames in.
+        pub fn available_pixel_formats(&self) -> Vec<OSType> {
+            let formats: *mut Object =
+                unsafe { msg_send![self.inner, availableVideoCVPixelFormatTypes] };
+            let count: NSUInteger = unsafe { NSArray::count(formats) };
+            (0..count)
+                .map(|index| {
+                    let number = unsafe { formats.objectAtIndex(index) };
+                    let format: u32 = unsafe { msg_send![number, unsignedIntValue] };
+                    format
+                })
+                .collect()
+        }
+
         pub fn add_delegate(&self, delegate: &AVCaptureVideoCallback) -> Result<(), NokhwaError> {
             unsafe {
                 let _: () = msg_send![
@@ -2341,6 +3194,27 @@ mod internal {
             unsafe { msg_send![self.inner, removeOutput:output.inner] }
         }
 
+        pub fn can_add_photo_output(&self, output: &AVCapturePhotoOutput) -> bool {
+            let result: BOOL = unsafe { msg_send![self.inner, canAddOutput:output.inner] };
+            result == YES
+        }
+
+        pub fn add_photo_output(&self, output: &AVCapturePhotoOutput) -> Result<(), NokhwaError> {
+            if self.can_add_photo_output(output) {
+                let _: () = unsafe { msg_send![self.inner, addOutput:output.inner] };
+                return Ok(());
+            }
+            Err(NokhwaError::SetPropertyError {
+                property: "AVCapturePhotoOutput".to_string(),
+                value: "add new output".to_string(),
+                error: "Rejected".to_string(),
+            })
+        }
+
+        pub fn remove_photo_output(&self, output: &AVCapturePhotoOutput) {
+            unsafe { msg_send![self.inner, removeOutput:output.inner] }
+        }
+
         pub fn is_running(&self) -> bool {
             let running: BOOL = unsafe { msg_send![self.inner, isRunning] };
             running == YES
@@ -2367,6 +3241,38 @@ mod internal {
             let interrupted: BOOL = unsafe { msg_send![self.inner, isInterrupted] };
             interrupted == YES
         }
+
+        pub fn can_set_session_preset(&self, preset: AVCaptureSessionPreset) -> bool {
+            let preset: *mut Object = preset.into();
+            let result: BOOL = unsafe { msg_send![self.inner, canSetSessionPreset: preset] };
+            result == YES
+        }
+
+        pub fn set_session_preset(
+            &self,
+            preset: AVCaptureSessionPreset,
+        ) -> Result<(), NokhwaError> {
+            if !self.can_set_session_preset(preset) {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "sessionPreset".to_string(),
+                    value: format!("{preset:?}"),
+                    error: "Not supported by the session's input".to_string(),
+                });
+            }
+            let preset: *mut Object = preset.into();
+            let _: () = unsafe { msg_send![self.inner, setSessionPreset: preset] };
+            Ok(())
+        }
+
+        /// Gets the clock the timestamps of the session's frames are on.
+        pub fn clock(&self) -> CMClockRef {
+            // `masterClock` is deprecated since macOS 12.3, but the only one before
+            if responds_to(self.inner, sel!(synchronizationClock)) {
+                unsafe { msg_send![self.inner, synchronizationClock] }
+            } else {
+                unsafe { msg_send![self.inner, masterClock] }
+            }
+        }
     }
 
     impl Default for AVCaptureSession {
@@ -2379,7 +3285,128 @@ mod internal {
             AVCaptureSession { inner: session }
         }
     }
+
+    /// An `AVCaptureSession` that streams several cameras of one device at once, on one clock. Only iOS and iPadOS 13 and up on
+    /// devices with the hardware for it support this, see [`is_supported()`](AVCaptureMultiCamSession::is_supported).
+    impl AVCaptureMultiCamSession {
+        pub fn new() -> Result<Self, NokhwaError> {
+            let cls = match Class::get("AVCaptureMultiCamSession") {
+                Some(cls) if AVCaptureMultiCamSession::is_supported() => cls,
+                _ => {
+                    return Err(NokhwaError::UnsupportedOperationError(
+                        ApiBackend::AVFoundation,
+                    ))
+                }
+            };
+            let session: *mut Object = unsafe {
+                let alloc: *mut Object = msg_send![cls, alloc];
+                msg_send![alloc, init]
+            };
+            Ok(AVCaptureMultiCamSession { inner: session })
+        }
+
+        /// Returns `true` if this device can stream several cameras at once.
+        pub fn is_supported() -> bool {
+            match Class::get("AVCaptureMultiCamSession") {
+                Some(cls) => {
+                    let supported: BOOL = unsafe { msg_send![cls, isMultiCamSupported] };
+                    supported == YES
+                }
+                None => false,
+            }
+        }
+
+        /// The session as a plain [`AVCaptureSession`], e.g. to start it or get its clock.
+        pub fn session(&self) -> AVCaptureSession {
+            AVCaptureSession::from(self.inner)
+        }
+
+        /// Adds `input`, and connects its video port to `output`. A multi-cam session does not connect inputs and outputs by itself,
+        /// as it could not tell which output belongs to which camera.
+        pub fn add_camera(
+            &self,
+            input: &AVCaptureDeviceInput,
+            output: &AVCaptureVideoDataOutput,
+        ) -> Result<(), NokhwaError> {
+            let session = self.session();
+            if !session.can_add_input(input) || !session.can_add_output(output) {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "AVCaptureMultiCamSession".to_string(),
+                    value: "add camera".to_string(),
+                    error: "Rejected".to_string(),
+                });
+            }
+            unsafe {
+                let _: () = msg_send![self.inner, addInputWithNoConnections: input.inner];
+                let _: () = msg_send![self.inner, addOutputWithNoConnections: output.inner];
+            }
+
+            let ports: *mut Object = unsafe { msg_send![input.inner, ports] };
+            let video_port = (0..unsafe { NSArray::count(ports) })
+                .map(|index| unsafe { ports.objectAtIndex(index) })
+                .find(|port| {
+                    let media_type: *mut Object = unsafe { msg_send![*port, mediaType] };
+                    let video: BOOL = unsafe {
+                        msg_send![media_type, isEqualToString: AVMediaTypeVideo.clone().0]
+                    };
+                    video == YES
+                })
+                .ok_or_else(|| NokhwaError::GetPropertyError {
+                    property: "AVCaptureInputPort".to_string(),
+                    error: "The input has no video port".to_string(),
+                })?;
+            let connection: *mut Object = unsafe {
+                let port_array: *mut Object =
+                    msg_send![class!(NSArray), arrayWithObject: video_port];
+                let alloc: *mut Object = msg_send![class!(AVCaptureConnection), alloc];
+                msg_send![alloc, initWithInputPorts: port_array output: output.inner]
+            };
+            let can_add: BOOL = unsafe { msg_send![self.inner, canAddConnection: connection] };
+            if can_add == NO {
+                let _: () = unsafe { msg_send![connection, release] };
+                self.remove_camera(input, output);
+                return Err(NokhwaError::SetPropertyError {
+                    property: "AVCaptureConnection".to_string(),
+                    value: "add connection".to_string(),
+                    error: "The camera's active format does not support multi-cam capture"
+                        .to_string(),
+                });
+            }
+            unsafe {
+                let _: () = msg_send![self.inner, addConnection: connection];
+                let _: () = msg_send![connection, release];
+            }
+            Ok(())
+        }
+
+        /// Removes `input` and `output`, and with them the connection between them.
+        pub fn remove_camera(
+            &self,
+            input: &AVCaptureDeviceInput,
+            output: &AVCaptureVideoDataOutput,
+        ) {
+            let session = self.session();
+            session.remove_output(output);
+            session.remove_input(input);
+        }
+
+        /// How much of the hardware's bandwidth the cameras take, as a fraction. The session fails to start above `1.0`.
+        pub fn hardware_cost(&self) -> f64 {
+            let cost: CGFloat = unsafe { msg_send![self.inner, hardwareCost] };
+            cost
+        }
+    }
 }
 
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 pub use crate::internal::*;
+
+#[cfg(target_os = "macos")]
+mod virtual_camera;
+#[cfg(target_os = "macos")]
+pub use crate::virtual_camera::*;
+
+#[cfg(all(feature = "metal", any(target_os = "macos", target_os = "ios")))]
+mod metal;
+#[cfg(all(feature = "metal", any(target_os = "macos", target_os = "ios")))]
+pub use crate::metal::*;
@@ -0,0 +1,231 @@
+/*
+* Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+*
+* Licensed under the Apache License, Version 2.0 (the "License");
+* you may not use this file except in compliance with the License.
+* You may obtain a copy of the License at
+*
+*     http://www.apache.org/licenses/LICENSE-2.0
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+*/
+
+//! `MTLTexture`s of captured frames through a `CVMetalTextureCache`, so they can be drawn without touching the CPU.
+
+use crate::internal::{
+    core_media::{
+        CVPixelBufferGetHeightOfPlane, CVPixelBufferGetWidthOfPlane, CVPixelBufferRef, OSType,
+    },
+    PixelBuffer,
+};
+use core_video_sys::{
+    kCVPixelFormatType_32BGRA, kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+    kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange, kCVPixelFormatType_422YpCbCr8,
+    kCVPixelFormatType_422YpCbCr8_yuvs,
+};
+use nokhwa_core::error::NokhwaError;
+use objc::runtime::Object;
+use std::{ffi::c_void, ptr};
+
+type CVMetalTextureCacheRef = *mut c_void;
+type CVMetalTextureRef = *mut c_void;
+type MTLPixelFormat = usize;
+
+// see MTLPixelFormat.h
+const MTL_PIXEL_FORMAT_R8_UNORM: MTLPixelFormat = 10;
+const MTL_PIXEL_FORMAT_RG8_UNORM: MTLPixelFormat = 30;
+const MTL_PIXEL_FORMAT_BGRA8_UNORM: MTLPixelFormat = 80;
+const MTL_PIXEL_FORMAT_GBGR422: MTLPixelFormat = 240;
+const MTL_PIXEL_FORMAT_BGRG422: MTLPixelFormat = 241;
+
+#[allow(non_snake_case)]
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    fn CVMetalTextureCacheCreate(
+        allocator: *const c_void,
+        cacheAttributes: *const c_void,
+        metalDevice: *mut Object,
+        textureAttributes: *const c_void,
+        cacheOut: *mut CVMetalTextureCacheRef,
+    ) -> i32;
+
+    fn CVMetalTextureCacheCreateTextureFromImage(
+        allocator: *const c_void,
+        textureCache: CVMetalTextureCacheRef,
+        sourceImage: CVPixelBufferRef,
+        textureAttributes: *const c_void,
+        pixelFormat: MTLPixelFormat,
+        width: usize,
+        height: usize,
+        planeIndex: usize,
+        textureOut: *mut CVMetalTextureRef,
+    ) -> i32;
+
+    fn CVMetalTextureCacheFlush(textureCache: CVMetalTextureCacheRef, options: u64);
+
+    fn CVMetalTextureGetTexture(image: CVMetalTextureRef) -> *mut Object;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+// The Metal pixel format of each plane. Luma and chroma of biplanar formats are separate textures, to be combined by a shader.
+#[allow(non_upper_case_globals)]
+fn plane_formats(pixel_format: OSType) -> Option<&'static [MTLPixelFormat]> {
+    match pixel_format {
+        kCVPixelFormatType_32BGRA => Some(&[MTL_PIXEL_FORMAT_BGRA8_UNORM]),
+        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+        | kCVPixelFormatType_420YpCbCr8BiPlanarFullRange => {
+            Some(&[MTL_PIXEL_FORMAT_R8_UNORM, MTL_PIXEL_FORMAT_RG8_UNORM])
+        }
+        // '2vuy' is UYVY, 'yuvs' is YUYV
+        kCVPixelFormatType_422YpCbCr8 => Some(&[MTL_PIXEL_FORMAT_BGRG422]),
+        kCVPixelFormatType_422YpCbCr8_yuvs => Some(&[MTL_PIXEL_FORMAT_GBGR422]),
+        _ => None,
+    }
+}
+
+/// Creates `MTLTexture`s that share memory with [`PixelBuffer`]s, for one `MTLDevice`.
+pub struct MetalTextureCache {
+    cache: CVMetalTextureCacheRef,
+}
+
+// SAFETY: CVMetalTextureCache is thread safe
+unsafe impl Send for MetalTextureCache {}
+unsafe impl Sync for MetalTextureCache {}
+
+impl MetalTextureCache {
+    /// Creates a cache for textures used with `device`, the `id<MTLDevice>` the application renders with
+    /// (e.g. `metal::Device::as_ptr()`).
+    /// # Errors
+    /// If `CoreVideo` fails to create the cache, this will error.
+    /// # Safety
+    /// `device` must be a valid `id<MTLDevice>`.
+    pub unsafe fn new(device: *mut Object) -> Result<Self, NokhwaError> {
+        let mut cache = ptr::null_mut();
+        let status =
+            CVMetalTextureCacheCreate(ptr::null(), ptr::null(), device, ptr::null(), &mut cache);
+        if status != 0 || cache.is_null() {
+            return Err(NokhwaError::GeneralError(format!(
+                "Failed to create CVMetalTextureCache: {status}"
+            )));
+        }
+        Ok(MetalTextureCache { cache })
+    }
+
+    /// Creates the textures of `pixel_buffer`, one per plane. BGRA and packed 4:2:2 frames are one texture, NV12 frames are a
+    /// `R8Unorm` luma and a `RG8Unorm` chroma texture.
+    /// # Errors
+    /// If the pixel format has no Metal equivalent, or `CoreVideo` fails to create a texture, this will error.
+    pub fn textures(&self, pixel_buffer: &PixelBuffer) -> Result<Vec<MetalTexture>, NokhwaError> {
+        let pixel_format = pixel_buffer.pixel_format();
+        let formats = plane_formats(pixel_format).ok_or_else(|| {
+            NokhwaError::GeneralError(format!(
+                "Pixel format {pixel_format:#010x} has no Metal texture format"
+            ))
+        })?;
+
+        formats
+            .iter()
+            .enumerate()
+            .map(|(plane, format)| {
+                let raw = pixel_buffer.as_raw();
+                let (width, height) = if formats.len() > 1 {
+                    unsafe {
+                        (
+                            CVPixelBufferGetWidthOfPlane(raw, plane),
+                            CVPixelBufferGetHeightOfPlane(raw, plane),
+                        )
+                    }
+                } else {
+                    let resolution = pixel_buffer.resolution();
+                    (resolution.width() as usize, resolution.height() as usize)
+                };
+
+                let mut texture = ptr::null_mut();
+                let status = unsafe {
+                    CVMetalTextureCacheCreateTextureFromImage(
+                        ptr::null(),
+                        self.cache,
+                        raw,
+                        ptr::null(),
+                        *format,
+                        width,
+                        height,
+                        plane,
+                        &mut texture,
+                    )
+                };
+                if status != 0 || texture.is_null() {
+                    return Err(NokhwaError::GeneralError(format!(
+                        "Failed to create Metal texture of plane {plane}: {status}"
+                    )));
+                }
+                Ok(MetalTexture {
+                    texture,
+                    plane,
+                    _pixel_buffer: pixel_buffer.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Frees textures that are no longer in use. Call this once per frame, or every few frames.
+    pub fn flush(&self) {
+        unsafe { CVMetalTextureCacheFlush(self.cache, 0) };
+    }
+}
+
+impl Drop for MetalTextureCache {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.cache) };
+    }
+}
+
+/// An `MTLTexture` that shares memory with a [`PixelBuffer`], which it keeps alive.
+pub struct MetalTexture {
+    texture: CVMetalTextureRef,
+    plane: usize,
+    _pixel_buffer: PixelBuffer,
+}
+
+// SAFETY: CVMetalTextures are reference counted CF objects that may be released from any thread
+unsafe impl Send for MetalTexture {}
+
+impl MetalTexture {
+    /// Gets the `id<MTLTexture>`. It is only valid while this is alive, so keep this until the GPU is done with the texture
+    /// (e.g. in the command buffer's completion handler).
+    pub fn texture(&self) -> *mut Object {
+        unsafe { CVMetalTextureGetTexture(self.texture) }
+    }
+
+    /// Gets the index of the plane of the pixel buffer this is a texture of.
+    pub fn plane(&self) -> usize {
+        self.plane
+    }
+}
+
+impl Drop for MetalTexture {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.texture) };
+    }
+}
+
+impl PixelBuffer {
+    /// Creates the `MTLTexture`s of this frame, see [`MetalTextureCache::textures()`].
+    /// # Errors
+    /// If the pixel format has no Metal equivalent, or `CoreVideo` fails to create a texture, this will error.
+    pub fn metal_textures(
+        &self,
+        cache: &MetalTextureCache,
+    ) -> Result<Vec<MetalTexture>, NokhwaError> {
+        cache.textures(self)
+    }
+}
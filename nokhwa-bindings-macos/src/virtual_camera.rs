@@ -0,0 +1,869 @@
+/*
+* Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+*
+* Licensed under the Apache License, Version 2.0 (the "License");
+* you may not use this file except in compliance with the License.
+* You may obtain a copy of the License at
+*
+*     http://www.apache.org/licenses/LICENSE-2.0
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+*/
+
+//! A virtual camera backed by a `CoreMediaIO` Camera Extension, which `nokhwa` feeds frames through the extension's sink stream.
+
+use crate::internal::core_media::{
+    kCVPixelBufferPixelFormatTypeKey, CMClockGetHostTimeClock, CMTimeMake,
+    CMVideoFormatDescriptionGetDimensions, CVPixelBufferGetBaseAddress,
+    CVPixelBufferGetBaseAddressOfPlane, CVPixelBufferGetBytesPerRow,
+    CVPixelBufferGetBytesPerRowOfPlane, CVPixelBufferLockBaseAddress, CVPixelBufferRef,
+    CVPixelBufferRelease, CVPixelBufferUnlockBaseAddress, OSType,
+};
+use core_media_sys::{
+    kCMTimeInvalid, CMClockRef, CMFormatDescriptionGetMediaSubType, CMFormatDescriptionRef,
+    CMSampleBufferRef, CMSampleTimingInfo, CMTime,
+};
+use core_video_sys::{
+    kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange, kCVPixelFormatType_422YpCbCr8,
+    kCVPixelFormatType_422YpCbCr8_yuvs,
+};
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    traits::VirtualBackendTrait,
+    types::{ApiBackend, CameraFormat, CameraIndex, CameraInfo, FrameFormat},
+};
+use objc::{
+    declare::ClassDecl,
+    runtime::{Class, Object, Protocol, Sel},
+};
+use once_cell::sync::Lazy;
+use std::{
+    ffi::{c_void, CStr},
+    mem, ptr, thread,
+    time::{Duration, Instant},
+};
+
+type CMIOObjectID = u32;
+type CMSimpleQueueRef = *mut c_void;
+type CVPixelBufferPoolRef = *mut c_void;
+type CMIODeviceStreamQueueAlteredProc =
+    Option<extern "C" fn(stream: CMIOObjectID, token: *mut c_void, ref_con: *mut c_void)>;
+
+#[repr(C)]
+struct CMIOObjectPropertyAddress {
+    selector: u32,
+    scope: u32,
+    element: u32,
+}
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+// see CMIOHardwareObject.h, CMIOHardwareDevice.h and CMIOHardwareStream.h
+const CMIO_OBJECT_SYSTEM_OBJECT: CMIOObjectID = 1;
+const CMIO_OBJECT_PROPERTY_SCOPE_GLOBAL: u32 = fourcc(b"glob");
+const CMIO_OBJECT_PROPERTY_ELEMENT_MAIN: u32 = 0;
+const CMIO_OBJECT_PROPERTY_NAME: u32 = fourcc(b"lnam");
+const CMIO_HARDWARE_PROPERTY_DEVICES: u32 = fourcc(b"dev#");
+const CMIO_DEVICE_PROPERTY_DEVICE_UID: u32 = fourcc(b"uid ");
+const CMIO_DEVICE_PROPERTY_STREAMS: u32 = fourcc(b"stm#");
+const CMIO_STREAM_PROPERTY_DIRECTION: u32 = fourcc(b"sdir");
+const CMIO_STREAM_PROPERTY_FORMAT_DESCRIPTION: u32 = fourcc(b"pft ");
+// a stream the application writes into, instead of reading from
+const CMIO_STREAM_DIRECTION_SINK: u32 = 1;
+
+// how long writing waits for the extension to take frames out of a full queue
+const DEFAULT_FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[allow(non_snake_case)]
+#[link(name = "CoreMediaIO", kind = "framework")]
+extern "C" {
+    fn CMIOObjectGetPropertyDataSize(
+        objectID: CMIOObjectID,
+        address: *const CMIOObjectPropertyAddress,
+        qualifierDataSize: u32,
+        qualifierData: *const c_void,
+        dataSize: *mut u32,
+    ) -> i32;
+
+    fn CMIOObjectGetPropertyData(
+        objectID: CMIOObjectID,
+        address: *const CMIOObjectPropertyAddress,
+        qualifierDataSize: u32,
+        qualifierData: *const c_void,
+        dataSize: u32,
+        dataUsed: *mut u32,
+        data: *mut c_void,
+    ) -> i32;
+
+    fn CMIOObjectSetPropertyData(
+        objectID: CMIOObjectID,
+        address: *const CMIOObjectPropertyAddress,
+        qualifierDataSize: u32,
+        qualifierData: *const c_void,
+        dataSize: u32,
+        data: *const c_void,
+    ) -> i32;
+
+    fn CMIOObjectIsPropertySettable(
+        objectID: CMIOObjectID,
+        address: *const CMIOObjectPropertyAddress,
+        isSettable: *mut u8,
+    ) -> i32;
+
+    fn CMIOStreamCopyBufferQueue(
+        streamID: CMIOObjectID,
+        queueAlteredProc: CMIODeviceStreamQueueAlteredProc,
+        queueAlteredRefCon: *mut c_void,
+        queue: *mut CMSimpleQueueRef,
+    ) -> i32;
+
+    fn CMIODeviceStartStream(deviceID: CMIOObjectID, streamID: CMIOObjectID) -> i32;
+
+    fn CMIODeviceStopStream(deviceID: CMIOObjectID, streamID: CMIOObjectID) -> i32;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "CoreMedia", kind = "framework")]
+extern "C" {
+    fn CMSimpleQueueEnqueue(queue: CMSimpleQueueRef, element: *const c_void) -> i32;
+
+    fn CMSimpleQueueGetCount(queue: CMSimpleQueueRef) -> i32;
+
+    fn CMSimpleQueueGetCapacity(queue: CMSimpleQueueRef) -> i32;
+
+    fn CMClockGetTime(clock: CMClockRef) -> CMTime;
+
+    fn CMVideoFormatDescriptionCreate(
+        allocator: *const c_void,
+        codecType: OSType,
+        width: i32,
+        height: i32,
+        extensions: *const c_void,
+        formatDescriptionOut: *mut CMFormatDescriptionRef,
+    ) -> i32;
+
+    fn CMVideoFormatDescriptionCreateForImageBuffer(
+        allocator: *const c_void,
+        imageBuffer: CVPixelBufferRef,
+        formatDescriptionOut: *mut CMFormatDescriptionRef,
+    ) -> i32;
+
+    fn CMSampleBufferCreateReadyWithImageBuffer(
+        allocator: *const c_void,
+        imageBuffer: CVPixelBufferRef,
+        formatDescription: CMFormatDescriptionRef,
+        sampleTiming: *const CMSampleTimingInfo,
+        sampleBufferOut: *mut CMSampleBufferRef,
+    ) -> i32;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    static kCVPixelBufferWidthKey: *mut Object;
+    static kCVPixelBufferHeightKey: *mut Object;
+    static kCVPixelBufferIOSurfacePropertiesKey: *mut Object;
+
+    fn CVPixelBufferPoolCreate(
+        allocator: *const c_void,
+        poolAttributes: *const c_void,
+        pixelBufferAttributes: *mut Object,
+        poolOut: *mut CVPixelBufferPoolRef,
+    ) -> i32;
+
+    fn CVPixelBufferPoolCreatePixelBuffer(
+        allocator: *const c_void,
+        pixelBufferPool: CVPixelBufferPoolRef,
+        pixelBufferOut: *mut CVPixelBufferRef,
+    ) -> i32;
+}
+
+#[allow(non_snake_case)]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+#[link(name = "SystemExtensions", kind = "framework")]
+extern "C" {}
+
+extern "C" {
+    fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut Object;
+}
+
+fn address(selector: u32) -> CMIOObjectPropertyAddress {
+    CMIOObjectPropertyAddress {
+        selector,
+        scope: CMIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+        element: CMIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+    }
+}
+
+fn property_error(property: &str, status: i32) -> NokhwaError {
+    NokhwaError::GetPropertyError {
+        property: property.to_string(),
+        error: format!("OSStatus {status}"),
+    }
+}
+
+// Reads a property holding an array of `T`.
+fn property_data<T: Copy>(
+    object: CMIOObjectID,
+    selector: u32,
+    property: &str,
+) -> Result<Vec<T>, NokhwaError> {
+    let address = address(selector);
+    let mut size = 0;
+    let status =
+        unsafe { CMIOObjectGetPropertyDataSize(object, &address, 0, ptr::null(), &mut size) };
+    if status != 0 {
+        return Err(property_error(property, status));
+    }
+    let mut data = Vec::<T>::with_capacity(size as usize / mem::size_of::<T>());
+    let mut used = 0;
+    let status = unsafe {
+        CMIOObjectGetPropertyData(
+            object,
+            &address,
+            0,
+            ptr::null(),
+            size,
+            &mut used,
+            data.as_mut_ptr().cast(),
+        )
+    };
+    if status != 0 {
+        return Err(property_error(property, status));
+    }
+    unsafe { data.set_len(used as usize / mem::size_of::<T>()) };
+    Ok(data)
+}
+
+// Reads a property holding a CFString, which the caller owns.
+fn property_string(object: CMIOObjectID, selector: u32, property: &str) -> Option<String> {
+    let string = *property_data::<*mut Object>(object, selector, property)
+        .ok()?
+        .first()?;
+    if string.is_null() {
+        return None;
+    }
+    let utf8: *const std::os::raw::c_char = unsafe { msg_send![string, UTF8String] };
+    let value = (!utf8.is_null()).then(|| {
+        unsafe { CStr::from_ptr(utf8) }
+            .to_string_lossy()
+            .to_string()
+    });
+    unsafe { CFRelease(string.cast()) };
+    value
+}
+
+// The first stream of `device` that applications write into.
+fn sink_stream(device: CMIOObjectID) -> Option<CMIOObjectID> {
+    property_data::<CMIOObjectID>(device, CMIO_DEVICE_PROPERTY_STREAMS, "Streams")
+        .ok()?
+        .into_iter()
+        .find(|stream| {
+            property_data::<u32>(*stream, CMIO_STREAM_PROPERTY_DIRECTION, "Direction")
+                .ok()
+                .and_then(|direction| direction.first().copied())
+                == Some(CMIO_STREAM_DIRECTION_SINK)
+        })
+}
+
+// Every `CoreMediaIO` device with a sink stream, with its name and unique ID.
+fn virtual_devices() -> Result<Vec<(CMIOObjectID, String, String)>, NokhwaError> {
+    let devices = property_data::<CMIOObjectID>(
+        CMIO_OBJECT_SYSTEM_OBJECT,
+        CMIO_HARDWARE_PROPERTY_DEVICES,
+        "Devices",
+    )?;
+    Ok(devices
+        .into_iter()
+        .filter(|device| sink_stream(*device).is_some())
+        .map(|device| {
+            let name =
+                property_string(device, CMIO_OBJECT_PROPERTY_NAME, "Name").unwrap_or_default();
+            let uid = property_string(device, CMIO_DEVICE_PROPERTY_DEVICE_UID, "DeviceUID")
+                .unwrap_or_default();
+            (device, name, uid)
+        })
+        .collect())
+}
+
+/// Lists the virtual cameras of installed Camera Extensions that have a sink stream, which [`CMIOVirtualCamera`] can write to.
+/// Their [`CameraIndex`] is a [`CameraIndex::String`] of the device's unique ID.
+/// # Errors
+/// If `CoreMediaIO` fails to list its devices, this will error.
+pub fn query_virtual_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Ok(virtual_devices()?
+        .into_iter()
+        .map(|(_, name, uid)| {
+            CameraInfo::new(
+                &name,
+                "Camera Extension",
+                &uid,
+                &CameraIndex::String(uid.clone()),
+            )
+        })
+        .collect())
+}
+
+#[allow(non_upper_case_globals)]
+fn frameformat_to_raw_fcc(format: FrameFormat) -> Option<OSType> {
+    match format {
+        FrameFormat::Nv12 => Some(kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange),
+        // '2vuy' is UYVY, 'yuvs' is YUYV
+        FrameFormat::Uyv422 => Some(kCVPixelFormatType_422YpCbCr8),
+        FrameFormat::Yuv422 => Some(kCVPixelFormatType_422YpCbCr8_yuvs),
+        _ => None,
+    }
+}
+
+// The bytes per row and rows of each plane of a `width` x `height` frame.
+fn plane_layout(format: FrameFormat, width: usize, height: usize) -> Vec<(usize, usize)> {
+    match format {
+        FrameFormat::Nv12 => vec![(width, height), (width, (height + 1) / 2)],
+        _ => vec![(width * 2, height)],
+    }
+}
+
+static EXTENSION_DELEGATE_CLASS: Lazy<&'static Class> = Lazy::new(|| {
+    let mut decl = ClassDecl::new("NokhwaExtensionRequestDelegate", class!(NSObject)).unwrap();
+
+    decl.add_ivar::<*mut c_void>("_callbackptr");
+
+    // takes the callback out, so it is called once
+    fn finish(this: &mut Object, result: Result<(), NokhwaError>) {
+        let callback_ptr: *mut c_void = unsafe { *this.get_ivar("_callbackptr") };
+        if callback_ptr.is_null() {
+            return;
+        }
+        unsafe { this.set_ivar("_callbackptr", ptr::null_mut::<c_void>()) };
+        let callback = unsafe { Box::from_raw(callback_ptr.cast::<ActivationCallback>()) };
+        callback(result);
+        // the request only holds on to its delegate weakly, so it kept itself alive until now
+        let _: *mut Object = unsafe { msg_send![this, autorelease] };
+    }
+
+    extern "C" fn replacement_action(
+        _: &Object,
+        _: Sel,
+        _: *mut Object,
+        _: *mut Object,
+        _: *mut Object,
+    ) -> isize {
+        // OSSystemExtensionReplacementActionReplace, so an updated application updates its extension
+        1
+    }
+    extern "C" fn needs_user_approval(_: &Object, _: Sel, _: *mut Object) {}
+    extern "C" fn did_finish(this: &mut Object, _: Sel, _: *mut Object, _: isize) {
+        finish(this, Ok(()));
+    }
+    extern "C" fn did_fail(this: &mut Object, _: Sel, _: *mut Object, error: *mut Object) {
+        let description: *mut Object = unsafe { msg_send![error, localizedDescription] };
+        let utf8: *const std::os::raw::c_char = unsafe { msg_send![description, UTF8String] };
+        let message = unsafe { CStr::from_ptr(utf8) }
+            .to_string_lossy()
+            .to_string();
+        finish(
+            this,
+            Err(NokhwaError::InitializeError {
+                backend: ApiBackend::AVFoundation,
+                error: message,
+            }),
+        );
+    }
+
+    unsafe {
+        decl.add_method(
+            sel!(request:actionForReplacingExtension:withExtension:),
+            replacement_action
+                as extern "C" fn(&Object, Sel, *mut Object, *mut Object, *mut Object) -> isize,
+        );
+        decl.add_method(
+            sel!(requestNeedsUserApproval:),
+            needs_user_approval as extern "C" fn(&Object, Sel, *mut Object),
+        );
+        decl.add_method(
+            sel!(request:didFinishWithResult:),
+            did_finish as extern "C" fn(&mut Object, Sel, *mut Object, isize),
+        );
+        decl.add_method(
+            sel!(request:didFailWithError:),
+            did_fail as extern "C" fn(&mut Object, Sel, *mut Object, *mut Object),
+        );
+
+        decl.add_protocol(Protocol::get("OSSystemExtensionRequestDelegate").unwrap());
+    }
+
+    decl.register()
+});
+
+type ActivationCallback = Box<dyn FnOnce(Result<(), NokhwaError>) + Send + 'static>;
+
+/// Asks macOS to activate the Camera Extension with the bundle identifier `extension_id`, which must be bundled in the running
+/// application's `Contents/Library/SystemExtensions`. `callback` is called from a different thread once it is active, or activation
+/// failed (e.g. the user denied it).
+///
+/// The first activation waits for the user to allow the extension in System Settings. Once active, the extension's virtual camera shows up
+/// in [`query_virtual_cameras()`].
+pub fn request_extension_activation(
+    extension_id: &str,
+    callback: impl FnOnce(Result<(), NokhwaError>) + Send + 'static,
+) {
+    let callback: Box<ActivationCallback> = Box::new(Box::new(callback));
+    unsafe {
+        let cls = &EXTENSION_DELEGATE_CLASS as &Class;
+        let delegate: *mut Object = msg_send![cls, new];
+        (*delegate).set_ivar("_callbackptr", Box::into_raw(callback).cast::<c_void>());
+
+        let identifier: *mut Object = msg_send![class!(NSString), alloc];
+        let identifier: *mut Object = msg_send![
+            identifier,
+            initWithBytes: extension_id.as_ptr().cast::<c_void>()
+            length: extension_id.len()
+            encoding: 4_usize
+        ];
+        let request: *mut Object = msg_send![
+            class!(OSSystemExtensionRequest),
+            activationRequestForExtension: identifier
+            queue: dispatch_get_global_queue(0, 0)
+        ];
+        let _: () = msg_send![identifier, release];
+        let _: () = msg_send![request, setDelegate: delegate];
+        let manager: *mut Object = msg_send![class!(OSSystemExtensionManager), sharedManager];
+        let _: () = msg_send![manager, submitRequest: request];
+    }
+}
+
+/// A virtual camera backed by a `CoreMediaIO` Camera Extension, which FaceTime, browsers and conferencing apps capture from
+/// like from any other camera. `nokhwa` writes frames into the extension's sink stream, which the extension passes on to its source stream.
+/// To see what this does, please see [`VirtualBackendTrait`].
+/// # Quirks
+/// - The Camera Extension itself is not part of `nokhwa`. The application has to bundle one with a sink stream (e.g. Xcode's Camera
+///   Extension template, forwarding sink buffers to its source stream), and activate it with [`request_extension_activation()`].
+/// - Only [`FrameFormat::Nv12`], [`FrameFormat::Yuv422`] and [`FrameFormat::Uyv422`] can be written. Extensions usually fix the
+///   format of their sink stream, in which case [`set_camera_format()`](VirtualBackendTrait::set_camera_format) only accepts that format.
+/// - Frames are paced to the frame rate of the [`CameraFormat`]. If the extension stops taking frames, writing waits up to a second
+///   and then fails with [`NokhwaError::TimeoutError`].
+pub struct CMIOVirtualCamera {
+    device: CMIOObjectID,
+    stream: CMIOObjectID,
+    queue: CMSimpleQueueRef,
+    pool: CVPixelBufferPoolRef,
+    info: CameraInfo,
+    camera_format: CameraFormat,
+    next_frame: Option<Instant>,
+}
+
+// SAFETY: CoreMediaIO objects are IDs, and simple queues and pixel buffer pools may be used from any thread
+unsafe impl Send for CMIOVirtualCamera {}
+
+impl CMIOVirtualCamera {
+    /// Opens the sink stream of the virtual camera at `index` (see [`query_virtual_cameras()`]), matching a [`CameraIndex::String`]
+    /// against the unique ID or name of the device, and negotiates `camera_format` with it.
+    /// # Errors
+    /// This will error if there is no such virtual camera, its sink stream can not be opened, or it rejects the format.
+    pub fn new(index: &CameraIndex, camera_format: CameraFormat) -> Result<Self, NokhwaError> {
+        let devices = virtual_devices()?;
+        let found = match index {
+            CameraIndex::Index(index) => devices.into_iter().nth(*index as usize),
+            CameraIndex::String(id) => devices
+                .into_iter()
+                .find(|(_, name, uid)| uid == id || name == id),
+        };
+        let (device, name, uid) = found.ok_or_else(|| {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such virtual camera".to_string())
+        })?;
+        let stream = sink_stream(device).ok_or_else(|| {
+            NokhwaError::OpenDeviceError(uid.clone(), "No sink stream".to_string())
+        })?;
+
+        let mut queue = ptr::null_mut();
+        let status =
+            unsafe { CMIOStreamCopyBufferQueue(stream, None, ptr::null_mut(), &mut queue) };
+        if status != 0 || queue.is_null() {
+            return Err(NokhwaError::OpenStreamError(format!(
+                "Failed to get the sink stream's queue: OSStatus {status}"
+            )));
+        }
+
+        let mut camera = CMIOVirtualCamera {
+            device,
+            stream,
+            queue,
+            pool: ptr::null_mut(),
+            info: CameraInfo::new(
+                &name,
+                "Camera Extension",
+                &uid,
+                &CameraIndex::String(uid.clone()),
+            ),
+            camera_format,
+            next_frame: None,
+        };
+        camera.set_camera_format(camera_format)?;
+
+        let status = unsafe { CMIODeviceStartStream(device, stream) };
+        if status != 0 {
+            return Err(NokhwaError::OpenStreamError(format!(
+                "Failed to start the sink stream: OSStatus {status}"
+            )));
+        }
+        Ok(camera)
+    }
+
+    /// Gets the [`CameraInfo`] of the virtual camera.
+    #[must_use]
+    pub fn info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    // Sleeps until the next frame is due, without trying to catch up after a stall.
+    fn pace(&mut self) {
+        let interval =
+            Duration::from_secs_f32(1.0 / self.camera_format.frame_rate().as_float().max(1.0));
+        let now = Instant::now();
+        let due = match self.next_frame {
+            Some(due) if due > now => {
+                thread::sleep(due - now);
+                due
+            }
+            _ => now,
+        };
+        self.next_frame = Some(due + interval);
+    }
+
+    // Checks the format the sink stream currently has, changing it if the extension allows.
+    fn negotiate(&self, pixel_format: OSType, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        let format_address = address(CMIO_STREAM_PROPERTY_FORMAT_DESCRIPTION);
+        let current = property_data::<CMFormatDescriptionRef>(
+            self.stream,
+            CMIO_STREAM_PROPERTY_FORMAT_DESCRIPTION,
+            "FormatDescription",
+        )?
+        .first()
+        .copied()
+        .unwrap_or(ptr::null_mut());
+        if !current.is_null() {
+            let dimensions = unsafe { CMVideoFormatDescriptionGetDimensions(current) };
+            let subtype = unsafe { CMFormatDescriptionGetMediaSubType(current) };
+            unsafe { CFRelease(current.cast()) };
+            if dimensions.width as u32 == new_fmt.width()
+                && dimensions.height as u32 == new_fmt.height()
+                && subtype == pixel_format
+            {
+                return Ok(());
+            }
+        }
+
+        let mut settable = 0;
+        unsafe { CMIOObjectIsPropertySettable(self.stream, &format_address, &mut settable) };
+        if settable == 0 {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Resolution, FrameFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "The sink stream's format is fixed by the Camera Extension".to_string(),
+            });
+        }
+        let mut description = ptr::null_mut();
+        let status = unsafe {
+            CMVideoFormatDescriptionCreate(
+                ptr::null(),
+                pixel_format,
+                new_fmt.width() as i32,
+                new_fmt.height() as i32,
+                ptr::null(),
+                &mut description,
+            )
+        };
+        if status != 0 {
+            return Err(NokhwaError::StructureError {
+                structure: "CMVideoFormatDescription".to_string(),
+                error: format!("OSStatus {status}"),
+            });
+        }
+        let status = unsafe {
+            CMIOObjectSetPropertyData(
+                self.stream,
+                &format_address,
+                0,
+                ptr::null(),
+                mem::size_of::<CMFormatDescriptionRef>() as u32,
+                (&description as *const CMFormatDescriptionRef).cast(),
+            )
+        };
+        unsafe { CFRelease(description.cast()) };
+        if status != 0 {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Resolution, FrameFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: format!("OSStatus {status}"),
+            });
+        }
+        Ok(())
+    }
+
+    // Copies `buffer` into `pixel_buffer` row by row, since their strides may differ.
+    fn copy_into(
+        &self,
+        buffer: &Buffer,
+        pixel_buffer: CVPixelBufferRef,
+    ) -> Result<(), NokhwaError> {
+        let layout = plane_layout(
+            self.camera_format.format(),
+            self.camera_format.width() as usize,
+            self.camera_format.height() as usize,
+        );
+        // a single contiguous buffer holds the planes back to back
+        let mut offset = 0;
+        let sources = layout
+            .iter()
+            .enumerate()
+            .map(
+                |(index, (row_bytes, rows))| match buffer.planes().get(index) {
+                    Some(plane) if buffer.planes().len() == layout.len() => (
+                        buffer.plane(index).unwrap_or_default(),
+                        plane.stride().unwrap_or(*row_bytes),
+                    ),
+                    _ => {
+                        let start = offset.min(buffer.buffer().len());
+                        offset += row_bytes * rows;
+                        let end = offset.min(buffer.buffer().len());
+                        (&buffer.buffer()[start..end], *row_bytes)
+                    }
+                },
+            )
+            .collect::<Vec<(&[u8], usize)>>();
+        if let Some(((data, stride), (row_bytes, rows))) =
+            sources
+                .iter()
+                .zip(&layout)
+                .find(|((data, stride), (row_bytes, rows))| {
+                    data.len() < stride * rows.saturating_sub(1) + row_bytes
+                })
+        {
+            return Err(NokhwaError::ProcessFrameError {
+                src: buffer.source_frame_format(),
+                destination: "Camera Extension".to_string(),
+                error: format!(
+                    "Plane is {} bytes, expected {rows} rows of {row_bytes} bytes every {stride} bytes",
+                    data.len()
+                ),
+            });
+        }
+
+        unsafe { CVPixelBufferLockBaseAddress(pixel_buffer, 0) };
+        for (plane, ((data, stride), (row_bytes, rows))) in sources.iter().zip(&layout).enumerate()
+        {
+            let (base, destination_stride) = unsafe {
+                if layout.len() > 1 {
+                    (
+                        CVPixelBufferGetBaseAddressOfPlane(pixel_buffer, plane),
+                        CVPixelBufferGetBytesPerRowOfPlane(pixel_buffer, plane),
+                    )
+                } else {
+                    (
+                        CVPixelBufferGetBaseAddress(pixel_buffer),
+                        CVPixelBufferGetBytesPerRow(pixel_buffer),
+                    )
+                }
+            };
+            if base.is_null() {
+                continue;
+            }
+            for row in 0..*rows {
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        data[row * stride..].as_ptr(),
+                        base.cast::<u8>().add(row * destination_stride),
+                        (*row_bytes).min(destination_stride),
+                    );
+                }
+            }
+        }
+        unsafe { CVPixelBufferUnlockBaseAddress(pixel_buffer, 0) };
+        Ok(())
+    }
+
+    // Waits until the extension has room for another frame.
+    fn wait_for_room(&self) -> Result<(), NokhwaError> {
+        let start = Instant::now();
+        while unsafe { CMSimpleQueueGetCount(self.queue) >= CMSimpleQueueGetCapacity(self.queue) } {
+            if start.elapsed() >= DEFAULT_FRAME_TIMEOUT {
+                return Err(NokhwaError::TimeoutError(DEFAULT_FRAME_TIMEOUT));
+            }
+            // the queue does not signal space on its own
+            thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+}
+
+impl VirtualBackendTrait for CMIOVirtualCamera {
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::AVFoundation
+    }
+
+    fn camera_format(&self) -> CameraFormat {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        let pixel_format =
+            frameformat_to_raw_fcc(new_fmt.format()).ok_or(NokhwaError::SetPropertyError {
+                property: "FrameFormat".to_string(),
+                value: new_fmt.format().to_string(),
+                error: "Format can not be written to a Camera Extension".to_string(),
+            })?;
+        self.negotiate(pixel_format, new_fmt)?;
+
+        let attributes: *mut Object = unsafe {
+            let attributes: *mut Object = msg_send![class!(NSMutableDictionary), dictionary];
+            let pixel_format: *mut Object =
+                msg_send![class!(NSNumber), numberWithUnsignedInt: pixel_format];
+            let width: *mut Object =
+                msg_send![class!(NSNumber), numberWithUnsignedInt: new_fmt.width()];
+            let height: *mut Object =
+                msg_send![class!(NSNumber), numberWithUnsignedInt: new_fmt.height()];
+            // frames cross into the extension's process through their IOSurface
+            let surface_properties: *mut Object = msg_send![class!(NSDictionary), dictionary];
+            let _: () = msg_send![
+                attributes,
+                setObject: pixel_format
+                forKey: kCVPixelBufferPixelFormatTypeKey.0
+            ];
+            let _: () = msg_send![attributes, setObject: width forKey: kCVPixelBufferWidthKey];
+            let _: () = msg_send![attributes, setObject: height forKey: kCVPixelBufferHeightKey];
+            let _: () = msg_send![
+                attributes,
+                setObject: surface_properties
+                forKey: kCVPixelBufferIOSurfacePropertiesKey
+            ];
+            attributes
+        };
+        let mut pool = ptr::null_mut();
+        let status =
+            unsafe { CVPixelBufferPoolCreate(ptr::null(), ptr::null(), attributes, &mut pool) };
+        if status != 0 || pool.is_null() {
+            return Err(NokhwaError::StructureError {
+                structure: "CVPixelBufferPool".to_string(),
+                error: format!("OSStatus {status}"),
+            });
+        }
+        if !self.pool.is_null() {
+            unsafe { CFRelease(self.pool) };
+        }
+
+        self.pool = pool;
+        self.camera_format = new_fmt;
+        self.next_frame = None;
+        Ok(())
+    }
+
+    fn write_frame(&mut self, buffer: &Buffer) -> Result<(), NokhwaError> {
+        if buffer.source_frame_format() != self.camera_format.format()
+            || buffer.resolution() != self.camera_format.resolution()
+        {
+            return Err(NokhwaError::ProcessFrameError {
+                src: buffer.source_frame_format(),
+                destination: "Camera Extension".to_string(),
+                error: format!(
+                    "Frame of {} does not match the device format {}",
+                    buffer.resolution(),
+                    self.camera_format
+                ),
+            });
+        }
+
+        let mut pixel_buffer = ptr::null_mut();
+        let status = unsafe {
+            CVPixelBufferPoolCreatePixelBuffer(ptr::null(), self.pool, &mut pixel_buffer)
+        };
+        if status != 0 || pixel_buffer.is_null() {
+            return Err(NokhwaError::StructureError {
+                structure: "CVPixelBuffer".to_string(),
+                error: format!("OSStatus {status}"),
+            });
+        }
+        let sample_buffer = self.copy_into(buffer, pixel_buffer).and_then(|()| {
+            let mut description = ptr::null_mut();
+            let status = unsafe {
+                CMVideoFormatDescriptionCreateForImageBuffer(
+                    ptr::null(),
+                    pixel_buffer,
+                    &mut description,
+                )
+            };
+            if status != 0 {
+                return Err(NokhwaError::StructureError {
+                    structure: "CMVideoFormatDescription".to_string(),
+                    error: format!("OSStatus {status}"),
+                });
+            }
+            let fps = self.camera_format.frame_rate().as_float().max(1.0);
+            let timing = CMSampleTimingInfo {
+                duration: unsafe { CMTimeMake(1_000_000, (fps * 1_000_000.0) as i32) },
+                presentation_time_stamp: unsafe { CMClockGetTime(CMClockGetHostTimeClock()) },
+                decode_time_stamp: unsafe { kCMTimeInvalid },
+            };
+            let mut sample_buffer = ptr::null_mut();
+            let status = unsafe {
+                CMSampleBufferCreateReadyWithImageBuffer(
+                    ptr::null(),
+                    pixel_buffer,
+                    description,
+                    &timing,
+                    &mut sample_buffer,
+                )
+            };
+            unsafe { CFRelease(description.cast()) };
+            if status != 0 || sample_buffer.is_null() {
+                return Err(NokhwaError::StructureError {
+                    structure: "CMSampleBuffer".to_string(),
+                    error: format!("OSStatus {status}"),
+                });
+            }
+            Ok(sample_buffer)
+        });
+        // the sample buffer holds its own reference
+        unsafe { CVPixelBufferRelease(pixel_buffer) };
+        let sample_buffer = sample_buffer?;
+
+        self.pace();
+        if let Err(why) = self.wait_for_room() {
+            unsafe { CFRelease(sample_buffer.cast()) };
+            return Err(why);
+        }
+        // the extension releases the sample buffer once it took it out of the queue
+        let status = unsafe { CMSimpleQueueEnqueue(self.queue, sample_buffer.cast()) };
+        if status != 0 {
+            unsafe { CFRelease(sample_buffer.cast()) };
+            return Err(NokhwaError::ProcessFrameError {
+                src: buffer.source_frame_format(),
+                destination: "Camera Extension".to_string(),
+                error: format!("Failed to enqueue the frame: OSStatus {status}"),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CMIOVirtualCamera {
+    fn drop(&mut self) {
+        unsafe {
+            CMIODeviceStopStream(self.device, self.stream);
+            CFRelease(self.queue);
+            if !self.pool.is_null() {
+                CFRelease(self.pool);
+            }
+        }
+    }
+}
@@ -29,10 +29,13 @@
 
 #[cfg(all(windows, not(feature = "docs-only")))]
 pub mod wmf {
+    use nokhwa_core::buffer::{FrameCounter, FrameTimestamp, TimestampClock};
     use nokhwa_core::error::NokhwaError;
+    use nokhwa_core::frame_format::PlatformSpecific;
     use nokhwa_core::types::{
         ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
-        ControlValueSetter, FrameFormat, KnownCameraControl, KnownCameraControlFlag, Resolution,
+        ControlValueSetter, FrameFormat, FrameRate, KnownCameraControl, KnownCameraControlFlag,
+        Resolution,
     };
     use once_cell::sync::Lazy;
     use std::ffi::c_void;
@@ -45,10 +48,12 @@ pub mod wmf {
             atomic::{AtomicBool, AtomicUsize, Ordering},
             Arc,
         },
+        time::Duration,
     };
     use windows::Win32::Media::DirectShow::{CameraControl_Flags_Auto, CameraControl_Flags_Manual};
     use windows::Win32::Media::MediaFoundation::{
-        IMFMediaType, MFCreateSample, MF_SOURCE_READER_FIRST_VIDEO_STREAM,
+        IMFMediaType, MFCreateSample, MFSampleExtension_DeviceTimestamp,
+        MF_SOURCE_READER_FIRST_VIDEO_STREAM,
     };
     use windows::{
         core::{Interface, GUID, PWSTR},
@@ -72,7 +77,8 @@ pub mod wmf {
                     MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
                     MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK, MF_MT_FRAME_RATE,
                     MF_MT_FRAME_RATE_RANGE_MAX, MF_MT_FRAME_RATE_RANGE_MIN, MF_MT_FRAME_SIZE,
-                    MF_MT_MAJOR_TYPE, MF_MT_SUBTYPE, MF_READWRITE_DISABLE_CONVERTERS,
+                    MF_MT_DEFAULT_STRIDE, MF_MT_MAJOR_TYPE, MF_MT_SUBTYPE,
+                    MF_READWRITE_DISABLE_CONVERTERS,
                 },
             },
             System::Com::{CoInitializeEx, CoUninitialize, COINIT},
@@ -105,18 +111,33 @@ pub mod wmf {
         0x0010,
         [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
     );
+    // 'Y16 ', which radiometric thermal cameras stream in
+    const MF_VIDEO_FORMAT_Y16: GUID = GUID::from_values(
+        0x2036_3159,
+        0x0000,
+        0x0010,
+        [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
+    );
     const MF_VIDEO_FORMAT_NV12: GUID = GUID::from_values(
         0x3231_564E,
         0x0000,
         0x0010,
         [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
     );
+    // stored as B, G, R
     const MF_VIDEO_FORMAT_RGB24: GUID = GUID::from_values(
         0x0000_0014,
         0x0000,
         0x0010,
         [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
     );
+    // stored as B, G, R, padding
+    const MF_VIDEO_FORMAT_RGB32: GUID = GUID::from_values(
+        0x0000_0016,
+        0x0000,
+        0x0010,
+        [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
+    );
 
     const MEDIA_FOUNDATION_FIRST_VIDEO_STREAM: u32 = 0xFFFF_FFFC;
     const MF_SOURCE_READER_MEDIASOURCE: u32 = 0xFFFF_FFFF;
@@ -162,25 +183,77 @@ pub mod wmf {
     //     };
     // }
 
-    fn guid_to_frameformat(guid: GUID) -> Option<FrameFormat> {
+    // subtypes nokhwa does not know are still listed and can be requested, their frames are handed out untouched
+    fn guid_to_frameformat(guid: GUID) -> FrameFormat {
         match guid {
-            MF_VIDEO_FORMAT_NV12 => Some(FrameFormat::NV12),
-            MF_VIDEO_FORMAT_RGB24 => Some(FrameFormat::RAWRGB),
-            MF_VIDEO_FORMAT_GRAY => Some(FrameFormat::GRAY),
-            MF_VIDEO_FORMAT_YUY2 => Some(FrameFormat::YUYV),
-            MF_VIDEO_FORMAT_MJPEG => Some(FrameFormat::MJPEG),
-            _ => None,
+            MF_VIDEO_FORMAT_NV12 => FrameFormat::Nv12,
+            MF_VIDEO_FORMAT_RGB24 => FrameFormat::Bgr8,
+            MF_VIDEO_FORMAT_RGB32 => FrameFormat::BgrA8,
+            MF_VIDEO_FORMAT_GRAY => FrameFormat::Luma8,
+            MF_VIDEO_FORMAT_Y16 => FrameFormat::Luma16,
+            MF_VIDEO_FORMAT_YUY2 => FrameFormat::Yuv422,
+            MF_VIDEO_FORMAT_MJPEG => FrameFormat::MJpeg,
+            _ => FrameFormat::PlatformSpecificCustomFormat(PlatformSpecific::new(
+                ApiBackend::MediaFoundation,
+                guid.to_u128(),
+            )),
         }
     }
 
-    fn frameformat_to_guid(frameformat: FrameFormat) -> GUID {
-        match frameformat {
-            FrameFormat::MJPEG => MF_VIDEO_FORMAT_MJPEG,
-            FrameFormat::YUYV => MF_VIDEO_FORMAT_YUY2,
-            FrameFormat::NV12 => MF_VIDEO_FORMAT_NV12,
-            FrameFormat::GRAY => MF_VIDEO_FORMAT_GRAY,
-            FrameFormat::RAWRGB => MF_VIDEO_FORMAT_RGB24,
-        }
+    // an MFRatio is the numerator in the upper 32 bits and the denominator in the lower
+    fn mf_ratio_to_frame_rate(ratio: u64) -> Option<FrameRate> {
+        FrameRate::new_rational((ratio >> 32) as u32, ratio as u32).ok()
+    }
+
+    fn frame_rate_to_mf_ratio(frame_rate: FrameRate) -> u64 {
+        let (numerator, denominator) = frame_rate.as_rational();
+        (u64::from(numerator) << 32) | u64::from(denominator)
+    }
+
+    // Media Foundation times are in 100ns units
+    fn hns_to_duration(hns: u64) -> Duration {
+        Duration::from_nanos(hns.saturating_mul(100))
+    }
+
+    // `MFSampleExtension_DeviceTimestamp` is when the device captured the frame, taken from `QueryPerformanceCounter` as `Instant`
+    // is. Sources that do not set it only have the sample time, which counts from the start of the stream.
+    fn sample_timestamp(sample: &IMFSample) -> Option<FrameTimestamp> {
+        let timestamp = match unsafe { sample.GetUINT64(&MFSampleExtension_DeviceTimestamp) } {
+            Ok(device_time) => {
+                FrameTimestamp::new(hns_to_duration(device_time), TimestampClock::Monotonic)
+            }
+            Err(_) => {
+                let sample_time = u64::try_from(unsafe { sample.GetSampleTime() }.ok()?).ok()?;
+                FrameTimestamp::new(hns_to_duration(sample_time), TimestampClock::Unknown)
+            }
+        };
+        let duration = unsafe { sample.GetSampleDuration() }
+            .ok()
+            .and_then(|duration| u64::try_from(duration).ok())
+            .filter(|duration| *duration != 0);
+        Some(match duration {
+            Some(duration) => timestamp.with_duration(hns_to_duration(duration)),
+            None => timestamp,
+        })
+    }
+
+    fn frameformat_to_guid(frameformat: FrameFormat) -> Option<GUID> {
+        let guid = match frameformat {
+            FrameFormat::MJpeg => MF_VIDEO_FORMAT_MJPEG,
+            FrameFormat::Yuv422 => MF_VIDEO_FORMAT_YUY2,
+            FrameFormat::Nv12 => MF_VIDEO_FORMAT_NV12,
+            FrameFormat::Luma8 => MF_VIDEO_FORMAT_GRAY,
+            FrameFormat::Luma16 => MF_VIDEO_FORMAT_Y16,
+            FrameFormat::Bgr8 => MF_VIDEO_FORMAT_RGB24,
+            FrameFormat::BgrA8 => MF_VIDEO_FORMAT_RGB32,
+            FrameFormat::PlatformSpecificCustomFormat(platform)
+                if platform.backend() == ApiBackend::MediaFoundation =>
+            {
+                GUID::from_u128(platform.format())
+            }
+            _ => return None,
+        };
+        Some(guid)
     }
 
     pub fn initialize_mf() -> Result<(), NokhwaError> {
@@ -406,6 +479,9 @@ pub mod wmf {
             KnownCameraControl::Exposure => MFControlId::CCValue(CameraControl_Exposure.0),
             KnownCameraControl::Iris => MFControlId::CCValue(CameraControl_Iris.0),
             KnownCameraControl::Focus => MFControlId::CCValue(CameraControl_Focus.0),
+            KnownCameraControl::JpegQuality
+            | KnownCameraControl::Privacy
+            | KnownCameraControl::Torch => return None,
             KnownCameraControl::Other(o) => {
                 if o == VideoProcAmp_ColorEnable.0 as u128 {
                     MFControlId::ProcAmpRange(o as i32)
@@ -422,6 +498,13 @@ pub mod wmf {
         is_open: Cell<bool>,
         device_specifier: CameraInfo,
         device_format: CameraFormat,
+        // `MF_MT_DEFAULT_STRIDE`, negative for bottom-up images
+        default_stride: Option<i32>,
+        last_timestamp: Option<FrameTimestamp>,
+        // the sample time of the last frame, in 100ns units, to find gaps in the stream
+        last_sample_time: Option<i64>,
+        last_sequence: Option<u64>,
+        frame_counter: FrameCounter,
         source_reader: IMFSourceReader,
     }
 
@@ -506,6 +589,11 @@ pub mod wmf {
                         is_open: Cell::new(false),
                         device_specifier: device_descriptor,
                         device_format: CameraFormat::default(),
+                        default_stride: None,
+                        last_timestamp: None,
+                        last_sample_time: None,
+                        last_sequence: None,
+                        frame_counter: FrameCounter::new(),
                         source_reader,
                     })
                 }
@@ -598,66 +686,31 @@ pub mod wmf {
                     }
                 };
 
-                // MFRatio is represented as 2 u32s in memory. This means we cann convert it to 2
-                let framerate_list = {
-                    let mut framerates = vec![0_u32; 3];
-                    if let Ok(fraction_u64) =
-                        unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE_RANGE_MAX) }
-                    {
-                        let mut numerator = (fraction_u64 >> 32) as u32;
-                        let denominator = fraction_u64 as u32;
-                        if denominator != 1 {
-                            numerator = 0;
-                        }
-                        framerates.push(numerator);
-                    };
-                    if let Ok(fraction_u64) =
-                        unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE_RANGE_MAX) }
-                    {
-                        let mut numerator = (fraction_u64 >> 32) as u32;
-                        let denominator = fraction_u64 as u32;
-                        if denominator != 1 {
-                            numerator = 0;
-                        }
-                        framerates.push(numerator);
-                    };
-                    if let Ok(fraction_u64) = unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE) } {
-                        let mut numerator = (fraction_u64 >> 32) as u32;
-                        let denominator = fraction_u64 as u32;
-                        if denominator != 1 {
-                            numerator = 0;
-                        }
-                        framerates.push(numerator);
-                    };
-                    if let Ok(fraction_u64) =
-                        unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE_RANGE_MIN) }
+                // every frame rate the media type reports, exactly, e.g. 30000/1001 for 29.97 FPS
+                let mut framerate_list = Vec::with_capacity(3);
+                for attribute in [
+                    &MF_MT_FRAME_RATE_RANGE_MAX,
+                    &MF_MT_FRAME_RATE,
+                    &MF_MT_FRAME_RATE_RANGE_MIN,
+                ] {
+                    if let Some(frame_rate) = unsafe { media_type.GetUINT64(attribute) }
+                        .ok()
+                        .and_then(mf_ratio_to_frame_rate)
                     {
-                        let mut numerator = (fraction_u64 >> 32) as u32;
-                        let denominator = fraction_u64 as u32;
-                        if denominator != 1 {
-                            numerator = 0;
+                        if !framerate_list.contains(&frame_rate) {
+                            framerate_list.push(frame_rate);
                         }
-                        framerates.push(numerator);
-                    };
-                    framerates
-                };
+                    }
+                }
 
-                let frame_fmt = match guid_to_frameformat(fourcc) {
-                    Some(fcc) => fcc,
-                    None => {
-                        index += 1;
-                        continue;
-                    },
-                };
+                let frame_fmt = guid_to_frameformat(fourcc);
 
                 for frame_rate in framerate_list {
-                    if frame_rate != 0 {
-                        camera_format_list.push(CameraFormat::new(
-                            Resolution::new(width, height),
-                            frame_fmt,
-                            frame_rate,
-                        ));
-                    }
+                    camera_format_list.push(CameraFormat::new(
+                        Resolution::new(width, height),
+                        frame_fmt,
+                        frame_rate,
+                    ));
                 }
 
                 index += 1;
@@ -946,6 +999,7 @@ pub mod wmf {
         }
 
         #[allow(clippy::cast_sign_loss)]
+        #[allow(clippy::cast_possible_wrap)]
         pub fn format_refreshed(&mut self) -> Result<CameraFormat, NokhwaError> {
             match unsafe {
                 self.source_reader
@@ -971,7 +1025,12 @@ pub mod wmf {
                     };
 
                     let frame_rate = match unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE) } {
-                        Ok(fps) => fps as u32,
+                        Ok(ratio) => mf_ratio_to_frame_rate(ratio).ok_or_else(|| {
+                            NokhwaError::GetPropertyError {
+                                property: "MF_MT_FRAME_RATE".to_string(),
+                                error: format!("Invalid frame rate {}/{}", ratio >> 32, ratio as u32),
+                            }
+                        })?,
                         Err(why) => {
                             return Err(NokhwaError::GetPropertyError {
                                 property: "MF_MT_FRAME_RATE".to_string(),
@@ -981,15 +1040,7 @@ pub mod wmf {
                     };
 
                     let format = match unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) } {
-                        Ok(fcc) => match guid_to_frameformat(fcc) {
-                            Some(ff) => ff,
-                            None => {
-                                return Err(NokhwaError::GetPropertyError {
-                                    property: "MF_MT_SUBTYPE".to_string(),
-                                    error: "Unknown".to_string(),
-                                })
-                            }
-                        },
+                        Ok(fcc) => guid_to_frameformat(fcc),
                         Err(why) => {
                             return Err(NokhwaError::GetPropertyError {
                                 property: "MF_MT_SUBTYPE".to_string(),
@@ -998,6 +1049,12 @@ pub mod wmf {
                         }
                     };
 
+                    // compressed formats have no stride, and uncompressed ones without it are tightly packed.
+                    // The stride is signed, but stored as a UINT32.
+                    self.default_stride = unsafe { media_type.GetUINT32(&MF_MT_DEFAULT_STRIDE) }
+                        .ok()
+                        .map(|stride| stride as i32);
+
                     let cfmt = CameraFormat::new(resolution, format, frame_rate);
                     self.device_format = cfmt;
 
@@ -1014,6 +1071,31 @@ pub mod wmf {
             self.device_format
         }
 
+        /// The length in bytes of one row of the luma (or only) plane, as of the last [`format_refreshed()`](Self::format_refreshed).
+        pub fn stride(&self) -> Option<usize> {
+            self.default_stride.map(|stride| stride.unsigned_abs() as usize)
+        }
+
+        /// Returns `true` if rows are stored bottom to top, as uncompressed RGB formats usually are.
+        pub fn is_bottom_up(&self) -> bool {
+            self.default_stride.is_some_and(|stride| stride < 0)
+        }
+
+        /// When the frame last returned by [`raw_bytes()`](Self::raw_bytes) was captured, `None` if the source did not say.
+        pub fn timestamp(&self) -> Option<FrameTimestamp> {
+            self.last_timestamp
+        }
+
+        /// The sequence number of the frame last returned by [`raw_bytes()`](Self::raw_bytes), counting dropped frames too.
+        pub fn sequence(&self) -> Option<u64> {
+            self.last_sequence
+        }
+
+        /// The number of frames dropped since the stream was started.
+        pub fn dropped_frames(&self) -> u64 {
+            self.frame_counter.dropped()
+        }
+
         pub fn set_format(&mut self, format: CameraFormat) -> Result<(), NokhwaError> {
             // convert to media_type
             let media_type: IMFMediaType = match unsafe { MFCreateMediaType() } {
@@ -1029,14 +1111,17 @@ pub mod wmf {
             // set relevant things
             let resolution = (u64::from(format.resolution().width_x) << 32_u64)
                 + u64::from(format.resolution().height_y);
-            let fps = {
-                let frame_rate_u64 = 0_u64;
-                let mut bytes: [u8; 8] = frame_rate_u64.to_le_bytes();
-                bytes[7] = format.frame_rate() as u8;
-                bytes[3] = 0x01;
-                u64::from_le_bytes(bytes)
+            let fps = frame_rate_to_mf_ratio(format.frame_rate());
+            let fourcc = match frameformat_to_guid(format.format()) {
+                Some(guid) => guid,
+                None => {
+                    return Err(NokhwaError::SetPropertyError {
+                        property: "MF_MT_SUBTYPE".to_string(),
+                        value: format.format().to_string(),
+                        error: "Unsupported by Media Foundation".to_string(),
+                    })
+                }
             };
-            let fourcc = frameformat_to_guid(format.format());
             // setting to the new media_type
             if let Err(why) = unsafe { media_type.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video) } {
                 return Err(NokhwaError::SetPropertyError {
@@ -1111,10 +1196,39 @@ pub mod wmf {
                 return Err(NokhwaError::OpenStreamError(why.to_string()));
             }
 
+            self.frame_counter.reset();
+            self.last_sample_time = None;
             self.is_open.set(true);
             Ok(())
         }
 
+        // Media Foundation does not number frames, so a gap between the sample times of consecutive frames that is longer than a
+        // frame counts as dropped frames. The source reader reports gaps it knows of with `MF_SOURCE_READERF_STREAMTICK`, those
+        // show up the same way.
+        fn count_frame(&mut self, sample: &IMFSample) -> u64 {
+            let sample_time = unsafe { sample.GetSampleTime() }.ok();
+            let frame_duration = unsafe { sample.GetSampleDuration() }
+                .ok()
+                .filter(|duration| *duration > 0)
+                .or_else(|| {
+                    let (numerator, denominator) = self.device_format.frame_rate().as_rational();
+                    (10_000_000 * i64::from(denominator))
+                        .checked_div(i64::from(numerator))
+                        .filter(|duration| *duration > 0)
+                });
+            if let (Some(time), Some(last_time), Some(duration)) =
+                (sample_time, self.last_sample_time, frame_duration)
+            {
+                // round to the nearest frame, sample times jitter
+                let frames = (time - last_time + duration / 2) / duration;
+                if frames > 1 {
+                    self.frame_counter.record_drops(frames.unsigned_abs() - 1);
+                }
+            }
+            self.last_sample_time = sample_time.or(self.last_sample_time);
+            self.frame_counter.next_sequence()
+        }
+
         pub fn raw_bytes(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
             let mut imf_sample: Option<IMFSample> = match unsafe { MFCreateSample() } {
                 Ok(sample) => Some(sample),
@@ -1151,6 +1265,8 @@ pub mod wmf {
                     return Err(NokhwaError::ReadFrameError("No sample".to_string()));
                 }
             };
+            self.last_timestamp = sample_timestamp(&imf_sample);
+            self.last_sequence = Some(self.count_frame(&imf_sample));
 
             let buffer = match unsafe { imf_sample.ConvertToContiguousBuffer() } {
                 Ok(buf) => buf,
@@ -1223,6 +1339,7 @@ pub mod wmf {
 #[allow(clippy::needless_pass_by_value)]
 #[allow(clippy::must_use_candidate)]
 pub mod wmf {
+    use nokhwa_core::buffer::FrameTimestamp;
     use nokhwa_core::error::NokhwaError;
     use nokhwa_core::types::{
         CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
@@ -1303,6 +1420,26 @@ pub mod wmf {
             CameraFormat::default()
         }
 
+        pub fn stride(&self) -> Option<usize> {
+            None
+        }
+
+        pub fn is_bottom_up(&self) -> bool {
+            false
+        }
+
+        pub fn timestamp(&self) -> Option<FrameTimestamp> {
+            None
+        }
+
+        pub fn sequence(&self) -> Option<u64> {
+            None
+        }
+
+        pub fn dropped_frames(&self) -> u64 {
+            0
+        }
+
         pub fn set_format(&mut self, _format: CameraFormat) -> Result<(), NokhwaError> {
             Err(NokhwaError::NotImplementedError(
                 "Only on Windows".to_string(),
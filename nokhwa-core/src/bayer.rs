@@ -0,0 +1,101 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Unpacking of the MIPI CSI-2 packed raw layouts that Bayer sensors (e.g. Raspberry Pi cameras in raw mode) output.
+//!
+//! The unpacked samples are 16 bit little endian words holding the sample in their low bits, matching
+//! [`FrameFormat::Bayer10`](crate::frame_format::FrameFormat::Bayer10) and [`FrameFormat::Bayer12`](crate::frame_format::FrameFormat::Bayer12).
+
+use alloc::{vec, vec::Vec};
+
+/// A packed raw layout.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum BayerPacking {
+    /// `RAW10`: 4 samples in 5 bytes. The first 4 bytes hold the high 8 bits of each sample, the last byte the low 2 bits.
+    Raw10,
+    /// `RAW12`: 2 samples in 3 bytes. The first 2 bytes hold the high 8 bits of each sample, the last byte the low 4 bits.
+    Raw12,
+}
+
+impl BayerPacking {
+    /// The amount of samples in one packed group.
+    #[must_use]
+    pub fn samples_per_group(self) -> usize {
+        match self {
+            BayerPacking::Raw10 => 4,
+            BayerPacking::Raw12 => 2,
+        }
+    }
+
+    /// The amount of bytes one packed group takes.
+    #[must_use]
+    pub fn bytes_per_group(self) -> usize {
+        self.samples_per_group() + 1
+    }
+
+    /// The bit depth of a sample.
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        match self {
+            BayerPacking::Raw10 => 10,
+            BayerPacking::Raw12 => 12,
+        }
+    }
+}
+
+/// Unpacks `height` rows of `width` packed samples into 16 bit little endian samples.
+///
+/// `stride` is the length of a packed row in bytes, including any padding the driver adds, or `0` for unpadded rows. Samples missing from
+/// `data` are zero.
+#[must_use]
+pub fn unpack_bayer(
+    data: &[u8],
+    packing: BayerPacking,
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Vec<u8> {
+    let mut unpacked = vec![0_u8; width * height * 2];
+    let samples_per_group = packing.samples_per_group();
+    let stride = if stride == 0 {
+        width.div_ceil(samples_per_group) * packing.bytes_per_group()
+    } else {
+        stride
+    };
+    if stride == 0 || width == 0 {
+        return unpacked;
+    }
+
+    let low_bits = packing.bits() - 8;
+    let low_mask = (1_u16 << low_bits) - 1;
+
+    for (row, unpacked_row) in data
+        .chunks(stride)
+        .zip(unpacked.chunks_exact_mut(width * 2))
+    {
+        let groups = row.chunks_exact(packing.bytes_per_group());
+        for (group, unpacked_group) in groups.zip(unpacked_row.chunks_mut(samples_per_group * 2)) {
+            let low = u16::from(group[samples_per_group]);
+            for (index, sample) in unpacked_group.chunks_exact_mut(2).enumerate() {
+                let shift = low_bits * u32::try_from(index).unwrap_or_default();
+                let value = (u16::from(group[index]) << low_bits) | ((low >> shift) & low_mask);
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    unpacked
+}
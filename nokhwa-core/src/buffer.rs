@@ -14,8 +14,257 @@
  * limitations under the License.
  */
 
-use crate::{ types::Resolution};
+use crate::{error::NokhwaError, thermal::RadiometricScale, types::Resolution};
+use alloc::{
+    format,
+    string::ToString,
+    vec,
+    vec::Vec,
+};
 use bytes::Bytes;
+use core::time::Duration;
+
+/// Describes where a single plane of a frame lives inside a [`Buffer`].
+///
+/// Packed formats have a single plane spanning the whole buffer. Planar formats (e.g. `NV12`) captured from devices
+/// that hand out each plane separately, or split with [`Buffer::with_stride`], have one entry per plane, laid out back to back.
+///
+/// Some drivers pad each row of a plane, e.g. to an alignment their DMA engine needs. Such planes report their [`stride`](BufferPlane::stride),
+/// which has to be used to find the start of each row instead of the width of the frame.
+#[derive(Copy, Clone, Debug, Hash, PartialOrd, PartialEq, Eq)]
+pub struct BufferPlane {
+    offset: usize,
+    length: usize,
+    stride: Option<usize>,
+}
+
+impl BufferPlane {
+    /// Creates a new plane description.
+    #[must_use]
+    pub fn new(offset: usize, length: usize) -> Self {
+        Self {
+            offset,
+            length,
+            stride: None,
+        }
+    }
+
+    /// Sets the stride of this plane.
+    #[must_use]
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = Some(stride);
+        self
+    }
+
+    /// Get the offset in bytes of this plane from the start of the buffer.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Get the length in bytes of this plane.
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Get the length in bytes of one row of this plane including its padding, or `None` if the backend did not report it.
+    /// Compressed formats have no stride.
+    #[must_use]
+    pub fn stride(&self) -> Option<usize> {
+        self.stride
+    }
+}
+
+/// The kind of picture a compressed (e.g. `H264`) frame holds, as reported by the camera.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialOrd, PartialEq, Eq)]
+pub enum FrameType {
+    /// The camera did not say, or the format is not inter-frame compressed.
+    #[default]
+    Unknown,
+    /// A keyframe (I-frame), decodable on its own.
+    KeyFrame,
+    /// A predicted frame (P-frame), which depends on previous frames.
+    PFrame,
+    /// A bi-directionally predicted frame (B-frame), which depends on previous and following frames.
+    BFrame,
+}
+
+/// The clock a [`FrameTimestamp`] was taken from.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialOrd, PartialEq, Eq)]
+pub enum TimestampClock {
+    /// The backend did not say. Only the differences between timestamps of the same stream are meaningful.
+    #[default]
+    Unknown,
+    /// The system's monotonic clock (e.g. `CLOCK_MONOTONIC` on Linux), which is not affected by wall clock changes.
+    Monotonic,
+    /// The wall clock, as a duration since the Unix epoch.
+    Realtime,
+}
+
+/// The range the luma and chroma samples of a YCbCr frame are quantized to.
+#[derive(Copy, Clone, Debug, Hash, PartialOrd, PartialEq, Eq)]
+pub enum ColorRange {
+    /// Luma from 16 to 235 and chroma from 16 to 240 for 8 bit samples, also called video or TV range.
+    Limited,
+    /// Luma and chroma over the whole sample range, also called PC range.
+    Full,
+}
+
+/// The time a frame was captured at, as reported by the backend.
+#[derive(Copy, Clone, Debug, Hash, PartialOrd, PartialEq, Eq)]
+pub struct FrameTimestamp {
+    time: Duration,
+    clock: TimestampClock,
+    duration: Option<Duration>,
+}
+
+impl FrameTimestamp {
+    /// Creates a new timestamp of `time` on `clock`.
+    #[must_use]
+    pub fn new(time: Duration, clock: TimestampClock) -> Self {
+        Self {
+            time,
+            clock,
+            duration: None,
+        }
+    }
+
+    /// Sets how long the frame lasts, until the next one is due.
+    #[must_use]
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Get the time of this timestamp, as a duration since the start of its [`TimestampClock`].
+    #[must_use]
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Get the [`TimestampClock`] this timestamp was taken from.
+    #[must_use]
+    pub fn clock(&self) -> TimestampClock {
+        self.clock
+    }
+
+    /// Get how long the frame lasts, if the backend says.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+}
+
+/// How the image in a [`Buffer`] is oriented, for cameras that are not mounted upright (e.g. phones) or that are shown mirrored.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialOrd, PartialEq, Eq)]
+pub struct FrameOrientation {
+    rotation: u16,
+    mirrored: bool,
+}
+
+impl FrameOrientation {
+    /// Creates a new orientation. `rotation` is in degrees clockwise and is wrapped to `0..360`.
+    #[must_use]
+    pub fn new(rotation: u16, mirrored: bool) -> Self {
+        Self {
+            rotation: rotation % 360,
+            mirrored,
+        }
+    }
+
+    /// Get how far the frame has to be rotated clockwise to appear upright, in degrees. This is usually a multiple of 90.
+    #[must_use]
+    pub fn rotation(&self) -> u16 {
+        self.rotation
+    }
+
+    /// Returns `true` if the frame is mirrored horizontally, like the preview of a front facing camera.
+    #[must_use]
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+}
+
+/// Numbers the frames of a stream and counts the frames that were dropped in it, for backends to fill in
+/// [`Buffer::sequence()`] and [`CaptureTrait::dropped_frames()`](crate::traits::CaptureTrait::dropped_frames).
+///
+/// Backends whose driver numbers frames (e.g. V4L2) pass those numbers to [`observe()`](FrameCounter::observe), and gaps count as
+/// dropped frames. Backends that are told about dropped frames instead (e.g. `AVFoundation`) number arriving frames with
+/// [`next_sequence()`](FrameCounter::next_sequence) and report drops with [`record_drops()`](FrameCounter::record_drops).
+#[derive(Copy, Clone, Debug, Default, Hash, PartialOrd, PartialEq, Eq)]
+pub struct FrameCounter {
+    next: Option<u64>,
+    dropped: u64,
+}
+
+impl FrameCounter {
+    /// Creates a new counter, for a stream that has not delivered any frames yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a frame the driver numbered `sequence`, and returns how many frames were dropped right before it.
+    /// A sequence number lower than expected means the driver started counting again, which is not counted as a drop.
+    pub fn observe(&mut self, sequence: u64) -> u64 {
+        let gap = match self.next {
+            Some(next) if sequence > next => sequence - next,
+            _ => 0,
+        };
+        self.dropped += gap;
+        self.next = Some(sequence + 1);
+        gap
+    }
+
+    /// Numbers a frame that arrived, following the frames counted so far (including dropped ones).
+    pub fn next_sequence(&mut self) -> u64 {
+        let sequence = self.next.unwrap_or_default();
+        self.next = Some(sequence + 1);
+        sequence
+    }
+
+    /// Records `count` dropped frames, which take up sequence numbers as if they had arrived.
+    pub fn record_drops(&mut self, count: u64) {
+        self.dropped += count;
+        self.next = Some(self.next.unwrap_or_default() + count);
+    }
+
+    /// Get the number of frames dropped since the counter was created or [`reset()`](FrameCounter::reset).
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Starts counting again, e.g. when the stream is reopened.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Per-frame data reported by the camera alongside the image, e.g. through a UVC metadata node.
+///
+/// Fields the camera did not report are `None`.
+#[derive(Clone, Debug, Default, Hash, PartialOrd, PartialEq, Eq)]
+pub struct FrameMetadata {
+    /// When the host received the start of the frame, on the system's monotonic clock.
+    pub host_timestamp: Option<Duration>,
+    /// When the frame was captured, in ticks of the camera's clock (the UVC presentation time stamp).
+    pub presentation_time: Option<u32>,
+    /// The camera's clock and the USB frame number, sampled while the frame was sent (the UVC source clock reference).
+    /// Together with [`host_timestamp`](FrameMetadata::host_timestamp) this relates the camera's clock to the host's.
+    pub source_clock: Option<(u32, u16)>,
+    /// The exposure time of the frame.
+    pub exposure_time: Option<Duration>,
+    /// The ISO speed of the frame.
+    pub iso_speed: Option<u32>,
+    /// The white balance of the frame, in Kelvin.
+    pub white_balance: Option<u32>,
+    /// How the samples of a radiometric [`FrameFormat::Luma16`] frame map to temperatures. See [`thermal`](crate::thermal).
+    pub radiometric_scale: Option<RadiometricScale>,
+    /// The metadata as the camera sent it, in a backend specific layout.
+    pub raw: Vec<u8>,
+}
 
 /// A buffer returned by a camera to accommodate custom decoding.
 /// Contains information of Resolution, the buffer's [`FrameFormat`], and the buffer.
@@ -25,7 +274,14 @@ use bytes::Bytes;
 pub struct Buffer {
     resolution: Resolution,
     buffer: Bytes,
+    planes: Vec<BufferPlane>,
     source_frame_format: FrameFormat,
+    frame_type: FrameType,
+    timestamp: Option<FrameTimestamp>,
+    sequence: Option<u64>,
+    metadata: Option<FrameMetadata>,
+    color_range: Option<ColorRange>,
+    orientation: Option<FrameOrientation>,
 }
 
 impl Buffer {
@@ -36,7 +292,43 @@ impl Buffer {
         Self {
             resolution: res,
             buffer: Bytes::copy_from_slice(buf),
+            planes: vec![BufferPlane::new(0, buf.len())],
+            source_frame_format,
+            frame_type: FrameType::Unknown,
+            timestamp: None,
+            sequence: None,
+            metadata: None,
+            color_range: None,
+            orientation: None,
+        }
+    }
+
+    /// Creates a new buffer from separate planes, e.g. from a multi-planar capture device.
+    ///
+    /// The planes are copied back to back into one contiguous buffer, in order.
+    #[must_use]
+    pub fn with_planes(res: Resolution, planes: &[&[u8]], source_frame_format: FrameFormat) -> Self {
+        let mut buffer = Vec::with_capacity(planes.iter().map(|plane| plane.len()).sum());
+        let planes = planes
+            .iter()
+            .map(|plane| {
+                let offset = buffer.len();
+                buffer.extend_from_slice(plane);
+                BufferPlane::new(offset, plane.len())
+            })
+            .collect();
+
+        Self {
+            resolution: res,
+            buffer: Bytes::from(buffer),
+            planes,
             source_frame_format,
+            frame_type: FrameType::Unknown,
+            timestamp: None,
+            sequence: None,
+            metadata: None,
+            color_range: None,
+            orientation: None,
         }
     }
 
@@ -58,15 +350,222 @@ impl Buffer {
         self.buffer.clone()
     }
 
+    /// Get the [`BufferPlane`]s of this buffer.
+    #[must_use]
+    pub fn planes(&self) -> &[BufferPlane] {
+        &self.planes
+    }
+
+    /// Sets the stride of each plane, in order. A stride of `0` (e.g. a compressed format) leaves the plane without one, and strides
+    /// past the last plane are ignored.
+    #[must_use]
+    pub fn with_strides(mut self, strides: &[usize]) -> Self {
+        for (plane, stride) in self.planes.iter_mut().zip(strides) {
+            plane.stride = Some(*stride).filter(|stride| *stride != 0);
+        }
+        self
+    }
+
+    /// Sets the row stride of a frame that arrived as one contiguous block, as single-planar V4L2 devices and Media Foundation
+    /// deliver them. Planar formats (e.g. `NV12`, `YV12`) are split into one [`BufferPlane`] per plane, with the chroma planes following
+    /// the luma plane at their own stride, so [`plane()`](Buffer::plane) and [`stride()`](Buffer::stride) work the same as for buffers
+    /// made with [`with_planes()`](Buffer::with_planes). A stride of `0` leaves the buffer unchanged.
+    #[must_use]
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        if stride == 0 || self.planes.len() != 1 {
+            return self.with_strides(&[stride]);
+        }
+
+        let rows = self.resolution.height() as usize;
+        let chroma_rows = rows.div_ceil(2);
+        // (stride, rows) of each plane
+        let layout = match self.source_frame_format {
+            FrameFormat::Nv12 | FrameFormat::Nv21 | FrameFormat::P010 => {
+                vec![(stride, rows), (stride, chroma_rows)]
+            }
+            FrameFormat::P210 => vec![(stride, rows), (stride, rows)],
+            FrameFormat::P410 => vec![(stride, rows), (stride * 2, rows)],
+            FrameFormat::Yv12 => vec![
+                (stride, rows),
+                (stride / 2, chroma_rows),
+                (stride / 2, chroma_rows),
+            ],
+            _ => {
+                self.planes[0].stride = Some(stride);
+                return self;
+            }
+        };
+
+        let total = self.buffer.len();
+        let mut offset = 0;
+        self.planes = layout
+            .into_iter()
+            .map(|(stride, rows)| {
+                let length = (stride * rows).min(total - offset);
+                let plane = BufferPlane::new(offset, length).with_stride(stride);
+                offset += length;
+                plane
+            })
+            .collect();
+        self
+    }
+
+    /// Get the data of the plane at `index`, or `None` if there is no such plane.
+    #[must_use]
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        let plane = self.planes.get(index)?;
+        self.buffer.get(plane.offset..plane.offset + plane.length)
+    }
+
+    /// Get the stride of the plane at `index`, or `None` if there is no such plane or the backend did not report its stride.
+    #[must_use]
+    pub fn stride(&self, index: usize) -> Option<usize> {
+        self.planes.get(index).and_then(BufferPlane::stride)
+    }
+
     /// Get the [`SourceFrameFormat`] of this buffer.
     #[must_use]
     pub fn source_frame_format(&self) -> FrameFormat {
         self.source_frame_format
     }
+
+    /// Sets the [`FrameType`] of this buffer.
+    #[must_use]
+    pub fn with_frame_type(mut self, frame_type: FrameType) -> Self {
+        self.frame_type = frame_type;
+        self
+    }
+
+    /// Get the [`FrameType`] of this buffer.
+    #[must_use]
+    pub fn frame_type(&self) -> FrameType {
+        self.frame_type
+    }
+
+    /// Returns `true` if the camera marked this buffer as a keyframe.
+    #[must_use]
+    pub fn is_keyframe(&self) -> bool {
+        self.frame_type == FrameType::KeyFrame
+    }
+
+    /// Sets the [`FrameTimestamp`] of this buffer.
+    #[must_use]
+    pub fn with_timestamp(mut self, timestamp: FrameTimestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Get the time this buffer was captured at, if the backend reports it.
+    #[must_use]
+    pub fn timestamp(&self) -> Option<FrameTimestamp> {
+        self.timestamp
+    }
+
+    /// Sets the sequence number of this buffer.
+    #[must_use]
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Get the sequence number of this buffer, if the backend reports it. The backend counts every frame the camera
+    /// produced, so gaps between consecutive buffers mean frames were dropped.
+    #[must_use]
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Sets the [`FrameMetadata`] of this buffer.
+    #[must_use]
+    pub fn with_frame_metadata(mut self, metadata: FrameMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Get the [`FrameMetadata`] the camera reported for this buffer, if any.
+    #[must_use]
+    pub fn frame_metadata(&self) -> Option<&FrameMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Sets the [`ColorRange`] of this buffer.
+    #[must_use]
+    pub fn with_color_range(mut self, color_range: ColorRange) -> Self {
+        self.color_range = Some(color_range);
+        self
+    }
+
+    /// Get the [`ColorRange`] of this buffer's YCbCr samples, if the backend reports it.
+    #[must_use]
+    pub fn color_range(&self) -> Option<ColorRange> {
+        self.color_range
+    }
+
+    /// Sets the [`FrameOrientation`] of this buffer.
+    #[must_use]
+    pub fn with_orientation(mut self, orientation: FrameOrientation) -> Self {
+        self.orientation = Some(orientation);
+        self
+    }
+
+    /// Get the [`FrameOrientation`] of this buffer, if the backend reports it. Frames without one are upright and not mirrored.
+    #[must_use]
+    pub fn orientation(&self) -> Option<FrameOrientation> {
+        self.orientation
+    }
+
+    /// The 16 bit little endian samples of a single plane `format` frame, row by row without padding.
+    pub(crate) fn u16_samples(&self, format: FrameFormat) -> Result<Vec<u16>, NokhwaError> {
+        let process_error = |error: &str| NokhwaError::ProcessFrameError {
+            src: self.source_frame_format,
+            destination: format!("{format} samples"),
+            error: error.to_string(),
+        };
+        if self.source_frame_format != format {
+            return Err(process_error("Wrong frame format"));
+        }
+
+        let width = self.resolution.width() as usize;
+        let height = self.resolution.height() as usize;
+        let stride = self
+            .planes
+            .first()
+            .and_then(BufferPlane::stride)
+            .unwrap_or(width * 2);
+        if width == 0 || stride < width * 2 {
+            return Err(process_error("Invalid stride"));
+        }
+
+        let mut samples = Vec::with_capacity(width * height);
+        for row in self.buffer.chunks(stride).take(height) {
+            let row = row
+                .get(..width * 2)
+                .ok_or_else(|| process_error("Buffer too short"))?;
+            samples.extend(
+                row.chunks_exact(2)
+                    .map(|sample| u16::from_le_bytes([sample[0], sample[1]])),
+            );
+        }
+        if samples.len() != width * height {
+            return Err(process_error("Buffer too short"));
+        }
+        Ok(samples)
+    }
+
+    /// Creates a new [`Buffer`] of the same frame from processed `samples`, keeping the frame type, timestamp, sequence number,
+    /// metadata and orientation.
+    #[must_use]
+    pub fn with_samples(&self, samples: &[u8], format: FrameFormat) -> Buffer {
+        let mut buffer =
+            Buffer::new(self.resolution, samples, format).with_frame_type(self.frame_type);
+        buffer.timestamp = self.timestamp;
+        buffer.sequence = self.sequence;
+        buffer.metadata.clone_from(&self.metadata);
+        buffer.orientation = self.orientation;
+        buffer
+    }
 }
 
-#[cfg(feature = "opencv-mat")]
-use crate::error::NokhwaError;
 #[cfg(feature = "opencv-mat")]
 use image::ImageBuffer;
 
@@ -158,6 +657,101 @@ impl Buffer {
     }
 }
 
+#[cfg(feature = "ndarray")]
+impl Buffer {
+    /// Gets a zero-copy [`ArrayView3`](ndarray::ArrayView3) of this buffer, shaped as height × width × channels.
+    ///
+    /// Only packed formats map to an array like this (e.g. `Rgb8` has 3 channels, `Yuv422` has 2 bytes per pixel). Planar and compressed formats will error.
+    /// Padded rows (see [`BufferPlane::stride`]) are skipped over, so the view is not contiguous.
+    /// # Errors
+    /// If the [`FrameFormat`] is not packed, or the buffer length does not match the resolution, this will error.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+    pub fn ndarray_view(&self) -> Result<ndarray::ArrayView3<'_, u8>, NokhwaError> {
+        use ndarray::ShapeBuilder;
+
+        let (height, width, channels) = self.ndarray_shape()?;
+        let stride = self
+            .planes
+            .first()
+            .and_then(BufferPlane::stride)
+            .unwrap_or(width * channels);
+        let shape = (height, width, channels).strides((stride, channels, 1));
+        ndarray::ArrayView3::from_shape(shape, &self.buffer).map_err(|why| {
+            NokhwaError::ProcessFrameError {
+                src: self.source_frame_format,
+                destination: "ndarray".to_string(),
+                error: why.to_string(),
+            }
+        })
+    }
+
+    /// Copies this buffer into an owned [`Array3`](ndarray::Array3), shaped as height × width × channels.
+    ///
+    /// If you do not need ownership, use [`ndarray_view`](Self::ndarray_view) instead to avoid the copy.
+    /// # Errors
+    /// If the [`FrameFormat`] is not packed, or the buffer length does not match the resolution, this will error.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+    pub fn to_ndarray(&self) -> Result<ndarray::Array3<u8>, NokhwaError> {
+        self.ndarray_view().map(|view| view.to_owned())
+    }
+
+    /// Decodes this buffer using the provided [`StaticDecoder`](crate::decoder::StaticDecoder), then moves the decoded pixels into an [`Array3`](ndarray::Array3) without copying.
+    /// # Errors
+    /// If the decoding fails, or the decoded image does not fit height × width × channels, this will error.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+    pub fn decode_ndarray<D>(&self) -> Result<ndarray::Array3<u8>, NokhwaError>
+    where
+        D: crate::decoder::StaticDecoder<PixelContainer = Vec<u8>>,
+        D::OutputPixels: image::Pixel<Subpixel = u8>,
+        D::Error: std::fmt::Display,
+    {
+        use image::Pixel;
+
+        let image = D::decode_static(self.clone()).map_err(|why| NokhwaError::ProcessFrameError {
+            src: self.source_frame_format,
+            destination: "ndarray".to_string(),
+            error: why.to_string(),
+        })?;
+        let shape = (
+            image.height() as usize,
+            image.width() as usize,
+            usize::from(D::OutputPixels::CHANNEL_COUNT),
+        );
+        ndarray::Array3::from_shape_vec(shape, image.into_raw()).map_err(|why| {
+            NokhwaError::ProcessFrameError {
+                src: self.source_frame_format,
+                destination: "ndarray".to_string(),
+                error: why.to_string(),
+            }
+        })
+    }
+
+    fn ndarray_shape(&self) -> Result<(usize, usize, usize), NokhwaError> {
+        let channels = match self.source_frame_format {
+            FrameFormat::Luma8 => 1,
+            FrameFormat::Luma16
+            | FrameFormat::Depth16
+            | FrameFormat::Yuv422
+            | FrameFormat::Uyv422 => 2,
+            FrameFormat::Rgb8 | FrameFormat::Bgr8 => 3,
+            FrameFormat::RgbA8 | FrameFormat::BgrA8 => 4,
+            _ => {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: self.source_frame_format,
+                    destination: "ndarray".to_string(),
+                    error: "Format is not packed".to_string(),
+                })
+            }
+        };
+
+        Ok((
+            self.resolution.height_y as usize,
+            self.resolution.width_x as usize,
+            channels,
+        ))
+    }
+}
+
 #[cfg(feature = "wgpu-types")]
 use wgpu::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, ImageCopyTexture, TextureAspect, ImageDataLayout};
 use crate::frame_format::FrameFormat;
@@ -5,7 +5,7 @@ use crate::frame_format::FrameFormat;
 /// Trait to define a struct that can decode a [`Buffer`]
 pub trait Decoder {
     /// Formats that the decoder can decode.
-    const ALLOWED_FORMATS: &'static FrameFormat;
+    const ALLOWED_FORMATS: &'static [FrameFormat];
     /// Output pixel type (e.g. [`Rgb<u8>`](image::Rgb))
     type OutputPixels: Pixel; 
     
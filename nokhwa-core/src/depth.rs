@@ -0,0 +1,229 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Calibration of depth cameras (e.g. Intel `RealSense` over UVC or V4L2), whose depth stream comes in [`FrameFormat::Depth16`].
+//!
+//! [`CameraIntrinsics`] relate the pixels of a stream to rays through its sensor, [`CameraExtrinsics`] relate the sensors of two streams
+//! to each other. Together they map a depth pixel onto the color image.
+
+use crate::{buffer::Buffer, error::NokhwaError, frame_format::FrameFormat, types::Resolution};
+use alloc::vec::Vec;
+
+/// The lens distortion model of [`CameraIntrinsics`], named like `librealsense` does.
+#[derive(Copy, Clone, Debug, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistortionModel {
+    /// The image is rectified, there is no distortion.
+    #[default]
+    None,
+    /// Brown-Conrady, mapping undistorted to distorted points.
+    BrownConrady,
+    /// Brown-Conrady, mapping distorted to undistorted points.
+    InverseBrownConrady,
+    /// The four coefficient Kannala-Brandt fisheye model.
+    KannalaBrandt4,
+}
+
+/// The pinhole model of a stream: its focal length and principal point in pixels, and its lens distortion.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraIntrinsics {
+    resolution: Resolution,
+    focal_length: (f32, f32),
+    principal_point: (f32, f32),
+    distortion: DistortionModel,
+    coefficients: [f32; 5],
+}
+
+impl CameraIntrinsics {
+    /// Creates new [`CameraIntrinsics`] without distortion.
+    #[must_use]
+    pub fn new(
+        resolution: Resolution,
+        focal_length: (f32, f32),
+        principal_point: (f32, f32),
+    ) -> Self {
+        CameraIntrinsics {
+            resolution,
+            focal_length,
+            principal_point,
+            distortion: DistortionModel::None,
+            coefficients: [0.0; 5],
+        }
+    }
+
+    /// Sets the lens distortion and its coefficients.
+    #[must_use]
+    pub fn with_distortion(mut self, distortion: DistortionModel, coefficients: [f32; 5]) -> Self {
+        self.distortion = distortion;
+        self.coefficients = coefficients;
+        self
+    }
+
+    /// Get the [`Resolution`] the intrinsics are for.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Get the focal length, horizontally and vertically, in pixels.
+    #[must_use]
+    pub fn focal_length(&self) -> (f32, f32) {
+        self.focal_length
+    }
+
+    /// Get the principal point, in pixels from the top left.
+    #[must_use]
+    pub fn principal_point(&self) -> (f32, f32) {
+        self.principal_point
+    }
+
+    /// Get the [`DistortionModel`].
+    #[must_use]
+    pub fn distortion(&self) -> DistortionModel {
+        self.distortion
+    }
+
+    /// Get the coefficients of the [`DistortionModel`].
+    #[must_use]
+    pub fn coefficients(&self) -> [f32; 5] {
+        self.coefficients
+    }
+
+    /// Maps a pixel and its depth in meters to a point in meters, relative to the sensor.
+    ///
+    /// The distortion is not applied, which is correct for the rectified depth streams of stereo depth cameras.
+    #[must_use]
+    pub fn deproject(&self, pixel: (f32, f32), depth: f32) -> [f32; 3] {
+        [
+            (pixel.0 - self.principal_point.0) / self.focal_length.0 * depth,
+            (pixel.1 - self.principal_point.1) / self.focal_length.1 * depth,
+            depth,
+        ]
+    }
+
+    /// Maps a point in meters, relative to the sensor, to a pixel. Points on or behind the sensor's plane map to `None`.
+    ///
+    /// The distortion is not applied, see [`deproject`](Self::deproject).
+    #[must_use]
+    pub fn project(&self, point: [f32; 3]) -> Option<(f32, f32)> {
+        if point[2] <= 0.0 {
+            return None;
+        }
+        Some((
+            point[0] / point[2] * self.focal_length.0 + self.principal_point.0,
+            point[1] / point[2] * self.focal_length.1 + self.principal_point.1,
+        ))
+    }
+}
+
+/// The rigid transform from the sensor of one stream to that of another: a rotation matrix (row major) and a translation in meters.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraExtrinsics {
+    rotation: [f32; 9],
+    translation: [f32; 3],
+}
+
+impl CameraExtrinsics {
+    /// The transform between two streams from the same sensor.
+    pub const IDENTITY: CameraExtrinsics = CameraExtrinsics {
+        rotation: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        translation: [0.0; 3],
+    };
+
+    /// Creates new [`CameraExtrinsics`].
+    #[must_use]
+    pub fn new(rotation: [f32; 9], translation: [f32; 3]) -> Self {
+        CameraExtrinsics {
+            rotation,
+            translation,
+        }
+    }
+
+    /// Get the rotation matrix, row major.
+    #[must_use]
+    pub fn rotation(&self) -> [f32; 9] {
+        self.rotation
+    }
+
+    /// Get the translation, in meters.
+    #[must_use]
+    pub fn translation(&self) -> [f32; 3] {
+        self.translation
+    }
+
+    /// Moves a point from the first sensor's space into the second's.
+    #[must_use]
+    pub fn transform(&self, point: [f32; 3]) -> [f32; 3] {
+        let r = &self.rotation;
+        [
+            r[0] * point[0] + r[1] * point[1] + r[2] * point[2] + self.translation[0],
+            r[3] * point[0] + r[4] * point[1] + r[5] * point[2] + self.translation[1],
+            r[6] * point[0] + r[7] * point[1] + r[8] * point[2] + self.translation[2],
+        ]
+    }
+
+    /// The transform the other way around.
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        let r = &self.rotation;
+        // the inverse of a rotation is its transpose
+        let rotation = [r[0], r[3], r[6], r[1], r[4], r[7], r[2], r[5], r[8]];
+        let t = &self.translation;
+        let translation = [
+            -(rotation[0] * t[0] + rotation[1] * t[1] + rotation[2] * t[2]),
+            -(rotation[3] * t[0] + rotation[4] * t[1] + rotation[5] * t[2]),
+            -(rotation[6] * t[0] + rotation[7] * t[1] + rotation[8] * t[2]),
+        ];
+        CameraExtrinsics {
+            rotation,
+            translation,
+        }
+    }
+}
+
+impl Default for CameraExtrinsics {
+    fn default() -> Self {
+        CameraExtrinsics::IDENTITY
+    }
+}
+
+/// The calibration of one stream of a depth camera. Neither UVC nor V4L2 report it, it comes from the vendor's SDK or a calibration of your own.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamCalibration {
+    /// The intrinsics of the stream.
+    pub intrinsics: Option<CameraIntrinsics>,
+    /// The transform from this stream to the color stream.
+    pub extrinsics: Option<CameraExtrinsics>,
+}
+
+impl Buffer {
+    /// Get the depth of every pixel of a [`FrameFormat::Depth16`] frame in meters, row by row. A depth of `0.0` means the camera
+    /// could not tell.
+    ///
+    /// `depth_units` is how many meters one step of the depth samples is, e.g. `0.001` for most `RealSense` cameras.
+    /// # Errors
+    /// If the frame is not [`FrameFormat::Depth16`], or the buffer is too short for its resolution, this will error.
+    pub fn depth_meters(&self, depth_units: f32) -> Result<Vec<f32>, NokhwaError> {
+        Ok(self
+            .u16_samples(FrameFormat::Depth16)?
+            .into_iter()
+            .map(|sample| f32::from(sample) * depth_units)
+            .collect())
+    }
+}
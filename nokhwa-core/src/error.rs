@@ -15,46 +15,90 @@
  */
 
 use crate::{frame_format::FrameFormat, types::ApiBackend};
-use thiserror::Error;
+use alloc::string::String;
+use core::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 
 /// All errors in `nokhwa`.
 #[allow(clippy::module_name_repetitions)]
-#[derive(Error, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub enum NokhwaError {
-    #[error("Unitialized Camera. Call `init()` first!")]
     UnitializedError,
-    #[error("Could not initialize {backend}: {error}")]
     InitializeError { backend: ApiBackend, error: String },
-    #[error("Could not shutdown {backend}: {error}")]
     ShutdownError { backend: ApiBackend, error: String },
-    #[error("Error: {0}")]
     GeneralError(String),
-    #[error("Could not generate required structure {structure}: {error}")]
     StructureError { structure: String, error: String },
-    #[error("Could not open device {0}: {1}")]
     OpenDeviceError(String, String),
-    #[error("Could not get device property {property}: {error}")]
     GetPropertyError { property: String, error: String },
-    #[error("Could not set device property {property} with value {value}: {error}")]
     SetPropertyError {
         property: String,
         value: String,
         error: String,
     },
-    #[error("Could not open device stream: {0}")]
     OpenStreamError(String),
-    #[error("Could not capture frame: {0}")]
     ReadFrameError(String),
-    #[error("Could not process frame {src} to {destination}: {error}")]
+    TimeoutError(Duration),
+    DeviceLost(String),
     ProcessFrameError {
         src: FrameFormat,
         destination: String,
         error: String,
     },
-    #[error("Could not stop stream: {0}")]
     StreamShutdownError(String),
-    #[error("This operation is not supported by backend {0}.")]
     UnsupportedOperationError(ApiBackend),
-    #[error("This operation is not implemented yet: {0}")]
     NotImplementedError(String),
 }
+
+impl Display for NokhwaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NokhwaError::UnitializedError => write!(f, "Unitialized Camera. Call `init()` first!"),
+            NokhwaError::InitializeError { backend, error } => {
+                write!(f, "Could not initialize {backend}: {error}")
+            }
+            NokhwaError::ShutdownError { backend, error } => {
+                write!(f, "Could not shutdown {backend}: {error}")
+            }
+            NokhwaError::GeneralError(why) => write!(f, "Error: {why}"),
+            NokhwaError::StructureError { structure, error } => write!(
+                f,
+                "Could not generate required structure {structure}: {error}"
+            ),
+            NokhwaError::OpenDeviceError(device, why) => write!(f, "Could not open device {device}: {why}"),
+            NokhwaError::GetPropertyError { property, error } => {
+                write!(f, "Could not get device property {property}: {error}")
+            }
+            NokhwaError::SetPropertyError {
+                property,
+                value,
+                error,
+            } => write!(
+                f,
+                "Could not set device property {property} with value {value}: {error}"
+            ),
+            NokhwaError::OpenStreamError(why) => write!(f, "Could not open device stream: {why}"),
+            NokhwaError::ReadFrameError(why) => write!(f, "Could not capture frame: {why}"),
+            NokhwaError::TimeoutError(timeout) => {
+                write!(f, "Timed out after {timeout:?} waiting for a frame")
+            }
+            NokhwaError::DeviceLost(why) => write!(f, "Device was lost: {why}"),
+            NokhwaError::ProcessFrameError {
+                src,
+                destination,
+                error,
+            } => write!(f, "Could not process frame {src} to {destination}: {error}"),
+            NokhwaError::StreamShutdownError(why) => write!(f, "Could not stop stream: {why}"),
+            NokhwaError::UnsupportedOperationError(backend) => {
+                write!(f, "This operation is not supported by backend {backend}.")
+            }
+            NokhwaError::NotImplementedError(what) => {
+                write!(f, "This operation is not implemented yet: {what}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NokhwaError {}
@@ -0,0 +1,232 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A subscribable bus of capture lifecycle and control events, so applications can log or measure what their cameras do in one place.
+//!
+//! `nokhwa`'s `Camera` publishes to [`EventBus::global()`].
+
+use crate::{
+    error::NokhwaError,
+    types::{
+        ApiBackend, CameraFormat, CameraIndex, ControlValueSetter, KnownCameraControl, StreamEvent,
+    },
+};
+use std::{
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver},
+        Arc, Mutex, OnceLock, PoisonError,
+    },
+    time::SystemTime,
+};
+
+/// What happened in a [`CaptureEvent`].
+#[derive(Clone, Debug)]
+pub enum CaptureEventKind {
+    /// The device was opened.
+    DeviceOpened,
+    /// The device was closed.
+    DeviceClosed,
+    /// The stream was opened.
+    StreamOpened,
+    /// The stream was stopped.
+    StreamClosed,
+    /// The camera format was changed to this.
+    FormatChanged(CameraFormat),
+    /// A control was written.
+    ControlWritten {
+        control: KnownCameraControl,
+        value: ControlValueSetter,
+    },
+    /// The stream reported something, e.g. a dropped frame.
+    Stream(StreamEvent),
+    /// An operation failed.
+    Error(NokhwaError),
+}
+
+impl Display for CaptureEventKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureEventKind::DeviceOpened => write!(f, "device opened"),
+            CaptureEventKind::DeviceClosed => write!(f, "device closed"),
+            CaptureEventKind::StreamOpened => write!(f, "stream opened"),
+            CaptureEventKind::StreamClosed => write!(f, "stream closed"),
+            CaptureEventKind::FormatChanged(format) => write!(f, "format changed to {format}"),
+            CaptureEventKind::ControlWritten { control, value } => {
+                write!(f, "control {control} set to {value}")
+            }
+            CaptureEventKind::Stream(event) => write!(f, "stream event {event:?}"),
+            CaptureEventKind::Error(why) => write!(f, "error: {why}"),
+        }
+    }
+}
+
+/// Something that happened to a camera, published on an [`EventBus`].
+#[derive(Clone, Debug)]
+pub struct CaptureEvent {
+    timestamp: SystemTime,
+    backend: ApiBackend,
+    device: CameraIndex,
+    kind: CaptureEventKind,
+}
+
+impl CaptureEvent {
+    /// Creates a new [`CaptureEvent`] that happened now.
+    #[must_use]
+    pub fn new(backend: ApiBackend, device: CameraIndex, kind: CaptureEventKind) -> Self {
+        CaptureEvent {
+            timestamp: SystemTime::now(),
+            backend,
+            device,
+            kind,
+        }
+    }
+
+    /// Get when the event happened.
+    #[must_use]
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Get the backend of the camera.
+    #[must_use]
+    pub fn backend(&self) -> ApiBackend {
+        self.backend
+    }
+
+    /// Get the index of the camera.
+    #[must_use]
+    pub fn device(&self) -> &CameraIndex {
+        &self.device
+    }
+
+    /// Get what happened.
+    #[must_use]
+    pub fn kind(&self) -> &CaptureEventKind {
+        &self.kind
+    }
+}
+
+impl Display for CaptureEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.backend, self.device, self.kind)
+    }
+}
+
+/// Identifies a subscription to an [`EventBus`], see [`EventBus::unsubscribe()`].
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct SubscriptionId(u64);
+
+type Subscriber = Arc<dyn Fn(&CaptureEvent) + Send + Sync + 'static>;
+
+/// Delivers [`CaptureEvent`]s to every subscriber. Clones publish to the same subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<(SubscriptionId, Subscriber)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl EventBus {
+    /// Creates a new [`EventBus`] without subscribers.
+    #[must_use]
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Get the process wide [`EventBus`].
+    #[must_use]
+    pub fn global() -> &'static EventBus {
+        static GLOBAL: OnceLock<EventBus> = OnceLock::new();
+        GLOBAL.get_or_init(EventBus::new)
+    }
+
+    /// Calls `subscriber` with every event published from now on. It is called from the thread that published the event, so
+    /// it should return quickly.
+    pub fn subscribe(
+        &self,
+        subscriber: impl Fn(&CaptureEvent) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((id, Arc::new(subscriber)));
+        id
+    }
+
+    /// Sends every event published from now on to the returned channel, e.g. for a logging thread. The subscription ends when
+    /// it is unsubscribed, the channel keeps the events sent before.
+    #[must_use]
+    pub fn subscribe_channel(&self) -> (SubscriptionId, Receiver<CaptureEvent>) {
+        let (sender, receiver) = channel();
+        let id = self.subscribe(move |event| {
+            let _ = sender.send(event.clone());
+        });
+        (id, receiver)
+    }
+
+    /// Stops delivering events to a subscriber. Returns `false` if it was not subscribed.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let before = subscribers.len();
+        subscribers.retain(|(subscribed, _)| *subscribed != id);
+        subscribers.len() != before
+    }
+
+    /// Returns `true` if anything is subscribed, so publishers can skip building events nobody receives.
+    #[must_use]
+    pub fn has_subscribers(&self) -> bool {
+        !self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    /// Delivers `event` to every subscriber.
+    pub fn publish(&self, event: &CaptureEvent) {
+        // call outside of the lock, so subscribers may subscribe or unsubscribe
+        let subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(_, subscriber)| subscriber.clone())
+            .collect::<Vec<Subscriber>>();
+        for subscriber in subscribers {
+            subscriber(event);
+        }
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field(
+                "subscribers",
+                &self
+                    .subscribers
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .len(),
+            )
+            .finish_non_exhaustive()
+    }
+}
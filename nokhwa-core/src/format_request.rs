@@ -1,7 +1,5 @@
-use std::{
-    cmp::Ordering,
-    collections::VecDeque
-};
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::cmp::Ordering;
 use crate::{
     frame_format::FrameFormat,
     types::{CameraFormat, Resolution, FrameRate, Range},
@@ -17,6 +15,7 @@ enum ClosestType {
 }
 
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum CustomFormatRequestType {
     HighestFrameRate,
     HighestResolution,
@@ -45,6 +44,12 @@ impl FormatRequest {
         ])
     }
 
+    /// Adds [`FrameFormat::Luma16`], which radiometric thermal cameras (e.g. FLIR Lepton and Boson) stream temperatures in.
+    #[must_use]
+    pub fn with_radiometric_frame_formats(self) -> Self {
+        self.push_frame_format(FrameFormat::Luma16)
+    }
+
     pub fn push_frame_format(mut self, frame_format: FrameFormat) -> Self {
         match &mut self.frame_format {
             Some(ffs) => ffs.push(frame_format),
@@ -93,6 +98,11 @@ impl FormatRequest {
         self
     }
 
+    #[must_use]
+    pub fn resolution_range(&self) -> Option<Range<Resolution>> {
+        self.resolution
+    }
+
     pub fn reset_resolution_range(mut self) -> Self {
         self.resolution = None;
         self
@@ -103,6 +113,11 @@ impl FormatRequest {
         self
     }
 
+    #[must_use]
+    pub fn frame_rate_range(&self) -> Option<Range<FrameRate>> {
+        self.frame_rate
+    }
+
     pub fn reset_frame_rate_range(mut self) -> Self {
         self.frame_rate = None;
         self
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use std::fmt::{Display, Formatter};
+use core::fmt::{Display, Formatter};
 
 use crate::types::ApiBackend;
 
@@ -34,25 +34,51 @@ pub enum FrameFormat {
     XVid,
     VP8,
     VP9,
+    Heif,
+    ProRes,
 
     // YCbCr formats
 
     // -> 422 16 BPP
     Yuv422,
     Uyv422,
+    // -> 422 10 bit, as HDMI capture cards deliver it. `V210` packs 6 pixels into 4 little endian 32 bit words,
+    //    `P210` is biplanar like `Nv12` with each sample in the high 10 bits of a 16 bit little endian word
+    V210,
+    P210,
+    // -> 422 16 bit, packed as Cb Y0 Cr Y1 16 bit little endian words
+    V216,
+
+    // 444
+    // -> 10 bit, biplanar like `P010` but without chroma subsampling
+    P410,
 
     // 420
     Nv12,
     Nv21,
     Yv12,
+    // -> 10 bit, biplanar like `Nv12` with each sample in the high 10 bits of a 16 bit little endian word
+    P010,
 
     // Grayscale Formats
     Luma8,
     Luma16,
 
+    // Depth Formats
+    // -> 16 bit little endian distances, in units of the camera's depth scale (`Z16`)
+    Depth16,
+
     // RGB Formats
     Rgb8,
     RgbA8,
+    // -> stored blue first, as Media Foundation's `RGB24` and `RGB32` are. `RGB32`'s fourth byte is padding, not alpha
+    Bgr8,
+    BgrA8,
+
+    // Bayer Formats, 10 and 12 bit samples are stored in the low bits of 16 bit little endian words
+    Bayer8(BayerPattern),
+    Bayer10(BayerPattern),
+    Bayer12(BayerPattern),
 
     // Custom
     Custom(u128),
@@ -72,15 +98,37 @@ impl FrameFormat {
         FrameFormat::XVid,
         FrameFormat::VP8,
         FrameFormat::VP9,
+        FrameFormat::Heif,
+        FrameFormat::ProRes,
         FrameFormat::Yuv422,
         FrameFormat::Uyv422,
+        FrameFormat::V210,
+        FrameFormat::P210,
+        FrameFormat::V216,
+        FrameFormat::P410,
         FrameFormat::Nv12,
         FrameFormat::Nv21,
         FrameFormat::Yv12,
+        FrameFormat::P010,
         FrameFormat::Luma8,
         FrameFormat::Luma16,
+        FrameFormat::Depth16,
         FrameFormat::Rgb8,
         FrameFormat::RgbA8,
+        FrameFormat::Bgr8,
+        FrameFormat::BgrA8,
+        FrameFormat::Bayer8(BayerPattern::Rggb),
+        FrameFormat::Bayer8(BayerPattern::Bggr),
+        FrameFormat::Bayer8(BayerPattern::Grbg),
+        FrameFormat::Bayer8(BayerPattern::Gbrg),
+        FrameFormat::Bayer10(BayerPattern::Rggb),
+        FrameFormat::Bayer10(BayerPattern::Bggr),
+        FrameFormat::Bayer10(BayerPattern::Grbg),
+        FrameFormat::Bayer10(BayerPattern::Gbrg),
+        FrameFormat::Bayer12(BayerPattern::Rggb),
+        FrameFormat::Bayer12(BayerPattern::Bggr),
+        FrameFormat::Bayer12(BayerPattern::Grbg),
+        FrameFormat::Bayer12(BayerPattern::Gbrg),
     ];
 
     pub const COMPRESSED: &'static [FrameFormat] = &[
@@ -95,19 +143,48 @@ impl FrameFormat {
         FrameFormat::XVid,
         FrameFormat::VP8,
         FrameFormat::VP9,
+        FrameFormat::Heif,
+        FrameFormat::ProRes,
     ];
 
     pub const CHROMA: &'static [FrameFormat] = &[
         FrameFormat::Yuv422,
         FrameFormat::Uyv422,
+        FrameFormat::V210,
+        FrameFormat::P210,
+        FrameFormat::V216,
+        FrameFormat::P410,
         FrameFormat::Nv12,
         FrameFormat::Nv21,
         FrameFormat::Yv12,
+        FrameFormat::P010,
     ];
 
     pub const LUMA: &'static [FrameFormat] = &[FrameFormat::Luma8, FrameFormat::Luma16];
 
-    pub const RGB: &'static [FrameFormat] = &[FrameFormat::Rgb8, FrameFormat::RgbA8];
+    pub const DEPTH: &'static [FrameFormat] = &[FrameFormat::Depth16];
+
+    pub const RGB: &'static [FrameFormat] = &[
+        FrameFormat::Rgb8,
+        FrameFormat::RgbA8,
+        FrameFormat::Bgr8,
+        FrameFormat::BgrA8,
+    ];
+
+    pub const BAYER: &'static [FrameFormat] = &[
+        FrameFormat::Bayer8(BayerPattern::Rggb),
+        FrameFormat::Bayer8(BayerPattern::Bggr),
+        FrameFormat::Bayer8(BayerPattern::Grbg),
+        FrameFormat::Bayer8(BayerPattern::Gbrg),
+        FrameFormat::Bayer10(BayerPattern::Rggb),
+        FrameFormat::Bayer10(BayerPattern::Bggr),
+        FrameFormat::Bayer10(BayerPattern::Grbg),
+        FrameFormat::Bayer10(BayerPattern::Gbrg),
+        FrameFormat::Bayer12(BayerPattern::Rggb),
+        FrameFormat::Bayer12(BayerPattern::Bggr),
+        FrameFormat::Bayer12(BayerPattern::Grbg),
+        FrameFormat::Bayer12(BayerPattern::Gbrg),
+    ];
     
     pub const COLOR_FORMATS: &'static [FrameFormat] = &[
         FrameFormat::H265,
@@ -121,20 +198,57 @@ impl FrameFormat {
         FrameFormat::XVid,
         FrameFormat::VP8,
         FrameFormat::VP9,
+        FrameFormat::Heif,
+        FrameFormat::ProRes,
         FrameFormat::Yuv422,
         FrameFormat::Uyv422,
+        FrameFormat::V210,
+        FrameFormat::P210,
+        FrameFormat::V216,
+        FrameFormat::P410,
         FrameFormat::Nv12,
         FrameFormat::Nv21,
         FrameFormat::Yv12,
+        FrameFormat::P010,
         FrameFormat::Rgb8,
         FrameFormat::RgbA8,
+        FrameFormat::Bgr8,
+        FrameFormat::BgrA8,
+        FrameFormat::Bayer8(BayerPattern::Rggb),
+        FrameFormat::Bayer8(BayerPattern::Bggr),
+        FrameFormat::Bayer8(BayerPattern::Grbg),
+        FrameFormat::Bayer8(BayerPattern::Gbrg),
+        FrameFormat::Bayer10(BayerPattern::Rggb),
+        FrameFormat::Bayer10(BayerPattern::Bggr),
+        FrameFormat::Bayer10(BayerPattern::Grbg),
+        FrameFormat::Bayer10(BayerPattern::Gbrg),
+        FrameFormat::Bayer12(BayerPattern::Rggb),
+        FrameFormat::Bayer12(BayerPattern::Bggr),
+        FrameFormat::Bayer12(BayerPattern::Grbg),
+        FrameFormat::Bayer12(BayerPattern::Gbrg),
     ];
     
     pub const GRAYSCALE: &'static [FrameFormat] = &[FrameFormat::Luma8, FrameFormat::Luma16];
 }
 
 impl Display for FrameFormat {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// The order of the color filters in the top left 2x2 block of a Bayer sensor, row by row.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum BayerPattern {
+    Rggb,
+    Bggr,
+    Grbg,
+    Gbrg,
+}
+
+impl Display for BayerPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
@@ -162,6 +276,19 @@ impl PlatformSpecific {
     pub fn as_tuple(&self) -> (ApiBackend, u128) {
         (self.backend, self.format)
     }
+
+    /// Creates a [`PlatformSpecific`] format from a `FourCC`, e.g. one a V4L2 camera lists that `nokhwa` does not know. The
+    /// code is stored as a little endian `u32`, like V4L2 does.
+    #[must_use]
+    pub fn from_fourcc(backend: ApiBackend, fourcc: [u8; 4]) -> Self {
+        Self::new(backend, u128::from(u32::from_le_bytes(fourcc)))
+    }
+
+    /// Get the `FourCC` of this format, or `None` if it does not fit in one (e.g. a Media Foundation subtype `GUID`).
+    #[must_use]
+    pub fn fourcc(&self) -> Option<[u8; 4]> {
+        u32::try_from(self.format).ok().map(u32::to_le_bytes)
+    }
 }
 
 impl From<(ApiBackend, u128)> for PlatformSpecific {
@@ -183,7 +310,15 @@ impl PartialEq<(ApiBackend, u128)> for PlatformSpecific {
 }
 
 impl Display for PlatformSpecific {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let fourcc = self.fourcc();
+        match fourcc
+            .as_ref()
+            .and_then(|fourcc| core::str::from_utf8(fourcc).ok())
+            .filter(|fourcc| fourcc.chars().all(|c| c.is_ascii_graphic() || c == ' '))
+        {
+            Some(fourcc) => write!(f, "{}({fourcc})", self.backend),
+            None => write!(f, "{}({:#x})", self.backend, self.format),
+        }
     }
 }
@@ -2,6 +2,7 @@
 #![warn(clippy::all)]
 #![cfg_attr(feature = "test-fail-warning", deny(warnings))]
 #![cfg_attr(feature = "docs-features", feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
 /*
  * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
  *
@@ -19,11 +20,32 @@
  */
 
 //! Core type definitions for `nokhwa`
+//!
+//! The format types, plane math and pure-Rust converters only need `alloc`. Disable the default `std` feature to use them
+//! without `std`, e.g. in an embedded pipeline. The capture traits, decoders, events, profiling and validation need `std`.
+
+extern crate alloc;
+
+pub mod bayer;
 pub mod buffer;
+pub mod depth;
 pub mod error;
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
+pub mod events;
 pub mod format_request;
 pub mod frame_format;
+pub mod lut;
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
+pub mod profiling;
+pub mod thermal;
 pub mod traits;
 pub mod types;
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
+pub mod validation;
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
 pub mod decoder;
 pub mod utils;
@@ -0,0 +1,385 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Color grading of RGB frames with 1D curves (e.g. gamma) and 3D LUTs in the `.cube` format, applied after the frames
+//! were converted to [`FrameFormat::Rgb8`] or [`FrameFormat::RgbA8`].
+
+use crate::{
+    buffer::{Buffer, BufferPlane},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+};
+use alloc::{format, string::ToString, vec::Vec};
+use core::fmt::{Debug, Formatter};
+
+/// A color transform that can be applied to 8 bit RGB samples.
+pub trait ColorLut {
+    /// Transforms the RGB samples of `pixels` in place. Each pixel has `channels` samples, the first three being red, green and
+    /// blue, the others (e.g. alpha) are left as they are.
+    fn apply(&self, pixels: &mut [u8], channels: usize);
+}
+
+/// A curve per channel, e.g. a gamma curve, stored as one table lookup per sample.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Lut1d {
+    tables: [[u8; 256]; 3],
+}
+
+impl Lut1d {
+    /// Creates a [`Lut1d`] that changes nothing.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn identity() -> Self {
+        let mut table = [0; 256];
+        for (index, value) in table.iter_mut().enumerate() {
+            *value = index as u8;
+        }
+        Lut1d { tables: [table; 3] }
+    }
+
+    /// Creates a [`Lut1d`] from a `curve` mapping 0.0 - 1.0 to 0.0 - 1.0, applied to every channel. Values outside of that range are clamped.
+    #[must_use]
+    pub fn from_curve(curve: impl Fn(f32) -> f32) -> Self {
+        let table = curve_table(&curve);
+        Lut1d { tables: [table; 3] }
+    }
+
+    /// Creates a [`Lut1d`] with a curve per channel, see [`from_curve()`](Self::from_curve).
+    #[must_use]
+    pub fn from_curves(
+        red: impl Fn(f32) -> f32,
+        green: impl Fn(f32) -> f32,
+        blue: impl Fn(f32) -> f32,
+    ) -> Self {
+        Lut1d {
+            tables: [curve_table(&red), curve_table(&green), curve_table(&blue)],
+        }
+    }
+
+    /// Creates a [`Lut1d`] that applies `gamma` to every channel, `output = input ^ (1 / gamma)`. A `gamma` above 1.0 brightens
+    /// the midtones, below 1.0 darkens them.
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
+    #[must_use]
+    pub fn gamma(gamma: f32) -> Self {
+        let exponent = 1.0 / gamma.max(f32::EPSILON);
+        Lut1d::from_curve(|value| value.powf(exponent))
+    }
+
+    /// Get the table of a channel, 0 being red, 1 green and 2 blue.
+    #[must_use]
+    pub fn table(&self, channel: usize) -> Option<&[u8; 256]> {
+        self.tables.get(channel)
+    }
+}
+
+impl Default for Lut1d {
+    fn default() -> Self {
+        Lut1d::identity()
+    }
+}
+
+impl ColorLut for Lut1d {
+    fn apply(&self, pixels: &mut [u8], channels: usize) {
+        if channels < 3 {
+            return;
+        }
+        let [red, green, blue] = &self.tables;
+        for pixel in pixels.chunks_exact_mut(channels) {
+            pixel[0] = red[usize::from(pixel[0])];
+            pixel[1] = green[usize::from(pixel[1])];
+            pixel[2] = blue[usize::from(pixel[2])];
+        }
+    }
+}
+
+/// A 3D LUT, a lattice of output colors that every input color is interpolated from, usually loaded from a `.cube` file
+/// exported by a color grading tool.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut3d {
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    lattice: Vec<[f32; 3]>,
+    fixed: FixedLattice,
+}
+
+/// A [`Lut3d`] in fixed point, so [`apply()`](ColorLut::apply) needs no float math per pixel.
+#[derive(Clone, PartialEq)]
+struct FixedLattice {
+    // per axis and input sample, the index of the lower lattice point along that axis (already multiplied by the axis' stride)
+    // and the weight of the upper one, out of 256
+    steps: [[(usize, i32); 256]; 3],
+    // the lattice points, clamped and in 1/256ths of an output sample
+    points: Vec<[i32; 3]>,
+}
+
+impl Debug for FixedLattice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FixedLattice").finish_non_exhaustive()
+    }
+}
+
+impl Lut3d {
+    /// Creates a [`Lut3d`] with `size` points per axis, ordered with red changing fastest, then green, then blue, as in `.cube` files.
+    /// # Errors
+    /// If `size` is below 2 or there are not `size³` points, this will error.
+    pub fn new(size: usize, lattice: Vec<[f32; 3]>) -> Result<Self, NokhwaError> {
+        Lut3d::with_domain(size, [0.0; 3], [1.0; 3], lattice)
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_possible_wrap
+    )]
+    fn with_domain(
+        size: usize,
+        domain_min: [f32; 3],
+        domain_max: [f32; 3],
+        lattice: Vec<[f32; 3]>,
+    ) -> Result<Self, NokhwaError> {
+        if size < 2 || Some(lattice.len()) != size.checked_pow(3) {
+            return Err(NokhwaError::StructureError {
+                structure: "Lut3d".to_string(),
+                error: format!("Need {size}³ points of at least 2³, got {}", lattice.len()),
+            });
+        }
+
+        let mut steps = [[(0, 0); 256]; 3];
+        for (axis, (steps, stride)) in steps.iter_mut().zip([1, size, size * size]).enumerate() {
+            for (value, step) in (0_u8..=255).zip(steps.iter_mut()) {
+                let (index, fraction) = locate(
+                    size,
+                    domain_min[axis],
+                    domain_max[axis],
+                    f32::from(value) / 255.0,
+                );
+                *step = (index * stride, (fraction * 256.0 + 0.5) as i32);
+            }
+        }
+        let points = lattice
+            .iter()
+            .map(|point| point.map(|value| (value.clamp(0.0, 1.0) * 255.0 * 256.0 + 0.5) as i32))
+            .collect();
+
+        Ok(Lut3d {
+            size,
+            domain_min,
+            domain_max,
+            lattice,
+            fixed: FixedLattice { steps, points },
+        })
+    }
+
+    /// Parses a LUT in the `.cube` format (`LUT_3D_SIZE`, optional `DOMAIN_MIN`/`DOMAIN_MAX` and the lattice points).
+    /// # Errors
+    /// If the text is not a 3D `.cube` LUT, this will error.
+    pub fn parse_cube(cube: &str) -> Result<Self, NokhwaError> {
+        let error = |why: &str| NokhwaError::StructureError {
+            structure: "Lut3d".to_string(),
+            error: why.to_string(),
+        };
+        let parse_triple = |values: &[&str]| -> Result<[f32; 3], NokhwaError> {
+            match values {
+                [red, green, blue] => Ok([
+                    red.parse().map_err(|_| error("Bad number"))?,
+                    green.parse().map_err(|_| error("Bad number"))?,
+                    blue.parse().map_err(|_| error("Bad number"))?,
+                ]),
+                _ => Err(error("Expected 3 numbers")),
+            }
+        };
+
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut lattice = Vec::new();
+        for line in cube.lines() {
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            match words.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["LUT_1D_SIZE", ..] => {
+                    return Err(error("1D .cube LUTs are not supported, use a Lut1d"))
+                }
+                ["LUT_3D_SIZE", points] => {
+                    size = Some(points.parse().map_err(|_| error("Bad LUT_3D_SIZE"))?);
+                }
+                ["DOMAIN_MIN", values @ ..] => domain_min = parse_triple(values)?,
+                ["DOMAIN_MAX", values @ ..] => domain_max = parse_triple(values)?,
+                // e.g. `TITLE`, or keywords of other tools
+                [keyword, ..] if keyword.starts_with(char::is_alphabetic) => {}
+                values => lattice.push(parse_triple(values)?),
+            }
+        }
+
+        Lut3d::with_domain(
+            size.ok_or_else(|| error("Missing LUT_3D_SIZE"))?,
+            domain_min,
+            domain_max,
+            lattice,
+        )
+    }
+
+    /// Reads a `.cube` file, see [`parse_cube()`](Self::parse_cube).
+    /// # Errors
+    /// If the file can not be read or is not a 3D `.cube` LUT, this will error.
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
+    pub fn open_cube(path: impl AsRef<std::path::Path>) -> Result<Self, NokhwaError> {
+        let cube = std::fs::read_to_string(path.as_ref()).map_err(|why| {
+            NokhwaError::GeneralError(format!("Failed to read {}: {why}", path.as_ref().display()))
+        })?;
+        Lut3d::parse_cube(&cube)
+    }
+
+    /// Get how many points the lattice has per axis.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Looks up a color, interpolating between the 8 lattice points around it. Colors outside of the domain are clamped to it.
+    #[must_use]
+    pub fn sample(&self, color: [f32; 3]) -> [f32; 3] {
+        let mut base = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            (base[axis], fraction[axis]) = locate(
+                self.size,
+                self.domain_min[axis],
+                self.domain_max[axis],
+                color[axis],
+            );
+        }
+
+        let point = |red: usize, green: usize, blue: usize| {
+            self.lattice[(base[0] + red)
+                + (base[1] + green) * self.size
+                + (base[2] + blue) * self.size * self.size]
+        };
+        let mut output = [0.0; 3];
+        for (channel, value) in output.iter_mut().enumerate() {
+            let lerp = |low: f32, high: f32, t: f32| low + (high - low) * t;
+            let along_red = |green, blue| {
+                lerp(
+                    point(0, green, blue)[channel],
+                    point(1, green, blue)[channel],
+                    fraction[0],
+                )
+            };
+            let along_green = |blue| lerp(along_red(0, blue), along_red(1, blue), fraction[1]);
+            *value = lerp(along_green(0), along_green(1), fraction[2]);
+        }
+        output
+    }
+}
+
+impl ColorLut for Lut3d {
+    /// Interpolates in fixed point with weights looked up per sample, which is within one step of [`sample()`](Lut3d::sample).
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn apply(&self, pixels: &mut [u8], channels: usize) {
+        if channels < 3 {
+            return;
+        }
+        let FixedLattice { steps, points } = &self.fixed;
+        let (green, blue) = (self.size, self.size * self.size);
+        // the channels of both points are interpolated at once
+        let lerp = |low: [i32; 3], high: [i32; 3], weight: i32| {
+            [0, 1, 2].map(|channel| low[channel] + (((high[channel] - low[channel]) * weight) >> 8))
+        };
+
+        for pixel in pixels.chunks_exact_mut(channels) {
+            let (red_base, red_weight) = steps[0][usize::from(pixel[0])];
+            let (green_base, green_weight) = steps[1][usize::from(pixel[1])];
+            let (blue_base, blue_weight) = steps[2][usize::from(pixel[2])];
+            let base = red_base + green_base + blue_base;
+            let along_red =
+                |offset: usize| lerp(points[base + offset], points[base + offset + 1], red_weight);
+            let along_green =
+                |offset: usize| lerp(along_red(offset), along_red(offset + green), green_weight);
+            let graded = lerp(along_green(0), along_green(blue), blue_weight);
+            for (sample, value) in pixel.iter_mut().zip(graded) {
+                // rounds to the nearest sample, the points are clamped so this stays in 0 - 255
+                *sample = ((value + 128) >> 8) as u8;
+            }
+        }
+    }
+}
+
+impl Buffer {
+    /// Applies `lut` to this [`FrameFormat::Rgb8`] or [`FrameFormat::RgbA8`] frame, keeping its timestamp, sequence, metadata and
+    /// orientation.
+    /// # Errors
+    /// If the frame is not [`FrameFormat::Rgb8`] or [`FrameFormat::RgbA8`], this will error.
+    pub fn apply_lut(&self, lut: &dyn ColorLut) -> Result<Buffer, NokhwaError> {
+        let channels = match self.source_frame_format() {
+            FrameFormat::Rgb8 => 3,
+            FrameFormat::RgbA8 => 4,
+            other => {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: other,
+                    destination: "LUT".to_string(),
+                    error: "Only RGB frames can be graded, convert the frame first".to_string(),
+                })
+            }
+        };
+        let row_length = self.resolution().width() as usize * channels;
+        let stride = self
+            .planes()
+            .first()
+            .and_then(BufferPlane::stride)
+            .unwrap_or(row_length);
+        let mut pixels = self.buffer().to_vec();
+        // skip the padding at the end of the rows
+        for row in pixels.chunks_mut(stride.max(1)) {
+            let length = row.len().min(row_length);
+            lut.apply(&mut row[..length], channels);
+        }
+
+        let graded = self.with_samples(&pixels, self.source_frame_format());
+        Ok(match self.planes().first().and_then(BufferPlane::stride) {
+            Some(stride) => graded.with_strides(&[stride]),
+            None => graded,
+        })
+    }
+}
+
+/// Finds where `value` falls on an axis of a lattice with `size` points spanning `min` to `max`, as the index of the lower
+/// point and how far it is towards the next one. Values outside of the domain are clamped to it.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn locate(size: usize, min: f32, max: f32, value: f32) -> (usize, f32) {
+    let last = (size - 1) as f32;
+    let range = (max - min).max(f32::EPSILON);
+    let position = ((value - min) / range).clamp(0.0, 1.0) * last;
+    // the last cell interpolates up to the last point
+    let index = (position as usize).min(size - 2);
+    (index, position - index as f32)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn curve_table(curve: &impl Fn(f32) -> f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (index, value) in (0_u8..=255).zip(table.iter_mut()) {
+        *value = (curve(f32::from(index) / 255.0).clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    }
+    table
+}
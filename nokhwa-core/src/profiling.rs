@@ -0,0 +1,214 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Opt-in per frame latency profiling, to find out which stage of a real-time pipeline the time goes to.
+//!
+//! A [`LatencyProfiler`] is a cheap handle, clones record into the same samples. Stages that `nokhwa` does not run itself (e.g. decoding)
+//! are recorded with [`LatencyProfiler::time()`]:
+//! ```ignore
+//! let image = profiler.time(LatencyStage::Decode, || buffer.decode_image::<RgbFormat>())?;
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A stage a frame goes through on its way from the sensor to the application.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum LatencyStage {
+    /// Waiting for the backend to hand over a frame, including its copy out of the driver's memory.
+    DriverDelivery,
+    /// Copying the frame between threads, e.g. into the last frame of a `CallbackCamera`.
+    Copy,
+    /// Decoding the frame into an image.
+    Decode,
+    /// Running the frame callback.
+    CallbackDispatch,
+}
+
+impl LatencyStage {
+    /// Every stage, in the order a frame goes through them.
+    pub const ALL: [LatencyStage; 4] = [
+        LatencyStage::DriverDelivery,
+        LatencyStage::Copy,
+        LatencyStage::Decode,
+        LatencyStage::CallbackDispatch,
+    ];
+}
+
+impl Display for LatencyStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// The latency of a [`LatencyStage`] over the profiler's window.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageLatency {
+    /// The stage.
+    pub stage: LatencyStage,
+    /// How many frames were measured.
+    pub samples: usize,
+    /// The average latency.
+    pub mean: Duration,
+    /// The median latency.
+    pub p50: Duration,
+    /// The latency 90% of frames stayed under.
+    pub p90: Duration,
+    /// The latency 99% of frames stayed under.
+    pub p99: Duration,
+    /// The highest latency.
+    pub max: Duration,
+}
+
+impl StageLatency {
+    fn new(stage: LatencyStage, samples: &VecDeque<Duration>) -> Option<Self> {
+        let mut sorted = samples.iter().copied().collect::<Vec<Duration>>();
+        sorted.sort_unstable();
+        let percentile = |percent: usize| sorted[(sorted.len() - 1) * percent / 100];
+        let total = sorted.iter().sum::<Duration>();
+
+        Some(StageLatency {
+            stage,
+            samples: sorted.len(),
+            mean: total / u32::try_from(sorted.len()).ok().filter(|len| *len != 0)?,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: *sorted.last()?,
+        })
+    }
+}
+
+/// The aggregated latencies of every [`LatencyStage`] that was measured, see [`LatencyProfiler::report()`].
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyReport {
+    stages: Vec<StageLatency>,
+}
+
+impl LatencyReport {
+    /// Get the latencies of every measured stage, in pipeline order.
+    #[must_use]
+    pub fn stages(&self) -> &[StageLatency] {
+        &self.stages
+    }
+
+    /// Get the latency of `stage`, if it was measured.
+    #[must_use]
+    pub fn stage(&self, stage: LatencyStage) -> Option<&StageLatency> {
+        self.stages.iter().find(|latency| latency.stage == stage)
+    }
+
+    /// Get the sum of the median latencies of every stage, about how long a typical frame spends in the pipeline.
+    #[must_use]
+    pub fn total_p50(&self) -> Duration {
+        self.stages.iter().map(|latency| latency.p50).sum()
+    }
+}
+
+impl Display for LatencyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for latency in &self.stages {
+            writeln!(
+                f,
+                "{}: p50 {:?}, p90 {:?}, p99 {:?}, max {:?} ({} frames)",
+                latency.stage, latency.p50, latency.p90, latency.p99, latency.max, latency.samples
+            )?;
+        }
+        write!(f, "total p50: {:?}", self.total_p50())
+    }
+}
+
+/// Records how long each frame spends in each [`LatencyStage`], keeping the latest `window` frames of every stage.
+#[derive(Clone, Debug)]
+pub struct LatencyProfiler {
+    window: usize,
+    samples: Arc<Mutex<HashMap<LatencyStage, VecDeque<Duration>>>>,
+}
+
+impl LatencyProfiler {
+    /// Creates a new [`LatencyProfiler`] that keeps the latest `window` frames of every stage.
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        LatencyProfiler {
+            window: window.max(1),
+            samples: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Get how many frames of every stage are kept.
+    #[must_use]
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Records that a frame spent `duration` in `stage`.
+    pub fn record(&self, stage: LatencyStage, duration: Duration) {
+        // a poisoned lock only means a recording thread panicked, the samples are fine
+        let mut samples = self
+            .samples
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let stage_samples = samples.entry(stage).or_default();
+        if stage_samples.len() == self.window {
+            stage_samples.pop_front();
+        }
+        stage_samples.push_back(duration);
+    }
+
+    /// Runs `stage` and records how long it took.
+    pub fn time<T>(&self, stage: LatencyStage, run: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let output = run();
+        self.record(stage, start.elapsed());
+        output
+    }
+
+    /// Aggregates the recorded samples into a [`LatencyReport`].
+    #[must_use]
+    pub fn report(&self) -> LatencyReport {
+        let samples = self
+            .samples
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        LatencyReport {
+            stages: LatencyStage::ALL
+                .into_iter()
+                .filter_map(|stage| StageLatency::new(stage, samples.get(&stage)?))
+                .collect(),
+        }
+    }
+
+    /// Forgets every recorded sample.
+    pub fn reset(&self) {
+        self.samples
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl Default for LatencyProfiler {
+    fn default() -> Self {
+        LatencyProfiler::new(1000)
+    }
+}
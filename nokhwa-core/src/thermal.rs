@@ -0,0 +1,215 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Radiometric thermal cameras, e.g. FLIR Lepton and Boson modules behind a UVC bridge (`PureThermal`), which stream
+//! [`FrameFormat::Luma16`] frames whose samples are temperatures rather than brightness.
+//!
+//! A [`TemperatureScale`] turns samples into temperatures, and a [`ToneMap`] turns such a frame into [`FrameFormat::Luma8`] for display.
+
+use crate::{
+    buffer::Buffer,
+    error::NokhwaError,
+    frame_format::FrameFormat,
+};
+use alloc::{vec, vec::Vec};
+
+/// The offset between Kelvin and degrees Celsius.
+const ZERO_CELSIUS: f32 = 273.15;
+
+/// Converts the raw samples of a radiometric frame into temperatures.
+///
+/// [`RadiometricScale`] covers the linear output (`TLinear`) of most thermal cores. Implement this for calibrations that are not linear.
+pub trait TemperatureScale {
+    /// The temperature of `sample`, in Kelvin.
+    fn kelvin(&self, sample: u16) -> f32;
+
+    /// The temperature of `sample`, in degrees Celsius.
+    fn celsius(&self, sample: u16) -> f32 {
+        self.kelvin(sample) - ZERO_CELSIUS
+    }
+}
+
+/// A linear mapping from a radiometric sample to a temperature: `sample * millikelvin_per_count + offset_millikelvin`.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct RadiometricScale {
+    millikelvin_per_count: u32,
+    offset_millikelvin: i64,
+}
+
+impl RadiometricScale {
+    /// FLIR `TLinear` in high resolution mode, where a count is 0.01 K. The default of the Lepton 3.5 and Boson.
+    pub const TLINEAR_HIGH_RESOLUTION: RadiometricScale = RadiometricScale::new(10, 0);
+    /// FLIR `TLinear` in low resolution mode, where a count is 0.1 K. Used for scenes hotter than 382 °C.
+    pub const TLINEAR_LOW_RESOLUTION: RadiometricScale = RadiometricScale::new(100, 0);
+
+    /// Creates a new [`RadiometricScale`].
+    #[must_use]
+    pub const fn new(millikelvin_per_count: u32, offset_millikelvin: i64) -> Self {
+        RadiometricScale {
+            millikelvin_per_count,
+            offset_millikelvin,
+        }
+    }
+
+    /// Get how many millikelvin one count is.
+    #[must_use]
+    pub fn millikelvin_per_count(&self) -> u32 {
+        self.millikelvin_per_count
+    }
+
+    /// Get the temperature of a sample of `0`, in millikelvin.
+    #[must_use]
+    pub fn offset_millikelvin(&self) -> i64 {
+        self.offset_millikelvin
+    }
+
+    /// The sample closest to `kelvin`, e.g. for the bounds of [`ToneMap::Fixed`]. Temperatures outside of the sensor's range saturate.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn sample(&self, kelvin: f32) -> u16 {
+        if self.millikelvin_per_count == 0 {
+            return 0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let counts =
+            (kelvin * 1000.0 - self.offset_millikelvin as f32) / self.millikelvin_per_count as f32;
+        // rounds to the nearest sample, `f32::round` needs `std`
+        (counts.clamp(0.0, f32::from(u16::MAX)) + 0.5) as u16
+    }
+}
+
+impl Default for RadiometricScale {
+    fn default() -> Self {
+        RadiometricScale::TLINEAR_HIGH_RESOLUTION
+    }
+}
+
+impl TemperatureScale for RadiometricScale {
+    #[allow(clippy::cast_precision_loss)]
+    fn kelvin(&self, sample: u16) -> f32 {
+        let millikelvin =
+            i64::from(sample) * i64::from(self.millikelvin_per_count) + self.offset_millikelvin;
+        millikelvin as f32 / 1000.0
+    }
+}
+
+/// How [`Buffer::tone_map`] squeezes 16 bit samples into 8 bits for display.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToneMap {
+    /// Stretches the coldest to the hottest sample of each frame over the full range, like most thermal viewers do by default.
+    MinMax,
+    /// Stretches `low..=high` over the full range and clips the samples outside of it. Unlike the other two, a temperature keeps
+    /// its brightness from frame to frame.
+    Fixed { low: u16, high: u16 },
+    /// Histogram equalization, which brings out detail in scenes with a few very hot or cold spots.
+    Equalize,
+}
+
+impl Buffer {
+    /// Get the samples of a [`FrameFormat::Luma16`] frame, row by row without padding.
+    /// # Errors
+    /// If the frame is not [`FrameFormat::Luma16`], or the buffer is too short for its resolution, this will error.
+    pub fn luma16_samples(&self) -> Result<Vec<u16>, NokhwaError> {
+        self.u16_samples(FrameFormat::Luma16)
+    }
+
+    /// Get the temperature of every sample of a radiometric [`FrameFormat::Luma16`] frame in Kelvin, row by row.
+    ///
+    /// Use [`radiometric_scale`](Self::radiometric_scale) for the scale the camera reported, if any.
+    /// # Errors
+    /// See [`luma16_samples`](Self::luma16_samples).
+    pub fn temperatures(&self, scale: &impl TemperatureScale) -> Result<Vec<f32>, NokhwaError> {
+        Ok(self
+            .luma16_samples()?
+            .into_iter()
+            .map(|sample| scale.kelvin(sample))
+            .collect())
+    }
+
+    /// Attaches the [`RadiometricScale`] of the frame to its [`FrameMetadata`](crate::buffer::FrameMetadata), e.g. from a thermal core's
+    /// `TLinear` resolution control.
+    #[must_use]
+    pub fn with_radiometric_scale(self, scale: RadiometricScale) -> Self {
+        let mut metadata = self.frame_metadata().cloned().unwrap_or_default();
+        metadata.radiometric_scale = Some(scale);
+        self.with_frame_metadata(metadata)
+    }
+
+    /// Get the [`RadiometricScale`] of the frame, if it has one.
+    #[must_use]
+    pub fn radiometric_scale(&self) -> Option<RadiometricScale> {
+        self.frame_metadata()
+            .and_then(|metadata| metadata.radiometric_scale)
+    }
+
+    /// Tone maps a [`FrameFormat::Luma16`] frame into a [`FrameFormat::Luma8`] one for display.
+    ///
+    /// The timestamp, sequence number, metadata and orientation are kept.
+    /// # Errors
+    /// See [`luma16_samples`](Self::luma16_samples).
+    pub fn tone_map(&self, tone_map: ToneMap) -> Result<Buffer, NokhwaError> {
+        let samples = self.luma16_samples()?;
+        let mapped = match tone_map {
+            ToneMap::MinMax => {
+                let low = samples.iter().copied().min().unwrap_or_default();
+                let high = samples.iter().copied().max().unwrap_or_default();
+                stretch(&samples, low, high)
+            }
+            ToneMap::Fixed { low, high } => stretch(&samples, low.min(high), high.max(low)),
+            ToneMap::Equalize => equalize(&samples),
+        };
+
+        Ok(self.with_samples(&mapped, FrameFormat::Luma8))
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn stretch(samples: &[u16], low: u16, high: u16) -> Vec<u8> {
+    let range = u32::from(high - low).max(1);
+    samples
+        .iter()
+        .map(|sample| {
+            let offset = u32::from(sample.clamp(&low, &high) - low);
+            (offset * 255 / range) as u8
+        })
+        .collect()
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn equalize(samples: &[u16]) -> Vec<u8> {
+    let mut histogram = vec![0_u64; usize::from(u16::MAX) + 1];
+    for sample in samples {
+        histogram[usize::from(*sample)] += 1;
+    }
+    // the cumulative distribution, starting at the first sample that occurs so the coldest one is black
+    let mut total = 0;
+    for count in &mut histogram {
+        total += *count;
+        *count = total;
+    }
+    let first = histogram
+        .iter()
+        .copied()
+        .find(|count| *count != 0)
+        .unwrap_or_default();
+    let range = (total - first).max(1);
+    samples
+        .iter()
+        .map(|sample| ((histogram[usize::from(*sample)] - first) * 255 / range) as u8)
+        .collect()
+}
@@ -17,14 +17,19 @@
 use crate::{
     buffer::Buffer,
     error::NokhwaError,
+    types::{ApiBackend, CameraFormat, DeviceEvent},
+};
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use crate::{
+    frame_format::FrameFormat,
     types::{
-        ApiBackend, CameraControl, CameraFormat, CameraInfo, ControlValueSetter,
-        KnownCameraControl, Resolution,
+        CameraControl, CameraInfo, ControlChange, ControlValueDescription, ControlValueSetter,
+        FrameRate, KnownCameraControl, PhotoSettings, Rect, Resolution, StreamEvent,
     },
 };
+#[cfg(feature = "std")]
 use std::{borrow::Cow, collections::HashMap};
-use crate::frame_format::FrameFormat;
-use crate::types::FrameRate;
 
 pub trait Backend {
     const BACKEND: ApiBackend;
@@ -37,6 +42,8 @@ pub trait Backend {
 /// - Backends, if not provided with a camera format, will be spawned with 640x480@15 FPS, MJPEG [`CameraFormat`].
 /// - Behaviour can differ from backend to backend. While the Camera struct abstracts most of this away, if you plan to use the raw backend structs please read the `Quirks` section of each backend.
 /// - If you call [`stop_stream()`](CaptureTrait::stop_stream()), you will usually need to call [`open_stream()`](CaptureTrait::open_stream()) to get more frames from the camera.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
 pub trait CaptureTrait {
     /// Initialize the camera, preparing it for use, with a random format (usually the first one).
     fn init(&mut self) -> Result<(), NokhwaError>;
@@ -149,6 +156,73 @@ pub trait CaptureTrait {
         value: ControlValueSetter,
     ) -> Result<(), NokhwaError>;
 
+    /// Gets the crop rectangle of the camera, as a [`ControlValueDescription::Area`] holding the current crop, the bounds it can be set
+    /// within, and the default.
+    /// # Errors
+    /// If the backend or camera does not support cropping, or the crop cannot be read, this will error.
+    fn crop(&self) -> Result<ControlValueDescription, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Sets the crop rectangle of the camera, letting the hardware crop the image (e.g. for digital zoom). The camera may round the
+    /// rectangle to what it supports, check [`crop()`](CaptureTrait::crop) for the applied value.
+    /// # Errors
+    /// If the backend or camera does not support cropping, or the camera rejects the rectangle, this will error.
+    fn set_crop(&mut self, crop: Rect) -> Result<(), NokhwaError> {
+        let _ = crop;
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Calls `callback` whenever a control changes, including changes the camera makes itself (e.g. automatic exposure or focus).
+    /// Setting a new callback replaces the previous one. The callback is called from a different thread.
+    /// # Errors
+    /// If the backend or camera does not support control change notifications, this will error.
+    fn on_control_change(
+        &mut self,
+        callback: Box<dyn FnMut(ControlChange) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        let _ = callback;
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Calls `callback` whenever a [`StreamEvent`] happens, e.g. a frame is dropped or another application takes the camera.
+    /// Setting a new callback replaces the previous one. The callback is called from a different thread.
+    /// # Errors
+    /// If the backend does not report stream events, this will error.
+    fn on_stream_event(
+        &mut self,
+        callback: Box<dyn FnMut(StreamEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        let _ = callback;
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Gets the number of frames dropped since the stream was opened, e.g. because the application did not keep up or the camera
+    /// skipped frames. The [`sequence()`](Buffer::sequence) of the frames skips the dropped ones.
+    /// # Errors
+    /// If the backend does not count dropped frames, this will error.
+    fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Gets whether the camera's hardware privacy switch or shutter is engaged, blanking the image regardless of any control.
+    /// Unlike [`KnownCameraControl::Privacy`](crate::types::KnownCameraControl::Privacy), this can not be changed from software.
+    /// # Errors
+    /// If the backend or camera does not report a hardware privacy state, this will error.
+    fn hardware_privacy(&self) -> Result<bool, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Takes a still photo with `settings`, which may be larger than the video frames and is processed (and encoded) by the platform.
+    /// If the stream is open, it keeps running while the photo is taken.
+    /// # Errors
+    /// If the backend or camera does not support still photos, the [`PhotoCodec`](crate::types::PhotoCodec) is not available, or
+    /// taking the photo fails, this will error.
+    fn take_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        let _ = settings;
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     /// Will open the camera stream with set parameters. This will be called internally if you try and call [`frame()`](CaptureTrait::frame()) before you call [`open_stream()`](CaptureTrait::open_stream()).
     /// # Errors
     /// If the specific backend fails to open the camera (e.g. already taken, busy, doesn't exist anymore) this will error.
@@ -235,6 +309,7 @@ pub trait CaptureTrait {
     fn stop_stream(&mut self) -> Result<(), NokhwaError>;
 }
 
+#[cfg(feature = "std")]
 impl<T> From<T> for Box<dyn CaptureTrait>
 where
     T: CaptureTrait + 'static,
@@ -244,8 +319,19 @@ where
     }
 }
 
+/// The async version of [`CaptureTrait`], for backends whose camera is promise or future based underneath (e.g. the browser),
+/// so the same application code runs on native and web targets.
+///
+/// On `wasm32` its futures are not `Send`, as the browser's objects can not leave their thread.
 #[cfg(feature = "async")]
-#[cfg_attr(feature = "async", async_trait::async_trait)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
 pub trait AsyncCaptureTrait: CaptureTrait {
     /// Initialize the camera, preparing it for use, with a random format (usually the first one).
     async fn init_async(&mut self) -> Result<(), NokhwaError>;
@@ -353,6 +439,8 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "std")))]
 pub trait OneShot: CaptureTrait {
     fn one_shot(&mut self) -> Result<Buffer, NokhwaError> {
         if self.is_stream_open() {
@@ -367,7 +455,14 @@ pub trait OneShot: CaptureTrait {
 }
 
 #[cfg(feature = "async")]
-#[cfg_attr(feature = "async", async_trait::async_trait)]
+#[cfg_attr(
+    all(feature = "async", not(target_arch = "wasm32")),
+    async_trait::async_trait
+)]
+#[cfg_attr(
+    all(feature = "async", target_arch = "wasm32"),
+    async_trait::async_trait(?Send)
+)]
 pub trait AsyncOneShot: AsyncCaptureTrait {
     async fn one_shot(&mut self) -> Result<Buffer, NokhwaError> {
         if self.is_stream_open() {
@@ -381,7 +476,66 @@ pub trait AsyncOneShot: AsyncCaptureTrait {
     }
 }
 
-pub trait VirtualBackendTrait {}
+/// This trait is for any backend that exposes a virtual camera, which other applications (e.g. browsers or conferencing apps) capture
+/// from like a physical one while `nokhwa` writes its frames.
+pub trait VirtualBackendTrait {
+    /// Returns the current backend used.
+    fn backend(&self) -> ApiBackend;
+
+    /// Gets the [`CameraFormat`] frames are currently written in.
+    fn camera_format(&self) -> CameraFormat;
+
+    /// Negotiates a new [`CameraFormat`] with the virtual camera. Consumers may need to reopen the camera to pick up the change.
+    /// # Errors
+    /// If the virtual camera rejects the format, this will error.
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError>;
+
+    /// Writes a frame to the virtual camera. The [`Buffer`] must have the [`Resolution`] and [`FrameFormat`] of the current [`CameraFormat`].
+    ///
+    /// Frames are paced to the frame rate of the current [`CameraFormat`], so this will block if called faster than that.
+    /// # Errors
+    /// If the frame does not match the current [`CameraFormat`] or can not be written, this will error.
+    fn write_frame(&mut self, buffer: &Buffer) -> Result<(), NokhwaError>;
+}
+
+/// This trait is for any backend that can watch for cameras being plugged in or unplugged, so applications do not have to poll `query()`.
+pub trait DeviceWatcher {
+    /// Returns the backend whose cameras are watched.
+    fn backend(&self) -> ApiBackend;
+
+    /// Starts calling `callback` with a [`DeviceEvent`] whenever a camera is plugged in or unplugged. Cameras that are already
+    /// connected are not reported. The callback is called from a different thread, and starting again replaces it.
+    /// # Errors
+    /// If the backend fails to start watching, this will error.
+    fn start(
+        &mut self,
+        callback: Box<dyn FnMut(DeviceEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError>;
+
+    /// Stops watching. The callback is not called once this returns.
+    /// # Errors
+    /// If the backend fails to stop watching, this will error.
+    fn stop(&mut self) -> Result<(), NokhwaError>;
+
+    /// Returns `true` if the watcher is started.
+    fn is_watching(&self) -> bool;
+}
+
+/// This trait is for anything frames can be written to, e.g. a file, a recorder or a virtual camera, so captured frames can be
+/// sent to several outputs without a hand written capture loop.
+pub trait FrameSink {
+    /// Writes a frame to the sink.
+    /// # Errors
+    /// If the sink can not take the frame (e.g. it has the wrong format) or fails to write it, this will error.
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError>;
+
+    /// Flushes the sink once no more frames will be written, e.g. to write a container trailer. Does nothing by default.
+    /// # Errors
+    /// If the sink fails to flush, this will error.
+    fn finish(&mut self) -> Result<(), NokhwaError> {
+        Ok(())
+    }
+}
 
 pub trait Distance<T> where T: PartialEq {
     fn distance_from(&self, other: &Self) -> T;
@@ -4,7 +4,12 @@ use crate::{
 };
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
-use std::{
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
     fmt::{
         Debug,
         Display,
@@ -12,7 +17,8 @@ use std::{
     },
     borrow::Borrow,
     cmp::Ordering,
-    hash::{Hash, Hasher}
+    hash::{Hash, Hasher},
+    time::Duration,
 };
 use crate::traits::Distance;
 
@@ -205,7 +211,7 @@ impl CameraIndex {
 }
 
 impl Display for CameraIndex {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.as_string())
     }
 }
@@ -297,7 +303,7 @@ impl Resolution {
 }
 
 impl Display for Resolution {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}x{}", self.x(), self.y())
     }
 }
@@ -330,9 +336,56 @@ impl Distance<u32> for Resolution {
     }
 }
 
+/// A rectangle on the camera's sensor or image, in pixels, e.g. a crop region.
+/// `x` and `y` are the offset of the top left corner, and may be negative on devices whose active area does not start at `(0, 0)`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Create a new rectangle with its top left corner at (`x`, `y`).
+    #[must_use]
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get the size of this rectangle as a [`Resolution`].
+    #[must_use]
+    pub fn size(&self) -> Resolution {
+        Resolution::new(self.width, self.height)
+    }
+
+    /// Returns `true` if `other` lies entirely within this rectangle.
+    #[must_use]
+    pub fn contains(&self, other: &Rect) -> bool {
+        let right = i64::from(self.x) + i64::from(self.width);
+        let bottom = i64::from(self.y) + i64::from(self.height);
+        other.x >= self.x
+            && other.y >= self.y
+            && i64::from(other.x) + i64::from(other.width) <= right
+            && i64::from(other.y) + i64::from(other.height) <= bottom
+    }
+}
+
+impl Display for Rect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
-/// The frame rate of a camera. DO NOT CONSTRUCT THIS ENUM DIRECTLY. YOU WILL VIOLATE INVARIANTS. Use [`FrameRate::new_integer`], [`FrameRate::new_fraction`], or [`FrameRate::new_float`] instead. 
+/// The frame rate of a camera. DO NOT CONSTRUCT THIS ENUM DIRECTLY. YOU WILL VIOLATE INVARIANTS. Use [`FrameRate::new_integer`], [`FrameRate::new_fraction`], [`FrameRate::new_rational`], or [`FrameRate::new_float`] instead. 
 pub enum FrameRate {
     /// The driver reports the frame rate as a clean integer (e.g. 30 FPS).
     Integer(u32),
@@ -355,7 +408,7 @@ impl FrameRate {
     }
 
     pub fn new_float(fps: f32) -> Result<Self, NokhwaError> {
-        if fps.is_nan() || fps.is_infinite() || fps.is_sign_negative() || (fps > f32::EPSILON) {
+        if fps.is_nan() || fps.is_infinite() || fps.is_sign_negative() || (fps <= f32::EPSILON) {
             return Err(NokhwaError::StructureError { structure: "FrameRate".to_string(), error: "Invalid F32 FrameRate".to_string() })
         }
         
@@ -375,6 +428,44 @@ impl FrameRate {
         )
     }
 
+    /// Creates a [`FrameRate`] from a ratio as drivers report it, e.g. `30000/1001` for 29.97 FPS or V4L2's frame interval
+    /// flipped over. The ratio is reduced, whole rates become [`FrameRate::Integer`], and ratios that do not fit a
+    /// [`FrameRate::Fraction`] even when reduced (e.g. `10000000/333333`) become [`FrameRate::Float`].
+    /// # Errors
+    /// If `numerator` or `denominator` is 0, this will error.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new_rational(numerator: u32, denominator: u32) -> Result<Self, NokhwaError> {
+        if numerator == 0 || denominator == 0 {
+            return Err(NokhwaError::StructureError { structure: "FrameRate".to_string(), error: "Invalid Fraction (denominator or numerator is 0)".to_string() })
+        }
+
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+        if denominator == 1 {
+            return Ok(FrameRate::Integer(numerator));
+        }
+        match (u16::try_from(numerator), u16::try_from(denominator)) {
+            (Ok(numerator), Ok(denominator)) => Ok(FrameRate::Fraction { numerator, denominator }),
+            _ => Ok(FrameRate::Float(numerator as f32 / denominator as f32)),
+        }
+    }
+
+    /// Get this frame rate as a `numerator/denominator` ratio, e.g. to hand it to a driver. [`FrameRate::Float`]s are approximated
+    /// to a thousandth of a frame, reduced.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn as_rational(&self) -> (u32, u32) {
+        match self {
+            FrameRate::Integer(fps) => (*fps, 1),
+            FrameRate::Fraction { numerator, denominator } => (u32::from(*numerator), u32::from(*denominator)),
+            FrameRate::Float(fps) => {
+                let thousandths = (fps * 1000.0 + 0.5) as u32;
+                let divisor = gcd(thousandths, 1000).max(1);
+                (thousandths / divisor, 1000 / divisor)
+            }
+        }
+    }
+
     pub fn as_float(&self) -> f32 {
         match self {
             FrameRate::Integer(fps) => *fps as f32,
@@ -424,7 +515,7 @@ impl Hash for FrameRate {
 }
 
 impl Display for FrameRate {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             FrameRate::Integer(fps) => write!(f, "Framerate: {fps} FPS"),
             FrameRate::Float(fps) => write!(f, "Framerate: {fps} FPS"),
@@ -438,13 +529,19 @@ impl Display for FrameRate {
 
 impl Distance<f32> for FrameRate {
     fn distance_from(&self, other: &Self) -> f32 {
-        let self_as_float = self.as_float();
-        let other_as_float = other.as_float();
+        let difference = self.as_float() - other.as_float();
 
-        self_as_float.powi(2) + other_as_float.powi(2)
+        difference * difference
     }
 }
 
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
 impl From<u32> for FrameRate {
     fn from(value: u32) -> Self {
         FrameRate::Integer(value)
@@ -557,7 +654,7 @@ impl Default for CameraFormat {
 }
 
 impl Display for CameraFormat {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{}@{}FPS, {} Format",
@@ -577,6 +674,11 @@ pub struct CameraInfo {
     description: String,
     misc: String,
     index: CameraIndex,
+    details: Option<DeviceDetails>,
+    position: CameraPosition,
+    related: Vec<CameraIndex>,
+    audio_device: Option<AudioDeviceInfo>,
+    kind: DeviceKind,
 }
 
 #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_class = CameraInfo))]
@@ -595,6 +697,11 @@ impl CameraInfo {
             description: description.to_string(),
             misc: misc.to_string(),
             index: index.clone(),
+            details: None,
+            position: CameraPosition::Unknown,
+            related: Vec::new(),
+            audio_device: None,
+            kind: DeviceKind::Camera,
         }
     }
 
@@ -694,8 +801,157 @@ impl CameraInfo {
     // }
 }
 
+impl CameraInfo {
+    /// Sets the [`DeviceDetails`] of the device.
+    #[must_use]
+    pub fn with_details(mut self, details: DeviceDetails) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Get the [`DeviceDetails`] of the device, if the backend provides them.
+    #[must_use]
+    pub fn details(&self) -> Option<&DeviceDetails> {
+        self.details.as_ref()
+    }
+
+    /// Sets the [`CameraPosition`] of the device.
+    #[must_use]
+    pub fn with_position(mut self, position: CameraPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Get where the device is mounted, [`CameraPosition::Unknown`] if the backend does not say.
+    #[must_use]
+    pub fn position(&self) -> CameraPosition {
+        self.position
+    }
+
+    /// Adds the index of a device that is part of the same physical camera, e.g. the Desk View of a Continuity Camera.
+    #[must_use]
+    pub fn with_related_device(mut self, index: CameraIndex) -> Self {
+        if !self.related.contains(&index) {
+            self.related.push(index);
+        }
+        self
+    }
+
+    /// Get the indexes of the devices that are part of the same physical camera, which can be captured together with this one.
+    #[must_use]
+    pub fn related_devices(&self) -> &[CameraIndex] {
+        &self.related
+    }
+
+    /// Sets the microphone that is built into the same device as the camera.
+    #[must_use]
+    pub fn with_audio_device(mut self, audio_device: AudioDeviceInfo) -> Self {
+        self.audio_device = Some(audio_device);
+        self
+    }
+
+    /// Get the microphone that is built into the same device as the camera (e.g. a camcorder or webcam), if the backend knows it.
+    /// Recording applications can use it to pick the matching microphone.
+    #[must_use]
+    pub fn audio_device(&self) -> Option<&AudioDeviceInfo> {
+        self.audio_device.as_ref()
+    }
+
+    /// Sets the [`DeviceKind`] of the device.
+    #[must_use]
+    pub fn with_kind(mut self, kind: DeviceKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Get what the device captures, [`DeviceKind::Camera`] unless the backend lists the screen as a device.
+    #[must_use]
+    pub fn kind(&self) -> DeviceKind {
+        self.kind
+    }
+}
+
+/// What a device captures, see [`CameraInfo::kind()`].
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum DeviceKind {
+    /// A camera.
+    #[default]
+    Camera,
+    /// The screen, or a part of it such as a window or browser tab. Which one is usually picked by the user once it is opened.
+    Screen,
+}
+
+/// Where a camera is mounted relative to the user, e.g. the front facing camera of a laptop or phone.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CameraPosition {
+    /// The backend did not say.
+    #[default]
+    Unknown,
+    /// Faces the user, e.g. the built-in camera of a laptop. Its frames are usually shown mirrored.
+    Front,
+    /// Faces away from the user.
+    Back,
+    /// Not part of the device, e.g. a USB webcam.
+    External,
+}
+
+/// Whether the application may use the cameras, as the operating system's privacy settings say.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum PermissionStatus {
+    /// The user has not been asked yet. Requesting permission shows them a prompt.
+    NotDetermined,
+    /// The user denied access. Only they can change that, in the system's privacy settings.
+    Denied,
+    /// Access is blocked by a policy the user can not change, e.g. a device management (MDM) profile or parental controls.
+    Restricted,
+    /// Access was granted.
+    Granted,
+}
+
+impl PermissionStatus {
+    /// Returns `true` if access was granted.
+    #[must_use]
+    pub fn is_granted(&self) -> bool {
+        *self == PermissionStatus::Granted
+    }
+}
+
+/// A microphone that belongs to a camera, see [`CameraInfo::audio_device()`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AudioDeviceInfo {
+    name: String,
+    id: String,
+}
+
+impl AudioDeviceInfo {
+    /// Create a new [`AudioDeviceInfo`].
+    #[must_use]
+    pub fn new(name: &str, id: &str) -> Self {
+        AudioDeviceInfo {
+            name: name.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    /// Get the human readable name of the microphone.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the ID the OS knows the microphone by, e.g. the Core Audio device UID on macOS.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
 impl Display for CameraInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Name: {}, Description: {}, Extra: {}, Index: {}",
@@ -704,6 +960,87 @@ impl Display for CameraInfo {
     }
 }
 
+/// Structured information about the device behind a [`CameraInfo`], for backends that provide it (`V4L2`).
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct DeviceDetails {
+    driver: String,
+    card: String,
+    bus_info: String,
+    capabilities: DeviceCapabilities,
+}
+
+impl DeviceDetails {
+    /// Create a new [`DeviceDetails`].
+    #[must_use]
+    pub fn new(
+        driver: &str,
+        card: &str,
+        bus_info: &str,
+        capabilities: DeviceCapabilities,
+    ) -> Self {
+        DeviceDetails {
+            driver: driver.to_string(),
+            card: card.to_string(),
+            bus_info: bus_info.to_string(),
+            capabilities,
+        }
+    }
+
+    /// Get the name of the driver, e.g. `uvcvideo`.
+    #[must_use]
+    pub fn driver(&self) -> &str {
+        &self.driver
+    }
+
+    /// Get the name of the device as the driver reports it.
+    #[must_use]
+    pub fn card(&self) -> &str {
+        &self.card
+    }
+
+    /// Get the location of the device on its bus, e.g. `usb-0000:00:14.0-1`.
+    #[must_use]
+    pub fn bus_info(&self) -> &str {
+        &self.bus_info
+    }
+
+    /// Get the [`DeviceCapabilities`] of the device.
+    #[must_use]
+    pub fn capabilities(&self) -> DeviceCapabilities {
+        self.capabilities
+    }
+}
+
+/// What a device node can do. One physical camera may expose several nodes, e.g. UVC cameras expose a metadata node next to the video one.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct DeviceCapabilities {
+    /// Captures video through the single-planar API.
+    pub video_capture: bool,
+    /// Captures video through the multi-planar API.
+    pub multiplanar: bool,
+    /// Captures metadata (e.g. UVC frame metadata) instead of video.
+    pub meta_capture: bool,
+    /// Is a memory-to-memory device, such as a hardware codec or scaler, instead of a camera.
+    pub memory_to_memory: bool,
+    /// Outputs video, such as a virtual camera.
+    pub video_output: bool,
+    /// Supports streaming I/O.
+    pub streaming: bool,
+    /// Supports reading frames with `read()`.
+    pub read_write: bool,
+}
+
+impl DeviceCapabilities {
+    /// Returns `true` if the device captures video and is not a memory-to-memory device, i.e. it is likely a camera.
+    #[must_use]
+    pub fn is_video_capture(&self) -> bool {
+        (self.video_capture || self.multiplanar) && !self.memory_to_memory
+    }
+}
+
 /// The list of known camera controls to the library. <br>
 /// These can control the picture brightness, etc. <br>
 /// Note that not all backends/devices support all these. Run [`supported_camera_controls()`](crate::traits::CaptureTrait::camera_controls) to see which ones can be set.
@@ -725,6 +1062,13 @@ pub enum KnownCameraControl {
     Exposure,
     Iris,
     Focus,
+    /// Compression quality of MJPEG frames, usually from 1 to 100. Lowering it trades image quality for less (e.g. USB) bandwidth.
+    JpegQuality,
+    /// Whether the camera's privacy mode is on, which blanks the image. Cameras with a hardware privacy switch or shutter report it as read-only,
+    /// see [`hardware_privacy()`](crate::traits::CaptureTrait::hardware_privacy).
+    Privacy,
+    /// Whether the torch is on, i.e. the flash LED lit continuously, such as the light on the back of a phone.
+    Torch,
     /// Other camera control. Listed is the ID.
     /// Wasteful, however is needed for a unified API across Windows, Linux, and MacOSX due to Microsoft's usage of GUIDs.
     ///
@@ -734,7 +1078,7 @@ pub enum KnownCameraControl {
 
 /// All camera controls in an array.
 #[must_use]
-pub const fn all_known_camera_controls() -> [KnownCameraControl; 15] {
+pub const fn all_known_camera_controls() -> [KnownCameraControl; 18] {
     [
         KnownCameraControl::Brightness,
         KnownCameraControl::Contrast,
@@ -751,11 +1095,14 @@ pub const fn all_known_camera_controls() -> [KnownCameraControl; 15] {
         KnownCameraControl::Exposure,
         KnownCameraControl::Iris,
         KnownCameraControl::Focus,
+        KnownCameraControl::JpegQuality,
+        KnownCameraControl::Privacy,
+        KnownCameraControl::Torch,
     ]
 }
 
 impl Display for KnownCameraControl {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:?}", &self)
     }
 }
@@ -775,11 +1122,30 @@ pub enum KnownCameraControlFlag {
 }
 
 impl Display for KnownCameraControlFlag {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 
+/// A single option of a [`ControlValueDescription::Menu`].
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ControlMenuEntry {
+    /// A human-readable option, e.g. `50 Hz` for a power line frequency control.
+    Name(String),
+    /// A numeric option, e.g. a link frequency.
+    Integer(i64),
+}
+
+impl Display for ControlMenuEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ControlMenuEntry::Name(name) => write!(f, "{name}"),
+            ControlMenuEntry::Integer(int) => write!(f, "{int}"),
+        }
+    }
+}
+
 /// The values for a [`CameraControl`].
 ///
 /// This provides a wide range of values that can be used to control a camera.
@@ -846,6 +1212,18 @@ pub enum ControlValueDescription {
         value: String,
         availible: Vec<String>,
     },
+    /// A choice between indexed options. `value` and `default` are indices into `items`. Set with [`ControlValueSetter::EnumValue`].
+    Menu {
+        value: i64,
+        items: Vec<(i64, ControlMenuEntry)>,
+        default: i64,
+    },
+    /// A region of the image, such as a crop rectangle. `value` and `default` lie within `bounds`. Set with [`ControlValueSetter::Area`].
+    Area {
+        value: Rect,
+        bounds: Rect,
+        default: Rect,
+    },
 }
 
 impl ControlValueDescription {
@@ -875,7 +1253,9 @@ impl ControlValueDescription {
             ControlValueDescription::Point { value, .. } => {
                 ControlValueSetter::Point(value.0, value.1)
             }
-            ControlValueDescription::Enum { value, .. } => ControlValueSetter::EnumValue(*value),
+            ControlValueDescription::Enum { value, .. }
+            | ControlValueDescription::Menu { value, .. } => ControlValueSetter::EnumValue(*value),
+            ControlValueDescription::Area { value, .. } => ControlValueSetter::Area(*value),
             ControlValueDescription::RGB { value, .. } => {
                 ControlValueSetter::RGB(value.0, value.1, value.2)
             }
@@ -947,7 +1327,8 @@ impl ControlValueDescription {
                 default,
             } => {
                 if step.abs() == 0_f64 {
-                    return true;
+                    // a continuous range
+                    return setter.as_float().is_some_and(|f| f >= min && f <= max);
                 }
 
                 match setter.as_float() {
@@ -974,12 +1355,20 @@ impl ControlValueDescription {
                 None => false,
             },
             ControlValueDescription::RGB { max, .. } => match setter.as_rgb() {
-                Some(v) => *v.0 >= max.0 && *v.1 >= max.1 && *v.2 >= max.2,
+                Some(v) => *v.0 <= max.0 && *v.1 <= max.1 && *v.2 <= max.2,
                 None => false,
             },
             ControlValueDescription::StringList { availible, .. } => {
                 availible.contains(&(setter.as_str().unwrap_or("").to_string())) // what the fuck??
             }
+            ControlValueDescription::Menu { items, .. } => match setter.as_enum() {
+                Some(e) => items.iter().any(|(index, _)| index == e),
+                None => false,
+            },
+            ControlValueDescription::Area { bounds, .. } => match setter.as_area() {
+                Some(area) => bounds.contains(area),
+                None => false,
+            },
         }
 
         // match setter {
@@ -1054,7 +1443,8 @@ impl ControlValueDescription {
 }
 
 impl Display for ControlValueDescription {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    #[allow(clippy::too_many_lines)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             ControlValueDescription::None => {
                 write!(f, "(None)")
@@ -1148,6 +1538,20 @@ impl Display for ControlValueDescription {
             ControlValueDescription::StringList { value, availible } => {
                 write!(f, "Current: {value}, Availible: {availible:?}")
             }
+            ControlValueDescription::Menu {
+                value,
+                items,
+                default,
+            } => {
+                write!(f, "Current: {value}, Items: {items:?}, Default: {default}")
+            }
+            ControlValueDescription::Area {
+                value,
+                bounds,
+                default,
+            } => {
+                write!(f, "Current: {value}, Bounds: {bounds}, Default: {default}")
+            }
         }
     }
 }
@@ -1243,7 +1647,7 @@ impl CameraControl {
 }
 
 impl Display for CameraControl {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Control: {}, Name: {}, Value: {}, Flag: {:?}, Active: {}",
@@ -1252,6 +1656,280 @@ impl Display for CameraControl {
     }
 }
 
+/// A change of a camera control, delivered to the callback set with [`on_control_change()`](crate::traits::CaptureTrait::on_control_change).
+/// Drivers report changes they make themselves too, e.g. the exposure picked by automatic exposure.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ControlChange {
+    control: KnownCameraControl,
+    value: ControlValueSetter,
+    value_changed: bool,
+    flags_changed: bool,
+    range_changed: bool,
+}
+
+impl ControlChange {
+    /// Create a new [`ControlChange`] of `control`, whose value is now `value`.
+    #[must_use]
+    pub fn new(
+        control: KnownCameraControl,
+        value: ControlValueSetter,
+        value_changed: bool,
+        flags_changed: bool,
+        range_changed: bool,
+    ) -> Self {
+        ControlChange {
+            control,
+            value,
+            value_changed,
+            flags_changed,
+            range_changed,
+        }
+    }
+
+    /// Gets the control that changed.
+    #[must_use]
+    pub fn control(&self) -> KnownCameraControl {
+        self.control
+    }
+
+    /// Gets the current value of the control.
+    #[must_use]
+    pub fn value(&self) -> &ControlValueSetter {
+        &self.value
+    }
+
+    /// Returns `true` if the value of the control changed.
+    #[must_use]
+    pub fn value_changed(&self) -> bool {
+        self.value_changed
+    }
+
+    /// Returns `true` if the flags of the control changed, e.g. it became read only because an automatic mode was enabled.
+    #[must_use]
+    pub fn flags_changed(&self) -> bool {
+        self.flags_changed
+    }
+
+    /// Returns `true` if the range (minimum, maximum or step) of the control changed.
+    #[must_use]
+    pub fn range_changed(&self) -> bool {
+        self.range_changed
+    }
+}
+
+/// A change of the [`CameraFormat`] of a running stream, e.g. by the V4L2 backend's `set_format_live()`.
+/// Frames read after the change are in the [`current()`](FormatChanged::current) format.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FormatChanged {
+    previous: Option<CameraFormat>,
+    current: CameraFormat,
+}
+
+impl FormatChanged {
+    /// Create a new [`FormatChanged`] from `previous` to `current`.
+    #[must_use]
+    pub fn new(previous: Option<CameraFormat>, current: CameraFormat) -> Self {
+        FormatChanged { previous, current }
+    }
+
+    /// Gets the format before the change, `None` if none was set.
+    #[must_use]
+    pub fn previous(&self) -> Option<CameraFormat> {
+        self.previous
+    }
+
+    /// Gets the format after the change.
+    #[must_use]
+    pub fn current(&self) -> CameraFormat {
+        self.current
+    }
+}
+
+/// A camera being plugged in or unplugged, delivered to the callback set with [`DeviceWatcher::start()`](crate::traits::DeviceWatcher::start).
+///
+/// The [`CameraInfo`] of an event has a stable [`CameraIndex::String`] where the backend has one, since numeric indices shift as cameras come and go.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum DeviceEvent {
+    /// A camera was plugged in, or otherwise became available.
+    Connected(CameraInfo),
+    /// A camera was unplugged, or otherwise went away.
+    Disconnected(CameraInfo),
+}
+
+impl DeviceEvent {
+    /// Gets the [`CameraInfo`] of the camera that was plugged in or unplugged.
+    #[must_use]
+    pub fn info(&self) -> &CameraInfo {
+        match self {
+            DeviceEvent::Connected(info) | DeviceEvent::Disconnected(info) => info,
+        }
+    }
+}
+
+/// Something that happened to an open stream, delivered to the callback set with
+/// [`on_stream_event()`](crate::traits::CaptureTrait::on_stream_event). These explain why a stream delivers fewer frames, or none at all.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum StreamEvent {
+    /// A frame was dropped before the application got it.
+    FrameDropped(FrameDropReason),
+    /// The stream stopped delivering frames until [`StreamEvent::InterruptionEnded`].
+    Interrupted(InterruptionReason),
+    /// The stream delivers frames again.
+    InterruptionEnded,
+    /// No frame arrived for this long, reported by a stream watchdog before it tries to recover the stream.
+    StallDetected(Duration),
+    /// A stream watchdog recovered the stream after a [`StreamEvent::StallDetected`] with this step.
+    Recovered(RecoveryStep),
+}
+
+/// A step a stream watchdog takes to recover a stalled stream, see [`StreamEvent::Recovered`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum RecoveryStep {
+    /// Stop and reopen the stream, flushing the frames queued in the driver.
+    Flush,
+    /// Select the stream's [`CameraFormat`] again, then reopen the stream.
+    ReselectStream,
+    /// Close and open the device again, then restore its [`CameraFormat`] and reopen the stream.
+    ReopenDevice,
+}
+
+impl Display for RecoveryStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Why a frame was dropped, see [`StreamEvent::FrameDropped`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum FrameDropReason {
+    /// The application took too long to get the previous frames, so newer ones replaced them.
+    Late,
+    /// The application held on to too many frames, so there was no buffer left to capture into.
+    OutOfBuffers,
+    /// The camera skipped frames, e.g. while it changed its format.
+    Discontinuity,
+    /// The backend did not say.
+    Unknown,
+}
+
+/// Why a stream was interrupted, see [`StreamEvent::Interrupted`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum InterruptionReason {
+    /// Another application took the camera.
+    InUseByAnotherClient,
+    /// The application moved to the background.
+    InBackground,
+    /// Another application in the foreground took the camera, e.g. in Split View on iPadOS.
+    MultipleForegroundApps,
+    /// The device is too hot or its battery too low.
+    SystemPressure,
+    /// The backend did not say.
+    Unknown,
+}
+
+/// How a still photo taken with [`take_photo()`](crate::traits::CaptureTrait::take_photo) is encoded.
+#[derive(Copy, Clone, Debug, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum PhotoCodec {
+    /// The camera's uncompressed samples, e.g. [`FrameFormat::Nv12`].
+    Uncompressed,
+    /// A JPEG image, as a [`FrameFormat::MJpeg`] buffer.
+    #[default]
+    Jpeg,
+    /// A HEIF image, as a [`FrameFormat::Heif`] buffer.
+    Heif,
+}
+
+/// Settings for a still photo taken with [`take_photo()`](crate::traits::CaptureTrait::take_photo).
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct PhotoSettings {
+    codec: PhotoCodec,
+    max_resolution: Option<Resolution>,
+    flash: Option<FlashMode>,
+    red_eye_reduction: Option<bool>,
+}
+
+impl PhotoSettings {
+    /// Create new [`PhotoSettings`] for a full resolution JPEG photo.
+    #[must_use]
+    pub fn new() -> Self {
+        PhotoSettings::default()
+    }
+
+    /// Sets the [`PhotoCodec`] of the photo.
+    #[must_use]
+    pub fn with_codec(mut self, codec: PhotoCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Limits the photo to `max_resolution`. Without a limit, the photo is as large as the camera can take it, which may be larger than
+    /// its video frames.
+    #[must_use]
+    pub fn with_max_resolution(mut self, max_resolution: Resolution) -> Self {
+        self.max_resolution = Some(max_resolution);
+        self
+    }
+
+    /// Gets the [`PhotoCodec`] of the photo.
+    #[must_use]
+    pub fn codec(&self) -> PhotoCodec {
+        self.codec
+    }
+
+    /// Gets the largest [`Resolution`] the photo may have, `None` if it is not limited.
+    #[must_use]
+    pub fn max_resolution(&self) -> Option<Resolution> {
+        self.max_resolution
+    }
+
+    /// Sets whether the flash (or fill light) fires for the photo. Backends that can not control the flash ignore this.
+    #[must_use]
+    pub fn with_flash(mut self, flash: FlashMode) -> Self {
+        self.flash = Some(flash);
+        self
+    }
+
+    /// Sets whether red-eye reduction is used for the photo. Backends that can not control it ignore this.
+    #[must_use]
+    pub fn with_red_eye_reduction(mut self, red_eye_reduction: bool) -> Self {
+        self.red_eye_reduction = Some(red_eye_reduction);
+        self
+    }
+
+    /// Gets the [`FlashMode`] of the photo, `None` if the camera decides.
+    #[must_use]
+    pub fn flash(&self) -> Option<FlashMode> {
+        self.flash
+    }
+
+    /// Gets whether red-eye reduction is used for the photo, `None` if the camera decides.
+    #[must_use]
+    pub fn red_eye_reduction(&self) -> Option<bool> {
+        self.red_eye_reduction
+    }
+}
+
+/// Whether the flash fires for a photo, see [`PhotoSettings::with_flash()`].
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum FlashMode {
+    /// The flash never fires.
+    Off,
+    /// The flash fires if the scene is too dark.
+    Auto,
+    /// The flash always fires.
+    On,
+}
+
 /// The setter for a control value
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
@@ -1267,6 +1945,7 @@ pub enum ControlValueSetter {
     EnumValue(i64),
     RGB(f64, f64, f64),
     StringList(String),
+    Area(Rect),
 }
 
 impl ControlValueSetter {
@@ -1359,10 +2038,18 @@ impl ControlValueSetter {
             None
         }
     }
+    #[must_use]
+    pub fn as_area(&self) -> Option<&Rect> {
+        if let ControlValueSetter::Area(area) = self {
+            Some(area)
+        } else {
+            None
+        }
+    }
 }
 
 impl Display for ControlValueSetter {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             ControlValueSetter::None => {
                 write!(f, "Value: None")
@@ -1397,6 +2084,9 @@ impl Display for ControlValueSetter {
             ControlValueSetter::StringList(s) => {
                 write!(f, "StringListValue: {s}")
             }
+            ControlValueSetter::Area(area) => {
+                write!(f, "AreaValue: {area}")
+            }
         }
     }
 }
@@ -1405,31 +2095,165 @@ impl Display for ControlValueSetter {
 /// - `AUTO` is special - it tells the Camera struct to automatically choose a backend most suited for the current platform.
 /// - `AVFoundation` - Uses `AVFoundation` on `MacOSX`
 /// - `Video4Linux` - `Video4Linux2`, a linux specific backend.
-/// - `UniversalVideoClass` -  ***DEPRECATED*** Universal Video Class (please check [libuvc](https://github.com/libuvc/libuvc)). Platform agnostic, although on linux it needs `sudo` permissions or similar to use.
+/// - `LibCamera` - `libcamera`, a linux specific backend for cameras that need an ISP pipeline (Raspberry Pi CSI, MIPI laptop sensors).
+/// - `PipeWire` - `PipeWire` through the `xdg-desktop-portal` camera interface, a linux specific backend for sandboxed (Flatpak, Snap) applications.
+/// - `UniversalVideoClass` - USB Video Class cameras driven directly over `libusb`. Platform agnostic, although on linux it needs `sudo` permissions or a `udev` rule to use.
 /// - `MediaFoundation` - Microsoft Media Foundation, Windows only,
 /// - `OpenCv` - Uses `OpenCV` to capture. Platform agnostic.
-/// - `GStreamer` - ***DEPRECATED*** Uses `GStreamer` RTP to capture. Platform agnostic.
+/// - `GStreamer` - Uses a `GStreamer` pipeline, built from the device monitor's source, to capture. Platform agnostic.
 /// - `Browser` - Uses browser APIs to capture from a webcam.
+/// - `Android` - The Android camera2 NDK API, Android only.
+/// - `Aravis` - `GigE Vision` and `USB3 Vision` industrial cameras through `Aravis`. Platform agnostic.
+/// - `Network` - ONVIF IP cameras, discovered with WS-Discovery and streamed over RTSP. Platform agnostic.
+/// - `FFmpeg` - `libavdevice` input devices (`v4l2`, `dshow`, `avfoundation`, `gdigrab`), decoded with `libavcodec`. Platform agnostic.
+/// - `DeckLink` - Blackmagic `DeckLink` SDI/HDMI capture cards through the `DeckLink` SDK. Platform agnostic.
+///
+/// With the `serialize` feature, a `Custom` backend can be serialized but not deserialized, as its name has to live for `'static`.
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
-#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum ApiBackend {
     Auto,
     Custom(&'static str),
     AVFoundation,
     Video4Linux,
+    LibCamera,
+    PipeWire,
     UniversalVideoClass,
     MediaFoundation,
     OpenCv,
     GStreamer,
     Browser,
+    Android,
+    Aravis,
+    Network,
+    FFmpeg,
+    DeckLink,
 }
 
 impl Display for ApiBackend {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> Deserialize<'de> for ApiBackend {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // mirrors `ApiBackend`, but owns the custom name so it can be read from any deserializer
+        #[derive(Deserialize)]
+        #[serde(rename = "ApiBackend")]
+        enum Named {
+            Auto,
+            Custom(String),
+            AVFoundation,
+            Video4Linux,
+            LibCamera,
+            PipeWire,
+            UniversalVideoClass,
+            MediaFoundation,
+            OpenCv,
+            GStreamer,
+            Browser,
+            Android,
+            Aravis,
+            Network,
+            FFmpeg,
+            DeckLink,
+        }
+
+        Ok(match Named::deserialize(deserializer)? {
+            Named::Auto => ApiBackend::Auto,
+            Named::Custom(name) => {
+                return Err(serde::de::Error::custom(format_args!(
+                    "Custom backend {name} can not be deserialized"
+                )))
+            }
+            Named::AVFoundation => ApiBackend::AVFoundation,
+            Named::Video4Linux => ApiBackend::Video4Linux,
+            Named::LibCamera => ApiBackend::LibCamera,
+            Named::PipeWire => ApiBackend::PipeWire,
+            Named::UniversalVideoClass => ApiBackend::UniversalVideoClass,
+            Named::MediaFoundation => ApiBackend::MediaFoundation,
+            Named::OpenCv => ApiBackend::OpenCv,
+            Named::GStreamer => ApiBackend::GStreamer,
+            Named::Browser => ApiBackend::Browser,
+            Named::Android => ApiBackend::Android,
+            Named::Aravis => ApiBackend::Aravis,
+            Named::Network => ApiBackend::Network,
+            Named::FFmpeg => ApiBackend::FFmpeg,
+            Named::DeckLink => ApiBackend::DeckLink,
+        })
+    }
+}
+
+/// Something a backend may or may not support, see [`BackendCapabilities`].
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BackendCapability {
+    /// Listing the connected cameras with `query()`.
+    Query,
+    /// Reading and writing [`CameraControl`]s.
+    Controls,
+    /// Listing the [`CameraFormat`]s a camera supports.
+    FormatEnumeration,
+    /// Watching for cameras being plugged in or unplugged with a [`DeviceWatcher`](crate::traits::DeviceWatcher).
+    Hotplug,
+    /// Handing out frames without copying them, through [`frame_raw()`](crate::traits::CaptureTrait::frame_raw).
+    ZeroCopy,
+    /// Capturing through [`AsyncCaptureTrait`](crate::traits::AsyncCaptureTrait) natively.
+    Async,
+    /// Taking still photos with [`take_photo()`](crate::traits::CaptureTrait::take_photo).
+    PhotoCapture,
+}
+
+impl Display for BackendCapability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 
+/// What a compiled in [`ApiBackend`] supports on this platform.
+///
+/// With the `serialize` feature this can only be serialized, as it describes the running build and is not meant to be read back.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct BackendCapabilities {
+    backend: ApiBackend,
+    capabilities: Vec<BackendCapability>,
+}
+
+impl BackendCapabilities {
+    /// Creates a new [`BackendCapabilities`].
+    #[must_use]
+    pub fn new(backend: ApiBackend, capabilities: &[BackendCapability]) -> Self {
+        BackendCapabilities {
+            backend,
+            capabilities: capabilities.to_vec(),
+        }
+    }
+
+    /// Get the [`ApiBackend`] these are the capabilities of.
+    #[must_use]
+    pub fn backend(&self) -> ApiBackend {
+        self.backend
+    }
+
+    /// Get everything the backend supports.
+    #[must_use]
+    pub fn capabilities(&self) -> &[BackendCapability] {
+        &self.capabilities
+    }
+
+    /// Checks if the backend supports `capability`.
+    #[must_use]
+    pub fn supports(&self, capability: BackendCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
 // /// A webcam index that supports both strings and integers. Most backends take an int, but `IPCamera`s take a URL (string).
 // #[derive(Clone, Debug, Hash, PartialEq, PartialOrd)]
 // pub enum CameraIndex {
@@ -1456,7 +2280,7 @@ impl Display for ApiBackend {
 // }
 
 // impl Display for CameraIndex {
-//     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+//     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
 //         match self {
 //             CameraIndex::Index(idx) => {
 //                 write!(f, "{}", idx)
@@ -1,3 +1,4 @@
+use alloc::{vec, vec::Vec};
 use core::ops::AddAssign;
 
 pub fn min_max_range<N: Copy + PartialOrd + AddAssign<N> + Sized>(min: N, max: N, step: N) -> Vec<N> {
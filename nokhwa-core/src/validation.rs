@@ -0,0 +1,313 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks for broken frames: buffers too short for their format, all zero or all black frames, and frames that repeat byte for byte
+//! because the driver stopped updating them.
+
+use crate::{
+    buffer::{Buffer, BufferPlane, FrameTimestamp},
+    frame_format::FrameFormat,
+    types::Resolution,
+};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::{Display, Formatter},
+    hash::{Hash, Hasher},
+};
+
+/// Something wrong with a frame, found by a [`FrameValidator`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameIssue {
+    /// The buffer is shorter than its [`FrameFormat`] and [`Resolution`] need.
+    TooShort { expected: usize, actual: usize },
+    /// A compressed frame does not start like its format does, e.g. an `MJPEG` frame without a JPEG start of image marker.
+    CorruptPayload,
+    /// Every byte of the frame is zero.
+    AllZero,
+    /// Every pixel of the frame is at or below the black threshold.
+    AllBlack,
+    /// The frame is byte for byte the same as the last `repeats` frames.
+    Stuck { repeats: u32 },
+}
+
+impl Display for FrameIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameIssue::TooShort { expected, actual } => {
+                write!(f, "Frame is {actual} bytes, expected at least {expected}")
+            }
+            FrameIssue::CorruptPayload => write!(f, "Frame payload is corrupt"),
+            FrameIssue::AllZero => write!(f, "Frame is all zero"),
+            FrameIssue::AllBlack => write!(f, "Frame is all black"),
+            FrameIssue::Stuck { repeats } => write!(f, "Frame repeated {repeats} times"),
+        }
+    }
+}
+
+/// What happens to a frame with a [`FrameIssue`].
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuarantinePolicy {
+    /// The frame is dropped, and the next one is captured in its place.
+    #[default]
+    Drop,
+    /// The frame is delivered along with its issues.
+    DeliverFlagged,
+}
+
+/// A frame that failed validation, passed to the callback set with [`FrameValidator::set_callback()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationEvent {
+    /// What is wrong with the frame.
+    pub issues: Vec<FrameIssue>,
+    /// The sequence number of the frame, if the backend reported one.
+    pub sequence: Option<u64>,
+    /// The timestamp of the frame, if the backend reported one.
+    pub timestamp: Option<FrameTimestamp>,
+    /// If the frame was dropped.
+    pub dropped: bool,
+}
+
+/// Checks each frame of a stream for [`FrameIssue`]s.
+///
+/// All checks are on by default, apart from [`FrameIssue::AllBlack`], which a covered lens also triggers, and [`FrameIssue::Stuck`],
+/// which sources that legitimately repeat (a still desktop on a capture card, `v4l2loopback`, test patterns) also trigger.
+/// Turn them on with [`set_black_threshold()`](FrameValidator::set_black_threshold) and [`set_stuck_after()`](FrameValidator::set_stuck_after).
+pub struct FrameValidator {
+    policy: QuarantinePolicy,
+    black_threshold: Option<u8>,
+    stuck_after: u32,
+    last_hash: Option<u64>,
+    repeats: u32,
+    callback: Option<Box<dyn FnMut(ValidationEvent) + Send + 'static>>,
+}
+
+impl FrameValidator {
+    /// Creates a new [`FrameValidator`].
+    #[must_use]
+    pub fn new(policy: QuarantinePolicy) -> Self {
+        FrameValidator {
+            policy,
+            black_threshold: None,
+            stuck_after: 0,
+            last_hash: None,
+            repeats: 0,
+            callback: None,
+        }
+    }
+
+    /// Gets the [`QuarantinePolicy`].
+    #[must_use]
+    pub fn policy(&self) -> QuarantinePolicy {
+        self.policy
+    }
+
+    /// Sets the [`QuarantinePolicy`].
+    pub fn set_policy(&mut self, policy: QuarantinePolicy) {
+        self.policy = policy;
+    }
+
+    /// Gets the brightest value a pixel of an [`AllBlack`](FrameIssue::AllBlack) frame may have, or `None` if the check is off.
+    #[must_use]
+    pub fn black_threshold(&self) -> Option<u8> {
+        self.black_threshold
+    }
+
+    /// Sets the brightest value a pixel of an [`AllBlack`](FrameIssue::AllBlack) frame may have, e.g. `16` for limited range video,
+    /// or `None` to turn the check off.
+    pub fn set_black_threshold(&mut self, black_threshold: Option<u8>) {
+        self.black_threshold = black_threshold;
+    }
+
+    /// Gets how many times a frame has to repeat to be [`Stuck`](FrameIssue::Stuck), or `0` if the check is off.
+    #[must_use]
+    pub fn stuck_after(&self) -> u32 {
+        self.stuck_after
+    }
+
+    /// Sets how many times a frame has to repeat to be [`Stuck`](FrameIssue::Stuck), e.g. `30`, or `0` to turn the check off.
+    pub fn set_stuck_after(&mut self, stuck_after: u32) {
+        self.stuck_after = stuck_after;
+    }
+
+    /// Sets the callback that is called with a [`ValidationEvent`] for every frame that fails validation.
+    pub fn set_callback(&mut self, callback: Box<dyn FnMut(ValidationEvent) + Send + 'static>) {
+        self.callback = Some(callback);
+    }
+
+    /// Removes the callback.
+    pub fn clear_callback(&mut self) {
+        self.callback = None;
+    }
+
+    /// Forgets the last frame, e.g. after the stream was reopened.
+    pub fn reset(&mut self) {
+        self.last_hash = None;
+        self.repeats = 0;
+    }
+
+    /// Checks `buffer`, calls the callback if it has issues, and returns them.
+    ///
+    /// The frame should be dropped if [`should_drop()`](FrameValidator::should_drop) says so.
+    pub fn validate(&mut self, buffer: &Buffer) -> Vec<FrameIssue> {
+        let mut issues = Vec::new();
+        let data = buffer.buffer();
+
+        if let Some(expected) = minimum_frame_size(buffer) {
+            if data.len() < expected {
+                issues.push(FrameIssue::TooShort {
+                    expected,
+                    actual: data.len(),
+                });
+            }
+        }
+        if !payload_intact(buffer.source_frame_format(), data) {
+            issues.push(FrameIssue::CorruptPayload);
+        }
+        if data.iter().all(|byte| *byte == 0) {
+            issues.push(FrameIssue::AllZero);
+        } else if let Some(threshold) = self.black_threshold {
+            if is_black(buffer, threshold) {
+                issues.push(FrameIssue::AllBlack);
+            }
+        }
+        if let Some(repeats) = self.repeats(data) {
+            issues.push(FrameIssue::Stuck { repeats });
+        }
+
+        if !issues.is_empty() {
+            if let Some(callback) = &mut self.callback {
+                callback(ValidationEvent {
+                    issues: issues.clone(),
+                    sequence: buffer.sequence(),
+                    timestamp: buffer.timestamp(),
+                    dropped: self.policy == QuarantinePolicy::Drop,
+                });
+            }
+        }
+        issues
+    }
+
+    /// Checks if a frame with `issues` should be dropped under the [`QuarantinePolicy`].
+    #[must_use]
+    pub fn should_drop(&self, issues: &[FrameIssue]) -> bool {
+        self.policy == QuarantinePolicy::Drop && !issues.is_empty()
+    }
+
+    fn repeats(&mut self, data: &[u8]) -> Option<u32> {
+        if self.stuck_after == 0 {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if self.last_hash == Some(hash) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.last_hash = Some(hash);
+            self.repeats = 0;
+        }
+        Some(self.repeats).filter(|repeats| *repeats >= self.stuck_after)
+    }
+}
+
+impl Default for FrameValidator {
+    fn default() -> Self {
+        FrameValidator::new(QuarantinePolicy::default())
+    }
+}
+
+/// The least amount of bytes a frame of `format` and `resolution` takes, without row padding. `None` for compressed formats.
+#[must_use]
+pub fn expected_frame_size(format: FrameFormat, resolution: Resolution) -> Option<usize> {
+    let width = resolution.width() as usize;
+    let height = resolution.height() as usize;
+    let pixels = width * height;
+    match format {
+        FrameFormat::Luma8 | FrameFormat::Bayer8(_) => Some(pixels),
+        FrameFormat::Yuv422
+        | FrameFormat::Uyv422
+        | FrameFormat::Luma16
+        | FrameFormat::Depth16
+        | FrameFormat::Bayer10(_)
+        | FrameFormat::Bayer12(_) => Some(pixels * 2),
+        FrameFormat::Rgb8 | FrameFormat::Bgr8 | FrameFormat::P010 => Some(pixels * 3),
+        FrameFormat::RgbA8 | FrameFormat::BgrA8 | FrameFormat::P210 | FrameFormat::V216 => {
+            Some(pixels * 4)
+        }
+        FrameFormat::Nv12 | FrameFormat::Nv21 | FrameFormat::Yv12 => Some(pixels * 3 / 2),
+        FrameFormat::P410 => Some(pixels * 6),
+        // 6 pixels in 16 bytes, rows padded to 48 pixels
+        FrameFormat::V210 => Some(width.div_ceil(48) * 128 * height),
+        _ => None,
+    }
+}
+
+// like `expected_frame_size`, but rows of packed frames are `stride` apart
+fn minimum_frame_size(buffer: &Buffer) -> Option<usize> {
+    let format = buffer.source_frame_format();
+    let resolution = buffer.resolution();
+    let size = expected_frame_size(format, resolution)?;
+    let stride = buffer.planes().first().and_then(BufferPlane::stride);
+    let height = resolution.height() as usize;
+    let planar = matches!(
+        format,
+        FrameFormat::Nv12
+            | FrameFormat::Nv21
+            | FrameFormat::Yv12
+            | FrameFormat::P010
+            | FrameFormat::P210
+            | FrameFormat::P410
+    );
+    match stride {
+        Some(stride) if height != 0 && !planar => Some(stride * (height - 1) + size / height),
+        _ => Some(size),
+    }
+}
+
+fn payload_intact(format: FrameFormat, data: &[u8]) -> bool {
+    match format {
+        FrameFormat::MJpeg => data.starts_with(&[0xFF, 0xD8]),
+        _ if FrameFormat::COMPRESSED.contains(&format) => !data.is_empty(),
+        _ => true,
+    }
+}
+
+// checks the luma or color samples, skipping chroma
+fn is_black(buffer: &Buffer, threshold: u8) -> bool {
+    let data = buffer.buffer();
+    let pixels = buffer.resolution().width() as usize * buffer.resolution().height() as usize;
+    match buffer.source_frame_format() {
+        FrameFormat::Luma8 | FrameFormat::Rgb8 | FrameFormat::Bgr8 => {
+            data.iter().all(|sample| *sample <= threshold)
+        }
+        FrameFormat::RgbA8 | FrameFormat::BgrA8 => data
+            .chunks_exact(4)
+            .all(|pixel| pixel[..3].iter().all(|sample| *sample <= threshold)),
+        FrameFormat::Yuv422 => data.iter().step_by(2).all(|luma| *luma <= threshold),
+        FrameFormat::Uyv422 => data
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(|luma| *luma <= threshold),
+        FrameFormat::Nv12 | FrameFormat::Nv21 | FrameFormat::Yv12 => data
+            .get(..pixels)
+            .is_some_and(|luma| luma.iter().all(|sample| *sample <= threshold)),
+        _ => false,
+    }
+}
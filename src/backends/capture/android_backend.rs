@@ -0,0 +1,1472 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use jni_sys::{jobject, jvalue, JNIEnv, JavaVM, JNI_OK};
+use ndk_sys::{
+    acamera_metadata_enum_acamera_control_ae_mode, acamera_metadata_enum_acamera_control_af_mode,
+    acamera_metadata_enum_acamera_lens_facing,
+    acamera_metadata_enum_acamera_scaler_available_stream_configurations,
+    acamera_metadata_enum_acamera_sensor_info_timestamp_source, acamera_metadata_tag,
+    camera_status_t, media_status_t, ACameraCaptureSession, ACameraCaptureSession_close,
+    ACameraCaptureSession_setRepeatingRequest, ACameraCaptureSession_stateCallbacks,
+    ACameraCaptureSession_stopRepeating, ACameraDevice, ACameraDevice_StateCallbacks,
+    ACameraDevice_close, ACameraDevice_createCaptureRequest, ACameraDevice_createCaptureSession,
+    ACameraDevice_request_template, ACameraIdList, ACameraManager, ACameraManager_create,
+    ACameraManager_delete, ACameraManager_deleteCameraIdList,
+    ACameraManager_getCameraCharacteristics, ACameraManager_getCameraIdList,
+    ACameraManager_openCamera, ACameraMetadata, ACameraMetadata_const_entry, ACameraMetadata_free,
+    ACameraMetadata_getConstEntry, ACameraOutputTarget, ACameraOutputTarget_create,
+    ACameraOutputTarget_free, ACaptureRequest, ACaptureRequest_addTarget, ACaptureRequest_free,
+    ACaptureRequest_setEntry_float, ACaptureRequest_setEntry_i32, ACaptureRequest_setEntry_i64,
+    ACaptureRequest_setEntry_u8, ACaptureSessionOutput, ACaptureSessionOutputContainer,
+    ACaptureSessionOutputContainer_add, ACaptureSessionOutputContainer_create,
+    ACaptureSessionOutputContainer_free, ACaptureSessionOutput_create, ACaptureSessionOutput_free,
+    AImage, AImageReader, AImageReader_ImageListener, AImageReader_acquireLatestImage,
+    AImageReader_delete, AImageReader_getWindow, AImageReader_new, AImageReader_setImageListener,
+    AImage_delete, AImage_getNumberOfPlanes, AImage_getPlaneData, AImage_getPlanePixelStride,
+    AImage_getPlaneRowStride, AImage_getTimestamp, ANativeWindow, AIMAGE_FORMATS,
+};
+use nokhwa_core::{
+    buffer::{Buffer, FrameOrientation, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, CameraPosition,
+        ControlValueDescription, ControlValueSetter, FrameRate, KnownCameraControl,
+        PermissionStatus, Resolution,
+    },
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::{c_int, c_void, CStr, CString},
+    future::Future,
+    pin::Pin,
+    ptr, slice,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, Ordering},
+        Condvar, Mutex,
+    },
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
+
+// the camera2 NDK API lives in its own library, `ndk-sys` only links the ones behind its features
+#[link(name = "camera2ndk")]
+extern "C" {}
+
+/// How many images the `AImageReader` holds. One is handed out while the camera fills the others.
+const MAX_IMAGES: i32 = 4;
+/// How long [`frame()`](CaptureTrait::frame) waits for an image.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long [`request_android_permission()`] waits for the user to answer the permission dialog.
+const PERMISSION_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the permission is checked while the dialog is shown, Android only tells the `Activity` about the answer.
+const PERMISSION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// `PackageManager.PERMISSION_GRANTED`
+const PERMISSION_GRANTED: i32 = 0;
+/// The request code passed to `Activity.requestPermissions()`, in case the app's `onRequestPermissionsResult()` wants to tell it apart.
+pub const ANDROID_PERMISSION_REQUEST_CODE: i32 = 0x6e6b;
+
+/// The [`KnownCameraControl`]s that map to a camera2 capture request key.
+const SUPPORTED_CONTROLS: [KnownCameraControl; 5] = [
+    KnownCameraControl::Brightness,
+    KnownCameraControl::Gain,
+    KnownCameraControl::Exposure,
+    KnownCameraControl::Focus,
+    KnownCameraControl::Zoom,
+];
+
+// set once `requestPermissions()` was called, so a permission that is still not granted afterwards was denied
+static PERMISSION_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+fn camera_result(status: camera_status_t, call: &str) -> Result<(), String> {
+    if status == camera_status_t::ACAMERA_OK {
+        Ok(())
+    } else {
+        Err(format!("{call} failed: camera_status_t {}", status.0))
+    }
+}
+
+fn media_result(status: media_status_t, call: &str) -> Result<(), String> {
+    if status == media_status_t::AMEDIA_OK {
+        Ok(())
+    } else {
+        Err(format!("{call} failed: media_status_t {}", status.0))
+    }
+}
+
+struct CameraManager(*mut ACameraManager);
+
+impl CameraManager {
+    fn new() -> Result<Self, NokhwaError> {
+        let manager = unsafe { ACameraManager_create() };
+        if manager.is_null() {
+            return Err(NokhwaError::InitializeError {
+                backend: ApiBackend::Android,
+                error: "ACameraManager_create returned null".to_string(),
+            });
+        }
+        Ok(CameraManager(manager))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn camera_ids(&self) -> Result<Vec<CString>, NokhwaError> {
+        let mut id_list: *mut ACameraIdList = ptr::null_mut();
+        camera_result(
+            unsafe { ACameraManager_getCameraIdList(self.0, ptr::addr_of_mut!(id_list)) },
+            "ACameraManager_getCameraIdList",
+        )
+        .map_err(|why| NokhwaError::InitializeError {
+            backend: ApiBackend::Android,
+            error: why,
+        })?;
+
+        // SAFETY: the list is valid until it is deleted, the IDs are copied out before that
+        let ids = unsafe {
+            let list = &*id_list;
+            slice::from_raw_parts(list.cameraIds, list.numCameras.max(0) as usize)
+                .iter()
+                .map(|id| CStr::from_ptr(*id).to_owned())
+                .collect()
+        };
+        unsafe { ACameraManager_deleteCameraIdList(id_list) };
+        Ok(ids)
+    }
+
+    fn characteristics(&self, id: &CStr) -> Result<Characteristics, NokhwaError> {
+        let mut metadata: *mut ACameraMetadata = ptr::null_mut();
+        camera_result(
+            unsafe {
+                ACameraManager_getCameraCharacteristics(
+                    self.0,
+                    id.as_ptr(),
+                    ptr::addr_of_mut!(metadata),
+                )
+            },
+            "ACameraManager_getCameraCharacteristics",
+        )
+        .map_err(|why| NokhwaError::GetPropertyError {
+            property: "CameraCharacteristics".to_string(),
+            error: why,
+        })?;
+        Ok(Characteristics(metadata))
+    }
+}
+
+impl Drop for CameraManager {
+    fn drop(&mut self) {
+        unsafe { ACameraManager_delete(self.0) };
+    }
+}
+
+struct Characteristics(*mut ACameraMetadata);
+
+impl Characteristics {
+    fn entry(&self, tag: acamera_metadata_tag) -> Option<ACameraMetadata_const_entry> {
+        let mut entry: ACameraMetadata_const_entry = unsafe { std::mem::zeroed() };
+        match unsafe { ACameraMetadata_getConstEntry(self.0, tag.0, ptr::addr_of_mut!(entry)) } {
+            camera_status_t::ACAMERA_OK if entry.count > 0 => Some(entry),
+            _ => None,
+        }
+    }
+
+    // SAFETY (for all of these): the entry points into the metadata, which lives as long as `self`, and has the type of its tag
+    fn u8s(&self, tag: acamera_metadata_tag) -> &[u8] {
+        self.entry(tag).map_or(&[][..], |entry| unsafe {
+            slice::from_raw_parts(entry.data.u8_, entry.count as usize)
+        })
+    }
+
+    fn i32s(&self, tag: acamera_metadata_tag) -> &[i32] {
+        self.entry(tag).map_or(&[][..], |entry| unsafe {
+            slice::from_raw_parts(entry.data.i32_, entry.count as usize)
+        })
+    }
+
+    fn i64s(&self, tag: acamera_metadata_tag) -> &[i64] {
+        self.entry(tag).map_or(&[][..], |entry| unsafe {
+            slice::from_raw_parts(entry.data.i64_, entry.count as usize)
+        })
+    }
+
+    fn f32s(&self, tag: acamera_metadata_tag) -> &[f32] {
+        self.entry(tag).map_or(&[][..], |entry| unsafe {
+            slice::from_raw_parts(entry.data.f, entry.count as usize)
+        })
+    }
+
+    fn position(&self) -> CameraPosition {
+        match self.u8s(acamera_metadata_tag::ACAMERA_LENS_FACING).first() {
+            Some(facing)
+                if u32::from(*facing)
+                    == acamera_metadata_enum_acamera_lens_facing::ACAMERA_LENS_FACING_FRONT.0 =>
+            {
+                CameraPosition::Front
+            }
+            Some(facing)
+                if u32::from(*facing)
+                    == acamera_metadata_enum_acamera_lens_facing::ACAMERA_LENS_FACING_BACK.0 =>
+            {
+                CameraPosition::Back
+            }
+            Some(_) => CameraPosition::External,
+            None => CameraPosition::Unknown,
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    fn sensor_orientation(&self) -> u16 {
+        self.i32s(acamera_metadata_tag::ACAMERA_SENSOR_ORIENTATION)
+            .first()
+            .map_or(0, |orientation| orientation.rem_euclid(360) as u16)
+    }
+
+    fn timestamp_clock(&self) -> TimestampClock {
+        let realtime = acamera_metadata_enum_acamera_sensor_info_timestamp_source::ACAMERA_SENSOR_INFO_TIMESTAMP_SOURCE_REALTIME.0;
+        match self
+            .u8s(acamera_metadata_tag::ACAMERA_SENSOR_INFO_TIMESTAMP_SOURCE)
+            .first()
+        {
+            // `elapsedRealtimeNanos()`, which is `CLOCK_BOOTTIME` and keeps counting in deep sleep
+            Some(source) if u32::from(*source) == realtime => TimestampClock::Unknown,
+            // `uptimeMillis()`, which is `CLOCK_MONOTONIC`
+            _ => TimestampClock::Monotonic,
+        }
+    }
+
+    // output stream configurations are (format, width, height, is input) quadruples
+    #[allow(clippy::cast_sign_loss)]
+    fn output_sizes(&self, format: AIMAGE_FORMATS) -> Vec<Resolution> {
+        let output = acamera_metadata_enum_acamera_scaler_available_stream_configurations::ACAMERA_SCALER_AVAILABLE_STREAM_CONFIGURATIONS_OUTPUT.0;
+        let mut sizes: Vec<Resolution> = self
+            .i32s(acamera_metadata_tag::ACAMERA_SCALER_AVAILABLE_STREAM_CONFIGURATIONS)
+            .chunks_exact(4)
+            .filter(|config| config[0] as u32 == format.0 && config[3] as u32 == output)
+            .map(|config| Resolution::new(config[1] as u32, config[2] as u32))
+            .collect();
+        sizes.dedup();
+        sizes
+    }
+
+    // the camera picks a frame rate in each (min, max) range, the fixed ones are the frame rates it can hold
+    #[allow(clippy::cast_sign_loss)]
+    fn frame_rates(&self) -> Vec<u32> {
+        let mut frame_rates: Vec<u32> = self
+            .i32s(acamera_metadata_tag::ACAMERA_CONTROL_AE_AVAILABLE_TARGET_FPS_RANGES)
+            .chunks_exact(2)
+            .map(|range| range[1].max(0) as u32)
+            .filter(|frame_rate| *frame_rate != 0)
+            .collect();
+        frame_rates.sort_unstable();
+        frame_rates.dedup();
+        frame_rates
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn control_description(
+        &self,
+        control: KnownCameraControl,
+        value: Option<f64>,
+    ) -> Option<ControlValueDescription> {
+        let integer_range = |range: &[i64], default: i64| match range {
+            [min, max, ..] => Some(ControlValueDescription::IntegerRange {
+                min: *min,
+                max: *max,
+                value: value.map_or(default, |value| value.round() as i64),
+                step: 1,
+                default,
+            }),
+            _ => None,
+        };
+        let float_range = |min: f64, max: f64, default: f64| ControlValueDescription::FloatRange {
+            min,
+            max,
+            value: value.unwrap_or(default),
+            step: 0.0,
+            default,
+        };
+
+        match control {
+            KnownCameraControl::Brightness => {
+                let range: Vec<i64> = self
+                    .i32s(acamera_metadata_tag::ACAMERA_CONTROL_AE_COMPENSATION_RANGE)
+                    .iter()
+                    .map(|v| i64::from(*v))
+                    .collect();
+                integer_range(&range, 0)
+            }
+            KnownCameraControl::Gain => {
+                let range: Vec<i64> = self
+                    .i32s(acamera_metadata_tag::ACAMERA_SENSOR_INFO_SENSITIVITY_RANGE)
+                    .iter()
+                    .map(|v| i64::from(*v))
+                    .collect();
+                integer_range(&range, range.first().copied().unwrap_or_default())
+            }
+            KnownCameraControl::Exposure => {
+                // in microseconds, the camera reports nanoseconds
+                let range: Vec<i64> = self
+                    .i64s(acamera_metadata_tag::ACAMERA_SENSOR_INFO_EXPOSURE_TIME_RANGE)
+                    .iter()
+                    .map(|v| v / 1000)
+                    .collect();
+                integer_range(&range, range.first().copied().unwrap_or_default())
+            }
+            KnownCameraControl::Focus => self
+                .f32s(acamera_metadata_tag::ACAMERA_LENS_INFO_MINIMUM_FOCUS_DISTANCE)
+                .first()
+                // fixed focus lenses report 0
+                .filter(|min_distance| **min_distance > 0.0)
+                .map(|min_distance| float_range(0.0, f64::from(*min_distance), 0.0)),
+            KnownCameraControl::Zoom => {
+                match self.f32s(acamera_metadata_tag::ACAMERA_CONTROL_ZOOM_RATIO_RANGE) {
+                    [min, max, ..] => Some(float_range(f64::from(*min), f64::from(*max), 1.0)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Drop for Characteristics {
+    fn drop(&mut self) {
+        unsafe { ACameraMetadata_free(self.0) };
+    }
+}
+
+/// Converts a [`FrameFormat`] into the `AImageReader` format it is captured with.
+#[must_use]
+pub fn frameformat_to_aimage_format(frame_format: FrameFormat) -> Option<AIMAGE_FORMATS> {
+    match frame_format {
+        // YUV_420_888 is repacked into NV12, whatever layout the camera used
+        FrameFormat::Nv12 => Some(AIMAGE_FORMATS::AIMAGE_FORMAT_YUV_420_888),
+        FrameFormat::MJpeg => Some(AIMAGE_FORMATS::AIMAGE_FORMAT_JPEG),
+        _ => None,
+    }
+}
+
+/// Converts an `AImageReader` format into the [`FrameFormat`] its frames are handed out in.
+#[must_use]
+pub fn aimage_format_to_frameformat(format: AIMAGE_FORMATS) -> Option<FrameFormat> {
+    match format {
+        AIMAGE_FORMATS::AIMAGE_FORMAT_YUV_420_888 => Some(FrameFormat::Nv12),
+        AIMAGE_FORMATS::AIMAGE_FORMAT_JPEG => Some(FrameFormat::MJpeg),
+        _ => None,
+    }
+}
+
+fn camera_info(characteristics: &Characteristics, id: &CStr, index: &CameraIndex) -> CameraInfo {
+    let id = id.to_string_lossy();
+    let position = characteristics.position();
+    let name = match position {
+        CameraPosition::Front => format!("Front Camera {id}"),
+        CameraPosition::Back => format!("Back Camera {id}"),
+        CameraPosition::External => format!("External Camera {id}"),
+        CameraPosition::Unknown => format!("Camera {id}"),
+    };
+    CameraInfo::new(&name, &format!("Android Camera2 @ {id}"), &id, index).with_position(position)
+}
+
+/// Query the system for Android cameras through the camera2 NDK API.
+///
+/// Listing the cameras does not need the `CAMERA` permission, opening them does.
+/// # Errors
+/// If the camera service can not be reached, this will error.
+#[allow(clippy::cast_possible_truncation)]
+pub fn query_android() -> Result<Vec<CameraInfo>, NokhwaError> {
+    let manager = CameraManager::new()?;
+    Ok(manager
+        .camera_ids()?
+        .iter()
+        .enumerate()
+        .filter_map(|(index, id)| {
+            manager.characteristics(id).ok().map(|characteristics| {
+                camera_info(&characteristics, id, &CameraIndex::Index(index as u32))
+            })
+        })
+        .collect())
+}
+
+// runs `f` with the JNI environment of this thread and the app's `Context`, as `ndk-context` got them from the glue crate
+// (`android-activity`, `ndk-glue`) that started the app
+fn with_jni<T>(
+    f: impl FnOnce(*mut JNIEnv, jobject) -> Result<T, String>,
+) -> Result<T, NokhwaError> {
+    let error = |error: String| NokhwaError::InitializeError {
+        backend: ApiBackend::Android,
+        error,
+    };
+    let context = std::panic::catch_unwind(ndk_context::android_context)
+        .map_err(|_| error("ndk-context was not initialized".to_string()))?;
+    let vm = context.vm().cast::<JavaVM>();
+    let activity = context.context() as jobject;
+    if vm.is_null() || activity.is_null() {
+        return Err(error("No Java VM or Context".to_string()));
+    }
+
+    // attaching an attached thread does nothing, and threads we attach stay attached as the VM is used again on the next call
+    let mut env: *mut JNIEnv = ptr::null_mut();
+    unsafe {
+        let attach = (**vm)
+            .AttachCurrentThread
+            .ok_or_else(|| error("No AttachCurrentThread".to_string()))?;
+        if attach(vm, ptr::addr_of_mut!(env).cast(), ptr::null_mut()) != JNI_OK {
+            return Err(error("Failed to attach to the Java VM".to_string()));
+        }
+    }
+    f(env, activity).map_err(error)
+}
+
+macro_rules! jni {
+    ($env:expr, $function:ident $(, $arg:expr)*) => {
+        match (**$env).$function {
+            Some(function) => function($env $(, $arg)*),
+            None => return Err(concat!("No JNI function ", stringify!($function)).to_string()),
+        }
+    };
+}
+
+// calls `method` with the `android.permission.CAMERA` string, clearing any exception it threw
+unsafe fn call_with_permission<T>(
+    env: *mut JNIEnv,
+    activity: jobject,
+    method: &CStr,
+    signature: &CStr,
+    call: impl FnOnce(*mut JNIEnv, jobject, jni_sys::jmethodID, jobject) -> T,
+) -> Result<T, String> {
+    let class = jni!(env, GetObjectClass, activity);
+    let method_id = jni!(env, GetMethodID, class, method.as_ptr(), signature.as_ptr());
+    jni!(env, DeleteLocalRef, class);
+    if method_id.is_null() {
+        jni!(env, ExceptionClear);
+        return Err(format!("No method {}", method.to_string_lossy()));
+    }
+
+    let permission = jni!(env, NewStringUTF, c"android.permission.CAMERA".as_ptr());
+    let result = call(env, activity, method_id, permission);
+    jni!(env, DeleteLocalRef, permission);
+    if jni!(env, ExceptionCheck) != 0 {
+        jni!(env, ExceptionClear);
+        return Err(format!("{} threw an exception", method.to_string_lossy()));
+    }
+    Ok(result)
+}
+
+/// Returns `true` if the app holds the `android.permission.CAMERA` runtime permission, which is needed to open a camera.
+///
+/// The permission also has to be declared with `<uses-permission android:name="android.permission.CAMERA" />` in the app's manifest
+/// (with `cargo-apk`, as a `[[package.metadata.android.uses_permission]]`), or it can not be granted.
+/// # Errors
+/// This errors if there is no Java VM or `Context` to ask, e.g. because no glue crate initialized `ndk-context`.
+pub fn android_permission_granted() -> Result<bool, NokhwaError> {
+    with_jni(|env, activity| unsafe {
+        call_with_permission(
+            env,
+            activity,
+            c"checkSelfPermission",
+            c"(Ljava/lang/String;)I",
+            |env, activity, method, permission| match (**env).CallIntMethodA {
+                Some(call) => call(env, activity, method, [jvalue { l: permission }].as_ptr()),
+                None => -1,
+            },
+        )
+    })
+    .map(|result| result == PERMISSION_GRANTED)
+}
+
+/// Gets whether the app may use the cameras. Android only tells the `Activity` how the user answered, so a permission that is
+/// still not granted after [`request_android_permission()`] asked is [`PermissionStatus::Denied`].
+#[must_use]
+pub fn android_permission_status() -> PermissionStatus {
+    match android_permission_granted() {
+        Ok(true) => PermissionStatus::Granted,
+        _ if PERMISSION_REQUESTED.load(Ordering::Acquire) => PermissionStatus::Denied,
+        _ => PermissionStatus::NotDetermined,
+    }
+}
+
+/// Shows the system's dialog asking for the `CAMERA` permission, and does not wait for the answer.
+///
+/// The `Context` `ndk-context` holds has to be an `Activity` (as it is with `NativeActivity` and `GameActivity`). The request uses
+/// [`ANDROID_PERMISSION_REQUEST_CODE`].
+/// # Errors
+/// This errors if there is no Java VM or `Activity` to ask.
+pub fn show_android_permission_dialog() -> Result<(), NokhwaError> {
+    with_jni(|env, activity| unsafe {
+        let string_class = jni!(env, FindClass, c"java/lang/String".as_ptr());
+        if string_class.is_null() {
+            jni!(env, ExceptionClear);
+            return Err("No java.lang.String".to_string());
+        }
+        let result = call_with_permission(
+            env,
+            activity,
+            c"requestPermissions",
+            c"([Ljava/lang/String;I)V",
+            |env, activity, method, permission| {
+                let (Some(new_array), Some(call), Some(delete)) = (
+                    (**env).NewObjectArray,
+                    (**env).CallVoidMethodA,
+                    (**env).DeleteLocalRef,
+                ) else {
+                    return;
+                };
+                let permissions = new_array(env, 1, string_class, permission);
+                call(
+                    env,
+                    activity,
+                    method,
+                    [
+                        jvalue { l: permissions },
+                        jvalue {
+                            i: ANDROID_PERMISSION_REQUEST_CODE,
+                        },
+                    ]
+                    .as_ptr(),
+                );
+                delete(env, permissions);
+            },
+        );
+        jni!(env, DeleteLocalRef, string_class);
+        result
+    })?;
+    PERMISSION_REQUESTED.store(true, Ordering::Release);
+    Ok(())
+}
+
+// waits for the permission by checking it again after an interval, as the answer only goes to the `Activity`
+struct PermissionAnswer {
+    asked: Instant,
+}
+
+impl Future for PermissionAnswer {
+    type Output = Result<(), NokhwaError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match android_permission_granted() {
+            Ok(true) => Poll::Ready(Ok(())),
+            Err(why) => Poll::Ready(Err(why)),
+            Ok(false) if self.asked.elapsed() > PERMISSION_TIMEOUT => {
+                Poll::Ready(Err(NokhwaError::InitializeError {
+                    backend: ApiBackend::Android,
+                    error: "Camera permission denied".to_string(),
+                }))
+            }
+            Ok(false) => {
+                let waker = cx.waker().clone();
+                thread::spawn(move || {
+                    thread::sleep(PERMISSION_POLL_INTERVAL);
+                    waker.wake();
+                });
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Asks the user for the `CAMERA` runtime permission if the app does not hold it yet, and waits for them to answer.
+///
+/// See [`show_android_permission_dialog()`] for what the app has to provide.
+/// # Errors
+/// This errors if the user denied the permission (or did not answer within 30 seconds), or there is no `Activity` to ask.
+pub async fn request_android_permission() -> Result<(), NokhwaError> {
+    if android_permission_granted()? {
+        return Ok(());
+    }
+    show_android_permission_dialog()?;
+    PermissionAnswer {
+        asked: Instant::now(),
+    }
+    .await
+}
+
+/// Blocking version of [`request_android_permission()`]. Do not call this on the UI thread, the dialog can not be shown while it is blocked.
+/// # Errors
+/// See [`request_android_permission()`].
+pub fn request_android_permission_blocking() -> Result<(), NokhwaError> {
+    if android_permission_granted()? {
+        return Ok(());
+    }
+    show_android_permission_dialog()?;
+    let asked = Instant::now();
+    while asked.elapsed() < PERMISSION_TIMEOUT {
+        thread::sleep(PERMISSION_POLL_INTERVAL);
+        if android_permission_granted()? {
+            return Ok(());
+        }
+    }
+    Err(NokhwaError::InitializeError {
+        backend: ApiBackend::Android,
+        error: "Camera permission denied".to_string(),
+    })
+}
+
+#[derive(Default)]
+struct DeviceState {
+    disconnected: AtomicBool,
+    // the `ERROR_CAMERA_*` code the device failed with, 0 if none
+    error: AtomicI32,
+}
+
+unsafe extern "C" fn on_disconnected(context: *mut c_void, _: *mut ACameraDevice) {
+    let state = &*context.cast::<DeviceState>();
+    state.disconnected.store(true, Ordering::Release);
+}
+
+unsafe extern "C" fn on_error(context: *mut c_void, _: *mut ACameraDevice, error: c_int) {
+    let state = &*context.cast::<DeviceState>();
+    state.error.store(error, Ordering::Release);
+    state.disconnected.store(true, Ordering::Release);
+}
+
+unsafe extern "C" fn on_session_state(_: *mut c_void, _: *mut ACameraCaptureSession) {}
+
+#[derive(Default)]
+struct FrameSignal {
+    available: Mutex<bool>,
+    condvar: Condvar,
+}
+
+unsafe extern "C" fn on_image_available(context: *mut c_void, _: *mut AImageReader) {
+    let signal = &*context.cast::<FrameSignal>();
+    if let Ok(mut available) = signal.available.lock() {
+        *available = true;
+        signal.condvar.notify_all();
+    }
+}
+
+struct Image(*mut AImage);
+
+impl Image {
+    #[allow(clippy::cast_sign_loss)]
+    fn plane(&self, index: i32) -> Result<(&[u8], usize, usize), NokhwaError> {
+        let mut data: *mut u8 = ptr::null_mut();
+        let mut length: c_int = 0;
+        let mut pixel_stride = 1;
+        let mut row_stride = 0;
+        unsafe {
+            media_result(
+                AImage_getPlaneData(
+                    self.0,
+                    index,
+                    ptr::addr_of_mut!(data),
+                    ptr::addr_of_mut!(length),
+                ),
+                "AImage_getPlaneData",
+            )
+            .map_err(NokhwaError::ReadFrameError)?;
+            // compressed images have neither stride
+            let _ = AImage_getPlanePixelStride(self.0, index, ptr::addr_of_mut!(pixel_stride));
+            let _ = AImage_getPlaneRowStride(self.0, index, ptr::addr_of_mut!(row_stride));
+        }
+        // SAFETY: the plane is valid until the image is deleted, which needs `self`
+        let data = unsafe { slice::from_raw_parts(data, length.max(0) as usize) };
+        Ok((
+            data,
+            pixel_stride.max(1) as usize,
+            row_stride.max(0) as usize,
+        ))
+    }
+
+    fn number_of_planes(&self) -> i32 {
+        let mut planes = 0;
+        unsafe { AImage_getNumberOfPlanes(self.0, ptr::addr_of_mut!(planes)) };
+        planes
+    }
+
+    fn timestamp(&self) -> Option<Duration> {
+        let mut timestamp = 0;
+        match unsafe { AImage_getTimestamp(self.0, ptr::addr_of_mut!(timestamp)) } {
+            media_status_t::AMEDIA_OK => u64::try_from(timestamp).ok().map(Duration::from_nanos),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        unsafe { AImage_delete(self.0) };
+    }
+}
+
+// YUV_420_888 planes may be planar (I420), semi-planar (NV12/NV21) or have padded rows, depending on the device,
+// so they are copied into tightly packed NV12
+fn yuv_420_888_to_nv12(
+    image: &Image,
+    resolution: Resolution,
+) -> Result<(Vec<u8>, Vec<u8>), NokhwaError> {
+    let (width, height) = (resolution.width() as usize, resolution.height() as usize);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let (y, _, y_row_stride) = image.plane(0)?;
+    let (u, u_pixel_stride, u_row_stride) = image.plane(1)?;
+    let (v, v_pixel_stride, v_row_stride) = image.plane(2)?;
+    let short_plane = || NokhwaError::ReadFrameError("YUV_420_888 plane is too short".to_string());
+
+    let mut luma = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let start = row * y_row_stride;
+        luma.extend_from_slice(y.get(start..start + width).ok_or_else(short_plane)?);
+    }
+
+    let mut chroma = Vec::with_capacity(chroma_width * chroma_height * 2);
+    if u_pixel_stride == 2 && v_pixel_stride == 2 && v.as_ptr() == u.as_ptr().wrapping_add(1) {
+        // already interleaved in NV12 order, the U plane ends one byte before the V plane does
+        // SAFETY: both planes point into the same buffer, which ends with the last V sample
+        let uv = unsafe { slice::from_raw_parts(u.as_ptr(), v.len() + 1) };
+        for row in 0..chroma_height {
+            let start = row * u_row_stride;
+            chroma.extend_from_slice(
+                uv.get(start..start + chroma_width * 2)
+                    .ok_or_else(short_plane)?,
+            );
+        }
+    } else {
+        for row in 0..chroma_height {
+            for column in 0..chroma_width {
+                chroma.push(
+                    *u.get(row * u_row_stride + column * u_pixel_stride)
+                        .ok_or_else(short_plane)?,
+                );
+                chroma.push(
+                    *v.get(row * v_row_stride + column * v_pixel_stride)
+                        .ok_or_else(short_plane)?,
+                );
+            }
+        }
+    }
+    Ok((luma, chroma))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn apply_controls(
+    request: *mut ACaptureRequest,
+    controls: &HashMap<KnownCameraControl, f64>,
+) -> Result<(), NokhwaError> {
+    for (control, value) in controls {
+        let status = unsafe {
+            match control {
+                KnownCameraControl::Brightness => ACaptureRequest_setEntry_i32(
+                    request,
+                    acamera_metadata_tag::ACAMERA_CONTROL_AE_EXPOSURE_COMPENSATION.0,
+                    1,
+                    &(*value as i32),
+                ),
+                KnownCameraControl::Gain => ACaptureRequest_setEntry_i32(
+                    request,
+                    acamera_metadata_tag::ACAMERA_SENSOR_SENSITIVITY.0,
+                    1,
+                    &(*value as i32),
+                ),
+                KnownCameraControl::Exposure => ACaptureRequest_setEntry_i64(
+                    request,
+                    acamera_metadata_tag::ACAMERA_SENSOR_EXPOSURE_TIME.0,
+                    1,
+                    &((*value * 1000.0) as i64),
+                ),
+                KnownCameraControl::Focus => ACaptureRequest_setEntry_float(
+                    request,
+                    acamera_metadata_tag::ACAMERA_LENS_FOCUS_DISTANCE.0,
+                    1,
+                    &(*value as f32),
+                ),
+                KnownCameraControl::Zoom => ACaptureRequest_setEntry_float(
+                    request,
+                    acamera_metadata_tag::ACAMERA_CONTROL_ZOOM_RATIO.0,
+                    1,
+                    &(*value as f32),
+                ),
+                _ => return Err(NokhwaError::UnsupportedOperationError(ApiBackend::Android)),
+            }
+        };
+        camera_result(status, "ACaptureRequest_setEntry").map_err(|why| {
+            NokhwaError::SetPropertyError {
+                property: control.to_string(),
+                value: value.to_string(),
+                error: why,
+            }
+        })?;
+    }
+
+    // manual sensor values are ignored while the automatic exposure and focus run
+    let manual_exposure = controls.contains_key(&KnownCameraControl::Gain)
+        || controls.contains_key(&KnownCameraControl::Exposure);
+    let exposure_mode = if manual_exposure {
+        acamera_metadata_enum_acamera_control_ae_mode::ACAMERA_CONTROL_AE_MODE_OFF.0
+    } else {
+        acamera_metadata_enum_acamera_control_ae_mode::ACAMERA_CONTROL_AE_MODE_ON.0
+    } as u8;
+    let focus_mode = if controls.contains_key(&KnownCameraControl::Focus) {
+        acamera_metadata_enum_acamera_control_af_mode::ACAMERA_CONTROL_AF_MODE_OFF.0
+    } else {
+        acamera_metadata_enum_acamera_control_af_mode::ACAMERA_CONTROL_AF_MODE_CONTINUOUS_VIDEO.0
+    } as u8;
+    unsafe {
+        ACaptureRequest_setEntry_u8(
+            request,
+            acamera_metadata_tag::ACAMERA_CONTROL_AE_MODE.0,
+            1,
+            ptr::addr_of!(exposure_mode),
+        );
+        ACaptureRequest_setEntry_u8(
+            request,
+            acamera_metadata_tag::ACAMERA_CONTROL_AF_MODE.0,
+            1,
+            ptr::addr_of!(focus_mode),
+        );
+    }
+    Ok(())
+}
+
+struct AndroidStream {
+    reader: *mut AImageReader,
+    output: *mut ACaptureSessionOutput,
+    outputs: *mut ACaptureSessionOutputContainer,
+    target: *mut ACameraOutputTarget,
+    request: *mut ACaptureRequest,
+    session: *mut ACameraCaptureSession,
+    // the image listener's context, freed after the reader that calls it
+    signal: Box<FrameSignal>,
+    sequence: u64,
+}
+
+impl AndroidStream {
+    fn repeat_request(&self) -> Result<(), String> {
+        let mut request = self.request;
+        camera_result(
+            unsafe {
+                ACameraCaptureSession_setRepeatingRequest(
+                    self.session,
+                    ptr::null_mut(),
+                    1,
+                    ptr::addr_of_mut!(request),
+                    ptr::null_mut(),
+                )
+            },
+            "ACameraCaptureSession_setRepeatingRequest",
+        )
+    }
+
+    fn next_image(&mut self) -> Result<Image, NokhwaError> {
+        let deadline = Instant::now() + FRAME_TIMEOUT;
+        loop {
+            let mut image: *mut AImage = ptr::null_mut();
+            match unsafe { AImageReader_acquireLatestImage(self.reader, ptr::addr_of_mut!(image)) }
+            {
+                media_status_t::AMEDIA_OK if !image.is_null() => {
+                    self.sequence += 1;
+                    return Ok(Image(image));
+                }
+                media_status_t::AMEDIA_OK
+                | media_status_t::AMEDIA_IMGREADER_NO_BUFFER_AVAILABLE => {}
+                status => {
+                    return Err(NokhwaError::ReadFrameError(format!(
+                        "AImageReader_acquireLatestImage failed: media_status_t {}",
+                        status.0
+                    )))
+                }
+            }
+
+            let available = self
+                .signal
+                .available
+                .lock()
+                .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let (mut available, result) = self
+                .signal
+                .condvar
+                .wait_timeout_while(available, timeout, |available| !*available)
+                .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+            if result.timed_out() {
+                return Err(NokhwaError::TimeoutError(FRAME_TIMEOUT));
+            }
+            *available = false;
+        }
+    }
+}
+
+impl Drop for AndroidStream {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.session.is_null() {
+                ACameraCaptureSession_stopRepeating(self.session);
+                ACameraCaptureSession_close(self.session);
+            }
+            if !self.request.is_null() {
+                ACaptureRequest_free(self.request);
+            }
+            if !self.target.is_null() {
+                ACameraOutputTarget_free(self.target);
+            }
+            if !self.outputs.is_null() {
+                ACaptureSessionOutputContainer_free(self.outputs);
+            }
+            if !self.output.is_null() {
+                ACaptureSessionOutput_free(self.output);
+            }
+            // no image callbacks run once the reader is deleted
+            AImageReader_delete(self.reader);
+        }
+    }
+}
+
+// creates the outputs and the repeating request of a stream whose reader was created
+unsafe fn configure_stream(
+    device: *mut ACameraDevice,
+    stream: &mut AndroidStream,
+    frame_rate: i32,
+) -> Result<(), String> {
+    // the listener is copied by the reader
+    let mut listener = AImageReader_ImageListener {
+        context: ptr::addr_of!(*stream.signal).cast_mut().cast(),
+        onImageAvailable: Some(on_image_available),
+    };
+    media_result(
+        AImageReader_setImageListener(stream.reader, ptr::addr_of_mut!(listener)),
+        "AImageReader_setImageListener",
+    )?;
+    let mut window: *mut ANativeWindow = ptr::null_mut();
+    media_result(
+        AImageReader_getWindow(stream.reader, ptr::addr_of_mut!(window)),
+        "AImageReader_getWindow",
+    )?;
+
+    camera_result(
+        ACaptureSessionOutput_create(window, ptr::addr_of_mut!(stream.output)),
+        "ACaptureSessionOutput_create",
+    )?;
+    camera_result(
+        ACaptureSessionOutputContainer_create(ptr::addr_of_mut!(stream.outputs)),
+        "ACaptureSessionOutputContainer_create",
+    )?;
+    camera_result(
+        ACaptureSessionOutputContainer_add(stream.outputs, stream.output),
+        "ACaptureSessionOutputContainer_add",
+    )?;
+    camera_result(
+        ACameraOutputTarget_create(window, ptr::addr_of_mut!(stream.target)),
+        "ACameraOutputTarget_create",
+    )?;
+
+    camera_result(
+        ACameraDevice_createCaptureRequest(
+            device,
+            ACameraDevice_request_template::TEMPLATE_RECORD,
+            ptr::addr_of_mut!(stream.request),
+        ),
+        "ACameraDevice_createCaptureRequest",
+    )?;
+    camera_result(
+        ACaptureRequest_addTarget(stream.request, stream.target),
+        "ACaptureRequest_addTarget",
+    )?;
+    camera_result(
+        ACaptureRequest_setEntry_i32(
+            stream.request,
+            acamera_metadata_tag::ACAMERA_CONTROL_AE_TARGET_FPS_RANGE.0,
+            2,
+            [frame_rate, frame_rate].as_ptr(),
+        ),
+        "ACaptureRequest_setEntry_i32",
+    )
+}
+
+/// The backend struct that interfaces with the Android camera2 NDK API (`ACameraManager`, `ACaptureSession` and `AImageReader`).
+/// It works in apps built with `cargo-ndk`, `cargo-apk` or `xbuild`, as long as `ndk-context` is initialized by the glue crate
+/// (`android-activity` or `ndk-glue`) or by the app.
+///
+/// To see what this does, please see [`CaptureTrait`]
+/// # Quirks
+/// - The app needs the `CAMERA` runtime permission before a camera can be opened, see [`request_android_permission()`] and
+///   [`android_permission_granted()`]. [`new()`](AndroidCaptureDevice::new) fails with [`NokhwaError::OpenDeviceError`] without it.
+/// - [`CameraIndex::Index`] is the position in the camera ID list, [`CameraIndex::String`] is a camera2 camera ID.
+/// - [`CameraInfo`]'s description will be "Android Camera2 @ {id}", its misc field will be the camera ID.
+/// - `YUV_420_888` is handed out as [`FrameFormat::Nv12`], repacked without row padding whatever layout the device uses.
+///   `JPEG` is handed out as [`FrameFormat::MJpeg`]. There are no other formats.
+/// - Frames are tagged with a [`FrameOrientation`] from the sensor orientation, relative to the device held in its natural orientation.
+///   Use [`set_display_rotation()`](AndroidCaptureDevice::set_display_rotation) when the UI is rotated.
+/// - The frame rates are the upper ends of the camera's auto exposure target frame rate ranges. The stream asks for a fixed range at the set frame rate.
+/// - [`KnownCameraControl::Brightness`] is the exposure compensation in steps, [`KnownCameraControl::Gain`] the ISO sensitivity,
+///   [`KnownCameraControl::Exposure`] the exposure time in microseconds, [`KnownCameraControl::Focus`] the focus distance in diopters
+///   and [`KnownCameraControl::Zoom`] the zoom ratio (Android 11 and later). Setting the gain or exposure turns off auto exposure,
+///   setting the focus turns off autofocus.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-android")))]
+pub struct AndroidCaptureDevice {
+    camera_info: CameraInfo,
+    camera_format: Option<CameraFormat>,
+    controls: HashMap<KnownCameraControl, f64>,
+    sensor_orientation: u16,
+    display_rotation: u16,
+    timestamp_clock: TimestampClock,
+    // dropped before the device it captures from
+    stream: Option<AndroidStream>,
+    device: *mut ACameraDevice,
+    // the device callbacks' context, freed after the device is closed
+    device_state: Box<DeviceState>,
+    characteristics: Characteristics,
+    _manager: CameraManager,
+}
+
+// SAFETY: camera2 NDK objects may be used from any thread, and the device is only used through `&mut self`
+unsafe impl Send for AndroidCaptureDevice {}
+
+impl AndroidCaptureDevice {
+    /// Creates a new capture device using the Android camera2 backend.
+    /// # Errors
+    /// If the camera does not exist, the app does not hold the `CAMERA` permission, or the camera is in use by a higher priority app, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let manager = CameraManager::new()?;
+        let ids = manager.camera_ids()?;
+        let id = match index {
+            CameraIndex::Index(idx) => ids.get(*idx as usize),
+            CameraIndex::String(id) => ids
+                .iter()
+                .find(|camera_id| camera_id.to_bytes() == id.as_bytes()),
+        }
+        .ok_or_else(|| {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+        })?;
+
+        let characteristics = manager.characteristics(id)?;
+        let camera_info = camera_info(&characteristics, id, index);
+
+        let device_state = Box::<DeviceState>::default();
+        let mut callbacks = ACameraDevice_StateCallbacks {
+            context: ptr::addr_of!(*device_state).cast_mut().cast(),
+            onDisconnected: Some(on_disconnected),
+            onError: Some(on_error),
+        };
+        let mut device: *mut ACameraDevice = ptr::null_mut();
+        camera_result(
+            unsafe {
+                ACameraManager_openCamera(
+                    manager.0,
+                    id.as_ptr(),
+                    ptr::addr_of_mut!(callbacks),
+                    ptr::addr_of_mut!(device),
+                )
+            },
+            "ACameraManager_openCamera",
+        )
+        .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why))?;
+
+        Ok(AndroidCaptureDevice {
+            camera_info,
+            camera_format: None,
+            controls: HashMap::new(),
+            sensor_orientation: characteristics.sensor_orientation(),
+            display_rotation: 0,
+            timestamp_clock: characteristics.timestamp_clock(),
+            stream: None,
+            device,
+            device_state,
+            characteristics,
+            _manager: manager,
+        })
+    }
+
+    /// Gets the camera2 ID of the camera.
+    #[must_use]
+    pub fn id(&self) -> String {
+        self.camera_info.misc()
+    }
+
+    /// Gets how far the sensor image is rotated clockwise from the device held in its natural orientation, in degrees.
+    #[must_use]
+    pub fn sensor_orientation(&self) -> u16 {
+        self.sensor_orientation
+    }
+
+    /// Gets the rotation of the UI the frames' [`FrameOrientation`] is relative to, in degrees.
+    #[must_use]
+    pub fn display_rotation(&self) -> u16 {
+        self.display_rotation
+    }
+
+    /// Sets the rotation of the UI in degrees (`Display.getRotation()` times 90), so the frames' [`FrameOrientation`] turns them upright on screen.
+    pub fn set_display_rotation(&mut self, degrees: u16) {
+        self.display_rotation = degrees % 360;
+    }
+
+    /// Gets the [`FrameOrientation`] the frames are tagged with.
+    #[must_use]
+    pub fn orientation(&self) -> FrameOrientation {
+        // the front camera faces the other way, so turning the device turns its image the other way too
+        let rotation = match self.camera_info.position() {
+            CameraPosition::Front => self.sensor_orientation + self.display_rotation,
+            _ => self.sensor_orientation + 360 - self.display_rotation,
+        };
+        FrameOrientation::new(rotation, false)
+    }
+
+    fn check_device(&self) -> Result<(), NokhwaError> {
+        if self.device_state.disconnected.load(Ordering::Acquire) {
+            return Err(NokhwaError::DeviceLost(
+                match self.device_state.error.load(Ordering::Acquire) {
+                    0 => "Camera disconnected".to_string(),
+                    error => format!("Camera failed with error {error}"),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    fn validated_format(&self, camera_format: CameraFormat) -> Result<AIMAGE_FORMATS, NokhwaError> {
+        let rejected = |error: &str| NokhwaError::SetPropertyError {
+            property: "CameraFormat".to_string(),
+            value: camera_format.to_string(),
+            error: error.to_string(),
+        };
+        let format = frameformat_to_aimage_format(camera_format.format())
+            .ok_or_else(|| rejected("unsupported FrameFormat"))?;
+        if !self
+            .characteristics
+            .output_sizes(format)
+            .contains(&camera_format.resolution())
+        {
+            return Err(rejected("unsupported Resolution"));
+        }
+        if !self
+            .characteristics
+            .frame_rates()
+            .contains(&camera_format.frame_rate().as_u32())
+        {
+            return Err(rejected("unsupported FrameRate"));
+        }
+        Ok(format)
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn start_stream(&self, camera_format: CameraFormat) -> Result<AndroidStream, NokhwaError> {
+        let format = self.validated_format(camera_format)?;
+        let mut reader: *mut AImageReader = ptr::null_mut();
+        media_result(
+            unsafe {
+                AImageReader_new(
+                    camera_format.width() as i32,
+                    camera_format.height() as i32,
+                    format.0 as i32,
+                    MAX_IMAGES,
+                    ptr::addr_of_mut!(reader),
+                )
+            },
+            "AImageReader_new",
+        )
+        .map_err(NokhwaError::OpenStreamError)?;
+
+        // from here on the stream frees whatever was created if a later step fails
+        let mut stream = AndroidStream {
+            reader,
+            output: ptr::null_mut(),
+            outputs: ptr::null_mut(),
+            target: ptr::null_mut(),
+            request: ptr::null_mut(),
+            session: ptr::null_mut(),
+            signal: Box::<FrameSignal>::default(),
+            sequence: 0,
+        };
+        let frame_rate = camera_format.frame_rate().as_u32() as i32;
+        unsafe {
+            configure_stream(self.device, &mut stream, frame_rate)
+                .map_err(NokhwaError::OpenStreamError)?;
+        }
+        apply_controls(stream.request, &self.controls)?;
+
+        let session_callbacks = ACameraCaptureSession_stateCallbacks {
+            context: ptr::null_mut(),
+            onClosed: Some(on_session_state),
+            onReady: Some(on_session_state),
+            onActive: Some(on_session_state),
+        };
+        camera_result(
+            unsafe {
+                ACameraDevice_createCaptureSession(
+                    self.device,
+                    stream.outputs,
+                    ptr::addr_of!(session_callbacks),
+                    ptr::addr_of_mut!(stream.session),
+                )
+            },
+            "ACameraDevice_createCaptureSession",
+        )
+        .and_then(|()| stream.repeat_request())
+        .map_err(NokhwaError::OpenStreamError)?;
+        Ok(stream)
+    }
+}
+
+impl Backend for AndroidCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::Android;
+}
+
+impl CaptureTrait for AndroidCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        let frame_rate = self
+            .characteristics
+            .frame_rates()
+            .into_iter()
+            .filter(|frame_rate| *frame_rate <= 30)
+            .max()
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: "FrameRate".to_string(),
+                error: "no target frame rate range".to_string(),
+            })?;
+        // the largest size is usually the full sensor, which the camera can not stream at the frame rate
+        let resolution = self
+            .characteristics
+            .output_sizes(AIMAGE_FORMATS::AIMAGE_FORMAT_YUV_420_888)
+            .into_iter()
+            .filter(|resolution| resolution.width() <= 1920 && resolution.height() <= 1080)
+            .max()
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: "Resolution".to_string(),
+                error: "no YUV_420_888 output".to_string(),
+            })?;
+        self.set_camera_format(CameraFormat::new(
+            resolution,
+            FrameFormat::Nv12,
+            FrameRate::new_integer(frame_rate)?,
+        ))
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Android
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        // the format only changes when we set it
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        self.validated_format(new_fmt)?;
+
+        let current_format = self.camera_format;
+        self.camera_format = Some(new_fmt);
+        if self.is_stream_open() {
+            self.stop_stream()?;
+            if let Err(why) = self.open_stream() {
+                self.camera_format = current_format;
+                return Err(why);
+            }
+        }
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let format = frameformat_to_aimage_format(fourcc).ok_or(NokhwaError::GetPropertyError {
+            property: "FrameFormat".to_string(),
+            error: "unsupported".to_string(),
+        })?;
+        let frame_rates = self
+            .characteristics
+            .frame_rates()
+            .into_iter()
+            .map(FrameRate::new_integer)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self
+            .characteristics
+            .output_sizes(format)
+            .into_iter()
+            .map(|resolution| (resolution, frame_rates.clone()))
+            .collect())
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Ok([
+            AIMAGE_FORMATS::AIMAGE_FORMAT_YUV_420_888,
+            AIMAGE_FORMATS::AIMAGE_FORMAT_JPEG,
+        ]
+        .into_iter()
+        .filter(|format| !self.characteristics.output_sizes(*format).is_empty())
+        .filter_map(aimage_format_to_frameformat)
+        .collect())
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::Nv12, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        let description = self
+            .characteristics
+            .control_description(control, self.controls.get(&control).copied())
+            .ok_or(NokhwaError::UnsupportedOperationError(ApiBackend::Android))?;
+        Ok(CameraControl::new(
+            control,
+            control.to_string(),
+            description,
+            vec![],
+            true,
+        ))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(SUPPORTED_CONTROLS
+            .into_iter()
+            .filter_map(|control| self.camera_control(control).ok())
+            .collect())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let control_val = match value {
+            ControlValueSetter::Integer(i) => i as f64,
+            ControlValueSetter::Float(f) => f,
+            val => {
+                return Err(NokhwaError::SetPropertyError {
+                    property: id.to_string(),
+                    value: val.to_string(),
+                    error: "unsupported value".to_string(),
+                })
+            }
+        };
+        self.camera_control(id)?;
+
+        let previous = self.controls.insert(id, control_val);
+        if let Some(stream) = &self.stream {
+            // the repeating request is copied when it is set, so it is set again with the new value
+            let result = apply_controls(stream.request, &self.controls).and_then(|()| {
+                stream
+                    .repeat_request()
+                    .map_err(|why| NokhwaError::SetPropertyError {
+                        property: id.to_string(),
+                        value: control_val.to_string(),
+                        error: why,
+                    })
+            });
+            if let Err(why) = result {
+                match previous {
+                    Some(previous) => self.controls.insert(id, previous),
+                    None => self.controls.remove(&id),
+                };
+                return Err(why);
+            }
+        }
+        Ok(())
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.check_device()?;
+        if self.is_stream_open() {
+            self.stop_stream()?;
+        }
+
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        self.stream = Some(self.start_stream(camera_format)?);
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        self.check_device()?;
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let orientation = self.orientation();
+        let clock = self.timestamp_clock;
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+        let image = stream.next_image()?;
+
+        let buffer = if image.number_of_planes() == 3 {
+            let (luma, chroma) = yuv_420_888_to_nv12(&image, camera_format.resolution())?;
+            Buffer::with_planes(
+                camera_format.resolution(),
+                &[&luma, &chroma],
+                FrameFormat::Nv12,
+            )
+        } else {
+            let (data, _, _) = image.plane(0)?;
+            Buffer::new(camera_format.resolution(), data, camera_format.format())
+        };
+        let buffer = buffer
+            .with_sequence(stream.sequence)
+            .with_orientation(orientation);
+        Ok(match image.timestamp() {
+            Some(timestamp) => buffer.with_timestamp(FrameTimestamp::new(timestamp, clock)),
+            None => buffer,
+        })
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        // the image is handed back to the reader right away, so the frame has to be copied out
+        Ok(Cow::Owned(self.frame()?.buffer().to_vec()))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        // closing the session stops the repeating request and waits for it
+        drop(self.stream.take());
+        Ok(())
+    }
+}
+
+impl Drop for AndroidCaptureDevice {
+    fn drop(&mut self) {
+        let _ = self.stop_stream();
+        unsafe { ACameraDevice_close(self.device) };
+    }
+}
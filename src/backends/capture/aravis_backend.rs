@@ -0,0 +1,820 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use aravis::{
+    prelude::*, AcquisitionMode, Auto, Buffer as ArvBuffer, BufferStatus, Camera, Stream,
+};
+use nokhwa_core::{
+    buffer::{Buffer, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::{BayerPattern, FrameFormat},
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlMenuEntry,
+        ControlValueDescription, ControlValueSetter, FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How many buffers the stream holds. One is handed out while the camera fills the others.
+const STREAM_BUFFERS: usize = 4;
+/// How long [`frame()`](CaptureTrait::frame) waits for a complete buffer.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether the camera waits for a trigger before each frame (`TriggerMode` of the `FrameStart` trigger). A [`ControlValueDescription::Menu`]
+/// of `Off` (free running) and `On`, which waits on the [`GENICAM_TRIGGER_SOURCE`].
+pub const GENICAM_TRIGGER_MODE: KnownCameraControl = KnownCameraControl::Other(0x6763_0001);
+/// Where the trigger comes from (`TriggerSource`), a [`ControlValueDescription::StringList`] of e.g. `Software`, `Line0` or `Line1`.
+/// A `Software` trigger is sent with [`AravisCaptureDevice::software_trigger()`].
+pub const GENICAM_TRIGGER_SOURCE: KnownCameraControl = KnownCameraControl::Other(0x6763_0002);
+/// How the camera adjusts its exposure time (`ExposureAuto`). A [`ControlValueDescription::Menu`] of `Off`, `Once` and `Continuous`.
+/// Setting [`KnownCameraControl::Exposure`] turns it off.
+pub const GENICAM_EXPOSURE_AUTO: KnownCameraControl = KnownCameraControl::Other(0x6763_0003);
+/// How the camera adjusts its gain (`GainAuto`). A [`ControlValueDescription::Menu`] of `Off`, `Once` and `Continuous`.
+/// Setting [`KnownCameraControl::Gain`] turns it off.
+pub const GENICAM_GAIN_AUTO: KnownCameraControl = KnownCameraControl::Other(0x6763_0004);
+
+/// The [`KnownCameraControl`]s that map to a `GenICam` standard feature.
+const SUPPORTED_CONTROLS: [KnownCameraControl; 6] = [
+    KnownCameraControl::Exposure,
+    KnownCameraControl::Gain,
+    GENICAM_EXPOSURE_AUTO,
+    GENICAM_GAIN_AUTO,
+    GENICAM_TRIGGER_MODE,
+    GENICAM_TRIGGER_SOURCE,
+];
+
+const AUTO_MODES: [&str; 3] = ["Off", "Once", "Continuous"];
+const TRIGGER_MODES: [&str; 2] = ["Off", "On"];
+
+/// The `GenICam` (PFNC) pixel formats nokhwa understands, in the order they are preferred when several map to the same [`FrameFormat`].
+/// 10 and 12 bit `Mono` samples are scaled up to [`FrameFormat::Luma16`], 10 and 12 bit `Bayer` samples already sit in the low bits of 16 bit words.
+const PIXEL_FORMATS: [(&str, FrameFormat); 22] = [
+    ("Mono8", FrameFormat::Luma8),
+    ("Mono16", FrameFormat::Luma16),
+    ("Mono12", FrameFormat::Luma16),
+    ("Mono10", FrameFormat::Luma16),
+    ("BayerRG8", FrameFormat::Bayer8(BayerPattern::Rggb)),
+    ("BayerBG8", FrameFormat::Bayer8(BayerPattern::Bggr)),
+    ("BayerGR8", FrameFormat::Bayer8(BayerPattern::Grbg)),
+    ("BayerGB8", FrameFormat::Bayer8(BayerPattern::Gbrg)),
+    ("BayerRG10", FrameFormat::Bayer10(BayerPattern::Rggb)),
+    ("BayerBG10", FrameFormat::Bayer10(BayerPattern::Bggr)),
+    ("BayerGR10", FrameFormat::Bayer10(BayerPattern::Grbg)),
+    ("BayerGB10", FrameFormat::Bayer10(BayerPattern::Gbrg)),
+    ("BayerRG12", FrameFormat::Bayer12(BayerPattern::Rggb)),
+    ("BayerBG12", FrameFormat::Bayer12(BayerPattern::Bggr)),
+    ("BayerGR12", FrameFormat::Bayer12(BayerPattern::Grbg)),
+    ("BayerGB12", FrameFormat::Bayer12(BayerPattern::Gbrg)),
+    ("RGB8", FrameFormat::Rgb8),
+    ("RGB8Packed", FrameFormat::Rgb8),
+    ("YUV422_8_UYVY", FrameFormat::Uyv422),
+    ("YUV422Packed", FrameFormat::Uyv422),
+    ("YUV422_8", FrameFormat::Yuv422),
+    ("YUV422_YUYV_Packed", FrameFormat::Yuv422),
+];
+
+/// The resolutions offered next to the full sensor, `GenICam` cameras take any region that fits the width and height increments.
+const COMMON_RESOLUTIONS: [(u32, u32); 6] = [
+    (640, 480),
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1280, 1024),
+    (1920, 1080),
+];
+
+/// Converts a `GenICam` pixel format name (e.g. `BayerRG12`) into a [`FrameFormat`].
+#[must_use]
+pub fn genicam_pixel_format_to_frameformat(pixel_format: &str) -> Option<FrameFormat> {
+    PIXEL_FORMATS
+        .iter()
+        .find(|(name, _)| *name == pixel_format)
+        .map(|(_, frame_format)| *frame_format)
+}
+
+/// Converts a [`FrameFormat`] into the `GenICam` pixel format names that deliver it, in the order they are preferred.
+#[must_use]
+pub fn frameformat_to_genicam_pixel_formats(frame_format: FrameFormat) -> Vec<&'static str> {
+    PIXEL_FORMATS
+        .iter()
+        .filter(|(_, format)| *format == frame_format)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+// how far the samples of a `Mono` format have to be shifted to fill 16 bits
+fn luma_shift(pixel_format: &str) -> u32 {
+    match pixel_format {
+        "Mono10" => 6,
+        "Mono12" => 4,
+        _ => 0,
+    }
+}
+
+/// Lists the `GigE` Vision and USB3 Vision cameras `Aravis` can see.
+///
+/// The [`CameraInfo::misc()`] of each camera is its `Aravis` device ID, e.g. `Basler-21234567`.
+/// # Errors
+/// This does not currently error, the signature matches the other backends.
+#[allow(clippy::unnecessary_wraps)]
+pub fn query_aravis() -> Result<Vec<CameraInfo>, NokhwaError> {
+    aravis::update_device_list();
+    Ok((0..aravis::n_devices())
+        .filter_map(|index| {
+            let id = aravis::device_id(index)?;
+            Some(device_info(index, &id))
+        })
+        .collect())
+}
+
+fn device_info(index: u32, id: &str) -> CameraInfo {
+    let vendor = aravis::device_vendor(index).unwrap_or_default();
+    let model = aravis::device_model(index).unwrap_or_default();
+    let protocol = aravis::device_protocol(index).unwrap_or_default();
+    let name = if model.is_empty() {
+        id.to_string()
+    } else {
+        format!("{vendor} {model}")
+    };
+    CameraInfo::new(
+        &name,
+        &format!(
+            "{protocol} Camera @ {}, Serial {}",
+            aravis::device_address(index).unwrap_or_default(),
+            aravis::device_serial_nbr(index).unwrap_or_default()
+        ),
+        id,
+        &CameraIndex::Index(index),
+    )
+}
+
+/// The capture device for `GigE` Vision and USB3 Vision industrial cameras, through [`Aravis`](https://github.com/AravisProject/aravis).
+///
+/// Besides [`KnownCameraControl::Exposure`] (in µs) and [`KnownCameraControl::Gain`] (in dB), the `GenICam` trigger and auto features are
+/// exposed as [`GENICAM_TRIGGER_MODE`], [`GENICAM_TRIGGER_SOURCE`], [`GENICAM_EXPOSURE_AUTO`] and [`GENICAM_GAIN_AUTO`].
+/// Every other feature can be reached through [`aravis_camera()`](AravisCaptureDevice::aravis_camera).
+/// # Quirks
+/// - `Mono10` and `Mono12` frames are scaled up to [`FrameFormat::Luma16`], so they look the same as `Mono16`.
+/// - The resolution is a region of interest at the top left of the sensor, binning is left as the camera has it.
+/// - With [`GENICAM_TRIGGER_MODE`] on, [`frame()`](CaptureTrait::frame) errors if no trigger arrives within 2 seconds.
+/// - `GigE` Vision cameras can only be opened by one host at a time.
+pub struct AravisCaptureDevice {
+    camera_info: CameraInfo,
+    camera_format: Option<CameraFormat>,
+    pixel_format: String,
+    stream: Option<Stream>,
+    camera: Camera,
+}
+
+impl AravisCaptureDevice {
+    /// Creates a new capture device using the `Aravis` backend.
+    ///
+    /// `index` is either an index into [`query_aravis()`] or an `Aravis` device ID, a serial number, or an IP address.
+    /// # Errors
+    /// If the camera does not exist, or is opened by someone else, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        aravis::update_device_list();
+        let (camera_info, id) = match index {
+            CameraIndex::Index(idx) => {
+                let id = aravis::device_id(*idx).ok_or_else(|| {
+                    NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+                })?;
+                (device_info(*idx, &id), id.to_string())
+            }
+            CameraIndex::String(id) => (
+                (0..aravis::n_devices())
+                    .find(|idx| aravis::device_id(*idx).is_some_and(|device| device == *id))
+                    .map_or_else(
+                        || CameraInfo::new(id, &format!("GenICam Camera @ {id}"), id, index),
+                        |idx| device_info(idx, id),
+                    ),
+                id.clone(),
+            ),
+        };
+
+        let camera = Camera::new(Some(&id))
+            .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?;
+        let pixel_format = camera
+            .pixel_format_as_string()
+            .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?
+            .to_string();
+
+        Ok(AravisCaptureDevice {
+            camera_info,
+            camera_format: None,
+            pixel_format,
+            stream: None,
+            camera,
+        })
+    }
+
+    /// Gets the `Aravis` camera, for `GenICam` features nokhwa has no control for.
+    ///
+    /// Changing the region, pixel format or acquisition through it while the stream is open leaves nokhwa's idea of the format stale.
+    #[must_use]
+    pub fn aravis_camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// Gets the `GenICam` pixel format the camera is set to, e.g. `BayerRG12`.
+    #[must_use]
+    pub fn pixel_format(&self) -> &str {
+        &self.pixel_format
+    }
+
+    /// Sends a software trigger (`TriggerSoftware`), which starts a frame if [`GENICAM_TRIGGER_MODE`] is on and the
+    /// [`GENICAM_TRIGGER_SOURCE`] is `Software`.
+    /// # Errors
+    /// If the camera has no software trigger, this will error.
+    pub fn software_trigger(&self) -> Result<(), NokhwaError> {
+        self.camera
+            .software_trigger()
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "TriggerSoftware".to_string(),
+                value: "Execute".to_string(),
+                error: why.to_string(),
+            })
+    }
+
+    fn available_pixel_formats(&self) -> Result<Vec<String>, NokhwaError> {
+        self.camera
+            .dup_available_pixel_formats_as_strings()
+            .map(|formats| formats.iter().map(ToString::to_string).collect())
+            .map_err(|why| NokhwaError::GetPropertyError {
+                property: "PixelFormat".to_string(),
+                error: why.to_string(),
+            })
+    }
+
+    fn supported_resolutions(&self) -> Result<Vec<Resolution>, NokhwaError> {
+        let get_error = |why: String| NokhwaError::GetPropertyError {
+            property: "Region".to_string(),
+            error: why,
+        };
+        let (min_width, max_width) = self
+            .camera
+            .width_bounds()
+            .map_err(|why| why.to_string())
+            .map_err(get_error)?;
+        let (min_height, max_height) = self
+            .camera
+            .height_bounds()
+            .map_err(|why| why.to_string())
+            .map_err(get_error)?;
+        let width_step = self
+            .camera
+            .width_increment()
+            .map_err(|why| why.to_string())
+            .map_err(get_error)?;
+        let height_step = self
+            .camera
+            .height_increment()
+            .map_err(|why| why.to_string())
+            .map_err(get_error)?;
+        let fits = |size: i32, min: i32, max: i32, step: i32| {
+            (min..=max).contains(&size) && (size - min).checked_rem(step.max(1)) == Some(0)
+        };
+
+        let mut resolutions = COMMON_RESOLUTIONS
+            .into_iter()
+            .filter_map(|(width, height)| {
+                let width = i32::try_from(width).ok()?;
+                let height = i32::try_from(height).ok()?;
+                (fits(width, min_width, max_width, width_step)
+                    && fits(height, min_height, max_height, height_step))
+                .then_some((width, height))
+            })
+            .chain(std::iter::once((max_width, max_height)))
+            .filter_map(|(width, height)| {
+                Some(Resolution::new(
+                    u32::try_from(width).ok()?,
+                    u32::try_from(height).ok()?,
+                ))
+            })
+            .collect::<Vec<_>>();
+        resolutions.sort();
+        resolutions.dedup();
+        Ok(resolutions)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn supported_frame_rates(&self) -> Result<Vec<FrameRate>, NokhwaError> {
+        let (min, max) =
+            self.camera
+                .frame_rate_bounds()
+                .map_err(|why| NokhwaError::GetPropertyError {
+                    property: "AcquisitionFrameRate".to_string(),
+                    error: why.to_string(),
+                })?;
+        let max = max.floor() as u32;
+        let mut frame_rates = [5, 10, 15, 30, 60, 120]
+            .into_iter()
+            .filter(|frame_rate| (min..=f64::from(max)).contains(&f64::from(*frame_rate)))
+            .chain(std::iter::once(max))
+            .filter(|frame_rate| *frame_rate > 0)
+            .collect::<Vec<_>>();
+        frame_rates.sort_unstable();
+        frame_rates.dedup();
+        frame_rates
+            .into_iter()
+            .map(FrameRate::new_integer)
+            .collect()
+    }
+
+    fn apply_format(&mut self, camera_format: CameraFormat) -> Result<(), NokhwaError> {
+        let set_error = |property: &str, why: String| NokhwaError::SetPropertyError {
+            property: property.to_string(),
+            value: camera_format.to_string(),
+            error: why,
+        };
+        let available = self.available_pixel_formats()?;
+        let pixel_format = frameformat_to_genicam_pixel_formats(camera_format.format())
+            .into_iter()
+            .find(|name| available.iter().any(|available| available == name))
+            .ok_or_else(|| set_error("PixelFormat", "unsupported".to_string()))?;
+        let width = i32::try_from(camera_format.width())
+            .map_err(|why| set_error("Width", why.to_string()))?;
+        let height = i32::try_from(camera_format.height())
+            .map_err(|why| set_error("Height", why.to_string()))?;
+
+        self.camera
+            .set_pixel_format_from_string(pixel_format)
+            .map_err(|why| set_error("PixelFormat", why.to_string()))?;
+        self.pixel_format = pixel_format.to_string();
+        self.camera
+            .set_region(0, 0, width, height)
+            .map_err(|why| set_error("Region", why.to_string()))?;
+        // not every camera can limit its frame rate, those run as fast as they can
+        if self.camera.is_frame_rate_available().unwrap_or(false) {
+            self.camera
+                .set_frame_rate(f64::from(camera_format.frame_rate().as_float()))
+                .map_err(|why| set_error("AcquisitionFrameRate", why.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn auto_mode(&self, control: KnownCameraControl) -> Result<Auto, String> {
+        if control == GENICAM_EXPOSURE_AUTO {
+            self.camera
+                .exposure_time_auto()
+                .map_err(|why| why.to_string())
+        } else {
+            self.camera.gain_auto().map_err(|why| why.to_string())
+        }
+    }
+
+    fn control_description(
+        &self,
+        control: KnownCameraControl,
+    ) -> Result<ControlValueDescription, String> {
+        let menu = |value: i64, names: &[&str]| ControlValueDescription::Menu {
+            value,
+            items: (0_i64..)
+                .zip(names)
+                .map(|(index, name)| (index, ControlMenuEntry::Name((*name).to_string())))
+                .collect(),
+            default: 0,
+        };
+        match control {
+            KnownCameraControl::Exposure => {
+                let (min, max) = self
+                    .camera
+                    .exposure_time_bounds()
+                    .map_err(|why| why.to_string())?;
+                Ok(ControlValueDescription::FloatRange {
+                    min,
+                    max,
+                    value: self.camera.exposure_time().map_err(|why| why.to_string())?,
+                    step: 0.0,
+                    default: min.max(10_000.0).min(max),
+                })
+            }
+            KnownCameraControl::Gain => {
+                let (min, max) = self.camera.gain_bounds().map_err(|why| why.to_string())?;
+                Ok(ControlValueDescription::FloatRange {
+                    min,
+                    max,
+                    value: self.camera.gain().map_err(|why| why.to_string())?,
+                    step: 0.0,
+                    default: min,
+                })
+            }
+            GENICAM_EXPOSURE_AUTO | GENICAM_GAIN_AUTO => {
+                let value = match self.auto_mode(control)? {
+                    Auto::Off => 0,
+                    Auto::Once => 1,
+                    _ => 2,
+                };
+                Ok(menu(value, &AUTO_MODES))
+            }
+            GENICAM_TRIGGER_MODE => {
+                let mode = self
+                    .camera
+                    .string("TriggerMode")
+                    .map_err(|why| why.to_string())?;
+                Ok(menu(i64::from(mode == "On"), &TRIGGER_MODES))
+            }
+            GENICAM_TRIGGER_SOURCE => Ok(ControlValueDescription::StringList {
+                value: self
+                    .camera
+                    .trigger_source()
+                    .map_err(|why| why.to_string())?
+                    .to_string(),
+                availible: self
+                    .camera
+                    .dup_available_trigger_sources()
+                    .map_err(|why| why.to_string())?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            }),
+            _ => Err("unsupported".to_string()),
+        }
+    }
+
+    fn apply_control(
+        &mut self,
+        control: KnownCameraControl,
+        value: &ControlValueSetter,
+    ) -> Result<(), String> {
+        let menu_index = match value {
+            ControlValueSetter::Integer(i) | ControlValueSetter::EnumValue(i) => Some(*i),
+            _ => None,
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let number = match value {
+            ControlValueSetter::Float(f) => Some(*f),
+            _ => menu_index.map(|i| i as f64),
+        };
+        match (control, number, menu_index) {
+            (KnownCameraControl::Exposure, Some(value), _) => {
+                self.camera
+                    .set_exposure_time_auto(Auto::Off)
+                    .map_err(|why| why.to_string())?;
+                self.camera
+                    .set_exposure_time(value)
+                    .map_err(|why| why.to_string())
+            }
+            (KnownCameraControl::Gain, Some(value), _) => {
+                self.camera
+                    .set_gain_auto(Auto::Off)
+                    .map_err(|why| why.to_string())?;
+                self.camera.set_gain(value).map_err(|why| why.to_string())
+            }
+            (GENICAM_EXPOSURE_AUTO | GENICAM_GAIN_AUTO, _, Some(index)) => {
+                let mode = match index {
+                    0 => Auto::Off,
+                    1 => Auto::Once,
+                    2 => Auto::Continuous,
+                    _ => return Err("no such mode".to_string()),
+                };
+                if control == GENICAM_EXPOSURE_AUTO {
+                    self.camera
+                        .set_exposure_time_auto(mode)
+                        .map_err(|why| why.to_string())
+                } else {
+                    self.camera
+                        .set_gain_auto(mode)
+                        .map_err(|why| why.to_string())
+                }
+            }
+            (GENICAM_TRIGGER_MODE, _, Some(0)) => {
+                self.camera.clear_triggers().map_err(|why| why.to_string())
+            }
+            (GENICAM_TRIGGER_MODE, _, Some(1)) => {
+                // `arv_camera_set_trigger()` sets up the `FrameStart` trigger and turns the others off
+                let source = self
+                    .camera
+                    .trigger_source()
+                    .map_err(|why| why.to_string())?;
+                self.camera
+                    .set_trigger(&source)
+                    .map_err(|why| why.to_string())
+            }
+            (GENICAM_TRIGGER_SOURCE, _, _) => match value {
+                ControlValueSetter::String(source) | ControlValueSetter::StringList(source) => self
+                    .camera
+                    .set_trigger_source(source)
+                    .map_err(|why| why.to_string()),
+                _ => Err("unsupported value".to_string()),
+            },
+            _ => Err("unsupported value".to_string()),
+        }
+    }
+
+    fn start_stream(&self) -> Result<Stream, NokhwaError> {
+        let open_error = |why: String| NokhwaError::OpenStreamError(why);
+        self.camera
+            .set_acquisition_mode(AcquisitionMode::Continuous)
+            .map_err(|why| why.to_string())
+            .map_err(open_error)?;
+        let payload = self
+            .camera
+            .payload()
+            .map_err(|why| why.to_string())
+            .map_err(open_error)?;
+        let stream = self
+            .camera
+            .create_stream()
+            .map_err(|why| why.to_string())
+            .map_err(open_error)?;
+        for _ in 0..STREAM_BUFFERS {
+            stream.push_buffer(ArvBuffer::new_allocate(payload as usize));
+        }
+        self.camera
+            .start_acquisition()
+            .map_err(|why| why.to_string())
+            .map_err(open_error)?;
+        Ok(stream)
+    }
+
+    fn next_buffer(&mut self) -> Result<(Vec<u8>, Option<FrameTimestamp>, u64), NokhwaError> {
+        let stream = self
+            .stream
+            .as_ref()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+        let deadline = Instant::now() + FRAME_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let timeout = u64::try_from(remaining.as_micros()).unwrap_or(u64::MAX);
+            let buffer = stream
+                .timeout_pop_buffer(timeout)
+                .ok_or(NokhwaError::TimeoutError(FRAME_TIMEOUT))?;
+            // GigE Vision drops packets under load, such buffers are given back and the next one is waited for
+            if buffer.status() != BufferStatus::Success {
+                stream.push_buffer(buffer);
+                if remaining.is_zero() {
+                    return Err(NokhwaError::ReadFrameError(
+                        "Only incomplete frames arrived".to_string(),
+                    ));
+                }
+                continue;
+            }
+
+            let data = buffer.data().to_vec();
+            let timestamp = Some(buffer.system_timestamp())
+                .filter(|nanos| *nanos != 0)
+                .map(|nanos| {
+                    FrameTimestamp::new(Duration::from_nanos(nanos), TimestampClock::Realtime)
+                });
+            let sequence = buffer.frame_id();
+            stream.push_buffer(buffer);
+            return Ok((data, timestamp, sequence));
+        }
+    }
+}
+
+impl Backend for AravisCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::Aravis;
+}
+
+impl CaptureTrait for AravisCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // start out with what the camera was left at
+        self.refresh_camera_format()
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Aravis
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        let get_error = |property: &str, why: String| NokhwaError::GetPropertyError {
+            property: property.to_string(),
+            error: why,
+        };
+        self.pixel_format = self
+            .camera
+            .pixel_format_as_string()
+            .map_err(|why| get_error("PixelFormat", why.to_string()))?
+            .to_string();
+        let format = genicam_pixel_format_to_frameformat(&self.pixel_format).ok_or_else(|| {
+            get_error(
+                "PixelFormat",
+                format!("{} is unsupported", self.pixel_format),
+            )
+        })?;
+        let (_, _, width, height) = self
+            .camera
+            .region()
+            .map_err(|why| get_error("Region", why.to_string()))?;
+        let frame_rate = self
+            .camera
+            .frame_rate()
+            .map_err(|why| get_error("AcquisitionFrameRate", why.to_string()))?;
+
+        self.camera_format = Some(CameraFormat::new(
+            Resolution::new(
+                u32::try_from(width).map_err(|why| get_error("Width", why.to_string()))?,
+                u32::try_from(height).map_err(|why| get_error("Height", why.to_string()))?,
+            ),
+            format,
+            FrameRate::new_integer(frame_rate.round().max(1.0) as u32)?,
+        ));
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        // the region and pixel format are locked while acquiring
+        let reopen = self.is_stream_open();
+        if reopen {
+            self.stop_stream()?;
+        }
+
+        let current_format = self.camera_format;
+        let result = self.apply_format(new_fmt);
+        match &result {
+            Ok(()) => self.camera_format = Some(new_fmt),
+            Err(_) => {
+                if let Some(current_format) = current_format {
+                    let _ = self.apply_format(current_format);
+                }
+            }
+        }
+        if reopen {
+            self.open_stream()?;
+        }
+        result
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        if !self.compatible_fourcc()?.contains(&fourcc) {
+            return Err(NokhwaError::GetPropertyError {
+                property: "FrameFormat".to_string(),
+                error: "unsupported".to_string(),
+            });
+        }
+        let frame_rates = self.supported_frame_rates()?;
+        Ok(self
+            .supported_resolutions()?
+            .into_iter()
+            .map(|resolution| (resolution, frame_rates.clone()))
+            .collect())
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        let mut formats = self
+            .available_pixel_formats()?
+            .iter()
+            .filter_map(|name| genicam_pixel_format_to_frameformat(name))
+            .collect::<Vec<_>>();
+        formats.sort();
+        formats.dedup();
+        Ok(formats)
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::Luma8, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        let description =
+            self.control_description(control)
+                .map_err(|why| NokhwaError::GetPropertyError {
+                    property: control.to_string(),
+                    error: why,
+                })?;
+        let name = match control {
+            GENICAM_TRIGGER_MODE => "Trigger Mode".to_string(),
+            GENICAM_TRIGGER_SOURCE => "Trigger Source".to_string(),
+            GENICAM_EXPOSURE_AUTO => "Exposure Auto".to_string(),
+            GENICAM_GAIN_AUTO => "Gain Auto".to_string(),
+            control => control.to_string(),
+        };
+        Ok(CameraControl::new(control, name, description, vec![], true))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        // cameras leave out the features they do not have
+        Ok(SUPPORTED_CONTROLS
+            .into_iter()
+            .filter_map(|control| self.camera_control(control).ok())
+            .collect())
+    }
+
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        self.apply_control(id, &value)
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: id.to_string(),
+                value: value.to_string(),
+                error: why,
+            })
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.is_stream_open() {
+            self.stop_stream()?;
+        }
+        if self.camera_format.is_none() {
+            self.refresh_camera_format()?;
+        }
+        self.stream = Some(self.start_stream()?);
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let (data, timestamp, sequence) = self.next_buffer()?;
+        let data = match luma_shift(&self.pixel_format) {
+            0 => data,
+            shift => data
+                .chunks_exact(2)
+                .flat_map(|sample| {
+                    (u16::from_le_bytes([sample[0], sample[1]]) << shift).to_le_bytes()
+                })
+                .collect(),
+        };
+
+        let buffer = Buffer::new(camera_format.resolution(), &data, camera_format.format())
+            .with_sequence(sequence);
+        Ok(match timestamp {
+            Some(timestamp) => buffer.with_timestamp(timestamp),
+            None => buffer,
+        })
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let (data, _, _) = self.next_buffer()?;
+        Ok(Cow::Owned(data))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.stream.take().is_some() {
+            self.camera
+                .stop_acquisition()
+                .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AravisCaptureDevice {
+    fn drop(&mut self) {
+        let _ = self.stop_stream();
+    }
+}
@@ -13,52 +13,122 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-#[cfg(target_os = "macos")]
-use flume::{Receiver, Sender};
-#[cfg(target_os = "macos")]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+use flume::{Receiver, RecvTimeoutError, Sender};
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
 use nokhwa_bindings_macos::{
-    AVCaptureDevice, AVCaptureDeviceInput, AVCaptureSession, AVCaptureVideoCallback,
-    AVCaptureVideoDataOutput,
+    query_avfoundation, AVCaptureDevice, AVCaptureDeviceInput, AVCaptureDeviceWatcher,
+    AVCaptureMultiCamSession, AVCapturePhotoCallback, AVCapturePhotoOutput, AVCaptureSession,
+    AVCaptureSessionInterruptionReason, AVCaptureSessionInterruptionWatcher,
+    AVCaptureSessionPreset, AVCaptureVideoCallback, AVCaptureVideoDataOutput, CapturedFrame,
+    PhotoResult, PixelBuffer, StreamEventCallback,
 };
 use nokhwa_core::{
     buffer::Buffer,
     error::NokhwaError,
     pixel_format::RgbFormat,
-    traits::CaptureTrait,
+    traits::{CaptureTrait, DeviceWatcher},
     types::{
-        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
-        FrameFormat, KnownCameraControl, RequestedFormat, RequestedFormatType, Resolution,
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, CameraPosition,
+        ControlValueSetter, DeviceEvent, FrameFormat, KnownCameraControl, PhotoCodec,
+        PhotoSettings, RequestedFormat, RequestedFormatType, Resolution, StreamEvent,
     },
 };
-#[cfg(target_os = "macos")]
-use std::{ffi::CString, sync::Arc};
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+use std::{
+    ffi::CString,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use std::{borrow::Cow, collections::HashMap};
 
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+const DISCONNECT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+const PHOTO_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// The backend struct that interfaces with V4L2.
 /// To see what this does, please see [`CaptureTrait`].
 /// # Quirks
-/// - While working with `iOS` is allowed, it is not officially supported and may not work.
+/// - On `iOS` and `iPadOS` this needs the `input-avfoundation-ios` feature. Open cameras by where they are with
+///   [`with_position()`](AVFoundationCaptureDevice::with_position), and let [`set_session_preset()`](AVFoundationCaptureDevice::set_session_preset)
+///   pick a format that suits the rest of the system. While the app is in the background the stream is
+///   [interrupted](AVFoundationCaptureDevice::interruption) and reading a frame fails, it resumes once the app is back.
 /// - You **must** call [`nokhwa_initialize`](crate::nokhwa_initialize) **before** doing anything with `AVFoundation`.
 /// - This only works on 64 bit platforms.
 /// - FPS adjustment does not work.
 /// - If permission has not been granted and you call `init()` it will error.
+/// - `420v` and `420f` formats are captured as [`FrameFormat::Nv12`] without conversion, the [`Buffer`] noting their [`ColorRange`](nokhwa_core::buffer::ColorRange).
+///   The 10 bit formats of capture cards are captured the same way, `v210` as [`FrameFormat::V210`], `x422`/`xf22` as [`FrameFormat::P210`]
+///   and `x420`/`xf20` as [`FrameFormat::P010`].
+/// - `x444`/`xf44` are captured as [`FrameFormat::P410`] and `v216` as [`FrameFormat::V216`]. ProRes formats of professional capture
+///   hardware are listed as [`FrameFormat::ProRes`], but `AVFoundation` decodes them, so their frames arrive uncompressed.
+/// - Some virtual cameras only deliver frames when their session picks the format. Open them with
+///   [`with_session_preset()`](AVFoundationCaptureDevice::with_session_preset), which leaves the device's format alone.
+/// - Focus, exposure and white balance are the lens position, exposure duration (in microseconds) and ISO (as [`Gain`](KnownCameraControl::Gain)), and white balance gains. Setting them
+///   locks focus, switches to custom exposure or locks white balance. Their modes, and controls without a [`KnownCameraControl`], are `Other`, see the bindings.
+/// - Continuity Cameras (iPhones) are listed while they are nearby, and reading a frame fails once one goes away. Their Desk View is
+///   a separate camera, see [`desk_view_camera()`](AVFoundationCaptureDevice::desk_view_camera).
+/// - Frames are only copied to the CPU by [`frame()`](CaptureTrait::frame) and [`frame_raw()`](CaptureTrait::frame_raw).
+///   [`frame_pixel_buffer()`](AVFoundationCaptureDevice::frame_pixel_buffer) hands out the `CVPixelBuffer` itself. Unread frames hold on to
+///   their pixel buffers, and the camera drops frames while it is out of them.
+/// - [`on_stream_event()`](CaptureTrait::on_stream_event) reports dropped frames and interruptions. macOS gives no reason for an
+///   interruption, other than another application using the camera.
+/// - Frame timestamps are on the host time clock (`mach_absolute_time()`, which [`Instant`](std::time::Instant) uses), and include
+///   the frame's duration.
+/// - [`take_photo()`](CaptureTrait::take_photo) uses `AVCapturePhotoOutput`, so photos may be larger than the video. It opens the stream
+///   for the photo if it is not open. Before macOS 13, photos are as large as the active format regardless of the maximum resolution.
+/// - Center Stage, Portrait and Studio Light are read-only `Other` controls the user sets in Control Center. Center Stage can be
+///   changed after [`request_center_stage()`](AVFoundationCaptureDevice::request_center_stage), and is system wide.
+/// - Frames carry the connection's rotation and mirroring as their [`FrameOrientation`](nokhwa_core::buffer::FrameOrientation). The
+///   rotation is `0` when `AVFoundation` rotated the frame itself. [`CameraInfo::position()`](nokhwa_core::types::CameraInfo::position)
+///   tells front facing cameras apart from external ones and Continuity Cameras.
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
-#[cfg(target_os = "macos")]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
 pub struct AVFoundationCaptureDevice {
     device: AVCaptureDevice,
     dev_input: Option<AVCaptureDeviceInput>,
     session: Option<AVCaptureSession>,
     data_out: Option<AVCaptureVideoDataOutput>,
     data_collect: Option<AVCaptureVideoCallback>,
+    photo_out: Option<AVCapturePhotoOutput>,
+    photo_collect: Option<AVCapturePhotoCallback>,
+    interruptions: Option<AVCaptureSessionInterruptionWatcher>,
+    session_preset: Option<AVCaptureSessionPreset>,
+    multi_cam: Option<AVCaptureMultiCamSession>,
+    events: StreamEventCallback,
     info: CameraInfo,
     buffer_name: CString,
     format: CameraFormat,
-    frame_buffer_receiver: Arc<Receiver<(Vec<u8>, FrameFormat)>>,
-    fbufsnd: Arc<Sender<(Vec<u8>, FrameFormat)>>,
+    frame_buffer_receiver: Arc<Receiver<CapturedFrame>>,
+    fbufsnd: Arc<Sender<CapturedFrame>>,
+    photo_receiver: Receiver<PhotoResult>,
+    photo_sender: Arc<Sender<PhotoResult>>,
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
 impl AVFoundationCaptureDevice {
     /// Creates a new capture device using the `AVFoundation` backend. Indexes are gives to devices by the OS, and usually numbered by order of discovery.
     ///
@@ -74,7 +144,30 @@ impl AVFoundationCaptureDevice {
             NokhwaError::OpenDeviceError("Cannot fulfill request".to_string(), req_fmt.to_string())
         })?;
         device.set_all(camera_fmt)?;
+        AVFoundationCaptureDevice::from_device(device, index, camera_fmt)
+    }
 
+    /// Creates a new capture device that negotiates its format through an [`AVCaptureSessionPreset`] instead of a [`CameraFormat`],
+    /// see [`set_session_preset()`](AVFoundationCaptureDevice::set_session_preset). The device's format is not touched until the stream
+    /// opens, which some virtual cameras need to behave.
+    /// # Errors
+    /// This function will error if the camera is currently busy or if `AVFoundation` can't read device information, or permission was not given by the user.
+    pub fn with_session_preset(
+        index: &CameraIndex,
+        preset: AVCaptureSessionPreset,
+    ) -> Result<Self, NokhwaError> {
+        let device = AVCaptureDevice::new(index)?;
+        let camera_fmt = device.active_format()?;
+        let mut camera = AVFoundationCaptureDevice::from_device(device, index, camera_fmt)?;
+        camera.session_preset = Some(preset);
+        Ok(camera)
+    }
+
+    fn from_device(
+        device: AVCaptureDevice,
+        index: &CameraIndex,
+        camera_fmt: CameraFormat,
+    ) -> Result<Self, NokhwaError> {
         let device_descriptor = device.info().clone();
         let buffername =
             CString::new(format!("{}_INDEX{}_", device_descriptor, index)).map_err(|why| {
@@ -85,17 +178,26 @@ impl AVFoundationCaptureDevice {
             })?;
 
         let (send, recv) = flume::unbounded();
+        let (photo_send, photo_recv) = flume::unbounded();
         Ok(AVFoundationCaptureDevice {
             device,
             dev_input: None,
             session: None,
             data_out: None,
             data_collect: None,
+            photo_out: None,
+            photo_collect: None,
+            interruptions: None,
+            session_preset: None,
+            multi_cam: None,
+            events: Arc::new(Mutex::new(None)),
             info: device_descriptor,
             buffer_name: buffername,
             format: camera_fmt,
             frame_buffer_receiver: Arc::new(recv),
             fbufsnd: Arc::new(send),
+            photo_receiver: photo_recv,
+            photo_sender: Arc::new(photo_send),
         })
     }
 
@@ -118,9 +220,149 @@ impl AVFoundationCaptureDevice {
             RequestedFormat::new::<RgbFormat>(RequestedFormatType::Exact(camera_format)),
         )
     }
+
+    /// Creates a new capture device of the first camera at `position`, e.g. the front facing camera of an iPhone or iPad.
+    /// # Errors
+    /// If there is no camera at `position`, or opening it fails as in [`new()`](AVFoundationCaptureDevice::new), this will error.
+    pub fn with_position(
+        position: CameraPosition,
+        req_fmt: RequestedFormat,
+    ) -> Result<Self, NokhwaError> {
+        let info = query_avfoundation()?
+            .into_iter()
+            .find(|info| info.position() == position)
+            .ok_or_else(|| {
+                NokhwaError::OpenDeviceError(format!("{position:?}"), "Not Found".to_string())
+            })?;
+        AVFoundationCaptureDevice::new(info.index(), req_fmt)
+    }
+
+    /// Sets the [`AVCaptureSessionPreset`] the stream is opened with, which picks the camera's format instead of its [`CameraFormat`].
+    /// `None` goes back to the [`CameraFormat`], as does setting a format. This takes effect the next time the stream is opened, after
+    /// which [`camera_format()`](CaptureTrait::camera_format) is the format the preset picked.
+    pub fn set_session_preset(&mut self, preset: Option<AVCaptureSessionPreset>) {
+        self.session_preset = preset;
+    }
+
+    /// Gets the [`AVCaptureSessionPreset`] the stream is opened with, `None` if it is opened with the [`CameraFormat`].
+    #[must_use]
+    pub fn session_preset(&self) -> Option<AVCaptureSessionPreset> {
+        self.session_preset
+    }
+
+    /// Gets why the stream is interrupted, e.g. because the app moved to the background on iOS, or `None` if it is not.
+    /// The stream resumes by itself once the interruption ends.
+    #[must_use]
+    pub fn interruption(&self) -> Option<AVCaptureSessionInterruptionReason> {
+        self.interruptions
+            .as_ref()
+            .and_then(AVCaptureSessionInterruptionWatcher::interruption)
+    }
+
+    /// Returns `true` if this camera is an iPhone used through Continuity Camera.
+    #[must_use]
+    pub fn is_continuity_camera(&self) -> bool {
+        self.device.is_continuity_camera()
+    }
+
+    /// Gets the Desk View camera that shows the desk below this Continuity Camera, if it has one. Open it with the
+    /// [`CameraIndex`] of the returned [`CameraInfo`], as its own [`AVFoundationCaptureDevice`], or capture both together with
+    /// [`AVFoundationCameraRig::with_desk_view()`]. It is also listed in [`related_devices()`](CameraInfo::related_devices).
+    #[must_use]
+    pub fn desk_view_camera(&self) -> Option<CameraInfo> {
+        self.device.desk_view_camera()
+    }
+
+    /// Shares control of Center Stage with the user and turns it on or off. Afterwards its control is no longer read-only.
+    /// # Errors
+    /// If the OS is older than macOS 12.3 or the active format does not support Center Stage, this will error.
+    pub fn request_center_stage(&mut self, enabled: bool) -> Result<(), NokhwaError> {
+        self.device.request_center_stage(enabled)
+    }
+
+    /// Gets the next frame as the retained [`PixelBuffer`] `AVFoundation` captured it into, without copying it to the CPU. Use this
+    /// to hand frames to Metal or `CoreImage`, e.g. through its [`io_surface()`](PixelBuffer::io_surface).
+    /// # Errors
+    /// If the stream is not open, the device went away, or the frame is compressed (e.g. MJPEG) and has no pixel buffer, this will error.
+    pub fn frame_pixel_buffer(&mut self) -> Result<PixelBuffer, NokhwaError> {
+        self.next_frame()?.into_pixel_buffer().ok_or_else(|| {
+            NokhwaError::ReadFrameError("Compressed frames have no pixel buffer".to_string())
+        })
+    }
+
+    fn capture_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        let (output, callback) = match (&self.photo_out, &self.photo_collect) {
+            (Some(output), Some(callback)) => (output, callback),
+            _ => {
+                return Err(NokhwaError::UnsupportedOperationError(
+                    ApiBackend::AVFoundation,
+                ))
+            }
+        };
+        // drop photos of earlier calls that timed out
+        let _ = self.photo_receiver.drain();
+        output.capture(&self.device, settings, callback)?;
+        let photo = self
+            .photo_receiver
+            .recv_timeout(PHOTO_TIMEOUT)
+            .map_err(|_| NokhwaError::TimeoutError(PHOTO_TIMEOUT))??;
+        let frame_format = match settings.codec() {
+            PhotoCodec::Uncompressed => FrameFormat::Nv12,
+            PhotoCodec::Jpeg => FrameFormat::MJpeg,
+            PhotoCodec::Heif => FrameFormat::Heif,
+        };
+        Ok(self.with_frame_info(photo.frame(), photo.to_buffer(frame_format)))
+    }
+
+    fn with_frame_info(&self, frame: &CapturedFrame, buffer: Buffer) -> Buffer {
+        let timestamp = self
+            .session
+            .as_ref()
+            .and_then(|session| frame.timestamp(session.clock()));
+        let buffer = match timestamp {
+            Some(timestamp) => buffer.with_timestamp(timestamp),
+            None => buffer,
+        };
+        let buffer = match frame.sequence() {
+            Some(sequence) => buffer.with_sequence(sequence),
+            None => buffer,
+        };
+        match frame.orientation() {
+            Some(orientation) => buffer.with_orientation(orientation),
+            None => buffer,
+        }
+    }
+
+    fn next_frame(&self) -> Result<CapturedFrame, NokhwaError> {
+        // a device that disappears (e.g. a Continuity Camera) stops sending frames without closing the channel
+        loop {
+            match self
+                .frame_buffer_receiver
+                .recv_timeout(DISCONNECT_POLL_INTERVAL)
+            {
+                Ok(frame) => return Ok(frame),
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(reason) = self.interruption() {
+                        return Err(NokhwaError::ReadFrameError(format!(
+                            "Capture session interrupted: {reason:?}"
+                        )));
+                    }
+                    if !self.device.is_connected() {
+                        return Err(NokhwaError::ReadFrameError(
+                            "Device disconnected".to_string(),
+                        ));
+                    }
+                }
+                Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
+            }
+        }
+    }
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
 impl CaptureTrait for AVFoundationCaptureDevice {
     fn backend(&self) -> ApiBackend {
         ApiBackend::AVFoundation
@@ -142,6 +384,7 @@ impl CaptureTrait for AVFoundationCaptureDevice {
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
         self.device.set_all(new_fmt)?;
         self.format = new_fmt;
+        self.session_preset = None;
         Ok(())
     }
 
@@ -238,23 +481,93 @@ impl CaptureTrait for AVFoundationCaptureDevice {
         res
     }
 
+    fn on_stream_event(
+        &mut self,
+        callback: Box<dyn FnMut(StreamEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        let mut events = self
+            .events
+            .lock()
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "StreamEventCallback".to_string(),
+                value: String::new(),
+                error: why.to_string(),
+            })?;
+        *events = Some(callback);
+        Ok(())
+    }
+
+    fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+        Ok(self
+            .data_collect
+            .as_ref()
+            .map_or(0, AVCaptureVideoCallback::dropped_frames))
+    }
+
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
         self.refresh_camera_format()?;
 
         let input = AVCaptureDeviceInput::new(&self.device)?;
+        let bufname = &self.buffer_name;
+        let videocallback = AVCaptureVideoCallback::new(bufname, &self.fbufsnd, &self.events)?;
+        let output = AVCaptureVideoDataOutput::new();
+
+        // cameras of a rig share its session, which the first camera to open starts
+        if let Some(multi_cam) = &self.multi_cam {
+            let session = multi_cam.session();
+            session.begin_configuration();
+            let added = self.device.set_all(self.format).and_then(|()| {
+                output.set_pixel_format(self.device.active_pixel_format());
+                output.add_delegate(&videocallback)?;
+                multi_cam.add_camera(&input, &output)
+            });
+            session.commit_configuration();
+            added?;
+            if !session.is_running() {
+                session.start()?;
+            }
+            self.interruptions = Some(AVCaptureSessionInterruptionWatcher::new(
+                &session,
+                &self.events,
+            ));
+            self.dev_input = Some(input);
+            self.session = Some(session);
+            self.data_collect = Some(videocallback);
+            self.data_out = Some(output);
+            return Ok(());
+        }
+
         let session = AVCaptureSession::new();
         session.begin_configuration();
         session.add_input(&input)?;
-
-        self.device.set_all(self.format)?; // hurr durr im an apple api and im fucking dumb hurr durr
-
-        let bufname = &self.buffer_name;
-        let videocallback = AVCaptureVideoCallback::new(bufname, &self.fbufsnd)?;
-        let output = AVCaptureVideoDataOutput::new();
+        match self.session_preset {
+            // the preset picks the format when the configuration is committed, and frames come in the output's default format
+            Some(preset) => session.set_session_preset(preset)?,
+            None => {
+                // hurr durr im an apple api and im fucking dumb hurr durr
+                self.device.set_all(self.format)?;
+                // otherwise frames are converted to the output's default format
+                output.set_pixel_format(self.device.active_pixel_format());
+            }
+        }
         output.add_delegate(&videocallback)?;
         session.add_output(&output)?;
+        // not every format can take photos, the stream works without them
+        let photo_output = AVCapturePhotoOutput::new();
+        if session.can_add_photo_output(&photo_output) {
+            session.add_photo_output(&photo_output)?;
+            self.photo_out = Some(photo_output);
+            self.photo_collect = Some(AVCapturePhotoCallback::new(&self.photo_sender));
+        }
         session.commit_configuration();
+        self.interruptions = Some(AVCaptureSessionInterruptionWatcher::new(
+            &session,
+            &self.events,
+        ));
         session.start()?;
+        if self.session_preset.is_some() {
+            self.refresh_camera_format()?;
+        }
 
         self.dev_input = Some(input);
         self.session = Some(session);
@@ -280,18 +593,26 @@ impl CaptureTrait for AVFoundationCaptureDevice {
     fn frame(&mut self) -> Result<Buffer, NokhwaError> {
         self.refresh_camera_format()?;
         let cfmt = self.camera_format();
-        let b = self.frame_raw()?;
-        let buffer = Buffer::new(cfmt.resolution(), b.as_ref(), cfmt.format());
+        let frame = self.next_frame()?;
+        let buffer =
+            self.with_frame_info(&frame, frame.to_buffer(cfmt.resolution(), cfmt.format()));
         let _ = self.frame_buffer_receiver.drain();
         Ok(buffer)
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        let result = match self.frame_buffer_receiver.recv() {
-            Ok(recv) => Ok(Cow::from(recv.0)),
-            Err(why) => Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-        result
+        self.next_frame().map(|frame| Cow::from(frame.into_data()))
+    }
+
+    fn take_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        // the photo output only takes photos while its session runs
+        if self.is_stream_open() {
+            return self.capture_photo(settings);
+        }
+        self.open_stream()?;
+        let photo = self.capture_photo(settings);
+        self.stop_stream()?;
+        photo
     }
 
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
@@ -330,20 +651,32 @@ impl CaptureTrait for AVFoundationCaptureDevice {
         };
 
         session.remove_output(output);
+        if let Some(photo_output) = &self.photo_out {
+            session.remove_photo_output(photo_output);
+        }
         session.remove_input(input);
-        session.stop();
+        // the rig stops a shared session
+        if self.multi_cam.is_none() {
+            session.stop();
+        }
 
         self.frame_buffer_receiver.try_iter();
         self.dev_input = None;
         self.session = None;
         self.data_collect = None;
         self.data_out = None;
+        self.photo_out = None;
+        self.photo_collect = None;
+        self.interruptions = None;
 
         Ok(())
     }
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
 impl Drop for AVFoundationCaptureDevice {
     fn drop(&mut self) {
         if self.stop_stream().is_err() {}
@@ -351,6 +684,196 @@ impl Drop for AVFoundationCaptureDevice {
     }
 }
 
+/// Several `AVFoundation` cameras streaming at once, e.g. the front and back cameras of an iPad, or the built-in camera of a Mac and a
+/// Continuity Camera.
+/// # Quirks
+/// - Where the device supports `AVCaptureMultiCamSession` (iOS and iPadOS 13 and up), the cameras share one session and their frames
+///   one clock, see [`is_synchronized()`](AVFoundationCameraRig::is_synchronized). Elsewhere each camera keeps a session of its own,
+///   and their frames' timestamps are still on the same host time clock.
+/// - In a shared session, the active format of every camera must support multi-cam capture, and together they must fit in the
+///   hardware's bandwidth. Opening the streams fails otherwise. Cameras in a shared session do not take photos.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+pub struct AVFoundationCameraRig {
+    session: Option<AVCaptureMultiCamSession>,
+    cameras: Vec<AVFoundationCaptureDevice>,
+}
+
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+impl AVFoundationCameraRig {
+    /// Groups `cameras` into a rig, stopping their streams first.
+    /// # Errors
+    /// If a stream can not be stopped, or the shared session can not be created, this will error.
+    pub fn new(mut cameras: Vec<AVFoundationCaptureDevice>) -> Result<Self, NokhwaError> {
+        let session = if AVCaptureMultiCamSession::is_supported() {
+            Some(AVCaptureMultiCamSession::new()?)
+        } else {
+            None
+        };
+        for camera in &mut cameras {
+            camera.stop_stream()?;
+            camera.multi_cam = session
+                .as_ref()
+                .map(|session| AVCaptureMultiCamSession::from(session.inner()));
+        }
+        Ok(AVFoundationCameraRig { session, cameras })
+    }
+
+    /// Groups a Continuity Camera with its Desk View camera, opened with `req_fmt`, to capture the face and desk views together.
+    /// The Desk View camera comes second in [`cameras()`](AVFoundationCameraRig::cameras).
+    /// # Errors
+    /// If `camera` has no Desk View camera, or it can not be opened, this will error.
+    pub fn with_desk_view(
+        camera: AVFoundationCaptureDevice,
+        req_fmt: RequestedFormat,
+    ) -> Result<Self, NokhwaError> {
+        let desk_view = camera.desk_view_camera().ok_or_else(|| {
+            NokhwaError::OpenDeviceError(
+                camera.camera_info().human_name(),
+                "No Desk View camera".to_string(),
+            )
+        })?;
+        let desk_view = AVFoundationCaptureDevice::new(desk_view.index(), req_fmt)?;
+        AVFoundationCameraRig::new(vec![camera, desk_view])
+    }
+
+    /// Returns `true` if the cameras share one session, so their frames are captured on one clock.
+    #[must_use]
+    pub fn is_synchronized(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Gets the cameras of the rig, in the order they were given.
+    #[must_use]
+    pub fn cameras(&self) -> &[AVFoundationCaptureDevice] {
+        &self.cameras
+    }
+
+    /// Gets the cameras of the rig mutably, e.g. to change their controls.
+    pub fn cameras_mut(&mut self) -> &mut [AVFoundationCaptureDevice] {
+        &mut self.cameras
+    }
+
+    /// Opens the stream of every camera.
+    /// # Errors
+    /// If a stream fails to open, or the cameras together take more bandwidth than the hardware has, this will error and stop them.
+    pub fn open_streams(&mut self) -> Result<(), NokhwaError> {
+        let opened = self
+            .cameras
+            .iter_mut()
+            .try_for_each(CaptureTrait::open_stream);
+        let over_budget = self
+            .session
+            .as_ref()
+            .map_or(false, |session| session.hardware_cost() > 1.0);
+        match opened {
+            Ok(()) if over_budget => {
+                self.stop_streams()?;
+                Err(NokhwaError::OpenStreamError(
+                    "The cameras together take more bandwidth than the hardware has".to_string(),
+                ))
+            }
+            Ok(()) => Ok(()),
+            Err(why) => {
+                self.stop_streams()?;
+                Err(why)
+            }
+        }
+    }
+
+    /// Gets the next frame of every camera, in order.
+    /// # Errors
+    /// If reading a frame of any camera fails, this will error.
+    pub fn frames(&mut self) -> Result<Vec<Buffer>, NokhwaError> {
+        self.cameras.iter_mut().map(CaptureTrait::frame).collect()
+    }
+
+    /// Stops the stream of every camera.
+    /// # Errors
+    /// If a stream fails to stop, this will error.
+    pub fn stop_streams(&mut self) -> Result<(), NokhwaError> {
+        for camera in &mut self.cameras {
+            camera.stop_stream()?;
+        }
+        if let Some(session) = &self.session {
+            session.session().stop();
+        }
+        Ok(())
+    }
+
+    /// Stops the streams and takes the cameras out of the rig, after which each opens a session of its own again.
+    /// # Errors
+    /// If a stream fails to stop, this will error.
+    pub fn into_cameras(mut self) -> Result<Vec<AVFoundationCaptureDevice>, NokhwaError> {
+        self.stop_streams()?;
+        for camera in &mut self.cameras {
+            camera.multi_cam = None;
+        }
+        Ok(self.cameras)
+    }
+}
+
+/// Watches for cameras being plugged in or unplugged using `AVFoundation`. To see what this does, please see [`DeviceWatcher`].
+/// # Quirks
+/// - Events carry a [`CameraIndex::String`] of the device's unique ID, which opens it with [`AVFoundationCaptureDevice::new()`].
+/// - `AVFoundation` only posts these notifications while the application's main run loop runs, which GUI applications do anyway.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+#[derive(Default)]
+pub struct AVFoundationDeviceWatcher {
+    watcher: Option<AVCaptureDeviceWatcher>,
+}
+
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+impl AVFoundationDeviceWatcher {
+    /// Creates a new, stopped, [`AVFoundationDeviceWatcher`].
+    #[must_use]
+    pub fn new() -> Self {
+        AVFoundationDeviceWatcher::default()
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+))]
+impl DeviceWatcher for AVFoundationDeviceWatcher {
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::AVFoundation
+    }
+
+    fn start(
+        &mut self,
+        callback: Box<dyn FnMut(DeviceEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        // stop the old observers first, so no event goes to both callbacks
+        self.watcher = None;
+        self.watcher = Some(AVCaptureDeviceWatcher::new(callback));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), NokhwaError> {
+        self.watcher = None;
+        Ok(())
+    }
+
+    fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+}
+
 /// The backend struct that interfaces with V4L2.
 /// To see what this does, please see [`CaptureTrait`].
 /// # Quirks
@@ -360,10 +883,16 @@ impl Drop for AVFoundationCaptureDevice {
 /// - FPS adjustment does not work.
 /// - If permission has not been granted and you call `init()` it will error.
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
 pub struct AVFoundationCaptureDevice {}
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
 #[allow(unused_variables)]
 #[allow(unreachable_code)]
 impl AVFoundationCaptureDevice {
@@ -393,7 +922,10 @@ impl AVFoundationCaptureDevice {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
 #[allow(unreachable_code)]
 impl CaptureTrait for AVFoundationCaptureDevice {
     fn backend(&self) -> ApiBackend {
@@ -488,10 +1020,63 @@ impl CaptureTrait for AVFoundationCaptureDevice {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
 #[allow(unreachable_code)]
 impl Drop for AVFoundationCaptureDevice {
     fn drop(&mut self) {
         todo!()
     }
 }
+
+/// Watches for cameras being plugged in or unplugged using `AVFoundation`. To see what this does, please see [`DeviceWatcher`].
+/// # Quirks
+/// - Events carry a [`CameraIndex::String`] of the device's unique ID, which opens it with [`AVFoundationCaptureDevice::new()`].
+/// - `AVFoundation` only posts these notifications while the application's main run loop runs, which GUI applications do anyway.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
+#[derive(Default)]
+pub struct AVFoundationDeviceWatcher {}
+
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
+impl AVFoundationDeviceWatcher {
+    /// Creates a new, stopped, [`AVFoundationDeviceWatcher`].
+    #[must_use]
+    pub fn new() -> Self {
+        AVFoundationDeviceWatcher::default()
+    }
+}
+
+#[cfg(not(any(
+    target_os = "macos",
+    all(target_os = "ios", feature = "input-avfoundation-ios")
+)))]
+#[allow(unused_variables)]
+impl DeviceWatcher for AVFoundationDeviceWatcher {
+    fn backend(&self) -> ApiBackend {
+        todo!()
+    }
+
+    fn start(
+        &mut self,
+        callback: Box<dyn FnMut(DeviceEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        todo!()
+    }
+
+    fn stop(&mut self) -> Result<(), NokhwaError> {
+        todo!()
+    }
+
+    fn is_watching(&self) -> bool {
+        todo!()
+    }
+}
@@ -181,6 +181,8 @@ fn control_to_str(control: KnownCameraControl) -> &'static str {
         KnownCameraControl::Exposure => "exposureMode",
         KnownCameraControl::Iris => "focusDistance",
         KnownCameraControl::Focus => "focusMode",
+        KnownCameraControl::Torch => "torch",
+        KnownCameraControl::JpegQuality | KnownCameraControl::Privacy => "",
         KnownCameraControl::Other(u) => match u {
             0 => "frameRate",
             1 => "width",
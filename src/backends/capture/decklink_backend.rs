@@ -0,0 +1,521 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use nokhwa_bindings_decklink::{
+    DeckLinkConnector, DeckLinkDevice, DeckLinkDisplayMode, DeckLinkFrame, BMD_FORMAT_10BIT_YUV,
+    BMD_FORMAT_8BIT_YUV,
+};
+use nokhwa_core::{
+    buffer::{Buffer, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlMenuEntry,
+        ControlValueDescription, ControlValueSetter, FrameRate, KnownCameraControl,
+        KnownCameraControlFlag, Resolution,
+    },
+};
+use std::{borrow::Cow, collections::HashMap, time::Duration};
+
+/// How long [`frame()`](CaptureTrait::frame) waits for the card.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The input connector the card captures from. A [`ControlValueDescription::Menu`] of the card's connectors, keyed by their `BMDVideoConnection` bit.
+pub const DECKLINK_INPUT_CONNECTOR: KnownCameraControl = KnownCameraControl::Other(0x646c_0001);
+/// Whether there is a signal on the input, a read-only [`ControlValueDescription::Boolean`].
+pub const DECKLINK_SIGNAL_PRESENT: KnownCameraControl = KnownCameraControl::Other(0x646c_0002);
+
+// the pixel formats nokhwa has a `FrameFormat` for
+const PIXEL_FORMATS: [(u32, FrameFormat); 2] = [
+    (BMD_FORMAT_8BIT_YUV, FrameFormat::Uyv422),
+    (BMD_FORMAT_10BIT_YUV, FrameFormat::V210),
+];
+
+fn frameformat_to_pixel_format(frame_format: FrameFormat) -> Option<u32> {
+    PIXEL_FORMATS
+        .into_iter()
+        .find(|(_, format)| *format == frame_format)
+        .map(|(pixel_format, _)| pixel_format)
+}
+
+fn pixel_format_to_frameformat(pixel_format: u32) -> Option<FrameFormat> {
+    PIXEL_FORMATS
+        .into_iter()
+        .find(|(pixel, _)| *pixel == pixel_format)
+        .map(|(_, format)| format)
+}
+
+fn mode_frame_rate(mode: &DeckLinkDisplayMode) -> Option<FrameRate> {
+    let duration = mode.frame_duration();
+    let scale = mode.time_scale();
+    if duration != 0 && scale.checked_rem(duration) == Some(0) {
+        return FrameRate::new_integer(scale / duration).ok();
+    }
+    // e.g. 60000/1001 for 59.94
+    FrameRate::new_fraction(u16::try_from(scale).ok()?, u16::try_from(duration).ok()?).ok()
+}
+
+fn mode_resolution(mode: &DeckLinkDisplayMode) -> Resolution {
+    Resolution::new(mode.width(), mode.height())
+}
+
+/// Lists the `DeckLink` devices that have an input.
+///
+/// The [`CameraInfo::misc()`] of each device is its persistent ID, if the card has one.
+/// # Errors
+/// This does not currently error, the signature matches the other backends.
+#[allow(clippy::unnecessary_wraps)]
+pub fn query_decklink() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Ok((0..DeckLinkDevice::count())
+        .filter_map(|index| {
+            let device = DeckLinkDevice::open(index).ok()?;
+            Some(device_info(&device, &CameraIndex::Index(index)))
+        })
+        .collect())
+}
+
+fn device_info(device: &DeckLinkDevice, index: &CameraIndex) -> CameraInfo {
+    let connectors = device
+        .connectors()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ");
+    CameraInfo::new(
+        &device.name(),
+        &format!("{} ({connectors})", device.model()),
+        &device
+            .persistent_id()
+            .map(|id| format!("{id:x}"))
+            .unwrap_or_default(),
+        index,
+    )
+}
+
+/// The backend struct that interfaces with Blackmagic `DeckLink` SDI/HDMI capture cards through the `DeckLink` SDK.
+/// To see what this does, please see [`CaptureTrait`].
+///
+/// The input connector is picked with [`set_input_connector()`](DeckLinkCaptureDevice::set_input_connector) or the [`DECKLINK_INPUT_CONNECTOR`] control.
+/// # Quirks
+/// - Needs the Blackmagic Desktop Video driver, and the `DeckLink` SDK to build. See `nokhwa-bindings-decklink`.
+/// - The formats are the card's display modes in [`FrameFormat::Uyv422`] and [`FrameFormat::V210`] (10 bit). `V210` rows are padded to 48 pixels,
+///   the [`Buffer`]'s plane has the stride.
+/// - By default, cards that can detect the input's format switch to it on their own, and the [`CameraFormat`] follows. Turn it off with
+///   [`set_format_detection()`](DeckLinkCaptureDevice::set_format_detection).
+/// - Without a signal the card keeps handing out (black) frames. Check [`signal_present()`](DeckLinkCaptureDevice::signal_present).
+/// - Frame timestamps are the card's hardware reference clock.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-decklink")))]
+pub struct DeckLinkCaptureDevice {
+    camera_format: Option<CameraFormat>,
+    camera_info: CameraInfo,
+    modes: Vec<DeckLinkDisplayMode>,
+    detect_format: bool,
+    streaming: bool,
+    device: DeckLinkDevice,
+}
+
+impl DeckLinkCaptureDevice {
+    /// Creates a new capture device using the `DeckLink` backend.
+    ///
+    /// `index` is either an index into [`query_decklink()`], or a device's display name or persistent ID.
+    /// # Errors
+    /// If the device does not exist, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let device = match index {
+            CameraIndex::Index(idx) => DeckLinkDevice::open(*idx)?,
+            CameraIndex::String(name) => (0..DeckLinkDevice::count())
+                .filter_map(|idx| DeckLinkDevice::open(idx).ok())
+                .find(|device| {
+                    device.name() == *name
+                        || device.persistent_id().map(|id| format!("{id:x}")).as_ref() == Some(name)
+                })
+                .ok_or_else(|| {
+                    NokhwaError::OpenDeviceError(
+                        name.clone(),
+                        "No such DeckLink device".to_string(),
+                    )
+                })?,
+        };
+
+        Ok(DeckLinkCaptureDevice {
+            camera_format: None,
+            camera_info: device_info(&device, index),
+            modes: device.display_modes(),
+            detect_format: true,
+            streaming: false,
+            device,
+        })
+    }
+
+    /// Gets the underlying [`DeckLinkDevice`].
+    #[must_use]
+    pub fn decklink_device(&self) -> &DeckLinkDevice {
+        &self.device
+    }
+
+    /// Gets the display modes of the card.
+    #[must_use]
+    pub fn display_modes(&self) -> &[DeckLinkDisplayMode] {
+        &self.modes
+    }
+
+    /// Gets the display mode the card captures in, which follows the input if format detection is on.
+    #[must_use]
+    pub fn display_mode(&self) -> Option<&DeckLinkDisplayMode> {
+        let current = self.device.current_mode();
+        self.modes.iter().find(|mode| mode.id() == current)
+    }
+
+    /// Gets the input connectors of the card.
+    #[must_use]
+    pub fn input_connectors(&self) -> Vec<DeckLinkConnector> {
+        self.device.connectors()
+    }
+
+    /// Gets the input connector the card captures from.
+    #[must_use]
+    pub fn input_connector(&self) -> Option<DeckLinkConnector> {
+        self.device.connector()
+    }
+
+    /// Sets the input connector the card captures from.
+    /// # Errors
+    /// If the card does not have the connector, this will error.
+    pub fn set_input_connector(&mut self, connector: DeckLinkConnector) -> Result<(), NokhwaError> {
+        if !self.device.connectors().contains(&connector) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Input Connector".to_string(),
+                value: connector.to_string(),
+                error: "The card does not have this connector".to_string(),
+            });
+        }
+        self.device.set_connector(connector)
+    }
+
+    /// Gets whether there was a signal on the input in the last frame.
+    #[must_use]
+    pub fn signal_present(&self) -> bool {
+        self.device.signal_present()
+    }
+
+    /// Gets whether the card switches to the input's format on its own.
+    #[must_use]
+    pub fn format_detection(&self) -> bool {
+        self.detect_format
+    }
+
+    /// Sets whether the card switches to the input's format on its own. Takes effect when the stream is next opened.
+    pub fn set_format_detection(&mut self, detect_format: bool) {
+        self.detect_format = detect_format;
+    }
+
+    fn find_mode(&self, camera_format: CameraFormat) -> Option<&DeckLinkDisplayMode> {
+        self.modes.iter().find(|mode| {
+            mode_resolution(mode) == camera_format.resolution()
+                && mode_frame_rate(mode) == Some(camera_format.frame_rate())
+        })
+    }
+
+    fn start(&mut self, camera_format: CameraFormat) -> Result<(), NokhwaError> {
+        let unsupported = |why: &str| NokhwaError::SetPropertyError {
+            property: "CameraFormat".to_string(),
+            value: camera_format.to_string(),
+            error: why.to_string(),
+        };
+        let pixel_format = frameformat_to_pixel_format(camera_format.format())
+            .ok_or_else(|| unsupported("The card has no such pixel format"))?;
+        let mode = self
+            .find_mode(camera_format)
+            .ok_or_else(|| unsupported("The card has no such display mode"))?
+            .id();
+        if !self.device.supports(mode, pixel_format) {
+            return Err(unsupported("The card can not capture this"));
+        }
+        self.device.start(mode, pixel_format, self.detect_format)
+    }
+
+    // the card may have followed the input to another mode
+    fn follow_mode(&mut self, frame: &DeckLinkFrame) {
+        let Some(mode) = self
+            .modes
+            .iter()
+            .find(|mode| mode.id() == frame.display_mode())
+        else {
+            return;
+        };
+        if let (Some(camera_format), Some(frame_rate)) =
+            (&mut self.camera_format, mode_frame_rate(mode))
+        {
+            camera_format.set_resolution(mode_resolution(mode));
+            camera_format.set_frame_rate(frame_rate);
+        }
+    }
+}
+
+impl Backend for DeckLinkCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::DeckLink;
+}
+
+impl CaptureTrait for DeckLinkCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // the first mode the card can capture, format detection takes over once the stream opens
+        if self.camera_format.is_none() {
+            self.camera_format = self.compatible_camera_formats()?.first().copied();
+        }
+        Ok(())
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::DeckLink
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        if let (Some(mode), Some(camera_format)) = (self.display_mode(), self.camera_format) {
+            if let Some(frame_rate) = mode_frame_rate(mode) {
+                self.camera_format = Some(CameraFormat::new(
+                    mode_resolution(mode),
+                    camera_format.format(),
+                    frame_rate,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if self.streaming {
+            self.start(new_fmt)?;
+        } else if self.find_mode(new_fmt).is_none() {
+            return Err(NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "The card has no such display mode".to_string(),
+            });
+        }
+        self.camera_format = Some(new_fmt);
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let Some(pixel_format) = frameformat_to_pixel_format(fourcc) else {
+            return Ok(HashMap::new());
+        };
+        let mut resolution_map: HashMap<Resolution, Vec<FrameRate>> = HashMap::new();
+        for mode in &self.modes {
+            if let Some(frame_rate) = mode_frame_rate(mode) {
+                if self.device.supports(mode.id(), pixel_format) {
+                    resolution_map
+                        .entry(mode_resolution(mode))
+                        .or_default()
+                        .push(frame_rate);
+                }
+            }
+        }
+        Ok(resolution_map)
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Ok(PIXEL_FORMATS
+            .into_iter()
+            .filter(|(pixel_format, _)| {
+                self.modes
+                    .iter()
+                    .any(|mode| self.device.supports(mode.id(), *pixel_format))
+            })
+            .map(|(_, format)| format)
+            .collect())
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::Uyv422, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        match control {
+            DECKLINK_INPUT_CONNECTOR => Ok(CameraControl::new(
+                control,
+                "Input Connector".to_string(),
+                ControlValueDescription::Menu {
+                    value: self.input_connector().map_or(0, DeckLinkConnector::bit),
+                    items: self
+                        .input_connectors()
+                        .into_iter()
+                        .map(|connector| {
+                            (
+                                connector.bit(),
+                                ControlMenuEntry::Name(connector.to_string()),
+                            )
+                        })
+                        .collect(),
+                    default: DeckLinkConnector::Sdi.bit(),
+                },
+                vec![],
+                true,
+            )),
+            DECKLINK_SIGNAL_PRESENT => Ok(CameraControl::new(
+                control,
+                "Signal Present".to_string(),
+                ControlValueDescription::Boolean {
+                    value: self.signal_present(),
+                    default: false,
+                },
+                vec![
+                    KnownCameraControlFlag::ReadOnly,
+                    KnownCameraControlFlag::Volatile,
+                ],
+                true,
+            )),
+            _ => Err(NokhwaError::GetPropertyError {
+                property: control.to_string(),
+                error: "DeckLink cards do not have this control".to_string(),
+            }),
+        }
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        [DECKLINK_INPUT_CONNECTOR, DECKLINK_SIGNAL_PRESENT]
+            .into_iter()
+            .map(|control| self.camera_control(control))
+            .collect()
+    }
+
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let set_error = |why: &str| NokhwaError::SetPropertyError {
+            property: id.to_string(),
+            value: value.to_string(),
+            error: why.to_string(),
+        };
+        match (id, &value) {
+            (
+                DECKLINK_INPUT_CONNECTOR,
+                ControlValueSetter::Integer(bit) | ControlValueSetter::EnumValue(bit),
+            ) => {
+                let connector = DeckLinkConnector::from_bit(*bit)
+                    .ok_or_else(|| set_error("No such connector"))?;
+                self.set_input_connector(connector)
+            }
+            (DECKLINK_SIGNAL_PRESENT, _) => Err(set_error("Read only")),
+            _ => Err(set_error("unsupported control or value")),
+        }
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.streaming {
+            return Ok(());
+        }
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        self.start(camera_format)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        self.streaming = true;
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.streaming
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        if !self.streaming {
+            return Err(NokhwaError::ReadFrameError(
+                "Stream Not Started".to_string(),
+            ));
+        }
+        let frame = self.device.wait_frame(FRAME_TIMEOUT)?;
+        self.follow_mode(&frame);
+
+        let format = pixel_format_to_frameformat(frame.pixel_format()).ok_or_else(|| {
+            NokhwaError::ReadFrameError(format!(
+                "Unknown pixel format {:#010x}",
+                frame.pixel_format()
+            ))
+        })?;
+        let mut buffer = Buffer::new(
+            Resolution::new(frame.width(), frame.height()),
+            frame.data(),
+            format,
+        )
+        .with_strides(&[frame.row_bytes()])
+        .with_sequence(frame.sequence());
+        if let Some(time) = frame.hardware_time() {
+            let mut timestamp = FrameTimestamp::new(time, TimestampClock::Unknown);
+            if let Some(duration) = frame.hardware_duration() {
+                timestamp = timestamp.with_duration(duration);
+            }
+            buffer = buffer.with_timestamp(timestamp);
+        }
+        Ok(buffer)
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        if !self.streaming {
+            return Err(NokhwaError::ReadFrameError(
+                "Stream Not Started".to_string(),
+            ));
+        }
+        let frame = self.device.wait_frame(FRAME_TIMEOUT)?;
+        self.follow_mode(&frame);
+        Ok(Cow::Owned(frame.into_data()))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.device.stop();
+        self.streaming = false;
+        Ok(())
+    }
+}
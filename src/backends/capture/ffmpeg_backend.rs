@@ -0,0 +1,614 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use ffmpeg_next::{
+    codec::{self, Id as CodecId},
+    decoder,
+    device::{self, input as device_input},
+    ffi::{avdevice_free_list_devices, avdevice_list_input_sources, AVDeviceInfoList},
+    format::{self, context::Input, Pixel},
+    media,
+    software::scaling::{context::Context as Scaler, flag::Flags},
+    util::{error::EAGAIN, frame::Video as VideoFrame},
+    Dictionary, Error as FfmpegError, Format, Packet, Rational,
+};
+use nokhwa_core::{
+    buffer::{Buffer, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
+        FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use std::{borrow::Cow, collections::HashMap, ptr, time::Duration};
+
+// raw pixel formats that are handed out as they are, with their `FFmpeg` `pix_fmt` name
+const PIXEL_FORMATS: [(Pixel, FrameFormat, &str); 8] = [
+    (Pixel::YUYV422, FrameFormat::Yuv422, "yuyv422"),
+    (Pixel::UYVY422, FrameFormat::Uyv422, "uyvy422"),
+    (Pixel::NV12, FrameFormat::Nv12, "nv12"),
+    (Pixel::NV21, FrameFormat::Nv21, "nv21"),
+    (Pixel::GRAY8, FrameFormat::Luma8, "gray"),
+    (Pixel::GRAY16LE, FrameFormat::Luma16, "gray16le"),
+    (Pixel::RGB24, FrameFormat::Rgb8, "rgb24"),
+    (Pixel::RGBA, FrameFormat::RgbA8, "rgba"),
+];
+
+// compressed formats, with their `FFmpeg` codec name
+const CODEC_FORMATS: [(CodecId, FrameFormat, &str); 3] = [
+    (CodecId::MJPEG, FrameFormat::MJpeg, "mjpeg"),
+    (CodecId::H264, FrameFormat::H264, "h264"),
+    (CodecId::HEVC, FrameFormat::H265, "hevc"),
+];
+
+/// The `libavdevice` input format used when none is given: `v4l2` on Linux, `dshow` on Windows, and `avfoundation` on macOS.
+#[must_use]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-ffmpeg")))]
+pub fn default_ffmpeg_input_format() -> &'static str {
+    match std::env::consts::OS {
+        "windows" => "dshow",
+        "macos" => "avfoundation",
+        _ => "v4l2",
+    }
+}
+
+/// Lists the cameras of the default `libavdevice` input format, see [`default_ffmpeg_input_format()`].
+/// # Errors
+/// If `FFmpeg` fails to initialize, or the input format can not list its devices, this will error.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-ffmpeg")))]
+pub fn query_ffmpeg() -> Result<Vec<CameraInfo>, NokhwaError> {
+    query_ffmpeg_input_format(default_ffmpeg_input_format())
+}
+
+/// Lists the devices of a `libavdevice` input format, e.g. `dshow` or `v4l2`.
+///
+/// The [`CameraInfo::misc()`] of each device is the name `FFmpeg` opens it by, e.g. `/dev/video0`.
+/// # Errors
+/// If `FFmpeg` fails to initialize, the input format does not exist, or it can not list its devices (e.g. `gdigrab`), this will error.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-ffmpeg")))]
+pub fn query_ffmpeg_input_format(input_format: &str) -> Result<Vec<CameraInfo>, NokhwaError> {
+    Ok(list_sources(input_format)?
+        .into_iter()
+        .zip(0..)
+        .map(|((name, description), index)| {
+            CameraInfo::new(
+                &description,
+                input_format,
+                &name,
+                &CameraIndex::Index(index),
+            )
+        })
+        .collect())
+}
+
+fn init() -> Result<(), NokhwaError> {
+    ffmpeg_next::init().map_err(|why| NokhwaError::InitializeError {
+        backend: ApiBackend::FFmpeg,
+        error: why.to_string(),
+    })
+}
+
+fn find_input_format(input_format: &str) -> Result<format::Input, NokhwaError> {
+    init()?;
+    device_input::video()
+        .find_map(|format| match format {
+            Format::Input(input) if input.name() == input_format => Some(input),
+            _ => None,
+        })
+        .ok_or_else(|| NokhwaError::InitializeError {
+            backend: ApiBackend::FFmpeg,
+            error: format!("FFmpeg has no input device {input_format}"),
+        })
+}
+
+// (name, description) of every source
+fn list_sources(input_format: &str) -> Result<Vec<(String, String)>, NokhwaError> {
+    let format = find_input_format(input_format)?;
+    let mut list: *mut AVDeviceInfoList = ptr::null_mut();
+    // SAFETY: the format outlives the call, and the list is freed with `avdevice_free_list_devices` once read
+    unsafe {
+        let result =
+            avdevice_list_input_sources(format.as_ptr(), ptr::null(), ptr::null_mut(), &mut list);
+        if result < 0 || list.is_null() {
+            return Err(NokhwaError::GeneralError(format!(
+                "{input_format} can not list its devices: {}",
+                FfmpegError::from(result)
+            )));
+        }
+        let count = usize::try_from((*list).nb_devices).unwrap_or(0);
+        let sources = (0..count)
+            .map(|index| device::Info::wrap(*(*list).devices.add(index)))
+            .map(|info| (info.name().to_string(), info.description().to_string()))
+            .collect();
+        avdevice_free_list_devices(&mut list);
+        Ok(sources)
+    }
+}
+
+// the options each input format takes a `FrameFormat` with
+fn format_option(
+    input_format: &str,
+    frame_format: FrameFormat,
+) -> Option<(&'static str, &'static str)> {
+    let codec = CODEC_FORMATS
+        .into_iter()
+        .find(|(_, format, _)| *format == frame_format)
+        .map(|(_, _, name)| name);
+    let pixel = PIXEL_FORMATS
+        .into_iter()
+        .find(|(_, format, _)| *format == frame_format)
+        .map(|(_, _, name)| name);
+    match (input_format, codec, pixel) {
+        ("v4l2", Some(name), _) | ("v4l2", None, Some(name)) => Some(("input_format", name)),
+        ("dshow", Some(name), _) => Some(("vcodec", name)),
+        (_, None, Some(name)) => Some(("pixel_format", name)),
+        _ => None,
+    }
+}
+
+fn frame_rate_option(frame_rate: FrameRate) -> String {
+    match frame_rate {
+        FrameRate::Fraction {
+            numerator,
+            denominator,
+        } => format!("{numerator}/{denominator}"),
+        FrameRate::Integer(fps) => fps.to_string(),
+        FrameRate::Float(fps) => fps.to_string(),
+    }
+}
+
+fn rational_to_frame_rate(rational: Rational) -> Option<FrameRate> {
+    if rational.denominator() == 1 {
+        return FrameRate::new_integer(u32::try_from(rational.numerator()).ok()?).ok();
+    }
+    FrameRate::new_fraction(
+        u16::try_from(rational.numerator()).ok()?,
+        u16::try_from(rational.denominator()).ok()?,
+    )
+    .ok()
+}
+
+fn read_error(why: &FfmpegError) -> NokhwaError {
+    NokhwaError::ReadFrameError(why.to_string())
+}
+
+struct OpenInput {
+    input: Input,
+    stream_index: usize,
+    time_base: Rational,
+    decoder: decoder::Video,
+    scaler: Option<Scaler>,
+    // the format the packets already are, if nokhwa knows it
+    passthrough: Option<FrameFormat>,
+}
+
+/// The backend struct that interfaces with `libavdevice`, the device layer of `FFmpeg`.
+/// To see what this does, please see [`CaptureTrait`].
+///
+/// Any video input device `FFmpeg` was built with can be used, e.g. `v4l2`, `dshow`, `avfoundation`, or `gdigrab` for the screen.
+/// # Quirks
+/// - `libavdevice` does not list the formats of a device, [`compatible_fourcc()`](CaptureTrait::compatible_fourcc) is an [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
+/// - There are no camera controls, [`camera_controls()`](CaptureTrait::camera_controls) is empty.
+/// - The [`CameraFormat`] is passed to the input format as options (`video_size`, `framerate`, and `input_format`, `vcodec` or `pixel_format`), which are only read when the
+///   device opens. Setting it reopens the stream.
+/// - [`frame()`](CaptureTrait::frame) hands out raw formats `nokhwa` knows as they are. Everything else (e.g. MJPEG, H264, `yuv420p`, `bgr0`) is decoded with `libavcodec` and
+///   converted to [`FrameFormat::Rgb8`] with `swscale`. [`frame_raw()`](CaptureTrait::frame_raw) is always the packet as the device sent it.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-ffmpeg")))]
+pub struct FfmpegCaptureDevice {
+    camera_format: Option<CameraFormat>,
+    camera_info: CameraInfo,
+    input_format: String,
+    url: String,
+    options: Vec<(String, String)>,
+    sequence: u64,
+    stream: Option<OpenInput>,
+}
+
+impl FfmpegCaptureDevice {
+    /// Creates a new capture device using the `FFmpeg` backend with the default input format, see [`default_ffmpeg_input_format()`].
+    ///
+    /// `index` is either an index into [`query_ffmpeg()`], or the name `FFmpeg` opens the device by.
+    /// # Errors
+    /// If `FFmpeg` fails to initialize, or the device does not exist, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        Self::with_input_format(index, default_ffmpeg_input_format(), &[])
+    }
+
+    /// Creates a new capture device using the `FFmpeg` backend with a `libavdevice` input format, e.g. `gdigrab`, and extra options for it,
+    /// e.g. `("rtbufsize", "100M")` for `dshow`.
+    ///
+    /// `index` is either an index into [`query_ffmpeg_input_format()`], or the name `FFmpeg` opens the device by, e.g. `desktop` for `gdigrab`.
+    /// # Errors
+    /// If `FFmpeg` fails to initialize, the input format does not exist, or the device does not exist, this will error.
+    pub fn with_input_format(
+        index: &CameraIndex,
+        input_format: &str,
+        options: &[(&str, &str)],
+    ) -> Result<Self, NokhwaError> {
+        find_input_format(input_format)?;
+        let camera_info = match index {
+            CameraIndex::Index(_) => query_ffmpeg_input_format(input_format)?
+                .into_iter()
+                .find(|info| info.index() == index)
+                .ok_or_else(|| {
+                    NokhwaError::OpenDeviceError(index.to_string(), "No such device".to_string())
+                })?,
+            CameraIndex::String(name) => CameraInfo::new(
+                &format!("FFmpeg {input_format} {name}"),
+                input_format,
+                name,
+                index,
+            ),
+        };
+        let name = camera_info.misc();
+        // `dshow` opens devices by their type and name
+        let url = if input_format == "dshow" && !name.starts_with("video=") {
+            format!("video={name}")
+        } else {
+            name
+        };
+
+        Ok(FfmpegCaptureDevice {
+            camera_format: None,
+            camera_info,
+            input_format: input_format.to_string(),
+            url,
+            options: options
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+                .collect(),
+            sequence: 0,
+            stream: None,
+        })
+    }
+
+    /// Gets the `libavdevice` input format, e.g. `v4l2`.
+    #[must_use]
+    pub fn input_format(&self) -> &str {
+        &self.input_format
+    }
+
+    /// Gets the name the device is opened by, e.g. `/dev/video0` or `video=HD Webcam`.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Gets the `FFmpeg` codec of the stream, e.g. `mjpeg` or `rawvideo`.
+    /// # Errors
+    /// If the stream is not open, this will error.
+    pub fn codec_name(&self) -> Result<String, NokhwaError> {
+        let stream = self.stream.as_ref().ok_or(NokhwaError::UnitializedError)?;
+        Ok(stream.decoder.id().name().to_string())
+    }
+
+    fn dictionary(&self) -> Dictionary<'static> {
+        let mut dictionary = Dictionary::new();
+        if let Some(camera_format) = self.camera_format {
+            dictionary.set(
+                "video_size",
+                &format!("{}x{}", camera_format.width(), camera_format.height()),
+            );
+            dictionary.set("framerate", &frame_rate_option(camera_format.frame_rate()));
+            if let Some((key, value)) = format_option(&self.input_format, camera_format.format()) {
+                dictionary.set(key, value);
+            }
+        }
+        // the user's options come last, so they win
+        for (key, value) in &self.options {
+            dictionary.set(key, value);
+        }
+        dictionary
+    }
+
+    fn open_input(&self) -> Result<OpenInput, NokhwaError> {
+        let open_error =
+            |why: FfmpegError| NokhwaError::OpenStreamError(format!("{}: {why}", self.url));
+        let format = find_input_format(&self.input_format)?;
+        let input = match format::open_with(&self.url, &Format::Input(format), self.dictionary())
+            .map_err(open_error)?
+        {
+            format::context::Context::Input(input) => input,
+            format::context::Context::Output(_) => {
+                return Err(NokhwaError::OpenStreamError(
+                    "FFmpeg opened an output".to_string(),
+                ))
+            }
+        };
+        let stream = input
+            .streams()
+            .best(media::Type::Video)
+            .ok_or_else(|| NokhwaError::OpenStreamError("No video stream".to_string()))?;
+        let stream_index = stream.index();
+        let time_base = stream.time_base();
+        let decoder = codec::context::Context::from_parameters(stream.parameters())
+            .and_then(|context| context.decoder().video())
+            .map_err(open_error)?;
+
+        let passthrough = if decoder.id() == CodecId::RAWVIDEO {
+            PIXEL_FORMATS
+                .into_iter()
+                .find(|(pixel, _, _)| *pixel == decoder.format())
+                .map(|(_, format, _)| format)
+        } else {
+            None
+        };
+
+        Ok(OpenInput {
+            input,
+            stream_index,
+            time_base,
+            decoder,
+            scaler: None,
+            passthrough,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Packet, NokhwaError> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+        let mut packet = Packet::empty();
+        loop {
+            packet
+                .read(&mut stream.input)
+                .map_err(|why| read_error(&why))?;
+            if packet.stream() == stream.stream_index {
+                self.sequence += 1;
+                return Ok(packet);
+            }
+        }
+    }
+
+    fn timestamp(&self, packet: &Packet) -> Option<FrameTimestamp> {
+        let time_base = self.stream.as_ref()?.time_base;
+        let pts = u64::try_from(packet.pts()?).ok()?;
+        let nanos = u128::from(pts) * u128::try_from(time_base.numerator()).ok()? * 1_000_000_000
+            / u128::try_from(time_base.denominator()).ok()?.max(1);
+        Some(FrameTimestamp::new(
+            Duration::from_nanos(u64::try_from(nanos).ok()?),
+            TimestampClock::Unknown,
+        ))
+    }
+
+    // decodes until a frame comes out, then converts it to RGB
+    fn decode(&mut self, mut packet: Packet) -> Result<(Resolution, Vec<u8>), NokhwaError> {
+        let mut decoded = VideoFrame::empty();
+        loop {
+            let stream = self.stream.as_mut().ok_or(NokhwaError::UnitializedError)?;
+            stream
+                .decoder
+                .send_packet(&packet)
+                .map_err(|why| read_error(&why))?;
+            match stream.decoder.receive_frame(&mut decoded) {
+                Ok(()) => break,
+                Err(FfmpegError::Other { errno: EAGAIN }) => packet = self.next_packet()?,
+                Err(why) => return Err(read_error(&why)),
+            }
+        }
+
+        let stream = self.stream.as_mut().ok_or(NokhwaError::UnitializedError)?;
+        let (width, height) = (decoded.width(), decoded.height());
+        let stale = match &stream.scaler {
+            Some(scaler) => {
+                scaler.input().format != decoded.format()
+                    || scaler.input().width != width
+                    || scaler.input().height != height
+            }
+            None => true,
+        };
+        if stale {
+            stream.scaler = Some(
+                Scaler::get(
+                    decoded.format(),
+                    width,
+                    height,
+                    Pixel::RGB24,
+                    width,
+                    height,
+                    Flags::BILINEAR,
+                )
+                .map_err(|why| read_error(&why))?,
+            );
+        }
+        let mut rgb = VideoFrame::empty();
+        if let Some(scaler) = stream.scaler.as_mut() {
+            scaler
+                .run(&decoded, &mut rgb)
+                .map_err(|why| read_error(&why))?;
+        }
+
+        // `swscale` pads its rows
+        let row_bytes = width as usize * 3;
+        let data = rgb
+            .data(0)
+            .chunks(rgb.stride(0))
+            .take(height as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+        Ok((Resolution::new(width, height), data))
+    }
+}
+
+impl Backend for FfmpegCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::FFmpeg;
+}
+
+impl CaptureTrait for FfmpegCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // `libavdevice` picks the format, which is read back once the stream opens
+        Ok(())
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::FFmpeg
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        let Some(stream) = &self.stream else {
+            return Ok(());
+        };
+        let format = stream.passthrough.or_else(|| {
+            CODEC_FORMATS
+                .into_iter()
+                .find(|(codec, _, _)| *codec == stream.decoder.id())
+                .map(|(_, format, _)| format)
+        });
+        let frame_rate = stream
+            .input
+            .stream(stream.stream_index)
+            .and_then(|stream| rational_to_frame_rate(stream.avg_frame_rate()))
+            .unwrap_or_default();
+        self.camera_format = Some(CameraFormat::new(
+            Resolution::new(stream.decoder.width(), stream.decoder.height()),
+            format.unwrap_or(FrameFormat::Rgb8),
+            frame_rate,
+        ));
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        let old_fmt = self.camera_format.replace(new_fmt);
+        if self.stream.is_some() {
+            self.stop_stream()?;
+            if let Err(why) = self.open_stream() {
+                self.camera_format = old_fmt;
+                let _ = self.open_stream();
+                return Err(why);
+            }
+        }
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        _fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::FFmpeg))
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::FFmpeg))
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::Rgb8, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, _control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::FFmpeg))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(vec![])
+    }
+
+    fn set_camera_control(
+        &mut self,
+        _id: KnownCameraControl,
+        _value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::FFmpeg))
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.stream.is_some() {
+            return Ok(());
+        }
+        self.stream = Some(self.open_input()?);
+        self.sequence = 0;
+        self.refresh_camera_format()
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let packet = self.next_packet()?;
+        let timestamp = self.timestamp(&packet);
+        let sequence = self.sequence;
+        let passthrough = self.stream.as_ref().and_then(|stream| {
+            let resolution = Resolution::new(stream.decoder.width(), stream.decoder.height());
+            stream.passthrough.map(|format| (format, resolution))
+        });
+
+        let mut buffer = match passthrough {
+            Some((format, resolution)) => {
+                Buffer::new(resolution, packet.data().unwrap_or_default(), format)
+            }
+            None => {
+                let (resolution, data) = self.decode(packet)?;
+                Buffer::new(resolution, &data, FrameFormat::Rgb8)
+            }
+        }
+        .with_sequence(sequence);
+        if let Some(timestamp) = timestamp {
+            buffer = buffer.with_timestamp(timestamp);
+        }
+        Ok(buffer)
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let packet = self.next_packet()?;
+        Ok(Cow::Owned(packet.data().unwrap_or_default().to_vec()))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        // dropping the input closes the device
+        self.stream = None;
+        Ok(())
+    }
+}
@@ -14,142 +14,586 @@
  * limitations under the License.
  */
 
-use crate::{
-    mjpeg_to_rgb, yuyv422_to_rgb, ApiBackend, CameraControl, CameraFormat, CameraInfo,
-    CaptureBackendTrait, FrameFormat, KnownCameraControl, NokhwaError, Resolution,
-};
-use glib::Quark;
 use gstreamer::{
-    element_error,
-    glib::Cast,
-    prelude::{DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual, ElementExt, GstBinExt},
-    Bin, Caps, ClockTime, DeviceMonitor, Element, FlowError, FlowSuccess, MessageView,
-    ResourceError, State,
+    glib, prelude::*, Caps, CapsRef, ClockTime, Device, DeviceMonitor, Element, ElementFactory,
+    Fraction, FractionRange, IntRange, MessageType, MessageView, Pipeline, State, StructureRef,
+};
+use gstreamer_app::AppSink;
+use nokhwa_core::{
+    buffer::{Buffer, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::{BayerPattern, FrameFormat},
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
+        FrameRate, KnownCameraControl, Resolution,
+    },
 };
-use gstreamer_app::{AppSink, AppSinkCallbacks};
-use gstreamer_video::{VideoFormat, VideoInfo};
-use image::{ImageBuffer, Rgb};
-use parking_lot::Mutex;
-use regex::Regex;
-use std::{any::Any, borrow::Cow, collections::HashMap, str::FromStr, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, str::FromStr, time::Duration};
+
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+const STATE_TIMEOUT: Duration = Duration::from_secs(5);
+const CAPS_FILTER_NAME: &str = "nokhwa_caps_filter";
+
+// frame rates probed when a device only reports a range
+const COMMON_FRAME_RATES: [u32; 10] = [5, 10, 15, 20, 24, 25, 30, 50, 60, 120];
+
+// `video/x-raw` formats, by their `GStreamer` name
+const RAW_FORMATS: [(&str, FrameFormat); 12] = [
+    ("YUY2", FrameFormat::Yuv422),
+    ("UYVY", FrameFormat::Uyv422),
+    ("v210", FrameFormat::V210),
+    ("NV12", FrameFormat::Nv12),
+    ("NV21", FrameFormat::Nv21),
+    ("YV12", FrameFormat::Yv12),
+    ("P010_10LE", FrameFormat::P010),
+    ("GRAY8", FrameFormat::Luma8),
+    ("GRAY16_LE", FrameFormat::Luma16),
+    ("RGB", FrameFormat::Rgb8),
+    ("RGBA", FrameFormat::RgbA8),
+    ("RGBx", FrameFormat::RgbA8),
+];
+
+// `video/x-bayer` formats
+const BAYER_FORMATS: [(&str, BayerPattern); 4] = [
+    ("rggb", BayerPattern::Rggb),
+    ("bggr", BayerPattern::Bggr),
+    ("grbg", BayerPattern::Grbg),
+    ("gbrg", BayerPattern::Gbrg),
+];
+
+// formats that are a caps name of their own
+const ENCODED_FORMATS: [(&str, FrameFormat); 4] = [
+    ("image/jpeg", FrameFormat::MJpeg),
+    ("video/x-h264", FrameFormat::H264),
+    ("video/x-h265", FrameFormat::H265),
+    ("video/x-vp8", FrameFormat::VP8),
+];
+
+/// The parts of a `GStreamer` pipeline a [`GStreamerCaptureDevice`] is built from. Each part is a partial pipeline description, as
+/// `gst-launch-1.0` takes it, and the parts are linked in this order:
+/// ```.ignore
+/// <source> ! capsfilter ! <decoder> ! <elements...> ! appsink
+/// ```
+/// The caps filter is the [`CameraFormat`] of the device, narrowed down by [`with_caps_filter()`](GStreamerPipeline::with_caps_filter).
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub struct GStreamerPipeline {
+    source: Option<String>,
+    caps_filter: Option<String>,
+    decoder: Option<String>,
+    elements: Vec<String>,
+}
+
+impl GStreamerPipeline {
+    /// Creates a new [`GStreamerPipeline`] that hands out the frames of the device as they are.
+    #[must_use]
+    pub fn new() -> Self {
+        GStreamerPipeline::default()
+    }
+
+    /// Replaces the source element the device monitor would create, e.g. `v4l2src device=/dev/video2 io-mode=dmabuf` or `videotestsrc`.
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Narrows the caps asked of the source, e.g. `video/x-raw,colorimetry=bt709` or `image/jpeg,width=[1280,1920]`.
+    #[must_use]
+    pub fn with_caps_filter(mut self, caps_filter: &str) -> Self {
+        self.caps_filter = Some(caps_filter.to_string());
+        self
+    }
+
+    /// Decodes the frames of the source in the pipeline, e.g. `jpegdec`, `v4l2jpegdec` or `nvjpegdec ! videoconvert`.
+    ///
+    /// [`frame()`](CaptureTrait::frame) reports the format the decoder hands out, which has to be one `nokhwa` knows of. If it is not,
+    /// add a `videoconvert ! video/x-raw,format=RGB`.
+    #[must_use]
+    pub fn with_decoder(mut self, decoder: &str) -> Self {
+        self.decoder = Some(decoder.to_string());
+        self
+    }
+
+    /// Adds an element after the decoder, e.g. `queue max-size-buffers=1 leaky=downstream` or `videoflip method=horizontal-flip`.
+    #[must_use]
+    pub fn with_element(mut self, element: &str) -> Self {
+        self.elements.push(element.to_string());
+        self
+    }
+
+    /// Gets the source description, if it was replaced.
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Gets the caps filter.
+    #[must_use]
+    pub fn caps_filter(&self) -> Option<&str> {
+        self.caps_filter.as_deref()
+    }
+
+    /// Gets the decoder description.
+    #[must_use]
+    pub fn decoder(&self) -> Option<&str> {
+        self.decoder.as_deref()
+    }
+
+    /// Gets the element descriptions, in order.
+    #[must_use]
+    pub fn elements(&self) -> &[String] {
+        &self.elements
+    }
+}
+
+/// Gets the [`FrameFormat`] of a caps name and, for `video/x-raw` and `video/x-bayer`, its `format` field.
+#[must_use]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub fn gstreamer_caps_to_frameformat(name: &str, format: Option<&str>) -> Option<FrameFormat> {
+    match (name, format) {
+        ("video/x-raw", Some(format)) => RAW_FORMATS
+            .into_iter()
+            .find(|(raw, _)| *raw == format)
+            .map(|(_, frame_format)| frame_format),
+        ("video/x-bayer", Some(format)) => BAYER_FORMATS
+            .into_iter()
+            .find(|(bayer, _)| *bayer == format)
+            .map(|(_, pattern)| FrameFormat::Bayer8(pattern)),
+        (name, _) => ENCODED_FORMATS
+            .into_iter()
+            .find(|(encoded, _)| *encoded == name)
+            .map(|(_, frame_format)| frame_format),
+    }
+}
+
+/// Gets the caps name and `format` field of a [`FrameFormat`], or `None` if `GStreamer` has no name for it.
+#[must_use]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub fn frameformat_to_gstreamer_caps(
+    frame_format: FrameFormat,
+) -> Option<(&'static str, Option<&'static str>)> {
+    if let FrameFormat::Bayer8(pattern) = frame_format {
+        return BAYER_FORMATS
+            .into_iter()
+            .find(|(_, bayer)| *bayer == pattern)
+            .map(|(format, _)| ("video/x-bayer", Some(format)));
+    }
+    RAW_FORMATS
+        .into_iter()
+        .find(|(_, raw)| *raw == frame_format)
+        .map(|(format, _)| ("video/x-raw", Some(format)))
+        .or_else(|| {
+            ENCODED_FORMATS
+                .into_iter()
+                .find(|(_, encoded)| *encoded == frame_format)
+                .map(|(name, _)| (name, None))
+        })
+}
+
+/// Lists every [`CameraFormat`] in `caps`, e.g. the caps of a device from the device monitor.
+///
+/// Formats `nokhwa` does not know are left out. Width and height ranges are listed as their smallest and largest resolution,
+/// frame rate ranges as the common frame rates in them.
+#[must_use]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub fn caps_to_camera_formats(caps: &CapsRef) -> Vec<CameraFormat> {
+    let mut formats = vec![];
+    for structure in caps.iter() {
+        let resolutions = structure_resolutions(structure);
+        let frame_rates = structure_frame_rates(structure);
+        for frame_format in structure_frame_formats(structure) {
+            for resolution in &resolutions {
+                for frame_rate in &frame_rates {
+                    let format = CameraFormat::new(*resolution, frame_format, *frame_rate);
+                    if !formats.contains(&format) {
+                        formats.push(format);
+                    }
+                }
+            }
+        }
+    }
+    formats
+}
+
+/// Creates the caps of a [`CameraFormat`].
+/// # Errors
+/// If `GStreamer` has no name for the [`FrameFormat`], or the resolution does not fit, this will error.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub fn camera_format_to_caps(camera_format: CameraFormat) -> Result<Caps, NokhwaError> {
+    let structure_error = |why: String| NokhwaError::StructureError {
+        structure: "Caps".to_string(),
+        error: why,
+    };
+    let (name, format) = frameformat_to_gstreamer_caps(camera_format.format())
+        .ok_or_else(|| structure_error(format!("No caps for {}", camera_format.format())))?;
+    let width =
+        i32::try_from(camera_format.width()).map_err(|why| structure_error(why.to_string()))?;
+    let height =
+        i32::try_from(camera_format.height()).map_err(|why| structure_error(why.to_string()))?;
+    let framerate = match camera_format.frame_rate() {
+        FrameRate::Fraction {
+            numerator,
+            denominator,
+        } => Fraction::new(i32::from(numerator), i32::from(denominator)),
+        #[allow(clippy::cast_possible_truncation)]
+        FrameRate::Float(fps) => {
+            Fraction::approximate_f32(fps).unwrap_or_else(|| Fraction::new(fps.round() as i32, 1))
+        }
+        FrameRate::Integer(fps) => Fraction::new(i32::try_from(fps).unwrap_or(i32::MAX), 1),
+    };
+
+    let mut builder = Caps::builder(name)
+        .field("width", width)
+        .field("height", height)
+        .field("framerate", framerate);
+    if let Some(format) = format {
+        builder = builder.field("format", format);
+    }
+    Ok(builder.build())
+}
+
+/// Lists the video sources the `GStreamer` device monitor can see.
+///
+/// The [`CameraInfo::misc()`] of each source is its device path, if it has one.
+/// # Errors
+/// If `GStreamer` fails to initialize, or the device monitor fails to start, this will error.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub fn query_gstreamer() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Ok(video_sources()?
+        .iter()
+        .zip(0..)
+        .map(|(device, index)| device_info(device, &CameraIndex::Index(index)))
+        .collect())
+}
+
+fn video_sources() -> Result<Vec<Device>, NokhwaError> {
+    gstreamer::init().map_err(|why| NokhwaError::InitializeError {
+        backend: ApiBackend::GStreamer,
+        error: why.to_string(),
+    })?;
+    let device_monitor = DeviceMonitor::new();
+    device_monitor
+        .add_filter(Some("Video/Source"), None)
+        .ok_or_else(|| NokhwaError::StructureError {
+            structure: "Video Filter ID Video/Source".to_string(),
+            error: "Null".to_string(),
+        })?;
+    device_monitor.start().map_err(|why| {
+        NokhwaError::GeneralError(format!("Failed to start device monitor: {why}"))
+    })?;
+    let devices = device_monitor.devices().into_iter().collect();
+    device_monitor.stop();
+    Ok(devices)
+}
+
+fn device_path(device: &Device) -> Option<String> {
+    let properties = device.properties()?;
+    ["device.path", "api.v4l2.path", "object.path"]
+        .into_iter()
+        .find_map(|key| properties.get::<String>(key).ok())
+}
+
+fn device_info(device: &Device, index: &CameraIndex) -> CameraInfo {
+    CameraInfo::new(
+        &device.display_name(),
+        &device.device_class(),
+        &device_path(device).unwrap_or_default(),
+        index,
+    )
+}
+
+fn structure_frame_formats(structure: &StructureRef) -> Vec<FrameFormat> {
+    let name = structure.name().as_str();
+    if let Ok(format) = structure.get::<&str>("format") {
+        return gstreamer_caps_to_frameformat(name, Some(format))
+            .into_iter()
+            .collect();
+    }
+    if let Ok(formats) = structure.get::<gstreamer::List>("format") {
+        return formats
+            .iter()
+            .filter_map(|format| format.get::<&str>().ok())
+            .filter_map(|format| gstreamer_caps_to_frameformat(name, Some(format)))
+            .collect();
+    }
+    gstreamer_caps_to_frameformat(name, None)
+        .into_iter()
+        .collect()
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn structure_resolutions(structure: &StructureRef) -> Vec<Resolution> {
+    let dimension = |field: &str| -> Vec<u32> {
+        if let Ok(value) = structure.get::<i32>(field) {
+            vec![value as u32]
+        } else if let Ok(range) = structure.get::<IntRange<i32>>(field) {
+            vec![range.min() as u32, range.max() as u32]
+        } else {
+            vec![]
+        }
+    };
+    let widths = dimension("width");
+    let heights = dimension("height");
+    match (widths.as_slice(), heights.as_slice()) {
+        ([width], [height]) => vec![Resolution::new(*width, *height)],
+        // ranges pair up smallest with smallest and largest with largest
+        (widths, heights) => widths
+            .iter()
+            .zip(heights.iter().cycle())
+            .map(|(width, height)| Resolution::new(*width, *height))
+            .collect(),
+    }
+}
+
+fn structure_frame_rates(structure: &StructureRef) -> Vec<FrameRate> {
+    if let Ok(fraction) = structure.get::<Fraction>("framerate") {
+        return fraction_to_frame_rate(fraction).into_iter().collect();
+    }
+    if let Ok(fractions) = structure.get::<gstreamer::List>("framerate") {
+        return fractions
+            .iter()
+            .filter_map(|fraction| fraction.get::<Fraction>().ok())
+            .filter_map(fraction_to_frame_rate)
+            .collect();
+    }
+    if let Ok(range) = structure.get::<FractionRange>("framerate") {
+        return COMMON_FRAME_RATES
+            .into_iter()
+            .filter(|fps| {
+                let fps = Fraction::new(i32::try_from(*fps).unwrap_or(i32::MAX), 1);
+                range.min() <= fps && fps <= range.max()
+            })
+            .filter_map(|fps| FrameRate::new_integer(fps).ok())
+            .collect();
+    }
+    vec![]
+}
 
-type PipelineGenRet = (Element, AppSink, Arc<Mutex<ImageBuffer<Rgb<u8>, Vec<u8>>>>);
+fn fraction_to_frame_rate(fraction: Fraction) -> Option<FrameRate> {
+    if fraction.denom() == 1 {
+        return FrameRate::new_integer(u32::try_from(fraction.numer()).ok()?).ok();
+    }
+    FrameRate::new_fraction(
+        u16::try_from(fraction.numer()).ok()?,
+        u16::try_from(fraction.denom()).ok()?,
+    )
+    .ok()
+}
+
+fn describe(description: &str) -> Result<Element, NokhwaError> {
+    gstreamer::parse::bin_from_description(description, true)
+        .map(Cast::upcast)
+        .map_err(|why| {
+            NokhwaError::OpenStreamError(format!("Bad pipeline description {description}: {why}"))
+        })
+}
 
 /// The backend struct that interfaces with `GStreamer`.
-/// To see what this does, please see [`CaptureBackendTrait`].
+/// To see what this does, please see [`CaptureTrait`].
+///
+/// The pipeline can be changed with a [`GStreamerPipeline`], e.g. to decode MJPEG with `v4l2jpegdec` instead of `jpegdec`.
 /// # Quirks
-/// - `Drop`-ing this may cause a `panic`.
-/// - Setting controls is not supported.
+/// - `GStreamer` has no camera controls of its own, [`camera_controls()`](CaptureTrait::camera_controls) is empty and setting controls is an [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
+/// - The formats are from the caps the device monitor reports. With a replaced source and no matching device, [`compatible_fourcc()`](CaptureTrait::compatible_fourcc)
+///   is an [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
+/// - [`frame()`](CaptureTrait::frame) reports the format the pipeline ends in, which is not the [`CameraFormat`]'s if there is a decoder.
+/// - Frame timestamps are the pipeline's running time, not the wall clock.
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
-#[deprecated(
-    since = "0.10",
-    note = "Use one of the native backends instead(V4L, AVF, MSMF) or OpenCV"
-)]
 pub struct GStreamerCaptureDevice {
-    pipeline: Element,
-    app_sink: AppSink,
-    camera_format: CameraFormat,
+    camera_format: Option<CameraFormat>,
     camera_info: CameraInfo,
-    image_lock: Arc<Mutex<ImageBuffer<Rgb<u8>, Vec<u8>>>>,
-    caps: Option<Caps>,
+    description: GStreamerPipeline,
+    device: Option<Device>,
+    pipeline: Option<(Pipeline, AppSink)>,
 }
 
 impl GStreamerCaptureDevice {
-    /// Creates a new capture device using the `GStreamer` backend. Indexes are gives to devices by the OS, and usually numbered by order of discovery.
+    /// Creates a new capture device using the `GStreamer` backend.
     ///
-    /// `GStreamer` uses `v4l2src` on linux, `ksvideosrc` on windows, and `autovideosrc` on mac.
+    /// `index` is either an index into [`query_gstreamer()`], or a device's display name or device path.
+    /// # Errors
+    /// If `GStreamer` fails to initialize, or the device does not exist, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        Self::with_pipeline(index, GStreamerPipeline::new())
+    }
+
+    /// Creates a new capture device using the `GStreamer` backend, built from a [`GStreamerPipeline`].
     ///
-    /// If `camera_format` is `None`, it will be spawned with with 640x480@15 FPS, MJPEG [`CameraFormat`] default.
+    /// If the pipeline replaces the source, `index` only has to name a device to get its formats from.
     /// # Errors
-    /// This function will error if the camera is currently busy or if `GStreamer` can't read device information.
-    pub fn new(index: usize, cam_fmt: Option<CameraFormat>) -> Result<Self, NokhwaError> {
-        let camera_format = match cam_fmt {
-            Some(fmt) => fmt,
-            None => CameraFormat::default(),
+    /// If `GStreamer` fails to initialize, or the device does not exist and the source is not replaced, this will error.
+    pub fn with_pipeline(
+        index: &CameraIndex,
+        description: GStreamerPipeline,
+    ) -> Result<Self, NokhwaError> {
+        let devices = video_sources()?;
+        let device = match index {
+            CameraIndex::Index(idx) => devices.get(*idx as usize).cloned(),
+            CameraIndex::String(name) => devices.into_iter().find(|device| {
+                device.display_name() == name.as_str()
+                    || device_path(device).as_deref() == Some(name.as_str())
+            }),
+        };
+        let camera_info = match (&device, description.source()) {
+            (Some(device), _) => device_info(device, index),
+            (None, Some(source)) => {
+                CameraInfo::new(&format!("GStreamer Source {index}"), source, "", index)
+            }
+            (None, None) => {
+                return Err(NokhwaError::OpenDeviceError(
+                    index.to_string(),
+                    "No such device".to_string(),
+                ))
+            }
         };
 
-        let index = index.as_index()?;
+        Ok(GStreamerCaptureDevice {
+            camera_format: None,
+            camera_info,
+            description,
+            device,
+            pipeline: None,
+        })
+    }
+
+    /// Gets the [`GStreamerPipeline`] this device is built from.
+    #[must_use]
+    pub fn pipeline_description(&self) -> &GStreamerPipeline {
+        &self.description
+    }
 
-        if let Err(why) = gstreamer::init() {
-            return Err(NokhwaError::InitializeError {
-                backend: ApiBackend::GStreamer,
-                error: why.to_string(),
+    /// Replaces the [`GStreamerPipeline`], rebuilding the pipeline if the stream is open.
+    /// # Errors
+    /// If the new pipeline fails to start, this will error.
+    pub fn set_pipeline_description(
+        &mut self,
+        description: GStreamerPipeline,
+    ) -> Result<(), NokhwaError> {
+        self.description = description;
+        self.restart()
+    }
+
+    /// Gets the caps the device monitor reports for the device, if there is a device.
+    #[must_use]
+    pub fn device_caps(&self) -> Option<Caps> {
+        self.device.as_ref().and_then(DeviceExt::caps)
+    }
+
+    /// Gets the caps the caps filter is set to: the [`CameraFormat`] and the caps filter of the [`GStreamerPipeline`].
+    /// # Errors
+    /// If the caps filter does not parse, or nothing is left after narrowing, this will error.
+    pub fn filter_caps(&self) -> Result<Caps, NokhwaError> {
+        let caps = match self.camera_format {
+            Some(camera_format) => camera_format_to_caps(camera_format)?,
+            None => Caps::new_any(),
+        };
+        let Some(caps_filter) = self.description.caps_filter() else {
+            return Ok(caps);
+        };
+        let filter = Caps::from_str(caps_filter).map_err(|why| NokhwaError::StructureError {
+            structure: format!("Caps {caps_filter}"),
+            error: why.to_string(),
+        })?;
+        let caps = caps.intersect(&filter);
+        if caps.is_empty() {
+            return Err(NokhwaError::StructureError {
+                structure: format!("Caps {caps_filter}"),
+                error: "Does not match the camera format".to_string(),
             });
         }
+        Ok(caps)
+    }
 
-        let (camera_info, caps) = {
-            let device_monitor = DeviceMonitor::new();
-            let video_caps = match Caps::from_str("video/x-raw") {
-                Ok(cap) => cap,
-                Err(why) => {
-                    return Err(NokhwaError::GeneralError(format!(
-                        "Failed to generate caps: {}",
-                        why
-                    )))
-                }
-            };
-            let _video_filter_id =
-                match device_monitor.add_filter(Some("Video/Source"), Some(&video_caps)) {
-                    Some(id) => id,
-                    None => {
-                        return Err(NokhwaError::StructureError {
-                            structure: "Video Filter ID Video/Source".to_string(),
-                            error: "Null".to_string(),
-                        })
-                    }
-                };
-            if let Err(why) = device_monitor.start() {
-                return Err(NokhwaError::StructureError {
-                    structure: "Device Monitor".to_string(),
-                    error: format!("Not started, {}", why),
-                });
-            }
-            let device = match device_monitor.devices().get(index as usize) {
-                Some(dev) => dev.clone(),
-                None => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        "No device".to_string(),
-                    ))
-                }
-            };
-            device_monitor.stop();
-            let caps = device.caps();
-            (
-                CameraInfo::new(
-                    &DeviceExt::display_name(&device),
-                    &DeviceExt::device_class(&device),
-                    &"",
-                    index,
-                ),
-                caps,
-            )
+    fn build_pipeline(&self) -> Result<(Pipeline, AppSink), NokhwaError> {
+        let open_error = |why: glib::BoolError| NokhwaError::OpenStreamError(why.to_string());
+
+        let source = match (self.description.source(), &self.device) {
+            (Some(source), _) => describe(source)?,
+            (None, Some(device)) => device.create_element(None).map_err(open_error)?,
+            (None, None) => return Err(NokhwaError::UnitializedError),
         };
+        let caps_filter = ElementFactory::make("capsfilter")
+            .name(CAPS_FILTER_NAME)
+            .property("caps", self.filter_caps()?)
+            .build()
+            .map_err(open_error)?;
+        let mut elements = vec![source, caps_filter];
+        if let Some(decoder) = self.description.decoder() {
+            elements.push(describe(decoder)?);
+        }
+        for element in self.description.elements() {
+            elements.push(describe(element)?);
+        }
+        // only the newest frame is kept, like the other backends
+        let app_sink = AppSink::builder()
+            .sync(false)
+            .max_buffers(1)
+            .drop(true)
+            .build();
+        elements.push(app_sink.clone().upcast());
+
+        let pipeline = Pipeline::new();
+        pipeline.add_many(&elements).map_err(open_error)?;
+        Element::link_many(&elements).map_err(open_error)?;
+        Ok((pipeline, app_sink))
+    }
 
-        let (pipeline, app_sink, receiver) = generate_pipeline(camera_format, index as usize)?;
+    fn restart(&mut self) -> Result<(), NokhwaError> {
+        if self.pipeline.is_some() {
+            self.stop_stream()?;
+            self.open_stream()?;
+        }
+        Ok(())
+    }
 
-        Ok(GStreamerCaptureDevice {
-            pipeline,
-            app_sink,
-            camera_format,
-            camera_info,
-            image_lock: receiver,
-            caps,
-        })
+    fn bus_error(pipeline: &Pipeline) -> Option<String> {
+        let message = pipeline
+            .bus()?
+            .pop_filtered(&[MessageType::Error, MessageType::Eos])?;
+        match message.view() {
+            MessageView::Error(err) => Some(format!(
+                "{} ({})",
+                err.error(),
+                err.debug().unwrap_or_default()
+            )),
+            MessageView::Eos(_) => Some("End of stream".to_string()),
+            _ => None,
+        }
     }
 
-    /// Creates a new capture device using the `GStreamer` backend. Indexes are gives to devices by the OS, and usually numbered by order of discovery.
-    ///
-    /// `GStreamer` uses `v4l2src` on linux, `ksvideosrc` on windows, and `autovideosrc` on mac.
-    /// # Errors
-    /// This function will error if the camera is currently busy or if `GStreamer` can't read device information.
-    pub fn new_with(index: usize, width: u32, height: u32, fps: u32) -> Result<Self, NokhwaError> {
-        let cam_fmt = CameraFormat::new(Resolution::new(width, height));
-        GStreamerCaptureDevice::new(index, Some(cam_fmt))
+    fn pull_sample(&mut self) -> Result<gstreamer::Sample, NokhwaError> {
+        let (pipeline, app_sink) = self
+            .pipeline
+            .as_ref()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+        if let Some(why) = Self::bus_error(pipeline) {
+            return Err(NokhwaError::ReadFrameError(why));
+        }
+        app_sink
+            .try_pull_sample(ClockTime::from_nseconds(
+                u64::try_from(FRAME_TIMEOUT.as_nanos()).unwrap_or(u64::MAX),
+            ))
+            .ok_or(NokhwaError::TimeoutError(FRAME_TIMEOUT))
     }
 }
 
-impl GStreamerCaptureDevice {
+impl Backend for GStreamerCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::GStreamer;
+}
+
+impl CaptureTrait for GStreamerCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // the device's first caps are the ones `GStreamer` would pick
+        if self.camera_format.is_none() {
+            self.camera_format = self
+                .device_caps()
+                .and_then(|caps| caps_to_camera_formats(&caps).first().copied());
+        }
+        Ok(())
+    }
+
     fn backend(&self) -> ApiBackend {
         ApiBackend::GStreamer
     }
@@ -158,408 +602,213 @@ impl GStreamerCaptureDevice {
         &self.camera_info
     }
 
-    fn camera_format(&self) -> CameraFormat {
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        // only a playing pipeline has negotiated caps
+        let Some((pipeline, _)) = &self.pipeline else {
+            return Ok(());
+        };
+        let caps = pipeline
+            .by_name(CAPS_FILTER_NAME)
+            .and_then(|filter| filter.static_pad("src"))
+            .and_then(|pad| pad.current_caps());
+        if let Some(format) = caps.and_then(|caps| caps_to_camera_formats(&caps).first().copied()) {
+            self.camera_format = Some(format);
+        }
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
         self.camera_format
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        let mut reopen = false;
-        if self.is_stream_open() {
-            self.stop_stream()?;
-            reopen = true;
+        if let Some(device_caps) = self.device_caps() {
+            if !camera_format_to_caps(new_fmt)?.can_intersect(&device_caps) {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "CameraFormat".to_string(),
+                    value: new_fmt.to_string(),
+                    error: "The device does not have this format".to_string(),
+                });
+            }
         }
-        let (pipeline, app_sink, receiver) =
-            generate_pipeline(new_fmt, self.camera_info.index_num()? as usize)?;
-        self.pipeline = pipeline;
-        self.app_sink = app_sink;
-        self.image_lock = receiver;
-        if reopen {
-            self.open_stream()?;
+        let old_fmt = self.camera_format.replace(new_fmt);
+        if let Err(why) = self.restart() {
+            self.camera_format = old_fmt;
+            return Err(why);
         }
-        self.camera_format = new_fmt;
         Ok(())
     }
 
-    #[allow(clippy::too_many_lines)]
-    #[allow(clippy::cast_sign_loss)]
     fn compatible_list_by_resolution(
         &mut self,
         fourcc: FrameFormat,
-    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
-        let mut resolution_map = HashMap::new();
-
-        let frame_regex = Regex::new(r"(\d+/1)|((\d+/\d)+(\d/1)*)").unwrap();
-
-        match self.caps.clone() {
-            Some(c) => {
-                for capability in c.iter() {
-                    match fourcc {
-                        FrameFormat::MJPEG => {
-                            if capability.name() == "image/jpeg" {
-                                let mut fps_vec = vec![];
-
-                                let width = match capability.get::<i32>("width") {
-                                    Ok(w) => w,
-                                    Err(why) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Capibilities by Resolution: Width"
-                                                .to_string(),
-                                            error: why.to_string(),
-                                        })
-                                    }
-                                };
-                                let height = match capability.get::<i32>("height") {
-                                    Ok(w) => w,
-                                    Err(why) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Capibilities by Resolution: Height"
-                                                .to_string(),
-                                            error: why.to_string(),
-                                        })
-                                    }
-                                };
-                                let value = match capability
-                                    .value_by_quark(Quark::from_string("framerate"))
-                                {
-                                    Ok(v) => match v.transform::<String>() {
-                                        Ok(s) => {
-                                            format!("{:?}", s)
-                                        }
-                                        Err(why) => {
-                                            return Err(NokhwaError::GetPropertyError {
-                                                property: "Framerates".to_string(),
-                                                error: format!(
-                                                    "Failed to make framerates into string: {}",
-                                                    why
-                                                ),
-                                            });
-                                        }
-                                    },
-                                    Err(_) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Framerates".to_string(),
-                                            error: "Failed to get framerates: doesnt exist!"
-                                                .to_string(),
-                                        })
-                                    }
-                                };
-
-                                for m in frame_regex.find_iter(&value) {
-                                    let fraction_string: Vec<&str> =
-                                        m.as_str().split('/').collect();
-                                    if fraction_string.len() != 2 {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: format!("Fraction framerate had more than one demoninator: {:?}", fraction_string) });
-                                    }
-
-                                    if let Some(v) = fraction_string.get(1) {
-                                        if *v != "1" {
-                                            continue; // swallow error
-                                        }
-                                    } else {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: "No framerate denominator? Shouldn't happen, please report!".to_string() });
-                                    }
-
-                                    if let Some(numerator) = fraction_string.get(0) {
-                                        match numerator.parse::<u32>() {
-                                            Ok(fps) => fps_vec.push(fps),
-                                            Err(why) => {
-                                                return Err(NokhwaError::GetPropertyError {
-                                                    property: "Framerates".to_string(),
-                                                    error: format!(
-                                                        "Failed to parse numerator: {}",
-                                                        why
-                                                    ),
-                                                });
-                                            }
-                                        }
-                                    } else {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: "No framerate numerator? Shouldn't happen, please report!".to_string() });
-                                    }
-                                }
-                                resolution_map
-                                    .insert(Resolution::new(width as u32, height as u32), fps_vec);
-                            }
-                        }
-                        FrameFormat::YUYV => {
-                            if capability.name() == "video/x-raw"
-                                && capability.get::<String>("format").unwrap_or_default() == *"YUY2"
-                            {
-                                let mut fps_vec = vec![];
-
-                                let width = match capability.get::<i32>("width") {
-                                    Ok(w) => w,
-                                    Err(why) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Capibilities by Resolution: Width"
-                                                .to_string(),
-                                            error: why.to_string(),
-                                        })
-                                    }
-                                };
-                                let height = match capability.get::<i32>("height") {
-                                    Ok(w) => w,
-                                    Err(why) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Capibilities by Resolution: Height"
-                                                .to_string(),
-                                            error: why.to_string(),
-                                        })
-                                    }
-                                };
-                                let value = match capability
-                                    .value_by_quark(Quark::from_string("framerate"))
-                                {
-                                    Ok(v) => match v.transform::<String>() {
-                                        Ok(s) => {
-                                            format!("{:?}", s)
-                                        }
-                                        Err(why) => {
-                                            return Err(NokhwaError::GetPropertyError {
-                                                property: "Framerates".to_string(),
-                                                error: format!(
-                                                    "Failed to make framerates into string: {}",
-                                                    why
-                                                ),
-                                            });
-                                        }
-                                    },
-                                    Err(_) => {
-                                        return Err(NokhwaError::GetPropertyError {
-                                            property: "Framerates".to_string(),
-                                            error: "Failed to get framerates: doesnt exist!"
-                                                .to_string(),
-                                        })
-                                    }
-                                };
-
-                                for m in frame_regex.find_iter(&value) {
-                                    let fraction_string: Vec<&str> =
-                                        m.as_str().split('/').collect();
-                                    if fraction_string.len() != 2 {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: format!("Fraction framerate had more than one demoninator: {:?}", fraction_string) });
-                                    }
-
-                                    if let Some(v) = fraction_string.get(1) {
-                                        if *v != "1" {
-                                            continue; // swallow error
-                                        }
-                                    } else {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: "No framerate denominator? Shouldn't happen, please report!".to_string() });
-                                    }
-
-                                    if let Some(numerator) = fraction_string.get(0) {
-                                        match numerator.parse::<u32>() {
-                                            Ok(fps) => fps_vec.push(fps),
-                                            Err(why) => {
-                                                return Err(NokhwaError::GetPropertyError {
-                                                    property: "Framerates".to_string(),
-                                                    error: format!(
-                                                        "Failed to parse numerator: {}",
-                                                        why
-                                                    ),
-                                                });
-                                            }
-                                        }
-                                    } else {
-                                        return Err(NokhwaError::GetPropertyError { property: "Framerates".to_string(), error: "No framerate numerator? Shouldn't happen, please report!".to_string() });
-                                    }
-                                }
-                                resolution_map
-                                    .insert(Resolution::new(width as u32, height as u32), fps_vec);
-                            }
-                        }
-                        unsupported => {
-                            return Err(NokhwaError::NotImplementedError(format!(
-                                "Not supported frame format {unsupported:?}"
-                            )))
-                        }
-                    }
-                }
-            }
-            None => {
-                return Err(NokhwaError::GetPropertyError {
-                    property: "Device Caps".to_string(),
-                    error: "No device caps!".to_string(),
-                })
-            }
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let caps = self
+            .device_caps()
+            .ok_or(NokhwaError::UnsupportedOperationError(
+                ApiBackend::GStreamer,
+            ))?;
+        let mut resolution_map: HashMap<Resolution, Vec<FrameRate>> = HashMap::new();
+        for format in caps_to_camera_formats(&caps)
+            .into_iter()
+            .filter(|format| format.format() == fourcc)
+        {
+            resolution_map
+                .entry(format.resolution())
+                .or_default()
+                .push(format.frame_rate());
         }
-
         Ok(resolution_map)
     }
 
     fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
-        let mut format_vec = vec![];
-        match self.caps.clone() {
-            Some(c) => {
-                for capability in c.iter() {
-                    if capability.name() == "image/jpeg" {
-                        format_vec.push(FrameFormat::MJPEG);
-                    } else if capability.name() == "video/x-raw"
-                        && capability.get::<String>("format").unwrap_or_default() == *"YUY2"
-                    {
-                        format_vec.push(FrameFormat::YUYV);
-                    }
-                }
-            }
-            None => {
-                return Err(NokhwaError::GetPropertyError {
-                    property: "Device Caps".to_string(),
-                    error: "No device caps!".to_string(),
-                })
-            }
-        }
-        format_vec.sort();
-        format_vec.dedup();
-        Ok(format_vec)
+        let caps = self
+            .device_caps()
+            .ok_or(NokhwaError::UnsupportedOperationError(
+                ApiBackend::GStreamer,
+            ))?;
+        let mut formats = caps
+            .iter()
+            .flat_map(structure_frame_formats)
+            .collect::<Vec<FrameFormat>>();
+        formats.sort();
+        formats.dedup();
+        Ok(formats)
     }
 
-    fn resolution(&self) -> Resolution {
-        self.camera_format.resolution()
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
     }
 
     fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
-        let mut new_fmt = self.camera_format;
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
         new_fmt.set_resolution(new_res);
         self.set_camera_format(new_fmt)
     }
 
-    fn frame_rate(&self) -> u32 {
-        self.camera_format.frame_rate()
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
     }
 
     fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
-        let mut new_fmt = self.camera_format;
-        new_fmt.set_frame_rate(new_fps);
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
         self.set_camera_format(new_fmt)
     }
 
     fn frame_format(&self) -> FrameFormat {
-        self.camera_format.format()
-    }
-
-    fn set_frame_format(&mut self, _fourcc: FrameFormat) -> Result<(), NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(
-            ApiBackend::GStreamer,
-        ))
+        self.camera_format
+            .map_or(FrameFormat::MJpeg, |fmt| fmt.format())
     }
 
-    fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControl>, NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
-        ))
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
     }
 
     fn camera_control(&self, _control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
-        ))
-    }
-
-    fn set_camera_control(&mut self, _control: CameraControl) -> Result<(), NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
-        ))
-    }
-
-    fn raw_supported_camera_controls(&self) -> Result<Vec<Box<dyn Any>>, NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
+        Err(NokhwaError::UnsupportedOperationError(
+            ApiBackend::GStreamer,
         ))
     }
 
-    fn raw_camera_control(&self, _control: &dyn Any) -> Result<Box<dyn Any>, NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
-        ))
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(vec![])
     }
 
-    fn set_raw_camera_control(
+    fn set_camera_control(
         &mut self,
-        _control: &dyn Any,
-        _value: &dyn Any,
+        _id: KnownCameraControl,
+        _value: ControlValueSetter,
     ) -> Result<(), NokhwaError> {
-        Err(NokhwaError::NotImplementedError(
-            ApiBackend::GStreamer.to_string(),
+        Err(NokhwaError::UnsupportedOperationError(
+            ApiBackend::GStreamer,
         ))
     }
 
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        if let Err(why) = self.pipeline.set_state(State::Playing) {
-            return Err(NokhwaError::OpenStreamError(format!(
-                "Failed to set appsink to playing: {}",
-                why
-            )));
+        if self.pipeline.is_some() {
+            return Ok(());
         }
-        Ok(())
-    }
 
-    // TODO: someone validate this
-    fn is_stream_open(&self) -> bool {
-        let (res, state_from, state_to) = self.pipeline.state(ClockTime::from_mseconds(16));
-        if res.is_ok() {
-            if state_to == State::Playing {
-                return true;
-            }
-            false
-        } else {
-            if state_from == State::Playing {
-                return true;
-            }
-            false
+        let (pipeline, app_sink) = self.build_pipeline()?;
+        pipeline
+            .set_state(State::Playing)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        let (result, _, _) = pipeline.state(ClockTime::from_seconds(STATE_TIMEOUT.as_secs()));
+        if result.is_err() {
+            let why = Self::bus_error(&pipeline)
+                .unwrap_or_else(|| "Pipeline failed to start".to_string());
+            let _ = pipeline.set_state(State::Null);
+            return Err(NokhwaError::OpenStreamError(why));
         }
+        self.pipeline = Some((pipeline, app_sink));
+        self.refresh_camera_format()
     }
 
-    fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        let cam_fmt = self.camera_format;
-        let image_data = self.frame_raw()?;
-        let imagebuf =
-            match ImageBuffer::from_vec(cam_fmt.width(), cam_fmt.height(), image_data.to_vec()) {
-                Some(buf) => {
-                    let rgbbuf: ImageBuffer<Rgb<u8>, Vec<u8>> = buf;
-                    rgbbuf
-                }
-                None => return Err(NokhwaError::ReadFrameError(
-                    "Imagebuffer is not large enough! This is probably a bug, please report it!"
-                        .to_string(),
-                )),
-            };
-        Ok(imagebuf)
+    fn is_stream_open(&self) -> bool {
+        self.pipeline
+            .as_ref()
+            .is_some_and(|(pipeline, _)| pipeline.current_state() == State::Playing)
     }
 
-    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        let bus = match self.pipeline.bus() {
-            Some(bus) => bus,
-            None => {
-                return Err(NokhwaError::ReadFrameError(
-                    "The pipeline has no bus!".to_string(),
-                ))
-            }
-        };
-
-        if let Some(message) = bus.timed_pop(ClockTime::from_seconds(0)) {
-            match message.view() {
-                MessageView::Eos(..) => {
-                    return Err(NokhwaError::ReadFrameError("Stream is ended!".to_string()))
-                }
-                MessageView::Error(err) => {
-                    return Err(NokhwaError::ReadFrameError(format!(
-                        "Bus error: {}",
-                        err.error()
-                    )));
-                }
-                _ => {}
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let sample = self.pull_sample()?;
+        let read_error = |why: &str| NokhwaError::ReadFrameError(why.to_string());
+        let structure = sample
+            .caps()
+            .and_then(|caps| caps.structure(0))
+            .ok_or_else(|| read_error("Sample has no caps"))?;
+        let frame_format = structure_frame_formats(structure)
+            .first()
+            .copied()
+            .ok_or_else(|| read_error(&format!("Unsupported caps {structure}")))?;
+        let resolution = structure_resolutions(structure)
+            .first()
+            .copied()
+            .ok_or_else(|| read_error(&format!("No resolution in caps {structure}")))?;
+        let buffer = sample
+            .buffer()
+            .ok_or_else(|| read_error("Sample has no buffer"))?;
+        let map = buffer
+            .map_readable()
+            .map_err(|why| read_error(&why.to_string()))?;
+
+        let mut frame = Buffer::new(resolution, map.as_slice(), frame_format);
+        // `v4l2src` and most other sources count frames in the offset
+        if buffer.offset() != gstreamer::BUFFER_OFFSET_NONE {
+            frame = frame.with_sequence(buffer.offset());
+        }
+        if let Some(pts) = buffer.pts() {
+            let mut timestamp = FrameTimestamp::new(
+                Duration::from_nanos(pts.nseconds()),
+                TimestampClock::Unknown,
+            );
+            if let Some(duration) = buffer.duration() {
+                timestamp = timestamp.with_duration(Duration::from_nanos(duration.nseconds()));
             }
+            frame = frame.with_timestamp(timestamp);
         }
+        Ok(frame)
+    }
 
-        Ok(Cow::from(self.image_lock.lock().to_vec()))
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let sample = self.pull_sample()?;
+        let buffer = sample
+            .buffer()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Sample has no buffer".to_string()))?;
+        let map = buffer
+            .map_readable()
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+        Ok(Cow::Owned(map.as_slice().to_vec()))
     }
 
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-        if let Err(why) = self.pipeline.set_state(State::Null) {
-            return Err(NokhwaError::StreamShutdownError(format!(
-                "Could not change state: {}",
-                why
-            )));
+        if let Some((pipeline, _)) = self.pipeline.take() {
+            pipeline
+                .set_state(State::Null)
+                .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))?;
         }
         Ok(())
     }
@@ -567,273 +816,6 @@ impl GStreamerCaptureDevice {
 
 impl Drop for GStreamerCaptureDevice {
     fn drop(&mut self) {
-        let _ = self.pipeline.set_state(State::Null);
-    }
-}
-
-#[cfg(target_os = "macos")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
-    match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("autovideosrc location=/dev/video{} ! image/jpeg,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("autovideosrc location=/dev/video{} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        _ => {
-            format!("unsupproted! if you see this, switch to something else!")
-        }
-    }
-}
-
-#[cfg(target_os = "linux")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
-    match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("v4l2src device=/dev/video{} ! image/jpeg, width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("v4l2src device=/dev/video{} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        _ => {
-            format!("unsupproted! if you see this, switch to something else!")
-        }
-    }
-}
-
-#[cfg(target_os = "windows")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
-    match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("ksvideosrc device_index={} ! image/jpeg, width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("ksvideosrc device_index={} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        _ => {
-            format!("unsupproted! if you see this, switch to something else!")
-        }
+        let _ = self.stop_stream();
     }
 }
-
-#[allow(clippy::too_many_lines)]
-#[allow(clippy::let_and_return)]
-fn generate_pipeline(fmt: CameraFormat, index: usize) -> Result<PipelineGenRet, NokhwaError> {
-    let pipeline =
-        match gstreamer::parse_launch(webcam_pipeline(format!("{}", index).as_str(), fmt).as_str())
-        {
-            Ok(p) => p,
-            Err(why) => {
-                return Err(NokhwaError::OpenDeviceError(
-                    index.to_string(),
-                    format!(
-                        "Failed to open pipeline with args {}: {}",
-                        webcam_pipeline(format!("{}", index).as_str(), fmt),
-                        why
-                    ),
-                ))
-            }
-        };
-
-    let sink = match pipeline
-        .clone()
-        .dynamic_cast::<Bin>()
-        .unwrap()
-        .by_name("appsink")
-    {
-        Some(s) => s,
-        None => {
-            return Err(NokhwaError::OpenDeviceError(
-                index.to_string(),
-                "Failed to get sink element!".to_string(),
-            ))
-        }
-    };
-
-    let appsink = match sink.dynamic_cast::<AppSink>() {
-        Ok(aps) => aps,
-        Err(_) => {
-            return Err(NokhwaError::OpenDeviceError(
-                index.to_string(),
-                "Failed to get sink element as appsink".to_string(),
-            ))
-        }
-    };
-
-    pipeline.set_state(State::Playing).unwrap();
-
-    let image_lock = Arc::new(Mutex::new(ImageBuffer::default()));
-    let img_lck_clone = image_lock.clone();
-
-    appsink.set_callbacks(
-        AppSinkCallbacks::builder()
-            .new_sample(move |appsink| {
-                let sample = appsink.pull_sample().map_err(|_| FlowError::Eos)?;
-                let sample_caps = if let Some(c) = sample.caps() {
-                    c
-                } else {
-                    element_error!(
-                        appsink,
-                        ResourceError::Failed,
-                        ("Failed to get caps of sample")
-                    );
-                    return Err(FlowError::Error);
-                };
-
-                let video_info = match VideoInfo::from_caps(sample_caps) {
-                    Ok(vi) => vi, // help let me outtttttt
-                    Err(why) => {
-                        element_error!(
-                            appsink,
-                            ResourceError::Failed,
-                            (format!("Failed to get videoinfo from caps: {}", why).as_str())
-                        );
-
-                        return Err(FlowError::Error);
-                    }
-                };
-
-                let buffer = if let Some(buf) = sample.buffer() {
-                    buf
-                } else {
-                    element_error!(
-                        appsink,
-                        ResourceError::Failed,
-                        ("Failed to get buffer from sample")
-                    );
-                    return Err(FlowError::Error);
-                };
-
-                let buffer_map = match buffer.map_readable() {
-                    Ok(m) => m,
-                    Err(why) => {
-                        element_error!(
-                            appsink,
-                            ResourceError::Failed,
-                            (format!("Failed to map buffer to readablemap: {}", why).as_str())
-                        );
-
-                        return Err(FlowError::Error);
-                    }
-                };
-
-                let channels = if video_info.has_alpha() { 4 } else { 3 };
-
-                let image_buffer = match video_info.format() {
-                    VideoFormat::Yuy2 => {
-                        let mut decoded_buffer = match yuyv422_to_rgb(&buffer_map, false) {
-                            Ok(buf) => buf,
-                            Err(why) => {
-                                element_error!(
-                                    appsink,
-                                    ResourceError::Failed,
-                                    (format!("Failed to make yuy2 into rgb888: {}", why).as_str())
-                                );
-
-                                return Err(FlowError::Error);
-                            }
-                        };
-
-                        decoded_buffer.resize(
-                            (video_info.width() * video_info.height() * channels) as usize,
-                            0_u8,
-                        );
-
-                        let image = if let Some(i) = ImageBuffer::from_vec(
-                            video_info.width(),
-                            video_info.height(),
-                            decoded_buffer,
-                        ) {
-                            let rgb: ImageBuffer<Rgb<u8>, Vec<u8>> = i;
-                            rgb
-                        } else {
-                            element_error!(
-                                appsink,
-                                ResourceError::Failed,
-                                ("Failed to make rgb buffer into imagebuffer")
-                            );
-
-                            return Err(FlowError::Error);
-                        };
-                        image
-                    }
-                    VideoFormat::Rgb => {
-                        let mut decoded_buffer = buffer_map.as_slice().to_vec();
-                        decoded_buffer.resize(
-                            (video_info.width() * video_info.height() * channels) as usize,
-                            0_u8,
-                        );
-                        let image = if let Some(i) = ImageBuffer::from_vec(
-                            video_info.width(),
-                            video_info.height(),
-                            decoded_buffer,
-                        ) {
-                            let rgb: ImageBuffer<Rgb<u8>, Vec<u8>> = i;
-                            rgb
-                        } else {
-                            element_error!(
-                                appsink,
-                                ResourceError::Failed,
-                                ("Failed to make rgb buffer into imagebuffer")
-                            );
-
-                            return Err(FlowError::Error);
-                        };
-                        image
-                    }
-                    // MJPEG
-                    VideoFormat::Encoded => {
-                        let mut decoded_buffer = match mjpeg_to_rgb(&buffer_map, false) {
-                            Ok(buf) => buf,
-                            Err(why) => {
-                                element_error!(
-                                    appsink,
-                                    ResourceError::Failed,
-                                    (format!("Failed to make yuy2 into rgb888: {}", why).as_str())
-                                );
-
-                                return Err(FlowError::Error);
-                            }
-                        };
-
-                        decoded_buffer.resize(
-                            (video_info.width() * video_info.height() * channels) as usize,
-                            0_u8,
-                        );
-
-                        let image = if let Some(i) = ImageBuffer::from_vec(
-                            video_info.width(),
-                            video_info.height(),
-                            decoded_buffer,
-                        ) {
-                            let rgb: ImageBuffer<Rgb<u8>, Vec<u8>> = i;
-                            rgb
-                        } else {
-                            element_error!(
-                                appsink,
-                                ResourceError::Failed,
-                                ("Failed to make rgb buffer into imagebuffer")
-                            );
-
-                            return Err(FlowError::Error);
-                        };
-                        image
-                    }
-                    _ => {
-                        element_error!(
-                            appsink,
-                            ResourceError::Failed,
-                            ("Unsupported video format")
-                        );
-                        return Err(FlowError::Error);
-                    }
-                };
-
-                *img_lck_clone.lock() = image_buffer;
-
-                Ok(FlowSuccess::Ok)
-            })
-            .build(),
-    );
-    Ok((pipeline, appsink, image_lock))
-}
@@ -0,0 +1,803 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use libcamera::{
+    camera::{ActiveCamera, Camera, CameraConfiguration, CameraConfigurationStatus},
+    camera_manager::CameraManager,
+    control::ControlList,
+    controls,
+    framebuffer::AsFrameBuffer,
+    framebuffer_allocator::{FrameBuffer, FrameBufferAllocator},
+    framebuffer_map::MemoryMappedFrameBuffer,
+    geometry::Size,
+    pixel_format::PixelFormat,
+    properties,
+    request::{Request, RequestStatus, ReuseFlag},
+    stream::{Stream, StreamRole},
+};
+use nokhwa_core::{
+    buffer::{Buffer, FrameTimestamp, TimestampClock},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlMenuEntry,
+        ControlValueDescription, ControlValueSetter, FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver},
+        OnceLock,
+    },
+    time::Duration,
+};
+
+/// The frame rate reported for every resolution, `libcamera` does not enumerate frame rates per stream size.
+const DEFAULT_FRAME_RATE: u32 = 30;
+/// How long [`frame()`](CaptureTrait::frame) waits for a request to complete.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The automatic white balance mode of a Raspberry Pi camera (`libcamera` `AwbMode`). A [`ControlValueDescription::Menu`] of the lighting
+/// the white balance is tuned for: `Auto`, `Incandescent`, `Tungsten`, `Fluorescent`, `Indoor`, `Daylight`, `Cloudy` and `Custom`.
+pub const PI_AWB_MODE: KnownCameraControl =
+    KnownCameraControl::Other(controls::ControlId::AwbMode as u128);
+/// The sensitivity of a Raspberry Pi camera as an ISO value, e.g. `100`. Sets the analogue gain to `ISO / 100`, as `rpicam-apps` does,
+/// which fixes the gain the automatic exposure would otherwise choose.
+// ISO is not a libcamera control, so it uses an ID outside of libcamera's
+pub const PI_ISO: KnownCameraControl = KnownCameraControl::Other(0x7069_0000);
+/// How a Raspberry Pi camera avoids flicker from artificial lighting (`libcamera` `AeFlickerMode`). A [`ControlValueDescription::Menu`] of
+/// `Off`, `Manual` (using [`PI_FLICKER_PERIOD`]) and `Auto`.
+pub const PI_FLICKER_MODE: KnownCameraControl =
+    KnownCameraControl::Other(controls::ControlId::AeFlickerMode as u128);
+/// The period of the lighting flicker to avoid in [`PI_FLICKER_MODE`] `Manual`, in microseconds (`libcamera` `AeFlickerPeriod`).
+/// `10000` for 50 Hz mains, `8333` for 60 Hz mains.
+pub const PI_FLICKER_PERIOD: KnownCameraControl =
+    KnownCameraControl::Other(controls::ControlId::AeFlickerPeriod as u128);
+
+/// The [`KnownCameraControl`]s that map to a `libcamera` control.
+const SUPPORTED_CONTROLS: [KnownCameraControl; 12] = [
+    KnownCameraControl::Brightness,
+    KnownCameraControl::Contrast,
+    KnownCameraControl::Saturation,
+    KnownCameraControl::Sharpness,
+    KnownCameraControl::WhiteBalance,
+    KnownCameraControl::Gain,
+    KnownCameraControl::Exposure,
+    KnownCameraControl::Focus,
+    PI_AWB_MODE,
+    PI_ISO,
+    PI_FLICKER_MODE,
+    PI_FLICKER_PERIOD,
+];
+// menu items of the enum controls, indexed by their libcamera value
+const AWB_MODES: [&str; 8] = [
+    "Auto",
+    "Incandescent",
+    "Tungsten",
+    "Fluorescent",
+    "Indoor",
+    "Daylight",
+    "Cloudy",
+    "Custom",
+];
+const FLICKER_MODES: [&str; 3] = ["Off", "Manual", "Auto"];
+
+// libcamera only allows one camera manager per process, and its camera list is thread-safe.
+struct SharedCameraManager(CameraManager);
+
+unsafe impl Send for SharedCameraManager {}
+unsafe impl Sync for SharedCameraManager {}
+
+static CAMERA_MANAGER: OnceLock<Result<SharedCameraManager, String>> = OnceLock::new();
+
+fn camera_manager() -> Result<&'static CameraManager, NokhwaError> {
+    CAMERA_MANAGER
+        .get_or_init(|| {
+            CameraManager::new()
+                .map(SharedCameraManager)
+                .map_err(|why| why.to_string())
+        })
+        .as_ref()
+        .map(|manager| &manager.0)
+        .map_err(|why| NokhwaError::InitializeError {
+            backend: ApiBackend::LibCamera,
+            error: why.clone(),
+        })
+}
+
+/// Query the system for `libcamera` cameras.
+/// # Errors
+/// If the `libcamera` camera manager fails to start, this will error.
+#[allow(clippy::cast_possible_truncation)]
+pub fn query_libcamera() -> Result<Vec<CameraInfo>, NokhwaError> {
+    let cameras = camera_manager()?.cameras();
+    Ok((0..cameras.len())
+        .filter_map(|index| {
+            cameras
+                .get(index)
+                .map(|camera| camera_info(&camera, &CameraIndex::Index(index as u32)))
+        })
+        .collect())
+}
+
+fn camera_info(camera: &Camera, index: &CameraIndex) -> CameraInfo {
+    let name = camera
+        .properties()
+        .get::<properties::Model>()
+        .map_or_else(|_| camera.id().to_string(), |model| model.to_string());
+    CameraInfo::new(
+        &name,
+        &format!("libcamera Camera @ {}", camera.id()),
+        camera.id(),
+        index,
+    )
+}
+
+fn fourcc(code: [u8; 4]) -> u32 {
+    u32::from_le_bytes(code)
+}
+
+/// Converts a [`FrameFormat`] into a `libcamera` (DRM fourcc) [`PixelFormat`].
+#[must_use]
+pub fn frameformat_to_pixel_format(frame_format: FrameFormat) -> Option<PixelFormat> {
+    let code = match frame_format {
+        FrameFormat::MJpeg => *b"MJPG",
+        FrameFormat::Yuv422 => *b"YUYV",
+        FrameFormat::Uyv422 => *b"UYVY",
+        FrameFormat::Nv12 => *b"NV12",
+        FrameFormat::Nv21 => *b"NV21",
+        FrameFormat::Yv12 => *b"YV12",
+        FrameFormat::Luma8 => *b"R8  ",
+        FrameFormat::Luma16 => *b"R16 ",
+        // DRM fourccs name the components from the most significant bit
+        FrameFormat::Rgb8 => *b"BG24",
+        FrameFormat::RgbA8 => *b"AB24",
+        _ => return None,
+    };
+    Some(PixelFormat::new(fourcc(code), 0))
+}
+
+/// Converts a `libcamera` (DRM fourcc) [`PixelFormat`] into a [`FrameFormat`].
+#[must_use]
+pub fn pixel_format_to_frameformat(pixel_format: PixelFormat) -> Option<FrameFormat> {
+    match &pixel_format.fourcc().to_le_bytes() {
+        b"MJPG" => Some(FrameFormat::MJpeg),
+        b"YUYV" => Some(FrameFormat::Yuv422),
+        b"UYVY" => Some(FrameFormat::Uyv422),
+        b"NV12" => Some(FrameFormat::Nv12),
+        b"NV21" => Some(FrameFormat::Nv21),
+        b"YV12" => Some(FrameFormat::Yv12),
+        b"R8  " => Some(FrameFormat::Luma8),
+        b"R16 " => Some(FrameFormat::Luma16),
+        b"BG24" => Some(FrameFormat::Rgb8),
+        b"AB24" => Some(FrameFormat::RgbA8),
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn set_control(
+    list: &mut ControlList,
+    control: KnownCameraControl,
+    value: f64,
+) -> Result<(), NokhwaError> {
+    let result = match control {
+        KnownCameraControl::Brightness => list.set(controls::Brightness(value as f32)),
+        KnownCameraControl::Contrast => list.set(controls::Contrast(value as f32)),
+        KnownCameraControl::Saturation => list.set(controls::Saturation(value as f32)),
+        KnownCameraControl::Sharpness => list.set(controls::Sharpness(value as f32)),
+        KnownCameraControl::WhiteBalance => list.set(controls::ColourTemperature(value as i32)),
+        KnownCameraControl::Gain => list.set(controls::AnalogueGain(value as f32)),
+        KnownCameraControl::Exposure => list.set(controls::ExposureTime(value as i32)),
+        KnownCameraControl::Focus => list.set(controls::LensPosition(value as f32)),
+        PI_AWB_MODE => match controls::AwbMode::try_from(value as i32) {
+            Ok(mode) => list.set(mode),
+            Err(_) => return Err(no_such_mode(control, value)),
+        },
+        PI_ISO => list.set(controls::AnalogueGain((value / 100.0) as f32)),
+        PI_FLICKER_MODE => match controls::AeFlickerMode::try_from(value as i32) {
+            Ok(mode) => list.set(mode),
+            Err(_) => return Err(no_such_mode(control, value)),
+        },
+        PI_FLICKER_PERIOD => list.set(controls::AeFlickerPeriod(value as i32)),
+        _ => return Err(NokhwaError::UnsupportedOperationError(ApiBackend::LibCamera)),
+    };
+    result.map_err(|why| NokhwaError::SetPropertyError {
+        property: control.to_string(),
+        value: value.to_string(),
+        error: why.to_string(),
+    })
+}
+
+fn no_such_mode(control: KnownCameraControl, value: f64) -> NokhwaError {
+    NokhwaError::SetPropertyError {
+        property: control_name(control),
+        value: value.to_string(),
+        error: "no such mode".to_string(),
+    }
+}
+
+fn control_name(control: KnownCameraControl) -> String {
+    match control {
+        PI_AWB_MODE => "AWB Mode".to_string(),
+        PI_ISO => "ISO".to_string(),
+        PI_FLICKER_MODE => "Flicker Mode".to_string(),
+        PI_FLICKER_PERIOD => "Flicker Period".to_string(),
+        control => control.to_string(),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn control_description(control: KnownCameraControl, value: f64) -> ControlValueDescription {
+    let menu = |items: &[&str]| ControlValueDescription::Menu {
+        value: value as i64,
+        items: items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index as i64, ControlMenuEntry::Name((*item).to_string())))
+            .collect(),
+        default: 0,
+    };
+    match control {
+        PI_AWB_MODE => menu(&AWB_MODES),
+        PI_FLICKER_MODE => menu(&FLICKER_MODES),
+        PI_ISO => ControlValueDescription::Integer {
+            value: value as i64,
+            default: 100,
+            step: 1,
+        },
+        PI_FLICKER_PERIOD => ControlValueDescription::Integer {
+            value: value as i64,
+            default: 10000,
+            step: 1,
+        },
+        _ => ControlValueDescription::Float {
+            value,
+            default: 0.0,
+            step: 0.0,
+        },
+    }
+}
+
+fn control_value(list: &ControlList, control: KnownCameraControl) -> Option<f64> {
+    match control {
+        KnownCameraControl::Brightness => list
+            .get::<controls::Brightness>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::Contrast => list.get::<controls::Contrast>().ok().map(|v| f64::from(v.0)),
+        KnownCameraControl::Saturation => list
+            .get::<controls::Saturation>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::Sharpness => list
+            .get::<controls::Sharpness>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::WhiteBalance => list
+            .get::<controls::ColourTemperature>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::Gain => list
+            .get::<controls::AnalogueGain>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::Exposure => list
+            .get::<controls::ExposureTime>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        KnownCameraControl::Focus => list
+            .get::<controls::LensPosition>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        PI_AWB_MODE => list
+            .get::<controls::AwbMode>()
+            .ok()
+            .map(|mode| f64::from(mode as i32)),
+        PI_ISO => list
+            .get::<controls::AnalogueGain>()
+            .ok()
+            .map(|v| f64::from(v.0) * 100.0),
+        PI_FLICKER_MODE => list
+            .get::<controls::AeFlickerMode>()
+            .ok()
+            .map(|mode| f64::from(mode as i32)),
+        PI_FLICKER_PERIOD => list
+            .get::<controls::AeFlickerPeriod>()
+            .ok()
+            .map(|v| f64::from(v.0)),
+        _ => None,
+    }
+}
+
+// libcamera has no frame rate setting, the frame rate follows from the frame duration (in microseconds).
+#[allow(clippy::cast_possible_truncation)]
+fn set_frame_rate_control(list: &mut ControlList, frame_rate: FrameRate) -> Result<(), NokhwaError> {
+    let duration = (1_000_000.0 / f64::from(frame_rate.as_float())) as i64;
+    list.set(controls::FrameDurationLimits([duration, duration]))
+        .map_err(|why| NokhwaError::SetPropertyError {
+            property: "FrameDurationLimits".to_string(),
+            value: duration.to_string(),
+            error: why.to_string(),
+        })
+}
+
+fn queue_request(
+    camera: &ActiveCamera<'static>,
+    mut request: Request,
+    pending_controls: &mut HashMap<KnownCameraControl, f64>,
+) -> Result<(), NokhwaError> {
+    for (control, value) in pending_controls.drain() {
+        set_control(request.controls_mut(), control, value)?;
+    }
+    camera
+        .queue_request(request)
+        .map_err(|why| NokhwaError::ReadFrameError(format!("Failed to queue request: {why}")))
+}
+
+struct LibCameraStream {
+    stream: Stream,
+    completed: Receiver<Request>,
+    // the request whose buffer was last handed out, requeued on the next frame
+    current: Option<Request>,
+    // keeps the buffers of the requests allocated
+    _allocator: FrameBufferAllocator,
+}
+
+/// The backend struct that interfaces with `libcamera`, the camera stack for devices that need an ISP pipeline
+/// (e.g. Raspberry Pi CSI cameras, Intel IPU6 laptop sensors) and are not usable through plain V4L2.
+///
+/// To see what this does, please see [`CaptureTrait`]
+/// # Quirks
+/// - [`CameraIndex::Index`] is the position in the `libcamera` camera list, [`CameraIndex::String`] is a `libcamera` camera ID.
+/// - [`CameraInfo`]'s description will be "libcamera Camera @ {id}", its misc field will be the camera ID.
+/// - `libcamera` does not enumerate frame rates, so [`compatible_list_by_resolution()`](CaptureTrait::compatible_list_by_resolution) lists 30 FPS for every resolution. Other frame rates may still be set.
+/// - Controls set with [`set_camera_control()`](CaptureTrait::set_camera_control) are applied with the next queued request, and [`camera_control()`](CaptureTrait::camera_control) only reports values once a frame has been captured.
+/// - Only a single stream is configured, using the `VideoRecording` role.
+/// - Raspberry Pi tuning controls are available as [`PI_AWB_MODE`], [`PI_ISO`], [`PI_FLICKER_MODE`] and [`PI_FLICKER_PERIOD`]. Lens shading correction
+///   comes from the camera's tuning file, `libcamera` has no control for it.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-libcamera")))]
+pub struct LibCameraCaptureDevice {
+    camera_info: CameraInfo,
+    camera_format: Option<CameraFormat>,
+    pending_controls: HashMap<KnownCameraControl, f64>,
+    control_values: HashMap<KnownCameraControl, f64>,
+    // dropped before the camera it was configured on
+    stream: Option<LibCameraStream>,
+    camera: ActiveCamera<'static>,
+}
+
+impl LibCameraCaptureDevice {
+    /// Creates a new capture device using the `libcamera` backend. The camera is acquired exclusively until this is dropped.
+    /// # Errors
+    /// If the camera manager fails to start, the camera does not exist, or it is already acquired by another process, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let cameras = camera_manager()?.cameras();
+        let camera = match index {
+            CameraIndex::Index(idx) => cameras.get(*idx as usize),
+            CameraIndex::String(id) => (0..cameras.len())
+                .filter_map(|idx| cameras.get(idx))
+                .find(|camera| camera.id() == id),
+        }
+        .ok_or_else(|| {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+        })?;
+
+        let camera_info = camera_info(&camera, index);
+        let camera = camera
+            .acquire()
+            .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?;
+
+        Ok(LibCameraCaptureDevice {
+            camera_info,
+            camera_format: None,
+            pending_controls: HashMap::new(),
+            control_values: HashMap::new(),
+            stream: None,
+            camera,
+        })
+    }
+
+    /// Gets the `libcamera` ID of the camera.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        self.camera.id()
+    }
+
+    fn generate_configuration(&self) -> Result<CameraConfiguration, NokhwaError> {
+        self.camera
+            .generate_configuration(&[StreamRole::VideoRecording])
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: "CameraConfiguration".to_string(),
+                error: "No configuration for the VideoRecording role".to_string(),
+            })
+    }
+
+    fn validated_configuration(
+        &self,
+        camera_format: CameraFormat,
+    ) -> Result<CameraConfiguration, NokhwaError> {
+        let pixel_format = frameformat_to_pixel_format(camera_format.format()).ok_or_else(|| {
+            NokhwaError::SetPropertyError {
+                property: "FrameFormat".to_string(),
+                value: camera_format.format().to_string(),
+                error: "unsupported".to_string(),
+            }
+        })?;
+
+        let mut configuration = self.generate_configuration()?;
+        if let Some(mut stream_config) = configuration.get_mut(0) {
+            stream_config.set_pixel_format(pixel_format);
+            stream_config.set_size(Size {
+                width: camera_format.width(),
+                height: camera_format.height(),
+            });
+        }
+
+        match configuration.validate() {
+            CameraConfigurationStatus::Valid => Ok(configuration),
+            CameraConfigurationStatus::Adjusted | CameraConfigurationStatus::Invalid => {
+                Err(NokhwaError::SetPropertyError {
+                    property: "CameraFormat".to_string(),
+                    value: camera_format.to_string(),
+                    error: "Rejected".to_string(),
+                })
+            }
+        }
+    }
+
+    fn next_request(&mut self) -> Result<&Request, NokhwaError> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+
+        if let Some(mut request) = stream.current.take() {
+            request.reuse(ReuseFlag::REUSE_BUFFERS);
+            queue_request(&self.camera, request, &mut self.pending_controls)?;
+        }
+
+        let request = stream
+            .completed
+            .recv_timeout(FRAME_TIMEOUT)
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+        if request.status() != RequestStatus::Complete {
+            // hand the buffer back so the stream does not starve
+            stream.current = Some(request);
+            return Err(NokhwaError::ReadFrameError(
+                "Request was cancelled".to_string(),
+            ));
+        }
+
+        for control in SUPPORTED_CONTROLS {
+            if let Some(value) = control_value(request.metadata(), control) {
+                self.control_values.insert(control, value);
+            }
+        }
+        Ok(stream.current.insert(request))
+    }
+
+    fn frame_planes(&mut self) -> Result<(Vec<&[u8]>, u32, u64), NokhwaError> {
+        let stream = self.stream.as_ref().map(|stream| stream.stream.clone());
+        let request = self.next_request()?;
+        let framebuffer: &MemoryMappedFrameBuffer<FrameBuffer> = stream
+            .and_then(|stream| request.buffer(&stream))
+            .ok_or_else(|| NokhwaError::ReadFrameError("Request has no buffer".to_string()))?;
+        let metadata = framebuffer
+            .metadata()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Buffer has no metadata".to_string()))?;
+
+        let plane_metadata = metadata.planes();
+        let planes = framebuffer
+            .data()
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| match plane_metadata.get(index) {
+                // the buffer may be bigger than the (compressed) frame
+                Some(plane) => &data[..(plane.bytes_used as usize).min(data.len())],
+                None => data,
+            })
+            .collect();
+        Ok((planes, metadata.sequence(), metadata.timestamp()))
+    }
+}
+
+impl Backend for LibCameraCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::LibCamera;
+}
+
+impl CaptureTrait for LibCameraCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        let configuration = self.generate_configuration()?;
+        let stream_config = configuration
+            .get(0)
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: "StreamConfiguration".to_string(),
+                error: "empty configuration".to_string(),
+            })?;
+
+        let frame_format = match pixel_format_to_frameformat(stream_config.get_pixel_format()) {
+            Some(frame_format) => frame_format,
+            None => *self
+                .compatible_fourcc()?
+                .first()
+                .ok_or_else(|| NokhwaError::GetPropertyError {
+                    property: "FrameFormat".to_string(),
+                    error: "no supported frame format".to_string(),
+                })?,
+        };
+        let size = stream_config.get_size();
+        self.set_camera_format(CameraFormat::new(
+            Resolution::new(size.width, size.height),
+            frame_format,
+            FrameRate::new_integer(DEFAULT_FRAME_RATE)?,
+        ))
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::LibCamera
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        // the configuration only changes when we configure the camera
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        self.validated_configuration(new_fmt)?;
+
+        let current_format = self.camera_format;
+        self.camera_format = Some(new_fmt);
+        if self.is_stream_open() {
+            self.stop_stream()?;
+            if let Err(why) = self.open_stream() {
+                self.camera_format = current_format;
+                return Err(why);
+            }
+        }
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let pixel_format =
+            frameformat_to_pixel_format(fourcc).ok_or(NokhwaError::GetPropertyError {
+                property: "FrameFormat".to_string(),
+                error: "unsupported".to_string(),
+            })?;
+        let frame_rate = FrameRate::new_integer(DEFAULT_FRAME_RATE)?;
+
+        let configuration = self.generate_configuration()?;
+        Ok(configuration
+            .get(0)
+            .map(|stream_config| stream_config.formats().sizes(pixel_format))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|size| (Resolution::new(size.width, size.height), vec![frame_rate]))
+            .collect())
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        let configuration = self.generate_configuration()?;
+        Ok(configuration
+            .get(0)
+            .map(|stream_config| {
+                stream_config
+                    .formats()
+                    .pixel_formats()
+                    .into_iter()
+                    .filter_map(pixel_format_to_frameformat)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::MJpeg, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        if !SUPPORTED_CONTROLS.contains(&control) {
+            return Err(NokhwaError::UnsupportedOperationError(ApiBackend::LibCamera));
+        }
+        let value = self
+            .pending_controls
+            .get(&control)
+            .or_else(|| self.control_values.get(&control))
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: control_name(control),
+                error: "not yet reported by the camera".to_string(),
+            })?;
+        Ok(CameraControl::new(
+            control,
+            control_name(control),
+            control_description(control, *value),
+            vec![],
+            true,
+        ))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(SUPPORTED_CONTROLS
+            .into_iter()
+            .filter_map(|control| self.camera_control(control).ok())
+            .collect())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let control_val = match value {
+            ControlValueSetter::Integer(i) | ControlValueSetter::EnumValue(i) => i as f64,
+            ControlValueSetter::Float(f) => f,
+            ControlValueSetter::Boolean(b) => f64::from(u8::from(b)),
+            val => {
+                return Err(NokhwaError::SetPropertyError {
+                    property: id.to_string(),
+                    value: val.to_string(),
+                    error: "unsupported value".to_string(),
+                })
+            }
+        };
+
+        // catch unsupported controls now rather than when the request is queued
+        set_control(&mut ControlList::new(), id, control_val)?;
+        self.pending_controls.insert(id, control_val);
+        Ok(())
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.is_stream_open() {
+            self.stop_stream()?;
+        }
+
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let mut configuration = self.validated_configuration(camera_format)?;
+        self.camera
+            .configure(&mut configuration)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        let stream = configuration
+            .get(0)
+            .and_then(|stream_config| stream_config.stream())
+            .ok_or_else(|| NokhwaError::OpenStreamError("No stream configured".to_string()))?;
+
+        let mut allocator = FrameBufferAllocator::new(&self.camera);
+        let buffers = allocator
+            .alloc(&stream)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        let mut requests = Vec::with_capacity(buffers.len());
+        for buffer in buffers {
+            let buffer = MemoryMappedFrameBuffer::new(buffer)
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+            let mut request = self.camera.create_request(None).ok_or_else(|| {
+                NokhwaError::OpenStreamError("Failed to create request".to_string())
+            })?;
+            request
+                .add_buffer(&stream, buffer)
+                .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+            requests.push(request);
+        }
+
+        let (sender, completed) = mpsc::channel();
+        self.camera.on_request_completed(move |request| {
+            // the receiver is gone once the stream is stopped, the request is dropped with it
+            let _ = sender.send(request);
+        });
+
+        let mut start_controls = ControlList::new();
+        set_frame_rate_control(&mut start_controls, camera_format.frame_rate())?;
+        self.camera
+            .start(Some(&start_controls))
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        for request in requests {
+            queue_request(&self.camera, request, &mut self.pending_controls)?;
+        }
+
+        self.stream = Some(LibCameraStream {
+            stream,
+            completed,
+            current: None,
+            _allocator: allocator,
+        });
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let (planes, sequence, timestamp) = self.frame_planes()?;
+        let buffer = match planes.as_slice() {
+            [plane] => Buffer::new(camera_format.resolution(), plane, camera_format.format()),
+            planes => {
+                Buffer::with_planes(camera_format.resolution(), planes, camera_format.format())
+            }
+        };
+        Ok(buffer.with_sequence(u64::from(sequence)).with_timestamp(
+            FrameTimestamp::new(Duration::from_nanos(timestamp), TimestampClock::Monotonic),
+        ))
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let (planes, _, _) = self.frame_planes()?;
+        match planes.as_slice() {
+            [plane] => Ok(Cow::Borrowed(*plane)),
+            planes => Ok(Cow::Owned(planes.concat())),
+        }
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        if let Some(stream) = self.stream.take() {
+            self.camera
+                .stop()
+                .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))?;
+            drop(stream);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LibCameraCaptureDevice {
+    fn drop(&mut self) {
+        let _ = self.stop_stream();
+    }
+}
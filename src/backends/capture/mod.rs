@@ -21,11 +21,11 @@ macro_rules! resolver_platform {
         $(
             paste::paste! {
                 #[cfg(all(feature = $feat, target_os = $os))]
-                pub(crate) fn [< backend_gen_ $name >](index: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
-                    $item::new(index).map(|x| std::boxed::Box::new(x.into()))
+                pub(crate) fn [< backend_gen_ $name >](index: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                    $item::new(index).map(|device| std::boxed::Box::new(device) as Box<dyn nokhwa_core::traits::CaptureTrait>)
                 }
                 #[cfg(not(all(feature = $feat, target_os = $os)))]
-                pub(crate) fn [< backend_gen_ $name >](_: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                pub(crate) fn [< backend_gen_ $name >](_: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
                     return Err(nokhwa_core::error::NokhwaError::GeneralError("no feature".to_string()))
                 }
             }
@@ -39,12 +39,12 @@ macro_rules! resolver_platform_2 {
     ) => {
         $(
             paste::paste! {
-                #[cfg(all(feature = $feat, target_os = $os1, target_os = $os2))]
-                pub(crate) fn [< backend_gen_ $name >](index: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
-                    $item::new(index).map(|x| std::boxed::Box::new(x.into()))
+                #[cfg(all(feature = $feat, any(target_os = $os1, target_os = $os2)))]
+                pub(crate) fn [< backend_gen_ $name >](index: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                    $item::new(index).map(|device| std::boxed::Box::new(device) as Box<dyn nokhwa_core::traits::CaptureTrait>)
                 }
-                #[cfg(not(all(feature = $feat, target_os = $os1, target_os = $os2)))]
-                pub(crate) fn [< backend_gen_ $name >](_: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                #[cfg(not(all(feature = $feat, any(target_os = $os1, target_os = $os2))))]
+                pub(crate) fn [< backend_gen_ $name >](_: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
                     return Err(nokhwa_core::error::NokhwaError::GeneralError("no feature".to_string()))
                 }
             }
@@ -59,11 +59,11 @@ macro_rules! resolver_cross_platform {
         $(
             paste::paste! {
                 #[cfg(all(feature = $feat))]
-                pub(crate) fn [< backend_gen_ $name >](index: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
-                    $item::new(index).map(|x| std::boxed::Box::new(x.into()))
+                pub(crate) fn [< backend_gen_ $name >](index: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                    $item::new(index).map(|device| std::boxed::Box::new(device) as Box<dyn nokhwa_core::traits::CaptureTrait>)
                 }
                 #[cfg(not(all(feature = $feat)))]
-                pub(crate) fn [< backend_gen_ $name >](_: nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::Backend + nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
+                pub(crate) fn [< backend_gen_ $name >](_: &nokhwa_core::types::CameraIndex) -> Result<Box<dyn nokhwa_core::traits::CaptureTrait>, nokhwa_core::error::NokhwaError> {
                     return Err(nokhwa_core::error::NokhwaError::GeneralError("no feature".to_string()))
                 }
             }
@@ -78,11 +78,29 @@ resolver_platform!(
         "linux",
         nokhwa_bindings_linux::V4LCaptureDevice
     ),
+    (
+        libcamera,
+        "input-libcamera",
+        "linux",
+        libcamera_backend::LibCameraCaptureDevice
+    ),
+    (
+        pipewire,
+        "input-pipewire",
+        "linux",
+        pipewire_backend::PipeWireCaptureDevice
+    ),
     (
         msf,
         "input-msmf",
         "windows",
         msmf_backend::MediaFoundationCaptureDevice
+    ),
+    (
+        android,
+        "input-android",
+        "android",
+        android_backend::AndroidCaptureDevice
     )
 );
 
@@ -95,12 +113,77 @@ resolver_platform_2!((
 ));
 
 resolver_cross_platform!(
-    (opencv, "input-opencv", opencv_backend::OpenCvCaptureDevice) // TODO: wasm
+    (opencv, "input-opencv", opencv_backend::OpenCvCaptureDevice), // TODO: wasm
+    (uvc, "input-uvc", uvc_backend::UVCCaptureDevice),
+    (aravis, "input-aravis", aravis_backend::AravisCaptureDevice),
+    (gst, "input-gst", gst_backend::GStreamerCaptureDevice),
+    (ffmpeg, "input-ffmpeg", ffmpeg_backend::FfmpegCaptureDevice),
+    (decklink, "input-decklink", decklink_backend::DeckLinkCaptureDevice)
 );
 
 #[cfg(all(feature = "input-v4l", target_os = "linux"))]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-v4l")))]
-pub use nokhwa_bindings_linux::V4LCaptureDevice;
+pub use nokhwa_bindings_linux::{
+    check_usb_bandwidth, CaptureMemory, CodecControl, DmaBufFrame, ImportedDmaBuf,
+    MultiCameraPoller, MultiCameraPollerHandle, PolledCameraId, UsbBandwidth, UserBuffer,
+    UserPtrFrame, V4LCaptureDevice, V4LCaptureDeviceBuilder, V4LLoopbackDevice,
+};
+#[cfg(all(feature = "input-v4l", target_os = "linux"))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-v4l")))]
+pub use nokhwa_bindings_linux::media;
+#[cfg(any(
+    all(feature = "input-libcamera", target_os = "linux"),
+    all(
+        feature = "docs-only",
+        feature = "docs-nolink",
+        feature = "input-libcamera"
+    )
+))]
+mod libcamera_backend;
+#[cfg(any(
+    all(feature = "input-libcamera", target_os = "linux"),
+    all(
+        feature = "docs-only",
+        feature = "docs-nolink",
+        feature = "input-libcamera"
+    )
+))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-libcamera")))]
+pub use libcamera_backend::{
+    frameformat_to_pixel_format, pixel_format_to_frameformat, query_libcamera,
+    LibCameraCaptureDevice, PI_AWB_MODE, PI_FLICKER_MODE, PI_FLICKER_PERIOD, PI_ISO,
+};
+#[cfg(any(
+    all(feature = "input-pipewire", target_os = "linux"),
+    all(
+        feature = "docs-only",
+        feature = "docs-nolink",
+        feature = "input-pipewire"
+    )
+))]
+mod pipewire_backend;
+#[cfg(any(
+    all(feature = "input-pipewire", target_os = "linux"),
+    all(
+        feature = "docs-only",
+        feature = "docs-nolink",
+        feature = "input-pipewire"
+    )
+))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-pipewire")))]
+pub use pipewire_backend::{
+    pipewire_access_granted, query_pipewire, request_pipewire_access, PipeWireCaptureDevice,
+};
+#[cfg(all(feature = "input-android", target_os = "android"))]
+mod android_backend;
+#[cfg(all(feature = "input-android", target_os = "android"))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-android")))]
+pub use android_backend::{
+    aimage_format_to_frameformat, android_permission_granted, android_permission_status,
+    frameformat_to_aimage_format, query_android, request_android_permission,
+    request_android_permission_blocking, show_android_permission_dialog, AndroidCaptureDevice,
+    ANDROID_PERMISSION_REQUEST_CODE,
+};
 #[cfg(any(
     all(feature = "input-msmf", target_os = "windows"),
     all(feature = "docs-only", feature = "docs-nolink", feature = "input-msmf")
@@ -136,18 +219,69 @@ mod avfoundation;
     )
 ))]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
-pub use avfoundation::AVFoundationCaptureDevice;
-// FIXME: Fix Lifetime Issues
+pub use avfoundation::{AVFoundationCaptureDevice, AVFoundationDeviceWatcher};
+#[cfg(all(
+    feature = "input-avfoundation",
+    any(
+        target_os = "macos",
+        all(target_os = "ios", feature = "input-avfoundation-ios")
+    )
+))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+pub use avfoundation::AVFoundationCameraRig;
+#[cfg(all(
+    feature = "input-avfoundation",
+    any(target_os = "macos", target_os = "ios")
+))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+pub use nokhwa_bindings_macos::{AVCaptureSessionInterruptionReason, AVCaptureSessionPreset};
+#[cfg(all(feature = "input-avfoundation", target_os = "macos"))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-avfoundation")))]
+pub use nokhwa_bindings_macos::{
+    query_virtual_cameras, request_extension_activation, CMIOVirtualCamera, PixelBuffer,
+};
+#[cfg(all(feature = "output-metal", target_os = "macos"))]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-metal")))]
+pub use nokhwa_bindings_macos::{MetalTexture, MetalTextureCache};
 #[cfg(feature = "input-uvc")]
 mod uvc_backend;
 #[cfg(feature = "input-uvc")]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-uvc")))]
-pub use uvc_backend::UVCCaptureDevice;
-// #[cfg(feature = "input-gst")]
-// mod gst_backend;
-// #[cfg(feature = "input-gst")]
-// #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
-// pub use gst_backend::GStreamerCaptureDevice;
+pub use uvc_backend::{query_uvc, UVCCaptureDevice, UvcExtensionUnit};
+#[cfg(feature = "input-aravis")]
+mod aravis_backend;
+#[cfg(feature = "input-aravis")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-aravis")))]
+pub use aravis_backend::{
+    frameformat_to_genicam_pixel_formats, genicam_pixel_format_to_frameformat, query_aravis,
+    AravisCaptureDevice, GENICAM_EXPOSURE_AUTO, GENICAM_GAIN_AUTO, GENICAM_TRIGGER_MODE,
+    GENICAM_TRIGGER_SOURCE,
+};
+#[cfg(feature = "input-gst")]
+mod gst_backend;
+#[cfg(feature = "input-gst")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-gst")))]
+pub use gst_backend::{
+    camera_format_to_caps, caps_to_camera_formats, frameformat_to_gstreamer_caps,
+    gstreamer_caps_to_frameformat, query_gstreamer, GStreamerCaptureDevice, GStreamerPipeline,
+};
+#[cfg(feature = "input-ffmpeg")]
+mod ffmpeg_backend;
+#[cfg(feature = "input-ffmpeg")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-ffmpeg")))]
+pub use ffmpeg_backend::{
+    default_ffmpeg_input_format, query_ffmpeg, query_ffmpeg_input_format, FfmpegCaptureDevice,
+};
+#[cfg(feature = "input-decklink")]
+mod decklink_backend;
+#[cfg(feature = "input-decklink")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-decklink")))]
+pub use decklink_backend::{
+    query_decklink, DeckLinkCaptureDevice, DECKLINK_INPUT_CONNECTOR, DECKLINK_SIGNAL_PRESENT,
+};
+#[cfg(feature = "input-decklink")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-decklink")))]
+pub use nokhwa_bindings_decklink::{DeckLinkConnector, DeckLinkDevice, DeckLinkDisplayMode};
 // #[cfg(feature = "input-jscam")]
 // mod browser_backend;
 // #[cfg(feature = "input-jscam")]
@@ -167,4 +301,16 @@ mod opencv_backend;
 
 #[cfg(feature = "input-opencv")]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-opencv")))]
-pub use opencv_backend::OpenCvCaptureDevice;
+pub use opencv_backend::{
+    fourcc_to_frameformat, frameformat_to_fourcc, known_camera_control_to_video_capture_property,
+    opencv_property, OpenCvCaptureDevice, OPENCV_AUTOFOCUS, OPENCV_AUTO_EXPOSURE,
+    OPENCV_AUTO_WHITE_BALANCE, OPENCV_BUFFER_SIZE, OPENCV_FOURCC,
+};
+#[cfg(feature = "input-onvif")]
+mod onvif;
+#[cfg(feature = "input-onvif")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-onvif")))]
+pub use onvif::{
+    discover_onvif, query_onvif, OnvifCamera, OnvifCaptureDevice, OnvifCredentials, OnvifDevice,
+    OnvifProfile,
+};
@@ -15,7 +15,7 @@
  */
 use nokhwa_bindings_windows::wmf::MediaFoundationDevice;
 use nokhwa_core::{
-    buffer::Buffer,
+    buffer::{Buffer, FrameOrientation},
     error::NokhwaError,
     pixel_format::RgbFormat,
     traits::CaptureTrait,
@@ -233,6 +233,10 @@ impl CaptureTrait for MediaFoundationCaptureDevice {
         self.inner.set_control(id, value)
     }
 
+    fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+        Ok(self.inner.dropped_frames())
+    }
+
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
         self.inner.start_stream()
     }
@@ -244,11 +248,25 @@ impl CaptureTrait for MediaFoundationCaptureDevice {
     fn frame(&mut self) -> Result<Buffer, NokhwaError> {
         self.refresh_camera_format()?;
         let self_ctrl = self.camera_format();
-        Ok(Buffer::new(
+        let buffer = Buffer::new(
             self_ctrl.resolution(),
             &self.inner.raw_bytes()?,
             self_ctrl.format(),
-        ))
+        )
+        .with_stride(self.inner.stride().unwrap_or_default());
+        let buffer = match self.inner.timestamp() {
+            Some(timestamp) => buffer.with_timestamp(timestamp),
+            None => buffer,
+        };
+        let buffer = match self.inner.sequence() {
+            Some(sequence) => buffer.with_sequence(sequence),
+            None => buffer,
+        };
+        // a bottom-up image is upside down, which is the same as rotated and mirrored
+        if self.inner.is_bottom_up() {
+            return Ok(buffer.with_orientation(FrameOrientation::new(180, true)));
+        }
+        Ok(buffer)
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
@@ -0,0 +1,1061 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! ONVIF network cameras: WS-Discovery, the Media service's profiles and stream URIs, and PTZ.
+//!
+//! The video itself is RTSP, which is opened with the `OpenCV` backend.
+
+use crate::backends::capture::OpenCvCaptureDevice;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
+        ControlValueSetter, FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use roxmltree::{Document, Node};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Write,
+    io::ErrorKind,
+    net::UdpSocket,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How long [`query_onvif()`] listens for cameras answering the WS-Discovery probe.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a SOAP request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// The WS-Discovery multicast group.
+const DISCOVERY_ADDRESS: &str = "239.255.255.250:3702";
+
+const NAMESPACES: &str = r#"xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:tds="http://www.onvif.org/ver10/device/wsdl" xmlns:trt="http://www.onvif.org/ver10/media/wsdl" xmlns:tptz="http://www.onvif.org/ver20/ptz/wsdl" xmlns:tt="http://www.onvif.org/ver10/schema""#;
+const DEVICE_ACTION: &str = "http://www.onvif.org/ver10/device/wsdl/";
+const MEDIA_ACTION: &str = "http://www.onvif.org/ver10/media/wsdl/";
+const PTZ_ACTION: &str = "http://www.onvif.org/ver20/ptz/wsdl/";
+
+/// The PTZ axes as [`KnownCameraControl`]s, in the generic position spaces: pan and tilt in `-1.0..=1.0`, zoom in `0.0..=1.0`.
+const PTZ_CONTROLS: [KnownCameraControl; 3] = [
+    KnownCameraControl::Pan,
+    KnownCameraControl::Tilt,
+    KnownCameraControl::Zoom,
+];
+
+// feeds the nonces, which only have to differ between requests
+static NONCE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// The user of an ONVIF camera. The password is sent as a WS-Security `PasswordDigest`, never in plain text.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct OnvifCredentials {
+    username: String,
+    password: String,
+}
+
+impl OnvifCredentials {
+    /// Creates new [`OnvifCredentials`].
+    #[must_use]
+    pub fn new(username: &str, password: &str) -> Self {
+        OnvifCredentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Gets the user name.
+    #[must_use]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+/// A camera that answered a WS-Discovery probe.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct OnvifDevice {
+    endpoint: String,
+    addresses: Vec<String>,
+    name: Option<String>,
+    hardware: Option<String>,
+}
+
+impl OnvifDevice {
+    /// Gets the endpoint reference of the camera, usually a `urn:uuid:` that stays the same across address changes.
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Gets the URLs of the camera's device service.
+    #[must_use]
+    pub fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    /// Gets the name from the camera's `onvif://www.onvif.org/name/` scope, if it has one.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the model from the camera's `onvif://www.onvif.org/hardware/` scope, if it has one.
+    #[must_use]
+    pub fn hardware(&self) -> Option<&str> {
+        self.hardware.as_deref()
+    }
+}
+
+/// A media profile of an ONVIF camera, which ties a video encoder configuration to a stream.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct OnvifProfile {
+    token: String,
+    name: String,
+    format: Option<FrameFormat>,
+    resolution: Option<Resolution>,
+    frame_rate: Option<u32>,
+    ptz: bool,
+}
+
+impl OnvifProfile {
+    /// Gets the token that identifies the profile in requests.
+    #[must_use]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Gets the name of the profile, e.g. `mainStream`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the encoding of the stream, `None` if the camera uses one nokhwa does not know (e.g. MPEG-4).
+    #[must_use]
+    pub fn format(&self) -> Option<FrameFormat> {
+        self.format
+    }
+
+    /// Gets the resolution of the stream.
+    #[must_use]
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    /// Gets the frame rate limit of the stream.
+    #[must_use]
+    pub fn frame_rate(&self) -> Option<u32> {
+        self.frame_rate
+    }
+
+    /// Checks whether the profile has a PTZ configuration, which PTZ requests need.
+    #[must_use]
+    pub fn has_ptz(&self) -> bool {
+        self.ptz
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+}
+
+fn descendant<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.descendants()
+        .find(|child| child.tag_name().name() == name)
+}
+
+fn descendant_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    descendant(node, name)
+        .and_then(|node| node.text())
+        .map(str::trim)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+// 16 bytes that differ between requests, they do not have to be secret
+fn nonce() -> [u8; 16] {
+    let mut hasher = sha1_smol::Sha1::new();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.update(&now.as_nanos().to_le_bytes());
+    hasher.update(&NONCE_COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.update(&std::process::id().to_le_bytes());
+    let mut nonce = [0_u8; 16];
+    nonce.copy_from_slice(&hasher.digest().bytes()[..16]);
+    nonce
+}
+
+fn message_id() -> String {
+    let nonce = nonce();
+    let hex = nonce.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+    format!(
+        "uuid:{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn format_utc(unix: i64) -> String {
+    let (year, month, day) = civil_from_days(unix.div_euclid(86_400));
+    let seconds = unix.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn soap_fault(response: &str) -> Option<String> {
+    let document = Document::parse(response).ok()?;
+    let fault = descendant(document.root(), "Fault")?;
+    descendant_text(fault, "Text")
+        .or_else(|| descendant_text(fault, "faultstring"))
+        .or_else(|| descendant_text(fault, "Value"))
+        .map(ToString::to_string)
+}
+
+fn soap_request(url: &str, action: &str, envelope: &str) -> Result<String, String> {
+    let response = ureq::post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .set(
+            "Content-Type",
+            &format!("application/soap+xml; charset=utf-8; action=\"{action}\""),
+        )
+        .send_string(envelope);
+    match response {
+        Ok(response) => response.into_string().map_err(|why| why.to_string()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(soap_fault(&body).unwrap_or_else(|| format!("HTTP {code}")))
+        }
+        Err(why) => Err(why.to_string()),
+    }
+}
+
+fn parse_probe_match(response: &str) -> Vec<OnvifDevice> {
+    let Ok(document) = Document::parse(response) else {
+        return vec![];
+    };
+    document
+        .descendants()
+        .filter(|node| node.tag_name().name() == "ProbeMatch")
+        .filter_map(|probe_match| {
+            let addresses = descendant_text(probe_match, "XAddrs")?
+                .split_whitespace()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let endpoint = descendant(probe_match, "EndpointReference")
+                .and_then(|reference| descendant_text(reference, "Address"))
+                .map_or_else(|| addresses.join(" "), ToString::to_string);
+            let scopes = descendant_text(probe_match, "Scopes").unwrap_or_default();
+            let scope = |prefix: &str| {
+                scopes
+                    .split_whitespace()
+                    .find_map(|scope| scope.strip_prefix(prefix))
+                    .map(percent_decode)
+            };
+            Some(OnvifDevice {
+                endpoint,
+                addresses,
+                name: scope("onvif://www.onvif.org/name/"),
+                hardware: scope("onvif://www.onvif.org/hardware/"),
+            })
+        })
+        .collect()
+}
+
+/// Sends a WS-Discovery probe for network video transmitters and collects the cameras that answer within `timeout`.
+///
+/// Discovery is multicast, so it only finds cameras on the local network segment.
+/// # Errors
+/// If the UDP socket can not be opened or the probe can not be sent, this will error.
+pub fn discover_onvif(timeout: Duration) -> Result<Vec<OnvifDevice>, NokhwaError> {
+    let discovery_error =
+        |why: std::io::Error| NokhwaError::GeneralError(format!("ONVIF discovery failed: {why}"));
+    let probe = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:d="http://schemas.xmlsoap.org/ws/2005/04/discovery" xmlns:dn="http://www.onvif.org/ver10/network/wsdl"><s:Header><a:MessageID>{}</a:MessageID><a:To s:mustUnderstand="true">urn:schemas-xmlsoap-org:ws:2005:04:discovery</a:To><a:Action s:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2005/04/discovery/Probe</a:Action></s:Header><s:Body><d:Probe><d:Types>dn:NetworkVideoTransmitter</d:Types></d:Probe></s:Body></s:Envelope>"#,
+        message_id()
+    );
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(discovery_error)?;
+    socket
+        .send_to(probe.as_bytes(), DISCOVERY_ADDRESS)
+        .map_err(discovery_error)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut devices: Vec<OnvifDevice> = Vec::new();
+    let mut buffer = vec![0_u8; 65_536];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket
+            .set_read_timeout(Some(remaining))
+            .map_err(discovery_error)?;
+        let length = match socket.recv_from(&mut buffer) {
+            Ok((length, _)) => length,
+            Err(why) if matches!(why.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(why) => return Err(discovery_error(why)),
+        };
+        let response = String::from_utf8_lossy(&buffer[..length]);
+        for device in parse_probe_match(&response) {
+            // cameras answer once per network interface
+            if devices
+                .iter()
+                .all(|known| known.endpoint != device.endpoint)
+            {
+                devices.push(device);
+            }
+        }
+    }
+    Ok(devices)
+}
+
+/// Lists the ONVIF cameras on the local network, see [`discover_onvif()`].
+///
+/// The [`CameraInfo::misc()`] of each camera is its device service URL, which can be passed as a [`CameraIndex::String`] to [`OnvifCaptureDevice::new()`].
+/// # Errors
+/// If the discovery fails, this will error.
+pub fn query_onvif() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Ok(discover_onvif(DISCOVERY_TIMEOUT)?
+        .into_iter()
+        .filter(|device| !device.addresses.is_empty())
+        .zip(0_u32..)
+        .map(|(device, index)| {
+            let address = &device.addresses[0];
+            CameraInfo::new(
+                device.name().unwrap_or("ONVIF Camera"),
+                &format!(
+                    "ONVIF camera {} at {address}",
+                    device.hardware().unwrap_or_default()
+                ),
+                address,
+                &CameraIndex::Index(index),
+            )
+        })
+        .collect())
+}
+
+/// A client for the device, media and PTZ services of an ONVIF camera.
+#[derive(Clone, Debug)]
+pub struct OnvifCamera {
+    device_url: String,
+    media_url: String,
+    ptz_url: Option<String>,
+    credentials: Option<OnvifCredentials>,
+    // the camera's clock minus ours in seconds, as it checks the creation time of the digest
+    clock_offset: i64,
+    manufacturer: Option<String>,
+    model: Option<String>,
+}
+
+impl OnvifCamera {
+    /// Connects to the camera with the device service at `device_url`, e.g. `http://192.168.1.64/onvif/device_service`.
+    /// # Errors
+    /// If the camera can not be reached, rejects the credentials, or has no media service, this will error.
+    pub fn connect(
+        device_url: &str,
+        credentials: Option<OnvifCredentials>,
+    ) -> Result<Self, NokhwaError> {
+        let open_error = |why: String| NokhwaError::OpenDeviceError(device_url.to_string(), why);
+        let mut camera = OnvifCamera {
+            device_url: device_url.to_string(),
+            media_url: String::new(),
+            ptz_url: None,
+            credentials,
+            clock_offset: 0,
+            manufacturer: None,
+            model: None,
+        };
+
+        // cameras answer this without credentials, so the digest can use their clock
+        if let Ok(response) = camera.call(
+            device_url,
+            DEVICE_ACTION,
+            "GetSystemDateAndTime",
+            "<tds:GetSystemDateAndTime/>",
+        ) {
+            camera.clock_offset =
+                Self::parse_clock(&response).map_or(0, |camera_time| camera_time - unix_now());
+        }
+
+        let response = camera
+            .call(
+                device_url,
+                DEVICE_ACTION,
+                "GetCapabilities",
+                "<tds:GetCapabilities><tds:Category>All</tds:Category></tds:GetCapabilities>",
+            )
+            .map_err(open_error)?;
+        let document = Document::parse(&response).map_err(|why| open_error(why.to_string()))?;
+        let service_url = |service: &str| {
+            descendant(document.root(), "Capabilities")
+                .and_then(|capabilities| child(capabilities, service))
+                .and_then(|service| descendant_text(service, "XAddr"))
+                .map(ToString::to_string)
+        };
+        camera.media_url = service_url("Media")
+            .ok_or_else(|| open_error("The camera has no media service".to_string()))?;
+        camera.ptz_url = service_url("PTZ");
+
+        if let Ok(response) = camera.call(
+            device_url,
+            DEVICE_ACTION,
+            "GetDeviceInformation",
+            "<tds:GetDeviceInformation/>",
+        ) {
+            if let Ok(document) = Document::parse(&response) {
+                camera.manufacturer =
+                    descendant_text(document.root(), "Manufacturer").map(ToString::to_string);
+                camera.model = descendant_text(document.root(), "Model").map(ToString::to_string);
+            }
+        }
+        Ok(camera)
+    }
+
+    fn parse_clock(response: &str) -> Option<i64> {
+        let document = Document::parse(response).ok()?;
+        let utc = descendant(document.root(), "UTCDateTime")?;
+        let field = |parent: &str, name: &str| -> Option<i64> {
+            descendant_text(child(utc, parent)?, name)?.parse().ok()
+        };
+        let days = days_from_civil(
+            field("Date", "Year")?,
+            field("Date", "Month")?,
+            field("Date", "Day")?,
+        );
+        Some(
+            days * 86_400
+                + field("Time", "Hour")? * 3600
+                + field("Time", "Minute")? * 60
+                + field("Time", "Second")?,
+        )
+    }
+
+    fn security_header(&self) -> String {
+        let Some(credentials) = &self.credentials else {
+            return String::new();
+        };
+        let nonce = nonce();
+        let created = format_utc(unix_now() + self.clock_offset);
+        let mut hasher = sha1_smol::Sha1::new();
+        hasher.update(&nonce);
+        hasher.update(created.as_bytes());
+        hasher.update(credentials.password.as_bytes());
+        format!(
+            r#"<s:Header><Security s:mustUnderstand="1" xmlns="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd"><UsernameToken><Username>{}</Username><Password Type="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest">{}</Password><Nonce EncodingType="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary">{}</Nonce><Created xmlns="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd">{created}</Created></UsernameToken></Security></s:Header>"#,
+            escape_xml(&credentials.username),
+            STANDARD.encode(hasher.digest().bytes()),
+            STANDARD.encode(nonce),
+        )
+    }
+
+    fn call(
+        &self,
+        url: &str,
+        service: &str,
+        operation: &str,
+        body: &str,
+    ) -> Result<String, String> {
+        let envelope = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><s:Envelope {NAMESPACES}>{}<s:Body>{body}</s:Body></s:Envelope>"#,
+            self.security_header()
+        );
+        soap_request(url, &format!("{service}{operation}"), &envelope)
+    }
+
+    /// Gets the URL of the camera's device service.
+    #[must_use]
+    pub fn device_url(&self) -> &str {
+        &self.device_url
+    }
+
+    /// Gets the manufacturer the camera reported, if it answered `GetDeviceInformation`.
+    #[must_use]
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.manufacturer.as_deref()
+    }
+
+    /// Gets the model the camera reported, if it answered `GetDeviceInformation`.
+    #[must_use]
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Checks whether the camera has a PTZ service.
+    #[must_use]
+    pub fn has_ptz(&self) -> bool {
+        self.ptz_url.is_some()
+    }
+
+    /// Gets the media profiles of the camera.
+    /// # Errors
+    /// If the request fails, this will error.
+    pub fn profiles(&self) -> Result<Vec<OnvifProfile>, NokhwaError> {
+        let get_error = |why: String| NokhwaError::GetPropertyError {
+            property: "ONVIF Profiles".to_string(),
+            error: why,
+        };
+        let response = self
+            .call(
+                &self.media_url,
+                MEDIA_ACTION,
+                "GetProfiles",
+                "<trt:GetProfiles/>",
+            )
+            .map_err(get_error)?;
+        let document = Document::parse(&response).map_err(|why| get_error(why.to_string()))?;
+        Ok(document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "Profiles")
+            .filter_map(|profile| {
+                let token = profile.attribute("token")?.to_string();
+                let encoder = child(profile, "VideoEncoderConfiguration");
+                let format = encoder
+                    .and_then(|encoder| child(encoder, "Encoding"))
+                    .and_then(|encoding| encoding.text())
+                    .and_then(|encoding| match encoding.trim() {
+                        "JPEG" => Some(FrameFormat::MJpeg),
+                        "H264" => Some(FrameFormat::H264),
+                        "H265" => Some(FrameFormat::H265),
+                        _ => None,
+                    });
+                let resolution = encoder
+                    .and_then(|encoder| child(encoder, "Resolution"))
+                    .and_then(|resolution| {
+                        Some(Resolution::new(
+                            descendant_text(resolution, "Width")?.parse().ok()?,
+                            descendant_text(resolution, "Height")?.parse().ok()?,
+                        ))
+                    });
+                let frame_rate = encoder
+                    .and_then(|encoder| descendant_text(encoder, "FrameRateLimit"))
+                    .and_then(|frame_rate| frame_rate.parse().ok());
+                Some(OnvifProfile {
+                    name: descendant_text(profile, "Name")
+                        .map_or_else(|| token.clone(), ToString::to_string),
+                    token,
+                    format,
+                    resolution,
+                    frame_rate,
+                    ptz: child(profile, "PTZConfiguration").is_some(),
+                })
+            })
+            .collect())
+    }
+
+    /// Gets the RTSP URI of `profile`'s stream. If the camera has credentials, they are put in the URI, as most cameras ask for them again.
+    /// # Errors
+    /// If the request fails, this will error.
+    pub fn stream_uri(&self, profile: &OnvifProfile) -> Result<String, NokhwaError> {
+        let get_error = |why: String| NokhwaError::GetPropertyError {
+            property: "ONVIF Stream URI".to_string(),
+            error: why,
+        };
+        let body = format!(
+            "<trt:GetStreamUri><trt:StreamSetup><tt:Stream>RTP-Unicast</tt:Stream><tt:Transport><tt:Protocol>RTSP</tt:Protocol></tt:Transport></trt:StreamSetup><trt:ProfileToken>{}</trt:ProfileToken></trt:GetStreamUri>",
+            escape_xml(&profile.token)
+        );
+        let response = self
+            .call(&self.media_url, MEDIA_ACTION, "GetStreamUri", &body)
+            .map_err(get_error)?;
+        let document = Document::parse(&response).map_err(|why| get_error(why.to_string()))?;
+        let uri = descendant(document.root(), "MediaUri")
+            .and_then(|media_uri| descendant_text(media_uri, "Uri"))
+            .ok_or_else(|| get_error("No URI in the response".to_string()))?;
+
+        Ok(match (&self.credentials, uri.split_once("://")) {
+            (Some(credentials), Some((scheme, rest))) if !rest.contains('@') => format!(
+                "{scheme}://{}:{}@{rest}",
+                percent_encode(&credentials.username),
+                percent_encode(&credentials.password)
+            ),
+            _ => uri.to_string(),
+        })
+    }
+
+    fn ptz_url(&self) -> Result<&str, NokhwaError> {
+        self.ptz_url
+            .as_deref()
+            .ok_or(NokhwaError::UnsupportedOperationError(ApiBackend::Network))
+    }
+
+    /// Gets the pan, tilt and zoom position of `profile`'s PTZ configuration, in the generic spaces (see [`OnvifCaptureDevice`]).
+    /// # Errors
+    /// If the camera has no PTZ service or the request fails, this will error.
+    pub fn ptz_position(&self, profile: &OnvifProfile) -> Result<(f64, f64, f64), NokhwaError> {
+        let get_error = |why: String| NokhwaError::GetPropertyError {
+            property: "ONVIF PTZ Position".to_string(),
+            error: why,
+        };
+        let body = format!(
+            "<tptz:GetStatus><tptz:ProfileToken>{}</tptz:ProfileToken></tptz:GetStatus>",
+            escape_xml(&profile.token)
+        );
+        let response = self
+            .call(self.ptz_url()?, PTZ_ACTION, "GetStatus", &body)
+            .map_err(get_error)?;
+        let document = Document::parse(&response).map_err(|why| get_error(why.to_string()))?;
+        let position = descendant(document.root(), "Position")
+            .ok_or_else(|| get_error("No position in the response".to_string()))?;
+        let coordinate = |axis: &str, attribute: &str| {
+            child(position, axis)
+                .and_then(|axis| axis.attribute(attribute))
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or_default()
+        };
+        Ok((
+            coordinate("PanTilt", "x"),
+            coordinate("PanTilt", "y"),
+            coordinate("Zoom", "x"),
+        ))
+    }
+
+    /// Moves `profile`'s PTZ head to an absolute position in the generic spaces.
+    /// # Errors
+    /// If the camera has no PTZ service or rejects the move, this will error.
+    pub fn ptz_absolute_move(
+        &self,
+        profile: &OnvifProfile,
+        pan: f64,
+        tilt: f64,
+        zoom: f64,
+    ) -> Result<(), NokhwaError> {
+        let body = format!(
+            r#"<tptz:AbsoluteMove><tptz:ProfileToken>{}</tptz:ProfileToken><tptz:Position><tt:PanTilt x="{pan}" y="{tilt}"/><tt:Zoom x="{zoom}"/></tptz:Position></tptz:AbsoluteMove>"#,
+            escape_xml(&profile.token)
+        );
+        self.call(self.ptz_url()?, PTZ_ACTION, "AbsoluteMove", &body)
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "ONVIF PTZ Position".to_string(),
+                value: format!("{pan}, {tilt}, {zoom}"),
+                error: why,
+            })?;
+        Ok(())
+    }
+
+    /// Moves `profile`'s PTZ head at the given velocities, in `-1.0..=1.0`, until [`ptz_stop()`](OnvifCamera::ptz_stop) is called.
+    /// # Errors
+    /// If the camera has no PTZ service or rejects the move, this will error.
+    pub fn ptz_continuous_move(
+        &self,
+        profile: &OnvifProfile,
+        pan: f64,
+        tilt: f64,
+        zoom: f64,
+    ) -> Result<(), NokhwaError> {
+        let body = format!(
+            r#"<tptz:ContinuousMove><tptz:ProfileToken>{}</tptz:ProfileToken><tptz:Velocity><tt:PanTilt x="{pan}" y="{tilt}"/><tt:Zoom x="{zoom}"/></tptz:Velocity></tptz:ContinuousMove>"#,
+            escape_xml(&profile.token)
+        );
+        self.call(self.ptz_url()?, PTZ_ACTION, "ContinuousMove", &body)
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "ONVIF PTZ Velocity".to_string(),
+                value: format!("{pan}, {tilt}, {zoom}"),
+                error: why,
+            })?;
+        Ok(())
+    }
+
+    /// Stops any movement of `profile`'s PTZ head.
+    /// # Errors
+    /// If the camera has no PTZ service or the request fails, this will error.
+    pub fn ptz_stop(&self, profile: &OnvifProfile) -> Result<(), NokhwaError> {
+        let body = format!(
+            "<tptz:Stop><tptz:ProfileToken>{}</tptz:ProfileToken><tptz:PanTilt>true</tptz:PanTilt><tptz:Zoom>true</tptz:Zoom></tptz:Stop>",
+            escape_xml(&profile.token)
+        );
+        self.call(self.ptz_url()?, PTZ_ACTION, "Stop", &body)
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "ONVIF PTZ Velocity".to_string(),
+                value: "stop".to_string(),
+                error: why,
+            })?;
+        Ok(())
+    }
+}
+
+/// An ONVIF camera, streaming one of its media profiles over RTSP through the `OpenCV` backend.
+///
+/// The PTZ head, if the profile has one, is controlled with [`KnownCameraControl::Pan`], [`KnownCameraControl::Tilt`] and [`KnownCameraControl::Zoom`]
+/// as [`ControlValueSetter::Float`]s in the ONVIF generic position spaces: pan and tilt in `-1.0..=1.0`, zoom in `0.0..=1.0`.
+/// Any other control goes to the `OpenCV` backend.
+/// # Quirks
+/// - Only WS-Security `UsernameToken` authentication is supported, not HTTP digest authentication.
+/// - The format of the stream is set on the camera through its profiles, [`set_camera_format()`](CaptureTrait::set_camera_format) only changes what `OpenCV` asks for.
+/// - Discovery is multicast, cameras on other subnets have to be opened by their device service URL.
+pub struct OnvifCaptureDevice {
+    camera: OnvifCamera,
+    profile: OnvifProfile,
+    camera_info: CameraInfo,
+    stream: OpenCvCaptureDevice,
+}
+
+impl OnvifCaptureDevice {
+    /// Opens the first profile of an ONVIF camera, which is usually its main stream.
+    ///
+    /// `index` is either an index into [`query_onvif()`] or the camera's device service URL. A bare host (e.g. `192.168.1.64`) is taken as `http://<host>/onvif/device_service`.
+    /// # Errors
+    /// If the camera can not be found or reached, has no profiles, or `OpenCV` fails to open the stream, this will error.
+    pub fn new(
+        index: &CameraIndex,
+        credentials: Option<OnvifCredentials>,
+    ) -> Result<Self, NokhwaError> {
+        let device_url = match index {
+            CameraIndex::Index(idx) => query_onvif()?
+                .get(*idx as usize)
+                .map(CameraInfo::misc)
+                .ok_or_else(|| {
+                    NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+                })?,
+            CameraIndex::String(url) if url.contains("://") => url.clone(),
+            CameraIndex::String(host) => format!("http://{host}/onvif/device_service"),
+        };
+        let camera = OnvifCamera::connect(&device_url, credentials)?;
+        let profile = camera.profiles()?.into_iter().next().ok_or_else(|| {
+            NokhwaError::OpenDeviceError(
+                device_url.clone(),
+                "The camera has no profiles".to_string(),
+            )
+        })?;
+        Self::with_profile(camera, profile)
+    }
+
+    /// Opens `profile` of a connected camera.
+    /// # Errors
+    /// If the stream URI can not be retrieved, or `OpenCV` fails to open the stream, this will error.
+    pub fn with_profile(camera: OnvifCamera, profile: OnvifProfile) -> Result<Self, NokhwaError> {
+        let uri = camera.stream_uri(&profile)?;
+        let stream = OpenCvCaptureDevice::new(&CameraIndex::String(uri))?;
+
+        let name = match (camera.manufacturer(), camera.model()) {
+            (Some(manufacturer), Some(model)) => format!("{manufacturer} {model}"),
+            (None, Some(name)) | (Some(name), None) => name.to_string(),
+            (None, None) => "ONVIF Camera".to_string(),
+        };
+        let camera_info = CameraInfo::new(
+            &name,
+            &format!(
+                "ONVIF camera at {}, profile {}",
+                camera.device_url, profile.name
+            ),
+            &camera.device_url,
+            &CameraIndex::String(camera.device_url.clone()),
+        );
+        Ok(OnvifCaptureDevice {
+            camera,
+            profile,
+            camera_info,
+            stream,
+        })
+    }
+
+    /// Gets the ONVIF client of the camera, e.g. for [`OnvifCamera::ptz_continuous_move()`].
+    #[must_use]
+    pub fn camera(&self) -> &OnvifCamera {
+        &self.camera
+    }
+
+    /// Gets the profile that is streamed.
+    #[must_use]
+    pub fn profile(&self) -> &OnvifProfile {
+        &self.profile
+    }
+
+    fn ptz_control(&self, control: KnownCameraControl) -> Option<(f64, f64)> {
+        if !self.profile.ptz || !self.camera.has_ptz() {
+            return None;
+        }
+        match control {
+            KnownCameraControl::Pan | KnownCameraControl::Tilt => Some((-1.0, 1.0)),
+            KnownCameraControl::Zoom => Some((0.0, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+impl Backend for OnvifCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::Network;
+}
+
+impl CaptureTrait for OnvifCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        self.stream.init()
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Network
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        self.stream.refresh_camera_format()
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.stream.camera_format()
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        self.stream.set_camera_format(new_fmt)
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        // the profiles are what the camera offers
+        let mut formats: HashMap<Resolution, Vec<FrameRate>> = HashMap::new();
+        for profile in self.camera.profiles()? {
+            if let (Some(resolution), Some(frame_rate)) = (profile.resolution, profile.frame_rate) {
+                if profile.format == Some(fourcc) {
+                    formats
+                        .entry(resolution)
+                        .or_default()
+                        .push(FrameRate::new_integer(frame_rate)?);
+                }
+            }
+        }
+        Ok(formats)
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        let mut formats = self
+            .camera
+            .profiles()?
+            .into_iter()
+            .filter_map(|profile| profile.format)
+            .collect::<Vec<_>>();
+        formats.sort();
+        formats.dedup();
+        Ok(formats)
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.stream.resolution()
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        self.stream.set_resolution(new_res)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.stream.frame_rate()
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        self.stream.set_frame_rate(new_fps)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.stream.frame_format()
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        self.stream.set_frame_format(fourcc)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        let Some((min, max)) = self.ptz_control(control) else {
+            return self.stream.camera_control(control);
+        };
+        let (pan, tilt, zoom) = self.camera.ptz_position(&self.profile)?;
+        let value = match control {
+            KnownCameraControl::Pan => pan,
+            KnownCameraControl::Tilt => tilt,
+            _ => zoom,
+        };
+        Ok(CameraControl::new(
+            control,
+            control.to_string(),
+            ControlValueDescription::FloatRange {
+                min,
+                max,
+                value,
+                step: 0.0,
+                default: 0.0,
+            },
+            vec![],
+            true,
+        ))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        let mut controls = PTZ_CONTROLS
+            .into_iter()
+            .filter(|control| self.ptz_control(*control).is_some())
+            .map(|control| self.camera_control(control))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Ok(stream_controls) = self.stream.camera_controls() {
+            controls.extend(
+                stream_controls
+                    .into_iter()
+                    .filter(|control| !PTZ_CONTROLS.contains(&control.control())),
+            );
+        }
+        Ok(controls)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let Some((min, max)) = self.ptz_control(id) else {
+            return self.stream.set_camera_control(id, value);
+        };
+        let control_val = match value {
+            ControlValueSetter::Float(f) => f,
+            ControlValueSetter::Integer(i) => i as f64,
+            val => {
+                return Err(NokhwaError::SetPropertyError {
+                    property: id.to_string(),
+                    value: val.to_string(),
+                    error: "unsupported value".to_string(),
+                })
+            }
+        };
+        if !(min..=max).contains(&control_val) {
+            return Err(NokhwaError::SetPropertyError {
+                property: id.to_string(),
+                value: control_val.to_string(),
+                error: format!("out of range {min}..={max}"),
+            });
+        }
+
+        // an absolute move sets every axis, the others keep where they are
+        let (mut pan, mut tilt, mut zoom) = self.camera.ptz_position(&self.profile)?;
+        match id {
+            KnownCameraControl::Pan => pan = control_val,
+            KnownCameraControl::Tilt => tilt = control_val,
+            _ => zoom = control_val,
+        }
+        self.camera
+            .ptz_absolute_move(&self.profile, pan, tilt, zoom)
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stream.open_stream()
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_stream_open()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        self.stream.frame()
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        self.stream.frame_raw()
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stream.stop_stream()
+    }
+}
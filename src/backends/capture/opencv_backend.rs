@@ -14,28 +14,79 @@
  * limitations under the License.
  */
 
-use nokhwa_core::types::RequestedFormatType;
 use nokhwa_core::{
     buffer::Buffer,
     error::NokhwaError,
-    traits::CaptureTrait,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
     types::{
         ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
-        ControlValueSetter, FrameFormat, KnownCameraControl, RequestedFormat, Resolution,
+        ControlValueSetter, FrameRate, KnownCameraControl, Resolution,
     },
 };
 use opencv::{
-    core::{Mat, MatTraitConst, MatTraitConstManual, Vec3b},
+    core::{Mat, MatTraitConst, MatTraitConstManual, CV_8U},
     videoio::{
         VideoCapture, VideoCaptureProperties, VideoCaptureTrait, VideoCaptureTraitConst, CAP_ANY,
-        CAP_AVFOUNDATION, CAP_MSMF, CAP_PROP_FPS, CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH,
-        CAP_V4L2,
+        CAP_AVFOUNDATION, CAP_GSTREAMER, CAP_MSMF, CAP_PROP_AUTOFOCUS, CAP_PROP_AUTO_EXPOSURE,
+        CAP_PROP_AUTO_WB, CAP_PROP_BUFFERSIZE, CAP_PROP_FOURCC, CAP_PROP_FPS,
+        CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH, CAP_V4L2,
     },
 };
 use std::{borrow::Cow, collections::HashMap};
 
+// `KnownCameraControl::Other` IDs of `CAP_PROP_*` properties, "oc" in the high bytes
+const PROPERTY_BASE: u128 = 0x6f63_0000_0000;
+
+/// Any `OpenCV` `CAP_PROP_*` property as a [`KnownCameraControl`], for properties nokhwa has no control for (e.g. `CAP_PROP_ISO_SPEED`).
+/// Its value is a [`ControlValueDescription::Float`], as `OpenCV` has it.
+#[must_use]
+#[allow(clippy::cast_sign_loss)]
+pub const fn opencv_property(property: i32) -> KnownCameraControl {
+    KnownCameraControl::Other(PROPERTY_BASE | property as u32 as u128)
+}
+
+/// The four character code the camera is asked to deliver (`CAP_PROP_FOURCC`), a [`ControlValueDescription::String`] such as `MJPG` or `YUYV`.
+/// Set with [`ControlValueSetter::String`].
+pub const OPENCV_FOURCC: KnownCameraControl = opencv_property(CAP_PROP_FOURCC);
+/// How many frames `OpenCV` buffers (`CAP_PROP_BUFFERSIZE`). `1` keeps the latency low, as frames are never read late. Only some backends (e.g. `V4L2`) honour it.
+pub const OPENCV_BUFFER_SIZE: KnownCameraControl = opencv_property(CAP_PROP_BUFFERSIZE);
+/// The automatic exposure mode (`CAP_PROP_AUTO_EXPOSURE`). The values are the ones of the underlying API, e.g. `1` for manual and `3` for aperture priority with `V4L2`.
+pub const OPENCV_AUTO_EXPOSURE: KnownCameraControl = opencv_property(CAP_PROP_AUTO_EXPOSURE);
+/// Whether the camera focuses automatically (`CAP_PROP_AUTOFOCUS`), a [`ControlValueDescription::Boolean`].
+pub const OPENCV_AUTOFOCUS: KnownCameraControl = opencv_property(CAP_PROP_AUTOFOCUS);
+/// Whether the camera balances white automatically (`CAP_PROP_AUTO_WB`), a [`ControlValueDescription::Boolean`].
+pub const OPENCV_AUTO_WHITE_BALANCE: KnownCameraControl = opencv_property(CAP_PROP_AUTO_WB);
+
+/// The controls [`camera_controls()`](CaptureTrait::camera_controls) tries, `OpenCV` can not list what a camera has.
+const PROBED_CONTROLS: [KnownCameraControl; 20] = [
+    KnownCameraControl::Brightness,
+    KnownCameraControl::Contrast,
+    KnownCameraControl::Hue,
+    KnownCameraControl::Saturation,
+    KnownCameraControl::Sharpness,
+    KnownCameraControl::Gamma,
+    KnownCameraControl::WhiteBalance,
+    KnownCameraControl::BacklightComp,
+    KnownCameraControl::Gain,
+    KnownCameraControl::Pan,
+    KnownCameraControl::Tilt,
+    KnownCameraControl::Zoom,
+    KnownCameraControl::Exposure,
+    KnownCameraControl::Iris,
+    KnownCameraControl::Focus,
+    OPENCV_FOURCC,
+    OPENCV_BUFFER_SIZE,
+    OPENCV_AUTO_EXPOSURE,
+    OPENCV_AUTOFOCUS,
+    OPENCV_AUTO_WHITE_BALANCE,
+];
+
 /// Attempts to convert a [`KnownCameraControl`] into a `OpenCV` video capture property.
 /// If the associated control is not found, this will return `Err`
+/// # Errors
+/// If the control has no `OpenCV` property, this will error. Controls made with [`opencv_property()`] are handled by [`CaptureTrait::camera_control()`], as
+/// `OpenCV` has more properties than [`VideoCaptureProperties`] lists.
 pub fn known_camera_control_to_video_capture_property(
     ctrl: KnownCameraControl,
 ) -> Result<VideoCaptureProperties, NokhwaError> {
@@ -46,6 +97,7 @@ pub fn known_camera_control_to_video_capture_property(
         KnownCameraControl::Saturation => Ok(VideoCaptureProperties::CAP_PROP_SATURATION),
         KnownCameraControl::Sharpness => Ok(VideoCaptureProperties::CAP_PROP_SHARPNESS),
         KnownCameraControl::Gamma => Ok(VideoCaptureProperties::CAP_PROP_GAMMA),
+        KnownCameraControl::WhiteBalance => Ok(VideoCaptureProperties::CAP_PROP_WB_TEMPERATURE),
         KnownCameraControl::BacklightComp => Ok(VideoCaptureProperties::CAP_PROP_BACKLIGHT),
         KnownCameraControl::Gain => Ok(VideoCaptureProperties::CAP_PROP_GAIN),
         KnownCameraControl::Pan => Ok(VideoCaptureProperties::CAP_PROP_PAN),
@@ -58,24 +110,83 @@ pub fn known_camera_control_to_video_capture_property(
     }
 }
 
+// the `CAP_PROP_*` ID of a control
+fn property_id(control: KnownCameraControl) -> Result<i32, NokhwaError> {
+    match control {
+        KnownCameraControl::Other(id) if id & !0xffff_ffff == PROPERTY_BASE => {
+            // the low 32 bits are the property ID as it was passed to `opencv_property()`
+            Ok(i32::from_ne_bytes(
+                u32::try_from(id & 0xffff_ffff)
+                    .unwrap_or_default()
+                    .to_ne_bytes(),
+            ))
+        }
+        // `VideoCaptureProperties` is the `CAP_PROP_*` ID
+        control => known_camera_control_to_video_capture_property(control).map(|prop| prop as i32),
+    }
+}
+
+fn fourcc_to_string(fourcc: f64) -> String {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let code = (fourcc as u32).to_le_bytes();
+    String::from_utf8_lossy(&code)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+fn string_to_fourcc(fourcc: &str) -> Option<f64> {
+    let code: [u8; 4] = format!("{fourcc:<4}").as_bytes().try_into().ok()?;
+    Some(f64::from(u32::from_le_bytes(code)))
+}
+
+/// Converts a four character code as `OpenCV` reports it in `CAP_PROP_FOURCC` into the [`FrameFormat`] the camera delivers.
+#[must_use]
+pub fn fourcc_to_frameformat(fourcc: &str) -> Option<FrameFormat> {
+    match fourcc {
+        "MJPG" => Some(FrameFormat::MJpeg),
+        "YUYV" | "YUY2" => Some(FrameFormat::Yuv422),
+        "UYVY" => Some(FrameFormat::Uyv422),
+        "NV12" => Some(FrameFormat::Nv12),
+        "H264" | "avc1" => Some(FrameFormat::H264),
+        "GREY" | "Y800" => Some(FrameFormat::Luma8),
+        _ => None,
+    }
+}
+
+/// Converts a [`FrameFormat`] into the four character code that asks `OpenCV` for it.
+#[must_use]
+pub fn frameformat_to_fourcc(frame_format: FrameFormat) -> Option<&'static str> {
+    match frame_format {
+        FrameFormat::MJpeg => Some("MJPG"),
+        FrameFormat::Yuv422 => Some("YUYV"),
+        FrameFormat::Uyv422 => Some("UYVY"),
+        FrameFormat::Nv12 => Some("NV12"),
+        FrameFormat::H264 => Some("H264"),
+        FrameFormat::Luma8 => Some("GREY"),
+        _ => None,
+    }
+}
+
 /// The backend struct that interfaces with `OpenCV`. Note that an `opencv` matching the version that this was either compiled on must be present on the user's machine. (usually 4.5.2 or greater)
 /// For more information, please see [`opencv-rust`](https://github.com/twistedfall/opencv-rust) and [`OpenCV VideoCapture Docs`](https://docs.opencv.org/4.5.2/d8/dfe/classcv_1_1VideoCapture.html).
 ///
+/// Besides cameras, anything `cv::VideoCapture` opens can be captured from: IP camera URLs, video files, image sequences and, with
+/// [`from_gstreamer_pipeline()`](OpenCvCaptureDevice::from_gstreamer_pipeline), `GStreamer` pipelines.
+///
 /// To see what this does, please see [`CaptureTrait`]
 /// # Quirks
-///  - **Some features don't work properly on this backend (yet)! Setting [`Resolution`], FPS, [`FrameFormat`] does not work and will default to 640x480 30FPS. This is being worked on.**
 ///  - This is a **cross-platform** backend. This means that it will work on most platforms given that `OpenCV` is present.
-///  - This backend can also do IP Camera input.
-///  - The backend's backend will default to system level APIs on Linux(V4L2), Mac(AVFoundation), and Windows(Media Foundation). Otherwise, it will decide for itself.
-///  - If the [`OpenCvCaptureDevice`] is initialized as a `IPCamera`, the [`CameraFormat`]'s `index` value will be [`u32::MAX`](std::u32::MAX) (4294967295).
-///  - `OpenCV` does not support camera querying. Camera Name and Camera supported resolution/fps/fourcc is a [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
-/// Note: [`resolution()`](crate::camera_traits::CaptureTrait::resolution()), [`frame_format()`](crate::camera_traits::CaptureTrait::frame_format()), and [`frame_rate()`](crate::camera_traits::CaptureTrait::frame_rate()) is not affected.
+///  - The backend's backend will default to system level APIs on Linux(V4L2), Mac(AVFoundation), and Windows(Media Foundation) for indexes, and [`CAP_ANY`](https://docs.opencv.org/4.5.2/d4/d15/group__videoio__flags__base.html#gga023786be1ee68a9105bf2e48c700294da77ab1fe260fd182f8ec7655fab27a31d) for strings.
+///  - `OpenCV` does not support camera querying, and can not list the formats or controls a camera has. [`compatible_fourcc()`](CaptureTrait::compatible_fourcc) is an [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError),
+///    and [`camera_controls()`](CaptureTrait::camera_controls) lists the controls that have a value.
+///  - `OpenCV` decodes the frames, so [`frame()`](CaptureTrait::frame) is always [`FrameFormat::Rgb8`], [`FrameFormat::RgbA8`] or [`FrameFormat::Luma8`]. The [`CameraFormat`]'s format is what
+///    the camera is asked for with `CAP_PROP_FOURCC`, e.g. [`FrameFormat::MJpeg`] to get higher resolutions over USB.
+///  - Every `CAP_PROP_*` property can be reached with [`opencv_property()`]. Whether the camera honours it depends on the `OpenCV` backend.
 ///  - [`CameraInfo`]'s human name will be "`OpenCV` Capture Device {location}"
 ///  - [`CameraInfo`]'s description will contain the Camera's Index or IP.
-///  - The API Preference order is the native OS API (linux => `v4l2`, mac => `AVFoundation`, windows => `MSMF`) than [`CAP_AUTO`](https://docs.opencv.org/4.5.2/d4/d15/group__videoio__flags__base.html#gga023786be1ee68a9105bf2e48c700294da77ab1fe260fd182f8ec7655fab27a31d)
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-opencv")))]
 pub struct OpenCvCaptureDevice {
-    camera_format: CameraFormat,
+    camera_format: Option<CameraFormat>,
     camera_location: CameraIndex,
     camera_info: CameraInfo,
     api_preference: i32,
@@ -88,58 +199,68 @@ impl OpenCvCaptureDevice {
     ///
     /// Indexes are gives to devices by the OS, and usually numbered by order of discovery.
     ///
-    /// `IPCameras` follow the format
+    /// Strings are passed to `cv::VideoCapture` as they are: a file path, an image sequence (`img_%02d.jpg`), or a URL. `IPCameras` follow the format
     /// ```.ignore
     /// <protocol>://<IP>:<port>/
     /// ```
     /// , but please refer to the manufacturer for the actual IP format.
     ///
     /// # Errors
-    /// If the backend fails to open the camera (e.g. Device does not exist at specified index/ip), Camera does not support specified [`CameraFormat`], and/or other `OpenCV` Error, this will error.
-    /// # Panics
-    /// If the API u32 -> i32 fails this will error
-    #[allow(clippy::cast_possible_wrap)]
-    pub fn new(index: &CameraIndex, cam_fmt: RequestedFormat) -> Result<Self, NokhwaError> {
-        let api_pref = if index.is_string() {
+    /// If the backend fails to open the camera (e.g. Device does not exist at specified index/ip), and/or other `OpenCV` Error, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let api_preference = if index.is_string() {
             CAP_ANY
         } else {
             get_api_pref_int()
         };
+        Self::with_api_preference(index, api_preference)
+    }
 
-        let mut video_capture = match &index {
-            CameraIndex::Index(idx) => VideoCapture::new(*idx as i32, api_pref),
-            CameraIndex::String(ip) => VideoCapture::from_file(ip.as_str(), api_pref),
+    /// Creates a new capture device using the `OpenCV` backend, asking `OpenCV` to use a particular API (a `CAP_*` constant, e.g. `CAP_FFMPEG` or `CAP_DSHOW`).
+    /// Please refer to [`OpenCV VideoCapture Flag Docs`](https://docs.opencv.org/4.5.2/d4/d15/group__videoio__flags__base.html).
+    /// # Errors
+    /// If the backend fails to open the camera, or `OpenCV` was built without the API, this will error.
+    pub fn with_api_preference(
+        index: &CameraIndex,
+        api_preference: i32,
+    ) -> Result<Self, NokhwaError> {
+        let video_capture = match index {
+            CameraIndex::Index(idx) => VideoCapture::new(index_to_i32(*idx)?, api_preference),
+            CameraIndex::String(source) => VideoCapture::from_file(source, api_preference),
+        }
+        .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?;
+        if !video_capture.is_opened().unwrap_or(false) {
+            return Err(NokhwaError::OpenDeviceError(
+                index.to_string(),
+                "OpenCV could not open the source".to_string(),
+            ));
         }
-        .map_err(|why| {
-            NokhwaError::OpenDeviceError(format!("Failed to open {index}"), why.to_string())
-        })?;
-
-        let camera_format =
-            if let RequestedFormatType::Exact(exact) = cam_fmt.requested_format_type() {
-                exact
-            } else {
-                return Err(NokhwaError::UnsupportedOperationError(ApiBackend::OpenCv));
-            };
-
-        set_properties(&mut video_capture, camera_format)?;
 
         let camera_info = CameraInfo::new(
-            format!("OpenCV Capture Device {index}").as_str(),
-            index.to_string().as_str(),
+            &format!("OpenCV Capture Device {index}"),
+            &index.to_string(),
             "",
-            index.clone(),
+            index,
         );
 
         Ok(OpenCvCaptureDevice {
-            camera_format,
+            camera_format: None,
             camera_location: index.clone(),
             camera_info,
-            api_preference: api_pref,
+            api_preference,
             video_capture,
         })
     }
 
-    /// Gets weather said capture device is an `IPCamera`.
+    /// Creates a new capture device from a `GStreamer` pipeline that ends in an `appsink`, e.g.
+    /// `v4l2src device=/dev/video0 ! image/jpeg,width=1920,height=1080 ! v4l2jpegdec ! videoconvert ! appsink`.
+    /// # Errors
+    /// If `OpenCV` was built without `GStreamer`, or the pipeline fails to start, this will error.
+    pub fn from_gstreamer_pipeline(pipeline: &str) -> Result<Self, NokhwaError> {
+        Self::with_api_preference(&CameraIndex::String(pipeline.to_string()), CAP_GSTREAMER)
+    }
+
+    /// Gets weather said capture device is an `IPCamera`, or any other source opened from a string.
     pub fn is_ip_camera(&self) -> bool {
         match self.camera_location {
             CameraIndex::Index(_) => false,
@@ -165,76 +286,47 @@ impl OpenCvCaptureDevice {
         self.api_preference
     }
 
-    /// Gets the RGB24 frame directly read from `OpenCV` without any additional processing.
+    /// Gets the name of the API `OpenCV` opened the source with, e.g. `V4L2` or `GSTREAMER`.
     /// # Errors
-    /// If the frame is failed to be read, this will error.
-    #[allow(clippy::cast_sign_loss)]
-    pub fn raw_frame_vec(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        if !self.is_stream_open() {
-            return Err(NokhwaError::ReadFrameError(
-                "Stream is not open!".to_string(),
-            ));
-        }
-
-        let mut frame = Mat::default();
-        match self.video_capture.read(&mut frame) {
-            Ok(a) => {
-                if !a {
-                    return Err(NokhwaError::ReadFrameError(
-                        "Failed to read frame from videocapture: OpenCV return false, camera disconnected?".to_string(),
-                    ));
-                }
-            }
-            Err(why) => {
-                return Err(NokhwaError::ReadFrameError(format!(
-                    "Failed to read frame from videocapture: {}",
-                    why
-                )))
-            }
-        }
+    /// If the stream is not open, this will error.
+    pub fn opencv_backend_name(&self) -> Result<String, NokhwaError> {
+        self.video_capture
+            .get_backend_name()
+            .map_err(|why| NokhwaError::GetPropertyError {
+                property: "Backend Name".to_string(),
+                error: why.to_string(),
+            })
+    }
 
-        if frame.empty() {
-            return Err(NokhwaError::ReadFrameError("Frame Empty!".to_string()));
-        }
+    /// Gets a `CAP_PROP_*` property.
+    /// # Errors
+    /// If `OpenCV` fails to get the property, this will error.
+    pub fn property(&self, property: i32) -> Result<f64, NokhwaError> {
+        self.video_capture
+            .get(property)
+            .map_err(|why| NokhwaError::GetPropertyError {
+                property: property.to_string(),
+                error: why.to_string(),
+            })
+    }
 
-        match frame.size() {
-            Ok(size) => {
-                if size.width > 0 {
-                    return if frame.is_continuous() {
-                        let mut raw_vec: Vec<u8> = Vec::new();
-
-                        let frame_data_vec = match Mat::data_typed::<Vec3b>(&frame) {
-                            Ok(v) => v,
-                            Err(why) => {
-                                return Err(NokhwaError::ReadFrameError(format!(
-                                    "Failed to convert frame into raw Vec3b: {}",
-                                    why
-                                )))
-                            }
-                        };
-
-                        for pixel in frame_data_vec.iter() {
-                            let pixel_slice: &[u8; 3] = pixel;
-                            raw_vec.push(pixel_slice[2]);
-                            raw_vec.push(pixel_slice[1]);
-                            raw_vec.push(pixel_slice[0]);
-                        }
-
-                        Ok(Cow::from(raw_vec))
-                    } else {
-                        Err(NokhwaError::ReadFrameError(
-                            "Failed to read frame from videocapture: not cont".to_string(),
-                        ))
-                    };
-                }
-                Err(NokhwaError::ReadFrameError(
-                    "Frame width is less than zero!".to_string(),
-                ))
-            }
-            Err(why) => Err(NokhwaError::ReadFrameError(format!(
-                "Failed to read frame from videocapture: failed to read size: {}",
-                why
-            ))),
+    /// Sets a `CAP_PROP_*` property.
+    /// # Errors
+    /// If `OpenCV` fails to set the property, or the backend does not take it, this will error.
+    pub fn set_property(&mut self, property: i32, value: f64) -> Result<(), NokhwaError> {
+        let set_error = |why: String| NokhwaError::SetPropertyError {
+            property: property.to_string(),
+            value: value.to_string(),
+            error: why,
+        };
+        if self
+            .video_capture
+            .set(property, value)
+            .map_err(|why| set_error(why.to_string()))?
+        {
+            Ok(())
+        } else {
+            Err(set_error("rejected".to_string()))
         }
     }
 
@@ -244,26 +336,8 @@ impl OpenCvCaptureDevice {
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_truncation)]
     pub fn raw_resolution(&self) -> Result<Resolution, NokhwaError> {
-        let width = match self.video_capture.get(CAP_PROP_FRAME_WIDTH) {
-            Ok(width) => width as u32,
-            Err(why) => {
-                return Err(NokhwaError::GetPropertyError {
-                    property: "Width".to_string(),
-                    error: why.to_string(),
-                })
-            }
-        };
-
-        let height = match self.video_capture.get(CAP_PROP_FRAME_HEIGHT) {
-            Ok(height) => height as u32,
-            Err(why) => {
-                return Err(NokhwaError::GetPropertyError {
-                    property: "Height".to_string(),
-                    error: why.to_string(),
-                })
-            }
-        };
-
+        let width = self.property(CAP_PROP_FRAME_WIDTH)? as u32;
+        let height = self.property(CAP_PROP_FRAME_HEIGHT)? as u32;
         Ok(Resolution::new(width, height))
     }
 
@@ -273,17 +347,74 @@ impl OpenCvCaptureDevice {
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_truncation)]
     pub fn raw_framerate(&self) -> Result<u32, NokhwaError> {
-        match self.video_capture.get(CAP_PROP_FPS) {
-            Ok(fps) => Ok(fps as u32),
-            Err(why) => Err(NokhwaError::GetPropertyError {
-                property: "Framerate".to_string(),
-                error: why.to_string(),
-            }),
+        Ok(self.property(CAP_PROP_FPS)?.round() as u32)
+    }
+
+    fn read_mat(&mut self) -> Result<Mat, NokhwaError> {
+        if !self.is_stream_open() {
+            return Err(NokhwaError::ReadFrameError(
+                "Stream is not open!".to_string(),
+            ));
+        }
+
+        let mut frame = Mat::default();
+        let read = self
+            .video_capture
+            .read(&mut frame)
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+        if !read || frame.empty() {
+            // files end, cameras get unplugged
+            return Err(NokhwaError::ReadFrameError(
+                "OpenCV returned no frame, end of stream or camera disconnected?".to_string(),
+            ));
+        }
+        if frame.depth() != CV_8U || !frame.is_continuous() {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "Unsupported frame layout: depth {}, continuous {}",
+                frame.depth(),
+                frame.is_continuous()
+            )));
+        }
+        Ok(frame)
+    }
+
+    fn apply_format(&mut self, camera_format: CameraFormat) -> Result<(), NokhwaError> {
+        // the fourcc has to come first, as it limits the resolutions on most cameras
+        if let Some(fourcc) =
+            frameformat_to_fourcc(camera_format.format()).and_then(string_to_fourcc)
+        {
+            self.set_property(CAP_PROP_FOURCC, fourcc)?;
         }
+        self.set_property(CAP_PROP_FRAME_WIDTH, f64::from(camera_format.width()))?;
+        self.set_property(CAP_PROP_FRAME_HEIGHT, f64::from(camera_format.height()))?;
+        self.set_property(
+            CAP_PROP_FPS,
+            f64::from(camera_format.frame_rate().as_float()),
+        )?;
+
+        // `OpenCV` picks the closest resolution the camera has
+        let resolution = self.raw_resolution()?;
+        if resolution != camera_format.resolution() {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Resolution".to_string(),
+                value: camera_format.resolution().to_string(),
+                error: format!("the camera chose {resolution}"),
+            });
+        }
+        Ok(())
     }
 }
 
+impl Backend for OpenCvCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::OpenCv;
+}
+
 impl CaptureTrait for OpenCvCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // keep what the source opened with, files and streams have no other format
+        self.refresh_camera_format()
+    }
+
     fn backend(&self) -> ApiBackend {
         ApiBackend::OpenCv
     }
@@ -292,78 +423,38 @@ impl CaptureTrait for OpenCvCaptureDevice {
         &self.camera_info
     }
 
-    #[allow(clippy::cast_lossless)]
     fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
-        let width = u32::from(
-            self.video_capture
-                .set(CAP_PROP_FRAME_WIDTH, self.camera_format.width() as f64)
-                .map_err(|why| NokhwaError::SetPropertyError {
-                    property: "Resolution Width".to_string(),
-                    value: self.camera_format.to_string(),
-                    error: why.to_string(),
-                })?,
-        );
-        let height = self
-            .video_capture
-            .set(CAP_PROP_FRAME_HEIGHT, self.camera_format.height() as f64)
-            .map_err(|why| NokhwaError::SetPropertyError {
-                property: "Resolution Height".to_string(),
-                value: self.camera_format.to_string(),
-                error: why.to_string(),
-            })? as u32;
-        let fps = self
-            .video_capture
-            .set(CAP_PROP_FPS, self.camera_format.frame_rate() as f64)
-            .map_err(|why| NokhwaError::SetPropertyError {
-                property: "FPS".to_string(),
-                value: self.camera_format.to_string(),
-                error: why.to_string(),
-            })? as u32;
-
-        let ffmt = self.frame_format();
-        self.set_camera_format(CameraFormat::new_from(width, height, ffmt, fps))?;
-
+        let resolution = self.raw_resolution()?;
+        // streams and pipelines often report 0
+        let frame_rate = FrameRate::new_integer(self.raw_framerate().unwrap_or(0).max(1))?;
+        let format = self
+            .property(CAP_PROP_FOURCC)
+            .ok()
+            .and_then(|fourcc| fourcc_to_frameformat(&fourcc_to_string(fourcc)))
+            .unwrap_or(FrameFormat::Rgb8);
+        self.camera_format = Some(CameraFormat::new(resolution, format, frame_rate));
         Ok(())
     }
 
-    fn camera_format(&self) -> CameraFormat {
+    fn camera_format(&self) -> Option<CameraFormat> {
         self.camera_format
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        let current_format = self.camera_format;
-        let is_opened = match self.video_capture.is_opened() {
-            Ok(opened) => opened,
-            Err(why) => {
-                return Err(NokhwaError::GetPropertyError {
-                    property: "Is Stream Open".to_string(),
-                    error: why.to_string(),
-                })
+        if let Err(why) = self.apply_format(new_fmt) {
+            if let Some(current_format) = self.camera_format {
+                let _ = self.apply_format(current_format);
             }
-        };
-
-        self.camera_format = new_fmt;
-
-        if let Err(why) = set_properties(&mut self.video_capture, new_fmt) {
-            self.camera_format = current_format;
             return Err(why);
         }
-        if is_opened {
-            self.stop_stream()?;
-            if let Err(why) = self.open_stream() {
-                return Err(NokhwaError::OpenDeviceError(
-                    self.camera_location.to_string(),
-                    why.to_string(),
-                ));
-            }
-        }
+        self.camera_format = Some(new_fmt);
         Ok(())
     }
 
     fn compatible_list_by_resolution(
         &mut self,
         _fourcc: FrameFormat,
-    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
         Err(NokhwaError::UnsupportedOperationError(ApiBackend::OpenCv))
     }
 
@@ -371,152 +462,110 @@ impl CaptureTrait for OpenCvCaptureDevice {
         Err(NokhwaError::UnsupportedOperationError(ApiBackend::OpenCv))
     }
 
-    fn resolution(&self) -> Resolution {
-        self.raw_resolution()
-            .unwrap_or_else(|_| Resolution::new(640, 480))
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
     }
 
     fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
-        let mut current_fmt = self.camera_format;
-        current_fmt.set_resolution(new_res);
-        self.set_camera_format(current_fmt)
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
     }
 
-    fn frame_rate(&self) -> u32 {
-        self.raw_framerate().unwrap_or(30)
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
     }
 
     fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
-        let mut current_fmt = self.camera_format;
-        current_fmt.set_frame_rate(new_fps);
-        self.set_camera_format(current_fmt)
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
     }
 
     fn frame_format(&self) -> FrameFormat {
-        self.camera_format.format()
+        self.camera_format
+            .map_or(FrameFormat::Rgb8, |fmt| fmt.format())
     }
 
     fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
-        let mut current_fmt = self.camera_format;
-        current_fmt.set_format(fourcc);
-        self.set_camera_format(current_fmt)
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
     }
 
     fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
-        let id = known_camera_control_to_video_capture_property(control)? as i32;
-        let current = self
-            .video_capture
-            .get(id)
-            .map_err(|why| NokhwaError::GetPropertyError {
-                property: id.to_string(),
-                error: why.to_string(),
-            })?;
+        let id = property_id(control)?;
+        let value = self.property(id)?;
         Ok(CameraControl::new(
             control,
-            id.to_string(),
-            ControlValueDescription::Float {
-                value: current,
-                default: 0.0,
-                step: 0.0,
-            },
+            control.to_string(),
+            control_description(control, value),
             vec![],
             true,
         ))
     }
 
     fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(ApiBackend::OpenCv))
+        // backends report -1 (or fail) for properties they do not have
+        Ok(PROBED_CONTROLS
+            .into_iter()
+            .filter(|control| {
+                property_id(*control)
+                    .and_then(|id| self.property(id))
+                    .is_ok_and(|value| (value + 1.0).abs() > f64::EPSILON)
+            })
+            .filter_map(|control| self.camera_control(control).ok())
+            .collect())
     }
 
     #[allow(clippy::cast_precision_loss)]
-    #[allow(clippy::cast_lossless)]
     fn set_camera_control(
         &mut self,
         id: KnownCameraControl,
         value: ControlValueSetter,
     ) -> Result<(), NokhwaError> {
-        let control_val = match value {
-            ControlValueSetter::Integer(i) => i as f64,
-            ControlValueSetter::Float(f) => f,
-            ControlValueSetter::Boolean(b) => u8::from(b) as f64,
-            val => {
-                return Err(NokhwaError::SetPropertyError {
-                    property: "Camera Control".to_string(),
-                    value: val.to_string(),
-                    error: "unsupported value".to_string(),
-                })
+        let unsupported = |value: &ControlValueSetter| NokhwaError::SetPropertyError {
+            property: id.to_string(),
+            value: value.to_string(),
+            error: "unsupported value".to_string(),
+        };
+        let control_val = match &value {
+            ControlValueSetter::Integer(i) => *i as f64,
+            ControlValueSetter::Float(f) => *f,
+            ControlValueSetter::Boolean(b) => f64::from(u8::from(*b)),
+            ControlValueSetter::String(fourcc) if id == OPENCV_FOURCC => {
+                string_to_fourcc(fourcc).ok_or_else(|| unsupported(&value))?
             }
+            val => return Err(unsupported(val)),
         };
 
-        if !self
-            .video_capture
-            .set(
-                known_camera_control_to_video_capture_property(id)? as i32,
-                control_val,
-            )
-            .map_err(|why| NokhwaError::SetPropertyError {
-                property: "Camera Control".to_string(),
-                value: control_val.to_string(),
-                error: why.to_string(),
-            })?
-        {
-            return Err(NokhwaError::SetPropertyError {
-                property: "Camera Control".to_string(),
-                value: control_val.to_string(),
-                error: "false".to_string(),
-            });
-        }
-
-        let set_value = self.camera_control(id)?.value();
-        if set_value != value {
-            return Err(NokhwaError::SetPropertyError {
-                property: "Camera Control".to_string(),
-                value: control_val.to_string(),
-                error: "failed to set value: rejected".to_string(),
-            });
-        }
-
-        Ok(())
+        self.set_property(property_id(id)?, control_val)
     }
 
-    #[allow(clippy::cast_possible_wrap)]
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        match self.camera_location.clone() {
-            CameraIndex::Index(idx) => {
-                match self.video_capture.open(idx as i32, get_api_pref_int()) {
-                    Ok(open) => {
-                        if open {
-                            return Ok(());
-                        }
-                        Err(NokhwaError::OpenStreamError(
-                            "Stream is not opened after stream open attempt opencv".to_string(),
-                        ))
-                    }
-                    Err(why) => Err(NokhwaError::OpenDeviceError(
-                        idx.to_string(),
-                        format!("Failed to open device: {why}"),
-                    )),
-                }
-            }
-            CameraIndex::String(_) => Err(NokhwaError::OpenDeviceError(
-                "Cannot open".to_string(),
-                "String index not supported (try NetworkCamera instead)".to_string(),
-            )),
-        }?;
-
-        match self.video_capture.is_opened() {
-            Ok(open) => {
-                if open {
-                    return Ok(());
-                }
-                Err(NokhwaError::OpenStreamError(
-                    "Stream is not opened after stream open attempt opencv".to_string(),
-                ))
+        if self.is_stream_open() {
+            return Ok(());
+        }
+
+        let opened = match &self.camera_location {
+            CameraIndex::Index(idx) => self
+                .video_capture
+                .open(index_to_i32(*idx)?, self.api_preference),
+            CameraIndex::String(source) => {
+                self.video_capture.open_file(source, self.api_preference)
             }
-            Err(why) => Err(NokhwaError::GetPropertyError {
-                property: "Is Stream Open After Open Stream".to_string(),
-                error: why.to_string(),
-            }),
+        }
+        .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        if !opened {
+            return Err(NokhwaError::OpenStreamError(
+                "Stream is not opened after stream open attempt opencv".to_string(),
+            ));
+        }
+
+        // reopening resets the properties
+        match self.camera_format {
+            Some(camera_format) if self.is_index_camera() => self.apply_format(camera_format),
+            _ => self.refresh_camera_format(),
         }
     }
 
@@ -524,89 +573,87 @@ impl CaptureTrait for OpenCvCaptureDevice {
         self.video_capture.is_opened().unwrap_or(false)
     }
 
+    #[allow(clippy::cast_sign_loss)]
     fn frame(&mut self) -> Result<Buffer, NokhwaError> {
-        let camera_resolution = self.camera_format.resolution();
-        let image_data = {
-            let mut data = self.frame_raw()?.to_vec();
-            data.resize(
-                (camera_resolution.width() * camera_resolution.height() * 3) as usize,
-                0_u8,
-            );
-            data
+        let frame = self.read_mat()?;
+        let resolution = Resolution::new(frame.cols() as u32, frame.rows() as u32);
+        let data = frame
+            .data_bytes()
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+
+        // `OpenCV` hands out BGR(A)
+        let (data, format) = match frame.channels() {
+            1 => (data.to_vec(), FrameFormat::Luma8),
+            3 => (
+                data.chunks_exact(3)
+                    .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+                    .collect(),
+                FrameFormat::Rgb8,
+            ),
+            4 => (
+                data.chunks_exact(4)
+                    .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                    .collect(),
+                FrameFormat::RgbA8,
+            ),
+            channels => {
+                return Err(NokhwaError::ReadFrameError(format!(
+                    "Unsupported channel count {channels}"
+                )))
+            }
         };
-        Ok(Buffer::new(
-            camera_resolution,
-            &image_data,
-            self.camera_format.format(),
-        ))
+        Ok(Buffer::new(resolution, &data, format))
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        let cow = self.raw_frame_vec()?;
-        Ok(cow)
+        let frame = self.read_mat()?;
+        let data = frame
+            .data_bytes()
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+        Ok(Cow::Owned(data.to_vec()))
     }
 
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-        match self.video_capture.release() {
-            Ok(_) => Ok(()),
-            Err(why) => Err(NokhwaError::StreamShutdownError(why.to_string())),
-        }
+        self.video_capture
+            .release()
+            .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))
+    }
+}
+
+fn control_description(control: KnownCameraControl, value: f64) -> ControlValueDescription {
+    match control {
+        OPENCV_FOURCC => ControlValueDescription::String {
+            value: fourcc_to_string(value),
+            default: None,
+        },
+        OPENCV_AUTOFOCUS | OPENCV_AUTO_WHITE_BALANCE => ControlValueDescription::Boolean {
+            value: value > 0.0,
+            default: true,
+        },
+        #[allow(clippy::cast_possible_truncation)]
+        OPENCV_BUFFER_SIZE => ControlValueDescription::Integer {
+            value: value.round() as i64,
+            default: 4,
+            step: 1,
+        },
+        _ => ControlValueDescription::Float {
+            value,
+            default: 0.0,
+            step: 0.0,
+        },
     }
 }
 
+fn index_to_i32(index: u32) -> Result<i32, NokhwaError> {
+    i32::try_from(index)
+        .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), format!("Bad index: {why}")))
+}
+
 fn get_api_pref_int() -> i32 {
     match std::env::consts::OS {
         "linux" => CAP_V4L2,
         "windows" => CAP_MSMF,
-        "mac" => CAP_AVFOUNDATION,
+        "macos" => CAP_AVFOUNDATION,
         &_ => CAP_ANY,
     }
 }
-
-// I'm done. This stupid POS refuses to actually do anything useful with camera settings
-// If anyone else wants to tackle this monster, please do.
-fn set_properties(vc: &mut VideoCapture, camera_format: CameraFormat) -> Result<(), NokhwaError> {
-    if !vc
-        .set(CAP_PROP_FRAME_WIDTH, f64::from(camera_format.width()))
-        .map_err(|why| NokhwaError::SetPropertyError {
-            property: "Resolution Width".to_string(),
-            value: camera_format.to_string(),
-            error: why.to_string(),
-        })?
-    {
-        return Err(NokhwaError::SetPropertyError {
-            property: "Resolution Width".to_string(),
-            value: camera_format.to_string(),
-            error: "false".to_string(),
-        });
-    }
-    if !vc
-        .set(CAP_PROP_FRAME_HEIGHT, f64::from(camera_format.height()))
-        .map_err(|why| NokhwaError::SetPropertyError {
-            property: "Resolution Height".to_string(),
-            value: camera_format.to_string(),
-            error: why.to_string(),
-        })?
-    {
-        return Err(NokhwaError::SetPropertyError {
-            property: "Resolution Height".to_string(),
-            value: camera_format.to_string(),
-            error: "false".to_string(),
-        });
-    }
-    if !vc
-        .set(CAP_PROP_FPS, f64::from(camera_format.frame_rate()))
-        .map_err(|why| NokhwaError::SetPropertyError {
-            property: "FPS".to_string(),
-            value: camera_format.to_string(),
-            error: why.to_string(),
-        })?
-    {
-        return Err(NokhwaError::SetPropertyError {
-            property: "FPS".to_string(),
-            value: camera_format.to_string(),
-            error: "false".to_string(),
-        });
-    }
-    Ok(())
-}
@@ -0,0 +1,835 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use ashpd::desktop::camera::{pipewire_streams, Camera as CameraPortal, Stream as PortalStream};
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
+        FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use pipewire::{
+    self as pw,
+    context::Context,
+    core::{Core, PW_ID_CORE},
+    main_loop::MainLoop,
+    node::Node,
+    properties::properties,
+    spa::{
+        param::{
+            format::{FormatProperties, MediaSubtype, MediaType},
+            video::VideoFormat,
+            ParamType,
+        },
+        pod::{
+            deserialize::PodDeserializer, serialize::PodSerializer, ChoiceValue, Object, Pod,
+            Property, PropertyFlags, Value,
+        },
+        utils::{Choice, ChoiceEnum, Direction, Fraction, Id, Rectangle, SpaTypes},
+    },
+    stream::{Stream, StreamFlags},
+    types::ObjectType,
+};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::Cursor,
+    os::fd::{AsFd, OwnedFd},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// How long [`frame()`](CaptureTrait::frame) waits for PipeWire to deliver a frame.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many frames are held for the consumer before new ones are dropped.
+const FRAME_QUEUE: usize = 2;
+
+// set once the portal granted camera access to this process
+static PORTAL_ACCESS: AtomicBool = AtomicBool::new(false);
+
+fn portal_error(why: impl ToString) -> NokhwaError {
+    NokhwaError::InitializeError {
+        backend: ApiBackend::PipeWire,
+        error: why.to_string(),
+    }
+}
+
+/// Asks the `xdg-desktop-portal` camera interface for access to the cameras, which shows a permission dialog the first time.
+/// The portal remembers the answer, later requests return without asking.
+/// # Errors
+/// If there is no portal, the system has no camera, or the user denied access, this will error.
+pub async fn request_pipewire_access() -> Result<(), NokhwaError> {
+    open_remote().await.map(drop)
+}
+
+/// Returns `true` if the camera portal granted access to this process.
+#[must_use]
+pub fn pipewire_access_granted() -> bool {
+    PORTAL_ACCESS.load(Ordering::SeqCst)
+}
+
+// Requests camera access and opens a PipeWire remote that only exposes the cameras
+async fn open_remote() -> Result<OwnedFd, NokhwaError> {
+    let portal = CameraPortal::new().await.map_err(portal_error)?;
+    if !portal.is_present().await.map_err(portal_error)? {
+        return Err(portal_error("The system has no camera"));
+    }
+    portal
+        .request_access()
+        .await
+        .and_then(|request| request.response())
+        .map_err(|why| portal_error(format!("Camera access was denied: {why}")))?;
+    PORTAL_ACCESS.store(true, Ordering::SeqCst);
+    portal.open_pipe_wire_remote().await.map_err(portal_error)
+}
+
+fn portal_streams() -> Result<(OwnedFd, Vec<PortalStream>), NokhwaError> {
+    futures_lite::future::block_on(async {
+        let remote = open_remote().await?;
+        let streams = pipewire_streams(remote.as_fd())
+            .await
+            .map_err(portal_error)?;
+        Ok((remote, streams))
+    })
+}
+
+fn camera_info(stream: &PortalStream, index: &CameraIndex) -> CameraInfo {
+    let properties = stream.properties();
+    let name = properties
+        .get("node.description")
+        .or_else(|| properties.get("node.nick"))
+        .cloned()
+        .unwrap_or_else(|| format!("PipeWire Camera {}", stream.node_id()));
+    CameraInfo::new(
+        &name,
+        &format!("PipeWire Camera @ {}", stream.node_id()),
+        &stream.node_id().to_string(),
+        index,
+    )
+}
+
+/// Query the cameras the camera portal exposes. Requests camera access first, see [`request_pipewire_access()`].
+/// # Errors
+/// If there is no portal, the user denied access, or PipeWire can not be reached, this will error.
+#[allow(clippy::cast_possible_truncation)]
+pub fn query_pipewire() -> Result<Vec<CameraInfo>, NokhwaError> {
+    let (_, streams) = portal_streams()?;
+    Ok(streams
+        .iter()
+        .enumerate()
+        .map(|(index, stream)| camera_info(stream, &CameraIndex::Index(index as u32)))
+        .collect())
+}
+
+fn frameformat_to_video_format(frame_format: FrameFormat) -> Option<VideoFormat> {
+    match frame_format {
+        FrameFormat::Yuv422 => Some(VideoFormat::YUY2),
+        FrameFormat::Uyv422 => Some(VideoFormat::UYVY),
+        FrameFormat::Nv12 => Some(VideoFormat::NV12),
+        FrameFormat::Nv21 => Some(VideoFormat::NV21),
+        FrameFormat::Yv12 => Some(VideoFormat::YV12),
+        FrameFormat::Luma8 => Some(VideoFormat::GRAY8),
+        FrameFormat::Luma16 => Some(VideoFormat::GRAY16_LE),
+        FrameFormat::Rgb8 => Some(VideoFormat::RGB),
+        FrameFormat::RgbA8 => Some(VideoFormat::RGBA),
+        _ => None,
+    }
+}
+
+fn video_format_to_frameformat(video_format: VideoFormat) -> Option<FrameFormat> {
+    match video_format {
+        VideoFormat::YUY2 => Some(FrameFormat::Yuv422),
+        VideoFormat::UYVY => Some(FrameFormat::Uyv422),
+        VideoFormat::NV12 => Some(FrameFormat::Nv12),
+        VideoFormat::NV21 => Some(FrameFormat::Nv21),
+        VideoFormat::YV12 => Some(FrameFormat::Yv12),
+        VideoFormat::GRAY8 => Some(FrameFormat::Luma8),
+        VideoFormat::GRAY16_LE => Some(FrameFormat::Luma16),
+        VideoFormat::RGB => Some(FrameFormat::Rgb8),
+        VideoFormat::RGBA => Some(FrameFormat::RgbA8),
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn frame_rate_to_fraction(frame_rate: FrameRate) -> Fraction {
+    match frame_rate {
+        FrameRate::Integer(fps) => Fraction { num: fps, denom: 1 },
+        FrameRate::Float(fps) => Fraction {
+            num: (fps * 1000.0).round() as u32,
+            denom: 1000,
+        },
+        FrameRate::Fraction {
+            numerator,
+            denominator,
+        } => Fraction {
+            num: u32::from(numerator),
+            denom: u32::from(denominator),
+        },
+    }
+}
+
+fn fraction_to_frame_rate(fraction: Fraction) -> Option<FrameRate> {
+    if fraction.denom == 1 {
+        return FrameRate::new_integer(fraction.num).ok();
+    }
+    FrameRate::new_fraction(
+        u16::try_from(fraction.num).ok()?,
+        u16::try_from(fraction.denom).ok()?,
+    )
+    .ok()
+}
+
+fn property(key: FormatProperties, value: Value) -> Property {
+    Property {
+        key: key.as_raw(),
+        flags: PropertyFlags::empty(),
+        value,
+    }
+}
+
+// The EnumFormat pod that fixes the stream to `camera_format`
+fn format_pod(camera_format: CameraFormat) -> Result<Vec<u8>, NokhwaError> {
+    let mut properties = vec![property(
+        FormatProperties::MediaType,
+        Value::Id(Id(MediaType::Video.as_raw())),
+    )];
+    let subtype = match (
+        camera_format.format(),
+        frameformat_to_video_format(camera_format.format()),
+    ) {
+        (_, Some(video_format)) => {
+            properties.push(property(
+                FormatProperties::VideoFormat,
+                Value::Id(Id(video_format.as_raw())),
+            ));
+            MediaSubtype::Raw
+        }
+        (FrameFormat::MJpeg, None) => MediaSubtype::Mjpg,
+        (FrameFormat::H264, None) => MediaSubtype::H264,
+        (frame_format, None) => {
+            return Err(NokhwaError::SetPropertyError {
+                property: "FrameFormat".to_string(),
+                value: frame_format.to_string(),
+                error: "unsupported".to_string(),
+            })
+        }
+    };
+    properties.push(property(
+        FormatProperties::MediaSubtype,
+        Value::Id(Id(subtype.as_raw())),
+    ));
+    properties.push(property(
+        FormatProperties::VideoSize,
+        Value::Rectangle(Rectangle {
+            width: camera_format.width(),
+            height: camera_format.height(),
+        }),
+    ));
+    properties.push(property(
+        FormatProperties::VideoFramerate,
+        Value::Fraction(frame_rate_to_fraction(camera_format.frame_rate())),
+    ));
+
+    let object = Value::Object(Object {
+        type_: SpaTypes::ObjectParamFormat.as_raw(),
+        id: ParamType::EnumFormat.as_raw(),
+        properties,
+    });
+    PodSerializer::serialize(Cursor::new(Vec::new()), &object)
+        .map(|(cursor, _)| cursor.into_inner())
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "EnumFormat".to_string(),
+            error: format!("{why:?}"),
+        })
+}
+
+// Every alternative a format property offers. Ranges only contribute their default, as V4L2 cameras enumerate discrete sizes.
+fn ids(value: &Value) -> Vec<u32> {
+    match value {
+        Value::Id(id) => vec![id.0],
+        Value::Choice(ChoiceValue::Id(Choice(_, choice))) => match choice {
+            ChoiceEnum::Enum {
+                default,
+                alternatives,
+            } => std::iter::once(default)
+                .chain(alternatives)
+                .map(|id| id.0)
+                .collect(),
+            ChoiceEnum::None(id) => vec![id.0],
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn rectangles(value: &Value) -> Vec<Rectangle> {
+    match value {
+        Value::Rectangle(rectangle) => vec![*rectangle],
+        Value::Choice(ChoiceValue::Rectangle(Choice(_, choice))) => match choice {
+            ChoiceEnum::Enum {
+                default,
+                alternatives,
+            } => std::iter::once(default)
+                .chain(alternatives)
+                .copied()
+                .collect(),
+            ChoiceEnum::None(rectangle)
+            | ChoiceEnum::Range {
+                default: rectangle, ..
+            } => {
+                vec![*rectangle]
+            }
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn fractions(value: &Value) -> Vec<Fraction> {
+    match value {
+        Value::Fraction(fraction) => vec![*fraction],
+        Value::Choice(ChoiceValue::Fraction(Choice(_, choice))) => match choice {
+            ChoiceEnum::Enum {
+                default,
+                alternatives,
+            } => std::iter::once(default)
+                .chain(alternatives)
+                .copied()
+                .collect(),
+            ChoiceEnum::None(fraction)
+            | ChoiceEnum::Range {
+                default: fraction, ..
+            } => {
+                vec![*fraction]
+            }
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+// The camera formats an EnumFormat pod of the camera node describes
+fn parse_format(pod: &Pod) -> Vec<CameraFormat> {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(pod.as_bytes())
+    else {
+        return vec![];
+    };
+    let find = |key: FormatProperties| {
+        object
+            .properties
+            .iter()
+            .find(|property| property.key == key.as_raw())
+            .map(|property| &property.value)
+    };
+
+    let subtype = find(FormatProperties::MediaSubtype)
+        .and_then(|value| ids(value).first().copied())
+        .map(MediaSubtype::from_raw);
+    let frame_formats = match subtype {
+        Some(MediaSubtype::Raw) => find(FormatProperties::VideoFormat)
+            .map(ids)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| video_format_to_frameformat(VideoFormat::from_raw(id)))
+            .collect(),
+        Some(MediaSubtype::Mjpg) => vec![FrameFormat::MJpeg],
+        Some(MediaSubtype::H264) => vec![FrameFormat::H264],
+        _ => vec![],
+    };
+    let sizes = find(FormatProperties::VideoSize)
+        .map(rectangles)
+        .unwrap_or_default();
+    let frame_rates = find(FormatProperties::VideoFramerate)
+        .map(fractions)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(fraction_to_frame_rate)
+        .collect::<Vec<FrameRate>>();
+
+    let mut formats = vec![];
+    for frame_format in &frame_formats {
+        for size in &sizes {
+            for frame_rate in &frame_rates {
+                formats.push(CameraFormat::new(
+                    Resolution::new(size.width, size.height),
+                    *frame_format,
+                    *frame_rate,
+                ));
+            }
+        }
+    }
+    formats
+}
+
+// Runs the loop until the server processed everything sent so far
+fn roundtrip(mainloop: &MainLoop, core: &Core) -> Result<(), pw::Error> {
+    let done = Rc::new(Cell::new(false));
+    let pending = core.sync(0)?;
+    let _listener = core
+        .add_listener_local()
+        .done({
+            let done = done.clone();
+            let mainloop = mainloop.clone();
+            move |id, seq| {
+                if id == PW_ID_CORE && seq == pending {
+                    done.set(true);
+                    mainloop.quit();
+                }
+            }
+        })
+        .register();
+    while !done.get() {
+        mainloop.run();
+    }
+    Ok(())
+}
+
+// Lists the formats the camera node offers
+fn enum_formats(remote: OwnedFd, node_id: u32) -> Result<Vec<CameraFormat>, pw::Error> {
+    pw::init();
+    let mainloop = MainLoop::new(None)?;
+    let context = Context::new(&mainloop)?;
+    let core = context.connect_fd(remote, None)?;
+    let registry = Rc::new(core.get_registry()?);
+
+    let formats = Rc::new(RefCell::new(Vec::new()));
+    // the node and its listener have to live until the parameters arrived
+    let bound = Rc::new(RefCell::new(None));
+    let _registry_listener = registry
+        .add_listener_local()
+        .global({
+            let registry = Rc::downgrade(&registry);
+            let formats = formats.clone();
+            let bound = bound.clone();
+            move |global| {
+                if global.id != node_id || global.type_ != ObjectType::Node {
+                    return;
+                }
+                let Some(Ok(node)) = registry
+                    .upgrade()
+                    .map(|registry| registry.bind::<Node, _>(global))
+                else {
+                    return;
+                };
+                let formats = formats.clone();
+                let listener = node
+                    .add_listener_local()
+                    .param(move |_, _, _, _, param| {
+                        if let Some(param) = param {
+                            formats.borrow_mut().extend(parse_format(param));
+                        }
+                    })
+                    .register();
+                node.enum_params(0, Some(ParamType::EnumFormat), 0, u32::MAX);
+                *bound.borrow_mut() = Some((node, listener));
+            }
+        })
+        .register();
+
+    // the first roundtrip binds the node, the second collects its formats
+    roundtrip(&mainloop, &core)?;
+    roundtrip(&mainloop, &core)?;
+    let formats = formats.borrow().clone();
+    Ok(formats)
+}
+
+struct PipeWireFrame {
+    data: Vec<u8>,
+    stride: usize,
+    sequence: u64,
+}
+
+struct PipeWireStream {
+    frames: Receiver<PipeWireFrame>,
+    terminate: pw::channel::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+    // the frame last handed out, borrowed by frame_raw()
+    current: Option<PipeWireFrame>,
+}
+
+impl Drop for PipeWireStream {
+    fn drop(&mut self) {
+        // fails if the loop already stopped
+        let _ = self.terminate.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Connects a stream to the camera node and runs its loop until told to terminate, handing frames to `frames`.
+// `ready` is told once the stream is connected.
+fn run_stream(
+    remote: OwnedFd,
+    node_id: u32,
+    camera_format: CameraFormat,
+    frames: SyncSender<PipeWireFrame>,
+    terminate: pw::channel::Receiver<()>,
+    ready: &mpsc::Sender<Result<(), NokhwaError>>,
+) -> Result<(), NokhwaError> {
+    let stream_error = |why: pw::Error| NokhwaError::OpenStreamError(why.to_string());
+    let format = format_pod(camera_format)?;
+
+    pw::init();
+    let mainloop = MainLoop::new(None).map_err(stream_error)?;
+    let context = Context::new(&mainloop).map_err(stream_error)?;
+    let core = context.connect_fd(remote, None).map_err(stream_error)?;
+    let _terminate = terminate.attach(mainloop.loop_(), {
+        let mainloop = mainloop.clone();
+        move |()| mainloop.quit()
+    });
+
+    let stream = Stream::new(
+        &core,
+        "nokhwa",
+        properties! {
+            *pw::keys::MEDIA_TYPE => "Video",
+            *pw::keys::MEDIA_CATEGORY => "Capture",
+            *pw::keys::MEDIA_ROLE => "Camera",
+        },
+    )
+    .map_err(stream_error)?;
+    let _listener = stream
+        .add_local_listener_with_user_data(0_u64)
+        .process(move |stream, sequence| {
+            let Some(mut buffer) = stream.dequeue_buffer() else {
+                return;
+            };
+            let Some(data) = buffer.datas_mut().first_mut() else {
+                return;
+            };
+            let chunk = data.chunk();
+            let (offset, size) = (chunk.offset() as usize, chunk.size() as usize);
+            let stride = usize::try_from(chunk.stride()).unwrap_or_default();
+            let Some(bytes) = data.data() else {
+                return;
+            };
+            let end = offset.saturating_add(size).min(bytes.len());
+            let frame = PipeWireFrame {
+                data: bytes[offset.min(end)..end].to_vec(),
+                stride,
+                sequence: *sequence,
+            };
+            *sequence += 1;
+            // the consumer fell behind, drop the frame instead of stalling the graph
+            let _ = frames.try_send(frame);
+        })
+        .register()
+        .map_err(stream_error)?;
+
+    let mut params = [Pod::from_bytes(&format)
+        .ok_or_else(|| NokhwaError::OpenStreamError("Invalid format parameter".to_string()))?];
+    stream
+        .connect(
+            Direction::Input,
+            Some(node_id),
+            StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS,
+            &mut params,
+        )
+        .map_err(stream_error)?;
+
+    let _ = ready.send(Ok(()));
+    mainloop.run();
+    Ok(())
+}
+
+/// The backend struct that captures from PipeWire through the `xdg-desktop-portal` camera interface, for sandboxed (Flatpak, Snap)
+/// applications that can not open `/dev/video*` directly.
+///
+/// To see what this does, please see [`CaptureTrait`]
+/// # Quirks
+/// - Opening a camera requests camera access from the portal, which asks the user the first time. See [`request_pipewire_access()`]
+///   and [`nokhwa_initialize_callback()`](crate::nokhwa_initialize_callback) to ask up front.
+/// - [`CameraIndex::Index`] is the position in the portal's camera list, [`CameraIndex::String`] is a PipeWire node ID.
+/// - [`CameraInfo`]'s description will be "PipeWire Camera @ {node id}", its misc field will be the node ID.
+/// - PipeWire exposes no camera controls, so [`camera_controls()`](CaptureTrait::camera_controls) is empty and setting a control errors.
+/// - If the consumer falls behind, frames are dropped rather than queued.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-pipewire")))]
+pub struct PipeWireCaptureDevice {
+    camera_info: CameraInfo,
+    node_id: u32,
+    remote: OwnedFd,
+    formats: Vec<CameraFormat>,
+    camera_format: Option<CameraFormat>,
+    stream: Option<PipeWireStream>,
+}
+
+impl PipeWireCaptureDevice {
+    /// Creates a new capture device using the PipeWire backend, requesting camera access from the portal.
+    /// # Errors
+    /// If there is no portal, the user denied access, the camera does not exist, or its formats can not be read, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let (remote, streams) = portal_streams()?;
+        let stream = match index {
+            CameraIndex::Index(idx) => streams.get(*idx as usize),
+            CameraIndex::String(id) => streams
+                .iter()
+                .find(|stream| stream.node_id().to_string() == *id),
+        }
+        .ok_or_else(|| {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+        })?;
+
+        let node_id = stream.node_id();
+        let enum_remote = remote
+            .try_clone()
+            .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?;
+        let formats = enum_formats(enum_remote, node_id)
+            .map_err(|why| NokhwaError::OpenDeviceError(index.to_string(), why.to_string()))?;
+
+        Ok(PipeWireCaptureDevice {
+            camera_info: camera_info(stream, index),
+            node_id,
+            remote,
+            formats,
+            camera_format: None,
+            stream: None,
+        })
+    }
+
+    /// Gets the PipeWire node ID of the camera.
+    #[must_use]
+    pub fn node_id(&self) -> u32 {
+        self.node_id
+    }
+
+    fn next_frame(&mut self) -> Result<&PipeWireFrame, NokhwaError> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?;
+        let frame = stream
+            .frames
+            .recv_timeout(FRAME_TIMEOUT)
+            .map_err(|why| match why {
+                RecvTimeoutError::Timeout => NokhwaError::TimeoutError(FRAME_TIMEOUT),
+                RecvTimeoutError::Disconnected => {
+                    NokhwaError::ReadFrameError("PipeWire stream stopped".to_string())
+                }
+            })?;
+        Ok(stream.current.insert(frame))
+    }
+}
+
+impl Backend for PipeWireCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::PipeWire;
+}
+
+impl CaptureTrait for PipeWireCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        let camera_format = *self
+            .formats
+            .first()
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: "CameraFormat".to_string(),
+                error: "no supported format".to_string(),
+            })?;
+        self.set_camera_format(camera_format)
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::PipeWire
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        // the format only changes when we negotiate it
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if !self.formats.contains(&new_fmt) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "Not offered by the camera".to_string(),
+            });
+        }
+
+        let current_format = self.camera_format;
+        self.camera_format = Some(new_fmt);
+        if self.is_stream_open() {
+            self.stop_stream()?;
+            if let Err(why) = self.open_stream() {
+                self.camera_format = current_format;
+                return Err(why);
+            }
+        }
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let mut resolutions: HashMap<Resolution, Vec<FrameRate>> = HashMap::new();
+        for format in self
+            .formats
+            .iter()
+            .filter(|format| format.format() == fourcc)
+        {
+            resolutions
+                .entry(format.resolution())
+                .or_default()
+                .push(format.frame_rate());
+        }
+        Ok(resolutions)
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        let mut fourccs = vec![];
+        for format in &self.formats {
+            if !fourccs.contains(&format.format()) {
+                fourccs.push(format.format());
+            }
+        }
+        Ok(fourccs)
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::MJpeg, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, _: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::PipeWire))
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(vec![])
+    }
+
+    fn set_camera_control(
+        &mut self,
+        _: KnownCameraControl,
+        _: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::PipeWire))
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.is_stream_open() {
+            self.stop_stream()?;
+        }
+
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let remote = self
+            .remote
+            .try_clone()
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        let node_id = self.node_id;
+        let (frame_sender, frames) = mpsc::sync_channel(FRAME_QUEUE);
+        let (terminate, terminate_receiver) = pw::channel::channel::<()>();
+        let (ready_sender, ready) = mpsc::channel();
+
+        // PipeWire objects can not leave the thread that created them, so the stream lives on its own thread
+        let thread = thread::spawn(move || {
+            if let Err(why) = run_stream(
+                remote,
+                node_id,
+                camera_format,
+                frame_sender,
+                terminate_receiver,
+                &ready_sender,
+            ) {
+                let _ = ready_sender.send(Err(why));
+            }
+        });
+
+        let stream = PipeWireStream {
+            frames,
+            terminate,
+            thread: Some(thread),
+            current: None,
+        };
+        ready
+            .recv_timeout(FRAME_TIMEOUT)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))??;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let frame = self.next_frame()?;
+        let buffer = Buffer::new(
+            camera_format.resolution(),
+            &frame.data,
+            camera_format.format(),
+        );
+        Ok(buffer
+            .with_stride(frame.stride)
+            .with_sequence(frame.sequence))
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        self.next_frame()
+            .map(|frame| Cow::Borrowed(frame.data.as_slice()))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stream = None;
+        Ok(())
+    }
+}
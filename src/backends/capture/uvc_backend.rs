@@ -14,552 +14,1681 @@
  * limitations under the License.
  */
 
-#![allow(clippy::too_many_arguments)]
+//! A USB Video Class backend that talks to the camera over `libusb`, without the operating system's camera stack.
+//!
+//! The descriptors are parsed from the raw configuration descriptor, the format is negotiated with the probe/commit controls,
+//! and the payloads are read with isochronous or bulk transfers and assembled into frames here.
 
-use crate::{
-    ApiBackend, CameraControl, CameraFormat, CameraInfo, CaptureBackendTrait, FrameFormat,
-    KnownCameraControl, KnownCameraControlFlag, NokhwaError, Resolution,
+use libusb1_sys::{
+    constants::{
+        LIBUSB_TRANSFER_COMPLETED, LIBUSB_TRANSFER_NO_DEVICE, LIBUSB_TRANSFER_TIMED_OUT,
+        LIBUSB_TRANSFER_TYPE_BULK, LIBUSB_TRANSFER_TYPE_ISOCHRONOUS,
+    },
+    libusb_alloc_transfer, libusb_cancel_transfer, libusb_free_transfer, libusb_submit_transfer,
+    libusb_transfer,
 };
-use flume::{Receiver, Sender};
-use image::{ImageBuffer, Rgb};
-use ouroboros::self_referencing;
+use nokhwa_core::{
+    buffer::{Buffer, FrameMetadata},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{Backend, CaptureTrait},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
+        ControlValueSetter, FrameRate, KnownCameraControl, Resolution,
+    },
+};
+use rusb::{Context, Device, DeviceHandle, UsbContext};
 use std::{
-    any::Any,
     borrow::Cow,
-    cell::{Cell, RefCell},
     collections::HashMap,
-    mem::MaybeUninit,
-    sync::{atomic::AtomicUsize, Arc},
-};
-use uvc::{
-    ActiveStream, Context, DescriptionSubtype, Device, DeviceHandle, StreamFormat, StreamHandle,
+    ffi::{c_int, c_uint, c_void},
+    mem, slice,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
-// ignore the IDE, this compiles
-/// The backend struct that interfaces with `libuvc`.
-/// To see what this does, please see [`CaptureBackendTrait`]
-/// # Quirks
-/// - You may need administrator/superuser privileges to access a UVC device.
-/// - The indexing for this backend is based off of `libuvc`'s device ordering, not the OS.
-/// - You must call [create()](UVCCaptureDevice::create()) instead `new()`, some methods are auto-generated by the self-referencer and are not meant to be used.
-/// - The [create()](UVCCaptureDevice::create()) method will open the device twice.
-/// - Calling [`set_resolution()`](CaptureBackendTrait::set_resolution()), [`set_frame_rate()`](crate::CaptureBackendTrait::set_frame_rate()), or [`set_frame_format()`](crate::CaptureBackendTrait::set_frame_format()) each internally calls [`set_camera_format()`](crate::CaptureBackendTrait::set_camera_format()).
-/// - [`frame_raw()`](crate::CaptureBackendTrait::frame_raw()) returns the same raw data as [`get_frame()`](crate::CaptureBackendTrait::frame()), a.k.a. no custom decoding required, all data is automatically RGB
-/// - The [`frame_raw()`](crate::CaptureBackendTrait::frame_raw()) and by extension [`frame()`](crate::CaptureBackendTrait::frame()) functions block.
-/// - Setting controls is not supported.
-/// - This backend, once stream is open, will constantly collect frames. When you call [`frame()`](crate::CaptureBackendTrait::frame()) or one of its variants, it will only give you the latest frame.
-/// # Safety
-/// This backend requires use of `unsafe` due to the self-referencing structs involved.
-/// - If [`open_stream()`](crate::CaptureBackendTrait::open_stream()) and [`frame()`](crate::CaptureBackendTrait::frame()) are called in the wrong order this will cause undefined behaviour.
-/// - If internal variables `stream_handle_init` and `active_stream_init` become de-synchronized with the true reality (weather streamhandle/activestream is init or not) this will cause undefined behaviour.
-#[self_referencing]
-#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-uvc")))]
-#[deprecated(
-    since = "0.10",
-    note = "Use one of the native backends instead(V4L, AVF, MSMF) or OpenCV"
-)]
-pub struct UVCCaptureDevice<'a> {
-    camera_format: CameraFormat,
-    camera_info: CameraInfo<'a>,
-    frame_receiver: Receiver<Vec<u8>>,
-    frame_sender: Sender<Vec<u8>>,
-    stream_handle_init: Cell<bool>,
-    active_stream_init: Cell<bool>,
-    context: Context<'a>,
-    #[not_covariant]
-    #[borrows(context)]
-    device: Device<'this>,
-    #[not_covariant]
-    #[borrows(device)]
-    device_handle: DeviceHandle<'this>,
-    stream_handle: RefCell<MaybeUninit<StreamHandle<'a>>>,
-    active_stream: RefCell<MaybeUninit<ActiveStream<'a, Arc<AtomicUsize>>>>,
-}
-
-impl<'a> UVCCaptureDevice<'a> {
-    /// Creates a UVC Camera device with optional [`CameraFormat`].
-    /// If `camera_format` is `None`, it will be spawned with with 640x480@15 FPS, MJPEG [`CameraFormat`] default.
-    /// # Panics
-    /// This operation may panic! If the UVC Context fails to retrieve the device from the gotten IDs, this operation will panic.
-    /// # Errors
-    /// This may error when the `libuvc` backend fails to retrieve the device or its data.
-    pub fn create(index: usize, cam_fmt: Option<CameraFormat>) -> Result<Self, NokhwaError> {
-        let context = match Context::new() {
-            Ok(ctx) => ctx,
-            Err(why) => {
-                return Err(NokhwaError::OpenDeviceError(
-                    index.to_string(),
-                    why.to_string(),
-                ))
-            }
+/// How long [`frame()`](CaptureTrait::frame) waits for a frame.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many assembled frames wait for [`frame()`](CaptureTrait::frame) before new ones are dropped.
+const FRAME_QUEUE: usize = 2;
+/// How long a control request may take.
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the event thread blocks before checking whether the stream was stopped.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+/// How many transfers are kept in flight.
+const TRANSFERS: usize = 8;
+/// How many packets each isochronous transfer holds.
+const ISO_PACKETS: u16 = 32;
+
+const DESCRIPTOR_CONFIGURATION: u8 = 0x02;
+const DESCRIPTOR_INTERFACE: u8 = 0x04;
+const DESCRIPTOR_ENDPOINT: u8 = 0x05;
+const DESCRIPTOR_CS_INTERFACE: u8 = 0x24;
+const DESCRIPTOR_SS_ENDPOINT_COMPANION: u8 = 0x30;
+const CLASS_VIDEO: u8 = 0x0e;
+const SUBCLASS_VIDEO_CONTROL: u8 = 0x01;
+const SUBCLASS_VIDEO_STREAMING: u8 = 0x02;
+
+const VC_HEADER: u8 = 0x01;
+const VC_INPUT_TERMINAL: u8 = 0x02;
+const VC_PROCESSING_UNIT: u8 = 0x05;
+const VC_EXTENSION_UNIT: u8 = 0x06;
+const ITT_CAMERA: u16 = 0x0201;
+
+const VS_FORMAT_UNCOMPRESSED: u8 = 0x04;
+const VS_FRAME_UNCOMPRESSED: u8 = 0x05;
+const VS_FORMAT_MJPEG: u8 = 0x06;
+const VS_FRAME_MJPEG: u8 = 0x07;
+const VS_FORMAT_FRAME_BASED: u8 = 0x10;
+const VS_FRAME_FRAME_BASED: u8 = 0x11;
+
+const VS_PROBE_CONTROL: u8 = 0x01;
+const VS_COMMIT_CONTROL: u8 = 0x02;
+
+const REQUEST_TYPE_GET_DESCRIPTOR: u8 = 0x80;
+const REQUEST_GET_DESCRIPTOR: u8 = 0x06;
+// class specific requests to an interface or an entity of it
+const REQUEST_TYPE_SET: u8 = 0x21;
+const REQUEST_TYPE_GET: u8 = 0xa1;
+const SET_CUR: u8 = 0x01;
+const GET_CUR: u8 = 0x81;
+const GET_MIN: u8 = 0x82;
+const GET_MAX: u8 = 0x83;
+const GET_RES: u8 = 0x84;
+const GET_LEN: u8 = 0x85;
+const GET_DEF: u8 = 0x87;
+
+// bmHeaderInfo of the payload header
+const PAYLOAD_FID: u8 = 0x01;
+const PAYLOAD_EOF: u8 = 0x02;
+const PAYLOAD_PTS: u8 = 0x04;
+const PAYLOAD_SCR: u8 = 0x08;
+const PAYLOAD_ERR: u8 = 0x40;
+
+/// The UVC entity a control belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Entity {
+    CameraTerminal,
+    ProcessingUnit,
+}
+
+/// A standard UVC control that maps to a [`KnownCameraControl`].
+#[derive(Copy, Clone, Debug)]
+struct UnitControl {
+    control: KnownCameraControl,
+    entity: Entity,
+    selector: u8,
+    // the bit of bmControls that says the entity has the control
+    bit: usize,
+    length: u16,
+    // where the value is in the control, pan and tilt share one
+    offset: usize,
+    size: u16,
+    signed: bool,
+}
+
+impl UnitControl {
+    const fn new(
+        control: KnownCameraControl,
+        entity: Entity,
+        selector: u8,
+        bit: usize,
+        size: u16,
+        signed: bool,
+    ) -> Self {
+        UnitControl {
+            control,
+            entity,
+            selector,
+            bit,
+            length: size,
+            offset: 0,
+            size,
+            signed,
+        }
+    }
+
+    const fn pan_tilt(control: KnownCameraControl, offset: usize) -> Self {
+        UnitControl {
+            control,
+            entity: Entity::CameraTerminal,
+            selector: 0x0d,
+            bit: 11,
+            length: 8,
+            offset,
+            size: 4,
+            signed: true,
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn decode(&self, data: &[u8]) -> Option<i64> {
+        let bytes = data.get(self.offset..self.offset + usize::from(self.size))?;
+        let mut raw = [0_u8; 8];
+        raw[..bytes.len()].copy_from_slice(bytes);
+        let value = u64::from_le_bytes(raw);
+        Some(if self.signed {
+            let shift = 64 - 8 * u32::from(self.size);
+            ((value << shift) as i64) >> shift
+        } else {
+            value as i64
+        })
+    }
+
+    fn encode(&self, value: i64, data: &mut [u8]) {
+        let size = usize::from(self.size);
+        data[self.offset..self.offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+}
+
+/// The controls of the camera terminal and processing unit, see the UVC 1.5 specification, 4.2.2.1 and 4.2.2.3.
+const UNIT_CONTROLS: [UnitControl; 16] = [
+    UnitControl::new(
+        KnownCameraControl::Exposure,
+        Entity::CameraTerminal,
+        0x04,
+        3,
+        4,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Focus,
+        Entity::CameraTerminal,
+        0x06,
+        5,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Iris,
+        Entity::CameraTerminal,
+        0x09,
+        7,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Zoom,
+        Entity::CameraTerminal,
+        0x0b,
+        9,
+        2,
+        false,
+    ),
+    UnitControl::pan_tilt(KnownCameraControl::Pan, 0),
+    UnitControl::pan_tilt(KnownCameraControl::Tilt, 4),
+    UnitControl::new(
+        KnownCameraControl::Privacy,
+        Entity::CameraTerminal,
+        0x11,
+        18,
+        1,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::BacklightComp,
+        Entity::ProcessingUnit,
+        0x01,
+        8,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Brightness,
+        Entity::ProcessingUnit,
+        0x02,
+        0,
+        2,
+        true,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Contrast,
+        Entity::ProcessingUnit,
+        0x03,
+        1,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Gain,
+        Entity::ProcessingUnit,
+        0x04,
+        9,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Hue,
+        Entity::ProcessingUnit,
+        0x06,
+        2,
+        2,
+        true,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Saturation,
+        Entity::ProcessingUnit,
+        0x07,
+        3,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Sharpness,
+        Entity::ProcessingUnit,
+        0x08,
+        4,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::Gamma,
+        Entity::ProcessingUnit,
+        0x09,
+        5,
+        2,
+        false,
+    ),
+    UnitControl::new(
+        KnownCameraControl::WhiteBalance,
+        Entity::ProcessingUnit,
+        0x0a,
+        6,
+        2,
+        false,
+    ),
+];
+
+/// An extension unit of a UVC camera, which holds vendor specific controls.
+///
+/// The layout of its controls is up to the vendor, who identifies the unit by its [`guid()`](UvcExtensionUnit::guid).
+/// Use [`UVCCaptureDevice::extension_unit_control()`] and [`UVCCaptureDevice::set_extension_unit_control()`] to access them.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UvcExtensionUnit {
+    unit_id: u8,
+    guid: [u8; 16],
+    controls: Vec<u8>,
+}
+
+impl UvcExtensionUnit {
+    /// Gets the ID of the unit, which addresses it in control requests.
+    #[must_use]
+    pub fn unit_id(&self) -> u8 {
+        self.unit_id
+    }
+
+    /// Gets the GUID of the unit as it is in the descriptor, the first three fields are little endian.
+    #[must_use]
+    pub fn guid(&self) -> [u8; 16] {
+        self.guid
+    }
+
+    /// Checks whether the unit says it has the control `selector`. Selectors start at `1`.
+    #[must_use]
+    pub fn has_control(&self, selector: u8) -> bool {
+        let Some(bit) = usize::from(selector).checked_sub(1) else {
+            return false;
         };
+        self.controls
+            .get(bit / 8)
+            .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+    }
 
-        let (camera_info, frame_receiver, frame_sender) = {
-            let device_list = match context.devices() {
-                Ok(device_list) => device_list,
-                Err(why) => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        why.to_string(),
-                    ))
-                }
-            };
+    /// Gets the selectors of the controls the unit says it has.
+    #[must_use]
+    pub fn controls(&self) -> Vec<u8> {
+        (1..=u8::MAX)
+            .take(self.controls.len() * 8)
+            .filter(|selector| self.has_control(*selector))
+            .collect()
+    }
+}
 
-            let device = match device_list.into_iter().nth(index) {
-                Some(d) => d,
-                None => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        "Not Found".to_string(),
-                    ))
-                }
-            };
+#[derive(Clone, Debug)]
+struct Unit {
+    id: u8,
+    controls: Vec<u8>,
+}
 
-            let device_desc = match device.description() {
-                Ok(desc) => desc,
-                Err(why) => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        why.to_string(),
-                    ))
-                }
-            };
+impl Unit {
+    fn has_bit(&self, bit: usize) -> bool {
+        self.controls
+            .get(bit / 8)
+            .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct UvcFrameDescriptor {
+    index: u8,
+    resolution: Resolution,
+    // frame intervals in 100ns units
+    intervals: Vec<u32>,
+}
+
+#[derive(Clone, Debug)]
+struct UvcFormat {
+    index: u8,
+    frame_format: FrameFormat,
+    frames: Vec<UvcFrameDescriptor>,
+    known: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TransferKind {
+    Isochronous,
+    Bulk,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct AlternateSetting {
+    setting: u8,
+    endpoint: u8,
+    kind: TransferKind,
+    // bytes per (micro)frame for isochronous endpoints, the packet size for bulk ones
+    packet_size: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Descriptors {
+    uvc_version: u16,
+    control_interface: u8,
+    streaming_interface: u8,
+    camera_terminal: Option<Unit>,
+    processing_unit: Option<Unit>,
+    extension_units: Vec<UvcExtensionUnit>,
+    formats: Vec<UvcFormat>,
+    alternate_settings: Vec<AlternateSetting>,
+}
 
-            let device_name = match (device_desc.manufacturer, device_desc.product) {
-                (Some(manu), Some(prod)) => {
-                    format!("{} {}", manu, prod)
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    data.get(offset..offset + 2)
+        .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |bytes| {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+}
+
+fn guid_to_frameformat(guid: &[u8]) -> Option<FrameFormat> {
+    // the format GUIDs are the FourCC followed by the same 12 bytes
+    match guid.get(..4)? {
+        b"YUY2" => Some(FrameFormat::Yuv422),
+        b"UYVY" => Some(FrameFormat::Uyv422),
+        b"NV12" => Some(FrameFormat::Nv12),
+        b"NV21" => Some(FrameFormat::Nv21),
+        b"YV12" => Some(FrameFormat::Yv12),
+        b"Y800" | b"GREY" | b"Y8  " => Some(FrameFormat::Luma8),
+        b"Y16 " => Some(FrameFormat::Luma16),
+        b"Z16 " => Some(FrameFormat::Depth16),
+        b"MJPG" => Some(FrameFormat::MJpeg),
+        b"H264" => Some(FrameFormat::H264),
+        b"H265" | b"HEVC" => Some(FrameFormat::H265),
+        _ => None,
+    }
+}
+
+// the size an uncompressed frame has to reach, shorter ones lost payloads on the way
+fn uncompressed_frame_size(format: FrameFormat, resolution: Resolution) -> Option<usize> {
+    let pixels = resolution.width() as usize * resolution.height() as usize;
+    match format {
+        FrameFormat::Yuv422
+        | FrameFormat::Uyv422
+        | FrameFormat::Luma16
+        | FrameFormat::Depth16 => Some(pixels * 2),
+        FrameFormat::Nv12 | FrameFormat::Nv21 | FrameFormat::Yv12 => Some(pixels * 3 / 2),
+        FrameFormat::Luma8 => Some(pixels),
+        _ => None,
+    }
+}
+
+// bFrameIntervalType is followed by the intervals, which start at offset 26 in every frame descriptor
+fn frame_intervals(descriptor: &[u8], type_offset: usize) -> Vec<u32> {
+    let interval_offset = 26;
+    let count = descriptor.get(type_offset).copied().unwrap_or(0);
+    if count == 0 {
+        // continuous: minimum, maximum and step. Offer both ends and the common rates in between.
+        let min = read_u32(descriptor, interval_offset);
+        let max = read_u32(descriptor, interval_offset + 4);
+        let step = read_u32(descriptor, interval_offset + 8).max(1);
+        let mut intervals = [5, 10, 15, 20, 24, 25, 30, 60, 120]
+            .into_iter()
+            .map(|fps| 10_000_000 / fps)
+            .filter(|interval| {
+                (min..=max).contains(interval) && (interval - min).checked_rem(step) == Some(0)
+            })
+            .collect::<Vec<_>>();
+        intervals.extend([min, max]);
+        intervals.sort_unstable();
+        intervals.dedup();
+        intervals.retain(|interval| *interval != 0);
+        intervals
+    } else {
+        (0..usize::from(count))
+            .map(|index| read_u32(descriptor, interval_offset + index * 4))
+            .filter(|interval| *interval != 0)
+            .collect()
+    }
+}
+
+fn interval_to_frame_rate(interval: u32) -> u32 {
+    (10_000_000 + interval / 2) / interval
+}
+
+#[derive(Clone, Debug, Default)]
+struct StreamingInterface {
+    number: u8,
+    formats: Vec<UvcFormat>,
+    alternate_settings: Vec<AlternateSetting>,
+}
+
+// walks the configuration descriptor, which holds the class specific descriptors of every interface in order
+fn parse_descriptors(config: &[u8]) -> Option<Descriptors> {
+    let mut descriptors = Descriptors::default();
+    let mut control_interface = None;
+    let mut streaming: Vec<StreamingInterface> = Vec::new();
+    // the interface, alternate setting, class and subclass the following descriptors belong to
+    let mut current = (0, 0, 0, 0);
+
+    let mut rest = config;
+    while let [length, descriptor_type, ..] = *rest {
+        let length = usize::from(length);
+        if length < 2 || length > rest.len() {
+            break;
+        }
+        let (descriptor, next) = rest.split_at(length);
+        rest = next;
+
+        match (descriptor_type, current) {
+            (DESCRIPTOR_INTERFACE, _) if descriptor.len() >= 9 => {
+                current = (descriptor[2], descriptor[3], descriptor[5], descriptor[6]);
+                match current {
+                    (number, _, CLASS_VIDEO, SUBCLASS_VIDEO_CONTROL) => {
+                        control_interface.get_or_insert(number);
+                    }
+                    (number, _, CLASS_VIDEO, SUBCLASS_VIDEO_STREAMING)
+                        if streaming.iter().all(|interface| interface.number != number) =>
+                    {
+                        streaming.push(StreamingInterface {
+                            number,
+                            ..StreamingInterface::default()
+                        });
+                    }
+                    _ => {}
                 }
-                (_, Some(prod)) => prod,
-                (Some(manu), _) => {
-                    format!(
-                        "{}:{} {}",
-                        device_desc.vendor_id, device_desc.product_id, manu
-                    )
+            }
+            (DESCRIPTOR_ENDPOINT, (number, setting, CLASS_VIDEO, SUBCLASS_VIDEO_STREAMING))
+                if descriptor.len() >= 7 && descriptor[2] & 0x80 != 0 =>
+            {
+                let Some(interface) = streaming
+                    .iter_mut()
+                    .find(|interface| interface.number == number)
+                else {
+                    continue;
+                };
+                let kind = match descriptor[3] & 0x03 {
+                    0x01 => TransferKind::Isochronous,
+                    0x02 => TransferKind::Bulk,
+                    _ => continue,
+                };
+                // high speed endpoints send up to 3 transactions per microframe
+                let max_packet_size = usize::from(read_u16(descriptor, 4));
+                interface.alternate_settings.push(AlternateSetting {
+                    setting,
+                    endpoint: descriptor[2],
+                    kind,
+                    packet_size: (max_packet_size & 0x07ff)
+                        * (1 + ((max_packet_size >> 11) & 0x03)),
+                });
+            }
+            (
+                DESCRIPTOR_SS_ENDPOINT_COMPANION,
+                (number, _, CLASS_VIDEO, SUBCLASS_VIDEO_STREAMING),
+            ) if descriptor.len() >= 6 => {
+                // super speed endpoints say how much they send per service interval here
+                let bytes_per_interval = usize::from(read_u16(descriptor, 4));
+                if let Some(alternate) = streaming
+                    .iter_mut()
+                    .find(|interface| interface.number == number)
+                    .and_then(|interface| interface.alternate_settings.last_mut())
+                {
+                    if alternate.kind == TransferKind::Isochronous && bytes_per_interval != 0 {
+                        alternate.packet_size = bytes_per_interval;
+                    }
                 }
-                (_, _) => {
-                    format!("{}:{}", device_desc.vendor_id, device_desc.product_id)
+            }
+            (DESCRIPTOR_CS_INTERFACE, (_, 0, CLASS_VIDEO, SUBCLASS_VIDEO_CONTROL))
+                if descriptor.len() >= 3 =>
+            {
+                parse_control_descriptor(descriptor, &mut descriptors);
+            }
+            (DESCRIPTOR_CS_INTERFACE, (number, 0, CLASS_VIDEO, SUBCLASS_VIDEO_STREAMING))
+                if descriptor.len() >= 3 =>
+            {
+                if let Some(interface) = streaming
+                    .iter_mut()
+                    .find(|interface| interface.number == number)
+                {
+                    parse_streaming_descriptor(descriptor, &mut interface.formats);
                 }
-            };
+            }
+            _ => {}
+        }
+    }
 
-            let camera_info = CameraInfo::new(
-                device_name,
-                "".to_string(),
-                format!("{}:{}", device_desc.vendor_id, device_desc.product_id),
-                index,
-            );
+    descriptors.control_interface = control_interface?;
+    // the first streaming interface with a format we know is the camera, others are e.g. metadata
+    let mut interface = streaming.into_iter().find_map(|mut interface| {
+        interface.formats.retain(|format| !format.frames.is_empty());
+        (!interface.formats.is_empty()).then_some(interface)
+    })?;
+    interface
+        .alternate_settings
+        .sort_by_key(|alternate| alternate.packet_size);
+    descriptors.streaming_interface = interface.number;
+    descriptors.formats = interface.formats;
+    descriptors.alternate_settings = interface.alternate_settings;
+    Some(descriptors)
+}
 
-            let (frame_sender, frame_receiver) = {
-                let (a, b) = flume::unbounded::<Vec<u8>>();
-                (a, b)
+fn parse_control_descriptor(descriptor: &[u8], descriptors: &mut Descriptors) {
+    match descriptor[2] {
+        VC_HEADER => descriptors.uvc_version = read_u16(descriptor, 3),
+        VC_INPUT_TERMINAL if descriptor.len() >= 15 && read_u16(descriptor, 4) == ITT_CAMERA => {
+            let size = usize::from(descriptor[14]);
+            descriptors.camera_terminal = Some(Unit {
+                id: descriptor[3],
+                controls: descriptor.get(15..15 + size).unwrap_or_default().to_vec(),
+            });
+        }
+        VC_PROCESSING_UNIT if descriptor.len() >= 8 => {
+            let size = usize::from(descriptor[7]);
+            descriptors.processing_unit = Some(Unit {
+                id: descriptor[3],
+                controls: descriptor.get(8..8 + size).unwrap_or_default().to_vec(),
+            });
+        }
+        VC_EXTENSION_UNIT if descriptor.len() >= 24 => {
+            let pins = usize::from(descriptor[21]);
+            let size = usize::from(descriptor.get(22 + pins).copied().unwrap_or(0));
+            let mut guid = [0_u8; 16];
+            guid.copy_from_slice(&descriptor[4..20]);
+            descriptors.extension_units.push(UvcExtensionUnit {
+                unit_id: descriptor[3],
+                guid,
+                controls: descriptor
+                    .get(23 + pins..23 + pins + size)
+                    .unwrap_or_default()
+                    .to_vec(),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn parse_streaming_descriptor(descriptor: &[u8], formats: &mut Vec<UvcFormat>) {
+    let subtype = descriptor[2];
+    match subtype {
+        VS_FORMAT_UNCOMPRESSED | VS_FORMAT_MJPEG | VS_FORMAT_FRAME_BASED
+            if descriptor.len() >= 4 =>
+        {
+            let frame_format = if subtype == VS_FORMAT_MJPEG {
+                Some(FrameFormat::MJpeg)
+            } else {
+                descriptor.get(5..21).and_then(guid_to_frameformat)
             };
-            (camera_info, frame_receiver, frame_sender)
-        };
+            // formats we can not name are kept without frames, so their frames are not taken for another format's
+            formats.push(UvcFormat {
+                index: descriptor[3],
+                frame_format: frame_format.unwrap_or(FrameFormat::MJpeg),
+                frames: vec![],
+                known: frame_format.is_some(),
+            });
+        }
+        VS_FRAME_UNCOMPRESSED | VS_FRAME_MJPEG | VS_FRAME_FRAME_BASED if descriptor.len() >= 26 => {
+            let Some(format) = formats.last_mut().filter(|format| format.known) else {
+                return;
+            };
+            // frame based descriptors have no dwMaxVideoFrameBufferSize, but dwBytesPerLine after bFrameIntervalType
+            let type_offset = if subtype == VS_FRAME_FRAME_BASED {
+                21
+            } else {
+                25
+            };
+            format.frames.push(UvcFrameDescriptor {
+                index: descriptor[3],
+                resolution: Resolution::new(
+                    u32::from(read_u16(descriptor, 5)),
+                    u32::from(read_u16(descriptor, 7)),
+                ),
+                intervals: frame_intervals(descriptor, type_offset),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn usb_error(index: &CameraIndex, why: rusb::Error) -> NokhwaError {
+    NokhwaError::OpenDeviceError(index.to_string(), why.to_string())
+}
+
+fn is_uvc_device(device: &Device<Context>) -> bool {
+    let Ok(config) = device
+        .active_config_descriptor()
+        .or_else(|_| device.config_descriptor(0))
+    else {
+        return false;
+    };
+    config.interfaces().any(|interface| {
+        interface.descriptors().any(|descriptor| {
+            descriptor.class_code() == CLASS_VIDEO
+                && descriptor.sub_class_code() == SUBCLASS_VIDEO_CONTROL
+        })
+    })
+}
+
+fn uvc_devices(context: &Context) -> Result<Vec<Device<Context>>, rusb::Error> {
+    Ok(context.devices()?.iter().filter(is_uvc_device).collect())
+}
+
+fn camera_info(device: &Device<Context>, index: &CameraIndex) -> CameraInfo {
+    let (vendor_id, product_id) = device.device_descriptor().map_or((0, 0), |descriptor| {
+        (descriptor.vendor_id(), descriptor.product_id())
+    });
+    // the product string needs the device opened, which needs permission on most platforms
+    let product = device.device_descriptor().ok().and_then(|descriptor| {
+        device
+            .open()
+            .ok()?
+            .read_product_string_ascii(&descriptor)
+            .ok()
+    });
+    let usb_id = format!("{vendor_id:04x}:{product_id:04x}");
+    CameraInfo::new(
+        &product.unwrap_or_else(|| format!("UVC Camera {usb_id}")),
+        &format!(
+            "UVC Camera {usb_id} on bus {} address {}",
+            device.bus_number(),
+            device.address()
+        ),
+        &format!("{}:{}", device.bus_number(), device.address()),
+        index,
+    )
+}
 
-        let camera_format = match cam_fmt {
-            Some(cfmt) => cfmt,
-            None => CameraFormat::default(),
+/// Lists the USB Video Class cameras on every USB bus.
+///
+/// The [`CameraInfo::misc()`] of each camera is `bus:address`, which can be passed as a [`CameraIndex::String`].
+/// # Errors
+/// If `libusb` fails to initialize or list the USB devices, this will error.
+pub fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
+    let context = Context::new().map_err(|why| NokhwaError::InitializeError {
+        backend: ApiBackend::UniversalVideoClass,
+        error: why.to_string(),
+    })?;
+    let devices =
+        uvc_devices(&context).map_err(|why| NokhwaError::GeneralError(why.to_string()))?;
+    Ok(devices
+        .iter()
+        .zip(0_u32..)
+        .map(|(device, index)| camera_info(device, &CameraIndex::Index(index)))
+        .collect())
+}
+
+/// A frame assembled from payloads.
+struct UvcFrame {
+    data: Vec<u8>,
+    metadata: FrameMetadata,
+    sequence: u64,
+}
+
+struct FrameAssembler {
+    data: Vec<u8>,
+    metadata: FrameMetadata,
+    fid: Option<bool>,
+    error: bool,
+    // uncompressed frames shorter than this lost payloads
+    expected_size: Option<usize>,
+    sequence: u64,
+    frames: SyncSender<UvcFrame>,
+}
+
+impl FrameAssembler {
+    // bHeaderLength, bmHeaderInfo, then dwPresentationTime and scrSourceClock if flagged
+    fn push_payload(&mut self, payload: &[u8]) {
+        let [header_length, info, ..] = *payload else {
+            return;
         };
+        let header_length = usize::from(header_length);
+        if header_length < 2 || header_length > payload.len() {
+            return;
+        }
 
-        Ok(UVCCaptureDeviceBuilder {
-            camera_format,
-            camera_info,
-            frame_receiver,
-            frame_sender,
-            context,
-            stream_handle_init: Cell::new(false),
-            active_stream_init: Cell::new(false),
-            device_builder: |context_builder| {
-                context_builder
-                    .devices()
-                    .unwrap()
-                    .into_iter()
-                    .nth(index)
-                    .unwrap()
-            },
-            device_handle_builder: |device_builder| device_builder.open().unwrap(),
-            stream_handle: RefCell::new(MaybeUninit::uninit()),
-            active_stream: RefCell::new(MaybeUninit::uninit()),
-        }
-        .build())
-    }
-
-    /// Create a UVC Camera with desired settings.
-    /// # Panics
-    /// This operation may panic! If the UVC Context fails to retrieve the device from the gotten IDs, this operation will panic.
-    /// # Errors
-    /// This may error when the `libuvc` backend fails to retrieve the device or its data.
-    pub fn create_with(
-        index: usize,
-        width: u32,
-        height: u32,
-        fps: u32,
-        fourcc: FrameFormat,
-    ) -> Result<Self, NokhwaError> {
-        let camera_format = Some(CameraFormat::new_from(width, height, fourcc, fps));
-        UVCCaptureDevice::create(index, camera_format)
+        // the frame ID toggles with every frame, in case the camera never sets end of frame
+        let fid = info & PAYLOAD_FID != 0;
+        if self.fid.is_some_and(|previous| previous != fid) && !self.data.is_empty() {
+            self.finish();
+        }
+        self.fid = Some(fid);
+        if info & PAYLOAD_ERR != 0 {
+            self.error = true;
+        }
+
+        let header = &payload[..header_length];
+        let mut fields = &header[2..];
+        if info & PAYLOAD_PTS != 0 {
+            if let [a, b, c, d, ref rest @ ..] = *fields {
+                self.metadata
+                    .presentation_time
+                    .get_or_insert(u32::from_le_bytes([a, b, c, d]));
+                fields = rest;
+            }
+        }
+        if info & PAYLOAD_SCR != 0 {
+            if let [a, b, c, d, e, f, ..] = *fields {
+                // the last reference of the frame is the most precise
+                self.metadata.source_clock = Some((
+                    u32::from_le_bytes([a, b, c, d]),
+                    u16::from_le_bytes([e, f]) & 0x07ff,
+                ));
+            }
+        }
+        if self.data.is_empty() {
+            self.metadata.raw = header.to_vec();
+        }
+
+        self.data.extend_from_slice(&payload[header_length..]);
+        if info & PAYLOAD_EOF != 0 {
+            self.finish();
+        }
     }
-}
 
-// IDE Autocomplete ends here. Do not be afraid it your IDE does not show completion.
-// Here are some docs to help you out: https://docs.rs/ouroboros/0.9.3/ouroboros/attr.self_referencing.html
-impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
-    fn backend(&self) -> ApiBackend {
-        ApiBackend::UniversalVideoClass
+    fn finish(&mut self) {
+        let data = mem::take(&mut self.data);
+        let metadata = mem::take(&mut self.metadata);
+        let error = mem::replace(&mut self.error, false);
+        let complete = self
+            .expected_size
+            .map_or(!data.is_empty(), |size| data.len() >= size);
+        if error || !complete {
+            return;
+        }
+        self.sequence += 1;
+        // the queue is full if frames are not read fast enough, the newest ones are dropped then
+        let _ = self.frames.try_send(UvcFrame {
+            data,
+            metadata,
+            sequence: self.sequence,
+        });
     }
+}
 
-    fn camera_info(&self) -> &CameraInfo {
-        self.borrow_camera_info()
+/// State shared with the transfer callbacks.
+struct StreamState {
+    assembler: Mutex<FrameAssembler>,
+    running: AtomicBool,
+    lost: AtomicBool,
+    // transfers that were submitted and did not come back yet
+    pending: AtomicUsize,
+    packet_size: usize,
+}
+
+extern "system" fn transfer_done(transfer: *mut libusb_transfer) {
+    // SAFETY: user_data is the stream's state, which outlives its transfers
+    let state = unsafe { &*(*transfer).user_data.cast::<StreamState>() };
+    let status = unsafe { (*transfer).status };
+
+    if status == LIBUSB_TRANSFER_COMPLETED {
+        if let Ok(mut assembler) = state.assembler.lock() {
+            let packets = usize::try_from(unsafe { (*transfer).num_iso_packets }).unwrap_or(0);
+            if packets == 0 {
+                // a bulk transfer holds one payload
+                let payload = unsafe {
+                    slice::from_raw_parts(
+                        (*transfer).buffer,
+                        usize::try_from((*transfer).actual_length).unwrap_or(0),
+                    )
+                };
+                assembler.push_payload(payload);
+            } else {
+                let packets =
+                    unsafe { slice::from_raw_parts((*transfer).iso_packet_desc.as_ptr(), packets) };
+                for (index, packet) in packets.iter().enumerate() {
+                    if packet.status != LIBUSB_TRANSFER_COMPLETED || packet.actual_length == 0 {
+                        continue;
+                    }
+                    // every packet has its own slot of the buffer, however much it was filled
+                    let payload = unsafe {
+                        slice::from_raw_parts(
+                            (*transfer).buffer.add(index * state.packet_size),
+                            packet.actual_length as usize,
+                        )
+                    };
+                    assembler.push_payload(payload);
+                }
+            }
+        }
+    } else if status == LIBUSB_TRANSFER_NO_DEVICE {
+        state.lost.store(true, Ordering::SeqCst);
     }
 
-    fn camera_format(&self) -> CameraFormat {
-        *self.borrow_camera_format()
+    let resubmit = state.running.load(Ordering::SeqCst)
+        && (status == LIBUSB_TRANSFER_COMPLETED || status == LIBUSB_TRANSFER_TIMED_OUT);
+    if !resubmit || unsafe { libusb_submit_transfer(transfer) } != 0 {
+        state.pending.fetch_sub(1, Ordering::SeqCst);
     }
+}
 
-    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        let prev_fmt = *self.borrow_camera_format();
+struct UvcStream {
+    state: Arc<StreamState>,
+    frames: Receiver<UvcFrame>,
+    current: Option<UvcFrame>,
+    transfers: Vec<*mut libusb_transfer>,
+    // the transfers write into these
+    buffers: Vec<Vec<u8>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// SAFETY: the transfers are only touched by libusb's event handling until they are cancelled in drop
+unsafe impl Send for UvcStream {}
 
-        self.with_camera_format_mut(|cfmt| {
-            *cfmt = new_fmt;
+impl UvcStream {
+    // the stream has to be dropped before `handle` is closed
+    fn new(
+        context: &Context,
+        handle: &DeviceHandle<Context>,
+        alternate: AlternateSetting,
+        max_payload: usize,
+        expected_size: Option<usize>,
+    ) -> Result<Self, NokhwaError> {
+        let (frame_sender, frames) = mpsc::sync_channel(FRAME_QUEUE);
+        let packet_size = match alternate.kind {
+            TransferKind::Isochronous => alternate.packet_size,
+            // a bulk transfer holds a whole payload
+            TransferKind::Bulk => max_payload.max(alternate.packet_size),
+        };
+        let state = Arc::new(StreamState {
+            assembler: Mutex::new(FrameAssembler {
+                data: Vec::new(),
+                metadata: FrameMetadata::default(),
+                fid: None,
+                error: false,
+                expected_size,
+                sequence: 0,
+                frames: frame_sender,
+            }),
+            running: AtomicBool::new(true),
+            lost: AtomicBool::new(false),
+            pending: AtomicUsize::new(0),
+            packet_size,
+        });
+
+        let thread_state = state.clone();
+        let context = context.clone();
+        let thread = thread::spawn(move || {
+            // the callbacks run in here, until the last cancelled transfer came back
+            while thread_state.running.load(Ordering::SeqCst)
+                || thread_state.pending.load(Ordering::SeqCst) > 0
+            {
+                let _ = context.handle_events(Some(POLL_TIMEOUT));
+            }
         });
+        let mut stream = UvcStream {
+            state,
+            frames,
+            current: None,
+            transfers: Vec::new(),
+            buffers: Vec::new(),
+            thread: Some(thread),
+        };
+        // on error, dropping the stream cancels the transfers that were submitted
+        stream.submit_transfers(handle, alternate)?;
+        Ok(stream)
+    }
 
-        let is_streamh_some = self.borrow_stream_handle_init().get();
-
-        if is_streamh_some {
-            return match self.open_stream() {
-                Ok(_) => Ok(()),
-                Err(why) => {
-                    // revert
-                    self.with_camera_format_mut(|cfmt| {
-                        *cfmt = prev_fmt;
-                    });
-                    Err(NokhwaError::SetPropertyError {
-                        property: "CameraFormat".to_string(),
-                        value: new_fmt.to_string(),
-                        error: why.to_string(),
-                    })
+    fn submit_transfers(
+        &mut self,
+        handle: &DeviceHandle<Context>,
+        alternate: AlternateSetting,
+    ) -> Result<(), NokhwaError> {
+        let packet_size = self.state.packet_size;
+        let (transfer_type, packets) = match alternate.kind {
+            TransferKind::Isochronous => (LIBUSB_TRANSFER_TYPE_ISOCHRONOUS, ISO_PACKETS),
+            TransferKind::Bulk => (LIBUSB_TRANSFER_TYPE_BULK, 0),
+        };
+        let packet_length = c_uint::try_from(packet_size)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        for _ in 0..TRANSFERS {
+            let mut buffer = vec![0_u8; packet_size * usize::from(packets.max(1))];
+            let transfer = unsafe { libusb_alloc_transfer(c_int::from(packets)) };
+            if transfer.is_null() {
+                return Err(NokhwaError::OpenStreamError(
+                    "libusb_alloc_transfer failed".to_string(),
+                ));
+            }
+            unsafe {
+                (*transfer).dev_handle = handle.as_raw();
+                (*transfer).endpoint = alternate.endpoint;
+                (*transfer).transfer_type = transfer_type;
+                (*transfer).timeout = 0;
+                (*transfer).buffer = buffer.as_mut_ptr();
+                (*transfer).length = c_int::try_from(buffer.len()).unwrap_or(c_int::MAX);
+                (*transfer).num_iso_packets = c_int::from(packets);
+                (*transfer).callback = transfer_done;
+                (*transfer).user_data = Arc::as_ptr(&self.state).cast_mut().cast::<c_void>();
+                let descriptors = (*transfer).iso_packet_desc.as_mut_ptr();
+                for index in 0..usize::from(packets) {
+                    (*descriptors.add(index)).length = packet_length;
                 }
-            };
+            }
+            self.transfers.push(transfer);
+            self.buffers.push(buffer);
+
+            self.state.pending.fetch_add(1, Ordering::SeqCst);
+            let status = unsafe { libusb_submit_transfer(transfer) };
+            if status != 0 {
+                self.state.pending.fetch_sub(1, Ordering::SeqCst);
+                return Err(NokhwaError::OpenStreamError(format!(
+                    "libusb_submit_transfer failed: {status}"
+                )));
+            }
         }
         Ok(())
     }
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn compatible_list_by_resolution(
-        &mut self,
-        fourcc: FrameFormat,
-    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
-        let mut resolution_fps_map: HashMap<Resolution, Vec<u32>> = HashMap::new();
-        for fmt in self.with_device_handle(|devh| devh).supported_formats() {
-            for frame_desc in fmt.supported_formats() {
-                // FIXME: Verify that this is correct way to interpret DescriptionSubtype!
-                let format = match frame_desc.subtype() {
-                    DescriptionSubtype::FormatMJPEG | DescriptionSubtype::FrameMJPEG => {
-                        FrameFormat::MJPEG
-                    }
-                    DescriptionSubtype::FormatUncompressed
-                    | DescriptionSubtype::FrameUncompressed => FrameFormat::YUYV,
-                    _ => continue,
-                };
-
-                if format != fourcc {
-                    continue;
+    fn next_frame(&mut self) -> Result<&UvcFrame, NokhwaError> {
+        let frame = self
+            .frames
+            .recv_timeout(FRAME_TIMEOUT)
+            .map_err(|why| match why {
+                _ if self.state.lost.load(Ordering::SeqCst) => {
+                    NokhwaError::DeviceLost("The camera was unplugged".to_string())
+                }
+                RecvTimeoutError::Timeout => NokhwaError::TimeoutError(FRAME_TIMEOUT),
+                RecvTimeoutError::Disconnected => {
+                    NokhwaError::ReadFrameError("UVC stream stopped".to_string())
                 }
+            })?;
+        Ok(self.current.insert(frame))
+    }
+}
 
-                let resolution =
-                    Resolution::new(frame_desc.width().into(), frame_desc.height().into());
-                let fps: Vec<u32> = frame_desc
-                    .intervals_duration()
-                    .into_iter()
-                    .map(|duration| (1000 / duration.as_millis()) as u32)
-                    .collect();
-                resolution_fps_map.insert(resolution, fps);
-            }
+impl Drop for UvcStream {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::SeqCst);
+        for transfer in &self.transfers {
+            // transfers that already came back fail to cancel, which is fine
+            unsafe { libusb_cancel_transfer(*transfer) };
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // the event thread only stops once no transfer is pending, so none is in use anymore
+        for transfer in self.transfers.drain(..) {
+            unsafe { libusb_free_transfer(transfer) };
         }
-        Ok(resolution_fps_map)
     }
+}
 
-    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
-        let mut frameformats = vec![];
-        for fmt in self.with_device_handle(|devh| devh).supported_formats() {
-            for frame_desc in fmt.supported_formats() {
-                // FIXME: Verify that this is correct way to interpret DescriptionSubtype!
-                match frame_desc.subtype() {
-                    DescriptionSubtype::FormatMJPEG | DescriptionSubtype::FrameMJPEG => {
-                        frameformats.push(FrameFormat::MJPEG);
-                    }
-                    DescriptionSubtype::FormatUncompressed
-                    | DescriptionSubtype::FrameUncompressed => frameformats.push(FrameFormat::YUYV),
-                    _ => continue,
-                };
+/// The USB Video Class backend, which drives the camera directly over `libusb`.
+///
+/// This is useful on platforms whose camera stack is broken or missing, and to reach the UVC extension unit controls the operating system hides.
+/// # Quirks
+/// - The camera is claimed from the operating system's driver while it is open, so it disappears from other backends (e.g. the `/dev/video*` node) until it is dropped.
+/// - On Linux this needs write access to the device node in `/dev/bus/usb`, usually granted through a `udev` rule. On Windows the camera needs the `WinUSB` driver, e.g. installed with Zadig.
+/// - Frames are timestamped with the camera's clock only, see [`FrameMetadata`].
+/// - Still image capture and the UVC 1.5 encoding unit are not supported.
+pub struct UVCCaptureDevice {
+    camera_info: CameraInfo,
+    camera_format: Option<CameraFormat>,
+    descriptors: Descriptors,
+    stream: Option<UvcStream>,
+    handle: DeviceHandle<Context>,
+    context: Context,
+}
+
+impl UVCCaptureDevice {
+    /// Creates a new capture device using the USB Video Class backend.
+    ///
+    /// `index` is either an index into [`query_uvc()`] or the `bus:address` of the camera.
+    /// # Errors
+    /// If the camera does not exist, can not be opened (see Quirks), or its descriptors can not be parsed, this will error.
+    pub fn new(index: &CameraIndex) -> Result<Self, NokhwaError> {
+        let context = Context::new().map_err(|why| usb_error(index, why))?;
+        let devices = uvc_devices(&context).map_err(|why| usb_error(index, why))?;
+        let device = match index {
+            CameraIndex::Index(idx) => devices.get(*idx as usize),
+            CameraIndex::String(location) => devices.iter().find(|device| {
+                *location == format!("{}:{}", device.bus_number(), device.address())
+            }),
+        }
+        .ok_or_else(|| {
+            NokhwaError::OpenDeviceError(index.to_string(), "No such camera".to_string())
+        })?;
+
+        let camera_info = camera_info(device, index);
+        let mut handle = device.open().map_err(|why| usb_error(index, why))?;
+        let config =
+            Self::read_active_config(device, &handle).map_err(|why| usb_error(index, why))?;
+        let descriptors = parse_descriptors(&config).ok_or_else(|| {
+            NokhwaError::OpenDeviceError(
+                index.to_string(),
+                "No supported video streaming interface".to_string(),
+            )
+        })?;
+
+        // not every platform can detach drivers, claiming tells whether it is needed
+        let _ = handle.set_auto_detach_kernel_driver(true);
+        for interface in [
+            descriptors.control_interface,
+            descriptors.streaming_interface,
+        ] {
+            handle
+                .claim_interface(interface)
+                .map_err(|why| usb_error(index, why))?;
+        }
+        // the streaming interface idles at alternate setting 0, which has no bandwidth
+        let _ = handle.set_alternate_setting(descriptors.streaming_interface, 0);
+
+        Ok(UVCCaptureDevice {
+            camera_info,
+            camera_format: None,
+            descriptors,
+            stream: None,
+            handle,
+            context,
+        })
+    }
+
+    // libusb parses the descriptors of the interfaces, but keeps the class specific ones apart from the endpoints
+    fn read_active_config(
+        device: &Device<Context>,
+        handle: &DeviceHandle<Context>,
+    ) -> Result<Vec<u8>, rusb::Error> {
+        let active = handle.active_configuration()?;
+        let configurations = device.device_descriptor()?.num_configurations();
+        for index in 0..configurations {
+            let value = (u16::from(DESCRIPTOR_CONFIGURATION) << 8) | u16::from(index);
+            let mut header = [0_u8; 9];
+            handle.read_control(
+                REQUEST_TYPE_GET_DESCRIPTOR,
+                REQUEST_GET_DESCRIPTOR,
+                value,
+                0,
+                &mut header,
+                CONTROL_TIMEOUT,
+            )?;
+            if header[5] != active {
+                continue;
             }
+            let mut config = vec![0_u8; usize::from(read_u16(&header, 2))];
+            let length = handle.read_control(
+                REQUEST_TYPE_GET_DESCRIPTOR,
+                REQUEST_GET_DESCRIPTOR,
+                value,
+                0,
+                &mut config,
+                CONTROL_TIMEOUT,
+            )?;
+            config.truncate(length);
+            return Ok(config);
         }
+        Err(rusb::Error::NotFound)
+    }
 
-        frameformats.sort();
-        frameformats.dedup();
-        Ok(frameformats)
+    /// Gets the UVC version the camera implements as binary coded decimal, e.g. `0x0150` for UVC 1.5.
+    #[must_use]
+    pub fn uvc_version(&self) -> u16 {
+        self.descriptors.uvc_version
     }
 
-    fn resolution(&self) -> Resolution {
-        self.borrow_camera_format().resolution()
+    /// Gets the extension units of the camera.
+    #[must_use]
+    pub fn extension_units(&self) -> &[UvcExtensionUnit] {
+        &self.descriptors.extension_units
     }
 
-    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
-        let mut current_format = *self.borrow_camera_format();
-        current_format.set_resolution(new_res);
-        self.set_camera_format(current_format)
+    /// Reads the extension unit control `selector` of the unit `unit_id`.
+    ///
+    /// The length of the control is asked from the camera first. What the bytes mean is up to the vendor.
+    /// # Errors
+    /// If the unit does not exist or the camera rejects the request, this will error.
+    pub fn extension_unit_control(
+        &self,
+        unit_id: u8,
+        selector: u8,
+    ) -> Result<Vec<u8>, NokhwaError> {
+        let property = format!("Extension unit {unit_id} control {selector}");
+        let get_error = |why: rusb::Error| NokhwaError::GetPropertyError {
+            property: property.clone(),
+            error: why.to_string(),
+        };
+        self.extension_unit(unit_id)
+            .ok_or_else(|| get_error(rusb::Error::NotFound))?;
+
+        let mut length = [0_u8; 2];
+        self.unit_request(GET_LEN, selector, unit_id, &mut length)
+            .map_err(get_error)?;
+        let mut data = vec![0_u8; usize::from(u16::from_le_bytes(length))];
+        let read = self
+            .unit_request(GET_CUR, selector, unit_id, &mut data)
+            .map_err(get_error)?;
+        data.truncate(read);
+        Ok(data)
     }
 
-    fn frame_rate(&self) -> u32 {
-        self.borrow_camera_format().frame_rate()
+    /// Writes `data` to the extension unit control `selector` of the unit `unit_id`.
+    /// # Errors
+    /// If the unit does not exist, `data` has the wrong length, or the camera rejects the request, this will error.
+    pub fn set_extension_unit_control(
+        &mut self,
+        unit_id: u8,
+        selector: u8,
+        data: &[u8],
+    ) -> Result<(), NokhwaError> {
+        let set_error = |why: rusb::Error| NokhwaError::SetPropertyError {
+            property: format!("Extension unit {unit_id} control {selector}"),
+            value: format!("{data:02x?}"),
+            error: why.to_string(),
+        };
+        self.extension_unit(unit_id)
+            .ok_or_else(|| set_error(rusb::Error::NotFound))?;
+        self.handle
+            .write_control(
+                REQUEST_TYPE_SET,
+                SET_CUR,
+                u16::from(selector) << 8,
+                (u16::from(unit_id) << 8) | u16::from(self.descriptors.control_interface),
+                data,
+                CONTROL_TIMEOUT,
+            )
+            .map_err(set_error)?;
+        Ok(())
     }
 
-    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
-        let mut current_format = *self.borrow_camera_format();
-        current_format.set_frame_rate(new_fps);
-        self.set_camera_format(current_format)
+    fn extension_unit(&self, unit_id: u8) -> Option<&UvcExtensionUnit> {
+        self.descriptors
+            .extension_units
+            .iter()
+            .find(|unit| unit.unit_id == unit_id)
     }
 
-    fn frame_format(&self) -> FrameFormat {
-        self.borrow_camera_format().format()
+    fn unit_request(
+        &self,
+        request: u8,
+        selector: u8,
+        unit_id: u8,
+        data: &mut [u8],
+    ) -> Result<usize, rusb::Error> {
+        self.handle.read_control(
+            REQUEST_TYPE_GET,
+            request,
+            u16::from(selector) << 8,
+            (u16::from(unit_id) << 8) | u16::from(self.descriptors.control_interface),
+            data,
+            CONTROL_TIMEOUT,
+        )
     }
 
-    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
-        let mut current_format = *self.borrow_camera_format();
-        current_format.set_format(fourcc);
-        self.set_camera_format(current_format)
+    fn unit_control(&self, control: KnownCameraControl) -> Option<(UnitControl, u8)> {
+        let unit_control = UNIT_CONTROLS
+            .into_iter()
+            .find(|unit_control| unit_control.control == control)?;
+        let unit = match unit_control.entity {
+            Entity::CameraTerminal => self.descriptors.camera_terminal.as_ref(),
+            Entity::ProcessingUnit => self.descriptors.processing_unit.as_ref(),
+        }?;
+        unit.has_bit(unit_control.bit)
+            .then_some((unit_control, unit.id))
     }
 
-    fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControl>, NokhwaError> {
-        Ok(vec![
-            KnownCameraControl::Exposure,
-            KnownCameraControl::Focus,
-        ])
+    fn read_unit_control(
+        &self,
+        request: u8,
+        unit_control: &UnitControl,
+        unit_id: u8,
+    ) -> Result<i64, NokhwaError> {
+        let get_error = |why: String| NokhwaError::GetPropertyError {
+            property: unit_control.control.to_string(),
+            error: why,
+        };
+        let mut data = vec![0_u8; usize::from(unit_control.length)];
+        let read = self
+            .unit_request(request, unit_control.selector, unit_id, &mut data)
+            .map_err(|why| get_error(why.to_string()))?;
+        unit_control
+            .decode(&data[..read])
+            .ok_or_else(|| get_error("short response".to_string()))
     }
 
-    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
-        match control {
-            KnownCameraControl::Focus => match self.with_device_handle(|x| x).exposure_rel() {
-                Ok(v) => {
-                    let v: i8 = v;
-                    match CameraControl::new(
-                        control,
-                        i32::from(i8::MIN),
-                        i32::from(i8::MAX),
-                        i32::from(v),
-                        1_i32,
-                        i32::from(v),
-                        KnownCameraControlFlag::Automatic,
-                        true,
-                    ) {
-                        Ok(cc) => Ok(cc),
-                        Err(why) => Err(NokhwaError::GetPropertyError {
-                            property: control.to_string(),
-                            error: why.to_string(),
-                        }),
-                    }
-                }
-                Err(why) => Err(NokhwaError::GetPropertyError {
-                    property: control.to_string(),
-                    error: why.to_string(),
-                }),
-            },
-            _ => Err(NokhwaError::GetPropertyError {
-                property: control.to_string(),
-                error: "Not Supported".to_string(),
-            }),
+    fn probe_length(&self) -> usize {
+        match self.descriptors.uvc_version {
+            0x0150.. => 48,
+            0x0110.. => 34,
+            _ => 26,
         }
     }
 
-    fn set_camera_control(&mut self, _control: CameraControl) -> Result<(), NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(
-            ApiBackend::UniversalVideoClass,
-        ))
+    fn streaming_request(
+        &self,
+        request: u8,
+        selector: u8,
+        data: &mut [u8],
+    ) -> Result<usize, rusb::Error> {
+        let index = u16::from(self.descriptors.streaming_interface);
+        if request == SET_CUR {
+            self.handle.write_control(
+                REQUEST_TYPE_SET,
+                request,
+                u16::from(selector) << 8,
+                index,
+                data,
+                CONTROL_TIMEOUT,
+            )
+        } else {
+            self.handle.read_control(
+                REQUEST_TYPE_GET,
+                request,
+                u16::from(selector) << 8,
+                index,
+                data,
+                CONTROL_TIMEOUT,
+            )
+        }
     }
 
-    fn raw_supported_camera_controls(&self) -> Result<Vec<Box<dyn Any>>, NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(
-            ApiBackend::UniversalVideoClass,
-        ))
+    // finds the format and frame descriptor of `camera_format`, and the frame interval closest to its frame rate
+    fn find_format(
+        &self,
+        camera_format: CameraFormat,
+    ) -> Option<(&UvcFormat, &UvcFrameDescriptor, u32)> {
+        let format = self
+            .descriptors
+            .formats
+            .iter()
+            .find(|format| format.frame_format == camera_format.format())?;
+        let frame = format
+            .frames
+            .iter()
+            .find(|frame| frame.resolution == camera_format.resolution())?;
+        let wanted = camera_format.frame_rate().as_u32().max(1);
+        let interval = frame
+            .intervals
+            .iter()
+            .copied()
+            .min_by_key(|interval| interval_to_frame_rate(*interval).abs_diff(wanted))?;
+        Some((format, frame, interval))
     }
 
-    fn raw_camera_control(&self, _control: &dyn Any) -> Result<Box<dyn Any>, NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(
-            ApiBackend::UniversalVideoClass,
-        ))
+    // negotiates the format with the camera, returning the maximum payload size it settled on
+    fn probe_commit(&self, camera_format: CameraFormat) -> Result<usize, NokhwaError> {
+        let (format, frame, interval) =
+            self.find_format(camera_format)
+                .ok_or_else(|| NokhwaError::SetPropertyError {
+                    property: "CameraFormat".to_string(),
+                    value: camera_format.to_string(),
+                    error: "unsupported".to_string(),
+                })?;
+        let stream_error = |why: rusb::Error| NokhwaError::OpenStreamError(why.to_string());
+
+        let mut probe = vec![0_u8; self.probe_length()];
+        // bmHint: keep dwFrameInterval
+        probe[0] = 0x01;
+        probe[2] = format.index;
+        probe[3] = frame.index;
+        probe[4..8].copy_from_slice(&interval.to_le_bytes());
+        self.streaming_request(SET_CUR, VS_PROBE_CONTROL, &mut probe)
+            .map_err(stream_error)?;
+        let read = self
+            .streaming_request(GET_CUR, VS_PROBE_CONTROL, &mut probe)
+            .map_err(stream_error)?;
+        probe.truncate(read);
+        if probe.get(2) != Some(&format.index) || probe.get(3) != Some(&frame.index) {
+            return Err(NokhwaError::OpenStreamError(format!(
+                "camera negotiated a different format than {camera_format}"
+            )));
+        }
+        self.streaming_request(SET_CUR, VS_COMMIT_CONTROL, &mut probe)
+            .map_err(stream_error)?;
+        Ok(read_u32(&probe, 22) as usize)
     }
 
-    fn set_raw_camera_control(
-        &mut self,
-        _control: &dyn Any,
-        _value: &dyn Any,
-    ) -> Result<(), NokhwaError> {
-        Err(NokhwaError::UnsupportedOperationError(
-            ApiBackend::UniversalVideoClass,
-        ))
+    // the smallest alternate setting that fits a payload, as the bus bandwidth is shared
+    fn alternate_setting(&self, max_payload: usize) -> Option<AlternateSetting> {
+        let settings = &self.descriptors.alternate_settings;
+        if let Some(bulk) = settings
+            .iter()
+            .find(|alternate| alternate.kind == TransferKind::Bulk)
+        {
+            return Some(*bulk);
+        }
+        settings
+            .iter()
+            .find(|alternate| alternate.packet_size >= max_payload)
+            .or_else(|| settings.last())
+            .copied()
     }
 
-    fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        let ret: Result<(), NokhwaError> = self.with_mut(|fields| {
-            let stream_format: StreamFormat = StreamFormat {
-                width: (*fields.camera_format).width(),
-                height: (*fields.camera_format).height(),
-                fps: (*fields.camera_format).frame_rate(),
-                format: (*fields.camera_format).format().into(),
-            };
+    fn start_stream(&mut self, camera_format: CameraFormat) -> Result<UvcStream, NokhwaError> {
+        let max_payload = self.probe_commit(camera_format)?;
+        let alternate = self
+            .alternate_setting(max_payload)
+            .ok_or_else(|| NokhwaError::OpenStreamError("No streaming endpoint".to_string()))?;
+        self.handle
+            .set_alternate_setting(self.descriptors.streaming_interface, alternate.setting)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
 
-            // first, drop the existing stream by setting it to None
-            {
-                if fields.active_stream_init.get() {
-                    let innard_value = fields.active_stream.replace(MaybeUninit::uninit());
-                    unsafe {
-                        std::mem::drop(innard_value.assume_init());
-                    };
-                    fields.active_stream_init.set(false);
-                }
+        let expected_size =
+            uncompressed_frame_size(camera_format.format(), camera_format.resolution());
+        let stream = UvcStream::new(
+            &self.context,
+            &self.handle,
+            alternate,
+            max_payload,
+            expected_size,
+        );
+        if stream.is_err() {
+            let _ = self
+                .handle
+                .set_alternate_setting(self.descriptors.streaming_interface, 0);
+        }
+        stream
+    }
+}
 
-                if fields.stream_handle_init.get() {
-                    let innard_value = fields.stream_handle.replace(MaybeUninit::uninit());
-                    unsafe {
-                        std::mem::drop(innard_value.assume_init());
-                    };
-                    fields.stream_handle_init.set(false);
-                }
-            }
-            // second, set the stream handle according to the streamformat
-            match fields
-                .device_handle
-                .get_stream_handle_with_format(stream_format)
-            {
-                Ok(streamh) => match fields.stream_handle.try_borrow_mut() {
-                    Ok(mut streamh_raw) => {
-                        *streamh_raw = MaybeUninit::new(streamh);
-                        fields.stream_handle_init.set(true);
-                    }
-                    Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
-                },
-                Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
-            }
-            Ok(())
-        });
+impl Backend for UVCCaptureDevice {
+    const BACKEND: ApiBackend = ApiBackend::UniversalVideoClass;
+}
 
-        if ret.is_err() {
-            return ret;
-        }
+impl CaptureTrait for UVCCaptureDevice {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        // the first format and frame descriptors are the camera's preferred ones
+        let format = self
+            .descriptors
+            .formats
+            .first()
+            .ok_or(NokhwaError::UnitializedError)?;
+        let frame = format.frames.first().ok_or(NokhwaError::UnitializedError)?;
+        let frame_rate = frame
+            .intervals
+            .iter()
+            .map(|interval| interval_to_frame_rate(*interval))
+            .filter(|frame_rate| *frame_rate <= 30)
+            .max()
+            .or_else(|| frame.intervals.last().copied().map(interval_to_frame_rate))
+            .unwrap_or(30);
+        let camera_format = CameraFormat::new(
+            frame.resolution,
+            format.frame_format,
+            FrameRate::new_integer(frame_rate)?,
+        );
+        self.set_camera_format(camera_format)
+    }
 
-        let ret_2: Result<(), NokhwaError> = self.with(|fields| {
-            // finally, get the active stream
-            let counter = Arc::new(AtomicUsize::new(0));
-            let frame_sender: Sender<Vec<u8>> = self.with_frame_sender(Clone::clone);
-            let streamh = unsafe {
-                let raw_ptr =
-                    (*fields.stream_handle.borrow_mut()).as_ptr() as *mut MaybeUninit<StreamHandle>;
-                let assume_inited: *mut MaybeUninit<StreamHandle<'static>> =
-                    raw_ptr.cast::<MaybeUninit<uvc::StreamHandle>>();
-                &mut *assume_inited
-            };
-            let streamh_init = unsafe {
-                match streamh.as_mut_ptr().as_mut() {
-                    Some(sth) => sth,
-                    None => {
-                        return Err(NokhwaError::OpenStreamError(
-                            "Failed to get mutable raw pointer to stream handle!".to_string(),
-                        ))
-                    }
-                }
-            };
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::UniversalVideoClass
+    }
 
-            let active_stream = match streamh_init.start_stream(
-                move |frame, _count| {
-                    let vec_frame = frame.to_rgb().unwrap().to_bytes().to_vec();
-                    if frame_sender.send(vec_frame).is_err() {
-                        // do nothing
-                    }
-                },
-                counter,
-            ) {
-                Ok(active) => active,
-                Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
-            };
-            *fields.active_stream.borrow_mut() = MaybeUninit::new(active_stream);
-            Ok(())
-        });
+    fn camera_info(&self) -> &CameraInfo {
+        &self.camera_info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        // the format only changes when we commit it
+        Ok(())
+    }
 
-        if ret_2.is_err() {
-            return ret_2;
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if self.find_format(new_fmt).is_none() {
+            return Err(NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "unsupported".to_string(),
+            });
         }
-        self.borrow_active_stream_init().set(true);
 
+        let current_format = self.camera_format;
+        self.camera_format = Some(new_fmt);
+        if self.is_stream_open() {
+            self.stop_stream()?;
+            if let Err(why) = self.open_stream() {
+                self.camera_format = current_format;
+                return Err(why);
+            }
+        }
         Ok(())
     }
 
-    fn is_stream_open(&self) -> bool {
-        self.with_active_stream_init(Cell::get)
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        let format = self
+            .descriptors
+            .formats
+            .iter()
+            .find(|format| format.frame_format == fourcc)
+            .ok_or(NokhwaError::GetPropertyError {
+                property: "FrameFormat".to_string(),
+                error: "unsupported".to_string(),
+            })?;
+        format
+            .frames
+            .iter()
+            .map(|frame| {
+                let mut frame_rates = frame
+                    .intervals
+                    .iter()
+                    .map(|interval| interval_to_frame_rate(*interval))
+                    .collect::<Vec<_>>();
+                frame_rates.sort_unstable();
+                frame_rates.dedup();
+                Ok((
+                    frame.resolution,
+                    frame_rates
+                        .into_iter()
+                        .map(FrameRate::new_integer)
+                        .collect::<Result<Vec<_>, _>>()?,
+                ))
+            })
+            .collect()
     }
 
-    fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        let resolution: Resolution = self.borrow_camera_format().resolution();
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Ok(self
+            .descriptors
+            .formats
+            .iter()
+            .map(|format| format.frame_format)
+            .collect())
+    }
 
-        let data = match self.frame_raw() {
-            Ok(d) => d,
-            Err(why) => return Err(why),
-        };
+    fn resolution(&self) -> Option<Resolution> {
+        self.camera_format.map(|fmt| fmt.resolution())
+    }
 
-        let imagebuf: ImageBuffer<Rgb<u8>, Vec<u8>> =
-            match ImageBuffer::from_vec(resolution.width(), resolution.height(), data.to_vec()) {
-                Some(img) => img,
-                None => {
-                    return Err(NokhwaError::ReadFrameError(
-                        "ImageBuffer too small! This is probably a bug, please report it!"
-                            .to_string(),
-                    ))
-                }
-            };
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
 
-        Ok(imagebuf)
+    fn frame_rate(&self) -> Option<u32> {
+        self.camera_format.map(|fmt| fmt.frame_rate().as_u32())
     }
 
-    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        // assertions
-        if !self.borrow_active_stream_init().get() {
-            return Err(NokhwaError::ReadFrameError(
-                "Please call `open_stream()` first!".to_string(),
-            ));
-        }
-
-        let f_recv = self.borrow_frame_receiver();
-        let messages_iter = f_recv.drain();
-        match messages_iter.last() {
-            Some(msg) => Ok(Cow::from(msg)),
-            None => match f_recv.recv() {
-                Ok(msg) => Ok(Cow::from(msg)),
-                Err(why) => {
-                    return Err(NokhwaError::ReadFrameError(format!(
-                        "All sender dropped: {}",
-                        why.to_string()
-                    )))
-                }
-            },
-        }
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(new_fmt)
     }
 
-    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-        self.with(|fields| {
-            if fields.active_stream_init.get() {
-                let innard_value = fields.active_stream.replace(MaybeUninit::uninit());
-                unsafe {
-                    std::mem::drop(innard_value.assume_init());
-                };
-                fields.active_stream_init.set(false);
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format
+            .map_or(FrameFormat::MJpeg, |fmt| fmt.format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        let (unit_control, unit_id) =
+            self.unit_control(control)
+                .ok_or(NokhwaError::UnsupportedOperationError(
+                    ApiBackend::UniversalVideoClass,
+                ))?;
+        let value = self.read_unit_control(GET_CUR, &unit_control, unit_id)?;
+        let default = self.read_unit_control(GET_DEF, &unit_control, unit_id)?;
+        let description = if control == KnownCameraControl::Privacy {
+            ControlValueDescription::Boolean {
+                value: value != 0,
+                default: default != 0,
+            }
+        } else {
+            ControlValueDescription::IntegerRange {
+                min: self.read_unit_control(GET_MIN, &unit_control, unit_id)?,
+                max: self.read_unit_control(GET_MAX, &unit_control, unit_id)?,
+                value,
+                step: self
+                    .read_unit_control(GET_RES, &unit_control, unit_id)?
+                    .max(1),
+                default,
             }
+        };
+        Ok(CameraControl::new(
+            control,
+            control.to_string(),
+            description,
+            vec![],
+            true,
+        ))
+    }
 
-            if fields.stream_handle_init.get() {
-                let innard_value = fields.stream_handle.replace(MaybeUninit::uninit());
-                unsafe {
-                    std::mem::drop(innard_value.assume_init());
-                };
-                fields.stream_handle_init.set(false);
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        Ok(UNIT_CONTROLS
+            .into_iter()
+            .filter_map(|unit_control| self.camera_control(unit_control.control).ok())
+            .collect())
+    }
+
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let control_val = match value {
+            ControlValueSetter::Integer(i) => i,
+            ControlValueSetter::Boolean(b) => i64::from(b),
+            val => {
+                return Err(NokhwaError::SetPropertyError {
+                    property: id.to_string(),
+                    value: val.to_string(),
+                    error: "unsupported value".to_string(),
+                })
             }
-        });
+        };
+        let set_error = |why: String| NokhwaError::SetPropertyError {
+            property: id.to_string(),
+            value: control_val.to_string(),
+            error: why,
+        };
+        let (unit_control, unit_id) =
+            self.unit_control(id)
+                .ok_or(NokhwaError::UnsupportedOperationError(
+                    ApiBackend::UniversalVideoClass,
+                ))?;
+
+        let mut data = vec![0_u8; usize::from(unit_control.length)];
+        if unit_control.size < unit_control.length {
+            // pan and tilt are set together, the other half keeps its value
+            self.unit_request(GET_CUR, unit_control.selector, unit_id, &mut data)
+                .map_err(|why| set_error(why.to_string()))?;
+        }
+        unit_control.encode(control_val, &mut data);
+        self.handle
+            .write_control(
+                REQUEST_TYPE_SET,
+                SET_CUR,
+                u16::from(unit_control.selector) << 8,
+                (u16::from(unit_id) << 8) | u16::from(self.descriptors.control_interface),
+                &data,
+                CONTROL_TIMEOUT,
+            )
+            .map_err(|why| set_error(why.to_string()))?;
+        Ok(())
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.is_stream_open() {
+            self.stop_stream()?;
+        }
+
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        self.stream = Some(self.start_stream(camera_format)?);
         Ok(())
     }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let camera_format = self.camera_format.ok_or(NokhwaError::UnitializedError)?;
+        let frame = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?
+            .next_frame()?;
+        Ok(Buffer::new(
+            camera_format.resolution(),
+            &frame.data,
+            camera_format.format(),
+        )
+        .with_sequence(frame.sequence)
+        .with_frame_metadata(frame.metadata.clone()))
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| NokhwaError::ReadFrameError("Stream Not Started".to_string()))?
+            .next_frame()
+            .map(|frame| Cow::Borrowed(frame.data.as_slice()))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.stream.take().is_some() {
+            // alternate setting 0 releases the bandwidth and tells the camera to stop
+            self.handle
+                .set_alternate_setting(self.descriptors.streaming_interface, 0)
+                .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UVCCaptureDevice {
+    fn drop(&mut self) {
+        let _ = self.stop_stream();
+        for interface in [
+            self.descriptors.streaming_interface,
+            self.descriptors.control_interface,
+        ] {
+            let _ = self.handle.release_interface(interface);
+        }
+    }
 }
@@ -14,111 +14,575 @@
  * limitations under the License.
  */
 
-use nokhwa_core::format_request::FormatFilter;
-use nokhwa_core::frame_format::SourceFrameFormat;
-use nokhwa_core::traits::Backend;
+use nokhwa_core::events::{CaptureEvent, CaptureEventKind, EventBus};
+use nokhwa_core::format_request::FormatRequest;
+use nokhwa_core::profiling::{LatencyProfiler, LatencyReport, LatencyStage};
 use nokhwa_core::{
     buffer::Buffer,
     error::NokhwaError,
-    pixel_format::FormatDecoder,
+    frame_format::FrameFormat,
     traits::CaptureTrait,
     types::{
-        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
-        FrameFormat, KnownCameraControl, RequestedFormatType, Resolution,
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlChange,
+        ControlValueDescription, ControlValueSetter, FrameRate, KnownCameraControl,
+        KnownCameraControlFlag, PhotoSettings, Rect, Resolution, StreamEvent,
     },
 };
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+};
 #[cfg(feature = "output-wgpu")]
 use wgpu::{Device as WgpuDevice, Queue as WgpuQueue, Texture as WgpuTexture};
 
+/// A command run with the camera on its owner thread.
+pub(crate) type Command = Box<dyn FnOnce(&mut OwnedCamera) + Send + 'static>;
+
 /// The main `Camera` struct. This is the struct that abstracts over all the backends, providing a simplified interface for use.
+///
+/// Every camera runs on its own owner thread: the backend opens the device there and only ever touches it there. A [`Camera`] is
+/// a `Send + Sync` handle that sends each call to the owner thread as a command and waits for the reply. Backends such as Media
+/// Foundation (COM) and `AVFoundation` (Objective-C), which expect a device to be used from the thread that opened it, can so be
+/// used from any thread, e.g. from the worker threads of an async executor.
+/// # Quirks
+/// - Commands run one at a time in the order they were sent, a slow command (e.g. waiting for a frame) delays the others.
+/// - Frames from [`frame_raw()`](CaptureTrait::frame_raw) are copied off the owner thread, use [`frame()`](CaptureTrait::frame) to avoid the copy.
+/// - Dropping the [`Camera`] waits for the owner thread to close the device.
+/// - Use [`SharedCamera`](crate::SharedCamera) to share one camera between threads without a lock.
 pub struct Camera {
-    idx: CameraIndex,
     api: ApiBackend,
-    device: Box<dyn CaptureTrait + Backend>,
+    info: CameraInfo,
+    // `None` once dropped, which stops the owner thread
+    commands: Option<Sender<Command>>,
+    owner: Option<JoinHandle<()>>,
 }
 
 impl Camera {
-    pub fn new() -> Result<Self, NokhwaError> {}
+    /// Opens the camera at `index` with the best backend for this platform, see [`auto_api_backend()`](crate::auto_api_backend).
+    /// # Errors
+    /// If no backend is compiled in for this platform or the camera fails to open, this will error.
+    pub fn new(index: CameraIndex) -> Result<Self, NokhwaError> {
+        Self::with_api_backend(index, ApiBackend::Auto)
+    }
+
+    /// Opens the camera at `index` with `backend`. [`ApiBackend::Auto`] picks the best backend for this platform.
+    /// # Errors
+    /// If `backend` is not compiled in, can not open cameras by index (e.g. [`ApiBackend::Network`]), or the camera fails to open,
+    /// this will error.
+    pub fn with_api_backend(index: CameraIndex, backend: ApiBackend) -> Result<Self, NokhwaError> {
+        let backend = match backend {
+            ApiBackend::Auto => crate::auto_api_backend()
+                .ok_or(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))?,
+            backend => backend,
+        };
+        let device_index = index.clone();
+        Self::spawn(index, move || open_device(&device_index, backend))
+    }
+
+    /// Opens the camera at `index` with `open`, e.g. a device of a [`Custom`](ApiBackend::Custom) backend. `open` is called on the
+    /// owner thread, so the device does not need to be [`Send`].
+    /// # Errors
+    /// If the owner thread can not be spawned or `open` fails, this will error.
+    pub fn with_custom_backend<F, D>(index: CameraIndex, open: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<D, NokhwaError> + Send + 'static,
+        D: Into<Box<dyn CaptureTrait>>,
+    {
+        Self::spawn(index, move || open().map(Into::into))
+    }
+
+    fn spawn<F>(index: CameraIndex, open: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Box<dyn CaptureTrait>, NokhwaError> + Send + 'static,
+    {
+        let (commands, receiver) = mpsc::channel::<Command>();
+        let (opened_send, opened_recv) = mpsc::sync_channel(1);
+
+        let owner = thread::Builder::new()
+            .name("nokhwa-camera".to_string())
+            .spawn(move || {
+                let mut camera = match open() {
+                    Ok(device) => OwnedCamera::new(index, device),
+                    Err(why) => {
+                        let _ = opened_send.send(Err(why));
+                        return;
+                    }
+                };
+                let _ = opened_send.send(Ok((camera.api, camera.info.clone())));
+                while let Ok(command) = receiver.recv() {
+                    command(&mut camera);
+                }
+            })
+            .map_err(|why| {
+                NokhwaError::GeneralError(format!("Failed to spawn owner thread: {why}"))
+            })?;
+
+        let (api, info) = opened_recv.recv().map_err(|_| owner_stopped())??;
+        Ok(Camera {
+            api,
+            info,
+            commands: Some(commands),
+            owner: Some(owner),
+        })
+    }
+
+    /// Runs `command` with the device on the owner thread and returns its result, e.g. to make several calls without other commands
+    /// in between.
+    /// # Errors
+    /// If the owner thread has stopped (e.g. a previous command panicked), this will error.
+    /// # Quirks
+    /// Calling this [`Camera`] from inside `command` deadlocks, use the device you are given instead.
+    pub fn run<T, F>(&self, command: F) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.call(move |camera| command(camera))
+    }
+
+    /// Runs `command` on the owner thread and waits for its result.
+    pub(crate) fn call<T, F>(&self, command: F) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut OwnedCamera) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, response) = mpsc::sync_channel(1);
+        self.post(Box::new(move |camera| {
+            let _ = reply.send(command(camera));
+        }))?;
+        response.recv().map_err(|_| owner_stopped())
+    }
+
+    /// Sends `command` to the owner thread without waiting for it to run.
+    pub(crate) fn post(&self, command: Command) -> Result<(), NokhwaError> {
+        self.commands
+            .as_ref()
+            .ok_or_else(owner_stopped)?
+            .send(command)
+            .map_err(|_| owner_stopped())
+    }
+
+    /// Sets the format to the one of the camera's [`compatible_camera_formats()`](CaptureTrait::compatible_camera_formats) that best
+    /// satisfies `request`, and returns it.
+    /// # Errors
+    /// If the formats can not be queried, none satisfies `request` or the format can not be set, this will error.
+    pub fn set_camera_request(
+        &mut self,
+        request: &FormatRequest,
+    ) -> Result<CameraFormat, NokhwaError> {
+        let format = request
+            .resolve(&self.compatible_camera_formats()?)
+            .ok_or_else(|| NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: format!("{request:?}"),
+                error: "No format satisfies the request".to_string(),
+            })?;
+        self.set_camera_format(format)?;
+        Ok(format)
+    }
+
+    /// Starts timing every frame, keeping the latest `window` frames of every [`LatencyStage`]. Profiling is off by default.
+    ///
+    /// [`frame()`](CaptureTrait::frame) records [`LatencyStage::DriverDelivery`] and a [`CallbackCamera`](crate::CallbackCamera) records
+    /// [`LatencyStage::Copy`] and [`LatencyStage::CallbackDispatch`]. Use [`profiler()`](Self::profiler) to time your own stages, e.g. decoding.
+    pub fn enable_profiling(&mut self, window: usize) {
+        let _ = self.call(move |camera| camera.enable_profiling(window));
+    }
+
+    /// Stops timing frames and forgets the recorded samples.
+    pub fn disable_profiling(&mut self) {
+        let _ = self.call(OwnedCamera::disable_profiling);
+    }
 
-    pub fn with_api_backend() -> Result<Self, NokhwaError> {}
+    /// Get a handle to the latency profiler, if profiling is enabled.
+    #[must_use]
+    pub fn profiler(&self) -> Option<LatencyProfiler> {
+        self.call(|camera| camera.profiler()).ok().flatten()
+    }
+
+    /// Get the aggregated per stage latencies of the recent frames, if profiling is enabled.
+    #[must_use]
+    pub fn latency_report(&self) -> Option<LatencyReport> {
+        self.call(|camera| camera.latency_report()).ok().flatten()
+    }
+
+    /// Puts the camera in standby: the stream is stopped and the device is closed, which stops the sensor, releases its (e.g. USB)
+    /// bandwidth and usually turns off the camera's LED. The negotiated [`CameraFormat`] and the value of every writable control are
+    /// kept, and [`wake()`](Self::wake) reopens the device and restores them, so applications that capture intermittently do not need
+    /// to set the camera up again.
+    ///
+    /// Unlike [`stop_stream()`](CaptureTrait::stop_stream), nothing can be captured or configured until the camera is woken.
+    /// # Errors
+    /// If the controls can not be read or the stream can not be stopped, this will error and the camera stays awake. A backend that
+    /// does not support controls at all is not an error, only the format is restored then.
+    /// # Quirks
+    /// - Callbacks set with [`on_control_change()`](CaptureTrait::on_control_change) or [`on_stream_event()`](CaptureTrait::on_stream_event)
+    ///   are dropped with the device, set them again after waking.
+    /// - A device of a [`Custom`](ApiBackend::Custom) backend can not be reopened, so it is kept open with its stream stopped.
+    pub fn standby(&mut self) -> Result<(), NokhwaError> {
+        self.call(OwnedCamera::standby)?
+    }
+
+    /// Wakes the camera from [`standby()`](Self::standby), reopening the device, restoring the [`CameraFormat`] and controls that
+    /// were captured when it went into standby, and reopening the stream if it was open. Does nothing if the camera is not in standby.
+    ///
+    /// Restoring is best effort: the format and the controls that could not be restored are returned instead of failing the wake,
+    /// e.g. if the camera was replugged and no longer supports a value.
+    /// # Errors
+    /// If the device or the stream can not be reopened, this will error and the camera stays in standby.
+    pub fn wake(&mut self) -> Result<Vec<NokhwaError>, NokhwaError> {
+        self.call(OwnedCamera::wake)?
+    }
 
-    pub fn with_custom_backend() -> Result<Self, NokhwaError> {}
+    /// Returns `true` if the camera is in [`standby()`](Self::standby).
+    #[must_use]
+    pub fn is_standby(&self) -> bool {
+        self.call(|camera| camera.is_standby()).unwrap_or(false)
+    }
 }
 
-impl CaptureTrait for Camera {
-    fn init(&mut self) -> Result<(), NokhwaError> {
-        todo!()
+impl Drop for Camera {
+    fn drop(&mut self) {
+        // closing the channel stops the owner thread, which drops the device
+        self.commands = None;
+        if let Some(owner) = self.owner.take() {
+            if owner.thread().id() != thread::current().id() {
+                let _ = owner.join();
+            }
+        }
+    }
+}
+
+/// The part of a [`Camera`] that lives on its owner thread: the device and everything kept alongside it.
+pub(crate) struct OwnedCamera {
+    idx: CameraIndex,
+    api: ApiBackend,
+    // kept here, as the device is closed in standby
+    info: CameraInfo,
+    // `None` while in standby, as the device is closed
+    device: Option<Box<dyn CaptureTrait>>,
+    profiler: Option<LatencyProfiler>,
+    standby: Option<StandbyState>,
+}
+
+/// What [`Camera::wake()`] restores.
+struct StandbyState {
+    format: Option<CameraFormat>,
+    controls: Vec<(KnownCameraControl, ControlValueSetter)>,
+    was_streaming: bool,
+}
+
+impl OwnedCamera {
+    fn new(idx: CameraIndex, device: Box<dyn CaptureTrait>) -> Self {
+        OwnedCamera {
+            idx,
+            api: device.backend(),
+            info: device.camera_info().clone(),
+            device: Some(device),
+            profiler: None,
+            standby: None,
+        }
+    }
+
+    pub(crate) fn enable_profiling(&mut self, window: usize) {
+        self.profiler = Some(LatencyProfiler::new(window));
+    }
+
+    pub(crate) fn disable_profiling(&mut self) {
+        self.profiler = None;
     }
 
-    fn init_with_format(&mut self, format: FormatFilter) -> Result<CameraFormat, NokhwaError> {
-        todo!()
+    pub(crate) fn profiler(&self) -> Option<LatencyProfiler> {
+        self.profiler.clone()
+    }
+
+    pub(crate) fn latency_report(&self) -> Option<LatencyReport> {
+        self.profiler.as_ref().map(LatencyProfiler::report)
+    }
+
+    pub(crate) fn standby(&mut self) -> Result<(), NokhwaError> {
+        if self.standby.is_some() {
+            return Ok(());
+        }
+
+        let device = self.device_mut()?;
+        let controls = match device.camera_controls() {
+            Ok(controls) => controls,
+            // a backend without controls has none to restore
+            Err(NokhwaError::UnsupportedOperationError(_)) => Vec::new(),
+            Err(why) => return Err(why),
+        };
+        let mut controls = controls
+            .into_iter()
+            .filter(|control| {
+                !control.flag().iter().any(|flag| {
+                    matches!(
+                        flag,
+                        KnownCameraControlFlag::ReadOnly
+                            | KnownCameraControlFlag::WriteOnly
+                            | KnownCameraControlFlag::Volatile
+                            | KnownCameraControlFlag::Disabled
+                    )
+                })
+            })
+            .collect::<Vec<CameraControl>>();
+        // a manual value is ignored or refused while its auto mode is on, so the auto modes are restored first
+        controls.sort_by_key(|control| !is_auto_mode(control));
+        let controls = controls
+            .into_iter()
+            .map(|control| (control.control(), control.value()))
+            .collect();
+        let format = device.camera_format();
+        let was_streaming = device.is_stream_open();
+        if was_streaming {
+            device.stop_stream()?;
+        }
+
+        self.standby = Some(StandbyState {
+            format,
+            controls,
+            was_streaming,
+        });
+        if !matches!(self.api, ApiBackend::Custom(_)) {
+            self.device = None;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn wake(&mut self) -> Result<Vec<NokhwaError>, NokhwaError> {
+        let Some(state) = self.standby.take() else {
+            return Ok(Vec::new());
+        };
+        let device = match &mut self.device {
+            Some(device) => device,
+            device @ None => match open_device(&self.idx, self.api) {
+                Ok(opened) => device.insert(opened),
+                Err(why) => {
+                    self.standby = Some(state);
+                    return Err(why);
+                }
+            },
+        };
+
+        let mut failures = Vec::new();
+        if let Some(format) = state.format {
+            if let Err(why) = device.set_camera_format(format) {
+                failures.push(why);
+            }
+        }
+        for (control, value) in &state.controls {
+            if let Err(why) = device.set_camera_control(*control, value.clone()) {
+                failures.push(why);
+            }
+        }
+        if state.was_streaming {
+            if let Err(why) = device.open_stream() {
+                self.standby = Some(state);
+                return Err(why);
+            }
+        }
+        Ok(failures)
+    }
+
+    pub(crate) fn is_standby(&self) -> bool {
+        self.standby.is_some()
+    }
+
+    fn device(&self) -> Result<&(dyn CaptureTrait + 'static), NokhwaError> {
+        match self.device.as_deref() {
+            Some(device) if self.standby.is_none() => Ok(device),
+            _ => Err(standby_error()),
+        }
+    }
+
+    fn device_mut(&mut self) -> Result<&mut (dyn CaptureTrait + 'static), NokhwaError> {
+        match self.device.as_deref_mut() {
+            Some(device) if self.standby.is_none() => Ok(device),
+            _ => Err(standby_error()),
+        }
+    }
+
+    /// Publishes `kind` to the global [`EventBus`].
+    fn publish(&self, kind: CaptureEventKind) {
+        publish(self.api, &self.idx, kind);
+    }
+
+    /// Publishes the outcome of an operation, as `kind` if it succeeded or as an error if it failed.
+    fn publish_result<T>(
+        &self,
+        result: &Result<T, NokhwaError>,
+        kind: impl FnOnce(&T) -> CaptureEventKind,
+    ) {
+        match result {
+            Ok(value) => self.publish(kind(value)),
+            Err(why) => self.publish(CaptureEventKind::Error(why.clone())),
+        }
+    }
+
+    /// Publishes the new format after a part of it was set, or the error if it could not be.
+    fn publish_format_change(&self, result: &Result<(), NokhwaError>) {
+        match (result, self.camera_format()) {
+            (Ok(()), Some(format)) => self.publish(CaptureEventKind::FormatChanged(format)),
+            (Ok(()), None) => {}
+            (Err(why), _) => self.publish(CaptureEventKind::Error(why.clone())),
+        }
+    }
+}
+
+/// Publishes `kind`, which happened to the camera at `idx` of `api`, to the global [`EventBus`].
+fn publish(api: ApiBackend, idx: &CameraIndex, kind: CaptureEventKind) {
+    let bus = EventBus::global();
+    if bus.has_subscribers() {
+        bus.publish(&CaptureEvent::new(api, idx.clone(), kind));
+    }
+}
+
+fn standby_error() -> NokhwaError {
+    NokhwaError::GeneralError("The camera is in standby, call `wake()` first".to_string())
+}
+
+pub(crate) fn owner_stopped() -> NokhwaError {
+    NokhwaError::GeneralError("The camera's owner thread has stopped".to_string())
+}
+
+fn standby_read_error() -> NokhwaError {
+    NokhwaError::ReadFrameError("The camera is in standby, call `wake()` first".to_string())
+}
+
+/// Returns `true` if `control` switches an automatic mode (e.g. auto exposure) rather than holding a manual value.
+fn is_auto_mode(control: &CameraControl) -> bool {
+    control.flag().contains(&KnownCameraControlFlag::Automatic)
+        || control.name().to_ascii_lowercase().contains("auto")
+}
+
+/// Opens the camera at `index` with `backend`, which must not be [`ApiBackend::Auto`].
+pub(crate) fn open_device(
+    index: &CameraIndex,
+    backend: ApiBackend,
+) -> Result<Box<dyn CaptureTrait>, NokhwaError> {
+    use crate::backends::capture::{
+        backend_gen_android, backend_gen_aravis, backend_gen_avf, backend_gen_decklink,
+        backend_gen_ffmpeg, backend_gen_gst, backend_gen_libcamera, backend_gen_msf,
+        backend_gen_opencv, backend_gen_pipewire, backend_gen_uvc, backend_gen_v4l,
+    };
+
+    match backend {
+        ApiBackend::AVFoundation => backend_gen_avf(index),
+        ApiBackend::Video4Linux => backend_gen_v4l(index),
+        ApiBackend::LibCamera => backend_gen_libcamera(index),
+        ApiBackend::PipeWire => backend_gen_pipewire(index),
+        ApiBackend::UniversalVideoClass => backend_gen_uvc(index),
+        ApiBackend::MediaFoundation => backend_gen_msf(index),
+        ApiBackend::OpenCv => backend_gen_opencv(index),
+        ApiBackend::GStreamer => backend_gen_gst(index),
+        ApiBackend::Android => backend_gen_android(index),
+        ApiBackend::Aravis => backend_gen_aravis(index),
+        ApiBackend::FFmpeg => backend_gen_ffmpeg(index),
+        ApiBackend::DeckLink => backend_gen_decklink(index),
+        ApiBackend::Auto | ApiBackend::Custom(_) | ApiBackend::Browser | ApiBackend::Network => {
+            Err(NokhwaError::NotImplementedError(format!(
+                "{backend} can not open a camera by index"
+            )))
+        }
+    }
+}
+
+impl CaptureTrait for OwnedCamera {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        let result = self.device_mut().and_then(CaptureTrait::init);
+        self.publish_result(&result, |()| CaptureEventKind::DeviceOpened);
+        result
     }
 
     fn backend(&self) -> ApiBackend {
-        todo!()
+        self.api
     }
 
     fn camera_info(&self) -> &CameraInfo {
-        todo!()
+        &self.info
     }
 
     fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
-        todo!()
+        self.device_mut()?.refresh_camera_format()
     }
 
     fn camera_format(&self) -> Option<CameraFormat> {
-        todo!()
+        match &self.standby {
+            // the device is closed, so report the format it will be restored to
+            Some(state) => state.format,
+            None => self.device().ok()?.camera_format(),
+        }
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        todo!()
+        let result = self
+            .device_mut()
+            .and_then(|device| device.set_camera_format(new_fmt));
+        self.publish_result(&result, |()| CaptureEventKind::FormatChanged(new_fmt));
+        result
     }
 
     fn compatible_list_by_resolution(
         &mut self,
-        fourcc: SourceFrameFormat,
-    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
-        todo!()
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        self.device_mut()?.compatible_list_by_resolution(fourcc)
     }
 
-    fn compatible_fourcc(&mut self) -> Result<Vec<SourceFrameFormat>, NokhwaError> {
-        todo!()
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        self.device_mut()?.compatible_fourcc()
     }
 
     fn resolution(&self) -> Option<Resolution> {
-        todo!()
+        match self.device() {
+            Ok(device) => device.resolution(),
+            Err(_) => self.camera_format().map(|format| format.resolution()),
+        }
     }
 
     fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
-        todo!()
+        let result = self
+            .device_mut()
+            .and_then(|device| device.set_resolution(new_res));
+        self.publish_format_change(&result);
+        result
     }
 
     fn frame_rate(&self) -> Option<u32> {
-        todo!()
+        self.device().ok()?.frame_rate()
     }
 
     fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
-        todo!()
+        let result = self
+            .device_mut()
+            .and_then(|device| device.set_frame_rate(new_fps));
+        self.publish_format_change(&result);
+        result
     }
 
-    fn frame_format(&self) -> SourceFrameFormat {
-        todo!()
+    fn frame_format(&self) -> FrameFormat {
+        match self.device() {
+            Ok(device) => device.frame_format(),
+            Err(_) => self.camera_format().unwrap_or_default().format(),
+        }
     }
 
-    fn set_frame_format(
-        &mut self,
-        fourcc: impl Into<SourceFrameFormat>,
-    ) -> Result<(), NokhwaError> {
-        todo!()
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let result = self
+            .device_mut()
+            .and_then(|device| device.set_frame_format(fourcc));
+        self.publish_format_change(&result);
+        result
     }
 
     fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
-        todo!()
+        self.device()?.camera_control(control)
     }
 
     fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
-        todo!()
+        self.device()?.camera_controls()
     }
 
     fn set_camera_control(
@@ -126,34 +590,249 @@ impl CaptureTrait for Camera {
         id: KnownCameraControl,
         value: ControlValueSetter,
     ) -> Result<(), NokhwaError> {
-        todo!()
+        let result = self
+            .device_mut()
+            .and_then(|device| device.set_camera_control(id, value.clone()));
+        self.publish_result(&result, |()| CaptureEventKind::ControlWritten {
+            control: id,
+            value,
+        });
+        result
+    }
+
+    fn crop(&self) -> Result<ControlValueDescription, NokhwaError> {
+        self.device()?.crop()
+    }
+
+    fn set_crop(&mut self, crop: Rect) -> Result<(), NokhwaError> {
+        self.device_mut()?.set_crop(crop)
+    }
+
+    fn on_control_change(
+        &mut self,
+        callback: Box<dyn FnMut(ControlChange) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        self.device_mut()?.on_control_change(callback)
+    }
+
+    fn on_stream_event(
+        &mut self,
+        mut callback: Box<dyn FnMut(StreamEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        // the backend calls this from its own thread, so publish with copies of what identifies the camera
+        let (api, idx) = (self.api, self.idx.clone());
+        self.device_mut()?.on_stream_event(Box::new(move |event| {
+            publish(api, &idx, CaptureEventKind::Stream(event));
+            callback(event);
+        }))
+    }
+
+    fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+        self.device()?.dropped_frames()
+    }
+
+    fn hardware_privacy(&self) -> Result<bool, NokhwaError> {
+        self.device()?.hardware_privacy()
+    }
+
+    fn take_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        self.device_mut()?.take_photo(settings)
     }
 
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        todo!()
+        let result = self.device_mut().and_then(CaptureTrait::open_stream);
+        self.publish_result(&result, |()| CaptureEventKind::StreamOpened);
+        result
     }
 
     fn is_stream_open(&self) -> bool {
-        todo!()
+        // a camera in standby has no open device, and so no open stream
+        self.device().is_ok_and(CaptureTrait::is_stream_open)
     }
 
     fn frame(&mut self) -> Result<Buffer, NokhwaError> {
-        todo!()
+        let device = match self.device.as_deref_mut() {
+            Some(device) if self.standby.is_none() => device,
+            _ => return Err(standby_read_error()),
+        };
+        let frame = match &self.profiler {
+            Some(profiler) => profiler.time(LatencyStage::DriverDelivery, || device.frame()),
+            None => device.frame(),
+        };
+        if let Err(why) = &frame {
+            self.publish(CaptureEventKind::Error(why.clone()));
+        }
+        frame
     }
 
-    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        todo!()
+    fn frame_raw(&mut self) -> Result<Cow<'_, [u8]>, NokhwaError> {
+        let device = match self.device.as_deref_mut() {
+            Some(device) if self.standby.is_none() => device,
+            _ => return Err(standby_read_error()),
+        };
+        let frame = match &self.profiler {
+            Some(profiler) => profiler.time(LatencyStage::DriverDelivery, || device.frame_raw()),
+            None => device.frame_raw(),
+        };
+        // the frame borrows the device, so publish with the fields that identify the camera instead of `self`
+        if let Err(why) = &frame {
+            publish(self.api, &self.idx, CaptureEventKind::Error(why.clone()));
+        }
+        frame
     }
 
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-        todo!()
+        // a device in standby is closed, so its stream is already stopped
+        let result = self
+            .device
+            .as_deref_mut()
+            .map_or(Ok(()), CaptureTrait::stop_stream);
+        self.publish_result(&result, |()| CaptureEventKind::StreamClosed);
+        result
     }
 }
 
-impl Drop for Camera {
+impl Drop for OwnedCamera {
     fn drop(&mut self) {
         self.stop_stream().unwrap();
+        self.publish(CaptureEventKind::DeviceClosed);
     }
 }
 
-unsafe impl Send for Camera {}
+impl CaptureTrait for Camera {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        self.call(CaptureTrait::init)?
+    }
+
+    fn backend(&self) -> ApiBackend {
+        self.api
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        self.call(CaptureTrait::refresh_camera_format)?
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        self.call(|camera| camera.camera_format()).ok().flatten()
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_camera_format(new_fmt))?
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        self.call(move |camera| camera.compatible_list_by_resolution(fourcc))?
+    }
+
+    fn compatible_camera_formats(&mut self) -> Result<Vec<CameraFormat>, NokhwaError> {
+        self.call(CaptureTrait::compatible_camera_formats)?
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        self.call(CaptureTrait::compatible_fourcc)?
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        self.call(|camera| camera.resolution()).ok().flatten()
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_resolution(new_res))?
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.call(|camera| camera.frame_rate()).ok().flatten()
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_frame_rate(new_fps))?
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.call(|camera| camera.frame_format())
+            .unwrap_or_else(|_| CameraFormat::default().format())
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_frame_format(fourcc))?
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        self.call(move |camera| camera.camera_control(control))?
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        self.call(|camera| camera.camera_controls())?
+    }
+
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_camera_control(id, value))?
+    }
+
+    fn crop(&self) -> Result<ControlValueDescription, NokhwaError> {
+        self.call(|camera| camera.crop())?
+    }
+
+    fn set_crop(&mut self, crop: Rect) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.set_crop(crop))?
+    }
+
+    fn on_control_change(
+        &mut self,
+        callback: Box<dyn FnMut(ControlChange) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.on_control_change(callback))?
+    }
+
+    fn on_stream_event(
+        &mut self,
+        callback: Box<dyn FnMut(StreamEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        self.call(move |camera| camera.on_stream_event(callback))?
+    }
+
+    fn dropped_frames(&self) -> Result<u64, NokhwaError> {
+        self.call(|camera| camera.dropped_frames())?
+    }
+
+    fn hardware_privacy(&self) -> Result<bool, NokhwaError> {
+        self.call(|camera| camera.hardware_privacy())?
+    }
+
+    fn take_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        self.call(move |camera| camera.take_photo(settings))?
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.call(CaptureTrait::open_stream)?
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.call(|camera| camera.is_stream_open()).unwrap_or(false)
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        self.call(CaptureTrait::frame)?
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<'_, [u8]>, NokhwaError> {
+        // the frame is borrowed from the device on the owner thread, so it has to be copied
+        self.call(|camera| camera.frame_raw().map(Cow::into_owned))?
+            .map(Cow::Owned)
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.call(CaptureTrait::stop_stream)?
+    }
+}
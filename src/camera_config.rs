@@ -0,0 +1,526 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Declarative camera setups, read from TOML or JSON.
+//!
+//! A [`CameraConfig`] describes which camera to open, the format to request, the controls to set, the [`PipelineStep`]s frames
+//! go through and the [`SinkConfig`]s they end up in. [`Camera::from_config()`] and [`CameraConfig::capture_loop()`] build all of
+//! it, so a deployment can change its camera setup by editing a file instead of recompiling.
+//!
+//! ```toml
+//! [device]
+//! backend = "Video4Linux"
+//! name = "*Logitech*"
+//!
+//! [format]
+//! resolution = { width_x = 1920, height_y = 1080 }
+//! frame_rate = 30
+//! frame_formats = ["MJpeg", "Yuv422"]
+//!
+//! [[controls]]
+//! control = "Brightness"
+//! value = { Integer = 128 }
+//!
+//! [[pipeline]]
+//! step = "decode"
+//! decode = "Rgb8"
+//!
+//! [[pipeline]]
+//! step = "gamma"
+//! gamma = 2.2
+//!
+//! [[sinks]]
+//! kind = "image_sequence"
+//! directory = "frames"
+//! prefix = "cam0-"
+//! ```
+
+use crate::{
+    decoders::{nv12::NV12Decoder, rgb::RGBDecoder, yuyv::YUYVDecoder},
+    query, Camera, CaptureLoop, DeviceRegistry, ImageSequenceSink, RawStreamSink,
+};
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    format_request::{CustomFormatRequestType, FormatRequest},
+    frame_format::FrameFormat,
+    lut::{ColorLut, Lut1d, Lut3d},
+    thermal::ToneMap,
+    traits::{CaptureTrait, FrameSink},
+    types::{
+        ApiBackend, CameraInfo, ControlValueSetter, FrameRate, KnownCameraControl, Range,
+        Resolution,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf};
+
+/// The backends a [`DeviceSelector`] can name. Custom backends can not be selected by name.
+const NAMED_BACKENDS: [ApiBackend; 15] = [
+    ApiBackend::AVFoundation,
+    ApiBackend::Video4Linux,
+    ApiBackend::LibCamera,
+    ApiBackend::PipeWire,
+    ApiBackend::UniversalVideoClass,
+    ApiBackend::MediaFoundation,
+    ApiBackend::OpenCv,
+    ApiBackend::GStreamer,
+    ApiBackend::Browser,
+    ApiBackend::Android,
+    ApiBackend::Aravis,
+    ApiBackend::Network,
+    ApiBackend::FFmpeg,
+    ApiBackend::DeckLink,
+    ApiBackend::Auto,
+];
+
+/// A complete camera setup. Every section but `device` may be left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    pub device: DeviceSelector,
+    pub format: FormatConstraints,
+    pub controls: Vec<ControlSetting>,
+    pub pipeline: Vec<PipelineStep>,
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl CameraConfig {
+    /// Parses a [`CameraConfig`] from TOML.
+    /// # Errors
+    /// If the TOML is malformed or does not describe a [`CameraConfig`], this will error.
+    pub fn from_toml_str(config: &str) -> Result<Self, NokhwaError> {
+        toml::from_str(config).map_err(|why| NokhwaError::StructureError {
+            structure: "CameraConfig".to_string(),
+            error: why.to_string(),
+        })
+    }
+
+    /// Parses a [`CameraConfig`] from JSON.
+    /// # Errors
+    /// If the JSON is malformed or does not describe a [`CameraConfig`], this will error.
+    pub fn from_json_str(config: &str) -> Result<Self, NokhwaError> {
+        serde_json::from_str(config).map_err(|why| NokhwaError::StructureError {
+            structure: "CameraConfig".to_string(),
+            error: why.to_string(),
+        })
+    }
+
+    /// Reads a [`CameraConfig`] from a file, as JSON if its extension is `.json` and as TOML otherwise.
+    /// # Errors
+    /// If the file can not be read or does not hold a valid [`CameraConfig`], this will error.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NokhwaError> {
+        let path = path.as_ref();
+        let config = fs::read_to_string(path).map_err(|why| {
+            NokhwaError::GeneralError(format!("Failed to read {}: {why}", path.display()))
+        })?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        {
+            Self::from_json_str(&config)
+        } else {
+            Self::from_toml_str(&config)
+        }
+    }
+
+    /// Builds the pipeline and the sinks, and opens a [`CaptureLoop`] over `camera` feeding them.
+    /// # Errors
+    /// If a `.cube` file can not be read or a sink fails to open, this will error.
+    pub fn capture_loop(&self, camera: Camera) -> Result<CaptureLoop, NokhwaError> {
+        let mut sinks: Vec<Box<dyn FrameSink>> = Vec::with_capacity(self.sinks.len());
+        for sink in &self.sinks {
+            sinks.push(sink.build()?);
+        }
+        let steps = self
+            .pipeline
+            .iter()
+            .map(PipelineStep::build)
+            .collect::<Result<Vec<BuiltStep>, NokhwaError>>()?;
+        Ok(CaptureLoop::new(camera).with_sink(PipelineSink { steps, sinks }))
+    }
+}
+
+/// Which camera to open. The first camera matching every given criterion is opened.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSelector {
+    /// The backend to use, by name (e.g. `"Video4Linux"`, case insensitive). If left out, every local backend is tried, see
+    /// [`DeviceRegistry::default()`]. `"Auto"` is the same as leaving it out.
+    pub backend: Option<String>,
+    /// The backend specific unique id of the camera, matched against its [`misc()`](CameraInfo::misc) (e.g. the device path or
+    /// serial) and its [`index()`](CameraInfo::index).
+    pub unique_id: Option<String>,
+    /// A pattern the camera's [`human_name()`](CameraInfo::human_name) has to match. `*` matches any run of characters and `?` any
+    /// single character.
+    pub name: Option<String>,
+}
+
+impl DeviceSelector {
+    /// Get the backend named by this selector, if any.
+    /// # Errors
+    /// If the backend name is not known, this will error.
+    pub fn api_backend(&self) -> Result<Option<ApiBackend>, NokhwaError> {
+        let Some(name) = &self.backend else {
+            return Ok(None);
+        };
+        NAMED_BACKENDS
+            .into_iter()
+            .find(|backend| backend.to_string().eq_ignore_ascii_case(name))
+            .map(|backend| (backend != ApiBackend::Auto).then_some(backend))
+            .ok_or_else(|| NokhwaError::StructureError {
+                structure: "DeviceSelector".to_string(),
+                error: format!("Unknown backend {name}"),
+            })
+    }
+
+    /// Returns `true` if `info` matches this selector.
+    #[must_use]
+    pub fn matches(&self, info: &CameraInfo) -> bool {
+        let id_matches = self
+            .unique_id
+            .as_ref()
+            .is_none_or(|id| *id == info.misc() || *id == info.index().to_string());
+        let name_matches = self
+            .name
+            .as_ref()
+            .is_none_or(|pattern| wildcard_match(pattern, &info.human_name()));
+        id_matches && name_matches
+    }
+
+    /// Finds the first camera matching this selector.
+    /// # Errors
+    /// If the backends fail to query or no camera matches, this will error.
+    pub fn select(&self) -> Result<(ApiBackend, CameraInfo), NokhwaError> {
+        let found = if let Some(backend) = self.api_backend()? {
+            query(backend)?
+                .into_iter()
+                .find(|info| self.matches(info))
+                .map(|info| (backend, info))
+        } else {
+            let mut registry = DeviceRegistry::global()
+                .lock()
+                .map_err(|why| NokhwaError::GeneralError(why.to_string()))?;
+            registry.devices()?.iter().find_map(|device| {
+                std::iter::once((device.backend(), device.info()))
+                    .chain(
+                        device
+                            .alternatives()
+                            .iter()
+                            .map(|(backend, info)| (*backend, info)),
+                    )
+                    .find(|(_, info)| self.matches(info))
+                    .map(|(backend, info)| (backend, info.clone()))
+            })
+        };
+        found.ok_or_else(|| {
+            NokhwaError::OpenDeviceError(format!("{self:?}"), "No camera matches".to_string())
+        })
+    }
+}
+
+/// The constraints the opened format has to satisfy. If none are given, the camera keeps the format it opened with.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConstraints {
+    /// The preferred resolution.
+    pub resolution: Option<Resolution>,
+    pub min_resolution: Option<Resolution>,
+    pub max_resolution: Option<Resolution>,
+    /// The preferred frame rate, in frames per second.
+    pub frame_rate: Option<u32>,
+    pub min_frame_rate: Option<u32>,
+    pub max_frame_rate: Option<u32>,
+    /// The frame formats that may be used. If empty, any is.
+    pub frame_formats: Vec<FrameFormat>,
+    /// How to pick among the formats that satisfy the constraints. Defaults to the closest to the preferred values.
+    pub strategy: Option<CustomFormatRequestType>,
+}
+
+impl FormatConstraints {
+    /// Returns `true` if no constraint is given.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == FormatConstraints::default()
+    }
+
+    /// Converts the constraints to a [`FormatRequest`].
+    /// # Errors
+    /// If a frame rate is 0, this will error.
+    pub fn to_request(&self) -> Result<FormatRequest, NokhwaError> {
+        let frame_rate = |fps: Option<u32>| fps.map(FrameRate::new_integer).transpose();
+
+        let mut request = FormatRequest::new()
+            .with_request_type(self.strategy.unwrap_or(CustomFormatRequestType::Closest));
+        if self.resolution.is_some()
+            || self.min_resolution.is_some()
+            || self.max_resolution.is_some()
+        {
+            request = request.set_resolution_range(Range::new(
+                self.resolution,
+                self.min_resolution,
+                self.max_resolution,
+            ));
+        }
+        if self.frame_rate.is_some()
+            || self.min_frame_rate.is_some()
+            || self.max_frame_rate.is_some()
+        {
+            request = request.set_frame_rate_range(Range::new(
+                frame_rate(self.frame_rate)?,
+                frame_rate(self.min_frame_rate)?,
+                frame_rate(self.max_frame_rate)?,
+            ));
+        }
+        Ok(self.frame_formats.iter().fold(request, |request, format| {
+            request.push_frame_format(*format)
+        }))
+    }
+}
+
+/// A control to set once the camera is open.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ControlSetting {
+    pub control: KnownCameraControl,
+    pub value: ControlValueSetter,
+}
+
+/// A step every frame goes through before it reaches the sinks, in order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PipelineStep {
+    /// Decodes frames to `Rgb8` or `RgbA8`, which the LUT steps need. Frames already in that format pass through unchanged.
+    ///
+    /// `MJpeg`, `Yuv422`, `Nv12` and the RGB/BGR formats can be decoded, `MJpeg` only with the `decoding-mozjpeg` feature.
+    Decode { decode: FrameFormat },
+    /// Applies a gamma curve to RGB frames, see [`Lut1d::gamma()`].
+    Gamma { gamma: f32 },
+    /// Applies a `.cube` 3D LUT to RGB frames, see [`Lut3d::open_cube()`].
+    Cube { path: PathBuf },
+    /// Tone maps radiometric thermal frames to [`FrameFormat::Luma8`], see [`Buffer::tone_map()`].
+    ToneMap { tone_map: ToneMap },
+}
+
+impl PipelineStep {
+    fn build(&self) -> Result<BuiltStep, NokhwaError> {
+        Ok(match self {
+            PipelineStep::Decode { decode } => match decode {
+                FrameFormat::Rgb8 | FrameFormat::RgbA8 => BuiltStep::Decode(*decode),
+                _ => {
+                    return Err(NokhwaError::StructureError {
+                        structure: "PipelineStep".to_string(),
+                        error: format!("Can not decode to {decode}, only to Rgb8 or RgbA8"),
+                    })
+                }
+            },
+            PipelineStep::Gamma { gamma } => BuiltStep::Lut(Box::new(Lut1d::gamma(*gamma))),
+            PipelineStep::Cube { path } => BuiltStep::Lut(Box::new(Lut3d::open_cube(path)?)),
+            PipelineStep::ToneMap { tone_map } => BuiltStep::ToneMap(*tone_map),
+        })
+    }
+}
+
+/// Where frames end up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// An [`ImageSequenceSink`].
+    ImageSequence {
+        directory: PathBuf,
+        #[serde(default)]
+        prefix: String,
+    },
+    /// A [`RawStreamSink`] connected over TCP.
+    Tcp { address: String },
+    /// A [`RawStreamSink`] sending over UDP.
+    Udp { address: String },
+}
+
+impl SinkConfig {
+    fn build(&self) -> Result<Box<dyn FrameSink>, NokhwaError> {
+        Ok(match self {
+            SinkConfig::ImageSequence { directory, prefix } => {
+                Box::new(ImageSequenceSink::new(directory.clone(), prefix)?)
+            }
+            SinkConfig::Tcp { address } => Box::new(RawStreamSink::tcp(address.as_str())?),
+            SinkConfig::Udp { address } => Box::new(RawStreamSink::udp(address.as_str())?),
+        })
+    }
+}
+
+enum BuiltStep {
+    Decode(FrameFormat),
+    Lut(Box<dyn ColorLut>),
+    ToneMap(ToneMap),
+}
+
+/// Runs the configured pipeline on every frame and hands the result to every configured sink.
+struct PipelineSink {
+    steps: Vec<BuiltStep>,
+    sinks: Vec<Box<dyn FrameSink>>,
+}
+
+impl FrameSink for PipelineSink {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        let buffer = self
+            .steps
+            .iter()
+            .try_fold(buffer, |buffer, step| match step {
+                BuiltStep::Decode(format) => decode(buffer, *format),
+                BuiltStep::Lut(lut) => buffer.apply_lut(lut.as_ref()),
+                BuiltStep::ToneMap(tone_map) => buffer.tone_map(*tone_map),
+            })?;
+        self.sinks
+            .iter_mut()
+            .try_for_each(|sink| sink.consume(buffer.clone()))
+    }
+
+    fn finish(&mut self) -> Result<(), NokhwaError> {
+        self.sinks
+            .iter_mut()
+            .map(|sink| sink.finish())
+            .fold(Ok(()), Result::and)
+    }
+}
+
+impl Camera {
+    /// Opens the camera described by `config`, then requests its format and sets its controls. Use
+    /// [`CameraConfig::capture_loop()`] to feed the configured pipeline and sinks.
+    /// # Errors
+    /// If no camera matches, it fails to open, no format satisfies the constraints or a control can not be set, this will error.
+    pub fn from_config(config: &CameraConfig) -> Result<Self, NokhwaError> {
+        let (backend, info) = config.device.select()?;
+        let mut camera = Camera::with_api_backend(info.index().clone(), backend)?;
+        camera.configure(config)?;
+        Ok(camera)
+    }
+
+    /// Requests the format and sets the controls of `config` on this camera, ignoring its `device` section, e.g. for a camera of a
+    /// [`Custom`](ApiBackend::Custom) backend.
+    /// # Errors
+    /// If no format satisfies the constraints or a control can not be set, this will error.
+    pub fn configure(&mut self, config: &CameraConfig) -> Result<(), NokhwaError> {
+        if !config.format.is_empty() {
+            self.set_camera_request(&config.format.to_request()?)?;
+        }
+        for setting in &config.controls {
+            self.set_camera_control(setting.control, setting.value.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes `buffer` to `format`, which is either `Rgb8` or `RgbA8`.
+fn decode(buffer: Buffer, format: FrameFormat) -> Result<Buffer, NokhwaError> {
+    let source = buffer.source_frame_format();
+    if source == format {
+        return Ok(buffer);
+    }
+
+    let resolution = buffer.resolution();
+    let mut rgb = vec![0; resolution.width() as usize * resolution.height() as usize * 3];
+    match source {
+        FrameFormat::Rgb8 | FrameFormat::RgbA8 | FrameFormat::Bgr8 | FrameFormat::BgrA8 => {
+            RGBDecoder::decode_into(&buffer, &mut rgb)?;
+        }
+        FrameFormat::Yuv422 => YUYVDecoder::decode_into(&buffer, &mut rgb)?,
+        FrameFormat::Nv12 => NV12Decoder::decode_into(&buffer, &mut rgb)?,
+        FrameFormat::MJpeg => decode_mjpeg(&buffer, &mut rgb)?,
+        _ => {
+            return Err(NokhwaError::ProcessFrameError {
+                src: source,
+                destination: format.to_string(),
+                error: "No decoder for this format".to_string(),
+            })
+        }
+    }
+
+    if format == FrameFormat::RgbA8 {
+        let rgba = rgb
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect::<Vec<u8>>();
+        return Ok(buffer.with_samples(&rgba, format));
+    }
+    Ok(buffer.with_samples(&rgb, format))
+}
+
+/// Decodes a `MJpeg` `buffer` into `destination`, which holds `width * height * 3` bytes.
+#[cfg(feature = "decoding-mozjpeg")]
+fn decode_mjpeg(buffer: &Buffer, destination: &mut [u8]) -> Result<(), NokhwaError> {
+    let process_error = |error: String| NokhwaError::ProcessFrameError {
+        src: FrameFormat::MJpeg,
+        destination: "RGB888".to_string(),
+        error,
+    };
+
+    let mut decompress = mozjpeg::Decompress::new_mem(buffer.buffer())
+        .and_then(mozjpeg::Decompress::rgb)
+        .map_err(|why| process_error(why.to_string()))?;
+    let resolution = buffer.resolution();
+    if decompress.width() != resolution.width() as usize
+        || decompress.height() != resolution.height() as usize
+    {
+        return Err(process_error(format!(
+            "Decoded {}x{}, expected {resolution}",
+            decompress.width(),
+            decompress.height()
+        )));
+    }
+    decompress
+        .read_scanlines_into(destination)
+        .map_err(|why| process_error(why.to_string()))?;
+    decompress
+        .finish()
+        .map_err(|why| process_error(why.to_string()))
+}
+
+#[cfg(not(feature = "decoding-mozjpeg"))]
+fn decode_mjpeg(_: &Buffer, _: &mut [u8]) -> Result<(), NokhwaError> {
+    Err(NokhwaError::NotImplementedError(
+        "Decoding MJpeg needs the `decoding-mozjpeg` feature".to_string(),
+    ))
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters and `?` any single character.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+    let (mut p, mut t) = (0, 0);
+    // where the last `*` was and where in `text` it started matching, to backtrack to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
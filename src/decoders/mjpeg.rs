@@ -43,7 +43,7 @@ fn decompress(
 pub struct MJPegDecoder;
 
 impl Decoder for MJPegDecoder {
-    const ALLOWED_FORMATS: &'static [SourceFrameFormat] = &[SourceFrameFormat::FrameFormat(FrameFormat::MJpeg)];
+    const ALLOWED_FORMATS: &'static [FrameFormat] = &[FrameFormat::MJpeg];
     type Pixel = Rgb<u8>;
     type Container = Vec<u8>;
     type Error = NokhwaError;
@@ -1,3 +1,4 @@
 pub mod mjpeg;
 pub mod yuyv;
 pub mod nv12;
+pub mod rgb;
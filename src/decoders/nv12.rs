@@ -1,41 +1,141 @@
 use image::{ImageBuffer, Rgb};
 use nokhwa_core::buffer::Buffer;
 use nokhwa_core::decoder::{Decoder, IdemptDecoder, StaticDecoder};
-use nokhwa_core::frame_format::SourceFrameFormat;
+use nokhwa_core::error::NokhwaError;
+use nokhwa_core::frame_format::FrameFormat;
+use nokhwa_core::types::{buf_nv12_to_rgb, Resolution};
+use std::borrow::Cow;
 
-pub struct NV12Decoder {}
+/// Decodes NV12 (4:2:0, a full resolution luma plane followed by an interleaved `UV` plane at half resolution) to RGB888.
+///
+/// Media Foundation and `AVFoundation` cameras often only offer NV12 besides MJPEG, and V4L2 hardware encoders and ISPs
+/// commonly produce it. Planes with a stride (padded rows) are repacked before converting.
+///
+/// The `*_to_buffer` trait methods are not given the frame to decode, so they error; use [`NV12Decoder::decode_into()`] instead.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NV12Decoder {
+    last_resolution: Option<Resolution>,
+}
+
+impl NV12Decoder {
+    /// Creates a new [`NV12Decoder`].
+    #[must_use]
+    pub fn new() -> Self {
+        NV12Decoder::default()
+    }
+
+    /// Decodes `buffer` into `destination`, which has to hold exactly `width * height * 3` bytes.
+    /// # Errors
+    /// If `buffer` is not NV12, has an odd resolution, is truncated, or `destination` has the wrong size, this will error.
+    pub fn decode_into(buffer: &Buffer, destination: &mut [u8]) -> Result<(), NokhwaError> {
+        buf_nv12_to_rgb(buffer.resolution(), &packed_nv12(buffer)?, destination, false)
+    }
+}
 
 impl Decoder for NV12Decoder {
-    const ALLOWED_FORMATS: &'static [SourceFrameFormat] = &[];
-    type Pixel = Rgb<u8>;
-    type Container = Vec<u8>;
-    type Error = ();
+    const ALLOWED_FORMATS: &'static [FrameFormat] = &[FrameFormat::Nv12];
+    type OutputPixels = Rgb<u8>;
+    type PixelContainer = Vec<u8>;
+    type Error = NokhwaError;
 
-    fn decode(&mut self, buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode(&mut self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        self.last_resolution = Some(buffer.resolution());
+        Self::decode_static(buffer)
     }
 
-    fn decode_buffer(&mut self, buffer: &mut [Pixel::Subpixel]) -> Result<(), Self::Error> {
-        todo!()
+    fn decode_buffer(&mut self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
     }
 
     fn predicted_size_of_frame(&mut self) -> Option<usize> {
-        todo!()
+        self.last_resolution
+            .map(|resolution| resolution.width() as usize * resolution.height() as usize * 3)
     }
 }
 
 impl StaticDecoder for NV12Decoder {
-    fn decode_static(buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode_static(buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        let resolution = buffer.resolution();
+        let mut rgb = vec![0; resolution.width() as usize * resolution.height() as usize * 3];
+        Self::decode_into(&buffer, &mut rgb)?;
+        ImageBuffer::from_raw(resolution.width(), resolution.height(), rgb).ok_or_else(|| {
+            NokhwaError::ProcessFrameError {
+                src: FrameFormat::Nv12,
+                destination: "RGB888".to_string(),
+                error: "Bad image buffer size".to_string(),
+            }
+        })
     }
 
-    fn decode_static_to_buffer(buffer: &mut [Pixel::Subpixel]) -> Result<(), Self::Error> {
-        todo!()
+    fn decode_static_to_buffer(_buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
     }
 }
 
 impl IdemptDecoder for NV12Decoder {
-    fn decode_nm(buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode_nm(&self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        Self::decode_static(buffer)
+    }
+
+    fn decode_nm_to_buffer(&self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
+    }
+}
+
+fn no_source_frame() -> NokhwaError {
+    NokhwaError::NotImplementedError(
+        "Decoding to a buffer needs the source frame, use NV12Decoder::decode_into()".to_string(),
+    )
+}
+
+fn process_error(error: &str) -> NokhwaError {
+    NokhwaError::ProcessFrameError {
+        src: FrameFormat::Nv12,
+        destination: "RGB888".to_string(),
+        error: error.to_string(),
+    }
+}
+
+// the conversion wants the planes back to back without padding, which is how most frames already arrive
+fn packed_nv12(buffer: &Buffer) -> Result<Cow<'_, [u8]>, NokhwaError> {
+    if buffer.source_frame_format() != FrameFormat::Nv12 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: buffer.source_frame_format(),
+            destination: "RGB888".to_string(),
+            error: "Not NV12".to_string(),
+        });
     }
-}
\ No newline at end of file
+
+    let width = buffer.resolution().width() as usize;
+    let height = buffer.resolution().height() as usize;
+    if buffer.planes().iter().all(|plane| plane.stride().is_none()) {
+        // drivers may hand out a larger buffer than the frame, e.g. a whole mmap buffer
+        return buffer
+            .buffer()
+            .get(..width * height * 3 / 2)
+            .map(Cow::Borrowed)
+            .ok_or_else(|| process_error("Buffer too short"));
+    }
+
+    let luma_stride = buffer.stride(0).unwrap_or(width);
+    let (luma, chroma, chroma_stride) = match (buffer.plane(0), buffer.plane(1)) {
+        (Some(luma), Some(chroma)) => (luma, chroma, buffer.stride(1).unwrap_or(width)),
+        // a single plane holds the chroma rows right after the luma rows, with the same stride
+        (Some(data), None) => {
+            let (luma, chroma) = data.split_at(data.len().min(luma_stride * height));
+            (luma, chroma, luma_stride)
+        }
+        _ => return Err(process_error("Missing planes")),
+    };
+
+    let mut packed = Vec::with_capacity(width * height * 3 / 2);
+    for (plane, stride, rows) in [(luma, luma_stride, height), (chroma, chroma_stride, height / 2)] {
+        for row in 0..rows {
+            let row = plane
+                .get(row * stride..row * stride + width)
+                .ok_or_else(|| process_error("Truncated plane"))?;
+            packed.extend_from_slice(row);
+        }
+    }
+    Ok(Cow::Owned(packed))
+}
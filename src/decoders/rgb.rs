@@ -0,0 +1,141 @@
+use image::{ImageBuffer, Rgb};
+use nokhwa_core::buffer::{Buffer, BufferPlane};
+use nokhwa_core::decoder::{Decoder, IdemptDecoder, StaticDecoder};
+use nokhwa_core::error::NokhwaError;
+use nokhwa_core::frame_format::FrameFormat;
+use nokhwa_core::types::Resolution;
+
+/// Passes RGB frames through as RGB888, for cameras that can deliver RGB themselves, avoiding a YUV conversion entirely.
+///
+/// [`FrameFormat::Rgb8`] frames are only copied (dropping any row padding). [`FrameFormat::RgbA8`], [`FrameFormat::Bgr8`] and
+/// [`FrameFormat::BgrA8`] (Media Foundation's `RGB24` and `RGB32`) have their samples reordered and the fourth byte dropped.
+///
+/// The `*_to_buffer` trait methods are not given the frame to decode, so they error; use [`RGBDecoder::decode_into()`] instead.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RGBDecoder {
+    last_resolution: Option<Resolution>,
+}
+
+impl RGBDecoder {
+    /// Creates a new [`RGBDecoder`].
+    #[must_use]
+    pub fn new() -> Self {
+        RGBDecoder::default()
+    }
+
+    /// Decodes `buffer` into `destination`, which has to hold exactly `width * height * 3` bytes.
+    /// # Errors
+    /// If `buffer` is not an RGB or BGR format, is truncated, or `destination` has the wrong size, this will error.
+    pub fn decode_into(buffer: &Buffer, destination: &mut [u8]) -> Result<(), NokhwaError> {
+        let format = buffer.source_frame_format();
+        let process_error = |error: &str| NokhwaError::ProcessFrameError {
+            src: format,
+            destination: "RGB888".to_string(),
+            error: error.to_string(),
+        };
+        let (channels, blue_first) = match format {
+            FrameFormat::Rgb8 => (3, false),
+            FrameFormat::RgbA8 => (4, false),
+            FrameFormat::Bgr8 => (3, true),
+            FrameFormat::BgrA8 => (4, true),
+            _ => return Err(process_error("Not an RGB format")),
+        };
+
+        let width = buffer.resolution().width() as usize;
+        let height = buffer.resolution().height() as usize;
+        if destination.len() != width * height * 3 {
+            return Err(process_error("Bad output buffer size"));
+        }
+        if width == 0 {
+            return Ok(());
+        }
+        let stride = buffer
+            .planes()
+            .first()
+            .and_then(BufferPlane::stride)
+            .unwrap_or(width * channels);
+        if stride < width * channels {
+            return Err(process_error("Invalid stride"));
+        }
+
+        for (row, output) in destination.chunks_exact_mut(width * 3).enumerate() {
+            let input = buffer
+                .buffer()
+                .get(row * stride..row * stride + width * channels)
+                .ok_or_else(|| process_error("Buffer too short"))?;
+            if channels == 3 && !blue_first {
+                output.copy_from_slice(input);
+                continue;
+            }
+            for (pixel, sample) in output.chunks_exact_mut(3).zip(input.chunks_exact(channels)) {
+                if blue_first {
+                    pixel.copy_from_slice(&[sample[2], sample[1], sample[0]]);
+                } else {
+                    pixel.copy_from_slice(&sample[..3]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for RGBDecoder {
+    const ALLOWED_FORMATS: &'static [FrameFormat] = &[
+        FrameFormat::Rgb8,
+        FrameFormat::RgbA8,
+        FrameFormat::Bgr8,
+        FrameFormat::BgrA8,
+    ];
+    type OutputPixels = Rgb<u8>;
+    type PixelContainer = Vec<u8>;
+    type Error = NokhwaError;
+
+    fn decode(&mut self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        self.last_resolution = Some(buffer.resolution());
+        Self::decode_static(buffer)
+    }
+
+    fn decode_buffer(&mut self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
+    }
+
+    fn predicted_size_of_frame(&mut self) -> Option<usize> {
+        self.last_resolution
+            .map(|resolution| resolution.width() as usize * resolution.height() as usize * 3)
+    }
+}
+
+impl StaticDecoder for RGBDecoder {
+    fn decode_static(buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        let resolution = buffer.resolution();
+        let mut rgb = vec![0; resolution.width() as usize * resolution.height() as usize * 3];
+        Self::decode_into(&buffer, &mut rgb)?;
+        ImageBuffer::from_raw(resolution.width(), resolution.height(), rgb).ok_or_else(|| {
+            NokhwaError::ProcessFrameError {
+                src: buffer.source_frame_format(),
+                destination: "RGB888".to_string(),
+                error: "Bad image buffer size".to_string(),
+            }
+        })
+    }
+
+    fn decode_static_to_buffer(_buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
+    }
+}
+
+impl IdemptDecoder for RGBDecoder {
+    fn decode_nm(&self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        Self::decode_static(buffer)
+    }
+
+    fn decode_nm_to_buffer(&self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
+    }
+}
+
+fn no_source_frame() -> NokhwaError {
+    NokhwaError::NotImplementedError(
+        "Decoding to a buffer needs the source frame, use RGBDecoder::decode_into()".to_string(),
+    )
+}
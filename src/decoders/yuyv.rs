@@ -1,7 +1,9 @@
-use image::ImageBuffer;
+use image::{ImageBuffer, Rgb};
 use nokhwa_core::buffer::Buffer;
 use nokhwa_core::decoder::{Decoder, IdemptDecoder, StaticDecoder};
-use nokhwa_core::frame_format::SourceFrameFormat;
+use nokhwa_core::error::NokhwaError;
+use nokhwa_core::frame_format::FrameFormat;
+use nokhwa_core::types::{buf_yuyv422_to_rgb, Resolution};
 
 // For those maintaining this, I recommend you read: https://docs.microsoft.com/en-us/windows/win32/medfound/recommended-8-bit-yuv-formats-for-video-rendering#yuy2
 // https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB
@@ -10,45 +12,111 @@ use nokhwa_core::frame_format::SourceFrameFormat;
 // First, the YUY2 is converted to YCbCr 4:4:4 (4:2:2 -> 4:4:4)
 // then it is converted to 6 bytes (2 pixels) of RGB888
 /// Converts a Yuv422 4:2:2 datastream to a RGB888 Stream. [For further reading](https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB)
-/// # Errors
-/// This may error when the data stream size is not divisible by 4, a i32 -> u8 conversion fails, or it fails to read from a certain index.
-pub struct YUYVDecoder {}
+///
+/// Frames with a stride (padded rows) are converted row by row, skipping the padding.
+///
+/// The `*_to_buffer` trait methods are not given the frame to decode, so they error; use [`YUYVDecoder::decode_into()`] instead.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct YUYVDecoder {
+    last_resolution: Option<Resolution>,
+}
+
+impl YUYVDecoder {
+    /// Creates a new [`YUYVDecoder`].
+    #[must_use]
+    pub fn new() -> Self {
+        YUYVDecoder::default()
+    }
+
+    /// Decodes `buffer` into `destination`, which has to hold exactly `width * height * 3` bytes.
+    /// # Errors
+    /// If `buffer` is not Yuv422, has an odd width, is truncated, or `destination` has the wrong size, this will error.
+    pub fn decode_into(buffer: &Buffer, destination: &mut [u8]) -> Result<(), NokhwaError> {
+        if buffer.source_frame_format() != FrameFormat::Yuv422 {
+            return Err(process_error("Not YUYV"));
+        }
+        let width = buffer.resolution().width() as usize;
+        let height = buffer.resolution().height() as usize;
+        if !width.is_multiple_of(2) {
+            return Err(process_error("Odd width"));
+        }
+        if destination.len() != width * height * 3 {
+            return Err(process_error("Bad output buffer size"));
+        }
+        if width == 0 {
+            return Ok(());
+        }
+        let stride = buffer.stride(0).unwrap_or(width * 2);
+        if stride < width * 2 {
+            return Err(process_error("Invalid stride"));
+        }
+
+        for (row, output) in destination.chunks_exact_mut(width * 3).enumerate() {
+            let input = buffer
+                .buffer()
+                .get(row * stride..row * stride + width * 2)
+                .ok_or_else(|| process_error("Buffer too short"))?;
+            buf_yuyv422_to_rgb(input, output, false)?;
+        }
+        Ok(())
+    }
+}
 
 impl Decoder for YUYVDecoder {
-    const ALLOWED_FORMATS: &'static [SourceFrameFormat] = &[];
-    type Pixel = ();
-    type Container = ();
-    type Error = ();
+    const ALLOWED_FORMATS: &'static [FrameFormat] = &[FrameFormat::Yuv422];
+    type OutputPixels = Rgb<u8>;
+    type PixelContainer = Vec<u8>;
+    type Error = NokhwaError;
 
-    fn decode(&mut self, buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode(&mut self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        self.last_resolution = Some(buffer.resolution());
+        Self::decode_static(buffer)
     }
 
-    fn decode_buffer(&mut self, buffer: &mut [Pixel::Subpixel]) -> Result<(), Self::Error> {
-        todo!()
+    fn decode_buffer(&mut self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
     }
 
     fn predicted_size_of_frame(&mut self) -> Option<usize> {
-        todo!()
+        self.last_resolution
+            .map(|resolution| resolution.width() as usize * resolution.height() as usize * 3)
     }
 }
 
 impl StaticDecoder for YUYVDecoder {
-    fn decode_static(buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode_static(buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        let resolution = buffer.resolution();
+        let mut rgb = vec![0; resolution.width() as usize * resolution.height() as usize * 3];
+        Self::decode_into(&buffer, &mut rgb)?;
+        ImageBuffer::from_raw(resolution.width(), resolution.height(), rgb)
+            .ok_or_else(|| process_error("Bad image buffer size"))
     }
 
-    fn decode_static_to_buffer(buffer: &mut [Pixel::Subpixel]) -> Result<(), Self::Error> {
-        todo!()
+    fn decode_static_to_buffer(_buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
     }
 }
 
 impl IdemptDecoder for YUYVDecoder {
-    fn decode_nm(&self, buffer: Buffer) -> Result<ImageBuffer<Self::Pixel, Self::Container>, Self::Error> {
-        todo!()
+    fn decode_nm(&self, buffer: Buffer) -> Result<ImageBuffer<Self::OutputPixels, Self::PixelContainer>, Self::Error> {
+        Self::decode_static(buffer)
+    }
+
+    fn decode_nm_to_buffer(&self, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+        Err(no_source_frame())
     }
+}
+
+fn no_source_frame() -> NokhwaError {
+    NokhwaError::NotImplementedError(
+        "Decoding to a buffer needs the source frame, use YUYVDecoder::decode_into()".to_string(),
+    )
+}
 
-    fn decode_nm_to_buffer(&self, buffer: &mut [Pixel::Subpixel]) -> Result<(), Self::Error> {
-        todo!()
+fn process_error(error: &str) -> NokhwaError {
+    NokhwaError::ProcessFrameError {
+        src: FrameFormat::Yuv422,
+        destination: "RGB888".to_string(),
+        error: error.to_string(),
     }
 }
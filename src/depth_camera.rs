@@ -0,0 +1,262 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use nokhwa_core::{
+    buffer::Buffer, depth::StreamCalibration, error::NokhwaError, frame_format::FrameFormat,
+    traits::CaptureTrait,
+};
+use std::time::Duration;
+
+/// How many frames one stream may be ahead before [`DepthColorCamera::frame()`] gives up on matching it.
+const MAX_SKIPPED_FRAMES: usize = 8;
+
+/// A depth frame and the color frame captured closest to it.
+#[derive(Clone, Debug)]
+pub struct DepthFrame {
+    depth: Buffer,
+    color: Buffer,
+    depth_units: f32,
+    depth_calibration: StreamCalibration,
+    color_calibration: StreamCalibration,
+}
+
+impl DepthFrame {
+    /// Get the [`FrameFormat::Depth16`] frame.
+    #[must_use]
+    pub fn depth(&self) -> &Buffer {
+        &self.depth
+    }
+
+    /// Get the color frame.
+    #[must_use]
+    pub fn color(&self) -> &Buffer {
+        &self.color
+    }
+
+    /// Get how many meters one step of the depth samples is.
+    #[must_use]
+    pub fn depth_units(&self) -> f32 {
+        self.depth_units
+    }
+
+    /// Get the calibration of the depth stream.
+    #[must_use]
+    pub fn depth_calibration(&self) -> StreamCalibration {
+        self.depth_calibration
+    }
+
+    /// Get the calibration of the color stream.
+    #[must_use]
+    pub fn color_calibration(&self) -> StreamCalibration {
+        self.color_calibration
+    }
+
+    /// Get how far apart the two frames were captured, if both have timestamps on the same clock.
+    #[must_use]
+    pub fn time_offset(&self) -> Option<Duration> {
+        time_offset(&self.depth, &self.color)
+    }
+
+    /// Get the depth of every pixel in meters, row by row. See [`Buffer::depth_meters`].
+    /// # Errors
+    /// If the depth frame is not [`FrameFormat::Depth16`] or is too short, this will error.
+    pub fn depth_meters(&self) -> Result<Vec<f32>, NokhwaError> {
+        self.depth.depth_meters(self.depth_units)
+    }
+
+    /// Splits this into the depth and the color frame.
+    #[must_use]
+    pub fn into_buffers(self) -> (Buffer, Buffer) {
+        (self.depth, self.color)
+    }
+}
+
+// how far apart two frames were captured, if both have timestamps on the same clock
+pub(crate) fn time_offset(first: &Buffer, second: &Buffer) -> Option<Duration> {
+    let first = first.timestamp()?;
+    let second = second.timestamp()?;
+    if first.clock() != second.clock() {
+        return None;
+    }
+    Some(first.time().abs_diff(second.time()))
+}
+
+// captures a frame from both streams, skipping ahead in whichever is behind until they are within `tolerance`
+pub(crate) fn paired_frames(
+    first: &mut dyn CaptureTrait,
+    second: &mut dyn CaptureTrait,
+    tolerance: Duration,
+) -> Result<(Buffer, Buffer), NokhwaError> {
+    let mut first_frame = first.frame()?;
+    let mut second_frame = second.frame()?;
+
+    for _ in 0..MAX_SKIPPED_FRAMES {
+        let (Some(first_time), Some(second_time)) =
+            (first_frame.timestamp(), second_frame.timestamp())
+        else {
+            break;
+        };
+        if first_time.clock() != second_time.clock()
+            || first_time.time().abs_diff(second_time.time()) <= tolerance
+        {
+            break;
+        }
+        if first_time.time() < second_time.time() {
+            first_frame = first.frame()?;
+        } else {
+            second_frame = second.frame()?;
+        }
+    }
+
+    if let Some(offset) = time_offset(&first_frame, &second_frame) {
+        if offset > tolerance {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "The frames of the two streams are {offset:?} apart"
+            )));
+        }
+    }
+    Ok((first_frame, second_frame))
+}
+
+/// Captures a depth stream and a color stream as one, e.g. the depth and RGB nodes of an Intel `RealSense` camera exposed over UVC or V4L2.
+///
+/// Each [`frame()`](DepthColorCamera::frame) pairs a depth frame with the color frame whose timestamp is within the [`tolerance`](DepthColorCamera::tolerance),
+/// skipping ahead in whichever stream is behind.
+/// # Quirks
+/// - Frames are only matched if both backends timestamp them on the same clock (e.g. both V4L2's monotonic clock). Otherwise the latest
+///   frames of both are paired as they are.
+/// - The depth stream has to be set to [`FrameFormat::Depth16`] before [`open_stream()`](DepthColorCamera::open_stream).
+/// - UVC and V4L2 do not report the calibration of the streams. Set it with [`set_calibration()`](DepthColorCamera::set_calibration),
+///   e.g. from the vendor's SDK.
+pub struct DepthColorCamera {
+    depth: Box<dyn CaptureTrait>,
+    color: Box<dyn CaptureTrait>,
+    tolerance: Duration,
+    depth_units: f32,
+    depth_calibration: StreamCalibration,
+    color_calibration: StreamCalibration,
+}
+
+impl DepthColorCamera {
+    /// Creates a new [`DepthColorCamera`] from the depth and the color stream's backends.
+    ///
+    /// The tolerance starts at 10 ms, and the depth units at 1 mm like most `RealSense` cameras.
+    pub fn new(
+        depth: impl Into<Box<dyn CaptureTrait>>,
+        color: impl Into<Box<dyn CaptureTrait>>,
+    ) -> Self {
+        DepthColorCamera {
+            depth: depth.into(),
+            color: color.into(),
+            tolerance: Duration::from_millis(10),
+            depth_units: 0.001,
+            depth_calibration: StreamCalibration::default(),
+            color_calibration: StreamCalibration::default(),
+        }
+    }
+
+    /// Gets the backend of the depth stream.
+    pub fn depth_device(&mut self) -> &mut dyn CaptureTrait {
+        self.depth.as_mut()
+    }
+
+    /// Gets the backend of the color stream.
+    pub fn color_device(&mut self) -> &mut dyn CaptureTrait {
+        self.color.as_mut()
+    }
+
+    /// Gets how far apart a depth and color frame may be captured to be paired.
+    #[must_use]
+    pub fn tolerance(&self) -> Duration {
+        self.tolerance
+    }
+
+    /// Sets how far apart a depth and color frame may be captured to be paired. Half a frame interval is a good start.
+    pub fn set_tolerance(&mut self, tolerance: Duration) {
+        self.tolerance = tolerance;
+    }
+
+    /// Gets how many meters one step of the depth samples is.
+    #[must_use]
+    pub fn depth_units(&self) -> f32 {
+        self.depth_units
+    }
+
+    /// Sets how many meters one step of the depth samples is.
+    pub fn set_depth_units(&mut self, depth_units: f32) {
+        self.depth_units = depth_units;
+    }
+
+    /// Gets the calibration of the depth and the color stream.
+    #[must_use]
+    pub fn calibration(&self) -> (StreamCalibration, StreamCalibration) {
+        (self.depth_calibration, self.color_calibration)
+    }
+
+    /// Sets the calibration of the depth and the color stream, which every [`DepthFrame`] carries.
+    pub fn set_calibration(&mut self, depth: StreamCalibration, color: StreamCalibration) {
+        self.depth_calibration = depth;
+        self.color_calibration = color;
+    }
+
+    /// Opens both streams.
+    /// # Errors
+    /// If the depth stream is not [`FrameFormat::Depth16`], or either stream fails to open, this will error.
+    pub fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.depth.frame_format() != FrameFormat::Depth16 {
+            return Err(NokhwaError::OpenStreamError(format!(
+                "The depth stream is {}, not Depth16",
+                self.depth.frame_format()
+            )));
+        }
+        self.depth.open_stream()?;
+        if let Err(why) = self.color.open_stream() {
+            let _ = self.depth.stop_stream();
+            return Err(why);
+        }
+        Ok(())
+    }
+
+    /// Checks if both streams are open.
+    #[must_use]
+    pub fn is_stream_open(&self) -> bool {
+        self.depth.is_stream_open() && self.color.is_stream_open()
+    }
+
+    /// Captures a depth frame and the color frame closest to it.
+    /// # Errors
+    /// If either stream fails to capture, or no frames within the tolerance turn up after a few frames, this will error.
+    pub fn frame(&mut self) -> Result<DepthFrame, NokhwaError> {
+        let (depth, color) =
+            paired_frames(self.depth.as_mut(), self.color.as_mut(), self.tolerance)?;
+        Ok(DepthFrame {
+            depth,
+            color,
+            depth_units: self.depth_units,
+            depth_calibration: self.depth_calibration,
+            color_calibration: self.color_calibration,
+        })
+    }
+
+    /// Stops both streams.
+    /// # Errors
+    /// If either stream fails to stop, this will error. The other is stopped regardless.
+    pub fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        let depth = self.depth.stop_stream();
+        let color = self.color.stop_stream();
+        depth.and(color)
+    }
+}
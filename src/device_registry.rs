@@ -0,0 +1,304 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{backend_capabilities, native_api_backend, query};
+use nokhwa_core::{
+    error::NokhwaError,
+    traits::DeviceWatcher,
+    types::{ApiBackend, BackendCapability, CameraIndex, CameraInfo},
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+/// The backends [`DeviceRegistry::default()`] queries, in order of preference.
+const LOCAL_BACKENDS: [ApiBackend; 12] = [
+    ApiBackend::Video4Linux,
+    ApiBackend::PipeWire,
+    ApiBackend::LibCamera,
+    ApiBackend::MediaFoundation,
+    ApiBackend::AVFoundation,
+    ApiBackend::Android,
+    ApiBackend::UniversalVideoClass,
+    ApiBackend::GStreamer,
+    ApiBackend::FFmpeg,
+    ApiBackend::OpenCv,
+    ApiBackend::Aravis,
+    ApiBackend::DeckLink,
+];
+
+/// A handle to a camera in a [`DeviceRegistry`]. It stays the same across refreshes for as long as the camera stays plugged in.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct DeviceHandle(u64);
+
+/// A camera in a [`DeviceRegistry`], with the backends it was found under.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisteredDevice {
+    handle: DeviceHandle,
+    backend: ApiBackend,
+    info: CameraInfo,
+    alternatives: Vec<(ApiBackend, CameraInfo)>,
+    key: DeviceKey,
+}
+
+impl RegisteredDevice {
+    /// Get the stable handle of the camera.
+    #[must_use]
+    pub fn handle(&self) -> DeviceHandle {
+        self.handle
+    }
+
+    /// Get the most preferred backend the camera was found under.
+    #[must_use]
+    pub fn backend(&self) -> ApiBackend {
+        self.backend
+    }
+
+    /// Get the [`CameraInfo`] of the camera under [`backend()`](Self::backend).
+    #[must_use]
+    pub fn info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    /// Get the same camera as found under the less preferred backends.
+    #[must_use]
+    pub fn alternatives(&self) -> &[(ApiBackend, CameraInfo)] {
+        &self.alternatives
+    }
+}
+
+/// Caches the results of [`query()`] over several backends, so applications do not enumerate the cameras again every time.
+///
+/// A camera that shows up under multiple backends (e.g. `Video4Linux` and `GStreamer`) is listed once, under the most preferred
+/// backend. The cache is refreshed on the next access after [`invalidate()`](Self::invalidate) or a hotplug event from a
+/// [`watch()`](Self::watch)ed [`DeviceWatcher`].
+/// # Quirks
+/// - A camera is recognised by the stable path its backend reports (a [`CameraIndex::String`] or the [`misc()`](CameraInfo::misc)
+///   string, e.g. a device node or symbolic link), so unplugging one of two identically named cameras does not move the other's handle.
+/// - Backends identify cameras differently, so where two backends do not both report a path, cameras are matched by name: the
+///   n-th camera named "X" under one backend is the n-th camera named "X" under another. The same goes for backends that report
+///   no path at all.
+#[derive(Debug)]
+pub struct DeviceRegistry {
+    backends: Vec<ApiBackend>,
+    devices: Vec<RegisteredDevice>,
+    next_handle: u64,
+    stale: Arc<AtomicBool>,
+}
+
+impl DeviceRegistry {
+    /// Creates a new [`DeviceRegistry`] over `backends`, in order of preference. Nothing is queried until the devices are accessed.
+    #[must_use]
+    pub fn new(backends: Vec<ApiBackend>) -> Self {
+        DeviceRegistry {
+            backends,
+            devices: Vec::new(),
+            next_handle: 0,
+            stale: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Get the process wide [`DeviceRegistry`], created with [`DeviceRegistry::default()`] the first time.
+    #[must_use]
+    pub fn global() -> &'static Mutex<DeviceRegistry> {
+        static GLOBAL: OnceLock<Mutex<DeviceRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Mutex::new(DeviceRegistry::default()))
+    }
+
+    /// Get the backends that are queried, in order of preference.
+    #[must_use]
+    pub fn backends(&self) -> &[ApiBackend] {
+        &self.backends
+    }
+
+    /// Get the cameras, querying the backends again if the cache was invalidated.
+    /// # Errors
+    /// If the cache has to be refreshed and every backend fails to query, this will error.
+    pub fn devices(&mut self) -> Result<&[RegisteredDevice], NokhwaError> {
+        if self.stale.load(Ordering::SeqCst) {
+            self.refresh()?;
+        }
+        Ok(&self.devices)
+    }
+
+    /// Get a camera by its handle, querying the backends again if the cache was invalidated.
+    /// # Errors
+    /// If the cache has to be refreshed and every backend fails to query, this will error.
+    pub fn get(&mut self, handle: DeviceHandle) -> Result<Option<&RegisteredDevice>, NokhwaError> {
+        Ok(self
+            .devices()?
+            .iter()
+            .find(|device| device.handle == handle))
+    }
+
+    /// Marks the cache as stale, so the next access queries the backends again.
+    pub fn invalidate(&self) {
+        self.stale.store(true, Ordering::SeqCst);
+    }
+
+    /// Starts `watcher`, invalidating the cache whenever a camera is plugged in or unplugged. This replaces the watcher's callback.
+    /// # Errors
+    /// If the watcher fails to start, this will error.
+    pub fn watch(&self, watcher: &mut dyn DeviceWatcher) -> Result<(), NokhwaError> {
+        let stale = self.stale.clone();
+        watcher.start(Box::new(move |_| stale.store(true, Ordering::SeqCst)))
+    }
+
+    /// Queries the backends now. Backends that fail to query (e.g. because they are not compiled in) are skipped.
+    /// # Errors
+    /// If every backend fails to query, this will error with the last error and the cache stays as it was.
+    pub fn refresh(&mut self) -> Result<(), NokhwaError> {
+        // clear first, so an event during the queries invalidates the new results
+        self.stale.store(false, Ordering::SeqCst);
+
+        let mut queried = false;
+        let mut last_error = None;
+        let mut found = Vec::new();
+        for backend in &self.backends {
+            match query(*backend) {
+                Ok(cameras) => {
+                    queried = true;
+                    found.extend(cameras.into_iter().map(|info| (*backend, info)));
+                }
+                Err(why) => last_error = Some(why),
+            }
+        }
+        if let (false, Some(why)) = (queried, last_error) {
+            self.stale.store(true, Ordering::SeqCst);
+            return Err(why);
+        }
+        self.register(found);
+        Ok(())
+    }
+
+    /// Replaces the cameras with `found`, merging the ones found under multiple backends and keeping the handles of the ones that
+    /// were found before.
+    fn register(&mut self, found: Vec<(ApiBackend, CameraInfo)>) {
+        let previous = self
+            .devices
+            .drain(..)
+            .map(|device| (device.key, device.handle))
+            .collect::<Vec<(DeviceKey, DeviceHandle)>>();
+        let mut paths: HashMap<(ApiBackend, String), usize> = HashMap::new();
+        for (backend, info) in &found {
+            if let Some(path) = stable_path(info) {
+                *paths.entry((*backend, path)).or_default() += 1;
+            }
+        }
+        let mut occurrences: HashMap<(ApiBackend, String), usize> = HashMap::new();
+        for (backend, info) in found {
+            let name = info.human_name();
+            let occurrence = occurrences.entry((backend, name.clone())).or_default();
+            let mut key = DeviceKey {
+                // a path more than one camera reports does not tell them apart
+                paths: stable_path(&info)
+                    .filter(|path| paths.get(&(backend, path.clone())) == Some(&1))
+                    .map(|path| (backend, path))
+                    .into_iter()
+                    .collect(),
+                name: (name, *occurrence),
+            };
+            *occurrence += 1;
+
+            if let Some(device) = self
+                .devices
+                .iter_mut()
+                .find(|device| device.key.matches(&key))
+            {
+                device.key.paths.append(&mut key.paths);
+                device.alternatives.push((backend, info));
+                continue;
+            }
+            self.devices.push(RegisteredDevice {
+                handle: DeviceHandle(0),
+                backend,
+                info,
+                alternatives: Vec::new(),
+                key,
+            });
+        }
+
+        // match handles only once every backend is merged in, so the paths of the alternatives count too
+        let mut taken = Vec::new();
+        for device in &mut self.devices {
+            let reused = previous.iter().find(|(previous_key, handle)| {
+                previous_key.matches(&device.key) && !taken.contains(handle)
+            });
+            device.handle = if let Some((_, handle)) = reused {
+                *handle
+            } else {
+                self.next_handle += 1;
+                DeviceHandle(self.next_handle)
+            };
+            taken.push(device.handle);
+        }
+    }
+}
+
+impl Default for DeviceRegistry {
+    /// Creates a [`DeviceRegistry`] over the native backend followed by every other compiled in local backend that can query.
+    /// Network cameras are left out, as discovering them takes seconds.
+    fn default() -> Self {
+        let native = native_api_backend();
+        let backends = native
+            .into_iter()
+            .chain(LOCAL_BACKENDS.into_iter().filter(|backend| {
+                Some(*backend) != native
+                    && backend_capabilities(*backend)
+                        .is_ok_and(|capabilities| capabilities.supports(BackendCapability::Query))
+            }))
+            .collect();
+        DeviceRegistry::new(backends)
+    }
+}
+
+/// The stable path `info` identifies its camera by, if any.
+fn stable_path(info: &CameraInfo) -> Option<String> {
+    match info.index() {
+        CameraIndex::String(path) if !path.is_empty() => Some(path.clone()),
+        _ => Some(info.misc()).filter(|misc| !misc.is_empty()),
+    }
+}
+
+/// How a camera is recognised across backends and refreshes.
+#[derive(Clone, Debug, PartialEq)]
+struct DeviceKey {
+    /// The stable paths of the camera, with the backend that reported each. Paths of different backends are not comparable.
+    paths: Vec<(ApiBackend, String)>,
+    /// The name of the camera and how many cameras with that name came before it under the same backend.
+    name: (String, usize),
+}
+
+impl DeviceKey {
+    /// Returns `true` if both keys are the same camera: by path under the backends both have a path for, by name otherwise.
+    fn matches(&self, other: &DeviceKey) -> bool {
+        let mut shared = self.paths.iter().filter_map(|(backend, path)| {
+            other
+                .paths
+                .iter()
+                .find(|(other_backend, _)| other_backend == backend)
+                .map(|(_, other_path)| path == other_path)
+        });
+        match shared.next() {
+            Some(same) => same && shared.all(|same| same),
+            None => self.name == other.name,
+        }
+    }
+}
@@ -0,0 +1,706 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use ffmpeg_next::{
+    codec::{self, Id as CodecId},
+    decoder, encoder,
+    ffi::{
+        av_mallocz, avformat_query_codec, AVMediaType, AV_INPUT_BUFFER_PADDING_SIZE,
+        FF_COMPLIANCE_NORMAL,
+    },
+    format::{self, context::Output, Pixel},
+    packet::Flags as PacketFlags,
+    software::scaling::{context::Context as Scaler, flag::Flags},
+    util::frame::Video as VideoFrame,
+    Packet, Rational,
+};
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{CaptureTrait, FrameSink},
+    types::{CameraFormat, FrameRate, Resolution},
+};
+
+/// How the [`FfmpegSink`] will get frames from the camera into the output container.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum FfmpegSinkMode {
+    /// The camera's compressed payload is written straight into the container without re-encoding.
+    Passthrough(FrameFormat),
+    /// The camera's compressed payload is decoded by `libavcodec`, then re-encoded.
+    Transcode(FrameFormat),
+    /// The camera's raw pixels are converted with `swscale`, then encoded.
+    Encode(FrameFormat),
+}
+
+impl FfmpegSinkMode {
+    /// Picks the [`FfmpegSinkMode`] for a [`FrameFormat`], given whether the output container can hold it as-is.
+    ///
+    /// Compressed formats (`MJPEG`, `H264`, `AVC1`, `H265`) are passed through when possible, everything else gets encoded.
+    #[must_use]
+    pub fn negotiate(frame_format: FrameFormat, container_accepts: bool) -> Option<Self> {
+        match frame_format {
+            ff if frameformat_to_codec_id(ff).is_some() => {
+                if container_accepts {
+                    Some(FfmpegSinkMode::Passthrough(frame_format))
+                } else {
+                    Some(FfmpegSinkMode::Transcode(frame_format))
+                }
+            }
+            ff => frameformat_to_pixel(ff).map(|_| FfmpegSinkMode::Encode(ff)),
+        }
+    }
+}
+
+/// A sink that takes frames from a camera and writes them to a file or a streaming URL (e.g. `rtmp://`) using `FFmpeg`.
+///
+/// Compressed formats (`MJPEG`, `H264`, `AVC1`, `H265`) are written as-is when the container supports them, otherwise
+/// frames are encoded into `H264` using the best available encoder (usually `libx264`).
+/// # Quirks
+/// - `rtmp://` URLs are always written as `flv`. Other targets have their container guessed from the file extension.
+/// - When passing `H264`/`H265` through, frames before the first keyframe are dropped. The container header is written
+/// once that keyframe arrives, with its parameter sets (SPS/PPS) as the codec extradata.
+/// - `AVC1` frames that are length prefixed are rewritten to Annex B before they are written.
+/// - You **must** call [`finish()`](FfmpegSink::finish) to write the container trailer, otherwise the file may be unreadable.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-ffmpeg")))]
+pub struct FfmpegSink {
+    output: Output,
+    mode: FfmpegSinkMode,
+    resolution: Resolution,
+    time_base: Rational,
+    stream_time_base: Rational,
+    decoder: Option<decoder::Video>,
+    encoder: Option<encoder::Video>,
+    scaler: Option<Scaler>,
+    frame_index: i64,
+    header_written: bool,
+    finished: bool,
+}
+
+impl FfmpegSink {
+    /// Creates a new [`FfmpegSink`] writing to `target` with frames of `camera_format`.
+    /// # Errors
+    /// If `FFmpeg` fails to initialize, the target can not be opened, the frame rate is zero, the [`FrameFormat`]
+    /// cannot be negotiated, or the encoder fails to open, this will error.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn new(target: &str, camera_format: CameraFormat) -> Result<Self, NokhwaError> {
+        ffmpeg_next::init().map_err(|why| NokhwaError::InitializeError {
+            backend: nokhwa_core::types::ApiBackend::FFmpeg,
+            error: why.to_string(),
+        })?;
+
+        let mut output = if target.starts_with("rtmp://") || target.starts_with("rtmps://") {
+            format::output_as(&target, "flv")
+        } else {
+            format::output(&target)
+        }
+        .map_err(|why| NokhwaError::OpenStreamError(format!("{target}: {why}")))?;
+
+        let resolution = camera_format.resolution();
+        let time_base = frame_rate_to_time_base(camera_format.frame_rate())?;
+        let source_format = camera_format.format();
+
+        let container_accepts = frameformat_to_codec_id(source_format)
+            .map(|id| container_accepts_codec(&output, id))
+            .unwrap_or(false);
+
+        let mode = FfmpegSinkMode::negotiate(source_format, container_accepts).ok_or(
+            NokhwaError::ProcessFrameError {
+                src: source_format,
+                destination: "FFmpeg".to_string(),
+                error: "No passthrough or encode path for this format".to_string(),
+            },
+        )?;
+
+        let global_header = output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER);
+
+        let (decoder, encoder, scaler) = match mode {
+            FfmpegSinkMode::Passthrough(ff) => {
+                let mut stream = output
+                    .add_stream(encoder::find(CodecId::None))
+                    .map_err(|why| NokhwaError::StructureError {
+                        structure: "FFmpeg Stream".to_string(),
+                        error: why.to_string(),
+                    })?;
+                stream.set_parameters(passthrough_parameters(ff, resolution, &[]));
+                stream.set_time_base(time_base);
+                (None, None, None)
+            }
+            FfmpegSinkMode::Transcode(ff) => {
+                let decoder = open_decoder(ff)?;
+                let encoder = open_encoder(&mut output, resolution, time_base, global_header)?;
+                (Some(decoder), Some(encoder), None)
+            }
+            FfmpegSinkMode::Encode(ff) => {
+                let pixel = frameformat_to_pixel(ff).unwrap_or(Pixel::RGB24);
+                let encoder = open_encoder(&mut output, resolution, time_base, global_header)?;
+                let scaler = Scaler::get(
+                    pixel,
+                    resolution.width(),
+                    resolution.height(),
+                    Pixel::YUV420P,
+                    resolution.width(),
+                    resolution.height(),
+                    Flags::BILINEAR,
+                )
+                .map_err(|why| NokhwaError::StructureError {
+                    structure: "FFmpeg Scaler".to_string(),
+                    error: why.to_string(),
+                })?;
+                (None, Some(encoder), Some(scaler))
+            }
+        };
+
+        let mut sink = FfmpegSink {
+            output,
+            mode,
+            resolution,
+            time_base,
+            stream_time_base: time_base,
+            decoder,
+            encoder,
+            scaler,
+            frame_index: 0,
+            header_written: false,
+            finished: false,
+        };
+
+        // H264/H265 passthrough needs the parameter sets of the first keyframe in the header
+        if !matches!(
+            mode,
+            FfmpegSinkMode::Passthrough(FrameFormat::H264 | FrameFormat::Avc1 | FrameFormat::H265)
+        ) {
+            sink.write_header()?;
+        }
+
+        Ok(sink)
+    }
+
+    /// The [`FfmpegSinkMode`] that was negotiated for this sink.
+    #[must_use]
+    pub fn mode(&self) -> FfmpegSinkMode {
+        self.mode
+    }
+
+    /// The amount of frames consumed so far.
+    #[must_use]
+    pub fn frames_written(&self) -> i64 {
+        self.frame_index
+    }
+
+    /// Writes a single [`Buffer`] into the sink.
+    /// # Errors
+    /// If the buffer does not match the negotiated format, or `FFmpeg` fails to decode/encode/write, this will error.
+    pub fn consume(&mut self, buffer: &Buffer) -> Result<(), NokhwaError> {
+        if self.finished {
+            return Err(NokhwaError::GeneralError(
+                "FfmpegSink already finished".to_string(),
+            ));
+        }
+
+        if buffer.resolution() != self.resolution {
+            return Err(NokhwaError::ProcessFrameError {
+                src: buffer.source_frame_format(),
+                destination: "FFmpeg".to_string(),
+                error: format!(
+                    "Resolution changed mid-stream: {} -> {}",
+                    self.resolution,
+                    buffer.resolution()
+                ),
+            });
+        }
+
+        match self.mode {
+            FfmpegSinkMode::Passthrough(ff) => self.passthrough(ff, buffer),
+            FfmpegSinkMode::Transcode(ff) => {
+                let pts = self.next_pts();
+                let decoder = self.decoder.as_mut().ok_or(NokhwaError::UnitializedError)?;
+                decoder
+                    .send_packet(&Packet::copy(buffer.buffer()))
+                    .map_err(|why| NokhwaError::ProcessFrameError {
+                        src: ff,
+                        destination: "FFmpeg Decoder".to_string(),
+                        error: why.to_string(),
+                    })?;
+
+                let mut decoded = VideoFrame::empty();
+                while decoder.receive_frame(&mut decoded).is_ok() {
+                    let scaler = match &mut self.scaler {
+                        Some(scaler) => scaler,
+                        None => self.scaler.insert(
+                            Scaler::get(
+                                decoded.format(),
+                                decoded.width(),
+                                decoded.height(),
+                                Pixel::YUV420P,
+                                self.resolution.width(),
+                                self.resolution.height(),
+                                Flags::BILINEAR,
+                            )
+                            .map_err(|why| NokhwaError::StructureError {
+                                structure: "FFmpeg Scaler".to_string(),
+                                error: why.to_string(),
+                            })?,
+                        ),
+                    };
+                    let mut converted = VideoFrame::empty();
+                    scaler
+                        .run(&decoded, &mut converted)
+                        .map_err(|why| NokhwaError::ProcessFrameError {
+                            src: ff,
+                            destination: "YUV420P".to_string(),
+                            error: why.to_string(),
+                        })?;
+                    converted.set_pts(Some(pts));
+                    self.encode(Some(&converted))?;
+                }
+                Ok(())
+            }
+            FfmpegSinkMode::Encode(ff) => {
+                let pts = self.next_pts();
+                let pixel = frameformat_to_pixel(ff).unwrap_or(Pixel::RGB24);
+                let mut source =
+                    VideoFrame::new(pixel, self.resolution.width(), self.resolution.height());
+                copy_into_frame(buffer.buffer(), &mut source).map_err(|why| {
+                    NokhwaError::ProcessFrameError {
+                        src: ff,
+                        destination: "FFmpeg Frame".to_string(),
+                        error: why,
+                    }
+                })?;
+
+                let mut converted = VideoFrame::empty();
+                self.scaler
+                    .as_mut()
+                    .ok_or(NokhwaError::UnitializedError)?
+                    .run(&source, &mut converted)
+                    .map_err(|why| NokhwaError::ProcessFrameError {
+                        src: ff,
+                        destination: "YUV420P".to_string(),
+                        error: why.to_string(),
+                    })?;
+                converted.set_pts(Some(pts));
+                self.encode(Some(&converted))
+            }
+        }
+    }
+
+    /// Captures `frames` frames from `camera` and writes them into the sink. The stream will be opened if it is not already.
+    /// # Errors
+    /// If the camera fails to capture or the sink fails to write, this will error.
+    pub fn capture_from(
+        &mut self,
+        camera: &mut dyn CaptureTrait,
+        frames: usize,
+    ) -> Result<(), NokhwaError> {
+        if !camera.is_stream_open() {
+            camera.open_stream()?;
+        }
+
+        for _ in 0..frames {
+            let buffer = camera.frame()?;
+            self.consume(&buffer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the encoder and writes the container trailer.
+    /// # Errors
+    /// If `FFmpeg` fails to flush or write the trailer, this will error.
+    pub fn finish(&mut self) -> Result<(), NokhwaError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        // no keyframe ever arrived, still leave a valid (empty) container behind
+        if !self.header_written {
+            self.write_header()?;
+        }
+
+        if self.encoder.is_some() {
+            self.encode(None)?;
+        }
+
+        self.output
+            .write_trailer()
+            .map_err(|why| NokhwaError::StreamShutdownError(why.to_string()))
+    }
+
+    fn next_pts(&mut self) -> i64 {
+        let pts = self.frame_index;
+        self.frame_index += 1;
+        pts
+    }
+
+    fn write_header(&mut self) -> Result<(), NokhwaError> {
+        self.output
+            .write_header()
+            .map_err(|why| NokhwaError::OpenStreamError(format!("FFmpeg header: {why}")))?;
+        self.header_written = true;
+        self.stream_time_base = self
+            .output
+            .stream(0)
+            .map_or(self.time_base, |stream| stream.time_base());
+        Ok(())
+    }
+
+    fn passthrough(
+        &mut self,
+        frame_format: FrameFormat,
+        buffer: &Buffer,
+    ) -> Result<(), NokhwaError> {
+        let mut data = buffer.buffer().to_vec();
+        if frame_format == FrameFormat::Avc1
+            && !is_annex_b(&data)
+            && !length_prefixed_to_annex_b(&mut data)
+        {
+            return Err(NokhwaError::ProcessFrameError {
+                src: frame_format,
+                destination: "FFmpeg".to_string(),
+                error: "Malformed length prefixed NAL units".to_string(),
+            });
+        }
+
+        let (keyframe, parameter_sets) = match frame_format {
+            FrameFormat::H264 | FrameFormat::Avc1 | FrameFormat::H265 => {
+                scan_access_unit(frame_format, &data)
+            }
+            _ => (true, Vec::new()),
+        };
+
+        if !self.header_written {
+            // nothing before the first keyframe can be decoded
+            if !keyframe {
+                return Ok(());
+            }
+            if let Some(mut stream) = self.output.stream_mut(0) {
+                stream.set_parameters(passthrough_parameters(
+                    frame_format,
+                    self.resolution,
+                    &parameter_sets,
+                ));
+            }
+            self.write_header()?;
+        }
+
+        let pts = self.next_pts();
+        let mut packet = Packet::copy(&data);
+        if keyframe {
+            packet.set_flags(PacketFlags::KEY);
+        }
+        packet.set_stream(0);
+        packet.set_pts(Some(pts));
+        packet.set_dts(Some(pts));
+        packet.rescale_ts(self.time_base, self.stream_time_base);
+        packet
+            .write_interleaved(&mut self.output)
+            .map_err(|why| NokhwaError::GeneralError(why.to_string()))
+    }
+
+    fn encode(&mut self, frame: Option<&VideoFrame>) -> Result<(), NokhwaError> {
+        let encoder = self.encoder.as_mut().ok_or(NokhwaError::UnitializedError)?;
+
+        match frame {
+            Some(f) => encoder.send_frame(f),
+            None => encoder.send_eof(),
+        }
+        .map_err(|why| NokhwaError::GeneralError(format!("FFmpeg encoder: {why}")))?;
+
+        let mut packet = Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(self.time_base, self.stream_time_base);
+            packet
+                .write_interleaved(&mut self.output)
+                .map_err(|why| NokhwaError::GeneralError(why.to_string()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FrameSink for FfmpegSink {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        FfmpegSink::consume(self, &buffer)
+    }
+
+    fn finish(&mut self) -> Result<(), NokhwaError> {
+        FfmpegSink::finish(self)
+    }
+}
+
+impl Drop for FfmpegSink {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+fn open_decoder(frame_format: FrameFormat) -> Result<decoder::Video, NokhwaError> {
+    let codec_id = frameformat_to_codec_id(frame_format).unwrap_or(CodecId::None);
+    let codec = decoder::find(codec_id).ok_or(NokhwaError::StructureError {
+        structure: "FFmpeg Decoder".to_string(),
+        error: format!("No decoder for {frame_format}"),
+    })?;
+    codec::context::Context::new_with_codec(codec)
+        .decoder()
+        .video()
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "FFmpeg Decoder".to_string(),
+            error: why.to_string(),
+        })
+}
+
+fn open_encoder(
+    output: &mut Output,
+    resolution: Resolution,
+    time_base: Rational,
+    global_header: bool,
+) -> Result<encoder::Video, NokhwaError> {
+    let codec = encoder::find(CodecId::H264).ok_or(NokhwaError::StructureError {
+        structure: "FFmpeg Encoder".to_string(),
+        error: "No H264 encoder found".to_string(),
+    })?;
+
+    let mut stream = output
+        .add_stream(codec)
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "FFmpeg Stream".to_string(),
+            error: why.to_string(),
+        })?;
+
+    let mut video = codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "FFmpeg Encoder".to_string(),
+            error: why.to_string(),
+        })?;
+    video.set_width(resolution.width());
+    video.set_height(resolution.height());
+    video.set_format(Pixel::YUV420P);
+    video.set_time_base(time_base);
+    video.set_frame_rate(Some(time_base.invert()));
+    if global_header {
+        video.set_flags(codec::Flags::GLOBAL_HEADER);
+    }
+
+    let opened = video
+        .open_as(codec)
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "FFmpeg Encoder".to_string(),
+            error: why.to_string(),
+        })?;
+    stream.set_parameters(&opened);
+    stream.set_time_base(time_base);
+
+    Ok(opened)
+}
+
+fn container_accepts_codec(output: &Output, codec_id: CodecId) -> bool {
+    unsafe {
+        avformat_query_codec(
+            output.format().as_ptr(),
+            codec_id.into(),
+            FF_COMPLIANCE_NORMAL,
+        ) == 1
+    }
+}
+
+// copies a tightly packed buffer into a frame, respecting ffmpeg's line padding
+fn copy_into_frame(data: &[u8], frame: &mut VideoFrame) -> Result<(), String> {
+    let width = frame.width() as usize;
+    let row_bytes = match frame.format() {
+        Pixel::YUYV422 | Pixel::UYVY422 | Pixel::GRAY16LE => width * 2,
+        Pixel::RGB24 => width * 3,
+        Pixel::RGBA => width * 4,
+        // NV12/NV21's interleaved chroma plane has the same row length as luma
+        _ => width,
+    };
+
+    let mut offset = 0;
+    for plane in 0..frame.planes() {
+        let plane_height = frame.plane_height(plane) as usize;
+        let stride = frame.stride(plane);
+        let needed = row_bytes * plane_height;
+        if row_bytes > stride || data.len() < offset + needed {
+            return Err("Buffer size does not match resolution".to_string());
+        }
+
+        let plane_data = frame.data_mut(plane);
+        for (row, chunk) in data[offset..offset + needed]
+            .chunks_exact(row_bytes)
+            .enumerate()
+        {
+            plane_data[row * stride..row * stride + row_bytes].copy_from_slice(chunk);
+        }
+        offset += needed;
+    }
+    Ok(())
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn passthrough_parameters(
+    frame_format: FrameFormat,
+    resolution: Resolution,
+    extradata: &[u8],
+) -> codec::Parameters {
+    let mut parameters = codec::Parameters::new();
+    unsafe {
+        let raw = parameters.as_mut_ptr();
+        (*raw).codec_type = AVMediaType::AVMEDIA_TYPE_VIDEO;
+        (*raw).codec_id = frameformat_to_codec_id(frame_format)
+            .unwrap_or(CodecId::None)
+            .into();
+        (*raw).width = resolution.width() as i32;
+        (*raw).height = resolution.height() as i32;
+        if !extradata.is_empty() {
+            // libavcodec owns (and frees) extradata, which must be padded
+            let padded =
+                av_mallocz(extradata.len() + AV_INPUT_BUFFER_PADDING_SIZE as usize).cast::<u8>();
+            if !padded.is_null() {
+                std::ptr::copy_nonoverlapping(extradata.as_ptr(), padded, extradata.len());
+                (*raw).extradata = padded;
+                (*raw).extradata_size = extradata.len() as i32;
+            }
+        }
+    }
+    parameters
+}
+
+fn is_annex_b(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 1]) || data.starts_with(&[0, 0, 0, 1])
+}
+
+// AVCC uses 4 byte big endian lengths, which are swapped for 4 byte start codes in place
+fn length_prefixed_to_annex_b(data: &mut [u8]) -> bool {
+    let mut offset = 0;
+    while offset + 4 <= data.len() {
+        let length = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize;
+        data[offset..offset + 4].copy_from_slice(&[0, 0, 0, 1]);
+        offset += 4 + length;
+    }
+    offset == data.len()
+}
+
+// the NAL units of an Annex B access unit, without their start codes
+fn annex_b_nal_units(data: &[u8]) -> Vec<&[u8]> {
+    let mut starts = Vec::new();
+    let mut idx = 0;
+    while idx + 3 <= data.len() {
+        if data[idx..idx + 3] == [0, 0, 1] {
+            starts.push(idx + 3);
+            idx += 3;
+        } else {
+            idx += 1;
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let mut end = starts.get(n + 1).map_or(data.len(), |next| next - 3);
+            // the leading zero of a 4 byte start code
+            while end > start && data[end - 1] == 0 {
+                end -= 1;
+            }
+            &data[start..end]
+        })
+        .collect()
+}
+
+// returns whether the access unit is a keyframe (IDR/IRAP), and its parameter sets as Annex B
+fn scan_access_unit(frame_format: FrameFormat, data: &[u8]) -> (bool, Vec<u8>) {
+    let mut keyframe = false;
+    let mut parameter_sets = Vec::new();
+
+    for nal in annex_b_nal_units(data) {
+        let Some(&header) = nal.first() else {
+            continue;
+        };
+        let (is_key, is_parameter_set) = if frame_format == FrameFormat::H265 {
+            let nal_type = (header >> 1) & 0x3F;
+            ((16..=21).contains(&nal_type), (32..=34).contains(&nal_type))
+        } else {
+            let nal_type = header & 0x1F;
+            (nal_type == 5, nal_type == 7 || nal_type == 8)
+        };
+
+        keyframe |= is_key;
+        if is_parameter_set {
+            parameter_sets.extend_from_slice(&[0, 0, 0, 1]);
+            parameter_sets.extend_from_slice(nal);
+        }
+    }
+
+    (keyframe, parameter_sets)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn frame_rate_to_time_base(frame_rate: FrameRate) -> Result<Rational, NokhwaError> {
+    let time_base = match frame_rate {
+        FrameRate::Integer(fps) => Rational::new(1, fps as i32),
+        FrameRate::Fraction {
+            numerator,
+            denominator,
+        } => Rational::new(i32::from(denominator), i32::from(numerator)),
+        FrameRate::Float(fps) => Rational::new(1000, (fps * 1000_f32) as i32),
+    };
+
+    // also catches NaN and rates that round down to zero
+    if time_base.numerator() <= 0 || time_base.denominator() <= 0 {
+        return Err(NokhwaError::StructureError {
+            structure: "FFmpeg Time Base".to_string(),
+            error: format!("{frame_rate} can not be used as a time base"),
+        });
+    }
+    Ok(time_base)
+}
+
+fn frameformat_to_codec_id(frame_format: FrameFormat) -> Option<CodecId> {
+    match frame_format {
+        FrameFormat::MJpeg => Some(CodecId::MJPEG),
+        FrameFormat::H264 | FrameFormat::Avc1 => Some(CodecId::H264),
+        FrameFormat::H265 => Some(CodecId::HEVC),
+        _ => None,
+    }
+}
+
+fn frameformat_to_pixel(frame_format: FrameFormat) -> Option<Pixel> {
+    match frame_format {
+        FrameFormat::Yuv422 => Some(Pixel::YUYV422),
+        FrameFormat::Uyv422 => Some(Pixel::UYVY422),
+        FrameFormat::Nv12 => Some(Pixel::NV12),
+        FrameFormat::Nv21 => Some(Pixel::NV21),
+        FrameFormat::Luma8 => Some(Pixel::GRAY8),
+        FrameFormat::Luma16 => Some(Pixel::GRAY16LE),
+        FrameFormat::Rgb8 => Some(Pixel::RGB24),
+        FrameFormat::RgbA8 => Some(Pixel::RGBA),
+        _ => None,
+    }
+}
@@ -0,0 +1,340 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::{CaptureTrait, FrameSink, VirtualBackendTrait},
+};
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    path::PathBuf,
+};
+
+/// The largest payload a [`RawStreamSink`] puts in one UDP datagram, leaving room for the header.
+const MAX_DATAGRAM_PAYLOAD: usize = 60_000;
+
+/// Writes every frame to its own file in a directory, named `{prefix}{index:06}.{extension}`.
+///
+/// `MJPEG` frames are written as `.jpg`, `Luma8` as `.pgm` and `Rgb8` as `.ppm`, which image viewers open as-is. Every other
+/// format is written as its raw samples with a `.raw` extension. The padding at the end of the rows of `Luma8` and `Rgb8` frames
+/// is left out, as `.pgm` and `.ppm` rows are packed.
+#[derive(Clone, Debug)]
+pub struct ImageSequenceSink {
+    directory: PathBuf,
+    prefix: String,
+    next_index: u64,
+}
+
+impl ImageSequenceSink {
+    /// Creates a new [`ImageSequenceSink`] writing into `directory`, creating it if it does not exist.
+    /// # Errors
+    /// If the directory can not be created, this will error.
+    pub fn new(directory: impl Into<PathBuf>, prefix: &str) -> Result<Self, NokhwaError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|why| {
+            NokhwaError::GeneralError(format!("Failed to create {}: {why}", directory.display()))
+        })?;
+        Ok(ImageSequenceSink {
+            directory,
+            prefix: prefix.to_string(),
+            next_index: 0,
+        })
+    }
+
+    /// Get how many frames were written.
+    #[must_use]
+    pub fn frames_written(&self) -> u64 {
+        self.next_index
+    }
+}
+
+impl FrameSink for ImageSequenceSink {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        let (width, height) = (buffer.resolution().width(), buffer.resolution().height());
+        let (extension, header, channels) = match buffer.source_frame_format() {
+            FrameFormat::MJpeg => ("jpg", None, None),
+            FrameFormat::Luma8 => ("pgm", Some(format!("P5\n{width} {height}\n255\n")), Some(1)),
+            FrameFormat::Rgb8 => ("ppm", Some(format!("P6\n{width} {height}\n255\n")), Some(3)),
+            _ => ("raw", None, None),
+        };
+        let samples = match channels {
+            Some(channels) => packed_rows(&buffer, channels)?,
+            None => Cow::Borrowed(buffer.buffer()),
+        };
+        let path = self
+            .directory
+            .join(format!("{}{:06}.{extension}", self.prefix, self.next_index));
+
+        let write = || -> std::io::Result<()> {
+            let mut file = BufWriter::new(File::create(&path)?);
+            if let Some(header) = header {
+                file.write_all(header.as_bytes())?;
+            }
+            file.write_all(&samples)?;
+            file.flush()
+        };
+        write().map_err(|why| {
+            NokhwaError::GeneralError(format!("Failed to write {}: {why}", path.display()))
+        })?;
+
+        self.next_index += 1;
+        Ok(())
+    }
+}
+
+/// Gets the samples of `buffer`, which has `channels` bytes per pixel, without the padding at the end of its rows.
+fn packed_rows(buffer: &Buffer, channels: usize) -> Result<Cow<'_, [u8]>, NokhwaError> {
+    let process_error = |error: &str| NokhwaError::ProcessFrameError {
+        src: buffer.source_frame_format(),
+        destination: "Netpbm".to_string(),
+        error: error.to_string(),
+    };
+    let row = buffer.resolution().width() as usize * channels;
+    let height = buffer.resolution().height() as usize;
+    let stride = buffer.stride(0).unwrap_or(row);
+    if stride < row {
+        return Err(process_error("Invalid stride"));
+    }
+    if row == 0 || height == 0 {
+        return Ok(Cow::Borrowed(&[]));
+    }
+
+    // the last row does not need its padding
+    let samples = buffer
+        .buffer()
+        .get(..stride * (height - 1) + row)
+        .ok_or_else(|| process_error("Buffer too short"))?;
+    if stride == row {
+        return Ok(Cow::Borrowed(samples));
+    }
+    Ok(Cow::Owned(
+        samples
+            .chunks(stride)
+            .flat_map(|padded| &padded[..row])
+            .copied()
+            .collect(),
+    ))
+}
+
+/// Writes every frame to a virtual camera, see [`VirtualBackendTrait`].
+pub struct VirtualCameraSink<V: VirtualBackendTrait> {
+    camera: V,
+}
+
+impl<V: VirtualBackendTrait> VirtualCameraSink<V> {
+    /// Creates a new [`VirtualCameraSink`] writing to `camera`.
+    pub fn new(camera: V) -> Self {
+        VirtualCameraSink { camera }
+    }
+
+    /// Gets the virtual camera.
+    pub fn camera(&mut self) -> &mut V {
+        &mut self.camera
+    }
+
+    /// Gets the virtual camera back.
+    pub fn into_inner(self) -> V {
+        self.camera
+    }
+}
+
+impl<V: VirtualBackendTrait> FrameSink for VirtualCameraSink<V> {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        self.camera.write_frame(&buffer)
+    }
+}
+
+/// Where a [`RawStreamSink`] sends frames.
+#[derive(Debug)]
+enum RawStreamTarget {
+    Tcp(BufWriter<TcpStream>),
+    Udp(UdpSocket),
+}
+
+/// Streams the raw frames over TCP or UDP, e.g. to a process on another machine that does the heavy processing.
+///
+/// Every frame (over TCP) or part of a frame (over UDP) starts with a big endian header:
+/// - the magic `NKHW`
+/// - the frame's `u64` sequence number, counting from 0
+/// - the `u32` width and height
+/// - the `u32` length of the whole frame and the `u32` offset of this part in it, always 0 over TCP
+/// - the length of the [`FrameFormat`]'s name as a `u8`, followed by the name (e.g. `MJpeg`)
+///
+/// followed by the samples. Over UDP, frames are split into datagrams of at most 60000 samples that the receiver puts back
+/// together by their offset. Lost datagrams are not sent again.
+#[derive(Debug)]
+pub struct RawStreamSink {
+    target: RawStreamTarget,
+    sequence: u64,
+}
+
+impl RawStreamSink {
+    /// Connects to a TCP listener at `address`.
+    /// # Errors
+    /// If the connection fails, this will error.
+    pub fn tcp(address: impl ToSocketAddrs) -> Result<Self, NokhwaError> {
+        let stream = TcpStream::connect(address)
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        let _ = stream.set_nodelay(true);
+        Ok(RawStreamSink {
+            target: RawStreamTarget::Tcp(BufWriter::new(stream)),
+            sequence: 0,
+        })
+    }
+
+    /// Sends datagrams to `address`.
+    /// # Errors
+    /// If no local socket can be bound or `address` can not be resolved, this will error.
+    pub fn udp(address: impl ToSocketAddrs) -> Result<Self, NokhwaError> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))
+            .and_then(|socket| socket.connect(address).map(|()| socket))
+            .map_err(|why| NokhwaError::OpenStreamError(why.to_string()))?;
+        Ok(RawStreamSink {
+            target: RawStreamTarget::Udp(socket),
+            sequence: 0,
+        })
+    }
+
+    /// Get how many frames were sent.
+    #[must_use]
+    pub fn frames_sent(&self) -> u64 {
+        self.sequence
+    }
+}
+
+impl FrameSink for RawStreamSink {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        let samples = buffer.buffer();
+        let length = u32::try_from(samples.len())
+            .map_err(|_| NokhwaError::GeneralError("Frame is too large to stream".to_string()))?;
+        let header = |offset: usize| {
+            let format = buffer.source_frame_format().to_string();
+            let mut header = Vec::with_capacity(29 + format.len());
+            header.extend_from_slice(b"NKHW");
+            header.extend_from_slice(&self.sequence.to_be_bytes());
+            header.extend_from_slice(&buffer.resolution().width().to_be_bytes());
+            header.extend_from_slice(&buffer.resolution().height().to_be_bytes());
+            header.extend_from_slice(&length.to_be_bytes());
+            // offsets are below `length`, which fits
+            #[allow(clippy::cast_possible_truncation)]
+            header.extend_from_slice(&(offset as u32).to_be_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            header.push(format.len() as u8);
+            header.extend_from_slice(format.as_bytes());
+            header
+        };
+
+        let sent = match &mut self.target {
+            RawStreamTarget::Tcp(stream) => stream
+                .write_all(&header(0))
+                .and_then(|()| stream.write_all(samples))
+                .and_then(|()| stream.flush()),
+            RawStreamTarget::Udp(socket) => samples
+                .chunks(MAX_DATAGRAM_PAYLOAD)
+                .enumerate()
+                .try_for_each(|(index, chunk)| {
+                    let mut datagram = header(index * MAX_DATAGRAM_PAYLOAD);
+                    datagram.extend_from_slice(chunk);
+                    socket.send(&datagram).map(|_| ())
+                }),
+        };
+        sent.map_err(|why| NokhwaError::GeneralError(format!("Failed to send frame: {why}")))?;
+
+        self.sequence += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), NokhwaError> {
+        match &mut self.target {
+            RawStreamTarget::Tcp(stream) => stream
+                .flush()
+                .map_err(|why| NokhwaError::StreamShutdownError(why.to_string())),
+            RawStreamTarget::Udp(_) => Ok(()),
+        }
+    }
+}
+
+/// Captures frames from a camera and writes each of them to every attached [`FrameSink`].
+pub struct CaptureLoop {
+    camera: Box<dyn CaptureTrait>,
+    sinks: Vec<Box<dyn FrameSink>>,
+}
+
+impl CaptureLoop {
+    /// Creates a new [`CaptureLoop`] without sinks.
+    pub fn new(camera: impl Into<Box<dyn CaptureTrait>>) -> Self {
+        CaptureLoop {
+            camera: camera.into(),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Attaches a sink. Sinks get every frame in the order they were attached.
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl FrameSink + 'static) -> Self {
+        self.attach(sink);
+        self
+    }
+
+    /// Attaches a sink. Sinks get every frame in the order they were attached.
+    pub fn attach(&mut self, sink: impl FrameSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Gets the camera.
+    pub fn camera(&mut self) -> &mut dyn CaptureTrait {
+        self.camera.as_mut()
+    }
+
+    /// Captures one frame and writes it to every sink. The stream will be opened if it is not already.
+    /// # Errors
+    /// If the camera fails to capture or a sink fails to write, this will error. The sinks after the failing one do not get the frame.
+    pub fn step(&mut self) -> Result<(), NokhwaError> {
+        if !self.camera.is_stream_open() {
+            self.camera.open_stream()?;
+        }
+        let buffer = self.camera.frame()?;
+        // `Buffer`s share their samples, so every sink gets the frame without copying it
+        self.sinks
+            .iter_mut()
+            .try_for_each(|sink| sink.consume(buffer.clone()))
+    }
+
+    /// Captures `frames` frames, writing each to every sink, then finishes the sinks.
+    /// # Errors
+    /// If the camera fails to capture or a sink fails to write or finish, this will error.
+    pub fn run(&mut self, frames: usize) -> Result<(), NokhwaError> {
+        for _ in 0..frames {
+            self.step()?;
+        }
+        self.finish()
+    }
+
+    /// Finishes every sink, e.g. before dropping the loop.
+    /// # Errors
+    /// If a sink fails to finish, this will error after the other sinks were finished.
+    pub fn finish(&mut self) -> Result<(), NokhwaError> {
+        self.sinks
+            .iter_mut()
+            .map(|sink| sink.finish())
+            .fold(Ok(()), Result::and)
+    }
+}
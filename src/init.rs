@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use nokhwa_core::{error::NokhwaError, types::PermissionStatus};
+
 #[cfg(not(all(
     feature = "input-avfoundation",
     any(target_os = "macos", target_os = "ios")
@@ -36,68 +38,157 @@ fn init_avfoundation(callback: impl Fn(bool) + Send + Sync + 'static) {
     feature = "input-avfoundation",
     any(target_os = "macos", target_os = "ios")
 )))]
-fn status_avfoundation() -> bool {
-    true
+fn status_avfoundation() -> PermissionStatus {
+    PermissionStatus::Granted
 }
 
 #[cfg(all(
     feature = "input-avfoundation",
     any(target_os = "macos", target_os = "ios")
 ))]
-fn status_avfoundation() -> bool {
-    use nokhwa_bindings_macos::{current_authorization_status, AVAuthorizationStatus};
+fn status_avfoundation() -> PermissionStatus {
+    nokhwa_bindings_macos::authorization_status()
+}
 
-    matches!(
-        current_authorization_status(),
-        AVAuthorizationStatus::Authorized
-    )
+#[cfg(not(all(feature = "input-pipewire", target_os = "linux")))]
+fn init_pipewire(callback: impl Fn(bool) + Send + 'static) {
+    callback(true);
 }
 
-#[cfg(feature = "input-jscam")]
-pub async fn request_permission() -> Result<(), NokhwaError> {
-    let window: Window = window()?;
-    let navigator = window.navigator();
-    let media_devices = media_devices(&navigator)?;
-
-    match media_devices.get_user_media_with_constraints(
-        MediaStreamConstraints::new()
-            .video(&JsValue::from_bool(true))
-            .audio(&JsValue::from_bool(false)),
-    ) {
-        Ok(promise) => {
-            let js_future = JsFuture::from(promise);
-            match js_future.await {
-                Ok(stream) => {
-                    let media_stream = MediaStream::from(stream);
-                    media_stream
-                        .get_tracks()
-                        .iter()
-                        .for_each(|track| MediaStreamTrack::from(track).stop());
-                    Ok(())
-                }
-                Err(why) => Err(NokhwaError::OpenStreamError(format!("{why:?}"))),
-            }
-        }
-        Err(why) => Err(NokhwaError::StructureError {
-            structure: "UserMediaPermission".to_string(),
-            error: format!("{why:?}"),
-        }),
+#[cfg(all(feature = "input-pipewire", target_os = "linux"))]
+fn init_pipewire(callback: impl Fn(bool) + Send + Sync + 'static) {
+    use crate::backends::capture::request_pipewire_access;
+
+    // the portal dialog can take as long as the user wants, do not block the caller on it
+    std::thread::spawn(move || {
+        callback(futures_lite::future::block_on(request_pipewire_access()).is_ok());
+    });
+}
+
+#[cfg(not(all(feature = "input-pipewire", target_os = "linux")))]
+fn status_pipewire() -> PermissionStatus {
+    PermissionStatus::Granted
+}
+
+#[cfg(all(feature = "input-pipewire", target_os = "linux"))]
+fn status_pipewire() -> PermissionStatus {
+    // the portal only answers by asking, so an earlier request is all there is to go by
+    if crate::backends::capture::pipewire_access_granted() {
+        PermissionStatus::Granted
+    } else {
+        PermissionStatus::NotDetermined
     }
 }
 
+#[cfg(not(all(feature = "input-android", target_os = "android")))]
+fn init_android(callback: impl Fn(bool) + Send + 'static) {
+    callback(true);
+}
+
+#[cfg(all(feature = "input-android", target_os = "android"))]
+fn init_android(callback: impl Fn(bool) + Send + Sync + 'static) {
+    use crate::backends::capture::request_android_permission_blocking;
+
+    // the dialog is shown on the UI thread, which may well be the caller
+    std::thread::spawn(move || {
+        callback(request_android_permission_blocking().is_ok());
+    });
+}
+
+#[cfg(not(all(feature = "input-android", target_os = "android")))]
+fn status_android() -> PermissionStatus {
+    PermissionStatus::Granted
+}
+
+#[cfg(all(feature = "input-android", target_os = "android"))]
+fn status_android() -> PermissionStatus {
+    crate::backends::capture::android_permission_status()
+}
+
 #[cfg(not(feature = "input-jscam"))]
+fn status_browser() -> PermissionStatus {
+    PermissionStatus::Granted
+}
+
+#[cfg(feature = "input-jscam")]
+fn status_browser() -> PermissionStatus {
+    crate::js_camera::js_permission_status()
+}
+
+/// Asks the user for access to the cameras, showing the system's (or browser's) prompt if they have not decided yet.
+///
+/// In the browser, camera labels are hidden until access is granted, so [`query()`](crate::query) only has useful names after this.
+/// # Errors
+/// This errors if the user denied access, or the backend could not ask.
+#[cfg(feature = "input-jscam")]
+pub async fn request_permission() -> Result<(), NokhwaError> {
+    crate::js_camera::request_permission().await
+}
+
+/// Asks the browser whether the page may use the cameras through the Permissions API, without showing a prompt, and updates
+/// [`permission_status()`] with the answer. It keeps following the permission afterwards.
+/// # Errors
+/// This errors if the browser can not query the `camera` permission (e.g. it has no Permissions API).
+#[cfg(feature = "input-jscam")]
+pub async fn query_permission() -> Result<PermissionStatus, NokhwaError> {
+    crate::js_camera::query_js_permission().await?;
+    Ok(permission_status())
+}
+
+#[cfg(all(
+    feature = "input-pipewire",
+    target_os = "linux",
+    not(feature = "input-jscam")
+))]
+pub async fn request_permission() -> Result<(), NokhwaError> {
+    crate::backends::capture::request_pipewire_access().await
+}
+
+#[cfg(all(
+    feature = "input-avfoundation",
+    any(target_os = "macos", target_os = "ios"),
+    not(feature = "input-jscam")
+))]
+pub async fn request_permission() -> Result<(), NokhwaError> {
+    nokhwa_bindings_macos::request_access().await
+}
+
+#[cfg(all(
+    feature = "input-android",
+    target_os = "android",
+    not(feature = "input-jscam")
+))]
+pub async fn request_permission() -> Result<(), NokhwaError> {
+    crate::backends::capture::request_android_permission().await
+}
+
+#[cfg(not(any(
+    feature = "input-jscam",
+    all(feature = "input-android", target_os = "android"),
+    all(feature = "input-pipewire", target_os = "linux"),
+    all(
+        feature = "input-avfoundation",
+        any(target_os = "macos", target_os = "ios")
+    )
+)))]
 pub async fn request_permission() -> Result<(), NokhwaError> {
     Ok(())
 }
 
 // todo: make this work on browser code
 /// Initialize `nokhwa`
-/// It is your responsibility to call this function before anything else, but only on `MacOS`.
+/// It is your responsibility to call this function before anything else, but only on `MacOS`, on Android, or on Linux with the `PipeWire` backend.
 ///
 /// The `on_complete` is called after initialization (a.k.a User granted permission). The callback's argument
 /// is weather the initialization was successful or not
 pub fn nokhwa_initialize_callback(on_complete: impl Fn(bool) + Send + Sync + 'static) {
-    init_avfoundation(on_complete);
+    if cfg!(all(feature = "input-pipewire", target_os = "linux")) {
+        init_pipewire(on_complete);
+    } else if cfg!(all(feature = "input-android", target_os = "android")) {
+        init_android(on_complete);
+    } else {
+        init_avfoundation(on_complete);
+    }
     // TODO: implement initialization
 }
 
@@ -105,5 +196,33 @@ pub fn nokhwa_initialize_callback(on_complete: impl Fn(bool) + Send + Sync + 'st
 /// True if the initialization is successful (ready-to-use)
 #[must_use]
 pub fn nokhwa_check() -> bool {
-    status_avfoundation()
+    permission_status().is_granted()
+}
+
+/// Gets whether `nokhwa` may use the cameras, without asking the user, e.g. to explain why a prompt is coming before calling
+/// [`request_permission()`], or to point the user at the system's settings when it is [`PermissionStatus::Denied`].
+///
+/// [`PermissionStatus::Restricted`] means access is blocked by a policy (e.g. an MDM profile on `MacOS`), so there is no point in asking.
+/// With the `PipeWire` backend this is [`PermissionStatus::NotDetermined`] until [`request_permission()`] or
+/// [`nokhwa_initialize_callback()`] got access, as the portal can not be asked without showing a prompt.
+///
+/// On Android this is [`PermissionStatus::Denied`] if the `CAMERA` permission is still not granted after it was requested, as only the
+/// `Activity` is told how the user answered.
+///
+/// In the browser this is [`PermissionStatus::NotDetermined`] until [`query_permission()`], [`request_permission()`] or [`query()`](crate::query)
+/// found out. [`query_permission()`] does not show a prompt, so call it first to pick the UI to show.
+/// Listing the cameras there takes two phases: they are first enumerated without a prompt, and if they have no labels
+/// (access was not granted before) permission is requested and they are enumerated again, now with labels.
+#[must_use]
+pub fn permission_status() -> PermissionStatus {
+    match status_avfoundation() {
+        PermissionStatus::Granted => match status_pipewire() {
+            PermissionStatus::Granted => match status_android() {
+                PermissionStatus::Granted => status_browser(),
+                status => status,
+            },
+            status => status,
+        },
+        status => status,
+    }
 }
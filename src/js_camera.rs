@@ -19,25 +19,60 @@
 //! Anything starting with `js` is meant as a binding, a.k.a. not meant for consumption.
 //!
 //! This assumes that you are running a modern browser on the desktop.
+//!
+//! [`JSCamera`] implements [`AsyncCaptureTrait`], so async code reading cameras runs unchanged on native and web targets.
+//!
+//! To process frames without blocking the page, hand the camera to a dedicated worker with [`JSCamera::transfer_to_worker()`] and
+//! read it there with [`JSWorkerCamera`].
+//!
+//! To use nokhwa as a video effects layer, [`JSCamera::processed_track()`] runs the camera's frames through a function and emits them
+//! as a new `MediaStreamTrack`, e.g. for WebRTC.
+//!
+//! Frames can be put on the GPU without reading them back into WASM memory, in a WebGL texture with `frame_to_webgl_texture()`, or
+//! as a WebGPU external texture ([`JSExternalTexture`]) with `frame_external_texture()`.
+//!
+//! [`JSRecorder`] records a camera into a file, encoded by the browser's `MediaRecorder`.
+//!
+//! The web APIs used differ between browsers, [`BrowserCapabilities::detect()`] tells which ones this browser has.
+//!
+//! With the `output-webcodecs` feature, frames can also be handed out as WebCodecs `VideoFrame`s, which skips reading them back
+//! into WASM memory. `web-sys` only has `VideoFrame` with `RUSTFLAGS="--cfg=web_sys_unstable_apis"`.
 
 use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbImage, Rgba};
-use js_sys::{Array, JsString, Map, Object, Promise};
+use js_sys::{Array, Function, JsString, Map, Object, Promise, Reflect, Uint8Array};
 use nokhwa_core::{
+    buffer::{Buffer, FrameOrientation, FrameTimestamp, TimestampClock},
     error::NokhwaError,
-    types::{CameraIndex, CameraInfo, Resolution},
+    format_request::FormatRequest,
+    frame_format::FrameFormat,
+    traits::{AsyncCaptureTrait, CaptureTrait, DeviceWatcher},
+    types::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
+        ControlValueSetter, DeviceEvent, DeviceKind, FlashMode, FrameRate, KnownCameraControl,
+        KnownCameraControlFlag, PermissionStatus, PhotoCodec, PhotoSettings, Range, Resolution,
+    },
 };
 use std::{
     borrow::{Borrow, Cow},
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
     convert::TryFrom,
     fmt::{Debug, Display, Formatter},
     ops::Deref,
+    rc::Rc,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+    time::Duration,
 };
-use wasm_bindgen::{JsCast, JsValue};
-use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+#[cfg(feature = "output-webcodecs")]
+use web_sys::VideoFrame;
 use web_sys::{
     console::log_1, CanvasRenderingContext2d, Document, Element, HtmlCanvasElement,
     HtmlVideoElement, ImageData, MediaDeviceInfo, MediaDeviceKind, MediaDevices, MediaStream,
-    MediaStreamConstraints, MediaStreamTrack, MediaStreamTrackState, Navigator, Node, Window,
+    MediaStreamConstraints, MediaStreamTrack, MediaStreamTrackState, MediaTrackConstraints,
+    Navigator, Node, OffscreenCanvas, PermissionState, WebGl2RenderingContext, WebGlTexture,
+    Window, Worker,
 };
 #[cfg(feature = "output-wgpu")]
 use wgpu::{
@@ -56,30 +91,6 @@ macro_rules! jsv {
     }};
 }
 
-macro_rules! obj {
-    ($(($key:expr, $value:expr)),+ ) => {{
-        use js_sys::{Map, Object};
-        use wasm_bindgen::JsValue;
-
-        let map = Map::new();
-        $(
-            map.set(&jsv!($key), &jsv!($value));
-        )+
-        Object::from(map)
-    }};
-    ($object:expr, $(($key:expr, $value:expr)),+ ) => {{
-        use js_sys::{Map, Object};
-        use wasm_bindgen::JsValue;
-
-        let map = Map::new();
-        $(
-            map.set(&jsv!($key), &jsv!($value));
-        )+
-        let o = Object::from(map);
-        Object::assign(&$object, &o)
-    }};
-}
-
 fn window() -> Result<Window, NokhwaError> {
     match web_sys::window() {
         Some(win) => Ok(win),
@@ -193,10 +204,403 @@ fn set_autoplay_inline(element: &Element) -> Result<(), NokhwaError> {
     Ok(())
 }
 
+fn js_get(target: &JsValue, key: &str) -> Result<JsValue, NokhwaError> {
+    Reflect::get(target, &jsv!(key)).map_err(|why| NokhwaError::GetPropertyError {
+        property: key.to_string(),
+        error: format!("{why:?}"),
+    })
+}
+
+fn js_call(target: &JsValue, method: &str, args: &Array) -> Result<JsValue, NokhwaError> {
+    let function = js_get(target, method)?
+        .dyn_into::<Function>()
+        .map_err(|_| NokhwaError::GetPropertyError {
+            property: method.to_string(),
+            error: "Not a function".to_string(),
+        })?;
+    function
+        .apply(target, args)
+        .map_err(|why| NokhwaError::StructureError {
+            structure: method.to_string(),
+            error: format!("{why:?}"),
+        })
+}
+
+async fn js_await(promise: JsValue, name: &str) -> Result<JsValue, NokhwaError> {
+    JsFuture::from(Promise::from(promise))
+        .await
+        .map_err(|why| NokhwaError::StructureError {
+            structure: name.to_string(),
+            error: format!("{why:?}"),
+        })
+}
+
+// a plain object, which is what the web APIs take as dictionaries
+fn js_object(entries: &[(&str, &JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in entries {
+        let _ = Reflect::set(&object, &jsv!(*key), value);
+    }
+    object
+}
+
+/// Returns `true` if the browser has `MediaStreamTrackProcessor`, which [`JSCamera::frame_raw_async()`] reads frames with. Outside of
+/// workers, only Chromium based browsers have it.
+#[must_use]
+pub fn track_processor_supported() -> bool {
+    Reflect::has(&js_sys::global(), &jsv!("MediaStreamTrackProcessor")).unwrap_or(false)
+}
+
+/// Returns `true` if the browser has `MediaStreamTrackGenerator`, which [`JSCamera::processed_track()`] emits frames with. Like
+/// `MediaStreamTrackProcessor`, only Chromium based browsers have it.
+#[must_use]
+pub fn track_generator_supported() -> bool {
+    Reflect::has(&js_sys::global(), &jsv!("MediaStreamTrackGenerator")).unwrap_or(false)
+}
+
+// describes the device the video of `stream` comes from, like `enumerate_js_cameras()` would
+fn stream_camera_info(stream: &MediaStream) -> CameraInfo {
+    let track = stream.get_video_tracks().get(0);
+    if track.is_undefined() {
+        return CameraInfo::new("Null Stream", "", "", CameraIndex::String(String::new()));
+    }
+    let track = MediaStreamTrack::from(track);
+    let settings =
+        js_call(&track, "getSettings", &Array::new()).unwrap_or_else(|_| Object::new().into());
+    let setting = |key: &str| {
+        js_get(&settings, key)
+            .ok()
+            .and_then(|value| value.as_string())
+    };
+    // only captures of the screen have a display surface
+    if setting("displaySurface").is_some() {
+        return screen_pseudo_device();
+    }
+    let id = format!(
+        "{} {}",
+        setting("groupId").unwrap_or_default(),
+        setting("deviceId").unwrap_or_default()
+    );
+    CameraInfo::new(
+        &track.label(),
+        &format!("{:?}", MediaDeviceKind::Videoinput),
+        &id,
+        CameraIndex::String(id.clone()),
+    )
+}
+
+/// Gets the time of the browser's monotonic clock, [`performance.now()`](https://developer.mozilla.org/en-US/docs/Web/API/Performance/now),
+/// which the [`Buffer`] timestamps of the browser backend are on. Subtract a frame's timestamp from it to measure the frame's latency.
+///
+/// Every worker has its own clock, so compare it with timestamps of frames read in the same worker.
+#[must_use]
+pub fn performance_now() -> Duration {
+    js_get(&js_sys::global(), "performance")
+        .and_then(|performance| js_call(&performance, "now", &Array::new()))
+        .ok()
+        .and_then(|now| now.as_f64())
+        .map_or(Duration::ZERO, |millis| {
+            Duration::from_secs_f64(millis.max(0_f64) / 1000_f64)
+        })
+}
+
+// puts `VideoFrame.timestamp`s, microseconds from an unspecified start, on `performance_now()`. The first frame anchors the two clocks,
+// so later frames keep their spacing.
+#[derive(Copy, Clone, Debug, Default)]
+struct FrameClock {
+    offset_micros: Option<f64>,
+}
+
+impl FrameClock {
+    fn timestamp(&mut self, video_frame: &JsValue) -> Option<FrameTimestamp> {
+        let micros = js_get(video_frame, "timestamp").ok()?.as_f64()?;
+        let now_micros = performance_now().as_secs_f64() * 1_000_000_f64;
+        let offset_micros = *self.offset_micros.get_or_insert(now_micros - micros);
+        Some(FrameTimestamp::new(
+            Duration::from_secs_f64(((micros + offset_micros) / 1_000_000_f64).max(0_f64)),
+            TimestampClock::Monotonic,
+        ))
+    }
+}
+
+// copies the visible part of a `VideoFrame` as RGBA, which the browser converts to if the frame is in another format
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+async fn copy_video_frame(video_frame: &JsValue) -> Result<(Vec<u8>, Resolution), NokhwaError> {
+    let visible_rect = js_get(video_frame, "visibleRect")?;
+    let resolution = Resolution::new(
+        js_get(&visible_rect, "width")?.as_f64().unwrap_or(0_f64) as u32,
+        js_get(&visible_rect, "height")?.as_f64().unwrap_or(0_f64) as u32,
+    );
+    let options = js_object(&[("format", &jsv!("RGBA"))]);
+    let size = js_call(video_frame, "allocationSize", &Array::of1(&options))?
+        .as_f64()
+        .unwrap_or(0_f64) as usize;
+    let mut data = vec![0_u8; size];
+    // SAFETY: `data` outlives the copy. Should the WASM memory grow meanwhile, the view is detached and the copy fails instead.
+    let view = unsafe { Uint8Array::view_mut_raw(data.as_mut_ptr(), size) };
+    js_await(
+        js_call(video_frame, "copyTo", &Array::of2(&view, &options))?,
+        "VideoFrame copyTo",
+    )
+    .await?;
+    Ok((data, resolution))
+}
+
+// whether the screen is in a landscape orientation, and how far it is rotated from its natural orientation, in degrees
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn screen_orientation() -> Option<(bool, u16)> {
+    let orientation = js_get(&js_get(&js_sys::global(), "screen").ok()?, "orientation").ok()?;
+    let landscape = js_get(&orientation, "type")
+        .ok()?
+        .as_string()?
+        .starts_with("landscape");
+    let angle = js_get(&orientation, "angle").ok()?.as_f64()? as u16;
+    Some((landscape, angle))
+}
+
+// rotates tightly packed RGBA pixels clockwise by a multiple of 90 degrees
+fn rotate_rgba(data: &[u8], resolution: Resolution, rotation: u16) -> (Vec<u8>, Resolution) {
+    let (width, height) = (resolution.width() as usize, resolution.height() as usize);
+    let rotated_resolution = match rotation {
+        90 | 270 => Resolution::new(resolution.height(), resolution.width()),
+        180 => resolution,
+        _ => return (data.to_vec(), resolution),
+    };
+    let mut rotated = vec![0_u8; data.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let (to_x, to_y, to_width) = match rotation {
+                90 => (height - 1 - y, x, height),
+                180 => (width - 1 - x, height - 1 - y, width),
+                _ => (y, width - 1 - x, height),
+            };
+            let from = (y * width + x) * 4;
+            let to = (to_y * to_width + to_x) * 4;
+            if let (Some(pixel), Some(target)) =
+                (data.get(from..from + 4), rotated.get_mut(to..to + 4))
+            {
+                target.copy_from_slice(pixel);
+            }
+        }
+    }
+    (rotated, rotated_resolution)
+}
+
+// a reader of the `VideoFrame`s of `track`, read from where `MediaStreamTrackProcessor` is available (a worker, or Chromium's main thread)
+fn track_processor_readable(track: &JsValue) -> Result<JsValue, NokhwaError> {
+    let processor_class = js_get(&js_sys::global(), "MediaStreamTrackProcessor")?
+        .dyn_into::<Function>()
+        .map_err(|_| NokhwaError::StructureError {
+            structure: "MediaStreamTrackProcessor".to_string(),
+            error: "Not a constructor".to_string(),
+        })?;
+    let processor = Reflect::construct(
+        &processor_class,
+        &Array::of1(&js_object(&[("track", track)])),
+    )
+    .map_err(|why| NokhwaError::StructureError {
+        structure: "MediaStreamTrackProcessor".to_string(),
+        error: format!("{why:?}"),
+    })?;
+    js_get(&processor, "readable")
+}
+
+// the next `VideoFrame`, or `None` once the track has ended. The frame must be closed after use.
+async fn read_video_frame(reader: &JsValue) -> Result<Option<JsValue>, NokhwaError> {
+    let read = js_await(
+        js_call(reader, "read", &Array::new())?,
+        "ReadableStreamDefaultReader read",
+    )
+    .await?;
+    if js_get(&read, "done")?.as_bool() == Some(true) {
+        return Ok(None);
+    }
+    js_get(&read, "value").map(Some)
+}
+
+// copies `video_frame` out, runs it through `process`, and writes the result to `writer` as a new `VideoFrame` with the same timestamp
+async fn process_video_frame(
+    video_frame: &JsValue,
+    writer: &JsValue,
+    process: &mut impl FnMut(Buffer) -> Result<Buffer, NokhwaError>,
+    clock: &mut FrameClock,
+) -> Result<(), NokhwaError> {
+    let timestamp = js_get(video_frame, "timestamp");
+    let frame_timestamp = clock.timestamp(video_frame);
+    let copied = copy_video_frame(video_frame).await;
+    let _ = js_call(video_frame, "close", &Array::new());
+    let (timestamp, (data, resolution)) = (timestamp?, copied?);
+
+    let mut buffer = Buffer::new(resolution, &data, FrameFormat::RgbA8);
+    if let Some(frame_timestamp) = frame_timestamp {
+        buffer = buffer.with_timestamp(frame_timestamp);
+    }
+    let processed = process(buffer)?;
+    if processed.source_frame_format() != FrameFormat::RgbA8 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: processed.source_frame_format(),
+            destination: "VideoFrame".to_string(),
+            error: "Processed frames must be RgbA8".to_string(),
+        });
+    }
+
+    let video_frame_class = js_get(&js_sys::global(), "VideoFrame")?
+        .dyn_into::<Function>()
+        .map_err(|_| NokhwaError::StructureError {
+            structure: "VideoFrame".to_string(),
+            error: "Not a constructor".to_string(),
+        })?;
+    let init = js_object(&[
+        ("format", &jsv!("RGBA")),
+        ("codedWidth", &jsv!(processed.resolution().width())),
+        ("codedHeight", &jsv!(processed.resolution().height())),
+        ("timestamp", &timestamp),
+    ]);
+    let output = Reflect::construct(
+        &video_frame_class,
+        &Array::of2(&Uint8Array::from(processed.buffer()), &init),
+    )
+    .map_err(|why| NokhwaError::ProcessFrameError {
+        src: FrameFormat::RgbA8,
+        destination: "VideoFrame".to_string(),
+        error: format!("{why:?}"),
+    })?;
+    // the generator closes the frames it is written
+    js_await(
+        js_call(writer, "write", &Array::of1(&output))?,
+        "WritableStreamDefaultWriter write",
+    )
+    .await
+    .map(drop)
+}
+
+/// Returns `true` if the browser has [`requestVideoFrameCallback()`](https://developer.mozilla.org/en-US/docs/Web/API/HTMLVideoElement/requestVideoFrameCallback),
+/// which paces [`JSCamera::next_frame()`].
+#[must_use]
+pub fn video_frame_callback_supported() -> bool {
+    js_get(&js_sys::global(), "HTMLVideoElement")
+        .and_then(|class| js_get(&class, "prototype"))
+        .map(|prototype| {
+            Reflect::has(&prototype, &jsv!("requestVideoFrameCallback")).unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+// waits until the browser presents the next frame of `video`, returning that frame's `VideoFrameCallbackMetadata`
+async fn next_presented_frame(video: &HtmlVideoElement) -> Result<JsValue, NokhwaError> {
+    let mut requested = Ok(());
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let callback = Closure::once_into_js(move |_now: JsValue, metadata: JsValue| {
+            let _ = resolve.call1(&JsValue::NULL, &metadata);
+        });
+        requested = js_call(video, "requestVideoFrameCallback", &Array::of1(&callback)).map(drop);
+    });
+    requested?;
+    js_await(promise.into(), "requestVideoFrameCallback").await
+}
+
+// what the last permission request or enumeration showed, as the browser can only be asked without a prompt asynchronously
+static PERMISSION_STATUS: AtomicU8 = AtomicU8::new(PERMISSION_NOT_DETERMINED);
+const PERMISSION_NOT_DETERMINED: u8 = 0;
+const PERMISSION_DENIED: u8 = 1;
+const PERMISSION_RESTRICTED: u8 = 2;
+const PERMISSION_GRANTED: u8 = 3;
+
+fn set_permission_status(status: u8) {
+    PERMISSION_STATUS.store(status, Ordering::SeqCst);
+}
+
+/// Gets whether the page may use the cameras, as far as nokhwa has seen. This is [`PermissionStatus::NotDetermined`] until
+/// [`query_js_permission()`], [`request_permission()`], [`enumerate_js_cameras()`] or [`query_js_cameras()`] found out, as the
+/// browser can not be asked without waiting on it.
+///
+/// [`PermissionStatus::Restricted`] means a [permissions policy](https://developer.mozilla.org/en-US/docs/Web/HTTP/Permissions_Policy)
+/// (e.g. of the `<iframe>` the page is in) blocks the camera, or the page is not served over HTTPS.
+#[must_use]
+pub fn js_permission_status() -> PermissionStatus {
+    match PERMISSION_STATUS.load(Ordering::SeqCst) {
+        PERMISSION_DENIED => PermissionStatus::Denied,
+        PERMISSION_RESTRICTED => PermissionStatus::Restricted,
+        PERMISSION_GRANTED => PermissionStatus::Granted,
+        _ => PermissionStatus::NotDetermined,
+    }
+}
+
+static WATCHING_PERMISSION: AtomicBool = AtomicBool::new(false);
+
+fn set_permission_state(state: PermissionState) {
+    set_permission_status(match state {
+        PermissionState::Granted => PERMISSION_GRANTED,
+        PermissionState::Denied => PERMISSION_DENIED,
+        _ => PERMISSION_NOT_DETERMINED,
+    });
+}
+
+/// Asks the browser whether the page may use the cameras with [`Permissions::query()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.Permissions.html#method.query) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/Permissions/query),
+/// which never shows a prompt. A `prompt` state is [`PermissionStatus::NotDetermined`].
+///
+/// After this, [`js_permission_status()`] (and [`permission_status()`](crate::permission_status)) follow the user changing the
+/// permission in the browser's settings.
+/// # Errors
+/// This will error if there is no valid web context, or the browser does not have the Permissions API or can not query the `camera` permission.
+pub async fn query_js_permission() -> Result<PermissionStatus, NokhwaError> {
+    let window: Window = window()?;
+    let permissions =
+        window
+            .navigator()
+            .permissions()
+            .map_err(|why| NokhwaError::StructureError {
+                structure: "Permissions".to_string(),
+                error: format!("{why:?}"),
+            })?;
+    let promise = permissions
+        .query(&js_object(&[("name", &jsv!("camera"))]))
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "PermissionsQuery".to_string(),
+            error: format!("{why:?}"),
+        })?;
+    let status =
+        web_sys::PermissionStatus::from(js_await(promise.into(), "PermissionsQuery").await?);
+    set_permission_state(status.state());
+
+    if !WATCHING_PERMISSION.swap(true, Ordering::SeqCst) {
+        let watched = status.clone();
+        let on_change = Closure::<dyn FnMut()>::new(move || set_permission_state(watched.state()));
+        status.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        // lives as long as the page, like the permission it watches
+        on_change.forget();
+    }
+    Ok(js_permission_status())
+}
+
+/// Asks the browser whether the page may use the cameras, without showing a prompt. See [`query_js_permission()`].
+/// # Errors
+/// This will error if there is no valid web context or the Permissions API can not query the `camera` permission.
+/// # JS-WASM
+/// This is exported as `queryPermission`, and resolves to `true` if access is granted. It may throw an exception.
+#[cfg(feature = "output-wasm")]
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = queryPermission))]
+pub async fn js_query_js_permission() -> Result<bool, JsValue> {
+    match query_js_permission().await {
+        Ok(status) => Ok(status.is_granted()),
+        Err(why) => Err(JsValue::from(why.to_string())),
+    }
+}
+
 /// Requests Webcam permissions from the browser using [`MediaDevices::get_user_media()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.get_user_media) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getUserMedia)
+///
+/// The browser shows its prompt if the user has not decided yet. Once this returns, [`enumerate_js_cameras()`] has the labels of the cameras.
+/// The permission is looked up with [`query_js_permission()`] first, so a denied permission fails without calling `getUserMedia()`.
 /// # Errors
-/// This will error if there is no valid web context or the web API is not supported
+/// This will error if there is no valid web context, the web API is not supported, or the user denied access.
 pub async fn request_permission() -> Result<(), NokhwaError> {
+    // browsers without the Permissions API (or the `camera` permission in it) are asked through `getUserMedia()` directly
+    if let Ok(PermissionStatus::Denied) = query_js_permission().await {
+        return Err(NokhwaError::OpenStreamError(
+            "Camera permission denied".to_string(),
+        ));
+    }
+
     let window: Window = window()?;
     let navigator = window.navigator();
     let media_devices = media_devices(&navigator)?;
@@ -215,9 +619,22 @@ pub async fn request_permission() -> Result<(), NokhwaError> {
                         .get_tracks()
                         .iter()
                         .for_each(|track| MediaStreamTrack::from(track).stop());
+                    set_permission_status(PERMISSION_GRANTED);
                     Ok(())
                 }
-                Err(why) => Err(NokhwaError::OpenStreamError(format!("{why:?}"))),
+                Err(why) => {
+                    // `getUserMedia()` rejects with a `DOMException`, whose name tells a refusal apart from a missing camera
+                    match js_get(&why, "name")
+                        .ok()
+                        .and_then(|name| name.as_string())
+                        .as_deref()
+                    {
+                        Some("NotAllowedError") => set_permission_status(PERMISSION_DENIED),
+                        Some("SecurityError") => set_permission_status(PERMISSION_RESTRICTED),
+                        _ => {}
+                    }
+                    Err(NokhwaError::OpenStreamError(format!("{why:?}")))
+                }
             }
         }
         Err(why) => Err(NokhwaError::StructureError {
@@ -241,111 +658,131 @@ pub async fn js_request_permission() -> Result<(), JsValue> {
     Ok(())
 }
 
-/// Queries Cameras using [`MediaDevices::enumerate_devices()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.enumerate_devices) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/enumerateDevices)
+/// The [`CameraIndex`] of the screen, which [`enumerate_js_cameras()`] lists as a pseudo-device with [`DeviceKind::Screen`] where
+/// [`screen_capture_supported()`].
+pub const SCREEN_INDEX: &str = "screen";
+
+/// Returns `true` if the browser has [`getDisplayMedia()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getDisplayMedia),
+/// which [`JSCamera::new_screen()`] captures the screen with. Mobile browsers do not have it.
+#[must_use]
+pub fn screen_capture_supported() -> bool {
+    window()
+        .and_then(|window| media_devices(&window.navigator()))
+        .map(|media_devices| {
+            Reflect::has(&media_devices, &jsv!("getDisplayMedia")).unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+fn screen_pseudo_device() -> CameraInfo {
+    CameraInfo::new(
+        "Screen",
+        "Screen, window or browser tab picked by the user",
+        SCREEN_INDEX,
+        &CameraIndex::String(SCREEN_INDEX.to_string()),
+    )
+    .with_kind(DeviceKind::Screen)
+}
+
+/// Lists the cameras using [`MediaDevices::enumerate_devices()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.enumerate_devices) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/enumerateDevices),
+/// without asking for permission.
+///
+/// Until the user granted access, browsers hide the labels (and some browsers the device IDs) of the cameras, which are then
+/// named after their index (e.g. `Videoinput#0`). Cameras having labels means access was granted, so this also updates
+/// [`js_permission_status()`].
+///
+/// Where [`screen_capture_supported()`], the screen is listed last, as a pseudo-device with [`DeviceKind::Screen`] and the index
+/// [`SCREEN_INDEX`]. Open it with [`JSCamera::from_camera_info()`] or [`JSCamera::new_screen()`].
 /// # Errors
 /// This will error if there is no valid web context or the web API is not supported
-pub async fn query_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
+pub async fn enumerate_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
     let window: Window = window()?;
     let navigator = window.navigator();
     let media_devices = media_devices(&navigator)?;
 
-    match media_devices.enumerate_devices() {
-        Ok(prom) => {
-            let prom: Promise = prom;
-            let future = JsFuture::from(prom);
-            match future.await {
-                Ok(v) => {
-                    let array: Array = Array::from(&v);
-                    let mut device_list = vec![];
-                    request_permission().await.unwrap_or(()); // swallow errors
-                    for idx_device in 0_u32..array.length() {
-                        if MediaDeviceInfo::instanceof(&array.get(idx_device)) {
-                            let media_device_info =
-                                MediaDeviceInfo::unchecked_from_js(array.get(idx_device));
-
-                            if media_device_info.kind() == MediaDeviceKind::Videoinput {
-                                match media_devices.get_user_media_with_constraints(
-                                    MediaStreamConstraints::new()
-                                        .audio(&jsv!(false))
-                                        .video(&jsv!(obj!((
-                                            "deviceId",
-                                            media_device_info.device_id()
-                                        )))),
-                                ) {
-                                    Ok(promised_stream) => {
-                                        let future_stream = JsFuture::from(promised_stream);
-                                        if let Ok(stream) = future_stream.await {
-                                            let stream = MediaStream::from(stream);
-                                            let tracks = stream.get_video_tracks();
-                                            let first = tracks.get(0);
-                                            let name = if first.is_undefined() {
-                                                format!(
-                                                    "{:?}#{}",
-                                                    media_device_info.kind(),
-                                                    idx_device
-                                                )
-                                            } else {
-                                                MediaStreamTrack::from(first).label()
-                                            };
-                                            device_list.push(CameraInfo::new(
-                                                &name,
-                                                &format!("{:?}", media_device_info.kind()),
-                                                &format!(
-                                                    "{} {}",
-                                                    media_device_info.group_id(),
-                                                    media_device_info.device_id()
-                                                ),
-                                                CameraIndex::String(format!(
-                                                    "{} {}",
-                                                    media_device_info.group_id(),
-                                                    media_device_info.device_id()
-                                                )),
-                                            ));
-                                            tracks
-                                                .iter()
-                                                .for_each(|t| MediaStreamTrack::from(t).stop());
-                                        }
-                                    }
-                                    Err(_) => {
-                                        device_list.push(CameraInfo::new(
-                                            &format!(
-                                                "{:?}#{}",
-                                                media_device_info.kind(),
-                                                idx_device
-                                            ),
-                                            &format!("{:?}", media_device_info.kind()),
-                                            &format!(
-                                                "{} {}",
-                                                media_device_info.group_id(),
-                                                media_device_info.device_id()
-                                            ),
-                                            CameraIndex::String(format!(
-                                                "{} {}",
-                                                media_device_info.group_id(),
-                                                media_device_info.device_id()
-                                            )),
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Ok(device_list)
-                }
-                Err(why) => Err(NokhwaError::StructureError {
-                    structure: "EnumerateDevicesFuture".to_string(),
-                    error: format!("{why:?}"),
-                }),
-            }
-        }
-        Err(why) => Err(NokhwaError::StructureError {
+    let promise = media_devices
+        .enumerate_devices()
+        .map_err(|why| NokhwaError::StructureError {
             structure: "EnumerateDevices".to_string(),
             error: format!("{why:?}"),
-        }),
+        })?;
+    let devices = Array::from(&js_await(promise.into(), "EnumerateDevicesFuture").await?);
+
+    let mut device_list = vec![];
+    for (idx_device, device) in devices.iter().enumerate() {
+        if !MediaDeviceInfo::instanceof(&device) {
+            continue;
+        }
+        let media_device_info = MediaDeviceInfo::unchecked_from_js(device);
+        if media_device_info.kind() != MediaDeviceKind::Videoinput {
+            continue;
+        }
+
+        let label = media_device_info.label();
+        let name = if label.is_empty() {
+            format!("{:?}#{idx_device}", media_device_info.kind())
+        } else {
+            set_permission_status(PERMISSION_GRANTED);
+            label
+        };
+        let id = format!(
+            "{} {}",
+            media_device_info.group_id(),
+            media_device_info.device_id()
+        );
+        device_list.push(CameraInfo::new(
+            &name,
+            &format!("{:?}", media_device_info.kind()),
+            &id,
+            CameraIndex::String(id.clone()),
+        ));
+    }
+    if screen_capture_supported() {
+        device_list.push(screen_pseudo_device());
+    }
+    Ok(device_list)
+}
+
+/// Lists the cameras using [`MediaDevices::enumerate_devices()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.enumerate_devices) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/enumerateDevices),
+/// without asking for permission. See [`enumerate_js_cameras()`].
+/// # Errors
+/// This will error if there is no valid web context or the web API is not supported
+/// # JS-WASM
+/// This is exported as `enumerateCameras`. It may throw an exception.
+#[cfg(feature = "output-wasm")]
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = enumerateCameras))]
+pub async fn js_enumerate_js_cameras() -> Result<Array, JsValue> {
+    match enumerate_js_cameras().await {
+        Ok(cameras) => Ok(cameras.into_iter().map(JsValue::from).collect()),
+        Err(why) => Err(JsValue::from(why.to_string())),
+    }
+}
+
+/// Queries Cameras with their labels, asking for permission if needed.
+///
+/// This runs in two phases:
+/// 1. The cameras are listed without a prompt, using [`enumerate_js_cameras()`]. If they have labels, access was granted before and these are returned.
+/// 2. Otherwise [`request_permission()`] shows the browser's prompt, and the cameras are listed again, now with labels.
+///
+/// If the user does not grant access, or [`query_js_permission()`] finds they denied it before, the unlabeled cameras from the first phase are returned.
+/// # Errors
+/// This will error if there is no valid web context or the web API is not supported
+pub async fn query_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
+    let cameras = enumerate_js_cameras().await?;
+    let has_cameras = cameras
+        .iter()
+        .any(|camera| camera.kind() == DeviceKind::Camera);
+    if !has_cameras || js_permission_status().is_granted() {
+        return Ok(cameras);
     }
+    // `request_permission()` checks for a denied permission before prompting
+    if request_permission().await.is_err() {
+        return Ok(cameras);
+    }
+    enumerate_js_cameras().await
 }
 
-/// Queries Cameras using [`MediaDevices::enumerate_devices()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.enumerate_devices) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/enumerateDevices)
+/// Queries Cameras with their labels, asking for permission if needed. See [`query_js_cameras()`].
 /// # Errors
 /// This will error if there is no valid web context or the web API is not supported
 /// # JS-WASM
@@ -898,149 +1335,9 @@ impl JSCameraConstraintsBuilder {
 
 impl JSCameraConstraintsBuilder {
     /// Builds the [`JSCameraConstraints`]
-    #[allow(clippy::too_many_lines)]
     #[must_use]
     pub fn build(self) -> JSCameraConstraints {
-        let null_resolution = Resolution::default();
-        let null_string = String::new();
-
-        let mut video_object = Object::new();
-
-        // width
-        if self.resolution_exact {
-            if self.preferred_resolution != null_resolution {
-                video_object = obj!(
-                    video_object,
-                    ("width", obj!(("exact", self.preferred_resolution.width())))
-                );
-            }
-        } else {
-            let mut width_object = Object::new();
-
-            if let Some(min_res) = self.min_resolution {
-                width_object = obj!(width_object, ("min", min_res.width()));
-            }
-
-            width_object = obj!(width_object, ("ideal", self.preferred_resolution.width()));
-            if let Some(max_res) = self.max_resolution {
-                width_object = obj!(width_object, ("max", max_res.width()));
-            }
-
-            video_object = obj!(video_object, ("width", width_object));
-        }
-
-        // height
-        if self.resolution_exact {
-            if self.preferred_resolution != null_resolution {
-                video_object = obj!(
-                    video_object,
-                    (
-                        "height",
-                        obj!(("exact", self.preferred_resolution.height()))
-                    )
-                );
-            }
-        } else {
-            let mut height_object = Object::new();
-
-            if let Some(min_res) = self.min_resolution {
-                height_object = obj!(height_object, ("min", min_res.height()));
-            }
-
-            height_object = obj!(height_object, ("ideal", self.preferred_resolution.height()));
-            if let Some(max_res) = self.max_resolution {
-                height_object = obj!(height_object, ("max", max_res.height()));
-            }
-
-            video_object = obj!(video_object, ("height", height_object));
-        }
-
-        // aspect ratio
-        if self.aspect_ratio_exact {
-            if self.aspect_ratio != 0_f64 {
-                video_object = obj!(
-                    video_object,
-                    ("aspectRatio", obj!(("exact", self.aspect_ratio)))
-                );
-            }
-        } else {
-            let mut aspect_ratio_object = Object::new();
-
-            if let Some(min_ratio) = self.min_aspect_ratio {
-                aspect_ratio_object = obj!(aspect_ratio_object, ("min", min_ratio));
-            }
-
-            aspect_ratio_object = obj!(aspect_ratio_object, ("ideal", self.aspect_ratio));
-            if let Some(max_ratio) = self.max_aspect_ratio {
-                aspect_ratio_object = obj!(aspect_ratio_object, ("max", max_ratio));
-            }
-
-            video_object = obj!(video_object, ("aspectRatio", aspect_ratio_object));
-        }
-
-        if self.facing_mode != JSCameraFacingMode::Any && self.facing_mode_exact {
-            video_object = obj!(
-                video_object,
-                ("facingMode", obj!(("exact", self.facing_mode.to_string())))
-            );
-        } else if self.facing_mode != JSCameraFacingMode::Any {
-            video_object = obj!(
-                video_object,
-                ("facingMode", obj!(("ideal", self.facing_mode.to_string())))
-            );
-        }
-
-        // aspect ratio
-        if self.frame_rate_exact {
-            if self.frame_rate != 0 {
-                video_object = obj!(
-                    video_object,
-                    ("frameRate", obj!(("exact", self.frame_rate)))
-                );
-            }
-        } else {
-            let mut frame_rate_object = Object::new();
-
-            if let Some(min_frame_rate) = self.min_frame_rate {
-                frame_rate_object = obj!(frame_rate_object, ("min", min_frame_rate));
-            }
-
-            frame_rate_object = obj!(frame_rate_object, ("ideal", self.frame_rate));
-            if let Some(max_frame_rate) = self.max_frame_rate {
-                frame_rate_object = obj!(frame_rate_object, ("max", max_frame_rate));
-            }
-
-            video_object = obj!(video_object, ("frameRate", frame_rate_object));
-        }
-
-        if self.resize_mode != JSCameraResizeMode::Any && self.resize_mode_exact {
-            video_object = obj!(
-                video_object,
-                ("resizeMode", obj!(("exact", self.resize_mode.to_string())))
-            );
-        } else if self.resize_mode != JSCameraResizeMode::Any {
-            video_object = obj!(
-                video_object,
-                ("resizeMode", obj!(("ideal", self.resize_mode.to_string())))
-            );
-        }
-
-        if self.device_id != null_string && self.device_id_exact {
-            video_object = obj!(video_object, ("deviceId", obj!(("exact", &self.device_id))));
-        } else if self.device_id != null_string {
-            video_object = obj!(video_object, ("deviceId", obj!(("ideal", &self.device_id))));
-        }
-
-        if self.group_id != null_string && self.group_id_exact {
-            video_object = obj!(video_object, ("groupId", obj!(("exact", &self.group_id))));
-        } else if self.group_id != null_string {
-            video_object = obj!(video_object, ("groupId", obj!(("ideal", &self.group_id))));
-        }
-
-        let media_stream_constraints = MediaStreamConstraints::new()
-            .audio(&jsv!(false))
-            .video(&jsv!(video_object))
-            .clone();
+        let media_stream_constraints = BrowserConstraints::from(&self).media_constraints();
 
         JSCameraConstraints {
             media_constraints: media_stream_constraints,
@@ -1063,7 +1360,7 @@ impl JSCameraConstraintsBuilder {
             device_id: self.device_id,
             device_id_exact: self.device_id_exact,
             group_id: self.group_id,
-            group_id_exact: self.device_id_exact,
+            group_id_exact: self.group_id_exact,
         }
     }
 }
@@ -1095,57 +1392,543 @@ impl Default for JSCameraConstraintsBuilder {
     }
 }
 
-/// Constraints to create a [`JSCamera`]
+// one numeric member of a `MediaTrackConstraints` dictionary, e.g. `width: { min: 640, ideal: 1280 }`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct ConstrainNumber {
+    min: Option<f64>,
+    ideal: Option<f64>,
+    max: Option<f64>,
+    exact: Option<f64>,
+}
+
+impl ConstrainNumber {
+    fn to_js(self) -> Option<JsValue> {
+        if let Some(exact) = self.exact {
+            return Some(js_object(&[("exact", &jsv!(exact))]).into());
+        }
+        let entries = [("min", self.min), ("ideal", self.ideal), ("max", self.max)]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, jsv!(value))))
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            return None;
+        }
+        let entries = entries
+            .iter()
+            .map(|(key, value)| (*key, value))
+            .collect::<Vec<_>>();
+        Some(js_object(&entries).into())
+    }
+
+    // the value the browser aims for, which the older constraint structs keep as a single number
+    fn target(self) -> Option<f64> {
+        self.exact.or(self.ideal)
+    }
+}
+
+// one string member of a `MediaTrackConstraints` dictionary, e.g. `deviceId: { exact: "..." }`
+#[derive(Clone, Debug, PartialEq)]
+struct ConstrainString {
+    value: String,
+    exact: bool,
+}
+
+impl ConstrainString {
+    fn new(value: impl ToString, exact: bool) -> Option<Self> {
+        let value = value.to_string();
+        if value.is_empty() {
+            return None;
+        }
+        Some(ConstrainString { value, exact })
+    }
+
+    fn to_js(&self) -> JsValue {
+        let key = if self.exact { "exact" } else { "ideal" };
+        js_object(&[(key, &jsv!(self.value.as_str()))]).into()
+    }
+}
+
+/// Typed [`MediaTrackConstraints`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints) for `getUserMedia()`.
 ///
-/// If you want more options, see [`JSCameraConstraintsBuilder`]
+/// Only what is set ends up in the dictionary, so the browser is free to pick anything else. Every `ideal` or `min`/`max` value
+/// is a preference the browser may not meet, while `exact` values make `getUserMedia()` fail with an `OverconstrainedError` if no
+/// camera can meet them.
+/// ```ignore
+/// let constraints = BrowserConstraints::new()
+///     .facing_mode(JSCameraFacingMode::Environment)
+///     .ideal_resolution(Resolution::new(1280, 720))
+///     .frame_rate(30)
+///     .build();
+/// let camera = JSCamera::new(constraints).await?;
+/// ```
 /// # JS-WASM
-/// This is exported as `CameraConstraints`.
-#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = CameraConstraints))]
-#[derive(Clone, Debug)]
-#[allow(clippy::struct_excessive_bools)]
-pub struct JSCameraConstraints {
-    pub(crate) media_constraints: MediaStreamConstraints,
-    pub(crate) min_resolution: Option<Resolution>,
-    pub(crate) preferred_resolution: Resolution,
-    pub(crate) max_resolution: Option<Resolution>,
-    pub(crate) resolution_exact: bool,
-    pub(crate) min_aspect_ratio: Option<f64>,
-    pub(crate) aspect_ratio: f64,
-    pub(crate) max_aspect_ratio: Option<f64>,
-    pub(crate) aspect_ratio_exact: bool,
-    pub(crate) facing_mode: JSCameraFacingMode,
-    pub(crate) facing_mode_exact: bool,
-    pub(crate) min_frame_rate: Option<u32>,
-    pub(crate) frame_rate: u32,
-    pub(crate) max_frame_rate: Option<u32>,
-    pub(crate) frame_rate_exact: bool,
-    pub(crate) resize_mode: JSCameraResizeMode,
-    pub(crate) resize_mode_exact: bool,
-    pub(crate) device_id: String,
-    pub(crate) device_id_exact: bool,
-    pub(crate) group_id: String,
-    pub(crate) group_id_exact: bool,
+/// This is exported as `BrowserConstraints`.
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = BrowserConstraints))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BrowserConstraints {
+    width: ConstrainNumber,
+    height: ConstrainNumber,
+    aspect_ratio: ConstrainNumber,
+    frame_rate: ConstrainNumber,
+    facing_mode: Option<ConstrainString>,
+    resize_mode: Option<ConstrainString>,
+    device_id: Option<ConstrainString>,
+    group_id: Option<ConstrainString>,
 }
 
-#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_class = CameraConstraints))]
-impl JSCameraConstraints {
-    /// Gets the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html)
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_class = BrowserConstraints))]
+impl BrowserConstraints {
+    /// Creates empty [`BrowserConstraints`], letting the browser pick any camera and format.
     /// # JS-WASM
-    /// This is exported as `get_MediaStreamConstraints`.
+    /// This is exported as a constructor.
     #[must_use]
-    #[cfg_attr(
-        feature = "output-wasm",
-        wasm_bindgen(getter = MediaStreamConstraints)
-    )]
-    pub fn media_constraints(&self) -> MediaStreamConstraints {
-        self.media_constraints.clone()
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Self {
+        BrowserConstraints::default()
     }
 
-    /// Gets the minimum [`Resolution`].
-    /// # JS-WASM
-    /// This is exported as `get_MinResolution`.
-    #[cfg_attr(
-        feature = "output-wasm",
+    /// Prefers a camera facing `facing_mode`. [`JSCameraFacingMode::Any`] removes the preference.
+    ///
+    /// Sets [`facingMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/facingMode).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = facingMode))]
+    pub fn facing_mode(mut self, facing_mode: JSCameraFacingMode) -> Self {
+        self.facing_mode = Self::facing_mode_constraint(facing_mode, false);
+        self
+    }
+
+    /// Requires a camera facing `facing_mode`. [`JSCameraFacingMode::Any`] removes the requirement.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = exactFacingMode))]
+    pub fn exact_facing_mode(mut self, facing_mode: JSCameraFacingMode) -> Self {
+        self.facing_mode = Self::facing_mode_constraint(facing_mode, true);
+        self
+    }
+
+    /// Sets the resolution the browser aims for.
+    ///
+    /// Sets `ideal` of [`width`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/width) and [`height`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/height).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = idealResolution))]
+    pub fn ideal_resolution(mut self, resolution: Resolution) -> Self {
+        self.width.ideal = Some(f64::from(resolution.width()));
+        self.height.ideal = Some(f64::from(resolution.height()));
+        self
+    }
+
+    /// Sets the lowest acceptable resolution.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = minResolution))]
+    pub fn min_resolution(mut self, resolution: Resolution) -> Self {
+        self.width.min = Some(f64::from(resolution.width()));
+        self.height.min = Some(f64::from(resolution.height()));
+        self
+    }
+
+    /// Sets the highest acceptable resolution.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = maxResolution))]
+    pub fn max_resolution(mut self, resolution: Resolution) -> Self {
+        self.width.max = Some(f64::from(resolution.width()));
+        self.height.max = Some(f64::from(resolution.height()));
+        self
+    }
+
+    /// Requires exactly `resolution`. This overrides any ideal, minimum and maximum resolution.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = exactResolution))]
+    pub fn exact_resolution(mut self, resolution: Resolution) -> Self {
+        self.width.exact = Some(f64::from(resolution.width()));
+        self.height.exact = Some(f64::from(resolution.height()));
+        self
+    }
+
+    /// Sets the frame rate the browser aims for.
+    ///
+    /// Sets `ideal` of [`frameRate`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/frameRate).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = frameRate))]
+    pub fn frame_rate(mut self, fps: u32) -> Self {
+        self.frame_rate.ideal = Some(f64::from(fps));
+        self
+    }
+
+    /// Sets the lowest acceptable frame rate.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = minFrameRate))]
+    pub fn min_frame_rate(mut self, fps: u32) -> Self {
+        self.frame_rate.min = Some(f64::from(fps));
+        self
+    }
+
+    /// Sets the highest acceptable frame rate.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = maxFrameRate))]
+    pub fn max_frame_rate(mut self, fps: u32) -> Self {
+        self.frame_rate.max = Some(f64::from(fps));
+        self
+    }
+
+    /// Requires exactly `fps`. This overrides any ideal, minimum and maximum frame rate.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = exactFrameRate))]
+    pub fn exact_frame_rate(mut self, fps: u32) -> Self {
+        self.frame_rate.exact = Some(f64::from(fps));
+        self
+    }
+
+    /// Sets the aspect ratio (width divided by height) the browser aims for.
+    ///
+    /// Sets `ideal` of [`aspectRatio`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/aspectRatio).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = aspectRatio))]
+    pub fn aspect_ratio(mut self, ratio: f64) -> Self {
+        self.aspect_ratio.ideal = Some(ratio);
+        self
+    }
+
+    /// Sets the lowest and highest acceptable aspect ratio.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = aspectRatioRange))]
+    pub fn aspect_ratio_range(mut self, min: f64, max: f64) -> Self {
+        self.aspect_ratio.min = Some(min);
+        self.aspect_ratio.max = Some(max);
+        self
+    }
+
+    /// Requires exactly `ratio`. This overrides any ideal, minimum and maximum aspect ratio.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = exactAspectRatio))]
+    pub fn exact_aspect_ratio(mut self, ratio: f64) -> Self {
+        self.aspect_ratio.exact = Some(ratio);
+        self
+    }
+
+    /// Sets whether the browser may crop and/or scale to meet the resolution. [`JSCameraResizeMode::Any`] removes the preference.
+    ///
+    /// Sets [`resizeMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints#resizemode).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = resizeMode))]
+    pub fn resize_mode(mut self, resize_mode: JSCameraResizeMode) -> Self {
+        self.resize_mode = ConstrainString::new(resize_mode, false);
+        self
+    }
+
+    /// Prefers the camera with the `deviceId` `id`, as in [`CameraInfo::misc()`]. If `exact` is set, no other camera is accepted.
+    /// An empty `id` removes the preference.
+    ///
+    /// Sets [`deviceId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/deviceId).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = deviceId))]
+    pub fn device_id(mut self, id: &str, exact: bool) -> Self {
+        self.device_id = ConstrainString::new(id, exact);
+        self
+    }
+
+    /// Prefers a camera in the group `id`, which is shared by devices on the same physical device. If `exact` is set, no other
+    /// camera is accepted. An empty `id` removes the preference.
+    ///
+    /// Sets [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId).
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = groupId))]
+    pub fn group_id(mut self, id: &str, exact: bool) -> Self {
+        self.group_id = ConstrainString::new(id, exact);
+        self
+    }
+
+    /// Gets the [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html)
+    /// to pass to `getUserMedia()`, with no audio.
+    /// # JS-WASM
+    /// This is exported as `get_MediaStreamConstraints`.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(getter = MediaStreamConstraints))]
+    pub fn media_constraints(&self) -> MediaStreamConstraints {
+        MediaStreamConstraints::new()
+            .audio(&jsv!(false))
+            .video(&self.track_constraints().into())
+            .clone()
+    }
+
+    /// Builds the [`JSCameraConstraints`] to open a [`JSCamera`] with.
+    ///
+    /// The getters of [`JSCameraConstraints`] only hold whole frame rates, so calling [`JSCameraConstraints::apply_constraints()`]
+    /// rounds a fractional frame rate down.
+    /// # JS-WASM
+    /// This is exported as `buildCameraConstraints`.
+    #[must_use]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = buildCameraConstraints))]
+    pub fn build(self) -> JSCameraConstraints {
+        let media_constraints = self.media_constraints();
+        let mut constraints = JSCameraConstraintsBuilder::from(self).build();
+        constraints.media_constraints = media_constraints;
+        constraints
+    }
+}
+
+impl BrowserConstraints {
+    /// Takes the resolution and frame rate of a [`FormatRequest`]. The preferred value becomes `ideal`, and the range bounds `min` and `max`.
+    ///
+    /// Frame formats and the request type can not be expressed to the browser, which always hands out RGBA, and are ignored.
+    #[must_use]
+    pub fn with_format_request(mut self, request: &FormatRequest) -> Self {
+        if let Some(range) = request.resolution_range() {
+            let width = |resolution: Option<Resolution>| resolution.map(|r| f64::from(r.width()));
+            let height = |resolution: Option<Resolution>| resolution.map(|r| f64::from(r.height()));
+            self.width = ConstrainNumber {
+                min: width(range.minimum()),
+                ideal: width(range.preferred()),
+                max: width(range.maximum()),
+                exact: None,
+            };
+            self.height = ConstrainNumber {
+                min: height(range.minimum()),
+                ideal: height(range.preferred()),
+                max: height(range.maximum()),
+                exact: None,
+            };
+        }
+        if let Some(range) = request.frame_rate_range() {
+            self.frame_rate = Self::frame_rate_constraint(range);
+        }
+        self
+    }
+
+    /// Gets the [`MediaTrackConstraints`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints) dictionary for the video track.
+    #[must_use]
+    pub fn track_constraints(&self) -> Object {
+        let numbers = [
+            ("width", self.width),
+            ("height", self.height),
+            ("aspectRatio", self.aspect_ratio),
+            ("frameRate", self.frame_rate),
+        ];
+        let strings = [
+            ("facingMode", &self.facing_mode),
+            ("resizeMode", &self.resize_mode),
+            ("deviceId", &self.device_id),
+            ("groupId", &self.group_id),
+        ];
+
+        let entries = numbers
+            .into_iter()
+            .filter_map(|(key, value)| value.to_js().map(|value| (key, value)))
+            .chain(
+                strings
+                    .into_iter()
+                    .filter_map(|(key, value)| value.as_ref().map(|value| (key, value.to_js()))),
+            )
+            .collect::<Vec<_>>();
+        let entries = entries
+            .iter()
+            .map(|(key, value)| (*key, value))
+            .collect::<Vec<_>>();
+        js_object(&entries)
+    }
+
+    fn facing_mode_constraint(
+        facing_mode: JSCameraFacingMode,
+        exact: bool,
+    ) -> Option<ConstrainString> {
+        match facing_mode {
+            JSCameraFacingMode::Any => None,
+            mode => ConstrainString::new(mode, exact),
+        }
+    }
+
+    fn frame_rate_constraint(range: Range<FrameRate>) -> ConstrainNumber {
+        let fps = |frame_rate: Option<FrameRate>| frame_rate.map(|f| f64::from(f.as_float()));
+        ConstrainNumber {
+            min: fps(range.minimum()),
+            ideal: fps(range.preferred()),
+            max: fps(range.maximum()),
+            exact: None,
+        }
+    }
+}
+
+// `JSCameraConstraintsBuilder` keeps unset values as 0 or an empty string, which the dictionary leaves out
+impl From<&JSCameraConstraintsBuilder> for BrowserConstraints {
+    fn from(builder: &JSCameraConstraintsBuilder) -> Self {
+        let non_zero = |value: f64| (value != 0_f64).then_some(value);
+        let resolution = |resolution: Resolution, pick: fn(Resolution) -> u32| {
+            non_zero(f64::from(pick(resolution)))
+        };
+        let resolution_constraint = |pick: fn(Resolution) -> u32| {
+            let preferred = resolution(builder.preferred_resolution, pick);
+            if builder.resolution_exact {
+                return ConstrainNumber {
+                    exact: preferred,
+                    ..ConstrainNumber::default()
+                };
+            }
+            ConstrainNumber {
+                min: builder.min_resolution.and_then(|r| resolution(r, pick)),
+                ideal: preferred,
+                max: builder.max_resolution.and_then(|r| resolution(r, pick)),
+                exact: None,
+            }
+        };
+        let range_constraint = |min: Option<f64>, preferred: f64, max: Option<f64>, exact: bool| {
+            if exact {
+                return ConstrainNumber {
+                    exact: non_zero(preferred),
+                    ..ConstrainNumber::default()
+                };
+            }
+            ConstrainNumber {
+                min: min.and_then(non_zero),
+                ideal: non_zero(preferred),
+                max: max.and_then(non_zero),
+                exact: None,
+            }
+        };
+
+        BrowserConstraints {
+            width: resolution_constraint(Resolution::width),
+            height: resolution_constraint(Resolution::height),
+            aspect_ratio: range_constraint(
+                builder.min_aspect_ratio,
+                builder.aspect_ratio,
+                builder.max_aspect_ratio,
+                builder.aspect_ratio_exact,
+            ),
+            frame_rate: range_constraint(
+                builder.min_frame_rate.map(f64::from),
+                f64::from(builder.frame_rate),
+                builder.max_frame_rate.map(f64::from),
+                builder.frame_rate_exact,
+            ),
+            facing_mode: BrowserConstraints::facing_mode_constraint(
+                builder.facing_mode,
+                builder.facing_mode_exact,
+            ),
+            resize_mode: match builder.resize_mode {
+                JSCameraResizeMode::Any => None,
+                mode => ConstrainString::new(mode, builder.resize_mode_exact),
+            },
+            device_id: ConstrainString::new(&builder.device_id, builder.device_id_exact),
+            group_id: ConstrainString::new(&builder.group_id, builder.group_id_exact),
+        }
+    }
+}
+
+impl From<BrowserConstraints> for JSCameraConstraintsBuilder {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from(constraints: BrowserConstraints) -> Self {
+        let resolution = |width: Option<f64>, height: Option<f64>| {
+            (width.is_some() || height.is_some()).then(|| {
+                Resolution::new(
+                    width.unwrap_or_default() as u32,
+                    height.unwrap_or_default() as u32,
+                )
+            })
+        };
+        let string = |constraint: Option<ConstrainString>| {
+            constraint.map_or((String::new(), false), |c| (c.value, c.exact))
+        };
+        let (device_id, device_id_exact) = string(constraints.device_id);
+        let (group_id, group_id_exact) = string(constraints.group_id);
+        let resize_mode = match constraints.resize_mode {
+            Some(ConstrainString { value, exact }) if value == "none" => {
+                (JSCameraResizeMode::None, exact)
+            }
+            Some(ConstrainString { exact, .. }) => (JSCameraResizeMode::CropAndScale, exact),
+            None => (JSCameraResizeMode::Any, false),
+        };
+        let facing_mode = match constraints.facing_mode {
+            Some(ConstrainString { value, exact }) => {
+                let mode = match value.as_str() {
+                    "environment" => JSCameraFacingMode::Environment,
+                    "user" => JSCameraFacingMode::User,
+                    "left" => JSCameraFacingMode::Left,
+                    "right" => JSCameraFacingMode::Right,
+                    _ => JSCameraFacingMode::Any,
+                };
+                (mode, exact)
+            }
+            None => (JSCameraFacingMode::Any, false),
+        };
+
+        JSCameraConstraintsBuilder {
+            min_resolution: resolution(constraints.width.min, constraints.height.min),
+            preferred_resolution: resolution(
+                constraints.width.target(),
+                constraints.height.target(),
+            )
+            .unwrap_or_default(),
+            max_resolution: resolution(constraints.width.max, constraints.height.max),
+            resolution_exact: constraints.width.exact.is_some(),
+            min_aspect_ratio: constraints.aspect_ratio.min,
+            aspect_ratio: constraints.aspect_ratio.target().unwrap_or_default(),
+            max_aspect_ratio: constraints.aspect_ratio.max,
+            aspect_ratio_exact: constraints.aspect_ratio.exact.is_some(),
+            facing_mode: facing_mode.0,
+            facing_mode_exact: facing_mode.1,
+            min_frame_rate: constraints.frame_rate.min.map(|fps| fps as u32),
+            frame_rate: constraints.frame_rate.target().unwrap_or_default() as u32,
+            max_frame_rate: constraints.frame_rate.max.map(|fps| fps as u32),
+            frame_rate_exact: constraints.frame_rate.exact.is_some(),
+            resize_mode: resize_mode.0,
+            resize_mode_exact: resize_mode.1,
+            device_id,
+            device_id_exact,
+            group_id,
+            group_id_exact,
+        }
+    }
+}
+
+/// Constraints to create a [`JSCamera`]
+///
+/// If you want more options, see [`JSCameraConstraintsBuilder`]
+/// # JS-WASM
+/// This is exported as `CameraConstraints`.
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = CameraConstraints))]
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct JSCameraConstraints {
+    pub(crate) media_constraints: MediaStreamConstraints,
+    pub(crate) min_resolution: Option<Resolution>,
+    pub(crate) preferred_resolution: Resolution,
+    pub(crate) max_resolution: Option<Resolution>,
+    pub(crate) resolution_exact: bool,
+    pub(crate) min_aspect_ratio: Option<f64>,
+    pub(crate) aspect_ratio: f64,
+    pub(crate) max_aspect_ratio: Option<f64>,
+    pub(crate) aspect_ratio_exact: bool,
+    pub(crate) facing_mode: JSCameraFacingMode,
+    pub(crate) facing_mode_exact: bool,
+    pub(crate) min_frame_rate: Option<u32>,
+    pub(crate) frame_rate: u32,
+    pub(crate) max_frame_rate: Option<u32>,
+    pub(crate) frame_rate_exact: bool,
+    pub(crate) resize_mode: JSCameraResizeMode,
+    pub(crate) resize_mode_exact: bool,
+    pub(crate) device_id: String,
+    pub(crate) device_id_exact: bool,
+    pub(crate) group_id: String,
+    pub(crate) group_id_exact: bool,
+}
+
+#[cfg_attr(feature = "output-wasm", wasm_bindgen(js_class = CameraConstraints))]
+impl JSCameraConstraints {
+    /// Gets the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html)
+    /// # JS-WASM
+    /// This is exported as `get_MediaStreamConstraints`.
+    #[must_use]
+    #[cfg_attr(
+        feature = "output-wasm",
+        wasm_bindgen(getter = MediaStreamConstraints)
+    )]
+    pub fn media_constraints(&self) -> MediaStreamConstraints {
+        self.media_constraints.clone()
+    }
+
+    /// Gets the minimum [`Resolution`].
+    /// # JS-WASM
+    /// This is exported as `get_MinResolution`.
+    #[cfg_attr(
+        feature = "output-wasm",
         wasm_bindgen(getter = MinResolution)
     )]
     #[must_use]
@@ -1672,6 +2455,141 @@ impl Deref for JSCameraConstraints {
     }
 }
 
+/// How [`JSCamera::frame_raw_async()`] and [`JSCamera::video_frame()`](JSCamera) get frames, picked from the [`BrowserCapabilities`].
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BrowserFramePath {
+    /// Frames are read from the track with `MediaStreamTrackProcessor`, without a `<video>`.
+    TrackProcessor,
+    /// Frames are drawn from a `<video>`, paced by `requestVideoFrameCallback()`.
+    VideoFrameCallback,
+    /// Frames are drawn from a `<video>` whenever asked, which may return the same frame twice.
+    Canvas,
+}
+
+/// Which of the web APIs nokhwa can use the browser has, as they differ between Chrome, Firefox and Safari.
+///
+/// [`JSCamera`] detects these when it is opened and picks the best way to read frames, which [`JSCamera::frame_path()`] tells.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct BrowserCapabilities {
+    track_processor: bool,
+    track_generator: bool,
+    video_frame_callback: bool,
+    image_capture: bool,
+    web_codecs: bool,
+    screen_capture: bool,
+    permissions: bool,
+}
+
+impl BrowserCapabilities {
+    /// Detects the APIs of the browser this runs in. This works in workers too, where APIs of the page (e.g. `<video>`) are missing.
+    #[must_use]
+    pub fn detect() -> Self {
+        let global = js_sys::global();
+        let has = |name: &str| Reflect::has(&global, &jsv!(name)).unwrap_or(false);
+        let navigator_has = |name: &str| {
+            js_get(&global, "navigator")
+                .ok()
+                .filter(|navigator| navigator.is_object())
+                .map_or(false, |navigator| {
+                    Reflect::has(&navigator, &jsv!(name)).unwrap_or(false)
+                })
+        };
+        BrowserCapabilities {
+            track_processor: track_processor_supported(),
+            track_generator: track_generator_supported(),
+            video_frame_callback: video_frame_callback_supported(),
+            image_capture: has("ImageCapture"),
+            web_codecs: has("VideoFrame") && has("VideoEncoder"),
+            screen_capture: screen_capture_supported(),
+            permissions: navigator_has("permissions"),
+        }
+    }
+
+    /// Returns `true` if the browser has `MediaStreamTrackProcessor`, see [`track_processor_supported()`].
+    #[must_use]
+    pub fn track_processor(&self) -> bool {
+        self.track_processor
+    }
+
+    /// Returns `true` if the browser has `MediaStreamTrackGenerator`, see [`track_generator_supported()`].
+    #[must_use]
+    pub fn track_generator(&self) -> bool {
+        self.track_generator
+    }
+
+    /// Returns `true` if the browser has `requestVideoFrameCallback()`, see [`video_frame_callback_supported()`].
+    #[must_use]
+    pub fn video_frame_callback(&self) -> bool {
+        self.video_frame_callback
+    }
+
+    /// Returns `true` if the browser has `ImageCapture`, which [`JSCamera::take_photo()`] takes full resolution photos with.
+    #[must_use]
+    pub fn image_capture(&self) -> bool {
+        self.image_capture
+    }
+
+    /// Returns `true` if the browser has WebCodecs (`VideoFrame` and `VideoEncoder`).
+    #[must_use]
+    pub fn web_codecs(&self) -> bool {
+        self.web_codecs
+    }
+
+    /// Returns `true` if the browser has `getDisplayMedia()`, see [`screen_capture_supported()`].
+    #[must_use]
+    pub fn screen_capture(&self) -> bool {
+        self.screen_capture
+    }
+
+    /// Returns `true` if the browser has the Permissions API, which [`query_js_permission()`] uses.
+    #[must_use]
+    pub fn permissions(&self) -> bool {
+        self.permissions
+    }
+
+    /// Gets the best way to read frames with these APIs.
+    #[must_use]
+    pub fn frame_path(&self) -> BrowserFramePath {
+        if self.track_processor {
+            BrowserFramePath::TrackProcessor
+        } else if self.video_frame_callback {
+            BrowserFramePath::VideoFrameCallback
+        } else {
+            BrowserFramePath::Canvas
+        }
+    }
+}
+
+/// What the camera can do for photos taken with [`JSCamera::take_photo()`], from the `ImageCapture`'s
+/// [`getPhotoCapabilities()`](https://developer.mozilla.org/en-US/docs/Web/API/ImageCapture/getPhotoCapabilities).
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct JSPhotoCapabilities {
+    max_resolution: Option<Resolution>,
+    flash_modes: Vec<FlashMode>,
+    red_eye_reduction: Option<bool>,
+}
+
+impl JSPhotoCapabilities {
+    /// Gets the largest [`Resolution`] of a photo, if the browser says.
+    #[must_use]
+    pub fn max_resolution(&self) -> Option<Resolution> {
+        self.max_resolution
+    }
+
+    /// Gets the [`FlashMode`]s [`PhotoSettings::with_flash()`] can pick from. Empty if the camera has no flash or fill light.
+    #[must_use]
+    pub fn flash_modes(&self) -> &[FlashMode] {
+        &self.flash_modes
+    }
+
+    /// Gets whether red-eye reduction is used: `Some` if it is always on or always off, and `None` if it can be picked with
+    /// [`PhotoSettings::with_red_eye_reduction()`].
+    #[must_use]
+    pub fn red_eye_reduction(&self) -> Option<bool> {
+        self.red_eye_reduction
+    }
+}
+
 /// A wrapper around a [`MediaStream`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStream.html)
 /// # JS-WASM
 /// This is exported as `NokhwaCamera`.
@@ -1686,6 +2604,13 @@ pub struct JSCamera {
     measured_resolution: Resolution,
     attached_canvas: Option<HtmlCanvasElement>,
     canvas_context: Option<CanvasRenderingContext2d>,
+    track_reader: Option<JsValue>,
+    pacing_video: Option<HtmlVideoElement>,
+    presented_frames: Option<u64>,
+    dropped_frames: u64,
+    auto_rotate: bool,
+    capabilities: BrowserCapabilities,
+    info: CameraInfo,
 }
 
 #[cfg(feature = "input-jscam")]
@@ -1706,6 +2631,24 @@ impl JSCamera {
         }
     }
 
+    /// Creates a new [`JSCamera`] with the microphone in the same stream. See [`new_with_audio()`](crate::js_camera::JSCamera::new_with_audio).
+    ///
+    /// # Errors
+    /// This may error if permission is not granted, or the constraints are invalid.
+    /// # JS-WASM
+    /// This is exported as `NokhwaCamera.withAudio`. It returns a promise and may throw an error.
+    #[cfg(feature = "output-wasm")]
+    #[cfg_attr(feature = "output-wasm", wasm_bindgen(js_name = withAudio))]
+    pub async fn js_new_with_audio(
+        constraints: JSCameraConstraints,
+        audio: Option<MediaTrackConstraints>,
+    ) -> Result<JSCamera, JsValue> {
+        match JSCamera::new_with_audio(constraints, audio.as_ref()).await {
+            Ok(camera) => Ok(camera),
+            Err(why) => Err(JsValue::from(why.to_string())),
+        }
+    }
+
     /// Gets the internal [`JSCameraConstraints`].
     /// Most likely, you will edit this value by taking ownership of it, then feed it back into [`set_constraints`](crate::js_camera::JSCamera::set_constraints).
     /// # JS-WASM
@@ -1940,14 +2883,91 @@ impl JSCamera {
             }
         };
 
-        let mut js_camera = JSCamera {
-            media_stream: stream,
-            constraints,
-            attached: false,
-            attached_node: None,
+        JSCamera::from_stream(stream, constraints)
+    }
+
+    /// Creates a new [`JSCamera`] whose stream also has the microphone, asked for in the same `getUserMedia()` call, so the browser
+    /// shows one prompt for both. `audio` constrains the microphone (e.g. `echoCancellation`), or is `None` for any.
+    ///
+    /// The frames are read from the video as usual, the microphone is in [`audio_track()`](crate::js_camera::JSCamera::audio_track),
+    /// to add to an `RTCPeerConnection` next to the camera's video.
+    /// # Errors
+    /// This may error if permission is not granted, or the constraints are invalid.
+    pub async fn new_with_audio(
+        mut constraints: JSCameraConstraints,
+        audio: Option<&MediaTrackConstraints>,
+    ) -> Result<Self, NokhwaError> {
+        let audio = audio.map_or_else(|| jsv!(true), |audio| audio.into());
+        constraints.media_constraints.audio(&audio);
+        JSCamera::new(constraints).await
+    }
+
+    /// Captures the screen, a window or a browser tab using [`getDisplayMedia()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getDisplayMedia),
+    /// which shows the browser's picker. The resolution, frame rate and aspect ratio of `constraints` apply, the camera specific ones
+    /// (e.g. the facing mode) are ignored.
+    ///
+    /// # Errors
+    /// This may error if the user cancels the picker, the browser does not have `getDisplayMedia()` (see [`screen_capture_supported()`]),
+    /// or the constraints are invalid.
+    /// # Quirks
+    /// Browsers only show the picker in response to a user gesture, such as a click, so this must be called from an event handler.
+    pub async fn new_screen(constraints: JSCameraConstraints) -> Result<Self, NokhwaError> {
+        let window: Window = window()?;
+        let media_devices = media_devices(&window.navigator())?;
+        let stream = js_await(
+            js_call(
+                &media_devices,
+                "getDisplayMedia",
+                &Array::of1(&constraints.media_constraints()),
+            )?,
+            "MediaDevicesGetDisplayMedia",
+        )
+        .await?;
+
+        JSCamera::from_stream(MediaStream::from(stream), constraints)
+    }
+
+    /// Opens a device listed by [`query_js_cameras()`] or [`enumerate_js_cameras()`], so cameras and the screen open the same way.
+    /// Cameras are opened by their `deviceId`, replacing the device ID of `constraints`, and the screen with [`new_screen()`](crate::js_camera::JSCamera::new_screen).
+    ///
+    /// # Errors
+    /// See [`new()`](crate::js_camera::JSCamera::new) and [`new_screen()`](crate::js_camera::JSCamera::new_screen).
+    pub async fn from_camera_info(
+        info: &CameraInfo,
+        mut constraints: JSCameraConstraints,
+    ) -> Result<Self, NokhwaError> {
+        if info.kind() == DeviceKind::Screen {
+            return JSCamera::new_screen(constraints).await;
+        }
+
+        // `misc()` is the group ID and device ID, separated by a space
+        let misc = info.misc();
+        let device_id = misc.rsplit(' ').next().unwrap_or(&misc);
+        constraints.set_device_id(device_id.to_string());
+        constraints.set_device_id_exact(true);
+        constraints.apply_constraints();
+        JSCamera::new(constraints).await
+    }
+
+    fn from_stream(
+        stream: MediaStream,
+        constraints: JSCameraConstraints,
+    ) -> Result<Self, NokhwaError> {
+        let mut js_camera = JSCamera {
+            info: stream_camera_info(&stream),
+            media_stream: stream,
+            constraints,
+            attached: false,
+            attached_node: None,
             measured_resolution: Resolution::new(0, 0),
             attached_canvas: None,
             canvas_context: None,
+            track_reader: None,
+            pacing_video: None,
+            presented_frames: None,
+            dropped_frames: 0,
+            auto_rotate: false,
+            capabilities: BrowserCapabilities::detect(),
         };
         js_camera.measure_resolution()?;
 
@@ -2000,6 +3020,80 @@ impl JSCamera {
         Ok(())
     }
 
+    /// Switches the camera to the resolution and frame rate of `format`, with the track's [`applyConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/applyConstraints).
+    /// The stream keeps running, so the camera's light does not blink and there is no gap of seconds while it opens again.
+    ///
+    /// Only if the browser rejects the constraints (e.g. the camera can not switch while streaming) is the stream stopped and requested
+    /// again with `getUserMedia()`. The resolution is exact, the frame rate ideal, and the frame format ignored, as the browser always
+    /// hands out RGBA.
+    /// # Errors
+    /// If the stream has no video, or the camera could not be opened again with `format`, this will error. In the latter case the
+    /// camera is stopped.
+    pub async fn set_format(&mut self, format: CameraFormat) -> Result<(), NokhwaError> {
+        let mut constraints = self.constraints.clone();
+        constraints.set_resolution(format.resolution());
+        constraints.set_resolution_exact(true);
+        constraints.set_frame_rate(format.frame_rate().as_u32());
+        constraints.apply_constraints();
+
+        let track = self.video_track()?;
+        let track_constraints = js_get(&constraints.media_constraints(), "video")?;
+        let applied = match js_call(&track, "applyConstraints", &Array::of1(&track_constraints)) {
+            Ok(promise) => js_await(promise, "MediaStreamTrack applyConstraints")
+                .await
+                .map(drop),
+            Err(why) => Err(why),
+        };
+        if applied.is_err() {
+            self.reacquire(&constraints).await?;
+        }
+
+        self.constraints = constraints;
+        self.measure_resolution()
+    }
+
+    // stops the stream and opens the camera again with `constraints`, pointing the videos frames are read from at the new stream
+    async fn reacquire(&mut self, constraints: &JSCameraConstraints) -> Result<(), NokhwaError> {
+        let window: Window = window()?;
+        let media_devices = media_devices(&window.navigator())?;
+        // most cameras can only be opened once, so the old video goes first
+        self.media_stream
+            .get_video_tracks()
+            .iter()
+            .for_each(|track| MediaStreamTrack::from(track).stop());
+
+        let promise = media_devices
+            .get_user_media_with_constraints(&constraints.media_constraints())
+            .map_err(|why| NokhwaError::StructureError {
+                structure: "MediaDevicesGetUserMedia".to_string(),
+                error: format!("{why:?}"),
+            })?;
+        let stream =
+            MediaStream::from(js_await(promise.into(), "MediaDevicesGetUserMediaJsFuture").await?);
+        // the microphone of `new_with_audio()` was not stopped, and moves to the new stream
+        self.media_stream
+            .get_audio_tracks()
+            .iter()
+            .for_each(|track| stream.add_track(&MediaStreamTrack::from(track)));
+        self.media_stream = stream;
+        self.track_reader = None;
+        self.presented_frames = None;
+
+        if let Some(video) = &self.pacing_video {
+            video.set_src_object(Some(&self.media_stream));
+        }
+        if self.attached {
+            if let Some(node) = &self.attached_node {
+                if let Ok(video) =
+                    element_cast_ref::<Node, HtmlVideoElement>(node, "HtmlVideoElement")
+                {
+                    video.set_src_object(Some(&self.media_stream));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Measures the [`Resolution`] of the internal stream. You usually do not need to call this.
     ///
     /// # Errors
@@ -2220,6 +3314,17 @@ impl JSCamera {
                     return Err(NokhwaError::ReadFrameError(format!("{why:?}")));
                 }
             };
+        } else if let Some(video_element) = &self.pacing_video {
+            // the frame `next_frame()` waited for is the one the pacing `<video>` shows
+            if let Err(why) = context.draw_image_with_html_video_element_and_dw_and_dh(
+                video_element,
+                0_f64,
+                0_f64,
+                self.resolution().width().into(),
+                self.resolution().height().into(),
+            ) {
+                return Err(NokhwaError::ReadFrameError(format!("{why:?}")));
+            }
         } else {
             let video_element = match document.create_element("video") {
                 Ok(new_element) => new_element,
@@ -2491,6 +3596,666 @@ impl JSCamera {
         Ok(Cow::from(image_data))
     }
 
+    /// Captures a raw RGBA frame like [`frame_raw()`](crate::js_camera::JSCamera::frame_raw), but reads it straight from the track with
+    /// `MediaStreamTrackProcessor` where the browser has it, see [`track_processor_supported()`]. This skips the `<video>` and canvas,
+    /// and copies the frame once, from the `VideoFrame` into the returned buffer. Otherwise, or if the browser can not convert the
+    /// frame to RGBA, this falls back to [`frame_raw()`](crate::js_camera::JSCamera::frame_raw).
+    /// # Errors
+    /// If the track has ended or reading the frame fails, this will error.
+    pub async fn frame_raw_async(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        if !self.capabilities.track_processor() {
+            return self.frame_raw();
+        }
+
+        let reader = self.track_reader()?;
+        let Some(video_frame) = read_video_frame(&reader).await? else {
+            self.track_reader = None;
+            return Err(NokhwaError::ReadFrameError(
+                "The track has ended".to_string(),
+            ));
+        };
+
+        let copied = copy_video_frame(&video_frame).await;
+        // frames hold on to the camera's buffers until they are closed, and the track stalls once it runs out
+        let _ = js_call(&video_frame, "close", &Array::new());
+        match copied {
+            Ok((data, resolution)) => {
+                self.measured_resolution = resolution;
+                Ok(Cow::from(data))
+            }
+            Err(_) => self.frame_raw(),
+        }
+    }
+
+    /// Waits for the browser to present a new frame, then captures it like [`frame_raw()`](crate::js_camera::JSCamera::frame_raw).
+    ///
+    /// Where [`video_frame_callback_supported()`], this is paced by `requestVideoFrameCallback()` instead of a timer, so every call
+    /// returns a new frame and none is read twice. The [`Buffer`]'s timestamp is then when the frame was captured (`captureTime`),
+    /// or when the browser does not know, presented (`presentationTime`), on the [`performance_now()`] clock. Its sequence number is
+    /// the frame's `presentedFrames`, whose gaps are counted by [`dropped_frames()`](crate::js_camera::JSCamera::dropped_frames).
+    /// Otherwise, the current frame is captured right away, without a timestamp or sequence number.
+    ///
+    /// The frame carries its [`orientation()`](crate::js_camera::JSCamera::orientation), or is turned upright if
+    /// [`auto_rotate()`](crate::js_camera::JSCamera::auto_rotate) is set.
+    /// # Errors
+    /// If the `<video>` can not be created, or capturing the frame fails, this will error.
+    /// # Quirks
+    /// If the camera is not [attached](crate::js_camera::JSCamera::attach), a `<video>` is added to the page to pace the frames. It is
+    /// kept transparent instead of hidden, as browsers do not present frames of hidden videos.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub async fn next_frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let metadata = if self.capabilities.video_frame_callback() {
+            let video = self.paced_video()?;
+            Some(next_presented_frame(&video).await?)
+        } else {
+            None
+        };
+
+        let data = self.frame_raw()?.to_vec();
+        let orientation = self.orientation();
+        let mut buffer = if self.auto_rotate {
+            let (data, resolution) = rotate_rgba(&data, self.resolution(), orientation.rotation());
+            Buffer::new(resolution, &data, FrameFormat::RgbA8)
+                .with_orientation(FrameOrientation::new(0, orientation.is_mirrored()))
+        } else {
+            Buffer::new(self.resolution(), &data, FrameFormat::RgbA8).with_orientation(orientation)
+        };
+        if let Some(metadata) = metadata {
+            // `captureTime` is only there for cameras whose browser knows it, `presentationTime` always is
+            let time = js_get(&metadata, "captureTime")?.as_f64().or(js_get(
+                &metadata,
+                "presentationTime",
+            )?
+            .as_f64());
+            if let Some(millis) = time {
+                buffer = buffer.with_timestamp(FrameTimestamp::new(
+                    Duration::from_secs_f64(millis.max(0_f64) / 1000_f64),
+                    TimestampClock::Monotonic,
+                ));
+            }
+            if let Some(presented_frames) = js_get(&metadata, "presentedFrames")?.as_f64() {
+                let presented_frames = presented_frames as u64;
+                if let Some(last) = self.presented_frames {
+                    self.dropped_frames += presented_frames.saturating_sub(last + 1);
+                }
+                self.presented_frames = Some(presented_frames);
+                buffer = buffer.with_sequence(presented_frames);
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Waits for the next frame and hands it out as a WebCodecs [`VideoFrame`](https://developer.mozilla.org/en-US/docs/Web/API/VideoFrame),
+    /// without reading it back into WASM memory. Pass it to a `VideoEncoder`, or import it into WebGPU with `importExternalTexture()`.
+    ///
+    /// Where [`track_processor_supported()`], this is the frame `MediaStreamTrackProcessor` reads, which is often backed by a GPU
+    /// texture. Otherwise it is made from the `<video>` once it presents a new frame, like [`next_frame()`](crate::js_camera::JSCamera::next_frame).
+    ///
+    /// The frame holds on to the camera's buffers, so it must be [`close()`](VideoFrame::close)d as soon as it is no longer used, or the
+    /// camera stalls.
+    /// # Errors
+    /// If the track has ended, or the frame can not be made, this will error.
+    #[cfg(feature = "output-webcodecs")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-webcodecs")))]
+    pub async fn video_frame(&mut self) -> Result<VideoFrame, NokhwaError> {
+        if self.capabilities.track_processor() {
+            let reader = self.track_reader()?;
+            let Some(video_frame) = read_video_frame(&reader).await? else {
+                self.track_reader = None;
+                return Err(NokhwaError::ReadFrameError(
+                    "The track has ended".to_string(),
+                ));
+            };
+            return Ok(video_frame.unchecked_into());
+        }
+
+        let video = self.paced_video()?;
+        if self.capabilities.video_frame_callback() {
+            next_presented_frame(&video).await?;
+        }
+        VideoFrame::new_with_html_video_element(&video)
+            .map_err(|why| NokhwaError::ReadFrameError(format!("{why:?}")))
+    }
+
+    /// Gets how the camera's frames have to be turned to appear upright, from the screen's orientation and the track's facing mode.
+    /// Frames of a front facing (`user`) camera are [mirrored](FrameOrientation::is_mirrored).
+    ///
+    /// This is read again for every frame, so it follows the phone being turned.
+    /// # Quirks
+    /// - Browsers do not report the sensor's rotation. Most already turn the frames with the screen, in which case this is not rotated.
+    ///   A rotation is only reported when the frame's aspect does not match the screen's, and then assumes the usual sensor mounted 90 degrees
+    ///   from the phone's natural (portrait) orientation.
+    #[must_use]
+    pub fn orientation(&self) -> FrameOrientation {
+        let mirrored = self
+            .video_track()
+            .and_then(|track| js_call(&track, "getSettings", &Array::new()))
+            .and_then(|settings| js_get(&settings, "facingMode"))
+            .ok()
+            .and_then(|facing_mode| facing_mode.as_string())
+            .as_deref()
+            == Some("user");
+        let resolution = self.resolution();
+        let frame_landscape = resolution.width() > resolution.height();
+        let rotation = match screen_orientation() {
+            Some((screen_landscape, angle)) if screen_landscape != frame_landscape => {
+                (90 + angle) % 360
+            }
+            _ => 0,
+        };
+        FrameOrientation::new(rotation, mirrored)
+    }
+
+    /// Returns `true` if [`next_frame()`](crate::js_camera::JSCamera::next_frame) turns frames upright itself.
+    #[must_use]
+    pub fn auto_rotate(&self) -> bool {
+        self.auto_rotate
+    }
+
+    /// Sets whether [`next_frame()`](crate::js_camera::JSCamera::next_frame) turns frames upright itself, according to
+    /// [`orientation()`](crate::js_camera::JSCamera::orientation), so consumers on mobile always get upright frames. Their orientation
+    /// then only tells whether they are mirrored. Off by default.
+    pub fn set_auto_rotate(&mut self, auto_rotate: bool) {
+        self.auto_rotate = auto_rotate;
+    }
+
+    /// Gets the microphone's track, if the camera was opened with [`new_with_audio()`](crate::js_camera::JSCamera::new_with_audio).
+    /// It is stopped with the camera.
+    #[must_use]
+    pub fn audio_track(&self) -> Option<MediaStreamTrack> {
+        let track = self.media_stream.get_audio_tracks().get(0);
+        (!track.is_undefined()).then(|| MediaStreamTrack::from(track))
+    }
+
+    /// Gets the [`BrowserCapabilities`] detected when the camera was opened.
+    #[must_use]
+    pub fn browser_capabilities(&self) -> BrowserCapabilities {
+        self.capabilities
+    }
+
+    /// Gets how [`frame_raw_async()`](crate::js_camera::JSCamera::frame_raw_async) reads frames in this browser.
+    /// [`next_frame()`](crate::js_camera::JSCamera::next_frame) always uses the `<video>`, paced if the browser can.
+    #[must_use]
+    pub fn frame_path(&self) -> BrowserFramePath {
+        self.capabilities.frame_path()
+    }
+
+    /// Gets how many frames the browser presented without [`next_frame()`](crate::js_camera::JSCamera::next_frame) returning them,
+    /// because it was not called in time.
+    #[must_use]
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    // the frame rate the track runs at, which browsers report as a float
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn settings_frame_rate(&self) -> Option<FrameRate> {
+        let settings = js_call(&self.video_track().ok()?, "getSettings", &Array::new()).ok()?;
+        let frame_rate = js_get(&settings, "frameRate").ok()?.as_f64()?;
+        FrameRate::new_integer(frame_rate.round() as u32).ok()
+    }
+
+    // the `<video>` frames are paced by: the attached one, or one added for pacing
+    fn paced_video(&mut self) -> Result<HtmlVideoElement, NokhwaError> {
+        if self.attached {
+            if let Some(node) = &self.attached_node {
+                return element_cast_ref::<Node, HtmlVideoElement>(node, "HtmlVideoElement")
+                    .cloned();
+            }
+        }
+        if let Some(video) = &self.pacing_video {
+            return Ok(video.clone());
+        }
+
+        let window: Window = window()?;
+        let document: Document = document(&window)?;
+        let video_element = create_element(&document, "video")?;
+        set_autoplay_inline(&video_element)?;
+        if let Err(why) = video_element.set_attribute(
+            "style",
+            "position: fixed; width: 1px; height: 1px; opacity: 0; pointer-events: none;",
+        ) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Video-style".to_string(),
+                value: "transparent".to_string(),
+                error: format!("{why:?}"),
+            });
+        }
+        let video_element =
+            element_cast::<Element, HtmlVideoElement>(video_element, "HtmlVideoElement")?;
+        video_element.set_src_object(Some(&self.media_stream()));
+
+        match document.body() {
+            Some(body) => {
+                if let Err(why) = body.append_child(&video_element) {
+                    return Err(NokhwaError::ReadFrameError(format!(
+                        "Failed to attach video: {why:?}"
+                    )));
+                }
+            }
+            None => {
+                return Err(NokhwaError::ReadFrameError(
+                    "Failed to get body".to_string(),
+                ))
+            }
+        }
+
+        self.pacing_video = Some(video_element.clone());
+        Ok(video_element)
+    }
+
+    /// Gets the [`CameraControl`]s the camera supports, from the track's
+    /// [`getCapabilities()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/getCapabilities) and current settings.
+    ///
+    /// Only [`Zoom`](KnownCameraControl::Zoom), [`Focus`](KnownCameraControl::Focus) (the focus distance) and
+    /// [`Torch`](KnownCameraControl::Torch) are mapped, and only where the camera has them, which mostly means the cameras of phones.
+    /// # Errors
+    /// This will error if the camera has no video track, or the browser does not have `getCapabilities()` (e.g. Firefox).
+    pub fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        let track = self.video_track()?;
+        let capabilities = js_call(&track, "getCapabilities", &Array::new())?;
+        let settings = js_call(&track, "getSettings", &Array::new())?;
+
+        let mut controls = vec![];
+        for (control, property) in [
+            (KnownCameraControl::Zoom, "zoom"),
+            (KnownCameraControl::Focus, "focusDistance"),
+        ] {
+            let range = js_get(&capabilities, property)?;
+            let (Some(min), Some(max)) = (
+                js_get(&range, "min").ok().and_then(|min| min.as_f64()),
+                js_get(&range, "max").ok().and_then(|max| max.as_f64()),
+            ) else {
+                continue;
+            };
+            let step = js_get(&range, "step")?.as_f64().unwrap_or(0_f64);
+            let value = js_get(&settings, property)?.as_f64().unwrap_or(min);
+
+            // `focusMode` says whether the focus distance is held, or left to the camera
+            let mut flags = vec![KnownCameraControlFlag::Manual];
+            if control == KnownCameraControl::Focus
+                && js_get(&settings, "focusMode")?.as_string().as_deref() != Some("manual")
+            {
+                flags = vec![KnownCameraControlFlag::Continuous];
+            }
+            controls.push(CameraControl::new(
+                control,
+                property.to_string(),
+                ControlValueDescription::FloatRange {
+                    min,
+                    max,
+                    value,
+                    step,
+                    default: value,
+                },
+                flags,
+                true,
+            ));
+        }
+
+        if js_get(&capabilities, "torch")?.as_bool() == Some(true) {
+            controls.push(CameraControl::new(
+                KnownCameraControl::Torch,
+                "torch".to_string(),
+                ControlValueDescription::Boolean {
+                    value: js_get(&settings, "torch")?.as_bool().unwrap_or(false),
+                    default: false,
+                },
+                vec![KnownCameraControlFlag::Manual],
+                true,
+            ));
+        }
+        Ok(controls)
+    }
+
+    /// Gets the [`CameraControl`] of `control`, see [`camera_controls()`](crate::js_camera::JSCamera::camera_controls).
+    /// # Errors
+    /// This will error if the camera does not have `control`, or reading the capabilities fails.
+    pub fn camera_control(
+        &self,
+        control: KnownCameraControl,
+    ) -> Result<CameraControl, NokhwaError> {
+        self.camera_controls()?
+            .into_iter()
+            .find(|camera_control| camera_control.control() == control)
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: control.to_string(),
+                error: "Not supported by the camera".to_string(),
+            })
+    }
+
+    /// Sets `control` to `value` with the track's [`applyConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/applyConstraints).
+    ///
+    /// [`Zoom`](KnownCameraControl::Zoom) and [`Focus`](KnownCameraControl::Focus) take a [`ControlValueSetter::Float`] (or
+    /// [`ControlValueSetter::Integer`]), and setting the focus distance switches `focusMode` to `manual`. [`Torch`](KnownCameraControl::Torch)
+    /// takes a [`ControlValueSetter::Boolean`].
+    /// # Errors
+    /// This will error if `control` is not one of the above, `value` does not fit it, or the browser rejects the constraint.
+    #[allow(clippy::cast_precision_loss)]
+    pub async fn set_camera_control_async(
+        &mut self,
+        control: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        let invalid = || NokhwaError::SetPropertyError {
+            property: control.to_string(),
+            value: format!("{value:?}"),
+            error: "Invalid value".to_string(),
+        };
+        let number = match &value {
+            ControlValueSetter::Float(float) => Some(*float),
+            ControlValueSetter::Integer(int) => Some(*int as f64),
+            _ => None,
+        };
+
+        let constraint = match control {
+            KnownCameraControl::Zoom => js_object(&[("zoom", &jsv!(number.ok_or_else(invalid)?))]),
+            KnownCameraControl::Focus => js_object(&[
+                ("focusMode", &jsv!("manual")),
+                ("focusDistance", &jsv!(number.ok_or_else(invalid)?)),
+            ]),
+            KnownCameraControl::Torch => {
+                js_object(&[("torch", &jsv!(*value.as_boolean().ok_or_else(invalid)?))])
+            }
+            _ => return Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser)),
+        };
+
+        // these are only applied from `advanced`, as `MediaTrackConstraintSet` has no `ideal` or `exact` for them
+        let constraints = js_object(&[("advanced", &Array::of1(&constraint).into())]);
+        js_await(
+            js_call(
+                &self.video_track()?,
+                "applyConstraints",
+                &Array::of1(&constraints),
+            )?,
+            "MediaStreamTrack applyConstraints",
+        )
+        .await
+        .map_err(|why| NokhwaError::SetPropertyError {
+            property: control.to_string(),
+            value: format!("{value:?}"),
+            error: why.to_string(),
+        })?;
+        Ok(())
+    }
+
+    fn video_track(&self) -> Result<JsValue, NokhwaError> {
+        let track = self.media_stream.get_video_tracks().get(0);
+        if track.is_undefined() {
+            return Err(NokhwaError::ReadFrameError("Null Stream".to_string()));
+        }
+        Ok(track)
+    }
+
+    /// Gets what the camera can do for photos.
+    /// # Errors
+    /// This will error if the browser does not have `ImageCapture` (e.g. Firefox and Safari), or the camera has no video track.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub async fn photo_capabilities(&self) -> Result<JSPhotoCapabilities, NokhwaError> {
+        let image_capture = self.image_capture()?;
+        let capabilities = js_await(
+            js_call(&image_capture, "getPhotoCapabilities", &Array::new())?,
+            "ImageCapture getPhotoCapabilities",
+        )
+        .await?;
+
+        let max = |range: &str| {
+            js_get(&capabilities, range)
+                .and_then(|range| js_get(&range, "max"))
+                .ok()
+                .and_then(|max| max.as_f64())
+        };
+        let max_resolution = match (max("imageWidth"), max("imageHeight")) {
+            (Some(width), Some(height)) => Some(Resolution::new(width as u32, height as u32)),
+            _ => None,
+        };
+        let flash_modes = Array::from(&js_get(&capabilities, "fillLightMode")?)
+            .iter()
+            .filter_map(|mode| match mode.as_string().as_deref() {
+                Some("off") => Some(FlashMode::Off),
+                Some("auto") => Some(FlashMode::Auto),
+                Some("flash") => Some(FlashMode::On),
+                _ => None,
+            })
+            .collect();
+        let red_eye_reduction = match js_get(&capabilities, "redEyeReduction")?
+            .as_string()
+            .as_deref()
+        {
+            Some("always") => Some(true),
+            Some("never") => Some(false),
+            _ => None,
+        };
+
+        Ok(JSPhotoCapabilities {
+            max_resolution,
+            flash_modes,
+            red_eye_reduction,
+        })
+    }
+
+    /// Takes a still photo with the [`ImageCapture`](https://developer.mozilla.org/en-US/docs/Web/API/ImageCapture) API, which may be
+    /// larger than the video frames. The flash and red-eye reduction of `settings` are applied where [`photo_capabilities()`](crate::js_camera::JSCamera::photo_capabilities)
+    /// allows them.
+    ///
+    /// [`PhotoCodec::Jpeg`] uses `takePhoto()`, and returns a [`FrameFormat::MJpeg`] buffer. [`PhotoCodec::Uncompressed`] grabs a
+    /// frame from the video as a [`FrameFormat::RgbA8`] buffer, as does a photo in a browser without `ImageCapture` or if `takePhoto()` fails.
+    /// # Errors
+    /// This will error for [`PhotoCodec::Heif`], which browsers do not take, or if grabbing the frame fails.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub async fn take_photo(&mut self, settings: PhotoSettings) -> Result<Buffer, NokhwaError> {
+        match settings.codec() {
+            PhotoCodec::Heif => {
+                return Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+            }
+            PhotoCodec::Uncompressed => return self.grab_photo(),
+            PhotoCodec::Jpeg => {}
+        }
+
+        let Ok(image_capture) = self.image_capture() else {
+            return self.grab_photo();
+        };
+        let mut photo_settings = vec![];
+        if let Some(max_resolution) = settings.max_resolution() {
+            photo_settings.push(("imageWidth", jsv!(max_resolution.width())));
+            photo_settings.push(("imageHeight", jsv!(max_resolution.height())));
+        }
+        if let Some(flash) = settings.flash() {
+            let mode = match flash {
+                FlashMode::Off => "off",
+                FlashMode::Auto => "auto",
+                FlashMode::On => "flash",
+            };
+            photo_settings.push(("fillLightMode", jsv!(mode)));
+        }
+        if let Some(red_eye_reduction) = settings.red_eye_reduction() {
+            photo_settings.push(("redEyeReduction", jsv!(red_eye_reduction)));
+        }
+        let photo_settings = photo_settings
+            .iter()
+            .map(|(key, value)| (*key, value))
+            .collect::<Vec<_>>();
+
+        let Ok(blob) = js_await(
+            js_call(
+                &image_capture,
+                "takePhoto",
+                &Array::of1(&js_object(&photo_settings)),
+            )?,
+            "ImageCapture takePhoto",
+        )
+        .await
+        else {
+            // e.g. the camera can not take photos while streaming, which the browser reports as an `UnknownError`
+            return self.grab_photo();
+        };
+        let data = js_await(
+            js_call(&blob, "arrayBuffer", &Array::new())?,
+            "Blob arrayBuffer",
+        )
+        .await?;
+        let data = Uint8Array::new(&data).to_vec();
+
+        // the photo is not decoded, so its size is the one the browser says it took it at
+        let taken = js_await(
+            js_call(&image_capture, "getPhotoSettings", &Array::new())?,
+            "ImageCapture getPhotoSettings",
+        )
+        .await?;
+        let resolution = Resolution::new(
+            js_get(&taken, "imageWidth")?.as_f64().unwrap_or(0_f64) as u32,
+            js_get(&taken, "imageHeight")?.as_f64().unwrap_or(0_f64) as u32,
+        );
+        Ok(Buffer::new(resolution, &data, FrameFormat::MJpeg))
+    }
+
+    fn grab_photo(&mut self) -> Result<Buffer, NokhwaError> {
+        let data = self.frame_raw()?.to_vec();
+        Ok(Buffer::new(self.resolution(), &data, FrameFormat::RgbA8))
+    }
+
+    fn image_capture(&self) -> Result<JsValue, NokhwaError> {
+        let image_capture_class = js_get(&js_sys::global(), "ImageCapture")?
+            .dyn_into::<Function>()
+            .map_err(|_| NokhwaError::UnsupportedOperationError(ApiBackend::Browser))?;
+        Reflect::construct(&image_capture_class, &Array::of1(&self.video_track()?)).map_err(|why| {
+            NokhwaError::StructureError {
+                structure: "ImageCapture".to_string(),
+                error: format!("{why:?}"),
+            }
+        })
+    }
+
+    /// Runs every frame of the camera through `process` and emits the result as a new `MediaStreamTrack`, which can be sent over
+    /// WebRTC with `RTCPeerConnection.addTrack()` or shown in a `<video>`. This makes the camera usable as a video effects layer.
+    ///
+    /// `process` gets each frame as a [`FrameFormat::RgbA8`] [`Buffer`], timestamped like [`JSWorkerCamera::next_frame()`], and must
+    /// return one as well, at any resolution. The frames
+    /// are processed on the page's thread, in order, as fast as they arrive. Returning an error stops the track.
+    ///
+    /// The camera keeps running and can still be read from, the track works on a clone of it.
+    /// # Errors
+    /// If the browser does not have `MediaStreamTrackProcessor` and `MediaStreamTrackGenerator`, or the stream has no video, this will error.
+    /// # Quirks
+    /// - Frames are copied out of and back into the browser's memory, so large resolutions can not be processed at full frame rate.
+    pub fn processed_track(
+        &self,
+        mut process: impl FnMut(Buffer) -> Result<Buffer, NokhwaError> + 'static,
+    ) -> Result<JSProcessedTrack, NokhwaError> {
+        if !(track_processor_supported() && track_generator_supported()) {
+            return Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser));
+        }
+        // a clone of the track, so ending it ends only the processing
+        let source = MediaStreamTrack::from(self.video_track()?).clone();
+        let reader = js_call(
+            &track_processor_readable(&source)?,
+            "getReader",
+            &Array::new(),
+        )?;
+
+        let generator_class = js_get(&js_sys::global(), "MediaStreamTrackGenerator")?
+            .dyn_into::<Function>()
+            .map_err(|_| NokhwaError::StructureError {
+                structure: "MediaStreamTrackGenerator".to_string(),
+                error: "Not a constructor".to_string(),
+            })?;
+        let generator = Reflect::construct(
+            &generator_class,
+            &Array::of1(&js_object(&[("kind", &jsv!("video"))])),
+        )
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "MediaStreamTrackGenerator".to_string(),
+            error: format!("{why:?}"),
+        })?;
+        let writer = js_call(&js_get(&generator, "writable")?, "getWriter", &Array::new())?;
+
+        let running = Rc::new(Cell::new(true));
+        let pump_running = running.clone();
+        // `MediaStreamTrack::clone()` would clone the track itself, this is another handle to the same one
+        let pump_source = Clone::clone(&source);
+        spawn_local(async move {
+            let mut clock = FrameClock::default();
+            while pump_running.get() {
+                let result = match read_video_frame(&reader).await {
+                    Ok(Some(video_frame)) => {
+                        process_video_frame(&video_frame, &writer, &mut process, &mut clock).await
+                    }
+                    Ok(None) => break,
+                    Err(why) => Err(why),
+                };
+                if let Err(why) = result {
+                    log_1(&jsv!(format!("Processed track stopped: {why}")));
+                    break;
+                }
+            }
+            // ending the source ends the reader, and closing the writer ends the emitted track
+            pump_running.set(false);
+            let _ = js_call(&reader, "cancel", &Array::new());
+            let _ = js_call(&writer, "close", &Array::new());
+            pump_source.stop();
+        });
+
+        Ok(JSProcessedTrack {
+            source,
+            track: generator.unchecked_into(),
+            running,
+        })
+    }
+
+    /// Posts the camera's video to a dedicated `worker`, where [`JSWorkerCamera::from_message()`] reads it. The message is an object
+    /// with the transferred track under [`WORKER_TRACK_KEY`] where the browser can transfer tracks, and otherwise the transferred
+    /// `ReadableStream` of `VideoFrame`s of a `MediaStreamTrackProcessor` under [`WORKER_FRAMES_KEY`].
+    ///
+    /// Once transferred, the frames belong to the worker, so the frame functions of this [`JSCamera`] stop receiving them. Stopping
+    /// (or dropping) this [`JSCamera`] still stops the camera.
+    /// # Errors
+    /// This will error if the camera has no video track, or the browser can neither transfer the track nor has `MediaStreamTrackProcessor`.
+    pub fn transfer_to_worker(&self, worker: &Worker) -> Result<(), NokhwaError> {
+        let track = self.media_stream.get_video_tracks().get(0);
+        if track.is_undefined() {
+            return Err(NokhwaError::ReadFrameError("Null Stream".to_string()));
+        }
+
+        // a clone is transferred, so the page keeps a track to stop the camera with
+        let clone = MediaStreamTrack::from(track).clone();
+        let message = js_object(&[(WORKER_TRACK_KEY, clone.as_ref())]);
+        if worker
+            .post_message_with_transfer(&message, &Array::of1(&clone))
+            .is_ok()
+        {
+            return Ok(());
+        }
+        clone.stop();
+
+        // browsers that can not transfer tracks yet can transfer streams, so the frames are pulled here and handed over
+        if !track_processor_supported() {
+            return Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser));
+        }
+        let readable = track_processor_readable(&self.media_stream.get_video_tracks().get(0))?;
+        let message = js_object(&[(WORKER_FRAMES_KEY, &readable)]);
+        worker
+            .post_message_with_transfer(&message, &Array::of1(&readable))
+            .map_err(|why| NokhwaError::StructureError {
+                structure: "WorkerPostMessage".to_string(),
+                error: format!("{why:?}"),
+            })
+    }
+
+    fn track_reader(&mut self) -> Result<JsValue, NokhwaError> {
+        if let Some(reader) = &self.track_reader {
+            return Ok(reader.clone());
+        }
+
+        let track = self.media_stream.get_video_tracks().get(0);
+        if track.is_undefined() {
+            return Err(NokhwaError::ReadFrameError("Null Stream".to_string()));
+        }
+        let readable = track_processor_readable(&track)?;
+        let reader = js_call(&readable, "getReader", &Array::new())?;
+        self.track_reader = Some(reader.clone());
+        Ok(reader)
+    }
+
     /// This takes the output from [`frame_raw()`](crate::js_camera::JSCamera::frame_raw) and turns it into an `ImageBuffer<Rgb<u8>, Vec<u8>>`.
     /// # Errors
     /// This will error if the frame vec is too small(this is probably a bug, please report it!) or if the frame fails to capture. See [`frame_raw()`](crate::js_camera::JSCamera::frame_raw).
@@ -2633,6 +4398,50 @@ impl JSCamera {
         Ok(texture)
     }
 
+    /// Uploads the frame the camera's `<video>` currently shows into `texture` with `texImage2D()`, as RGBA. The browser copies it
+    /// on the GPU, so the frame is never read back into WASM memory.
+    ///
+    /// The texture is bound to `TEXTURE_2D` of `gl` afterwards. Call this once per rendered frame, e.g. from `requestAnimationFrame()`.
+    /// # Errors
+    /// If the video can not be made or `texImage2D()` fails, this will error.
+    pub fn frame_to_webgl_texture(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        texture: &WebGlTexture,
+    ) -> Result<Resolution, NokhwaError> {
+        let video = self.paced_video()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+        #[allow(clippy::cast_possible_wrap)]
+        gl.tex_image_2d_with_u32_and_u32_and_html_video_element(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            &video,
+        )
+        .map_err(|why| NokhwaError::ProcessFrameError {
+            src: FrameFormat::RgbA8,
+            destination: "WebGLTexture".to_string(),
+            error: format!("{why:?}"),
+        })?;
+        Ok(Resolution::new(video.video_width(), video.video_height()))
+    }
+
+    /// Imports the frame the camera's `<video>` currently shows into the WebGPU `device` (a `GPUDevice`) as a `GPUExternalTexture`,
+    /// which samples the video's memory directly.
+    /// # Errors
+    /// If the video can not be made or `importExternalTexture()` fails, this will error.
+    /// # Quirks
+    /// - The browser expires the texture once the current task ends, so import it again for every rendered frame.
+    pub fn frame_external_texture(
+        &mut self,
+        device: &JsValue,
+    ) -> Result<JSExternalTexture, NokhwaError> {
+        let video = self.paced_video()?;
+        JSExternalTexture::import(device, &video, None)
+    }
+
     /// Checks if the stream is open.
     pub fn is_open(&self) -> bool {
         let stream = self
@@ -2693,6 +4502,13 @@ impl JSCamera {
     /// There may be an error while detaching the camera. Please see [`detach()`](crate::js_camera::JSCamera::detach) for more details.
     pub fn stop_all(&mut self) -> Result<(), NokhwaError> {
         self.detach()?;
+        if let Some(reader) = self.track_reader.take() {
+            let _ = js_call(&reader, "cancel", &Array::new());
+        }
+        if let Some(video) = self.pacing_video.take() {
+            video.set_src_object(None);
+            video.remove();
+        }
         self.media_stream.get_tracks().iter().for_each(|track| {
             let media_track = MediaStreamTrack::from(track);
             media_track.stop();
@@ -2701,20 +4517,933 @@ impl JSCamera {
     }
 }
 
-impl Deref for JSCamera {
-    type Target = MediaStream;
+/// The browser can not wait synchronously, so the [`CaptureTrait`] setters that reconfigure the camera (e.g.
+/// [`set_camera_format()`](CaptureTrait::set_camera_format)) error with [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
+/// Use their [`AsyncCaptureTrait`] versions, which this implements on top of the camera's promises, instead.
+#[cfg(feature = "input-jscam")]
+impl CaptureTrait for JSCamera {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        self.measure_resolution()
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.media_stream
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Browser
     }
-}
 
-impl Drop for JSCamera {
-    fn drop(&mut self) {
-        self.stop_all().unwrap_or(()); // swallow errors
+    fn camera_info(&self) -> &CameraInfo {
+        &self.info
     }
-}
 
-// SAFETY: JSCamera is used in WASM, it will never be sent to a different thread. This is only done to satisfy the compiler.
-#[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl Send for JSCamera {}
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        self.measure_resolution()
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        Some(CameraFormat::new(
+            JSCamera::resolution(self),
+            FrameFormat::RgbA8,
+            self.settings_frame_rate()?,
+        ))
+    }
+
+    fn set_camera_format(&mut self, _: CameraFormat) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        // browsers scale to any resolution and frame rate within the capabilities, so only the current format is listed
+        let mut formats = HashMap::new();
+        if let Some(format) = self
+            .camera_format()
+            .filter(|format| format.format() == fourcc)
+        {
+            formats.insert(format.resolution(), vec![format.frame_rate()]);
+        }
+        Ok(formats)
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Ok(vec![FrameFormat::RgbA8])
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        Some(JSCamera::resolution(self))
+    }
+
+    fn set_resolution(&mut self, _: Resolution) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        self.settings_frame_rate()
+            .map(|frame_rate| frame_rate.as_u32())
+    }
+
+    fn set_frame_rate(&mut self, _: u32) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        FrameFormat::RgbA8
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        if fourcc == FrameFormat::RgbA8 {
+            return Ok(());
+        }
+        Err(NokhwaError::SetPropertyError {
+            property: "FrameFormat".to_string(),
+            value: fourcc.to_string(),
+            error: "The browser only hands out RgbA8".to_string(),
+        })
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        JSCamera::camera_control(self, control)
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        JSCamera::camera_controls(self)
+    }
+
+    fn set_camera_control(
+        &mut self,
+        _: KnownCameraControl,
+        _: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        if self.is_open() {
+            return Ok(());
+        }
+        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.is_open()
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let data = JSCamera::frame_raw(self)?.to_vec();
+        Ok(Buffer::new(
+            JSCamera::resolution(self),
+            &data,
+            FrameFormat::RgbA8,
+        ))
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        JSCamera::frame_raw(self)
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stop_all()
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+#[async_trait::async_trait(?Send)]
+impl AsyncCaptureTrait for JSCamera {
+    async fn init_async(&mut self) -> Result<(), NokhwaError> {
+        self.init()
+    }
+
+    async fn refresh_camera_format_async(&mut self) -> Result<(), NokhwaError> {
+        self.refresh_camera_format()
+    }
+
+    async fn set_camera_format_async(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        self.set_frame_format(new_fmt.format())?;
+        self.set_format(new_fmt).await
+    }
+
+    async fn compatible_list_by_resolution_async(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
+        Ok(self
+            .compatible_list_by_resolution(fourcc)?
+            .into_iter()
+            .map(|(resolution, frame_rates)| {
+                let frame_rates = frame_rates.iter().map(FrameRate::as_u32).collect();
+                (resolution, frame_rates)
+            })
+            .collect())
+    }
+
+    async fn compatible_camera_formats_async(&mut self) -> Result<Vec<CameraFormat>, NokhwaError> {
+        self.compatible_camera_formats()
+    }
+
+    async fn compatible_fourcc_async(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        self.compatible_fourcc()
+    }
+
+    async fn set_resolution_async(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let frame_rate = self.settings_frame_rate().unwrap_or_default();
+        self.set_format(CameraFormat::new(new_res, FrameFormat::RgbA8, frame_rate))
+            .await
+    }
+
+    async fn set_frame_rate_async(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let frame_rate = FrameRate::new_integer(new_fps)?;
+        let resolution = JSCamera::resolution(self);
+        self.set_format(CameraFormat::new(
+            resolution,
+            FrameFormat::RgbA8,
+            frame_rate,
+        ))
+        .await
+    }
+
+    async fn set_frame_format_async(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        self.set_frame_format(fourcc)
+    }
+
+    async fn set_camera_control_async(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        JSCamera::set_camera_control_async(self, id, value).await
+    }
+
+    async fn open_stream_async(&mut self) -> Result<(), NokhwaError> {
+        if self.is_open() {
+            return Ok(());
+        }
+        self.restart().await
+    }
+
+    async fn frame_async(&mut self) -> Result<Buffer, NokhwaError> {
+        self.next_frame().await
+    }
+
+    async fn frame_raw_async(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        JSCamera::frame_raw_async(self).await
+    }
+
+    async fn stop_stream_async(&mut self) -> Result<(), NokhwaError> {
+        self.stop_all()
+    }
+}
+
+impl Deref for JSCamera {
+    type Target = MediaStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.media_stream
+    }
+}
+
+impl Drop for JSCamera {
+    fn drop(&mut self) {
+        self.stop_all().unwrap_or(()); // swallow errors
+    }
+}
+
+// SAFETY: JSCamera is used in WASM, it will never be sent to a different thread. This is only done to satisfy the compiler.
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl Send for JSCamera {}
+
+// `None` once the watcher is stopped or restarted, so enumerations still running drop their results
+type WatcherState = Rc<RefCell<Option<WatchedCameras>>>;
+
+struct WatchedCameras {
+    known: Option<Vec<CameraInfo>>,
+    callback: Box<dyn FnMut(DeviceEvent) + Send + 'static>,
+}
+
+/// Watches for cameras being plugged in or unplugged using the [`devicechange`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/devicechange_event)
+/// event. To see what this does, please see [`DeviceWatcher`].
+///
+/// The event does not say what changed, so the cameras are listed again with [`enumerate_js_cameras()`] and compared to the
+/// previous list.
+/// # Quirks
+/// - The callback is called on the page's (or worker's) event loop, not a different thread.
+/// - Until permission is granted, browsers hide the device IDs, so at most one camera is listed and changes may go unnoticed.
+///   Once permission is granted, the cameras show up with their IDs and are reported as plugged in, and the unnamed one as unplugged.
+/// - The screen pseudo-device is never reported.
+#[cfg(feature = "input-jscam")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-jscam")))]
+#[derive(Default)]
+pub struct JSDeviceWatcher {
+    state: WatcherState,
+    on_device_change: Option<Closure<dyn FnMut()>>,
+}
+
+#[cfg(feature = "input-jscam")]
+impl JSDeviceWatcher {
+    /// Creates a new, stopped, [`JSDeviceWatcher`].
+    #[must_use]
+    pub fn new() -> Self {
+        JSDeviceWatcher::default()
+    }
+
+    // lists the cameras and reports the difference to the last list. The first list is only remembered.
+    async fn refresh(state: WatcherState) {
+        let Ok(cameras) = enumerate_js_cameras().await else {
+            return;
+        };
+        let cameras = cameras
+            .into_iter()
+            .filter(|camera| camera.kind() == DeviceKind::Camera)
+            .collect::<Vec<_>>();
+
+        let mut state = state.borrow_mut();
+        let Some(watched) = state.as_mut() else {
+            return;
+        };
+        let Some(known) = watched.known.replace(cameras.clone()) else {
+            return;
+        };
+        for gone in known
+            .iter()
+            .filter(|old| !cameras.iter().any(|new| new.index() == old.index()))
+        {
+            (watched.callback)(DeviceEvent::Disconnected(gone.clone()));
+        }
+        for added in cameras
+            .iter()
+            .filter(|new| !known.iter().any(|old| old.index() == new.index()))
+        {
+            (watched.callback)(DeviceEvent::Connected(added.clone()));
+        }
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl DeviceWatcher for JSDeviceWatcher {
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Browser
+    }
+
+    fn start(
+        &mut self,
+        callback: Box<dyn FnMut(DeviceEvent) + Send + 'static>,
+    ) -> Result<(), NokhwaError> {
+        self.stop()?;
+        let window: Window = window()?;
+        let media_devices = media_devices(&window.navigator())?;
+
+        let state: WatcherState = Rc::new(RefCell::new(Some(WatchedCameras {
+            known: None,
+            callback,
+        })));
+        // the cameras that are already connected are only remembered
+        spawn_local(JSDeviceWatcher::refresh(state.clone()));
+
+        let on_change_state = state.clone();
+        let on_device_change = Closure::<dyn FnMut()>::new(move || {
+            spawn_local(JSDeviceWatcher::refresh(on_change_state.clone()));
+        });
+        media_devices.set_ondevicechange(Some(on_device_change.as_ref().unchecked_ref()));
+
+        self.state = state;
+        self.on_device_change = Some(on_device_change);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), NokhwaError> {
+        if self.on_device_change.take().is_some() {
+            let window: Window = window()?;
+            media_devices(&window.navigator())?.set_ondevicechange(None);
+        }
+        self.state.borrow_mut().take();
+        Ok(())
+    }
+
+    fn is_watching(&self) -> bool {
+        self.on_device_change.is_some()
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl Drop for JSDeviceWatcher {
+    fn drop(&mut self) {
+        self.stop().unwrap_or(()); // swallow errors
+    }
+}
+
+/// The message [`JSCamera::transfer_to_worker()`] posts has the track under this key, if the browser could transfer it.
+pub const WORKER_TRACK_KEY: &str = "nokhwaTrack";
+/// The message [`JSCamera::transfer_to_worker()`] posts has a stream of `VideoFrame`s under this key, if the track could not be transferred.
+pub const WORKER_FRAMES_KEY: &str = "nokhwaFrames";
+
+/// Reads the frames of a [`JSCamera`] in a dedicated worker, so converting and processing them does not block the page.
+///
+/// On the main thread, [`JSCamera::transfer_to_worker()`] posts the camera's video to the worker. In the worker's `onmessage`,
+/// [`JSWorkerCamera::from_message()`] takes it from the event's `data`:
+/// ```ignore
+/// // main thread
+/// camera.transfer_to_worker(&worker)?;
+/// // worker
+/// let mut camera = JSWorkerCamera::from_message(&event.data())?;
+/// let frame = camera.frame().await?;
+/// ```
+/// # Quirks
+/// - The worker needs `MediaStreamTrackProcessor` (Chromium and Safari have it in workers) if the track was transferred. Otherwise
+///   the main thread needs it, and the frames are read there and handed to the worker as a transferred `ReadableStream`.
+/// - Only dedicated workers can receive the video; shared and service workers can not.
+#[cfg(feature = "input-jscam")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-jscam")))]
+pub struct JSWorkerCamera {
+    track: Option<MediaStreamTrack>,
+    reader: Option<JsValue>,
+    resolution: Resolution,
+    clock: FrameClock,
+}
+
+#[cfg(feature = "input-jscam")]
+impl JSWorkerCamera {
+    /// Takes the video posted by [`JSCamera::transfer_to_worker()`] from the `data` of the worker's `message` event.
+    /// # Errors
+    /// This will error if `data` is not a message from [`JSCamera::transfer_to_worker()`], or the worker can not read the transferred track.
+    pub fn from_message(data: &JsValue) -> Result<Self, NokhwaError> {
+        let frames = js_get(data, WORKER_FRAMES_KEY)?;
+        let (track, readable) = if frames.is_undefined() {
+            let track = js_get(data, WORKER_TRACK_KEY)?;
+            if track.is_undefined() {
+                return Err(NokhwaError::StructureError {
+                    structure: "JSWorkerCamera".to_string(),
+                    error: "Not a message from JSCamera::transfer_to_worker()".to_string(),
+                });
+            }
+            let readable = track_processor_readable(&track)?;
+            (Some(MediaStreamTrack::from(track)), readable)
+        } else {
+            (None, frames)
+        };
+
+        Ok(JSWorkerCamera {
+            track,
+            reader: Some(js_call(&readable, "getReader", &Array::new())?),
+            resolution: Resolution::new(0, 0),
+            clock: FrameClock::default(),
+        })
+    }
+
+    /// Gets the [`Resolution`] of the last frame read.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Waits for the next frame, as RGBA.
+    /// # Errors
+    /// If the camera has been stopped, the track has ended, or reading the frame fails, this will error.
+    pub async fn frame_raw(&mut self) -> Result<Vec<u8>, NokhwaError> {
+        let video_frame = self.next_video_frame().await?;
+        let copied = copy_video_frame(&video_frame).await;
+        // frames hold on to the camera's buffers until they are closed, and the track stalls once it runs out
+        let _ = js_call(&video_frame, "close", &Array::new());
+        let (data, resolution) = copied?;
+        self.resolution = resolution;
+        Ok(data)
+    }
+
+    /// Waits for the next frame, as a [`FrameFormat::RgbA8`] [`Buffer`]. Its timestamp is the frame's `timestamp`, put on the
+    /// worker's [`performance_now()`] clock, so subtracting it from that measures the frame's latency.
+    /// # Errors
+    /// See [`frame_raw()`](crate::js_camera::JSWorkerCamera::frame_raw).
+    /// # Quirks
+    /// - Browsers do not say which clock `VideoFrame.timestamp` is on, so the first frame is taken to be read as soon as it was captured.
+    ///   Later timestamps keep their spacing from it, and the latency measured is how much longer they took than the first frame.
+    pub async fn next_frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let video_frame = self.next_video_frame().await?;
+        let timestamp = self.clock.timestamp(&video_frame);
+        let copied = copy_video_frame(&video_frame).await;
+        let _ = js_call(&video_frame, "close", &Array::new());
+        let (data, resolution) = copied?;
+        self.resolution = resolution;
+        let buffer = Buffer::new(resolution, &data, FrameFormat::RgbA8);
+        Ok(match timestamp {
+            Some(timestamp) => buffer.with_timestamp(timestamp),
+            None => buffer,
+        })
+    }
+
+    /// Waits for the next frame, as an `ImageBuffer<Rgb<u8>, Vec<u8>>`.
+    /// # Errors
+    /// See [`frame_raw()`](crate::js_camera::JSWorkerCamera::frame_raw).
+    pub async fn frame(&mut self) -> Result<RgbImage, NokhwaError> {
+        let raw_data = self.frame_raw().await?;
+        let resolution = self.resolution;
+        let Some(rgba_buf) = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_vec(
+            resolution.width(),
+            resolution.height(),
+            raw_data,
+        ) else {
+            return Err(NokhwaError::ReadFrameError(
+                "ImageBuffer is not large enough! This is probably a bug, please report it!"
+                    .to_string(),
+            ));
+        };
+        Ok(rgba_buf.convert())
+    }
+
+    /// Waits for the next frame and hands it out as a WebCodecs [`VideoFrame`](https://developer.mozilla.org/en-US/docs/Web/API/VideoFrame),
+    /// without reading it back into WASM memory. See [`JSCamera::video_frame()`], it must be closed just the same.
+    /// # Errors
+    /// If the camera has been stopped or the track has ended, this will error.
+    #[cfg(feature = "output-webcodecs")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-webcodecs")))]
+    pub async fn video_frame(&mut self) -> Result<VideoFrame, NokhwaError> {
+        Ok(self.next_video_frame().await?.unchecked_into())
+    }
+
+    /// Waits for the next frame and uploads it into `texture` with `texImage2D()`, as RGBA, without reading it back into WASM memory.
+    /// `gl` is the `WebGL2RenderingContext` of an `OffscreenCanvas` in the worker, and the texture is bound to its `TEXTURE_2D` afterwards.
+    /// # Errors
+    /// If the camera has been stopped, the track has ended, or `texImage2D()` fails, this will error.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub async fn frame_to_webgl_texture(
+        &mut self,
+        gl: &JsValue,
+        texture: &JsValue,
+    ) -> Result<Resolution, NokhwaError> {
+        const TEXTURE_2D: u32 = 0x0DE1;
+        const RGBA: u32 = 0x1908;
+        const UNSIGNED_BYTE: u32 = 0x1401;
+
+        let video_frame = self.next_video_frame().await?;
+        let uploaded = js_call(gl, "bindTexture", &Array::of2(&jsv!(TEXTURE_2D), texture))
+            .and_then(|_| {
+                let args = Array::new();
+                for arg in [
+                    jsv!(TEXTURE_2D),
+                    jsv!(0),
+                    jsv!(RGBA),
+                    jsv!(RGBA),
+                    jsv!(UNSIGNED_BYTE),
+                ] {
+                    args.push(&arg);
+                }
+                args.push(&video_frame);
+                js_call(gl, "texImage2D", &args)
+            });
+        let resolution = js_get(&video_frame, "displayWidth").and_then(|width| {
+            js_get(&video_frame, "displayHeight").map(|height| {
+                Resolution::new(
+                    width.as_f64().unwrap_or(0_f64) as u32,
+                    height.as_f64().unwrap_or(0_f64) as u32,
+                )
+            })
+        });
+        // closed before any error is returned, or the track stalls
+        let _ = js_call(&video_frame, "close", &Array::new());
+        uploaded?;
+        let resolution = resolution?;
+        self.resolution = resolution;
+        Ok(resolution)
+    }
+
+    /// Waits for the next frame and imports it into the WebGPU `device` (a `GPUDevice`) as a `GPUExternalTexture`, without reading it
+    /// back into WASM memory. The frame is closed when the returned texture is dropped.
+    /// # Errors
+    /// If the camera has been stopped, the track has ended, or `importExternalTexture()` fails, this will error.
+    pub async fn frame_external_texture(
+        &mut self,
+        device: &JsValue,
+    ) -> Result<JSExternalTexture, NokhwaError> {
+        let video_frame = self.next_video_frame().await?;
+        JSExternalTexture::import(device, &video_frame.clone(), Some(video_frame))
+    }
+
+    /// Waits for the next frame and draws it on `canvas`, resizing the canvas to the frame. This skips copying the frame into WASM memory.
+    /// # Errors
+    /// If the camera has been stopped, the track has ended, or the canvas has no 2D context, this will error.
+    pub async fn frame_to_canvas(
+        &mut self,
+        canvas: &OffscreenCanvas,
+    ) -> Result<Resolution, NokhwaError> {
+        let video_frame = self.next_video_frame().await?;
+        let drawn = Self::draw_video_frame(&video_frame, canvas);
+        let _ = js_call(&video_frame, "close", &Array::new());
+        self.resolution = drawn?;
+        Ok(self.resolution)
+    }
+
+    /// Stops reading frames, and stops the track if it was transferred to the worker.
+    pub fn stop(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            let _ = js_call(&reader, "cancel", &Array::new());
+        }
+        if let Some(track) = self.track.take() {
+            track.stop();
+        }
+    }
+
+    async fn next_video_frame(&mut self) -> Result<JsValue, NokhwaError> {
+        let reader = self
+            .reader
+            .clone()
+            .ok_or_else(|| NokhwaError::ReadFrameError("The camera is stopped".to_string()))?;
+        match read_video_frame(&reader).await? {
+            Some(video_frame) => Ok(video_frame),
+            None => {
+                self.stop();
+                Err(NokhwaError::ReadFrameError(
+                    "The track has ended".to_string(),
+                ))
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn draw_video_frame(
+        video_frame: &JsValue,
+        canvas: &OffscreenCanvas,
+    ) -> Result<Resolution, NokhwaError> {
+        let resolution = Resolution::new(
+            js_get(video_frame, "displayWidth")?
+                .as_f64()
+                .unwrap_or(0_f64) as u32,
+            js_get(video_frame, "displayHeight")?
+                .as_f64()
+                .unwrap_or(0_f64) as u32,
+        );
+        if canvas.width() != resolution.width() || canvas.height() != resolution.height() {
+            canvas.set_width(resolution.width());
+            canvas.set_height(resolution.height());
+        }
+        let context = canvas
+            .get_context("2d")
+            .map_err(|why| NokhwaError::StructureError {
+                structure: "OffscreenCanvasRenderingContext2D".to_string(),
+                error: format!("{why:?}"),
+            })?
+            .ok_or_else(|| NokhwaError::StructureError {
+                structure: "OffscreenCanvasRenderingContext2D".to_string(),
+                error: "None".to_string(),
+            })?;
+        js_call(
+            &context,
+            "drawImage",
+            &Array::of3(video_frame, &jsv!(0), &jsv!(0)),
+        )?;
+        Ok(resolution)
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl Drop for JSWorkerCamera {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A WebGPU `GPUExternalTexture` of a camera frame, made by [`JSCamera::frame_external_texture()`] or
+/// [`JSWorkerCamera::frame_external_texture()`]. It keeps the `VideoFrame` it samples alive, and closes it on drop.
+#[cfg(feature = "input-jscam")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-jscam")))]
+pub struct JSExternalTexture {
+    texture: JsValue,
+    video_frame: Option<JsValue>,
+}
+
+#[cfg(feature = "input-jscam")]
+impl JSExternalTexture {
+    // `source` is a `<video>` or a `VideoFrame`, the latter kept as `video_frame` so it is closed with the texture
+    fn import(
+        device: &JsValue,
+        source: &JsValue,
+        video_frame: Option<JsValue>,
+    ) -> Result<Self, NokhwaError> {
+        let descriptor = js_object(&[("source", source)]);
+        let imported = js_call(device, "importExternalTexture", &Array::of1(&descriptor));
+        match imported {
+            Ok(texture) => Ok(JSExternalTexture {
+                texture,
+                video_frame,
+            }),
+            Err(why) => {
+                if let Some(video_frame) = video_frame {
+                    let _ = js_call(&video_frame, "close", &Array::new());
+                }
+                Err(why)
+            }
+        }
+    }
+
+    /// Gets the `GPUExternalTexture`, to put in a bind group as a `texture_external`. It is only valid while this is alive.
+    #[must_use]
+    pub fn texture(&self) -> &JsValue {
+        &self.texture
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl Drop for JSExternalTexture {
+    fn drop(&mut self) {
+        if let Some(video_frame) = &self.video_frame {
+            let _ = js_call(video_frame, "close", &Array::new());
+        }
+    }
+}
+
+/// A `MediaStreamTrack` of processed camera frames, made by [`JSCamera::processed_track()`].
+///
+/// The track stops when this is dropped, when [`stop()`](JSProcessedTrack::stop) is called, or when the camera is stopped.
+#[cfg(feature = "input-jscam")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-jscam")))]
+pub struct JSProcessedTrack {
+    source: MediaStreamTrack,
+    track: MediaStreamTrack,
+    running: Rc<Cell<bool>>,
+}
+
+#[cfg(feature = "input-jscam")]
+impl JSProcessedTrack {
+    /// Gets the emitted track.
+    #[must_use]
+    pub fn track(&self) -> &MediaStreamTrack {
+        &self.track
+    }
+
+    /// Makes a [`MediaStream`] of the emitted track, to set as a `<video>`'s `srcObject` or add to an `RTCPeerConnection`.
+    /// # Errors
+    /// If the stream can not be made, this will error.
+    pub fn media_stream(&self) -> Result<MediaStream, NokhwaError> {
+        MediaStream::new_with_tracks(&Array::of1(&self.track)).map_err(|why| {
+            NokhwaError::StructureError {
+                structure: "MediaStream".to_string(),
+                error: format!("{why:?}"),
+            }
+        })
+    }
+
+    /// Returns `true` while frames are still being processed.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.running.get()
+    }
+
+    /// Stops processing and ends the emitted track. The camera itself keeps running.
+    pub fn stop(&self) {
+        self.running.set(false);
+        // ends the frame being waited on, so the processing loop stops now instead of after the next frame
+        self.source.stop();
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl Drop for JSProcessedTrack {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// the chunks waiting to be read, in order, and whether they are being read
+struct RecordedChunks {
+    pending: VecDeque<JsValue>,
+    draining: bool,
+    on_data: Box<dyn FnMut(Vec<u8>)>,
+}
+
+type RecorderState = Rc<RefCell<RecordedChunks>>;
+
+/// Records a [`JSCamera`] into a file with the browser's [`MediaRecorder`](https://developer.mozilla.org/en-US/docs/Web/API/MediaRecorder),
+/// encoded by the browser (usually WebM, or MP4 in Safari).
+///
+/// The recording is handed out in chunks through the callback, in order. Appending them all gives the file.
+/// # Quirks
+/// - WebM files from `MediaRecorder` have no duration and no cues, so some players can not seek in them.
+/// - Which types are supported differs between browsers, see [`JSRecorder::is_type_supported()`].
+#[cfg(feature = "input-jscam")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input-jscam")))]
+pub struct JSRecorder {
+    recorder: JsValue,
+    mime_type: String,
+    on_data_available: Option<Closure<dyn FnMut(JsValue)>>,
+}
+
+#[cfg(feature = "input-jscam")]
+impl JSRecorder {
+    /// The types tried, in order, when no type is given.
+    pub const PREFERRED_TYPES: [&'static str; 4] = [
+        "video/webm;codecs=vp9",
+        "video/webm;codecs=vp8",
+        "video/webm",
+        "video/mp4",
+    ];
+
+    /// Returns `true` if the browser can record `mime_type` (e.g. `video/webm;codecs=vp9`).
+    #[must_use]
+    pub fn is_type_supported(mime_type: &str) -> bool {
+        js_get(&js_sys::global(), "MediaRecorder")
+            .and_then(|class| js_call(&class, "isTypeSupported", &Array::of1(&jsv!(mime_type))))
+            .map(|supported| supported.as_bool() == Some(true))
+            .unwrap_or(false)
+    }
+
+    /// Creates a stopped recorder of `camera`'s stream, recording `mime_type`, or the first supported of
+    /// [`PREFERRED_TYPES`](JSRecorder::PREFERRED_TYPES) if it is `None`. `on_data` gets the chunks of the recording.
+    /// # Errors
+    /// If the browser does not have `MediaRecorder` or can not record the type, this will error.
+    pub fn new(
+        camera: &JSCamera,
+        mime_type: Option<&str>,
+        on_data: impl FnMut(Vec<u8>) + 'static,
+    ) -> Result<Self, NokhwaError> {
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type.to_string(),
+            None => JSRecorder::PREFERRED_TYPES
+                .into_iter()
+                .find(|mime_type| JSRecorder::is_type_supported(mime_type))
+                .ok_or(NokhwaError::UnsupportedOperationError(ApiBackend::Browser))?
+                .to_string(),
+        };
+        let recorder_class = js_get(&js_sys::global(), "MediaRecorder")?
+            .dyn_into::<Function>()
+            .map_err(|_| NokhwaError::UnsupportedOperationError(ApiBackend::Browser))?;
+        let recorder = Reflect::construct(
+            &recorder_class,
+            &Array::of2(
+                &camera.media_stream(),
+                &js_object(&[("mimeType", &jsv!(mime_type.as_str()))]),
+            ),
+        )
+        .map_err(|why| NokhwaError::StructureError {
+            structure: "MediaRecorder".to_string(),
+            error: format!("{why:?}"),
+        })?;
+
+        let state: RecorderState = Rc::new(RefCell::new(RecordedChunks {
+            pending: VecDeque::new(),
+            draining: false,
+            on_data: Box::new(on_data),
+        }));
+        let on_data_available = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+            let Ok(blob) = js_get(&event, "data") else {
+                return;
+            };
+            let mut chunks = state.borrow_mut();
+            chunks.pending.push_back(blob);
+            if !chunks.draining {
+                chunks.draining = true;
+                spawn_local(JSRecorder::drain(state.clone()));
+            }
+        });
+        Reflect::set(
+            &recorder,
+            &jsv!("ondataavailable"),
+            on_data_available.as_ref(),
+        )
+        .map_err(|why| NokhwaError::SetPropertyError {
+            property: "MediaRecorder-ondataavailable".to_string(),
+            value: "Closure".to_string(),
+            error: format!("{why:?}"),
+        })?;
+
+        Ok(JSRecorder {
+            recorder,
+            mime_type,
+            on_data_available: Some(on_data_available),
+        })
+    }
+
+    // reads the chunks one after the other, as reading a `Blob` is asynchronous and they would otherwise race
+    async fn drain(state: RecorderState) {
+        loop {
+            let Some(blob) = state.borrow_mut().pending.pop_front() else {
+                state.borrow_mut().draining = false;
+                return;
+            };
+            let data = match js_call(&blob, "arrayBuffer", &Array::new()) {
+                Ok(promise) => js_await(promise, "Blob arrayBuffer").await,
+                Err(why) => Err(why),
+            };
+            match data {
+                Ok(buffer) => (state.borrow_mut().on_data)(Uint8Array::new(&buffer).to_vec()),
+                Err(why) => log_1(&jsv!(format!("Failed to read recording chunk: {why}"))),
+            }
+        }
+    }
+
+    /// Gets the type being recorded.
+    #[must_use]
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// Starts recording. With a `timeslice`, a chunk is handed out every `timeslice`, otherwise the whole recording is one chunk
+    /// handed out on [`stop()`](JSRecorder::stop).
+    /// # Errors
+    /// If the recorder is already recording, this will error.
+    pub fn start(&mut self, timeslice: Option<Duration>) -> Result<(), NokhwaError> {
+        let args = match timeslice {
+            #[allow(clippy::cast_precision_loss)]
+            Some(timeslice) => Array::of1(&jsv!(timeslice.as_millis() as f64)),
+            None => Array::new(),
+        };
+        js_call(&self.recorder, "start", &args).map(drop)
+    }
+
+    /// Pauses recording, without ending the file.
+    /// # Errors
+    /// If the recorder is stopped, this will error.
+    pub fn pause(&mut self) -> Result<(), NokhwaError> {
+        js_call(&self.recorder, "pause", &Array::new()).map(drop)
+    }
+
+    /// Resumes a paused recording.
+    /// # Errors
+    /// If the recorder is stopped, this will error.
+    pub fn resume(&mut self) -> Result<(), NokhwaError> {
+        js_call(&self.recorder, "resume", &Array::new()).map(drop)
+    }
+
+    /// Stops recording and hands out the last chunk. The recorder can be started again, which begins a new file.
+    /// # Errors
+    /// If the recorder can not be stopped, this will error.
+    pub fn stop(&mut self) -> Result<(), NokhwaError> {
+        if self.is_stopped() {
+            return Ok(());
+        }
+        js_call(&self.recorder, "stop", &Array::new()).map(drop)
+    }
+
+    /// Returns `true` if the recorder is recording and not paused.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.state().as_deref() == Some("recording")
+    }
+
+    /// Returns `true` if the recorder is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.state().as_deref() == Some("paused")
+    }
+
+    /// Returns `true` if the recorder is not recording.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.state().as_deref() == Some("inactive")
+    }
+
+    fn state(&self) -> Option<String> {
+        js_get(&self.recorder, "state").ok()?.as_string()
+    }
+}
+
+#[cfg(feature = "input-jscam")]
+impl Drop for JSRecorder {
+    fn drop(&mut self) {
+        let Some(on_data_available) = self.on_data_available.take() else {
+            return;
+        };
+        if self.is_stopped() {
+            let _ = Reflect::set(&self.recorder, &jsv!("ondataavailable"), &JsValue::NULL);
+        } else {
+            // the last chunk is handed out after stopping, so the closure has to outlive the recorder
+            let _ = self.stop();
+            on_data_available.forget();
+        }
+    }
+}
@@ -33,6 +33,16 @@
 /// Raw access to each of Nokhwa's backends.
 pub mod backends;
 mod camera;
+/// Camera setups described in TOML or JSON files.
+#[cfg(feature = "config")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "config")))]
+pub mod camera_config;
+/// Captures a depth stream and a color stream together, pairing their frames by timestamp.
+pub mod depth_camera;
+/// Caches the cameras of several backends, listing each camera once.
+pub mod device_registry;
+/// Built-in [`FrameSink`](nokhwa_core::traits::FrameSink)s and a capture loop that writes to several of them.
+pub mod frame_sinks;
 mod init;
 /// A camera that uses native browser APIs meant for WASM applications.
 #[cfg(feature = "input-jscam")]
@@ -45,20 +55,49 @@ pub use nokhwa_core::pixel_format::FormatDecoder;
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-async")))]
 pub mod async_camera;
 mod query;
+/// Captures the two sensors of a stereo module as left and right frames.
+pub mod stereo_camera;
 /// A camera that runs in a different thread and can call your code based on callbacks.
 #[cfg(feature = "output-threaded")]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-threaded")))]
 pub mod threaded;
+/// A clonable handle that shares one camera between threads and async tasks without a lock.
+#[cfg(feature = "output-shared")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-shared")))]
+pub mod shared_camera;
+/// Drops or flags broken frames before they reach a decoder.
+pub mod validated_camera;
+/// Recovers streams that silently stop delivering frames.
+pub mod watchdog_camera;
 pub mod decoders;
+/// A sink that writes camera frames to a file or stream URL using `FFmpeg`.
+#[cfg(feature = "output-ffmpeg")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-ffmpeg")))]
+pub mod ffmpeg_sink;
 
 pub use camera::Camera;
+#[cfg(feature = "config")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "config")))]
+pub use camera_config::CameraConfig;
+pub use depth_camera::{DepthColorCamera, DepthFrame};
+pub use device_registry::{DeviceHandle, DeviceRegistry, RegisteredDevice};
+pub use frame_sinks::{CaptureLoop, ImageSequenceSink, RawStreamSink, VirtualCameraSink};
 pub use init::*;
 pub use nokhwa_core::buffer::Buffer;
 pub use nokhwa_core::error::NokhwaError;
 pub use query::*;
+pub use stereo_camera::{StereoCamera, StereoFrame, StereoLayout};
+pub use validated_camera::{ValidatedCamera, ValidatedFrame};
+pub use watchdog_camera::{WatchdogCamera, WatchdogPolicy};
 #[cfg(feature = "output-threaded")]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-threaded")))]
 pub use threaded::CallbackCamera;
+#[cfg(feature = "output-shared")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-shared")))]
+pub use shared_camera::SharedCamera;
+#[cfg(feature = "output-ffmpeg")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-ffmpeg")))]
+pub use ffmpeg_sink::FfmpegSink;
 
 pub mod utils {
     pub use nokhwa_core::types::*;
@@ -79,3 +118,27 @@ pub mod pixel_format {
 pub mod buffer {
     pub use nokhwa_core::buffer::*;
 }
+
+pub mod depth {
+    pub use nokhwa_core::depth::*;
+}
+
+pub mod thermal {
+    pub use nokhwa_core::thermal::*;
+}
+
+pub mod validation {
+    pub use nokhwa_core::validation::*;
+}
+
+pub mod profiling {
+    pub use nokhwa_core::profiling::*;
+}
+
+pub mod events {
+    pub use nokhwa_core::events::*;
+}
+
+pub mod lut {
+    pub use nokhwa_core::lut::*;
+}
@@ -16,7 +16,7 @@
 
 use nokhwa_core::{
     error::NokhwaError,
-    types::{ApiBackend, CameraInfo},
+    types::{ApiBackend, BackendCapabilities, BackendCapability, CameraInfo},
 };
 
 /// Gets the native [`ApiBackend`]
@@ -26,6 +26,51 @@ pub fn native_api_backend() -> Option<ApiBackend> {
         "linux" => Some(ApiBackend::Video4Linux),
         "macos" | "ios" => Some(ApiBackend::AVFoundation),
         "windows" => Some(ApiBackend::MediaFoundation),
+        "android" => Some(ApiBackend::Android),
+        _ => None,
+    }
+}
+
+/// Gets the [`ApiBackend`] that [`ApiBackend::Auto`] stands for, which is the first compiled in backend that works on this platform.
+/// Returns `None` if there is none.
+///
+/// On Linux, this is `PipeWire` inside a Flatpak or Snap sandbox, where `/dev/video*` can not be opened.
+#[must_use]
+pub fn auto_api_backend() -> Option<ApiBackend> {
+    match std::env::consts::OS {
+        "linux" => {
+            if cfg!(all(feature = "input-pipewire", target_os = "linux")) && is_sandboxed() {
+                Some(ApiBackend::PipeWire)
+            } else if cfg!(all(feature = "input-v4l", target_os = "linux")) {
+                Some(ApiBackend::Video4Linux)
+            } else if cfg!(all(feature = "input-libcamera", target_os = "linux")) {
+                Some(ApiBackend::LibCamera)
+            } else if cfg!(feature = "input-opencv") {
+                Some(ApiBackend::OpenCv)
+            } else {
+                None
+            }
+        }
+        "windows" => {
+            if cfg!(all(feature = "input-msmf", target_os = "windows")) {
+                Some(ApiBackend::MediaFoundation)
+            } else if cfg!(feature = "input-opencv") {
+                Some(ApiBackend::OpenCv)
+            } else {
+                None
+            }
+        }
+        "macos" => {
+            if cfg!(feature = "input-avfoundation") {
+                Some(ApiBackend::AVFoundation)
+            } else if cfg!(feature = "input-opencv") {
+                Some(ApiBackend::OpenCv)
+            } else {
+                None
+            }
+        }
+        "android" if cfg!(feature = "input-android") => Some(ApiBackend::Android),
+        "ios" if cfg!(feature = "input-avfoundation") => Some(ApiBackend::AVFoundation),
         _ => None,
     }
 }
@@ -37,72 +82,123 @@ pub fn native_api_backend() -> Option<ApiBackend> {
 /// - `Media Foundation`: The symbolic link for the device is listed in the `misc` attribute of the [`CameraInfo`].
 /// - `Media Foundation`: The names may contain invalid characters since they were converted from UTF16.
 /// - `AVFoundation`: The ID of the device is stored in the `misc` attribute of the [`CameraInfo`].
+/// - `libcamera`: The camera ID is stored in the `misc` attribute of the [`CameraInfo`].
+/// - `PipeWire`: The node ID is stored in the `misc` attribute of the [`CameraInfo`]. Querying asks the user for camera access the first time.
+/// - On Linux, [`ApiBackend::Auto`] picks `PipeWire` inside a Flatpak or Snap sandbox, where `/dev/video*` can not be opened.
 /// - `AVFoundation`: There is lots of miscellaneous info in the `desc` attribute.
+/// - `Android`: The camera2 camera ID is stored in the `misc` attribute of the [`CameraInfo`].
+/// - `Aravis`: The `Aravis` device ID is stored in the `misc` attribute of the [`CameraInfo`].
+/// - `Network`: Querying listens for WS-Discovery answers for two seconds. The device service URL is stored in the `misc` attribute of the [`CameraInfo`].
 /// - `WASM`: The `misc` field contains the device ID and group ID are seperated by a space (' ')
 /// # Errors
 /// If you use an unsupported API (check the README or crate root for more info), incompatible backend for current platform, incompatible platform, or insufficient permissions, etc
 /// this will error.
 pub fn query(api: ApiBackend) -> Result<Vec<CameraInfo>, NokhwaError> {
     match api {
-        ApiBackend::Auto => {
-            // determine platform
-            match std::env::consts::OS {
-                "linux" => {
-                    if cfg!(feature = "input-v4l") && cfg!(target_os = "linux") {
-                        query(ApiBackend::Video4Linux)
-                    } else if cfg!(feature = "input-opencv") {
-                        query(ApiBackend::OpenCv)
-                    } else {
-                        dbg!("Error: No suitable Backends available. Perhaps you meant to enable one of the backends such as `input-v4l`? (Please read the docs.)");
-                        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))
-                    }
-                }
-                "windows" => {
-                    if cfg!(feature = "input-msmf") && cfg!(target_os = "windows") {
-                        query(ApiBackend::MediaFoundation)
-                    } else if cfg!(feature = "input-opencv") {
-                        query(ApiBackend::OpenCv)
-                    } else {
-                        dbg!("Error: No suitable Backends available. Perhaps you meant to enable one of the backends such as `input-msmf`? (Please read the docs.)");
-                        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))
-                    }
-                }
-                "macos" => {
-                    if cfg!(feature = "input-avfoundation") {
-                        query(ApiBackend::AVFoundation)
-                    } else if cfg!(feature = "input-opencv") {
-                        query(ApiBackend::OpenCv)
-                    } else {
-                        dbg!("Error: No suitable Backends available. Perhaps you meant to enable one of the backends such as `input-avfoundation`? (Please read the docs.)");
-                        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))
-                    }
-                }
-                "ios" => {
-                    if cfg!(feature = "input-avfoundation") {
-                        query(ApiBackend::AVFoundation)
-                    } else {
-                        dbg!("Error: No suitable Backends available. Perhaps you meant to enable one of the backends such as `input-avfoundation`? (Please read the docs.)");
-                        Err(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))
-                    }
-                }
-                _ => {
-                    dbg!("Error: No suitable Backends available. You are on an unsupported platform.");
-                    Err(NokhwaError::NotImplementedError("Bad Platform".to_string()))
-                }
+        ApiBackend::Auto => match auto_api_backend() {
+            Some(backend) => query(backend),
+            None if native_api_backend().is_none() => {
+                dbg!("Error: No suitable Backends available. You are on an unsupported platform.");
+                Err(NokhwaError::NotImplementedError("Bad Platform".to_string()))
             }
-        }
+            None => {
+                dbg!("Error: No suitable Backends available. Perhaps you meant to enable one of the native backends such as `input-v4l`, `input-msmf` or `input-avfoundation`? (Please read the docs.)");
+                Err(NokhwaError::UnsupportedOperationError(ApiBackend::Auto))
+            }
+        },
         ApiBackend::AVFoundation => query_avfoundation(),
         ApiBackend::Video4Linux => query_v4l(),
-        #[allow(deprecated)]
+        ApiBackend::LibCamera => query_libcamera(),
+        ApiBackend::PipeWire => query_pipewire(),
         ApiBackend::UniversalVideoClass => query_uvc(),
         ApiBackend::MediaFoundation => query_msmf(),
-        #[allow(deprecated)]
         ApiBackend::GStreamer => query_gstreamer(),
-        ApiBackend::OpenCv | ApiBackend::Network => {
-            Err(NokhwaError::UnsupportedOperationError(api))
-        }
+        ApiBackend::OpenCv => Err(NokhwaError::UnsupportedOperationError(api)),
+        ApiBackend::Aravis => query_aravis(),
+        ApiBackend::Network => query_onvif(),
+        ApiBackend::FFmpeg => query_ffmpeg(),
+        ApiBackend::DeckLink => query_decklink(),
         ApiBackend::Browser => query_wasm(),
+        ApiBackend::Android => query_android(),
+        ApiBackend::Custom(name) => Err(NokhwaError::NotImplementedError(format!(
+            "Custom backend {name} can not be queried, query it directly"
+        ))),
+    }
+}
+
+/// Reports what a backend supports, so applications can adapt (e.g. hide the controls panel) instead of assuming per platform.
+///
+/// [`ApiBackend::Auto`] reports the backend [`query()`] would use, see [`auto_api_backend()`].
+/// # Errors
+/// If the backend is not compiled in, or does not run on this platform, this will error.
+pub fn backend_capabilities(api: ApiBackend) -> Result<BackendCapabilities, NokhwaError> {
+    use BackendCapability::{
+        Async, Controls, FormatEnumeration, Hotplug, PhotoCapture, Query, ZeroCopy,
+    };
+
+    let (compiled, capabilities): (bool, &[BackendCapability]) = match api {
+        ApiBackend::Auto => {
+            return auto_api_backend()
+                .ok_or(NokhwaError::UnsupportedOperationError(api))
+                .and_then(backend_capabilities)
+        }
+        ApiBackend::Custom(_) => (false, &[]),
+        ApiBackend::AVFoundation => (
+            cfg!(all(
+                feature = "input-avfoundation",
+                any(target_os = "macos", target_os = "ios")
+            )),
+            &[Query, Controls, FormatEnumeration, Hotplug, PhotoCapture],
+        ),
+        ApiBackend::Video4Linux => (
+            cfg!(all(feature = "input-v4l", target_os = "linux")),
+            &[Query, Controls, FormatEnumeration, ZeroCopy],
+        ),
+        ApiBackend::LibCamera => (
+            cfg!(all(feature = "input-libcamera", target_os = "linux")),
+            &[Query, Controls, FormatEnumeration, ZeroCopy],
+        ),
+        ApiBackend::PipeWire => (
+            cfg!(all(feature = "input-pipewire", target_os = "linux")),
+            &[Query, FormatEnumeration, ZeroCopy],
+        ),
+        ApiBackend::UniversalVideoClass => (
+            cfg!(feature = "input-uvc"),
+            &[Query, Controls, FormatEnumeration, ZeroCopy],
+        ),
+        ApiBackend::MediaFoundation => (
+            cfg!(all(feature = "input-msmf", target_os = "windows")),
+            &[Query, Controls, FormatEnumeration],
+        ),
+        ApiBackend::OpenCv => (cfg!(feature = "input-opencv"), &[Controls]),
+        ApiBackend::GStreamer => (cfg!(feature = "input-gst"), &[Query, FormatEnumeration]),
+        ApiBackend::Browser => (
+            cfg!(feature = "input-jscam"),
+            &[Query, Controls, Hotplug, Async, PhotoCapture],
+        ),
+        ApiBackend::Android => (
+            cfg!(all(feature = "input-android", target_os = "android")),
+            &[Query, Controls, FormatEnumeration],
+        ),
+        ApiBackend::Aravis => (
+            cfg!(feature = "input-aravis"),
+            &[Query, Controls, FormatEnumeration],
+        ),
+        ApiBackend::Network => (
+            cfg!(feature = "input-onvif"),
+            &[Query, Controls, FormatEnumeration],
+        ),
+        ApiBackend::FFmpeg => (cfg!(feature = "input-ffmpeg"), &[Query]),
+        ApiBackend::DeckLink => (
+            cfg!(feature = "input-decklink"),
+            &[Query, Controls, FormatEnumeration],
+        ),
+    };
+
+    if !compiled {
+        return Err(NokhwaError::UnsupportedOperationError(api));
     }
+    Ok(BackendCapabilities::new(api, capabilities))
 }
 
 // TODO: More
@@ -119,148 +215,101 @@ fn query_v4l() -> Result<Vec<CameraInfo>, NokhwaError> {
     ))
 }
 
-#[cfg(feature = "input-uvc")]
-fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
-    use crate::CameraIndex;
-    use uvc::Device;
-
-    let context = match uvc::Context::new() {
-        Ok(ctx) => ctx,
-        Err(why) => {
-            return Err(NokhwaError::GeneralError(format!(
-                "UVC Context failure: {}",
-                why
-            )))
-        }
-    };
+#[cfg(all(feature = "input-libcamera", target_os = "linux"))]
+fn query_libcamera() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_libcamera()
+}
 
-    let usb_devices = usb_enumeration::enumerate(None, None);
-    let uvc_devices = match context.devices() {
-        Ok(devs) => {
-            let device_vec: Vec<Device> = devs.collect();
-            device_vec
-        }
-        Err(why) => {
-            return Err(NokhwaError::GeneralError(format!(
-                "UVC Context Devicelist failure: {}",
-                why
-            )))
-        }
-    };
+#[cfg(any(not(feature = "input-libcamera"), not(target_os = "linux")))]
+fn query_libcamera() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::LibCamera))
+}
 
-    let mut camera_info_vec = vec![];
-    let mut counter = 0_usize;
-
-    // Optimize this O(n*m) algorithm
-    for usb_dev in &usb_devices {
-        for uvc_dev in &uvc_devices {
-            if let Ok(desc) = uvc_dev.description() {
-                if desc.product_id == usb_dev.product_id && desc.vendor_id == usb_dev.vendor_id {
-                    let name = usb_dev
-                        .description
-                        .as_ref()
-                        .unwrap_or(&format!(
-                            "{}:{} {} {}",
-                            desc.vendor_id,
-                            desc.product_id,
-                            desc.manufacturer.unwrap_or_else(|| "Generic".to_string()),
-                            desc.product.unwrap_or_else(|| "Camera".to_string())
-                        ))
-                        .clone();
-
-                    camera_info_vec.push(CameraInfo::new(
-                        name.clone(),
-                        usb_dev
-                            .description
-                            .as_ref()
-                            .unwrap_or(&"".to_string())
-                            .clone(),
-                        format!(
-                            "{}:{} {}",
-                            desc.vendor_id,
-                            desc.product_id,
-                            desc.serial_number.unwrap_or_else(|| "".to_string())
-                        ),
-                        CameraIndex::Index(counter as u32),
-                    ));
-                    counter += 1;
-                }
-            }
-        }
-    }
-    Ok(camera_info_vec)
+#[cfg(all(feature = "input-pipewire", target_os = "linux"))]
+fn query_pipewire() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_pipewire()
+}
+
+#[cfg(any(not(feature = "input-pipewire"), not(target_os = "linux")))]
+fn query_pipewire() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::PipeWire))
+}
+
+#[cfg(all(feature = "input-android", target_os = "android"))]
+fn query_android() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_android()
+}
+
+#[cfg(any(not(feature = "input-android"), not(target_os = "android")))]
+fn query_android() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::Android))
+}
+
+// Flatpak mounts its metadata at the root of the sandbox, snapd sets $SNAP
+fn is_sandboxed() -> bool {
+    std::path::Path::new("/.flatpak-info").exists() || std::env::var_os("SNAP").is_some()
+}
+
+#[cfg(feature = "input-uvc")]
+fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_uvc()
 }
 
 #[cfg(not(feature = "input-uvc"))]
-#[allow(deprecated)]
 fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
     Err(NokhwaError::UnsupportedOperationError(
         ApiBackend::UniversalVideoClass,
     ))
 }
 
+#[cfg(feature = "input-aravis")]
+fn query_aravis() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_aravis()
+}
+
+#[cfg(not(feature = "input-aravis"))]
+fn query_aravis() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::Aravis))
+}
+
+#[cfg(feature = "input-onvif")]
+fn query_onvif() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_onvif()
+}
+
+#[cfg(not(feature = "input-onvif"))]
+fn query_onvif() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::Network))
+}
+
+#[cfg(feature = "input-ffmpeg")]
+fn query_ffmpeg() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_ffmpeg()
+}
+
+#[cfg(not(feature = "input-ffmpeg"))]
+fn query_ffmpeg() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::FFmpeg))
+}
+
+#[cfg(feature = "input-decklink")]
+fn query_decklink() -> Result<Vec<CameraInfo>, NokhwaError> {
+    crate::backends::capture::query_decklink()
+}
+
+#[cfg(not(feature = "input-decklink"))]
+fn query_decklink() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::DeckLink))
+}
+
 #[cfg(feature = "input-gst")]
 fn query_gstreamer() -> Result<Vec<CameraInfo>, NokhwaError> {
-    use gstreamer::{
-        prelude::{DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual},
-        Caps, DeviceMonitor,
-    };
-    use nokhwa_core::types::CameraIndex;
-    use std::str::FromStr;
-
-    if let Err(why) = gstreamer::init() {
-        return Err(NokhwaError::GeneralError(format!(
-            "Failed to init gstreamer: {}",
-            why
-        )));
-    }
-    let device_monitor = DeviceMonitor::new();
-    let video_caps = match Caps::from_str("video/x-raw") {
-        Ok(cap) => cap,
-        Err(why) => {
-            return Err(NokhwaError::GeneralError(format!(
-                "Failed to generate caps: {}",
-                why
-            )))
-        }
-    };
-    let _video_filter_id = match device_monitor.add_filter(Some("Video/Source"), Some(&video_caps))
-    {
-        Some(id) => id,
-        None => {
-            return Err(NokhwaError::StructureError {
-                structure: "Video Filter ID Video/Source".to_string(),
-                error: "Null".to_string(),
-            })
-        }
-    };
-    if let Err(why) = device_monitor.start() {
-        return Err(NokhwaError::GeneralError(format!(
-            "Failed to start device monitor: {}",
-            why
-        )));
-    }
-    let mut counter = 0;
-    let devices: Vec<CameraInfo> = device_monitor
-        .devices()
-        .iter_mut()
-        .map(|gst_dev| {
-            let name = DeviceExt::display_name(gst_dev);
-            let class = DeviceExt::device_class(gst_dev);
-            counter += 1;
-            CameraInfo::new(&name, &class, "", CameraIndex::Index(counter - 1))
-        })
-        .collect();
-    device_monitor.stop();
-    Ok(devices)
+    crate::backends::capture::query_gstreamer()
 }
 
 #[cfg(not(feature = "input-gst"))]
-#[allow(deprecated)]
 fn query_gstreamer() -> Result<Vec<CameraInfo>, NokhwaError> {
-    Err(NokhwaError::UnsupportedOperationError(
-        ApiBackend::GStreamer,
-    ))
+    Err(NokhwaError::UnsupportedOperationError(ApiBackend::GStreamer))
 }
 
 // please refer to https://docs.microsoft.com/en-us/windows/win32/medfound/enumerating-video-capture-devices
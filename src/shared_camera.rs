@@ -0,0 +1,210 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#[cfg(feature = "output-async")]
+use crate::camera::owner_stopped;
+use crate::camera::OwnedCamera;
+use crate::Camera;
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    profiling::LatencyReport,
+    traits::CaptureTrait,
+    types::{CameraControl, CameraFormat, CameraInfo, ControlValueSetter, KnownCameraControl},
+};
+use std::sync::Arc;
+
+/// A cheap, clonable handle to a [`Camera`], for sharing one camera between threads and async tasks without a lock.
+///
+/// A [`Camera`] already runs its backend on its own owner thread, but its [`CaptureTrait`] methods take `&mut self`. Every method of
+/// a [`SharedCamera`] takes `&self` instead and is sent to the same owner thread, and with the `output-async` feature it can wait
+/// for the reply without blocking the executor.
+///
+/// The camera is closed once every handle is gone.
+///
+/// ```no_run
+/// # use nokhwa::{utils::CameraIndex, Camera, NokhwaError, SharedCamera};
+/// # fn main() -> Result<(), NokhwaError> {
+/// let index = CameraIndex::Index(0);
+/// let camera = SharedCamera::new(move || Camera::new(index))?;
+/// let capture = camera.clone();
+/// std::thread::spawn(move || capture.frame());
+/// camera.open_stream()?;
+/// # Ok(())
+/// # }
+/// ```
+/// # Quirks
+/// - Commands run one at a time in the order they were sent, a slow command (e.g. waiting for a frame) delays the others.
+/// - Calling the handle from inside a [`run()`](Self::run) command deadlocks, use the device you are given instead.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-shared")))]
+#[derive(Clone)]
+pub struct SharedCamera {
+    camera: Arc<Camera>,
+}
+
+impl SharedCamera {
+    /// Opens the camera with `open`, e.g. `SharedCamera::new(move || Camera::new(index))`.
+    /// # Errors
+    /// If `open` fails, this will error.
+    pub fn new<F>(open: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError>,
+    {
+        Ok(SharedCamera::from(open()?))
+    }
+
+    /// Runs `command` with the device on its owner thread and returns its result. See [`Camera::run()`].
+    /// # Errors
+    /// If the owner thread has stopped (e.g. a previous command panicked), this will error.
+    pub fn run<T, F>(&self, command: F) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.camera.run(command)
+    }
+
+    /// Runs `command` with the device on its owner thread and returns its result, without blocking the executor.
+    /// # Errors
+    /// If the owner thread has stopped (e.g. a previous command panicked), this will error.
+    #[cfg(feature = "output-async")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-async")))]
+    pub async fn run_async<T, F>(&self, command: F) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, response) = flume::bounded(1);
+        self.camera.post(Box::new(move |camera: &mut OwnedCamera| {
+            let _ = reply.send(command(camera));
+        }))?;
+        response.recv_async().await.map_err(|_| owner_stopped())
+    }
+
+    /// Gets the camera's information. See [`CaptureTrait::camera_info()`].
+    #[must_use]
+    pub fn camera_info(&self) -> &CameraInfo {
+        self.camera.camera_info()
+    }
+
+    /// Gets the current camera format. See [`CaptureTrait::camera_format()`].
+    /// # Errors
+    /// If the owner thread has stopped, this will error.
+    pub fn camera_format(&self) -> Result<Option<CameraFormat>, NokhwaError> {
+        self.run(|camera| camera.camera_format())
+    }
+
+    /// Sets the camera format. See [`CaptureTrait::set_camera_format()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to set the format, this will error.
+    pub fn set_camera_format(&self, format: CameraFormat) -> Result<(), NokhwaError> {
+        self.run(move |camera| camera.set_camera_format(format))?
+    }
+
+    /// Gets a camera control. See [`CaptureTrait::camera_control()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to read the control, this will error.
+    pub fn camera_control(
+        &self,
+        control: KnownCameraControl,
+    ) -> Result<CameraControl, NokhwaError> {
+        self.run(move |camera| camera.camera_control(control))?
+    }
+
+    /// Gets every camera control. See [`CaptureTrait::camera_controls()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to read the controls, this will error.
+    pub fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        self.run(|camera| camera.camera_controls())?
+    }
+
+    /// Sets a camera control. See [`CaptureTrait::set_camera_control()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to set the control, this will error.
+    pub fn set_camera_control(
+        &self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        self.run(move |camera| camera.set_camera_control(id, value))?
+    }
+
+    /// Opens the camera stream. See [`CaptureTrait::open_stream()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to open the stream, this will error.
+    pub fn open_stream(&self) -> Result<(), NokhwaError> {
+        self.run(|camera| camera.open_stream())?
+    }
+
+    /// Checks if the stream is open. See [`CaptureTrait::is_stream_open()`].
+    /// # Errors
+    /// If the owner thread has stopped, this will error.
+    pub fn is_stream_open(&self) -> Result<bool, NokhwaError> {
+        self.run(|camera| camera.is_stream_open())
+    }
+
+    /// Gets a frame. See [`CaptureTrait::frame()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to capture a frame, this will error.
+    pub fn frame(&self) -> Result<Buffer, NokhwaError> {
+        self.run(|camera| camera.frame())?
+    }
+
+    /// Gets a frame without blocking the executor. See [`CaptureTrait::frame()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to capture a frame, this will error.
+    #[cfg(feature = "output-async")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-async")))]
+    pub async fn frame_async(&self) -> Result<Buffer, NokhwaError> {
+        self.run_async(|camera| camera.frame()).await?
+    }
+
+    /// Stops the camera stream. See [`CaptureTrait::stop_stream()`].
+    /// # Errors
+    /// If the owner thread has stopped or the backend fails to stop the stream, this will error.
+    pub fn stop_stream(&self) -> Result<(), NokhwaError> {
+        self.run(|camera| camera.stop_stream())?
+    }
+
+    /// Puts the camera in standby. See [`Camera::standby()`].
+    /// # Errors
+    /// If the owner thread has stopped or the camera can not be put in standby, this will error.
+    pub fn standby(&self) -> Result<(), NokhwaError> {
+        self.camera.call(OwnedCamera::standby)?
+    }
+
+    /// Wakes the camera from standby, returning what could not be restored. See [`Camera::wake()`].
+    /// # Errors
+    /// If the owner thread has stopped or the camera can not be woken, this will error.
+    pub fn wake(&self) -> Result<Vec<NokhwaError>, NokhwaError> {
+        self.camera.call(OwnedCamera::wake)?
+    }
+
+    /// Gets the latency report of the camera. See [`Camera::latency_report()`].
+    /// # Errors
+    /// If the owner thread has stopped, this will error.
+    pub fn latency_report(&self) -> Result<Option<LatencyReport>, NokhwaError> {
+        self.camera.call(|camera| camera.latency_report())
+    }
+}
+
+impl From<Camera> for SharedCamera {
+    fn from(camera: Camera) -> Self {
+        SharedCamera {
+            camera: Arc::new(camera),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::depth_camera::{paired_frames, time_offset};
+use nokhwa_core::{
+    buffer::{Buffer, BufferPlane},
+    error::NokhwaError,
+    frame_format::FrameFormat,
+    traits::CaptureTrait,
+    types::Resolution,
+};
+use std::time::Duration;
+
+/// How a stereo module that streams both sensors on one node lays them out in its frames.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// The left image is the left half of the frame, the right image the right half.
+    SideBySide,
+    /// The left image is the top half of the frame, the right image the bottom half.
+    TopBottom,
+}
+
+/// A left and a right frame captured together.
+#[derive(Clone, Debug)]
+pub struct StereoFrame {
+    left: Buffer,
+    right: Buffer,
+}
+
+impl StereoFrame {
+    /// Creates a new [`StereoFrame`].
+    #[must_use]
+    pub fn new(left: Buffer, right: Buffer) -> Self {
+        StereoFrame { left, right }
+    }
+
+    /// Get the left frame.
+    #[must_use]
+    pub fn left(&self) -> &Buffer {
+        &self.left
+    }
+
+    /// Get the right frame.
+    #[must_use]
+    pub fn right(&self) -> &Buffer {
+        &self.right
+    }
+
+    /// Get how far apart the two frames were captured, if both have timestamps on the same clock. Frames split from one frame are `0` apart.
+    #[must_use]
+    pub fn time_offset(&self) -> Option<Duration> {
+        time_offset(&self.left, &self.right)
+    }
+
+    /// Splits this into the left and the right frame.
+    #[must_use]
+    pub fn into_buffers(self) -> (Buffer, Buffer) {
+        (self.left, self.right)
+    }
+
+    /// Merges the two frames back into one, laid out as `layout`. Takes the timestamp, sequence number and metadata of the left frame.
+    /// # Errors
+    /// If the frames differ in format or resolution, or their format is not packed, this will error.
+    pub fn merge(&self, layout: StereoLayout) -> Result<Buffer, NokhwaError> {
+        let format = self.left.source_frame_format();
+        let resolution = self.left.resolution();
+        if format != self.right.source_frame_format() || resolution != self.right.resolution() {
+            return Err(stereo_error(
+                format,
+                "The left and right frames differ in format or resolution",
+            ));
+        }
+        let left = packed_rows(&self.left)?;
+        let right = packed_rows(&self.right)?;
+
+        let (merged, merged_resolution) = match layout {
+            StereoLayout::SideBySide => (
+                left.iter()
+                    .zip(&right)
+                    .flat_map(|(left, right)| [*left, *right])
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<u8>>(),
+                Resolution::new(resolution.width() * 2, resolution.height()),
+            ),
+            StereoLayout::TopBottom => (
+                left.iter()
+                    .chain(&right)
+                    .flat_map(|row| row.iter())
+                    .copied()
+                    .collect(),
+                Resolution::new(resolution.width(), resolution.height() * 2),
+            ),
+        };
+        Ok(with_frame_info(
+            Buffer::new(merged_resolution, &merged, format),
+            &self.left,
+        ))
+    }
+}
+
+/// The streams a [`StereoCamera`] captures from.
+enum StereoSource {
+    Single {
+        device: Box<dyn CaptureTrait>,
+        layout: StereoLayout,
+    },
+    Pair {
+        left: Box<dyn CaptureTrait>,
+        right: Box<dyn CaptureTrait>,
+    },
+}
+
+/// Captures the two sensors of a stereo module as left and right frames.
+///
+/// Modules that stream both sensors on one node, [`side by side or top and bottom`](StereoLayout), have each frame split in two. Modules with a node per sensor
+/// have their frames paired by timestamp, within the [`tolerance`](StereoCamera::tolerance).
+/// # Quirks
+/// - Splitting needs a packed [`FrameFormat`] (e.g. `Yuv422`, `Luma8`, `Rgb8`), compressed frames (e.g. `MJpeg`) have to be decoded first.
+/// - `Yuv422`, `Uyv422` and Bayer frames need an even width (or height) per sensor, so that the halves keep their chroma pairs or Bayer pattern.
+/// - Two nodes are only matched if both backends timestamp their frames on the same clock, otherwise the latest frames of both are paired
+///   as they are.
+pub struct StereoCamera {
+    source: StereoSource,
+    tolerance: Duration,
+}
+
+impl StereoCamera {
+    /// Creates a new [`StereoCamera`] for a module that streams both sensors on one node, laid out as `layout`.
+    pub fn new_single(device: impl Into<Box<dyn CaptureTrait>>, layout: StereoLayout) -> Self {
+        StereoCamera {
+            source: StereoSource::Single {
+                device: device.into(),
+                layout,
+            },
+            tolerance: Duration::ZERO,
+        }
+    }
+
+    /// Creates a new [`StereoCamera`] for a module with a node per sensor. The tolerance starts at 1 ms, hardware synchronized sensors are much closer.
+    pub fn new_pair(
+        left: impl Into<Box<dyn CaptureTrait>>,
+        right: impl Into<Box<dyn CaptureTrait>>,
+    ) -> Self {
+        StereoCamera {
+            source: StereoSource::Pair {
+                left: left.into(),
+                right: right.into(),
+            },
+            tolerance: Duration::from_millis(1),
+        }
+    }
+
+    /// Gets the layout of the frames, or `None` if the sensors are on separate nodes.
+    #[must_use]
+    pub fn layout(&self) -> Option<StereoLayout> {
+        match &self.source {
+            StereoSource::Single { layout, .. } => Some(*layout),
+            StereoSource::Pair { .. } => None,
+        }
+    }
+
+    /// Gets the backends, the left and the right one. Both are the same if the sensors are on one node.
+    pub fn devices(&mut self) -> Vec<&mut dyn CaptureTrait> {
+        match &mut self.source {
+            StereoSource::Single { device, .. } => vec![device.as_mut()],
+            StereoSource::Pair { left, right } => vec![left.as_mut(), right.as_mut()],
+        }
+    }
+
+    /// Gets how far apart the left and right frame may be captured to be paired.
+    #[must_use]
+    pub fn tolerance(&self) -> Duration {
+        self.tolerance
+    }
+
+    /// Sets how far apart the left and right frame may be captured to be paired. Does nothing if the sensors are on one node.
+    pub fn set_tolerance(&mut self, tolerance: Duration) {
+        self.tolerance = tolerance;
+    }
+
+    /// Opens the stream, or both streams.
+    /// # Errors
+    /// If a stream fails to open, this will error.
+    pub fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        match &mut self.source {
+            StereoSource::Single { device, .. } => device.open_stream(),
+            StereoSource::Pair { left, right } => {
+                left.open_stream()?;
+                if let Err(why) = right.open_stream() {
+                    let _ = left.stop_stream();
+                    return Err(why);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks if the stream, or both streams, are open.
+    #[must_use]
+    pub fn is_stream_open(&self) -> bool {
+        match &self.source {
+            StereoSource::Single { device, .. } => device.is_stream_open(),
+            StereoSource::Pair { left, right } => left.is_stream_open() && right.is_stream_open(),
+        }
+    }
+
+    /// Captures a left and right frame.
+    /// # Errors
+    /// If capturing fails, a frame can not be split, or no frames within the tolerance turn up after a few frames, this will error.
+    pub fn frame(&mut self) -> Result<StereoFrame, NokhwaError> {
+        match &mut self.source {
+            StereoSource::Single { device, layout } => split(&device.frame()?, *layout),
+            StereoSource::Pair { left, right } => {
+                let (left, right) = paired_frames(left.as_mut(), right.as_mut(), self.tolerance)?;
+                Ok(StereoFrame::new(left, right))
+            }
+        }
+    }
+
+    /// Stops the stream, or both streams.
+    /// # Errors
+    /// If a stream fails to stop, this will error. The other is stopped regardless.
+    pub fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        match &mut self.source {
+            StereoSource::Single { device, .. } => device.stop_stream(),
+            StereoSource::Pair { left, right } => {
+                let left = left.stop_stream();
+                let right = right.stop_stream();
+                left.and(right)
+            }
+        }
+    }
+}
+
+/// Splits a frame with both sensors in it into a [`StereoFrame`].
+/// # Errors
+/// If the format is not packed, or the frame does not divide evenly into two, this will error.
+pub fn split(frame: &Buffer, layout: StereoLayout) -> Result<StereoFrame, NokhwaError> {
+    let format = frame.source_frame_format();
+    let resolution = frame.resolution();
+    let rows = packed_rows(frame)?;
+    // chroma pairs and the Bayer pattern span two pixels
+    let alignment = match format {
+        FrameFormat::Yuv422
+        | FrameFormat::Uyv422
+        | FrameFormat::Bayer8(_)
+        | FrameFormat::Bayer10(_)
+        | FrameFormat::Bayer12(_) => 2,
+        _ => 1,
+    };
+
+    let (left, right, half) = match layout {
+        StereoLayout::SideBySide => {
+            let width = resolution.width() / 2;
+            if width == 0
+                || !width.is_multiple_of(alignment)
+                || !resolution.width().is_multiple_of(2)
+            {
+                return Err(stereo_error(format, "The width does not split evenly"));
+            }
+            let row_bytes = rows.first().map_or(0, |row| row.len() / 2);
+            let (left, right): (Vec<&[u8]>, Vec<&[u8]>) =
+                rows.iter().map(|row| row.split_at(row_bytes)).unzip();
+            (
+                left.concat(),
+                right.concat(),
+                Resolution::new(width, resolution.height()),
+            )
+        }
+        StereoLayout::TopBottom => {
+            let height = resolution.height() / 2;
+            if height == 0
+                || !height.is_multiple_of(alignment)
+                || !resolution.height().is_multiple_of(2)
+            {
+                return Err(stereo_error(format, "The height does not split evenly"));
+            }
+            let (top, bottom) = rows.split_at(height as usize);
+            (
+                top.concat(),
+                bottom.concat(),
+                Resolution::new(resolution.width(), height),
+            )
+        }
+    };
+
+    Ok(StereoFrame::new(
+        with_frame_info(Buffer::new(half, &left, format), frame),
+        with_frame_info(Buffer::new(half, &right, format), frame),
+    ))
+}
+
+fn stereo_error(format: FrameFormat, error: &str) -> NokhwaError {
+    NokhwaError::ProcessFrameError {
+        src: format,
+        destination: "Stereo Frame".to_string(),
+        error: error.to_string(),
+    }
+}
+
+fn bytes_per_pixel(format: FrameFormat) -> Option<usize> {
+    match format {
+        FrameFormat::Luma8 | FrameFormat::Bayer8(_) => Some(1),
+        FrameFormat::Luma16
+        | FrameFormat::Depth16
+        | FrameFormat::Yuv422
+        | FrameFormat::Uyv422
+        | FrameFormat::Bayer10(_)
+        | FrameFormat::Bayer12(_) => Some(2),
+        FrameFormat::Rgb8 | FrameFormat::Bgr8 => Some(3),
+        FrameFormat::RgbA8 | FrameFormat::BgrA8 => Some(4),
+        _ => None,
+    }
+}
+
+// the rows of a packed frame, without their padding
+fn packed_rows(frame: &Buffer) -> Result<Vec<&[u8]>, NokhwaError> {
+    let format = frame.source_frame_format();
+    let bytes_per_pixel =
+        bytes_per_pixel(format).ok_or_else(|| stereo_error(format, "Format is not packed"))?;
+    let row_bytes = frame.resolution().width() as usize * bytes_per_pixel;
+    let stride = frame
+        .planes()
+        .first()
+        .and_then(BufferPlane::stride)
+        .unwrap_or(row_bytes);
+    if row_bytes == 0 || stride < row_bytes {
+        return Err(stereo_error(format, "Invalid stride"));
+    }
+
+    let rows = frame
+        .buffer()
+        .chunks(stride)
+        .take(frame.resolution().height() as usize)
+        .filter_map(|row| row.get(..row_bytes))
+        .collect::<Vec<&[u8]>>();
+    if rows.len() != frame.resolution().height() as usize {
+        return Err(stereo_error(format, "Buffer too short"));
+    }
+    Ok(rows)
+}
+
+fn with_frame_info(mut buffer: Buffer, frame: &Buffer) -> Buffer {
+    buffer = buffer.with_frame_type(frame.frame_type());
+    if let Some(timestamp) = frame.timestamp() {
+        buffer = buffer.with_timestamp(timestamp);
+    }
+    if let Some(sequence) = frame.sequence() {
+        buffer = buffer.with_sequence(sequence);
+    }
+    if let Some(metadata) = frame.frame_metadata() {
+        buffer = buffer.with_frame_metadata(metadata.clone());
+    }
+    buffer
+}
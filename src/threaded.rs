@@ -18,6 +18,7 @@ use crate::Camera;
 use nokhwa_core::{
     buffer::Buffer,
     error::NokhwaError,
+    profiling::LatencyStage,
     types::{
         ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueSetter,
         FrameFormat, KnownCameraControl, RequestedFormat, RequestedFormatType, Resolution,
@@ -553,11 +554,22 @@ fn camera_frame_thread_loop(
 ) {
     loop {
         if let Ok(mut camera) = camera.lock() {
+            let profiler = camera.profiler();
             if let Ok(frame) = camera.frame() {
                 if let Ok(mut last_frame) = last_frame_captured.lock() {
-                    *last_frame = frame.clone();
+                    match &profiler {
+                        Some(profiler) => {
+                            *last_frame = profiler.time(LatencyStage::Copy, || frame.clone());
+                        }
+                        None => *last_frame = frame.clone(),
+                    }
                     if let Ok(mut cb) = frame_callback.lock() {
-                        cb(frame);
+                        match &profiler {
+                            Some(profiler) => {
+                                profiler.time(LatencyStage::CallbackDispatch, || cb(frame));
+                            }
+                            None => cb(frame),
+                        }
                     }
                 }
             }
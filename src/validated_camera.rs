@@ -0,0 +1,131 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    traits::CaptureTrait,
+    validation::{FrameIssue, FrameValidator},
+};
+
+/// How many frames in a row may be dropped before [`ValidatedCamera::frame()`] gives up.
+const MAX_DROPPED_FRAMES: usize = 30;
+
+/// A frame that went through a [`FrameValidator`], with the issues it was delivered with.
+///
+/// Under [`QuarantinePolicy::Drop`](nokhwa_core::validation::QuarantinePolicy::Drop) the issues are always empty.
+#[derive(Clone, Debug)]
+pub struct ValidatedFrame {
+    buffer: Buffer,
+    issues: Vec<FrameIssue>,
+}
+
+impl ValidatedFrame {
+    /// Get the frame.
+    #[must_use]
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Get the issues of the frame.
+    #[must_use]
+    pub fn issues(&self) -> &[FrameIssue] {
+        &self.issues
+    }
+
+    /// Checks if the frame passed validation.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Gets the frame, dropping the issues.
+    #[must_use]
+    pub fn into_buffer(self) -> Buffer {
+        self.buffer
+    }
+}
+
+/// Runs every frame of a backend through a [`FrameValidator`], so frames from broken drivers are dropped or flagged before they reach a decoder.
+pub struct ValidatedCamera {
+    device: Box<dyn CaptureTrait>,
+    validator: FrameValidator,
+}
+
+impl ValidatedCamera {
+    /// Creates a new [`ValidatedCamera`].
+    pub fn new(device: impl Into<Box<dyn CaptureTrait>>, validator: FrameValidator) -> Self {
+        ValidatedCamera {
+            device: device.into(),
+            validator,
+        }
+    }
+
+    /// Gets the backend.
+    pub fn device(&mut self) -> &mut dyn CaptureTrait {
+        self.device.as_mut()
+    }
+
+    /// Gets the [`FrameValidator`].
+    #[must_use]
+    pub fn validator(&self) -> &FrameValidator {
+        &self.validator
+    }
+
+    /// Gets the [`FrameValidator`] mutably, e.g. to change the policy or the callback.
+    pub fn validator_mut(&mut self) -> &mut FrameValidator {
+        &mut self.validator
+    }
+
+    /// Opens the stream and forgets the frames of the last one.
+    /// # Errors
+    /// If the stream fails to open, this will error.
+    pub fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.validator.reset();
+        self.device.open_stream()
+    }
+
+    /// Captures a frame and validates it, capturing again while frames are dropped.
+    /// # Errors
+    /// If capturing fails, or too many frames in a row are dropped, this will error.
+    pub fn frame(&mut self) -> Result<ValidatedFrame, NokhwaError> {
+        let mut last_issues = Vec::new();
+        for _ in 0..MAX_DROPPED_FRAMES {
+            let buffer = self.device.frame()?;
+            let issues = self.validator.validate(&buffer);
+            if !self.validator.should_drop(&issues) {
+                return Ok(ValidatedFrame { buffer, issues });
+            }
+            last_issues = issues;
+        }
+
+        let issues = last_issues
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(", ");
+        Err(NokhwaError::ReadFrameError(format!(
+            "{MAX_DROPPED_FRAMES} frames in a row failed validation: {issues}"
+        )))
+    }
+
+    /// Stops the stream.
+    /// # Errors
+    /// If the stream fails to stop, this will error.
+    pub fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.device.stop_stream()
+    }
+}
@@ -0,0 +1,401 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use nokhwa_core::{
+    buffer::Buffer,
+    error::NokhwaError,
+    traits::CaptureTrait,
+    types::{CameraFormat, RecoveryStep, StreamEvent},
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+type Device = Box<dyn CaptureTrait + Send>;
+type ReopenFn = Box<dyn FnMut() -> Result<Device, NokhwaError> + Send + 'static>;
+type Command = Box<dyn FnOnce(&mut dyn CaptureTrait) + Send + 'static>;
+
+/// How a [`WatchdogCamera`] detects and recovers a stalled stream.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct WatchdogPolicy {
+    stall_after: Duration,
+    steps: Vec<RecoveryStep>,
+}
+
+impl WatchdogPolicy {
+    /// Creates a new [`WatchdogPolicy`] that considers the stream stalled once no frame arrived for `stall_after`, then tries
+    /// [`RecoveryStep::Flush`], [`RecoveryStep::ReselectStream`] and [`RecoveryStep::ReopenDevice`] in that order.
+    #[must_use]
+    pub fn new(stall_after: Duration) -> Self {
+        WatchdogPolicy {
+            stall_after,
+            steps: vec![
+                RecoveryStep::Flush,
+                RecoveryStep::ReselectStream,
+                RecoveryStep::ReopenDevice,
+            ],
+        }
+    }
+
+    /// Sets the recovery steps, tried in order until one gets a frame. No steps only reports the stall.
+    #[must_use]
+    pub fn with_steps(mut self, steps: Vec<RecoveryStep>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Get how long no frame may arrive before the stream is considered stalled.
+    #[must_use]
+    pub fn stall_after(&self) -> Duration {
+        self.stall_after
+    }
+
+    /// Get the recovery steps.
+    #[must_use]
+    pub fn steps(&self) -> &[RecoveryStep] {
+        &self.steps
+    }
+}
+
+impl Default for WatchdogPolicy {
+    fn default() -> Self {
+        WatchdogPolicy::new(Duration::from_secs(3))
+    }
+}
+
+/// Watches the stream of a backend and recovers it when it silently stops delivering frames, following a [`WatchdogPolicy`].
+///
+/// The backend is driven on a capture thread of its own, so a [`frame()`](Self::frame) that blocks without delivering is noticed
+/// too: it waits at most [`stall_after()`](WatchdogPolicy::stall_after) for the backend. A stall is reported as
+/// [`StreamEvent::StallDetected`] and a successful recovery as [`StreamEvent::Recovered`] to the callback set with
+/// [`on_event()`](Self::on_event).
+/// # Quirks
+/// - A frame that arrives after its [`frame()`](Self::frame) gave up waiting is dropped.
+/// - [`RecoveryStep::ReopenDevice`] needs [`with_reopen()`](Self::with_reopen) and is skipped without it. The old device is closed
+///   before the new one is opened, and the step fails for as long as the old device stays blocked in a call and can not be closed.
+/// - The device is reopened with the [`CameraFormat`] it had when [`open_stream()`](Self::open_stream) was called.
+pub struct WatchdogCamera {
+    // `None` while the device is closed to be reopened
+    worker: Option<Worker>,
+    // disconnects once a device closed for reopening has been dropped
+    releasing: Option<Receiver<()>>,
+    policy: WatchdogPolicy,
+    reopen: Option<ReopenFn>,
+    callback: Option<Box<dyn FnMut(StreamEvent) + Send + 'static>>,
+    format: Option<CameraFormat>,
+    last_frame: Instant,
+    streaming: bool,
+}
+
+impl WatchdogCamera {
+    /// Creates a new [`WatchdogCamera`], moving `device` to its capture thread.
+    /// # Errors
+    /// If the capture thread can not be spawned, this will error.
+    pub fn new(
+        device: impl CaptureTrait + Send + 'static,
+        policy: WatchdogPolicy,
+    ) -> Result<Self, NokhwaError> {
+        Ok(WatchdogCamera {
+            worker: Some(Worker::spawn(Box::new(device))?),
+            releasing: None,
+            policy,
+            reopen: None,
+            callback: None,
+            format: None,
+            last_frame: Instant::now(),
+            streaming: false,
+        })
+    }
+
+    /// Sets how to open the device again for [`RecoveryStep::ReopenDevice`].
+    #[must_use]
+    pub fn with_reopen(
+        mut self,
+        reopen: impl FnMut() -> Result<Box<dyn CaptureTrait + Send>, NokhwaError> + Send + 'static,
+    ) -> Self {
+        self.reopen = Some(Box::new(reopen));
+        self
+    }
+
+    /// Sets the callback that is called with [`StreamEvent::StallDetected`] and [`StreamEvent::Recovered`]. It is called from
+    /// the thread calling [`frame()`](Self::frame).
+    pub fn on_event(&mut self, callback: Box<dyn FnMut(StreamEvent) + Send + 'static>) {
+        self.callback = Some(callback);
+    }
+
+    /// Runs `job` with the backend on its capture thread and returns its result.
+    /// # Errors
+    /// If the capture thread has stopped (e.g. `job` panicked) or the device is closed to be reopened, this will error.
+    pub fn run<T, F>(&self, job: F) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.worker()?.run(job, None)
+    }
+
+    /// Gets the [`WatchdogPolicy`].
+    #[must_use]
+    pub fn policy(&self) -> &WatchdogPolicy {
+        &self.policy
+    }
+
+    /// Sets the [`WatchdogPolicy`].
+    pub fn set_policy(&mut self, policy: WatchdogPolicy) {
+        self.policy = policy;
+    }
+
+    /// Opens the stream and starts watching it.
+    /// # Errors
+    /// If the stream fails to open or does not open within the policy's window, this will error.
+    pub fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.format = self.worker()?.run(
+            |device| {
+                device.open_stream()?;
+                Ok(device.camera_format())
+            },
+            Some(self.policy.stall_after),
+        )??;
+        self.last_frame = Instant::now();
+        self.streaming = true;
+        Ok(())
+    }
+
+    /// Captures a frame. If no frame arrived for longer than the policy allows, because capturing failed or did not return in
+    /// time, the recovery steps are tried in order until one gets a frame.
+    /// # Errors
+    /// If capturing fails before the stream is considered stalled, or every recovery step fails, this will error with the last error.
+    pub fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        if !self.streaming {
+            return self.worker()?.run(|device| device.frame(), None)?;
+        }
+
+        let why = match self.capture(|device| device.frame()) {
+            Ok(frame) => {
+                self.last_frame = Instant::now();
+                return Ok(frame);
+            }
+            Err(why) => why,
+        };
+
+        let stalled_for = self.last_frame.elapsed();
+        if stalled_for < self.policy.stall_after {
+            return Err(why);
+        }
+        self.emit(StreamEvent::StallDetected(stalled_for));
+        self.recover(why)
+    }
+
+    /// Stops the stream and stops watching it.
+    /// # Errors
+    /// If the stream fails to stop or does not stop within the policy's window, this will error.
+    pub fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.streaming = false;
+        self.worker()?
+            .run(|device| device.stop_stream(), Some(self.policy.stall_after))?
+    }
+
+    fn worker(&self) -> Result<&Worker, NokhwaError> {
+        self.worker.as_ref().ok_or_else(|| {
+            NokhwaError::GeneralError(
+                "The device was closed to be reopened and has not been reopened yet".to_string(),
+            )
+        })
+    }
+
+    /// Runs `capture` on the capture thread, waiting at most the policy's window for it.
+    fn capture<F>(&self, capture: F) -> Result<Buffer, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> Result<Buffer, NokhwaError> + Send + 'static,
+    {
+        self.worker()?.run(capture, Some(self.policy.stall_after))?
+    }
+
+    fn recover(&mut self, mut last_error: NokhwaError) -> Result<Buffer, NokhwaError> {
+        // whatever is still queued was meant for the stalled stream and would undo the recovery
+        if let Some(worker) = &self.worker {
+            worker.invalidate();
+        }
+        for step in self.policy.steps.clone() {
+            match self.run_step(step) {
+                Ok(frame) => {
+                    self.last_frame = Instant::now();
+                    self.emit(StreamEvent::Recovered(step));
+                    return Ok(frame);
+                }
+                Err(why) => last_error = why,
+            }
+        }
+
+        // wait a whole window again before the next attempt
+        self.last_frame = Instant::now();
+        Err(last_error)
+    }
+
+    fn run_step(&mut self, step: RecoveryStep) -> Result<Buffer, NokhwaError> {
+        let format = self.format;
+        match step {
+            RecoveryStep::Flush => self.capture(|device| {
+                // the stalled stream may fail to stop, it is reopened either way
+                let _stop_stream_err = device.stop_stream();
+                device.open_stream()?;
+                device.frame()
+            }),
+            RecoveryStep::ReselectStream => self.capture(move |device| {
+                let _stop_stream_err = device.stop_stream();
+                if let Some(format) = format {
+                    device.set_camera_format(format)?;
+                }
+                device.open_stream()?;
+                device.frame()
+            }),
+            RecoveryStep::ReopenDevice => {
+                let Some(reopen) = self.reopen.as_mut() else {
+                    return Err(NokhwaError::GeneralError(
+                        "No way to reopen the device was set".to_string(),
+                    ));
+                };
+
+                // backends often can not open a device twice, so the old one is dropped first
+                if let Some(worker) = self.worker.take() {
+                    self.releasing = Some(worker.release());
+                }
+                if let Some(released) = &self.releasing {
+                    if let Err(RecvTimeoutError::Timeout) =
+                        released.recv_timeout(self.policy.stall_after)
+                    {
+                        return Err(NokhwaError::GeneralError(
+                            "The stalled device is blocked in a call and can not be closed"
+                                .to_string(),
+                        ));
+                    }
+                    self.releasing = None;
+                }
+
+                self.worker = Some(Worker::spawn(reopen()?)?);
+                self.capture(move |device| {
+                    if let Some(format) = format {
+                        device.set_camera_format(format)?;
+                    }
+                    device.open_stream()?;
+                    device.frame()
+                })
+            }
+        }
+    }
+
+    fn emit(&mut self, event: StreamEvent) {
+        if let Some(callback) = &mut self.callback {
+            callback(event);
+        }
+    }
+}
+
+/// The capture thread a device lives on, so a call blocked in the backend can be given up on.
+///
+/// Every job is tagged with the generation it was sent in. The generation moves on whenever a job is given up on or a recovery
+/// starts, and the thread drops the jobs of older generations instead of running them once the blocked call returns.
+struct Worker {
+    commands: Sender<(u64, Command)>,
+    generation: Arc<AtomicU64>,
+    // disconnects once the thread has dropped its device
+    released: Receiver<()>,
+}
+
+impl Worker {
+    fn spawn(device: Device) -> Result<Self, NokhwaError> {
+        let (commands, receiver) = mpsc::channel::<(u64, Command)>();
+        let (release, released) = mpsc::channel::<()>();
+        let generation = Arc::new(AtomicU64::new(0));
+        let current = generation.clone();
+        thread::Builder::new()
+            .name("nokhwa-watchdog".to_string())
+            .spawn(move || {
+                let mut device = device;
+                while let Ok((sent_in, command)) = receiver.recv() {
+                    if sent_in == current.load(Ordering::Acquire) {
+                        command(device.as_mut());
+                    }
+                }
+                drop(device);
+                drop(release);
+            })
+            .map_err(|why| {
+                NokhwaError::GeneralError(format!("Failed to spawn capture thread: {why}"))
+            })?;
+        Ok(Worker {
+            commands,
+            generation,
+            released,
+        })
+    }
+
+    /// Runs `job` on the capture thread and waits at most `timeout` for its result, or forever if it is `None`.
+    fn run<T, F>(&self, job: F, timeout: Option<Duration>) -> Result<T, NokhwaError>
+    where
+        F: FnOnce(&mut dyn CaptureTrait) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, response) = mpsc::sync_channel(1);
+        let generation = self.generation.load(Ordering::Acquire);
+        self.commands
+            .send((
+                generation,
+                Box::new(move |device: &mut dyn CaptureTrait| {
+                    let _ = reply.send(job(device));
+                }),
+            ))
+            .map_err(|_| worker_stopped())?;
+
+        match timeout {
+            Some(timeout) => response.recv_timeout(timeout).map_err(|why| match why {
+                RecvTimeoutError::Timeout => {
+                    self.invalidate();
+                    NokhwaError::ReadFrameError(format!(
+                        "The device did not return within {timeout:?}"
+                    ))
+                }
+                RecvTimeoutError::Disconnected => worker_stopped(),
+            }),
+            None => response.recv().map_err(|_| worker_stopped()),
+        }
+    }
+
+    /// Moves on to the next generation, so the jobs queued so far are dropped instead of run.
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Stops the capture thread once it finishes the call it is blocked in, dropping the device. The returned receiver
+    /// disconnects once the device is dropped.
+    fn release(self) -> Receiver<()> {
+        let Worker {
+            commands, released, ..
+        } = self;
+        drop(commands);
+        released
+    }
+}
+
+fn worker_stopped() -> NokhwaError {
+    NokhwaError::GeneralError("The capture thread has stopped".to_string())
+}
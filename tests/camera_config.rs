@@ -0,0 +1,89 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![cfg(feature = "config")]
+
+mod common;
+
+use common::{formats, mock_camera};
+use nokhwa::{
+    camera_traits::CaptureTrait,
+    utils::{FrameFormat, Resolution},
+    CameraConfig,
+};
+
+const CONFIG: &str = r#"
+[format]
+resolution = { width_x = 1280, height_y = 720 }
+frame_rate = 30
+
+[[controls]]
+control = "Exposure"
+value = { Integer = 250 }
+
+[[pipeline]]
+step = "gamma"
+gamma = 2.2
+"#;
+
+#[test]
+fn configure_requests_the_format_and_sets_the_controls() {
+    let config = CameraConfig::from_toml_str(CONFIG).unwrap();
+    let (mut camera, handle) = mock_camera();
+
+    camera.configure(&config).unwrap();
+
+    assert_eq!(camera.camera_format(), Some(formats()[1]));
+    assert_eq!(camera.resolution(), Some(Resolution::new(1280, 720)));
+    assert_eq!(camera.frame_format(), FrameFormat::MJpeg);
+    assert_eq!(handle.state().format, formats()[1]);
+    assert_eq!(handle.state().exposure, 250);
+}
+
+#[test]
+fn configure_fails_if_no_format_satisfies_the_constraints() {
+    let config = CameraConfig::from_toml_str(
+        r#"
+        [format]
+        resolution = { width_x = 1280, height_y = 720 }
+        frame_formats = ["Nv12"]
+        "#,
+    )
+    .unwrap();
+    let (mut camera, handle) = mock_camera();
+
+    assert!(camera.configure(&config).is_err());
+    assert_eq!(handle.state().format, formats()[0]);
+}
+
+#[test]
+fn capture_loop_feeds_the_configured_camera() {
+    let config = CameraConfig::from_toml_str(
+        r#"
+        [format]
+        frame_formats = ["Yuv422"]
+        "#,
+    )
+    .unwrap();
+    let (mut camera, handle) = mock_camera();
+    camera.configure(&config).unwrap();
+
+    let mut capture = config.capture_loop(camera).unwrap();
+    capture.run(3).unwrap();
+
+    assert_eq!(handle.state().stream_opens, 1);
+    assert_eq!(handle.state().frames, 3);
+}
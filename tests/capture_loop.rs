@@ -0,0 +1,59 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod common;
+
+use common::mock_camera;
+use nokhwa::{camera_traits::FrameSink, utils::Resolution, Buffer, CaptureLoop, NokhwaError};
+use std::sync::{Arc, Mutex};
+
+/// Records the resolution of every frame it gets.
+struct RecordingSink(Arc<Mutex<Vec<Resolution>>>);
+
+impl FrameSink for RecordingSink {
+    fn consume(&mut self, buffer: Buffer) -> Result<(), NokhwaError> {
+        self.0.lock().unwrap().push(buffer.resolution());
+        Ok(())
+    }
+}
+
+#[test]
+fn step_opens_the_stream_once() {
+    let (camera, handle) = mock_camera();
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let mut capture = CaptureLoop::new(camera).with_sink(RecordingSink(frames.clone()));
+
+    capture.step().unwrap();
+    capture.step().unwrap();
+
+    assert!(capture.camera().is_stream_open());
+    assert_eq!(handle.state().stream_opens, 1);
+    assert_eq!(handle.state().frames, 2);
+    assert_eq!(*frames.lock().unwrap(), vec![Resolution::new(640, 480); 2]);
+}
+
+#[test]
+fn step_reopens_a_stopped_stream() {
+    let (camera, handle) = mock_camera();
+    let mut capture = CaptureLoop::new(camera);
+
+    capture.step().unwrap();
+    capture.camera().stop_stream().unwrap();
+    assert!(!capture.camera().is_stream_open());
+    capture.step().unwrap();
+
+    assert_eq!(handle.state().stream_opens, 2);
+}
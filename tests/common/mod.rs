@@ -0,0 +1,278 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// every test binary includes this module but uses only part of it
+#![allow(dead_code)]
+
+use nokhwa::{
+    camera_traits::CaptureTrait,
+    utils::{
+        ApiBackend, CameraControl, CameraFormat, CameraIndex, CameraInfo, ControlValueDescription,
+        ControlValueSetter, FrameFormat, FrameRate, KnownCameraControl, KnownCameraControlFlag,
+        Resolution,
+    },
+    Buffer, Camera, NokhwaError,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+
+/// What a [`MockCamera`] did, shared with the test that created it.
+#[derive(Debug)]
+pub struct MockState {
+    pub format: CameraFormat,
+    pub streaming: bool,
+    pub stream_opens: usize,
+    pub frames: usize,
+    pub exposure: i64,
+    /// If `false`, reading the controls fails with [`NokhwaError::UnsupportedOperationError`].
+    pub controls_supported: bool,
+    /// If set, the next frame blocks for this long before it is delivered.
+    pub stall_next_frame: Option<Duration>,
+}
+
+/// A handle to the [`MockState`] of a [`MockCamera`].
+#[derive(Clone, Debug)]
+pub struct MockHandle(Arc<Mutex<MockState>>);
+
+impl MockHandle {
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.0.lock().unwrap()
+    }
+}
+
+/// A camera that supports 640x480 YUV 4:2:2 and 1280x720 MJPEG at 30 FPS and produces frames of zeroes.
+pub struct MockCamera {
+    info: CameraInfo,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockCamera {
+    pub fn new() -> (Self, MockHandle) {
+        let state = Arc::new(Mutex::new(MockState {
+            format: formats()[0],
+            streaming: false,
+            stream_opens: 0,
+            frames: 0,
+            exposure: 100,
+            controls_supported: true,
+            stall_next_frame: None,
+        }));
+        let camera = MockCamera {
+            info: CameraInfo::new("Mock Camera", "", "mock-0", &CameraIndex::Index(0)),
+            state: state.clone(),
+        };
+        (camera, MockHandle(state))
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// Opens a [`MockCamera`] as a [`Camera`].
+pub fn mock_camera() -> (Camera, MockHandle) {
+    let (device, handle) = MockCamera::new();
+    let camera = Camera::with_custom_backend(CameraIndex::Index(0), move || Ok(device)).unwrap();
+    (camera, handle)
+}
+
+pub fn formats() -> [CameraFormat; 2] {
+    let fps = FrameRate::new_integer(30).unwrap();
+    [
+        CameraFormat::new_from(640, 480, FrameFormat::Yuv422, fps),
+        CameraFormat::new_from(1280, 720, FrameFormat::MJpeg, fps),
+    ]
+}
+
+fn exposure(value: i64) -> CameraControl {
+    CameraControl::new(
+        KnownCameraControl::Exposure,
+        "Exposure".to_string(),
+        ControlValueDescription::Integer {
+            value,
+            default: 100,
+            step: 1,
+        },
+        vec![KnownCameraControlFlag::Manual],
+        true,
+    )
+}
+
+impl CaptureTrait for MockCamera {
+    fn init(&mut self) -> Result<(), NokhwaError> {
+        Ok(())
+    }
+
+    fn backend(&self) -> ApiBackend {
+        ApiBackend::Custom("mock")
+    }
+
+    fn camera_info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    fn refresh_camera_format(&mut self) -> Result<(), NokhwaError> {
+        Ok(())
+    }
+
+    fn camera_format(&self) -> Option<CameraFormat> {
+        Some(self.state().format)
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if !formats().contains(&new_fmt) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "Unsupported".to_string(),
+            });
+        }
+        self.state().format = new_fmt;
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<FrameRate>>, NokhwaError> {
+        Ok(formats()
+            .into_iter()
+            .filter(|format| format.format() == fourcc)
+            .map(|format| (format.resolution(), vec![format.frame_rate()]))
+            .collect())
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        Ok(formats()
+            .into_iter()
+            .map(|format| format.format())
+            .collect())
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        Some(self.state().format.resolution())
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut format = self.state().format;
+        format.set_resolution(new_res);
+        self.set_camera_format(format)
+    }
+
+    fn frame_rate(&self) -> Option<u32> {
+        Some(self.state().format.frame_rate().as_rational().0)
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut format = self.state().format;
+        format.set_frame_rate(FrameRate::new_integer(new_fps)?);
+        self.set_camera_format(format)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.state().format.format()
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut format = self.state().format;
+        format.set_format(fourcc);
+        self.set_camera_format(format)
+    }
+
+    fn camera_control(&self, control: KnownCameraControl) -> Result<CameraControl, NokhwaError> {
+        self.camera_controls()?
+            .into_iter()
+            .find(|camera_control| camera_control.control() == control)
+            .ok_or_else(|| NokhwaError::GetPropertyError {
+                property: control.to_string(),
+                error: "Unsupported".to_string(),
+            })
+    }
+
+    fn camera_controls(&self) -> Result<Vec<CameraControl>, NokhwaError> {
+        let state = self.state();
+        if !state.controls_supported {
+            return Err(NokhwaError::UnsupportedOperationError(self.backend()));
+        }
+        Ok(vec![exposure(state.exposure)])
+    }
+
+    fn set_camera_control(
+        &mut self,
+        id: KnownCameraControl,
+        value: ControlValueSetter,
+    ) -> Result<(), NokhwaError> {
+        match (id, value) {
+            (KnownCameraControl::Exposure, ControlValueSetter::Integer(value)) => {
+                self.state().exposure = value;
+                Ok(())
+            }
+            (id, value) => Err(NokhwaError::SetPropertyError {
+                property: id.to_string(),
+                value: value.to_string(),
+                error: "Unsupported".to_string(),
+            }),
+        }
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        let mut state = self.state();
+        state.streaming = true;
+        state.stream_opens += 1;
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.state().streaming
+    }
+
+    fn frame(&mut self) -> Result<Buffer, NokhwaError> {
+        let resolution = self.state().format.resolution();
+        let frame = self.frame_raw()?.into_owned();
+        Ok(Buffer::new(resolution, &frame, self.frame_format()))
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<'_, [u8]>, NokhwaError> {
+        // the lock is released while stalling, so the test can look at the state meanwhile
+        let stall = self.state().stall_next_frame.take();
+        if let Some(stall) = stall {
+            thread::sleep(stall);
+        }
+        let mut state = self.state();
+        if !state.streaming {
+            return Err(NokhwaError::ReadFrameError(
+                "The stream is not open".to_string(),
+            ));
+        }
+        state.frames += 1;
+        let resolution = state.format.resolution();
+        Ok(Cow::Owned(vec![
+            0;
+            (resolution.width() * resolution.height() * 2)
+                as usize
+        ]))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.state().streaming = false;
+        Ok(())
+    }
+}
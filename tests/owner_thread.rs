@@ -0,0 +1,56 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod common;
+
+use common::mock_camera;
+use nokhwa::{camera_traits::CaptureTrait, Camera};
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn camera_is_send_and_sync() {
+    assert_send_sync::<Camera>();
+}
+
+#[test]
+fn every_call_runs_on_the_owner_thread() {
+    let (camera, _) = mock_camera();
+    let owner = camera.run(|_| thread::current().id()).unwrap();
+    assert_ne!(owner, thread::current().id());
+
+    thread::scope(|scope| {
+        let callers = (0..4)
+            .map(|_| scope.spawn(|| camera.run(|_| thread::current().id()).unwrap()))
+            .collect::<Vec<_>>();
+        for caller in callers {
+            assert_eq!(caller.join().unwrap(), owner);
+        }
+    });
+}
+
+#[test]
+fn camera_can_be_moved_to_another_thread() {
+    let (mut camera, handle) = mock_camera();
+    camera.open_stream().unwrap();
+
+    let frame = thread::spawn(move || camera.frame()).join().unwrap();
+
+    assert!(frame.is_ok());
+    // dropping the camera on the other thread closed the device
+    assert!(!handle.state().streaming);
+}
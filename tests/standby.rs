@@ -0,0 +1,69 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod common;
+
+use common::{formats, mock_camera};
+use nokhwa::{
+    camera_traits::CaptureTrait,
+    utils::{ControlValueSetter, KnownCameraControl},
+};
+
+#[test]
+fn wake_restores_the_format_controls_and_stream() {
+    let (mut camera, handle) = mock_camera();
+    camera.set_camera_format(formats()[1]).unwrap();
+    camera
+        .set_camera_control(
+            KnownCameraControl::Exposure,
+            ControlValueSetter::Integer(250),
+        )
+        .unwrap();
+    camera.open_stream().unwrap();
+
+    camera.standby().unwrap();
+    assert!(camera.is_standby());
+    assert!(!camera.is_stream_open());
+    assert!(camera.frame().is_err());
+    // e.g. the camera was replugged while in standby
+    {
+        let mut state = handle.state();
+        state.format = formats()[0];
+        state.exposure = 100;
+    }
+
+    assert!(camera.wake().unwrap().is_empty());
+    assert!(camera.is_stream_open());
+    assert_eq!(handle.state().format, formats()[1]);
+    assert_eq!(handle.state().exposure, 250);
+    assert!(camera.frame().is_ok());
+}
+
+#[test]
+fn standby_without_control_support_restores_only_the_format() {
+    let (mut camera, handle) = mock_camera();
+    handle.state().controls_supported = false;
+    camera.set_camera_format(formats()[1]).unwrap();
+    camera.open_stream().unwrap();
+
+    camera.standby().unwrap();
+    handle.state().format = formats()[0];
+
+    assert!(camera.wake().unwrap().is_empty());
+    assert!(camera.is_stream_open());
+    assert_eq!(handle.state().format, formats()[1]);
+    assert_eq!(handle.state().exposure, 100);
+}
@@ -0,0 +1,54 @@
+/*
+ * Copyright 2022 l1npengtul <l1npengtul@protonmail.com> / The Nokhwa Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod common;
+
+use common::MockCamera;
+use nokhwa::{
+    utils::{RecoveryStep, StreamEvent},
+    WatchdogCamera, WatchdogPolicy,
+};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn jobs_given_up_on_do_not_run_after_the_device_recovers() {
+    let (device, handle) = MockCamera::new();
+    let policy =
+        WatchdogPolicy::new(Duration::from_millis(100)).with_steps(vec![RecoveryStep::Flush]);
+    let mut camera = WatchdogCamera::new(device, policy).unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    camera.on_event(Box::new(move |event| recorded.lock().unwrap().push(event)));
+    camera.open_stream().unwrap();
+
+    // the frame blocks past the window, and so does the flush queued behind it
+    handle.state().stall_next_frame = Some(Duration::from_millis(500));
+    assert!(camera.frame().is_err());
+    assert!(matches!(
+        events.lock().unwrap().as_slice(),
+        [StreamEvent::StallDetected(_)]
+    ));
+
+    // once the blocked call returns the device delivers again, and the flush given up on must not restart its stream
+    thread::sleep(Duration::from_millis(500));
+    assert!(camera.frame().is_ok());
+    assert_eq!(handle.state().stream_opens, 1);
+    assert_eq!(handle.state().frames, 2);
+}